@@ -0,0 +1,9 @@
+//! Host discovery for Slarti.
+//!
+//! Finds machines that are not (yet) in the ssh config so the hosts tree can
+//! offer them:
+//! - [`mdns`]: SSH servers announcing `_ssh._tcp` on the local network.
+//! - [`tailscale`]: peers of the local tailscale daemon, with online state.
+
+pub mod mdns;
+pub mod tailscale;
@@ -0,0 +1,131 @@
+//! mDNS (Bonjour/Avahi) browsing for SSH servers on the local network.
+
+use anyhow::{anyhow, Result};
+use mdns_sd::{ServiceDaemon, ServiceEvent};
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+/// Service type announced by sshd via Avahi/Bonjour.
+pub const SSH_SERVICE: &str = "_ssh._tcp.local.";
+
+/// An SSH server found on the local network.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiscoveredHost {
+    /// Service instance name, e.g. "raspberrypi".
+    pub name: String,
+    /// mDNS hostname without the trailing dot, e.g. "raspberrypi.local".
+    pub hostname: String,
+    pub port: u16,
+    pub addresses: Vec<IpAddr>,
+}
+
+impl DiscoveredHost {
+    /// Alias to use for a new ssh config entry: the hostname without ".local".
+    pub fn alias(&self) -> String {
+        self.hostname
+            .strip_suffix(".local")
+            .unwrap_or(&self.hostname)
+            .to_string()
+    }
+
+    /// Parameters for a new ssh config entry (Port only when not 22).
+    pub fn config_params(&self) -> Vec<(&'static str, String)> {
+        let mut params = vec![("HostName", self.hostname.clone())];
+        if self.port != 22 {
+            params.push(("Port", self.port.to_string()));
+        }
+        params
+    }
+}
+
+/// The instance part of a full service name: "raspberrypi._ssh._tcp.local."
+/// becomes "raspberrypi". Names of other services are returned unchanged.
+fn instance_name(fullname: &str) -> &str {
+    fullname
+        .strip_suffix(SSH_SERVICE)
+        .and_then(|rest| rest.strip_suffix('.'))
+        .unwrap_or(fullname)
+}
+
+/// Browse for `_ssh._tcp` services for `dur` and return the ones resolved in
+/// that time, by hostname. Blocks the calling thread.
+pub fn browse_ssh(dur: Duration) -> Result<Vec<DiscoveredHost>> {
+    let daemon = ServiceDaemon::new().map_err(|e| anyhow!("mdns: {}", e))?;
+    let events = daemon
+        .browse(SSH_SERVICE)
+        .map_err(|e| anyhow!("mdns browse: {}", e))?;
+
+    let deadline = Instant::now() + dur;
+    let mut found: Vec<DiscoveredHost> = Vec::new();
+    while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+        let Ok(event) = events.recv_timeout(left) else {
+            break;
+        };
+        if let ServiceEvent::ServiceResolved(info) = event {
+            let hostname = info.get_hostname().trim_end_matches('.').to_string();
+            if found.iter().any(|h| h.hostname == hostname) {
+                continue;
+            }
+            let name = instance_name(info.get_fullname()).to_string();
+            let mut addresses: Vec<IpAddr> = info.get_addresses().iter().copied().collect();
+            addresses.sort();
+            found.push(DiscoveredHost {
+                name,
+                hostname,
+                port: info.get_port(),
+                addresses,
+            });
+        }
+    }
+
+    let _ = daemon.shutdown();
+    found.sort_by(|a, b| a.hostname.cmp(&b.hostname));
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host(hostname: &str, port: u16) -> DiscoveredHost {
+        DiscoveredHost {
+            name: "pi".to_string(),
+            hostname: hostname.to_string(),
+            port,
+            addresses: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn alias_drops_the_local_domain() {
+        assert_eq!(host("raspberrypi.local", 22).alias(), "raspberrypi");
+        assert_eq!(host("nas.lan", 22).alias(), "nas.lan");
+        assert_eq!(host("local", 22).alias(), "local");
+    }
+
+    #[test]
+    fn config_params_name_the_port_only_when_not_22() {
+        assert_eq!(
+            host("pi.local", 22).config_params(),
+            vec![("HostName", "pi.local".to_string())]
+        );
+        assert_eq!(
+            host("pi.local", 2222).config_params(),
+            vec![
+                ("HostName", "pi.local".to_string()),
+                ("Port", "2222".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn instance_name_strips_the_service_type() {
+        assert_eq!(instance_name("raspberrypi._ssh._tcp.local."), "raspberrypi");
+        assert_eq!(instance_name("My Mac._ssh._tcp.local."), "My Mac");
+        assert_eq!(
+            instance_name("printer._ipp._tcp.local."),
+            "printer._ipp._tcp.local."
+        );
+        assert_eq!(instance_name("_ssh._tcp.local."), "_ssh._tcp.local.");
+    }
+}
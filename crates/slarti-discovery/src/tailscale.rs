@@ -0,0 +1,218 @@
+//! Peers of the local tailscale daemon (`tailscale status --json`).
+
+use anyhow::{anyhow, Context as _, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// A machine on the tailnet.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TailscalePeer {
+    /// Machine hostname, e.g. "nas".
+    pub host_name: String,
+    /// MagicDNS name without the trailing dot, e.g. "nas.tail1234.ts.net".
+    pub dns_name: String,
+    pub ips: Vec<String>,
+    pub os: String,
+    pub online: bool,
+}
+
+impl TailscalePeer {
+    /// Name to connect to: the MagicDNS name, else the first tailnet IP.
+    pub fn target(&self) -> String {
+        if !self.dns_name.is_empty() {
+            self.dns_name.clone()
+        } else {
+            self.ips
+                .first()
+                .cloned()
+                .unwrap_or_else(|| self.host_name.clone())
+        }
+    }
+
+    /// Whether `name` (an ssh alias or HostName) refers to this peer: its
+    /// hostname, MagicDNS name (full or first label) or a tailnet IP.
+    pub fn matches(&self, name: &str) -> bool {
+        let name = name.trim_end_matches('.');
+        let short = self.dns_name.split('.').next().unwrap_or_default();
+        name.eq_ignore_ascii_case(&self.host_name)
+            || (!self.dns_name.is_empty() && name.eq_ignore_ascii_case(&self.dns_name))
+            || (!short.is_empty() && name.eq_ignore_ascii_case(short))
+            || self.ips.iter().any(|ip| ip == name)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Status {
+    #[serde(default)]
+    peer: BTreeMap<String, PeerStatus>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PeerStatus {
+    #[serde(default)]
+    host_name: String,
+    #[serde(rename = "DNSName", default)]
+    dns_name: String,
+    #[serde(rename = "TailscaleIPs", default)]
+    tailscale_ips: Vec<String>,
+    #[serde(rename = "OS", default)]
+    os: String,
+    #[serde(default)]
+    online: bool,
+}
+
+/// Peers from `tailscale status --json`, sorted by hostname. Errors if the
+/// tailscale CLI is missing, the daemon is not running, or it takes longer
+/// than `dur`. Blocks the calling thread.
+pub fn peers(dur: Duration) -> Result<Vec<TailscalePeer>> {
+    let json = run_status(dur)?;
+    parse_status(&json)
+}
+
+fn parse_status(json: &[u8]) -> Result<Vec<TailscalePeer>> {
+    let status: Status = serde_json::from_slice(json).context("parse tailscale status")?;
+    let mut peers: Vec<TailscalePeer> = status
+        .peer
+        .into_values()
+        .map(|p| TailscalePeer {
+            host_name: p.host_name,
+            dns_name: p.dns_name.trim_end_matches('.').to_string(),
+            ips: p.tailscale_ips,
+            os: p.os,
+            online: p.online,
+        })
+        .collect();
+    peers.sort_by(|a, b| a.host_name.cmp(&b.host_name));
+    Ok(peers)
+}
+
+fn run_status(dur: Duration) -> Result<Vec<u8>> {
+    let mut child = Command::new("tailscale")
+        .args(["status", "--json"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("spawn tailscale")?;
+    // Drain stdout while waiting; the status of a large tailnet exceeds a pipe buffer.
+    let mut stdout = child.stdout.take().context("tailscale stdout")?;
+    let reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        stdout.read_to_end(&mut buf).map(|_| buf)
+    });
+
+    let deadline = Instant::now() + dur;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(anyhow!("tailscale status timed out"));
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    };
+    let out = reader
+        .join()
+        .map_err(|_| anyhow!("tailscale stdout reader panicked"))??;
+    if !status.success() {
+        let mut stderr = String::new();
+        if let Some(mut e) = child.stderr.take() {
+            let _ = e.read_to_string(&mut stderr);
+        }
+        return Err(anyhow!("tailscale status failed: {}", stderr.trim()));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(host_name: &str, dns_name: &str, ips: &[&str]) -> TailscalePeer {
+        TailscalePeer {
+            host_name: host_name.to_string(),
+            dns_name: dns_name.to_string(),
+            ips: ips.iter().map(|ip| ip.to_string()).collect(),
+            os: "linux".to_string(),
+            online: true,
+        }
+    }
+
+    #[test]
+    fn parses_peers_sorted_by_hostname() {
+        let json = br#"{
+            "BackendState": "Running",
+            "Self": {"HostName": "laptop"},
+            "Peer": {
+                "nodekey:b": {
+                    "HostName": "nas",
+                    "DNSName": "nas.tail1234.ts.net.",
+                    "TailscaleIPs": ["100.64.0.2", "fd7a:115c:a1e0::2"],
+                    "OS": "linux",
+                    "Online": true
+                },
+                "nodekey:a": {"HostName": "pi", "OS": "linux"}
+            }
+        }"#;
+        let peers = parse_status(json).unwrap();
+        assert_eq!(
+            peers,
+            vec![
+                peer(
+                    "nas",
+                    "nas.tail1234.ts.net",
+                    &["100.64.0.2", "fd7a:115c:a1e0::2"]
+                ),
+                TailscalePeer {
+                    online: false,
+                    ..peer("pi", "", &[])
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn status_without_peers_or_valid_json() {
+        assert_eq!(
+            parse_status(br#"{"BackendState": "Stopped"}"#).unwrap(),
+            Vec::new()
+        );
+        assert!(parse_status(b"tailscale: not running").is_err());
+    }
+
+    #[test]
+    fn target_prefers_the_magicdns_name() {
+        assert_eq!(
+            peer("nas", "nas.ts.net", &["100.64.0.2"]).target(),
+            "nas.ts.net"
+        );
+        assert_eq!(peer("nas", "", &["100.64.0.2"]).target(), "100.64.0.2");
+        assert_eq!(peer("nas", "", &[]).target(), "nas");
+    }
+
+    #[test]
+    fn matches_names_and_addresses() {
+        let nas = peer("NAS", "nas.tail1234.ts.net", &["100.64.0.2"]);
+        for name in [
+            "nas",
+            "NAS",
+            "nas.tail1234.ts.net",
+            "nas.tail1234.ts.net.",
+            "100.64.0.2",
+        ] {
+            assert!(nas.matches(name), "{name}");
+        }
+        for name in ["nas2", "tail1234", "100.64.0.3", ""] {
+            assert!(!nas.matches(name), "{name}");
+        }
+        // Without a MagicDNS name, an empty first label matches nothing.
+        assert!(!peer("pi", "", &[]).matches(""));
+    }
+}
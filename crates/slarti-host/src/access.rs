@@ -0,0 +1,152 @@
+//! Access section: the accounts that can log in, who is in the admin
+//! groups and what sudoers grants, for quick access audits.
+
+use crate::freshness::format_age;
+use crate::{unix_now, HostPanel, Section};
+use gpui::{div, prelude::*, Context};
+use slarti_proto as proto;
+use slarti_ui::Appearance;
+use std::time::{Duration, Instant, SystemTime};
+
+/// "ops (uid 1000, /bin/bash), password changed 120d ago"
+fn user_text(user: &proto::LocalUser, now: u64) -> String {
+    let mut s = format!("{} (uid {}, {})", user.name, user.uid, user.shell);
+    match user.password_locked {
+        Some(true) => s.push_str(", password locked"),
+        _ => {
+            if let Some(at) = user.password_changed_usec {
+                let age = Duration::from_secs(now.saturating_sub(at / 1_000_000));
+                s.push_str(&format!(", password changed {}", format_age(age)));
+            }
+        }
+    }
+    s
+}
+
+/// "sudo: ops, alice"
+fn group_text(group: &proto::GroupMembers) -> String {
+    if group.members.is_empty() {
+        format!("{}: (no members)", group.name)
+    } else {
+        format!("{}: {}", group.name, group.members.join(", "))
+    }
+}
+
+/// "deploy ALL=(root) NOPASSWD: /bin/systemctl restart app (/etc/sudoers.d/deploy)"
+fn rule_text(rule: &proto::SudoRule) -> String {
+    format!("{} {} ({})", rule.who, rule.spec, rule.file)
+}
+
+/// Accounts with a login shell; system accounts without one are left out.
+fn login_users(users: &proto::UserInventory) -> impl Iterator<Item = &proto::LocalUser> {
+    users.users.iter().filter(|u| u.can_login)
+}
+
+/// Plain-text access summary, for Copy.
+pub(crate) fn access_text(users: &proto::UserInventory) -> String {
+    let now = unix_now();
+    let mut out = String::from("Login accounts:\n");
+    for user in login_users(users) {
+        out.push_str(&format!("  {}\n", user_text(user, now)));
+    }
+    out.push_str("Admin groups:\n");
+    for group in &users.admin_groups {
+        out.push_str(&format!("  {}\n", group_text(group)));
+    }
+    out.push_str("sudo rules:\n");
+    for rule in &users.sudo_rules {
+        out.push_str(&format!("  {}\n", rule_text(rule)));
+    }
+    if let Some(error) = &users.sudoers_error {
+        out.push_str(&format!("  (not readable: {})\n", error));
+    }
+    out
+}
+
+impl HostPanel {
+    /// Update the users, admin groups and sudo rules shown in the panel.
+    pub fn set_users(&mut self, users: proto::UserInventory, cx: &mut Context<Self>) {
+        self.users = Some(users);
+        self.freshness.mark(Section::Access, SystemTime::now());
+        if let Some(sched) = self.scheduler_mut() {
+            sched.mark_fetched(Section::Access, Instant::now());
+        }
+        cx.notify();
+    }
+
+    /// Access section: header with controls, then login accounts, admin
+    /// group members and sudo rules. uid 0 accounts other than root are red,
+    /// rules that need no password orange.
+    pub(crate) fn render_access(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let section = div()
+            .flex()
+            .flex_col()
+            .gap_2()
+            .pl(ap.px(8.0))
+            .pr(ap.px(8.0))
+            .py(ap.px(8.0))
+            .border_b_1()
+            .border_color(pal.border)
+            .when(self.is_dimmed(Section::Access), |d| d.opacity(0.5))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .child(div().text_color(pal.fg).child("Access"))
+                    .child(self.render_section_controls(Section::Access, cx)),
+            );
+        let Some(users) = &self.users else {
+            return section.child("Not loaded yet: press ⟳.");
+        };
+
+        let now = unix_now();
+        let heading = |text: &'static str| div().text_color(pal.fg).child(text);
+        section
+            .child(heading("Login accounts"))
+            .children(login_users(users).map(|user| {
+                div()
+                    .pl(ap.px(8.0))
+                    .text_color(if user.uid == 0 && user.name != "root" {
+                        gpui::hsla(0.0, 0.8, 0.6, 1.0)
+                    } else {
+                        pal.fg_dim
+                    })
+                    .child(user_text(user, now))
+            }))
+            .child(heading("Admin groups"))
+            .when(users.admin_groups.is_empty(), |d| {
+                d.child(
+                    div()
+                        .pl(ap.px(8.0))
+                        .text_color(pal.muted)
+                        .child("No sudo, wheel or admin group."),
+                )
+            })
+            .children(users.admin_groups.iter().map(|group| {
+                div()
+                    .pl(ap.px(8.0))
+                    .text_color(pal.fg_dim)
+                    .child(group_text(group))
+            }))
+            .child(heading("sudo rules"))
+            .children(users.sudo_rules.iter().map(|rule| {
+                div()
+                    .pl(ap.px(8.0))
+                    .text_color(if rule.nopasswd {
+                        gpui::hsla(0.13, 0.8, 0.6, 1.0)
+                    } else {
+                        pal.fg_dim
+                    })
+                    .child(rule_text(rule))
+            }))
+            .children(users.sudoers_error.as_ref().map(|error| {
+                div()
+                    .pl(ap.px(8.0))
+                    .text_color(pal.muted)
+                    .child(format!("Not readable: {}", error))
+            }))
+    }
+}
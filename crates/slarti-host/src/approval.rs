@@ -0,0 +1,69 @@
+//! Approval of mutating actions: an optional preview of exactly what will run
+//! (host, user, escalation, command lines) that must be confirmed first.
+
+use crate::policy::ActionCategory;
+use gpui::{App, Global, SharedString};
+
+/// A HostPanel action that changes the remote host.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PendingAction {
+    /// Upload (or update) the agent.
+    Deploy,
+    /// Remove all but the most recent agent versions.
+    CleanAgents,
+    /// Install the user's public key (ssh-copy-id).
+    InstallKey,
+    /// Send a signal to a process (through the agent).
+    SignalProcess { pid: u32, signal: i32 },
+    /// Set a process's nice value (through the agent).
+    ReniceProcess { pid: u32, nice: i32 },
+}
+
+impl PendingAction {
+    /// Policy category the action falls under.
+    pub fn category(self) -> ActionCategory {
+        match self {
+            PendingAction::Deploy | PendingAction::CleanAgents => ActionCategory::Deploy,
+            PendingAction::InstallKey => ActionCategory::KeyInstall,
+            PendingAction::SignalProcess { .. } | PendingAction::ReniceProcess { .. } => {
+                ActionCategory::ProcessControl
+            }
+        }
+    }
+
+    /// Whether the action is previewed for confirmation even when the
+    /// preview setting is off (a signal cannot be taken back; the cleanup
+    /// preview is its dry-run listing).
+    pub fn always_confirm(self) -> bool {
+        matches!(
+            self,
+            PendingAction::SignalProcess { .. }
+                | PendingAction::ReniceProcess { .. }
+                | PendingAction::CleanAgents
+        )
+    }
+}
+
+/// What an action will run, awaiting Run/Cancel.
+#[derive(Clone, Debug)]
+pub struct ActionPreview {
+    pub action: PendingAction,
+    /// Host the preview was built for; Run is refused once another is selected
+    pub alias: String,
+    pub title: SharedString,
+    /// Host, user, escalation and command lines, one per row.
+    pub lines: Vec<SharedString>,
+}
+
+/// User setting: always preview mutating actions before they run.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PreviewActions(pub bool);
+
+impl Global for PreviewActions {}
+
+impl PreviewActions {
+    /// Whether previews are required (off if the app did not install the setting).
+    pub fn get(cx: &App) -> bool {
+        cx.try_global::<PreviewActions>().is_some_and(|p| p.0)
+    }
+}
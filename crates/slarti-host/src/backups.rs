@@ -0,0 +1,178 @@
+//! Backups section: the backup jobs found on the host (borg, borgmatic,
+//! restic, rsnapshot) and how long ago each last succeeded, flagging stale
+//! and failing ones.
+
+use crate::freshness::format_age;
+use crate::{unix_now, HostPanel, Section};
+use gpui::{div, prelude::*, Context, Hsla};
+use slarti_proto as proto;
+use slarti_ui::Appearance;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Age of the last successful run from which a job is stale (a daily job
+/// that missed a run, with slack).
+const STALE_SECS: u64 = 36 * 3_600;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Health {
+    Ok,
+    /// No success within `STALE_SECS` (or none on record)
+    Stale,
+    /// The last run failed
+    Failing,
+}
+
+fn health(job: &proto::BackupJob, now: u64) -> Health {
+    match (job.last_success_usec, job.last_failure_usec) {
+        (Some(success), Some(failure)) if failure > success => Health::Failing,
+        (None, Some(_)) => Health::Failing,
+        (Some(success), _) if now.saturating_sub(success / 1_000_000) <= STALE_SECS => Health::Ok,
+        _ => Health::Stale,
+    }
+}
+
+/// Orange for stale, red for failing, None when fine.
+fn health_color(health: Health) -> Option<Hsla> {
+    match health {
+        Health::Ok => None,
+        Health::Stale => Some(gpui::hsla(0.13, 0.8, 0.6, 1.0)),
+        Health::Failing => Some(gpui::hsla(0.0, 0.8, 0.6, 1.0)),
+    }
+}
+
+/// How long ago `usec` was; in hours up to two days, so ages around
+/// `STALE_SECS` read "30h ago" and "40h ago" rather than both "1d ago".
+fn ago(usec: u64, now: u64) -> String {
+    let secs = now.saturating_sub(usec / 1_000_000);
+    if (3_600..48 * 3_600).contains(&secs) {
+        return format!("{}h ago", secs / 3_600);
+    }
+    format_age(Duration::from_secs(secs))
+}
+
+/// "restic-backup.service (restic): last success 3h ago, last failure 2d ago"
+fn job_text(job: &proto::BackupJob, now: u64) -> String {
+    let mut s = format!("{} ({}): ", job.source, job.tool);
+    match job.last_success_usec {
+        Some(at) => s.push_str(&format!("last success {}", ago(at, now))),
+        None => s.push_str("no success on record"),
+    }
+    if let Some(at) = job.last_failure_usec {
+        s.push_str(&format!(", last failure {}", ago(at, now)));
+    }
+    if job.running {
+        s.push_str(", running");
+    }
+    s
+}
+
+/// "last successful backup: 26h ago" over all jobs, and whether that is stale.
+fn summary(jobs: &[proto::BackupJob], now: u64) -> (String, bool) {
+    match jobs.iter().filter_map(|j| j.last_success_usec).max() {
+        Some(at) => (
+            format!("last successful backup: {}", ago(at, now)),
+            now.saturating_sub(at / 1_000_000) > STALE_SECS,
+        ),
+        None => ("no successful backup on record".to_string(), true),
+    }
+}
+
+/// Plain-text backup jobs, for Copy.
+pub(crate) fn backups_text(jobs: &[proto::BackupJob]) -> String {
+    if jobs.is_empty() {
+        return "No backup jobs found.\n".to_string();
+    }
+    let now = unix_now();
+    let (summary, stale) = summary(jobs, now);
+    let mut out = format!("{}{}\n", summary, if stale { " (stale)" } else { "" });
+    for job in jobs {
+        out.push_str(&format!("  {}\n", job_text(job, now)));
+    }
+    out
+}
+
+impl HostPanel {
+    /// Update the backup jobs shown in the panel.
+    pub fn set_backups(&mut self, jobs: Vec<proto::BackupJob>, cx: &mut Context<Self>) {
+        self.backups = Some(jobs);
+        self.freshness.mark(Section::Backups, SystemTime::now());
+        if let Some(sched) = self.scheduler_mut() {
+            sched.mark_fetched(Section::Backups, Instant::now());
+        }
+        cx.notify();
+    }
+
+    /// Backups section: header with controls, the age of the newest
+    /// successful backup, then one row per job.
+    pub(crate) fn render_backups(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let section = div()
+            .flex()
+            .flex_col()
+            .gap_2()
+            .pl(ap.px(8.0))
+            .pr(ap.px(8.0))
+            .py(ap.px(8.0))
+            .border_b_1()
+            .border_color(pal.border)
+            .when(self.is_dimmed(Section::Backups), |d| d.opacity(0.5))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .child(div().text_color(pal.fg).child("Backups"))
+                    .child(self.render_section_controls(Section::Backups, cx)),
+            );
+        let Some(jobs) = &self.backups else {
+            return section.child("Not loaded yet: press ⟳.");
+        };
+        if jobs.is_empty() {
+            return section.child(
+                div()
+                    .text_color(pal.muted)
+                    .child("No backup jobs found (borg, borgmatic, restic, rsnapshot)."),
+            );
+        }
+
+        let now = unix_now();
+        let (summary, stale) = summary(jobs, now);
+        section
+            .child(
+                div()
+                    .text_color(if stale {
+                        gpui::hsla(0.13, 0.8, 0.6, 1.0)
+                    } else {
+                        pal.fg
+                    })
+                    .child(if stale {
+                        format!("{} (stale)", summary)
+                    } else {
+                        summary
+                    }),
+            )
+            .child(div().flex().flex_col().children(jobs.iter().map(|job| {
+                div()
+                    .pl(ap.px(8.0))
+                    .text_color(health_color(health(job, now)).unwrap_or(pal.fg_dim))
+                    .child(job_text(job, now))
+            })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ages_around_the_stale_threshold_are_in_hours() {
+        let now = 1_000_000;
+        let usec = |secs_ago: u64| (now - secs_ago) * 1_000_000;
+        assert_eq!(ago(usec(90), now), "1m ago");
+        assert_eq!(ago(usec(3 * 3_600), now), "3h ago");
+        assert_eq!(ago(usec(26 * 3_600 + 59), now), "26h ago");
+        assert_eq!(ago(usec(47 * 3_600), now), "47h ago");
+        assert_eq!(ago(usec(2 * 86_400), now), "2d ago");
+    }
+}
@@ -0,0 +1,227 @@
+//! Cgroups section: the host's cgroup hierarchy (systemd slices, services,
+//! scopes) as an expandable tree, with each node's share of the CPU and
+//! memory in use. Clicking a service opens its drawer.
+
+use crate::units::format_bytes;
+use crate::{HostPanel, Section};
+use gpui::{div, prelude::*, relative, Context, MouseButton};
+use slarti_proto as proto;
+use slarti_ui::Appearance;
+use std::time::{Instant, SystemTime};
+
+/// Width of one usage bar.
+const BAR_WIDTH: f32 = 60.0;
+
+/// CPU and memory in use by the whole host, the 100% of the bars.
+#[derive(Clone, Copy)]
+struct Totals {
+    cpu: f32,
+    memory: u64,
+}
+
+impl Totals {
+    fn of(root: &proto::CgroupNode) -> Self {
+        Self {
+            cpu: root.cpu_percent.unwrap_or(0.0),
+            // The root reports no memory of its own.
+            memory: root
+                .memory_bytes
+                .unwrap_or_else(|| root.children.iter().filter_map(|c| c.memory_bytes).sum()),
+        }
+    }
+}
+
+fn share(part: f32, total: f32) -> f32 {
+    if total > 0.0 {
+        (part / total).clamp(0.0, 1.0)
+    } else {
+        0.0
+    }
+}
+
+/// Plain-text tree (like systemd-cgls, with usage), for Copy.
+pub(crate) fn tree_text(root: &proto::CgroupNode) -> String {
+    fn walk(node: &proto::CgroupNode, depth: usize, out: &mut String) {
+        let cpu = node
+            .cpu_percent
+            .map(|c| format!("{:.1}%", c))
+            .unwrap_or_else(|| "-".to_string());
+        let memory = node
+            .memory_bytes
+            .map(format_bytes)
+            .unwrap_or_else(|| "-".to_string());
+        out.push_str(&format!(
+            "{}{}  cpu {} mem {}\n",
+            "  ".repeat(depth),
+            node.name,
+            cpu,
+            memory
+        ));
+        for child in &node.children {
+            walk(child, depth + 1, out);
+        }
+    }
+    let mut out = String::new();
+    walk(root, 0, &mut out);
+    out
+}
+
+impl HostPanel {
+    /// Update the cgroup tree shown in the panel.
+    pub fn set_cgroups(&mut self, root: proto::CgroupNode, cx: &mut Context<Self>) {
+        self.cgroups = Some(root);
+        self.freshness.mark(Section::Cgroups, SystemTime::now());
+        if let Some(sched) = self.scheduler_mut() {
+            sched.mark_fetched(Section::Cgroups, Instant::now());
+        }
+        cx.notify();
+    }
+
+    /// Whether the node at `path` shows its children (the root does unless
+    /// toggled; others do once toggled).
+    fn cgroup_expanded(&self, path: &str, depth: usize) -> bool {
+        (depth == 0) != self.cgroups_toggled.contains(path)
+    }
+
+    fn toggle_cgroup(&mut self, path: String, cx: &mut Context<Self>) {
+        if !self.cgroups_toggled.remove(&path) {
+            self.cgroups_toggled.insert(path);
+        }
+        cx.notify();
+    }
+
+    /// Rows of `node` and its expanded descendants, depth first.
+    fn cgroup_rows(
+        &self,
+        node: &proto::CgroupNode,
+        depth: usize,
+        totals: Totals,
+        rows: &mut Vec<gpui::Div>,
+        cx: &mut Context<Self>,
+    ) {
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let expanded = self.cgroup_expanded(&node.path, depth);
+        let bar = |fraction: f32, label: String| {
+            div()
+                .flex()
+                .items_center()
+                .gap_1()
+                .child(
+                    div()
+                        .w(ap.px(BAR_WIDTH))
+                        .h(ap.px(8.0))
+                        .bg(pal.border)
+                        .child(div().h_full().w(relative(fraction)).bg(pal.accent)),
+                )
+                .child(div().w(ap.px(56.0)).child(label))
+        };
+
+        let toggle = if node.children.is_empty() {
+            div().w(ap.px(12.0))
+        } else {
+            let path = node.path.clone();
+            div()
+                .w(ap.px(12.0))
+                .cursor_pointer()
+                .text_color(pal.muted)
+                .child(if expanded { "▾" } else { "▸" })
+                .on_mouse_up(
+                    MouseButton::Left,
+                    cx.listener(move |this: &mut Self, _ev, _w, cx| {
+                        this.toggle_cgroup(path.clone(), cx);
+                    }),
+                )
+        };
+        let mut name = div().text_color(pal.fg).child(if node.processes > 0 {
+            format!("{} ({})", node.name, node.processes)
+        } else {
+            node.name.clone()
+        });
+        if node.name.ends_with(".service") {
+            let unit = node.name.clone();
+            name = name.cursor_pointer().on_mouse_up(
+                MouseButton::Left,
+                cx.listener(move |this: &mut Self, _ev, _w, cx| {
+                    this.open_service(unit.clone(), cx);
+                }),
+            );
+        }
+
+        rows.push(
+            div()
+                .flex()
+                .items_center()
+                .justify_between()
+                .h(ap.px(20.0))
+                .child(
+                    div()
+                        .flex()
+                        .items_center()
+                        .pl(ap.px(12.0 * depth as f32))
+                        .child(toggle)
+                        .child(name),
+                )
+                .child(
+                    div()
+                        .flex()
+                        .gap_2()
+                        .child(bar(
+                            share(node.cpu_percent.unwrap_or(0.0), totals.cpu),
+                            node.cpu_percent
+                                .map(|c| format!("{:.1}%", c))
+                                .unwrap_or_default(),
+                        ))
+                        .child(bar(
+                            share(node.memory_bytes.unwrap_or(0) as f32, totals.memory as f32),
+                            node.memory_bytes.map(format_bytes).unwrap_or_default(),
+                        )),
+                ),
+        );
+        if expanded {
+            for child in &node.children {
+                self.cgroup_rows(child, depth + 1, totals, rows, cx);
+            }
+        }
+    }
+
+    /// Cgroups section: header with controls, then the tree.
+    pub(crate) fn render_cgroups(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let mut rows = Vec::new();
+        if let Some(root) = &self.cgroups {
+            self.cgroup_rows(root, 0, Totals::of(root), &mut rows, cx);
+        }
+        div()
+            .flex()
+            .flex_col()
+            .gap_2()
+            .pl(ap.px(8.0))
+            .pr(ap.px(8.0))
+            .py(ap.px(8.0))
+            .border_b_1()
+            .border_color(pal.border)
+            .when(self.is_dimmed(Section::Cgroups), |d| d.opacity(0.5))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .child(div().text_color(pal.fg).child("Cgroups"))
+                    .child(self.render_section_controls(Section::Cgroups, cx)),
+            )
+            .child(if rows.is_empty() {
+                div().child("Not loaded yet: press ⟳.").into_any_element()
+            } else {
+                div()
+                    .id("CgroupsScroll")
+                    .flex()
+                    .flex_col()
+                    .max_h(ap.px(240.0))
+                    .overflow_y_scroll()
+                    .children(rows)
+                    .into_any_element()
+            })
+    }
+}
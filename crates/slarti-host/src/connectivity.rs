@@ -0,0 +1,346 @@
+//! Connectivity section: whether the host can reach the endpoints the user
+//! listed (package mirrors, registries, internal APIs), as a green/red grid
+//! with connect latency, the resolver setup, the routes the traffic takes
+//! and the host's socket counts. Handy after firewall and DNS changes.
+
+use crate::{config_path, load_list, HostPanel, Section};
+use gpui::{div, prelude::*, Context, Hsla};
+use slarti_proto as proto;
+use slarti_ui::Appearance;
+use std::time::{Instant, SystemTime};
+
+/// User-edited list of "host:port" entries ("[2001:db8::1]:443" for IPv6
+/// addresses) checked from every host.
+const ENDPOINTS_FILE: &str = "endpoints.yaml";
+
+/// "host:port" → Endpoint; None when the port is missing or invalid.
+fn parse_endpoint(entry: &str) -> Option<proto::Endpoint> {
+    let (host, port) = entry.rsplit_once(':')?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if host.is_empty() {
+        return None;
+    }
+    Some(proto::Endpoint {
+        host: host.to_string(),
+        port: port.trim().parse().ok()?,
+    })
+}
+
+/// The endpoints listed in `endpoints.yaml` of the config dir, each once
+/// (entries that are not host:port are skipped).
+pub fn reachability_endpoints() -> Vec<proto::Endpoint> {
+    let mut endpoints: Vec<proto::Endpoint> = Vec::new();
+    for endpoint in load_list(&config_path(ENDPOINTS_FILE))
+        .iter()
+        .filter_map(|entry| parse_endpoint(entry))
+    {
+        if !endpoints.contains(&endpoint) {
+            endpoints.push(endpoint);
+        }
+    }
+    endpoints
+}
+
+fn endpoint_text(endpoint: &proto::Endpoint) -> String {
+    if endpoint.host.contains(':') {
+        format!("[{}]:{}", endpoint.host, endpoint.port)
+    } else {
+        format!("{}:{}", endpoint.host, endpoint.port)
+    }
+}
+
+/// "14 ms", or why the endpoint is unreachable.
+fn status_text(result: &proto::ReachResult) -> String {
+    match (result.latency_ms, &result.error) {
+        (Some(ms), _) => format!("{:.0} ms", ms),
+        (None, Some(error)) => error.clone(),
+        (None, None) => "unreachable".to_string(),
+    }
+}
+
+fn status_color(result: &proto::ReachResult) -> Hsla {
+    if result.latency_ms.is_some() {
+        gpui::hsla(0.33, 0.6, 0.5, 1.0)
+    } else {
+        gpui::hsla(0.0, 0.8, 0.6, 1.0)
+    }
+}
+
+/// Resolver summary lines: "nameservers: 127.0.0.53 (systemd-resolved)",
+/// "search: corp.example".
+fn resolver_lines(config: &proto::DnsConfig) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut servers = if config.nameservers.is_empty() {
+        "nameservers: none".to_string()
+    } else {
+        format!("nameservers: {}", config.nameservers.join(", "))
+    };
+    if config.resolved_status.is_some() {
+        servers.push_str(" (systemd-resolved)");
+    }
+    lines.push(servers);
+    if !config.search.is_empty() {
+        lines.push(format!("search: {}", config.search.join(" ")));
+    }
+    match (&config.resolv_conf, &config.resolv_conf_target) {
+        (None, _) => lines.push("/etc/resolv.conf cannot be read".to_string()),
+        (Some(_), Some(target)) => lines.push(format!("/etc/resolv.conf → {}", target)),
+        (Some(_), None) => {}
+    }
+    lines
+}
+
+/// "default via 192.0.2.1 dev eth0 proto dhcp src 192.0.2.2 metric 100",
+/// as `ip route` prints it.
+fn route_text(route: &proto::Route) -> String {
+    let mut s = route.destination.clone();
+    if let Some(gateway) = &route.gateway {
+        s.push_str(&format!(" via {}", gateway));
+    }
+    s.push_str(&format!(" dev {}", route.dev));
+    if let Some(protocol) = &route.protocol {
+        s.push_str(&format!(" proto {}", protocol));
+    }
+    if let Some(source) = &route.source {
+        s.push_str(&format!(" src {}", source));
+    }
+    if let Some(metric) = route.metric {
+        s.push_str(&format!(" metric {}", metric));
+    }
+    s
+}
+
+/// "TCP 14 (estab 9, timewait 3, listen 2, orphaned 0), UDP 4, unix 61",
+/// as `ss -s` summarizes it.
+fn sockets_line(summary: &proto::SocketSummary) -> String {
+    let mut tcp = format!(
+        "estab {}, timewait {}, listen {}",
+        summary.tcp_established, summary.tcp_time_wait, summary.tcp_listen
+    );
+    if let Some(orphaned) = summary.tcp_orphaned {
+        tcp.push_str(&format!(", orphaned {}", orphaned));
+    }
+    let mut s = format!(
+        "TCP {} ({}), UDP {}, unix {}",
+        summary.tcp_total, tcp, summary.udp, summary.unix
+    );
+    if let Some(used) = summary.used {
+        s.push_str(&format!("; {} in use", used));
+    }
+    s
+}
+
+/// "nginx (812): 4 established of 6"
+fn socket_user_text(user: &proto::SocketUser) -> String {
+    format!(
+        "{} ({}): {} established of {}",
+        user.process, user.pid, user.established, user.sockets
+    )
+}
+
+/// Plain-text reachability results, resolver setup, routes and socket
+/// counts, for Copy.
+pub(crate) fn connectivity_text(
+    results: Option<&Vec<proto::ReachResult>>,
+    dns: Option<&proto::DnsConfig>,
+    routes: Option<&Vec<proto::Route>>,
+    sockets: Option<&proto::SocketSummary>,
+) -> Option<String> {
+    if results.is_none() && dns.is_none() && routes.is_none() && sockets.is_none() {
+        return None;
+    }
+    let mut out = String::new();
+    for result in results.into_iter().flatten() {
+        let state = if result.latency_ms.is_some() {
+            "ok"
+        } else {
+            "FAIL"
+        };
+        out.push_str(&format!(
+            "{:<4} {} ({})\n",
+            state,
+            endpoint_text(&result.endpoint),
+            status_text(result)
+        ));
+    }
+    if let Some(dns) = dns {
+        out.push_str("resolver:\n");
+        for line in resolver_lines(dns) {
+            out.push_str(&format!("  {}\n", line));
+        }
+        if let Some(resolv_conf) = &dns.resolv_conf {
+            out.push_str("resolv.conf:\n");
+            for line in resolv_conf.lines() {
+                out.push_str(&format!("  {}\n", line));
+            }
+        }
+    }
+    if let Some(routes) = routes {
+        out.push_str("routes:\n");
+        for route in routes {
+            out.push_str(&format!("  {}\n", route_text(route)));
+        }
+    }
+    if let Some(sockets) = sockets {
+        out.push_str(&format!("sockets: {}\n", sockets_line(sockets)));
+        for user in &sockets.top_processes {
+            out.push_str(&format!("  {}\n", socket_user_text(user)));
+        }
+    }
+    Some(out)
+}
+
+impl HostPanel {
+    /// Update the reachability results shown in the panel.
+    pub fn set_connectivity(&mut self, results: Vec<proto::ReachResult>, cx: &mut Context<Self>) {
+        self.connectivity = Some(results);
+        self.freshness
+            .mark(Section::Connectivity, SystemTime::now());
+        if let Some(sched) = self.scheduler_mut() {
+            sched.mark_fetched(Section::Connectivity, Instant::now());
+        }
+        cx.notify();
+    }
+
+    /// Update the resolver setup shown in the panel.
+    pub fn set_dns_config(&mut self, config: proto::DnsConfig, cx: &mut Context<Self>) {
+        self.dns_config = Some(config);
+        self.freshness
+            .mark(Section::Connectivity, SystemTime::now());
+        if let Some(sched) = self.scheduler_mut() {
+            sched.mark_fetched(Section::Connectivity, Instant::now());
+        }
+        cx.notify();
+    }
+
+    /// Update the routing table shown in the panel.
+    pub fn set_routes(&mut self, routes: Vec<proto::Route>, cx: &mut Context<Self>) {
+        self.routes = Some(routes);
+        self.freshness
+            .mark(Section::Connectivity, SystemTime::now());
+        if let Some(sched) = self.scheduler_mut() {
+            sched.mark_fetched(Section::Connectivity, Instant::now());
+        }
+        cx.notify();
+    }
+
+    /// Update the socket counts shown in the panel.
+    pub fn set_sockets(&mut self, summary: proto::SocketSummary, cx: &mut Context<Self>) {
+        self.sockets = Some(summary);
+        self.freshness
+            .mark(Section::Connectivity, SystemTime::now());
+        if let Some(sched) = self.scheduler_mut() {
+            sched.mark_fetched(Section::Connectivity, Instant::now());
+        }
+        cx.notify();
+    }
+
+    /// Connectivity section: header with controls, then one cell per
+    /// endpoint, green when the host connected to it and red when not, the
+    /// resolver setup, the routing table and the socket counts with the
+    /// processes holding the most connections.
+    pub(crate) fn render_connectivity(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let section = div()
+            .flex()
+            .flex_col()
+            .gap_2()
+            .pl(ap.px(8.0))
+            .pr(ap.px(8.0))
+            .py(ap.px(8.0))
+            .border_b_1()
+            .border_color(pal.border)
+            .when(self.is_dimmed(Section::Connectivity), |d| d.opacity(0.5))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .child(div().text_color(pal.fg).child("Connectivity"))
+                    .child(self.render_section_controls(Section::Connectivity, cx)),
+            );
+        if self.connectivity.is_none()
+            && self.dns_config.is_none()
+            && self.routes.is_none()
+            && self.sockets.is_none()
+        {
+            return section.child("Not loaded yet: press ⟳.");
+        }
+
+        let section = match &self.connectivity {
+            None => section,
+            Some(results) if results.is_empty() => {
+                section.child(div().text_color(pal.muted).child(format!(
+                    "No endpoints to check: list host:port entries in {}.",
+                    config_path(ENDPOINTS_FILE).display()
+                )))
+            }
+            Some(results) => section.child(div().flex().flex_wrap().gap_2().children(
+                results.iter().map(|result| {
+                    let color = status_color(result);
+                    div()
+                        .flex()
+                        .flex_col()
+                        .px(ap.px(6.0))
+                        .py(ap.px(4.0))
+                        .max_w(ap.px(220.0))
+                        .border_1()
+                        .border_color(color)
+                        .rounded_md()
+                        .child(
+                            div()
+                                .text_color(pal.fg)
+                                .child(endpoint_text(&result.endpoint)),
+                        )
+                        .child(div().text_color(color).child(status_text(result)))
+                }),
+            )),
+        };
+        let section = match &self.dns_config {
+            None => section,
+            Some(config) => section
+                .child(div().text_color(pal.muted).child("Resolver"))
+                .child(
+                    div().flex().flex_col().children(
+                        resolver_lines(config)
+                            .into_iter()
+                            .map(|line| div().pl(ap.px(8.0)).text_color(pal.fg_dim).child(line)),
+                    ),
+                ),
+        };
+        let section = match &self.routes {
+            None => section,
+            Some(routes) => section
+                .child(div().text_color(pal.muted).child("Routes"))
+                .child(div().flex().flex_col().children(routes.iter().map(|route| {
+                    div()
+                        .pl(ap.px(8.0))
+                        .text_color(pal.fg_dim)
+                        .child(route_text(route))
+                }))),
+        };
+        let Some(sockets) = &self.sockets else {
+            return section;
+        };
+        section
+            .child(div().text_color(pal.muted).child("Sockets"))
+            .child(
+                div()
+                    .pl(ap.px(8.0))
+                    .text_color(pal.fg_dim)
+                    .child(sockets_line(sockets)),
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .children(sockets.top_processes.iter().map(|user| {
+                        div()
+                            .pl(ap.px(16.0))
+                            .text_color(pal.fg_dim)
+                            .child(socket_user_text(user))
+                    })),
+            )
+    }
+}
@@ -0,0 +1,72 @@
+//! CSV export of the panel's tables (RFC 4180 quoting).
+
+use crate::Section;
+
+/// Tables the host panel can export.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum CsvTable {
+    /// The services list, with its current filters applied.
+    Services,
+    /// The busiest processes, by CPU then by memory, in the agent's order.
+    Processes,
+    /// The open ports.
+    Listeners,
+    /// Health of every configured host (start screen).
+    Fleet,
+}
+
+impl CsvTable {
+    /// The table behind `section`'s CSV button, if it has one.
+    pub(crate) fn for_section(section: Section) -> Option<Self> {
+        match section {
+            Section::Services => Some(Self::Services),
+            Section::Processes => Some(Self::Processes),
+            Section::Listeners => Some(Self::Listeners),
+            _ => None,
+        }
+    }
+}
+
+/// Append one CSV record to `out`.
+pub(crate) fn push_record<S: AsRef<str>>(out: &mut String, fields: impl IntoIterator<Item = S>) {
+    for (i, field) in fields.into_iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let field = field.as_ref();
+        if field.contains([',', '"', '\n', '\r']) {
+            out.push('"');
+            out.push_str(&field.replace('"', "\"\""));
+            out.push('"');
+        } else {
+            out.push_str(field);
+        }
+    }
+    out.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_fields_are_written_as_is() {
+        let mut out = String::new();
+        push_record(&mut out, ["unit", "active", ""]);
+        push_record(&mut out, Vec::<String>::new());
+        assert_eq!(out, "unit,active,\r\n\r\n");
+    }
+
+    #[test]
+    fn special_fields_are_quoted() {
+        let mut out = String::new();
+        push_record(
+            &mut out,
+            ["a,b", "say \"hi\"", "two\nlines", "cr\r", "\"", "plain"],
+        );
+        assert_eq!(
+            out,
+            "\"a,b\",\"say \"\"hi\"\"\",\"two\nlines\",\"cr\r\",\"\"\"\",plain\r\n"
+        );
+    }
+}
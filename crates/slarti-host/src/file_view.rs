@@ -0,0 +1,296 @@
+//! File drawer: clicking one of a process's open files reads it through the
+//! agent a window at a time, so configs and logs can be checked without
+//! copying them over. Follow tails the file as it grows (a `TailFile`
+//! stream) until stopped.
+
+use crate::units::format_bytes;
+use crate::{AgentRequest, HostPanel};
+use gpui::{div, prelude::*, Context, MouseButton};
+use slarti_proto as proto;
+use slarti_ui::Appearance;
+use std::collections::VecDeque;
+
+/// Bytes read per window.
+pub(crate) const WINDOW: u64 = 64 * 1024;
+/// Lines of backlog a follow starts with.
+pub(crate) const TAIL_LINES: usize = 200;
+/// Followed lines kept; older ones are dropped.
+const TAIL_KEEP: usize = 2000;
+
+/// A window of the open file and its text (invalid UTF-8 replaced).
+pub(crate) struct FileWindow {
+    offset: u64,
+    len: u64,
+    size: u64,
+    eof: bool,
+    text: String,
+}
+
+/// The lines of a followed file.
+pub(crate) struct FileTail {
+    lines: VecDeque<String>,
+    /// Id of the agent's stream once it started
+    stream: Option<u64>,
+    /// False once the user stopped it or the stream ended
+    following: bool,
+}
+
+/// "12 KiB", or "64 KiB–128 KiB of 2.3 MiB" for part of a file.
+fn window_text(w: &FileWindow) -> String {
+    if w.offset == 0 && w.eof {
+        return format_bytes(w.size);
+    }
+    format!(
+        "{}–{} of {}",
+        format_bytes(w.offset),
+        format_bytes(w.offset + w.len),
+        format_bytes(w.size)
+    )
+}
+
+impl HostPanel {
+    /// Open the drawer for `path` and read its first window.
+    pub(crate) fn open_file(&mut self, path: String, cx: &mut Context<Self>) {
+        self.file_window = None;
+        self.agent_requests.push(AgentRequest::ReadFile {
+            path: path.clone(),
+            offset: 0,
+        });
+        self.file_path = Some(path);
+        cx.notify();
+    }
+
+    fn read_file_at(&mut self, offset: u64, cx: &mut Context<Self>) {
+        let Some(path) = self.file_path.clone() else {
+            return;
+        };
+        self.agent_requests
+            .push(AgentRequest::ReadFile { path, offset });
+        cx.notify();
+    }
+
+    fn close_file(&mut self, cx: &mut Context<Self>) {
+        self.stop_following(cx);
+        self.file_path = None;
+        self.file_window = None;
+        self.file_tail = None;
+        cx.notify();
+    }
+
+    /// Tail the open file and keep appending what is written to it.
+    fn follow_file(&mut self, cx: &mut Context<Self>) {
+        let Some(path) = self.file_path.clone() else {
+            return;
+        };
+        self.stop_following(cx);
+        self.file_tail = Some(FileTail {
+            lines: VecDeque::new(),
+            stream: None,
+            following: true,
+        });
+        self.agent_requests.push(AgentRequest::TailFile { path });
+        cx.notify();
+    }
+
+    /// End the follow, keeping the lines received.
+    fn stop_following(&mut self, cx: &mut Context<Self>) {
+        let Some(tail) = self.file_tail.as_mut() else {
+            return;
+        };
+        tail.following = false;
+        if let Some(id) = tail.stream.take() {
+            self.agent_requests.push(AgentRequest::TailStop { id });
+        }
+        cx.notify();
+    }
+
+    /// The session loop started the stream for `request` under `id`; one
+    /// no longer wanted (stopped, closed or for another file) is ended.
+    pub fn stream_started(&mut self, request: AgentRequest, id: u64, cx: &mut Context<Self>) {
+        let AgentRequest::TailFile { path } = request else {
+            return;
+        };
+        match self.file_tail.as_mut() {
+            Some(tail)
+                if tail.following && tail.stream.is_none() && self.file_path == Some(path) =>
+            {
+                tail.stream = Some(id)
+            }
+            _ => self.agent_requests.push(AgentRequest::TailStop { id }),
+        }
+        cx.notify();
+    }
+
+    /// Frames of the agent's streams, in arrival order.
+    pub fn apply_stream_frames(&mut self, frames: Vec<proto::Response>, cx: &mut Context<Self>) {
+        for frame in frames {
+            let Some(tail) = self
+                .file_tail
+                .as_mut()
+                .filter(|t| t.stream.is_some() && t.stream == frame.id())
+            else {
+                continue;
+            };
+            match frame {
+                proto::Response::FileLines { lines, end, .. } => {
+                    tail.lines.extend(lines);
+                    while tail.lines.len() > TAIL_KEEP {
+                        tail.lines.pop_front();
+                    }
+                    if end {
+                        tail.stream = None;
+                        tail.following = false;
+                    }
+                }
+                proto::Response::Error { message, .. } => {
+                    tail.stream = None;
+                    tail.following = false;
+                    self.push_progress(format!("follow: {}", message), cx);
+                }
+                _ => {}
+            }
+        }
+        cx.notify();
+    }
+
+    /// Show the agent's answer to a `ReadFile`.
+    pub(crate) fn apply_file_response(
+        &mut self,
+        path: String,
+        response: proto::Response,
+        cx: &mut Context<Self>,
+    ) {
+        // Ignore a late answer for a drawer closed or switched meanwhile.
+        if self.file_path.as_ref() != Some(&path) {
+            return;
+        }
+        match response {
+            proto::Response::ReadFileOk { chunk, .. } => match chunk.bytes() {
+                Ok(bytes) => {
+                    self.file_window = Some(FileWindow {
+                        offset: chunk.offset,
+                        len: chunk.len,
+                        size: chunk.size,
+                        eof: chunk.eof,
+                        text: String::from_utf8_lossy(&bytes).into_owned(),
+                    })
+                }
+                Err(e) => self.push_progress(format!("{}: {}", path, e), cx),
+            },
+            proto::Response::Error { message, .. } => {
+                self.file_path = None;
+                self.file_window = None;
+                self.push_progress(format!("{}: {}", path, message), cx);
+            }
+            _ => {}
+        }
+    }
+
+    /// Drawer with the open file's current window and buttons to move it.
+    pub(crate) fn render_file_drawer(&self, cx: &mut Context<Self>) -> Option<gpui::Div> {
+        let path = self.file_path.as_ref()?;
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let button = |label: &'static str| {
+            div()
+                .px(ap.px(8.0))
+                .h(ap.px(18.0))
+                .rounded_sm()
+                .border_1()
+                .border_color(pal.border)
+                .cursor_pointer()
+                .text_color(pal.fg)
+                .child(label)
+        };
+        let go = |label: &'static str, offset: u64| {
+            button(label).on_mouse_up(
+                MouseButton::Left,
+                cx.listener(move |this: &mut Self, _ev, _window, cx| {
+                    this.read_file_at(offset, cx);
+                }),
+            )
+        };
+
+        let mut controls = div().flex().items_center().gap_2();
+        let mut body = div()
+            .id("FileDrawer")
+            .flex()
+            .flex_col()
+            .max_h(ap.px(240.0))
+            .overflow_y_scroll();
+        if let Some(tail) = &self.file_tail {
+            // Following (or stopped with the lines kept): the tail replaces the window.
+            controls = controls
+                .child(div().text_color(pal.muted).child(if tail.following {
+                    "following"
+                } else {
+                    "stopped"
+                }))
+                .child(if tail.following {
+                    button("Stop").on_mouse_up(
+                        MouseButton::Left,
+                        cx.listener(|this: &mut Self, _ev, _window, cx| this.stop_following(cx)),
+                    )
+                } else {
+                    button("Follow").on_mouse_up(
+                        MouseButton::Left,
+                        cx.listener(|this: &mut Self, _ev, _window, cx| this.follow_file(cx)),
+                    )
+                })
+                .child(button("Window").on_mouse_up(
+                    MouseButton::Left,
+                    cx.listener(|this: &mut Self, _ev, _window, cx| {
+                        this.stop_following(cx);
+                        this.file_tail = None;
+                    }),
+                ));
+            body = body.children(tail.lines.iter().map(|l| div().child(l.clone())));
+        } else {
+            match &self.file_window {
+                None => body = body.child(div().text_color(pal.muted).child("loading…")),
+                Some(w) => {
+                    controls = controls.child(div().text_color(pal.muted).child(window_text(w)));
+                    if w.offset > 0 {
+                        controls = controls
+                            .child(go("Start", 0))
+                            .child(go("Previous", w.offset.saturating_sub(WINDOW)));
+                    }
+                    if !w.eof {
+                        controls = controls
+                            .child(go("Next", w.offset + w.len))
+                            .child(go("End", w.size.saturating_sub(WINDOW)));
+                    }
+                    body = body.children(w.text.lines().map(|l| div().child(l.to_string())));
+                }
+            }
+            controls = controls.child(button("Follow").on_mouse_up(
+                MouseButton::Left,
+                cx.listener(|this: &mut Self, _ev, _window, cx| this.follow_file(cx)),
+            ));
+        }
+
+        Some(
+            div()
+                .flex()
+                .flex_col()
+                .gap_1()
+                .p(ap.px(8.0))
+                .border_b_1()
+                .border_color(pal.border)
+                .child(
+                    div()
+                        .flex()
+                        .items_center()
+                        .justify_between()
+                        .child(div().text_color(pal.fg).child(path.clone()))
+                        .child(controls.child(button("Close").on_mouse_up(
+                            MouseButton::Left,
+                            cx.listener(|this: &mut Self, _ev, _window, cx| {
+                                this.close_file(cx);
+                            }),
+                        ))),
+                )
+                .child(body),
+        )
+    }
+}
@@ -0,0 +1,80 @@
+//! Fleet health for the start screen, derived from cached host snapshots
+//! (no ssh connections are made to compute it).
+
+use crate::snapshot::HostSnapshot;
+
+/// Data older than this counts as stale.
+const STALE_AFTER_SECS: u64 = 24 * 60 * 60;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Health {
+    /// Seen recently with no failed services.
+    Healthy,
+    /// Seen recently with failed services.
+    Degraded,
+    /// Last seen more than a day ago.
+    Stale,
+    /// Never fetched.
+    Unknown,
+}
+
+impl Health {
+    pub fn color(self) -> gpui::Hsla {
+        match self {
+            Health::Healthy => gpui::green(),
+            Health::Degraded => gpui::hsla(0.0, 0.8, 0.6, 1.0),
+            Health::Stale => gpui::hsla(0.13, 0.8, 0.6, 1.0),
+            Health::Unknown => gpui::opaque_grey(0.5, 1.0),
+        }
+    }
+}
+
+/// Summary of one host's last known state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HostHealth {
+    pub health: Health,
+    pub failed_services: usize,
+    /// Pending package updates at the last check; None if never checked
+    pub pending_updates: Option<usize>,
+    /// A reboot was pending at the last check
+    pub reboot_required: bool,
+    /// Most recent capture time of any section (unix seconds).
+    pub seen_at: Option<u64>,
+}
+
+impl HostHealth {
+    pub const UNKNOWN: HostHealth = HostHealth {
+        health: Health::Unknown,
+        failed_services: 0,
+        pending_updates: None,
+        reboot_required: false,
+        seen_at: None,
+    };
+
+    pub fn from_snapshot(snapshot: &HostSnapshot, now: u64) -> Self {
+        let seen_at = snapshot.sys_info_at.max(snapshot.services_at);
+        let failed_services = snapshot
+            .services
+            .iter()
+            .flatten()
+            .filter(|s| s.active_state == "failed")
+            .count();
+        let health = match seen_at {
+            None => Health::Unknown,
+            Some(at) if now.saturating_sub(at) > STALE_AFTER_SECS => Health::Stale,
+            Some(_) if failed_services > 0 => Health::Degraded,
+            Some(_) => Health::Healthy,
+        };
+        Self {
+            health,
+            failed_services,
+            pending_updates: snapshot.pending_updates,
+            reboot_required: snapshot
+                .sys_info
+                .as_ref()
+                .and_then(|info| info.reboot.as_ref())
+                .is_some_and(|r| r.required()),
+            seen_at,
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! Data-freshness model shared by HostPanel sections.
+//!
+//! Each section records when its data was last fetched, and the panel tracks
+//! whether the agent session that produced it is still alive. Sections render
+//! "updated 2m ago" and grey out once the session drops instead of silently
+//! showing old values. A failed fetch is kept next to the section until the
+//! next successful one.
+
+use crate::poll::Section;
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+/// State of the agent session feeding the panel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SessionState {
+    /// Probing/handshaking; data may not have arrived yet.
+    #[default]
+    Connecting,
+    /// Session open; fetched data is current.
+    Live,
+    /// Session ended or failed; anything shown is stale.
+    Dropped,
+}
+
+/// Per-section fetch timestamps and failures, plus the state of the session
+/// that produced them.
+#[derive(Clone, Debug, Default)]
+pub struct DataFreshness {
+    fetched_at: HashMap<Section, SystemTime>,
+    failed: HashMap<Section, String>,
+    session: SessionState,
+}
+
+impl DataFreshness {
+    /// Record that `section` was fetched at `at` (clearing an earlier failure).
+    pub fn mark(&mut self, section: Section, at: SystemTime) {
+        self.fetched_at.insert(section, at);
+        self.failed.remove(&section);
+    }
+
+    /// Record that fetching `section` failed with `message`.
+    pub fn fail(&mut self, section: Section, message: String) {
+        self.failed.insert(section, message);
+    }
+
+    /// Why the last fetch of `section` failed, unless one succeeded since.
+    pub fn failure(&self, section: Section) -> Option<&str> {
+        self.failed.get(&section).map(String::as_str)
+    }
+
+    pub fn fetched_at(&self, section: Section) -> Option<SystemTime> {
+        self.fetched_at.get(&section).copied()
+    }
+
+    pub fn session(&self) -> SessionState {
+        self.session
+    }
+
+    pub fn set_session(&mut self, state: SessionState) {
+        self.session = state;
+    }
+
+    /// True when data for `section` is shown but the session behind it is gone.
+    pub fn is_stale(&self, section: Section) -> bool {
+        self.session == SessionState::Dropped && self.fetched_at.contains_key(&section)
+    }
+
+    /// Human readable age of a section's data relative to `now`, e.g. "updated 2m ago".
+    pub fn age_label(&self, section: Section, now: SystemTime) -> Option<String> {
+        let at = self.fetched_at(section)?;
+        let age = now.duration_since(at).unwrap_or_default();
+        Some(format!("updated {}", format_age(age)))
+    }
+}
+
+/// Format an age using its largest whole unit ("just now", "12s ago", "2m ago", "3h ago", "2d ago").
+pub fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    if secs < 5 {
+        "just now".to_string()
+    } else if secs < 60 {
+        format!("{}s ago", secs)
+    } else if secs < 3_600 {
+        format!("{}m ago", secs / 60)
+    } else if secs < 86_400 {
+        format!("{}h ago", secs / 3_600)
+    } else {
+        format!("{}d ago", secs / 86_400)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_fetch_clears_the_failure_before_it() {
+        let mut freshness = DataFreshness::default();
+        freshness.fail(Section::Services, "batch failed: timed out".to_string());
+        assert_eq!(
+            freshness.failure(Section::Services),
+            Some("batch failed: timed out")
+        );
+        assert_eq!(freshness.failure(Section::SysInfo), None);
+
+        freshness.mark(Section::Services, SystemTime::UNIX_EPOCH);
+        assert_eq!(freshness.failure(Section::Services), None);
+    }
+}
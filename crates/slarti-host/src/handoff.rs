@@ -0,0 +1,143 @@
+//! Handoff bundles: the context for one host (cached snapshot, current
+//! alerts, free-text notes) written to a file that another slarti user can
+//! import during an on-call handover.
+
+use crate::fleet::{Health, HostHealth};
+use crate::snapshot::{format_timestamp, unix_secs, HostSnapshot};
+use anyhow::{bail, Context as _, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Current bundle format version.
+pub const HANDOFF_VERSION: u32 = 1;
+/// File name suffix of exported bundles.
+pub const HANDOFF_EXTENSION: &str = "slarti-handoff.json";
+
+/// One host's context for a handover.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HandoffBundle {
+    /// Format version; newer bundles are read as far as the fields are understood.
+    pub version: u32,
+    pub alias: String,
+    /// When the bundle was exported (unix seconds).
+    pub exported_at: u64,
+    /// Who exported it ($USER).
+    pub exported_by: String,
+    /// Free text for the person taking over (may be edited in the file before sending).
+    pub notes: String,
+    /// Problems visible at export time, e.g. "nginx.service failed".
+    pub alerts: Vec<String>,
+    /// Last known data for the host.
+    pub snapshot: HostSnapshot,
+}
+
+impl HandoffBundle {
+    pub fn new(alias: &str, snapshot: HostSnapshot, at: SystemTime) -> Self {
+        let now = unix_secs(at);
+        Self {
+            version: HANDOFF_VERSION,
+            alias: alias.to_string(),
+            exported_at: now,
+            exported_by: std::env::var("USER").unwrap_or_default(),
+            notes: String::new(),
+            alerts: alerts_for(&snapshot, now),
+            snapshot,
+        }
+    }
+
+    /// Suggested file name, e.g. "web1-20240501-1432.slarti-handoff.json".
+    pub fn file_name(&self) -> String {
+        let stamp: String = format_timestamp(self.exported_at)
+            .chars()
+            .filter(|c| c.is_ascii_digit())
+            .collect();
+        format!(
+            "{}-{}-{}.{}",
+            self.alias.replace(['/', '\\', ':'], "_"),
+            &stamp[..8],
+            &stamp[8..],
+            HANDOFF_EXTENSION
+        )
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let data = serde_json::to_vec_pretty(self).map_err(std::io::Error::other)?;
+        slarti_state::write(path, data)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = std::fs::read(path).with_context(|| format!("read {:?}", path))?;
+        let bundle: Self =
+            serde_json::from_slice(&bytes).with_context(|| format!("parse {:?}", path))?;
+        if bundle.alias.is_empty() {
+            bail!("{:?} is not a handoff bundle (no host)", path);
+        }
+        check_alias(&bundle.alias).with_context(|| format!("{:?}", path))?;
+        Ok(bundle)
+    }
+
+    /// One-line summary, e.g. "Handoff for web1 from alice at 2024-05-01 14:32 UTC".
+    pub fn summary(&self) -> String {
+        let mut s = format!("Handoff for {}", self.alias);
+        if !self.exported_by.is_empty() {
+            s.push_str(&format!(" from {}", self.exported_by));
+        }
+        s.push_str(&format!(" at {}", format_timestamp(self.exported_at)));
+        s
+    }
+}
+
+/// A bundle comes from someone else, and its alias names files here (the
+/// host's snapshot): refuse anything that is not a plain host alias.
+fn check_alias(alias: &str) -> Result<()> {
+    if alias.starts_with('.')
+        || alias.contains(['/', '\\'])
+        || alias.contains("..")
+        || alias.chars().any(char::is_control)
+    {
+        bail!("unsafe host alias {:?} in handoff bundle", alias);
+    }
+    Ok(())
+}
+
+/// Alerts derived from a snapshot: failed services, and stale or missing data.
+fn alerts_for(snapshot: &HostSnapshot, now: u64) -> Vec<String> {
+    let mut alerts: Vec<String> = snapshot
+        .services
+        .iter()
+        .flatten()
+        .filter(|s| s.active_state == "failed")
+        .map(|s| format!("{} failed", s.name))
+        .collect();
+    let health = HostHealth::from_snapshot(snapshot, now);
+    match (health.health, health.seen_at) {
+        (Health::Stale, Some(at)) => alerts.push(format!("no data since {}", format_timestamp(at))),
+        (Health::Unknown, _) => alerts.push("never reached".to_string()),
+        _ => {}
+    }
+    alerts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundle_aliases_must_be_plain() {
+        for alias in ["web1", "db-2.example.com", "ops@bastion"] {
+            assert!(check_alias(alias).is_ok(), "{}", alias);
+        }
+        for alias in [
+            "../../.config/x",
+            "a/b",
+            "a\\b",
+            ".hidden",
+            "a..b",
+            "web1\n",
+        ] {
+            assert!(check_alias(alias).is_err(), "{:?}", alias);
+        }
+    }
+}
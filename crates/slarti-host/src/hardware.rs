@@ -0,0 +1,435 @@
+//! Hardware section: CPU model, memory, and the CPU topology (sockets, cores
+//! and their hardware threads, NUMA nodes, caches) for performance tuning,
+//! the GPUs with their memory and utilization for ML/compute boxes, then the
+//! thermal status: CPU, GPU and NVMe temperatures and fan speeds.
+
+use crate::units::format_bytes;
+use crate::{HostPanel, Section};
+use gpui::{div, prelude::*, Context, Hsla};
+use slarti_proto as proto;
+use slarti_ui::Appearance;
+use std::time::{Instant, SystemTime};
+
+/// "0-3,8" (the sysfs CPU list style) for sorted `ids`.
+fn cpu_ranges(ids: &[u32]) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < ids.len() {
+        let mut j = i;
+        while j + 1 < ids.len() && ids[j + 1] == ids[j] + 1 {
+            j += 1;
+        }
+        parts.push(if i == j {
+            ids[i].to_string()
+        } else {
+            format!("{}-{}", ids[i], ids[j])
+        });
+        i = j + 1;
+    }
+    parts.join(",")
+}
+
+/// "1 socket, 4 cores, 8 threads"
+fn counts_text(t: &proto::CpuTopology) -> String {
+    let plural = |n: u32, what: &str| format!("{} {}{}", n, what, if n == 1 { "" } else { "s" });
+    format!(
+        "{}, {}, {}",
+        plural(t.sockets, "socket"),
+        plural(t.cores, "core"),
+        plural(t.threads, "thread")
+    )
+}
+
+/// "L1d 48 KiB ×4  L1i 32 KiB ×4  L2 2.0 MiB ×4  L3 32 MiB ×1"
+fn caches_text(t: &proto::CpuTopology) -> String {
+    t.caches
+        .iter()
+        .map(|c| {
+            let suffix = match c.kind.as_str() {
+                "Data" => "d",
+                "Instruction" => "i",
+                _ => "",
+            };
+            format!(
+                "L{}{} {} ×{}",
+                c.level,
+                suffix,
+                format_bytes(c.size_bytes),
+                c.instances
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("  ")
+}
+
+/// "node0: cpus 0-7, 16 GiB"
+fn node_text(n: &proto::NumaNode) -> String {
+    let mut s = format!("node{}: cpus {}", n.id, cpu_ranges(&n.cpus));
+    if let Some(bytes) = n.memory_bytes {
+        s.push_str(&format!(", {}", format_bytes(bytes)));
+    }
+    s
+}
+
+/// Cores of `socket` with their hardware threads, by core id.
+fn socket_cores(t: &proto::CpuTopology, socket: u32) -> Vec<(u32, Vec<u32>)> {
+    let mut cores: Vec<(u32, Vec<u32>)> = Vec::new();
+    for cpu in t.cpus.iter().filter(|c| c.socket == socket) {
+        match cores.iter_mut().find(|(core, _)| *core == cpu.core) {
+            Some((_, threads)) => threads.push(cpu.id),
+            None => cores.push((cpu.core, vec![cpu.id])),
+        }
+    }
+    cores.sort_by_key(|(core, _)| *core);
+    cores
+}
+
+fn sockets(t: &proto::CpuTopology) -> Vec<u32> {
+    let mut sockets: Vec<u32> = t.cpus.iter().map(|c| c.socket).collect();
+    sockets.sort_unstable();
+    sockets.dedup();
+    sockets
+}
+
+/// "NVIDIA A100-SXM4-40GB (0000:01:00.0): 1.2 / 40 GiB, 37% busy, nvidia 550.54.14"
+fn gpu_text(gpu: &proto::Gpu) -> String {
+    let mut parts = Vec::new();
+    match (gpu.vram_used_bytes, gpu.vram_total_bytes) {
+        (Some(used), Some(total)) => parts.push(format!(
+            "{:.1} / {}",
+            used as f64 / (1024.0 * 1024.0 * 1024.0),
+            format_bytes(total)
+        )),
+        (None, Some(total)) => parts.push(format_bytes(total)),
+        _ => {}
+    }
+    if let Some(busy) = gpu.utilization_percent {
+        parts.push(format!("{:.0}% busy", busy));
+    }
+    match (&gpu.driver, &gpu.driver_version) {
+        (Some(driver), Some(version)) => parts.push(format!("{} {}", driver, version)),
+        (Some(driver), None) => parts.push(driver.clone()),
+        _ => {}
+    }
+    let mut s = format!("{} ({})", gpu.model, gpu.address);
+    if !parts.is_empty() {
+        s.push_str(&format!(": {}", parts.join(", ")));
+    }
+    s
+}
+
+fn kind_label(kind: proto::SensorKind) -> &'static str {
+    match kind {
+        proto::SensorKind::Cpu => "CPU",
+        proto::SensorKind::Gpu => "GPU",
+        proto::SensorKind::Nvme => "NVMe",
+        proto::SensorKind::Disk => "disk",
+        proto::SensorKind::Other => "board",
+    }
+}
+
+/// "CPU coretemp Package id 0: 47.0 °C (high 80.0, crit 100.0)"; NVMe
+/// drives are named by device, as there is one "nvme" chip per drive.
+fn temperature_text(t: &proto::Temperature) -> String {
+    let source = match (t.kind, &t.device) {
+        (proto::SensorKind::Nvme, Some(device)) => device,
+        _ => &t.chip,
+    };
+    let mut s = format!(
+        "{} {} {}: {:.1} °C",
+        kind_label(t.kind),
+        source,
+        t.label,
+        t.celsius
+    );
+    let limits: Vec<String> = [("high", t.high), ("crit", t.critical)]
+        .into_iter()
+        .filter_map(|(name, limit)| Some(format!("{} {:.1}", name, limit?)))
+        .collect();
+    if !limits.is_empty() {
+        s.push_str(&format!(" ({})", limits.join(", ")));
+    }
+    s
+}
+
+/// "nct6798 CPU Fan: 1120 RPM (min 300)"
+fn fan_text(f: &proto::Fan) -> String {
+    let mut s = format!("{} {}: {} RPM", f.chip, f.label, f.rpm);
+    if let Some(min) = f.min_rpm {
+        s.push_str(&format!(" (min {})", min));
+    }
+    s
+}
+
+/// Red past the critical mark, amber past the high one.
+fn temperature_color(t: &proto::Temperature) -> Option<Hsla> {
+    if t.critical.is_some_and(|c| t.celsius >= c) {
+        Some(gpui::hsla(0.0, 0.8, 0.6, 1.0))
+    } else if t.high.is_some_and(|h| t.celsius >= h) {
+        Some(gpui::hsla(0.13, 0.8, 0.6, 1.0))
+    } else {
+        None
+    }
+}
+
+/// Temperatures grouped CPU, GPU, NVMe, disks, then the rest.
+fn by_kind(readings: &proto::SensorReadings) -> Vec<&proto::Temperature> {
+    let order = |kind: proto::SensorKind| match kind {
+        proto::SensorKind::Cpu => 0,
+        proto::SensorKind::Gpu => 1,
+        proto::SensorKind::Nvme => 2,
+        proto::SensorKind::Disk => 3,
+        proto::SensorKind::Other => 4,
+    };
+    let mut temps: Vec<_> = readings.temperatures.iter().collect();
+    temps.sort_by_key(|t| order(t.kind));
+    temps
+}
+
+/// Plain-text hardware summary, topology, GPUs and sensor readings, for Copy.
+pub(crate) fn hardware_text(
+    config: Option<&proto::StaticConfig>,
+    gpus: Option<&Vec<proto::Gpu>>,
+    sensors: Option<&proto::SensorReadings>,
+) -> Option<String> {
+    if config.is_none() && gpus.is_none() && sensors.is_none() {
+        return None;
+    }
+    let mut out = config.map(topology_text).unwrap_or_default();
+    for gpu in gpus.into_iter().flatten() {
+        out.push_str(&format!("GPU {}\n", gpu_text(gpu)));
+    }
+    if let Some(readings) = sensors {
+        out.push_str("sensors:\n");
+        for t in by_kind(readings) {
+            out.push_str(&format!("  {}\n", temperature_text(t)));
+        }
+        for f in &readings.fans {
+            out.push_str(&format!("  {}\n", fan_text(f)));
+        }
+    }
+    Some(out)
+}
+
+fn topology_text(config: &proto::StaticConfig) -> String {
+    let mut out = format!(
+        "{} CPUs, {} memory\n",
+        config.cpu_count,
+        format_bytes(config.mem_total_bytes)
+    );
+    let Some(t) = &config.topology else {
+        return out;
+    };
+    if let Some(model) = &t.model {
+        out.push_str(&format!("{}\n", model));
+    }
+    out.push_str(&format!("{}\n", counts_text(t)));
+    if !t.caches.is_empty() {
+        out.push_str(&format!("{}\n", caches_text(t)));
+    }
+    for socket in sockets(t) {
+        out.push_str(&format!("socket {}:\n", socket));
+        for (core, threads) in socket_cores(t, socket) {
+            out.push_str(&format!("  core {}: cpus {}\n", core, cpu_ranges(&threads)));
+        }
+    }
+    for node in &t.numa_nodes {
+        out.push_str(&format!("{}\n", node_text(node)));
+    }
+    out
+}
+
+impl HostPanel {
+    /// Update the hardware (static configuration) shown in the panel.
+    pub fn set_static_config(&mut self, config: proto::StaticConfig, cx: &mut Context<Self>) {
+        self.static_config = Some(config);
+        self.freshness.mark(Section::Hardware, SystemTime::now());
+        if let Some(sched) = self.scheduler_mut() {
+            sched.mark_fetched(Section::Hardware, Instant::now());
+        }
+        cx.notify();
+    }
+
+    /// Update the temperatures and fan speeds shown in the panel.
+    pub fn set_sensors(&mut self, readings: proto::SensorReadings, cx: &mut Context<Self>) {
+        self.sensors = Some(readings);
+        self.freshness.mark(Section::Hardware, SystemTime::now());
+        if let Some(sched) = self.scheduler_mut() {
+            sched.mark_fetched(Section::Hardware, Instant::now());
+        }
+        cx.notify();
+    }
+
+    /// Update the GPUs shown in the panel.
+    pub fn set_gpus(&mut self, gpus: Vec<proto::Gpu>, cx: &mut Context<Self>) {
+        self.gpus = Some(gpus);
+        self.freshness.mark(Section::Hardware, SystemTime::now());
+        if let Some(sched) = self.scheduler_mut() {
+            sched.mark_fetched(Section::Hardware, Instant::now());
+        }
+        cx.notify();
+    }
+
+    /// One line per GPU; nothing for hosts without one.
+    fn render_gpus(&self, cx: &mut Context<Self>) -> Option<gpui::Div> {
+        let gpus = self.gpus.as_ref().filter(|g| !g.is_empty())?;
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        Some(
+            div()
+                .flex()
+                .flex_col()
+                .child(div().text_color(pal.muted).child("GPUs"))
+                .children(gpus.iter().map(|gpu| {
+                    div()
+                        .pl(ap.px(8.0))
+                        .text_color(pal.fg_dim)
+                        .child(gpu_text(gpu))
+                })),
+        )
+    }
+
+    /// Temperatures (colored past their high and critical marks), then fans
+    /// (red below their minimum).
+    fn render_sensors(&self, cx: &mut Context<Self>) -> Option<gpui::Div> {
+        let readings = self.sensors.as_ref()?;
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let block = div()
+            .flex()
+            .flex_col()
+            .child(div().text_color(pal.muted).child("Sensors"));
+        if readings.temperatures.is_empty() && readings.fans.is_empty() {
+            return Some(
+                block.child(
+                    div()
+                        .pl(ap.px(8.0))
+                        .text_color(pal.fg_dim)
+                        .child("No sensors (usual in VMs and containers)"),
+                ),
+            );
+        }
+        Some(
+            block
+                .children(by_kind(readings).into_iter().map(|t| {
+                    div()
+                        .pl(ap.px(8.0))
+                        .text_color(temperature_color(t).unwrap_or(pal.fg_dim))
+                        .child(temperature_text(t))
+                }))
+                .children(readings.fans.iter().map(|f| {
+                    let stalled = f.min_rpm.is_some_and(|min| f.rpm < min);
+                    div()
+                        .pl(ap.px(8.0))
+                        .text_color(if stalled {
+                            gpui::hsla(0.0, 0.8, 0.6, 1.0)
+                        } else {
+                            pal.fg_dim
+                        })
+                        .child(fan_text(f))
+                })),
+        )
+    }
+
+    /// Hardware section: header with controls, summary, then one block per
+    /// socket with its cores (each listing its hardware threads), then the
+    /// GPUs and the sensor readings.
+    pub(crate) fn render_hardware(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let section = div()
+            .flex()
+            .flex_col()
+            .gap_2()
+            .pl(ap.px(8.0))
+            .pr(ap.px(8.0))
+            .py(ap.px(8.0))
+            .border_b_1()
+            .border_color(pal.border)
+            .when(self.is_dimmed(Section::Hardware), |d| d.opacity(0.5))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .child(div().text_color(pal.fg).child("Hardware"))
+                    .child(self.render_section_controls(Section::Hardware, cx)),
+            );
+        let gpus = self.render_gpus(cx);
+        let sensors = self.render_sensors(cx);
+        let Some(config) = &self.static_config else {
+            if gpus.is_none() && sensors.is_none() {
+                return section.child("Not loaded yet: press ⟳.");
+            }
+            return section.children(gpus).children(sensors);
+        };
+        let summary = format!(
+            "{} CPUs, {} memory",
+            config.cpu_count,
+            format_bytes(config.mem_total_bytes)
+        );
+        let Some(t) = &config.topology else {
+            return section
+                .child(div().text_color(pal.fg_dim).child(summary))
+                .children(gpus)
+                .children(sensors);
+        };
+
+        let socket_block = |socket: u32| {
+            div()
+                .flex()
+                .flex_col()
+                .gap_1()
+                .p(ap.px(4.0))
+                .rounded_sm()
+                .border_1()
+                .border_color(pal.border)
+                .child(
+                    div()
+                        .text_color(pal.muted)
+                        .child(format!("socket {}", socket)),
+                )
+                .child(div().flex().flex_wrap().gap_1().children(
+                    socket_cores(t, socket).into_iter().map(|(_, threads)| {
+                        div()
+                            .flex()
+                            .gap_0p5()
+                            .px(ap.px(4.0))
+                            .rounded_sm()
+                            .border_1()
+                            .border_color(pal.accent)
+                            .children(
+                                threads
+                                    .into_iter()
+                                    .map(|id| div().text_color(pal.fg_dim).child(id.to_string())),
+                            )
+                    }),
+                ))
+        };
+
+        section
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .text_color(pal.fg_dim)
+                    .children(t.model.clone())
+                    .child(format!("{}; {}", counts_text(t), summary))
+                    .when(!t.caches.is_empty(), |d| d.child(caches_text(t))),
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_wrap()
+                    .gap_2()
+                    .children(sockets(t).into_iter().map(socket_block)),
+            )
+            .children(
+                t.numa_nodes
+                    .iter()
+                    .map(|n| div().text_color(pal.muted).child(node_text(n))),
+            )
+            .children(gpus)
+            .children(sensors)
+    }
+}
@@ -0,0 +1,193 @@
+//! Inventory section: firmware identity (vendor, model, serial), NICs and
+//! other PCI devices, and the disk layout as an lsblk-style tree.
+
+use crate::units::format_bytes;
+use crate::{HostPanel, Section};
+use gpui::{div, prelude::*, Context};
+use slarti_proto as proto;
+use slarti_ui::Appearance;
+use std::time::{Instant, SystemTime};
+
+/// "Dell Inc. PowerEdge R640, serial 8XJ2Q53"
+fn system_text(dmi: &proto::DmiInfo) -> String {
+    let mut s = [dmi.sys_vendor.as_deref(), dmi.product_name.as_deref()]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if s.is_empty() {
+        s.push_str("unknown system");
+    }
+    if let Some(serial) = &dmi.product_serial {
+        s.push_str(&format!(", serial {}", serial));
+    }
+    s
+}
+
+/// "BIOS Dell Inc. 2.17.1 (08/22/2023)", None when nothing is known.
+fn bios_text(dmi: &proto::DmiInfo) -> Option<String> {
+    let parts: Vec<&str> = [dmi.bios_vendor.as_deref(), dmi.bios_version.as_deref()]
+        .into_iter()
+        .flatten()
+        .collect();
+    if parts.is_empty() {
+        return None;
+    }
+    let mut s = format!("BIOS {}", parts.join(" "));
+    if let Some(date) = &dmi.bios_date {
+        s.push_str(&format!(" ({})", date));
+    }
+    Some(s)
+}
+
+/// "0000:00:1f.6  Ethernet controller: Intel Corporation I219-LM [e1000e] eno1"
+fn pci_text(d: &proto::PciDevice) -> String {
+    let name = match (&d.vendor, &d.device) {
+        (Some(v), Some(dev)) => format!("{} {}", v, dev),
+        (Some(v), None) => format!("{} {:04x}", v, d.device_id),
+        _ => format!("{:04x}:{:04x}", d.vendor_id, d.device_id),
+    };
+    let mut s = format!("{}  {}: {}", d.address, d.class, name);
+    if let Some(driver) = &d.driver {
+        s.push_str(&format!(" [{}]", driver));
+    }
+    if !d.interfaces.is_empty() {
+        s.push_str(&format!(" {}", d.interfaces.join(", ")));
+    }
+    s
+}
+
+/// "nvme0n1p2  part  932 GiB  /home"
+fn block_text(b: &proto::BlockDevice) -> String {
+    let mut s = format!("{}  {}  {}", b.name, b.kind, format_bytes(b.size_bytes));
+    if let Some(model) = &b.model {
+        s.push_str(&format!("  {}", model));
+    }
+    if b.rotational {
+        s.push_str("  hdd");
+    }
+    if b.removable {
+        s.push_str("  removable");
+    }
+    if let Some(mountpoint) = &b.mountpoint {
+        s.push_str(&format!("  {}", mountpoint));
+    }
+    s
+}
+
+/// Block devices depth first, with their depth in the tree.
+fn block_rows(devices: &[proto::BlockDevice], depth: usize, out: &mut Vec<(usize, String)>) {
+    for b in devices {
+        out.push((depth, block_text(b)));
+        block_rows(&b.children, depth + 1, out);
+    }
+}
+
+/// NICs first (by address), then the other PCI devices.
+fn pci_ordered(pci: &[proto::PciDevice]) -> Vec<&proto::PciDevice> {
+    let mut devices: Vec<&proto::PciDevice> = pci.iter().collect();
+    devices.sort_by_key(|d| d.interfaces.is_empty());
+    devices
+}
+
+/// Plain-text inventory, for Copy.
+pub(crate) fn inventory_text(inv: &proto::HardwareInventory) -> String {
+    let mut out = String::new();
+    if let Some(dmi) = &inv.dmi {
+        out.push_str(&format!("{}\n", system_text(dmi)));
+        if let Some(bios) = bios_text(dmi) {
+            out.push_str(&format!("{}\n", bios));
+        }
+    }
+    out.push_str("PCI:\n");
+    for d in pci_ordered(&inv.pci) {
+        out.push_str(&format!("  {}\n", pci_text(d)));
+    }
+    out.push_str("Block devices:\n");
+    let mut rows = Vec::new();
+    block_rows(&inv.block, 1, &mut rows);
+    for (depth, text) in rows {
+        out.push_str(&format!("{}{}\n", "  ".repeat(depth), text));
+    }
+    out
+}
+
+impl HostPanel {
+    /// Update the hardware inventory shown in the panel.
+    pub fn set_inventory(&mut self, inventory: proto::HardwareInventory, cx: &mut Context<Self>) {
+        self.inventory = Some(inventory);
+        self.freshness.mark(Section::Inventory, SystemTime::now());
+        if let Some(sched) = self.scheduler_mut() {
+            sched.mark_fetched(Section::Inventory, Instant::now());
+        }
+        cx.notify();
+    }
+
+    /// Inventory section: header with controls, system identity, PCI devices
+    /// (NICs first) and the block device tree.
+    pub(crate) fn render_inventory(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let section = div()
+            .flex()
+            .flex_col()
+            .gap_2()
+            .pl(ap.px(8.0))
+            .pr(ap.px(8.0))
+            .py(ap.px(8.0))
+            .border_b_1()
+            .border_color(pal.border)
+            .when(self.is_dimmed(Section::Inventory), |d| d.opacity(0.5))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .child(div().text_color(pal.fg).child("Inventory"))
+                    .child(self.render_section_controls(Section::Inventory, cx)),
+            );
+        let Some(inv) = &self.inventory else {
+            return section.child("Not loaded yet: press ⟳.");
+        };
+
+        let heading = |title: &'static str| div().text_color(pal.muted).child(title);
+        let mut blocks = Vec::new();
+        block_rows(&inv.block, 0, &mut blocks);
+
+        section
+            .children(inv.dmi.as_ref().map(|dmi| {
+                div()
+                    .flex()
+                    .flex_col()
+                    .text_color(pal.fg_dim)
+                    .child(system_text(dmi))
+                    .children(bios_text(dmi))
+            }))
+            .child(
+                div()
+                    .id("InventoryScroll")
+                    .flex()
+                    .flex_col()
+                    .max_h(ap.px(240.0))
+                    .overflow_y_scroll()
+                    .child(heading("PCI devices"))
+                    .children(pci_ordered(&inv.pci).into_iter().map(|d| {
+                        div()
+                            .pl(ap.px(8.0))
+                            .text_color(if d.interfaces.is_empty() {
+                                pal.fg_dim
+                            } else {
+                                pal.fg
+                            })
+                            .child(pci_text(d))
+                    }))
+                    .child(heading("Block devices"))
+                    .children(blocks.into_iter().map(|(depth, text)| {
+                        div()
+                            .pl(ap.px(8.0 + 12.0 * depth as f32))
+                            .text_color(pal.fg_dim)
+                            .child(text)
+                    })),
+            )
+    }
+}
@@ -0,0 +1,343 @@
+//! Kernel section: loaded modules and selected kernel parameters (sysctl),
+//! compared against a baseline saved per host to spot unexpected changes.
+
+use crate::freshness::format_age;
+use crate::{config_path, load_list, unix_now, HostPanel, Section};
+use gpui::{div, prelude::*, Context, MouseButton};
+use serde::{Deserialize, Serialize};
+use slarti_proto as proto;
+use slarti_ui::Appearance;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
+/// User watch list of sysctl keys or namespaces, one per line.
+const WATCH_FILE: &str = "sysctl_watch.yaml";
+
+/// Parameters watched when the user has no watch list.
+const DEFAULT_SYSCTL_KEYS: [&str; 12] = [
+    "kernel.pid_max",
+    "vm.swappiness",
+    "vm.overcommit_memory",
+    "vm.max_map_count",
+    "vm.dirty_ratio",
+    "fs.file-max",
+    "fs.inotify.max_user_watches",
+    "net.core.somaxconn",
+    "net.ipv4.ip_forward",
+    "net.ipv4.tcp_syncookies",
+    "net.ipv4.ip_local_port_range",
+    "net.ipv6.conf.all.disable_ipv6",
+];
+
+/// The parameters the Kernel section asks each host for: the user's watch
+/// list (`~/.config/slarti/sysctl_watch.yaml`), else a default set.
+pub fn sysctl_keys() -> Vec<String> {
+    let keys = load_list(&config_path(WATCH_FILE));
+    if !keys.is_empty() {
+        return keys;
+    }
+    DEFAULT_SYSCTL_KEYS.iter().map(|k| k.to_string()).collect()
+}
+
+/// Kernel state an operator accepted as normal for a host.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct KernelBaseline {
+    /// When it was saved (unix seconds)
+    pub saved_at: u64,
+    pub modules: BTreeSet<String>,
+    pub sysctl: BTreeMap<String, String>,
+}
+
+impl KernelBaseline {
+    fn capture(modules: &[proto::KernelModule], values: &[proto::SysctlValue]) -> Self {
+        Self {
+            saved_at: unix_now(),
+            modules: modules.iter().map(|m| m.name.clone()).collect(),
+            sysctl: values
+                .iter()
+                .filter_map(|v| Some((v.key.clone(), v.value.clone()?)))
+                .collect(),
+        }
+    }
+
+    /// Differences between the current state and the baseline, one line
+    /// each. Parameters the baseline never recorded (the watch list grew)
+    /// are not changes.
+    fn changes(
+        &self,
+        modules: &[proto::KernelModule],
+        values: &[proto::SysctlValue],
+    ) -> Vec<String> {
+        let mut out = Vec::new();
+        let loaded: BTreeSet<&str> = modules.iter().map(|m| m.name.as_str()).collect();
+        for name in &loaded {
+            if !self.modules.contains(*name) {
+                out.push(format!("module loaded: {}", name));
+            }
+        }
+        for name in &self.modules {
+            if !loaded.contains(name.as_str()) {
+                out.push(format!("module unloaded: {}", name));
+            }
+        }
+        for value in values {
+            let Some(was) = self.sysctl.get(&value.key) else {
+                continue;
+            };
+            match &value.value {
+                Some(now) if now != was => out.push(format!(
+                    "{}: {} → {}",
+                    value.key,
+                    was.replace('\t', " "),
+                    now.replace('\t', " ")
+                )),
+                Some(_) => {}
+                None => out.push(format!("{}: missing (was {})", value.key, was)),
+            }
+        }
+        out
+    }
+}
+
+fn baseline_path(alias: &str) -> PathBuf {
+    let mut p = HostPanel::state_dir().unwrap_or_else(|| PathBuf::from("."));
+    p.push("kernel_baselines");
+    p.push(format!("{}.json", alias));
+    p
+}
+
+/// The kernel baseline saved for `alias`, if any.
+pub(crate) fn load_baseline(alias: &str) -> Option<KernelBaseline> {
+    let bytes = slarti_state::read(baseline_path(alias)).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+fn save_baseline(alias: &str, baseline: &KernelBaseline) -> std::io::Result<()> {
+    let path = baseline_path(alias);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let data = serde_json::to_vec_pretty(baseline)
+        .unwrap_or_else(|_| serde_json::to_vec(baseline).unwrap());
+    slarti_state::write(path, data)
+}
+
+/// "net.ipv4.ip_local_port_range = 32768 60999", as `sysctl` prints it.
+fn value_text(value: &proto::SysctlValue) -> String {
+    match (&value.value, &value.error) {
+        (Some(v), _) => format!("{} = {}", value.key, v.replace('\t', " ")),
+        (None, Some(error)) => format!("{}: {}", value.key, error),
+        (None, None) => value.key.clone(),
+    }
+}
+
+/// "nvidia (POE)" for a module that taints the kernel, else its name.
+fn module_text(module: &proto::KernelModule) -> String {
+    if module.taint.is_empty() {
+        module.name.clone()
+    } else {
+        format!("{} ({})", module.name, module.taint)
+    }
+}
+
+/// "Matches the baseline saved 3d ago" or "2 changes since the baseline saved 3d ago:"
+fn baseline_summary(baseline: &KernelBaseline, changes: usize, now: u64) -> String {
+    let age = format_age(Duration::from_secs(now.saturating_sub(baseline.saved_at)));
+    match changes {
+        0 => format!("Matches the baseline saved {}", age),
+        1 => format!("1 change since the baseline saved {}:", age),
+        n => format!("{} changes since the baseline saved {}:", n, age),
+    }
+}
+
+/// Plain-text kernel state, for Copy.
+pub(crate) fn kernel_text(
+    modules: Option<&Vec<proto::KernelModule>>,
+    values: Option<&Vec<proto::SysctlValue>>,
+    baseline: Option<&KernelBaseline>,
+) -> Option<String> {
+    if modules.is_none() && values.is_none() {
+        return None;
+    }
+    let mut out = String::new();
+    if let (Some(baseline), Some(modules), Some(values)) = (baseline, modules, values) {
+        let changes = baseline.changes(modules, values);
+        out.push_str(&format!(
+            "{}\n",
+            baseline_summary(baseline, changes.len(), unix_now())
+        ));
+        for change in changes {
+            out.push_str(&format!("  {}\n", change));
+        }
+    }
+    if let Some(modules) = modules {
+        out.push_str(&format!("modules ({}):\n", modules.len()));
+        for module in modules {
+            out.push_str(&format!("  {}\n", module_text(module)));
+        }
+    }
+    if let Some(values) = values {
+        out.push_str("sysctl:\n");
+        for value in values {
+            out.push_str(&format!("  {}\n", value_text(value)));
+        }
+    }
+    Some(out)
+}
+
+impl HostPanel {
+    /// Update the loaded kernel modules shown in the panel.
+    pub fn set_kernel_modules(
+        &mut self,
+        modules: Vec<proto::KernelModule>,
+        cx: &mut Context<Self>,
+    ) {
+        self.kernel_modules = Some(modules);
+        self.mark_kernel_fetched();
+        cx.notify();
+    }
+
+    /// Update the kernel parameters shown in the panel.
+    pub fn set_sysctl(&mut self, values: Vec<proto::SysctlValue>, cx: &mut Context<Self>) {
+        self.sysctl = Some(values);
+        self.mark_kernel_fetched();
+        cx.notify();
+    }
+
+    fn mark_kernel_fetched(&mut self) {
+        self.freshness.mark(Section::Kernel, SystemTime::now());
+        if let Some(sched) = self.scheduler_mut() {
+            sched.mark_fetched(Section::Kernel, Instant::now());
+        }
+    }
+
+    /// Record the current modules and parameters as the host's baseline.
+    fn save_kernel_baseline(&mut self, cx: &mut Context<Self>) {
+        let (Some(alias), Some(modules), Some(values)) =
+            (&self.selected_alias, &self.kernel_modules, &self.sysctl)
+        else {
+            return;
+        };
+        let baseline = KernelBaseline::capture(modules, values);
+        if save_baseline(alias, &baseline).is_ok() {
+            self.kernel_baseline = Some(baseline);
+            cx.notify();
+        }
+    }
+
+    /// Kernel section: header with a "Save baseline" button and controls,
+    /// the changes since the baseline, loaded modules, then one
+    /// "key = value" row per watched parameter.
+    pub(crate) fn render_kernel(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let loaded = self.kernel_modules.is_some() && self.sysctl.is_some();
+        let section = div()
+            .flex()
+            .flex_col()
+            .gap_2()
+            .pl(ap.px(8.0))
+            .pr(ap.px(8.0))
+            .py(ap.px(8.0))
+            .border_b_1()
+            .border_color(pal.border)
+            .when(self.is_dimmed(Section::Kernel), |d| d.opacity(0.5))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .child(div().text_color(pal.fg).child("Kernel"))
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .gap_2()
+                            .child(
+                                div()
+                                    .px(ap.px(6.0))
+                                    .rounded_sm()
+                                    .border_1()
+                                    .border_color(pal.border)
+                                    .text_color(if loaded { pal.fg } else { pal.muted })
+                                    .when(loaded, |d| {
+                                        d.cursor_pointer().on_mouse_up(
+                                            MouseButton::Left,
+                                            cx.listener(|this: &mut Self, _ev, _w, cx| {
+                                                this.save_kernel_baseline(cx);
+                                            }),
+                                        )
+                                    })
+                                    .child("Save baseline"),
+                            )
+                            .child(self.render_section_controls(Section::Kernel, cx)),
+                    ),
+            );
+        if self.kernel_modules.is_none() && self.sysctl.is_none() {
+            return section.child("Not loaded yet: press ⟳.");
+        }
+
+        let mut section = section;
+        match (&self.kernel_baseline, &self.kernel_modules, &self.sysctl) {
+            (None, _, _) => {
+                section = section.child(
+                    div()
+                        .text_color(pal.muted)
+                        .child("No baseline saved: press Save baseline to record this state."),
+                );
+            }
+            (Some(baseline), Some(modules), Some(values)) => {
+                let changes = baseline.changes(modules, values);
+                let color = if changes.is_empty() {
+                    gpui::hsla(0.33, 0.6, 0.5, 1.0)
+                } else {
+                    gpui::hsla(0.13, 0.8, 0.6, 1.0)
+                };
+                section = section
+                    .child(div().text_color(color).child(baseline_summary(
+                        baseline,
+                        changes.len(),
+                        unix_now(),
+                    )))
+                    .children(
+                        changes
+                            .into_iter()
+                            .map(|c| div().pl(ap.px(8.0)).text_color(color).child(c)),
+                    );
+            }
+            _ => {}
+        }
+        if let Some(modules) = &self.kernel_modules {
+            let names: Vec<String> = modules.iter().map(module_text).collect();
+            section = section
+                .child(
+                    div()
+                        .text_color(pal.muted)
+                        .child(format!("Modules ({})", modules.len())),
+                )
+                .child(
+                    div()
+                        .pl(ap.px(8.0))
+                        .text_color(pal.fg_dim)
+                        .child(names.join(", ")),
+                );
+        }
+        if let Some(values) = &self.sysctl {
+            section = section
+                .child(div().text_color(pal.muted).child("Tuning"))
+                .child(div().flex().flex_col().children(values.iter().map(|value| {
+                    div()
+                        .pl(ap.px(8.0))
+                        .text_color(if value.value.is_some() {
+                            pal.fg_dim
+                        } else {
+                            pal.muted
+                        })
+                        .child(value_text(value))
+                })));
+        }
+        section
+    }
+}
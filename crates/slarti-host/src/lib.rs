@@ -1,11 +1,61 @@
 use gpui::{
-    div, prelude::*, px, App, Context, FocusHandle, Focusable, MouseButton, SharedString, Window,
+    div, prelude::*, px, AnyView, App, Context, Div, Entity, FocusHandle, Focusable, MouseButton,
+    SharedString, StyleRefinement, Task, Window,
 };
-use serde::{Deserialize, Serialize};
 use slarti_proto as proto;
-use slarti_ui::Vector as UiVector;
-use std::collections::HashSet;
+use slarti_ui::{Appearance, Sparkline, Vector as UiVector};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+mod access;
+mod approval;
+mod backups;
+mod cgroups;
+mod connectivity;
+mod csv;
+mod file_view;
+mod fleet;
+mod freshness;
+mod handoff;
+mod hardware;
+mod inventory;
+mod kernel;
+mod listeners;
+mod packages;
+mod picture;
+mod policy;
+mod poll;
+mod pressure;
+mod processes;
+mod recent;
+#[cfg(test)]
+mod render_tests;
+mod requests;
+mod security;
+mod service_detail;
+mod services;
+mod sessions;
+mod snapshot;
+mod storage;
+mod units;
+mod updates;
+
+pub use approval::{ActionPreview, PendingAction, PreviewActions};
+pub use connectivity::reachability_endpoints;
+use csv::CsvTable;
+pub use fleet::{Health, HostHealth};
+pub use freshness::{format_age, DataFreshness, SessionState};
+pub use handoff::{HandoffBundle, HANDOFF_EXTENSION};
+pub use kernel::sysctl_keys;
+pub use policy::{ActionCategory, ActionPolicy};
+pub use poll::{PollScheduler, RefreshInterval, Section};
+pub use processes::signal_name;
+pub use recent::{RecentHost, RecentHosts};
+pub use requests::AgentRequest;
+pub use services::ServicesList;
+use services::ServicesView;
+pub use snapshot::{format_timestamp, from_unix_secs, HostSnapshot, SnapshotStore};
 
 /// Properties for constructing a HostPanel.
 ///
@@ -19,6 +69,17 @@ pub struct HostPanelProps {
     pub on_deploy: Option<Arc<dyn Fn(&mut Window, &mut Context<HostPanel>) + Send + Sync>>,
 }
 
+/// Start-screen shortcuts, carried out by the app.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuickAction {
+    /// Add a host to the ssh config.
+    AddHost,
+    /// Show and focus the local terminal.
+    OpenTerminal,
+    /// Open the app settings.
+    Settings,
+}
+
 /// HostPanel shows high-level information and observations about the
 /// currently selected host. For now it renders a set of placeholder
 /// sections to guide future observability work.
@@ -34,30 +95,214 @@ pub struct HostPanel {
     // Optional recent-select callback (emitted when clicking a recent alias)
     on_select_recent:
         Option<Arc<dyn Fn(String, &mut Window, &mut Context<HostPanel>) + Send + Sync>>,
+    // Optional "Install my key" callback (runs ssh-copy-id for the selected alias)
+    on_install_key: Option<Arc<dyn Fn(String, &mut Window, &mut Context<HostPanel>) + Send + Sync>>,
+    // Optional "Clean old agents" callback (removes older agent versions on the selected alias)
+    on_clean_agents:
+        Option<Arc<dyn Fn(String, &mut Window, &mut Context<HostPanel>) + Send + Sync>>,
+    // Optional "Authenticate interactively" callback (password/keyboard-interactive login in the terminal)
+    on_authenticate:
+        Option<Arc<dyn Fn(String, &mut Window, &mut Context<HostPanel>) + Send + Sync>>,
+    // Optional "Open console" callback (serial console for the selected host in the terminal)
+    on_open_console:
+        Option<Arc<dyn Fn(String, &mut Window, &mut Context<HostPanel>) + Send + Sync>>,
+    // Optional preview callback: builds what an action will run and hands it back via show_action_preview
+    on_preview: Option<
+        Arc<dyn Fn(PendingAction, String, &mut Window, &mut Context<HostPanel>) + Send + Sync>,
+    >,
+    // Action preview awaiting Run/Cancel
+    action_preview: Option<ActionPreview>,
+    // Optional "Retry now" callback (probe a suppressed host again right away)
+    on_retry: Option<Arc<dyn Fn(String, &mut Window, &mut Context<HostPanel>) + Send + Sync>>,
+    // Optional start-screen quick action callback
+    on_quick_action:
+        Option<Arc<dyn Fn(QuickAction, &mut Window, &mut Context<HostPanel>) + Send + Sync>>,
+    // True when ssh rejected non-interactive auth for the selected host
+    auth_required: bool,
+    // Serial line of the selected host when it is reached over a local serial port
+    serial_console: Option<SharedString>,
+    // True when probing the selected host is suspended after repeated timeouts
+    suppressed: bool,
+    // Tags of the selected host (ssh config `Tag`), for the action policy
+    host_tags: Vec<String>,
+    // Last imported handoff bundle, shown while its host is selected
+    handoff: Option<HandoffBundle>,
     // Deployment state for button behavior/animation
     deploy_running: bool,
     has_deployed: bool,
-    // Recently selected hosts (most-recent first, unique)
-    recent_hosts: Vec<String>,
+    // Recently selected hosts (pinned first, then most-recent first)
+    recent_hosts: RecentHosts,
+    // Whether the recents list is expanded past RECENT_SHOWN entries
+    show_all_recent: bool,
+    // Aliases in the ssh config and their last known health (from snapshots), for the start screen
+    known_hosts: Vec<String>,
+    fleet: HashMap<String, HostHealth>,
     // Latest system info received from the remote agent
     sys_info: Option<proto::SysInfo>,
+    // The agent's own resource use and self-limits
+    agent_stats: Option<proto::AgentStats>,
+    // Latest static configuration (CPUs, memory, CPU topology)
+    static_config: Option<proto::StaticConfig>,
+    // Latest temperatures and fan speeds
+    sensors: Option<proto::SensorReadings>,
+    // Latest GPUs with their memory and utilization
+    gpus: Option<Vec<proto::Gpu>>,
+    // Latest hardware inventory (DMI, PCI, block devices)
+    inventory: Option<proto::HardwareInventory>,
+    // Latest pressure stall information and OOM kills
+    pressure: Option<proto::Pressure>,
+    // Latest loaded kernel modules
+    kernel_modules: Option<Vec<proto::KernelModule>>,
+    // Latest values of the watched kernel parameters
+    sysctl: Option<Vec<proto::SysctlValue>>,
+    // Kernel state saved as normal for the selected host
+    kernel_baseline: Option<kernel::KernelBaseline>,
+    // Latest backup jobs and their last runs
+    backups: Option<Vec<proto::BackupJob>>,
+    raid_arrays: Option<Vec<proto::RaidArray>>,
+    // Latest ZFS pools, btrfs filesystems and LVM volume groups
+    storage_stacks: Option<proto::StorageStacks>,
+    // Latest mounted filesystems and their space
+    mounts: Option<Vec<proto::MountInfo>>,
+    // Latest listening sockets
+    listeners: Option<Vec<proto::NetListener>>,
+    // Latest pending package updates
+    updates: Option<proto::PendingUpdates>,
+    // Latest reachability results of the user's endpoints
+    connectivity: Option<Vec<proto::ReachResult>>,
+    // Latest resolver setup
+    dns_config: Option<proto::DnsConfig>,
+    // Latest routing table
+    routes: Option<Vec<proto::Route>>,
+    // Latest socket counts
+    sockets: Option<proto::SocketSummary>,
+    // Latest sshd settings (inner None: sshd is not installed)
+    sshd_config: Option<Option<proto::SshdConfig>>,
+    // Latest failed logins and fail2ban bans (shown with the sshd settings)
+    auth_failures: Option<proto::AuthFailures>,
+    // Latest users, admin groups and sudo rules
+    users: Option<proto::UserInventory>,
+    // Latest login sessions
+    sessions: Option<Vec<proto::LoginSession>>,
     // Latest services list received from the remote agent
     services: Option<Vec<proto::ServiceInfo>>,
-    // Local baseline service names (from ~/.config/slarti/baseline_services.yaml)
-    baseline_names: HashSet<String>,
-    // Services filter state (by active state)
-    service_filter: ServiceFilter,
-    // When true (default), show only explicitly enabled services.
-    // When false, show services that are disabled or not explicitly enabled (enabled == Some(false) or None).
-    enabled_only: bool,
-    // When true, include baseline (system) services; when false (default), hide them.
-    include_baseline: bool,
+    // Latest process overview received from the remote agent
+    processes: Option<proto::ProcessesSummary>,
+    // Process shown in the detail drawer, and its details once fetched
+    process_pid: Option<u32>,
+    process_detail: Option<proto::ProcessDetail>,
+    // Signal chosen in the drawer's picker, and the renice slider's value (None: unchanged)
+    process_signal: i32,
+    process_nice: Option<i32>,
+    // Latest cgroup tree, and the paths whose expansion the user toggled
+    cgroups: Option<proto::CgroupNode>,
+    cgroups_toggled: HashSet<String>,
+    // Unit shown in the service drawer, and its details once fetched
+    service_unit: Option<String>,
+    service_detail: Option<proto::ServiceDetail>,
+    // Remote file shown in the file drawer, its current window once read, and
+    // its followed lines
+    file_path: Option<String>,
+    file_window: Option<file_view::FileWindow>,
+    file_tail: Option<file_view::FileTail>,
+    // Installed packages fetched so far, and whether a page is on its way
+    packages: Option<proto::PackageList>,
+    packages_pending: bool,
+    // Drawer requests (details, kill, renice, file windows, package pages) awaiting the session loop
+    agent_requests: Vec<AgentRequest>,
+    // Services list and filters; a child entity so refreshes repaint only the list
+    services_list: Entity<ServicesList>,
+    // Services filters and scroll position per host alias, restored on re-selection
+    views: HashMap<String, ServicesView>,
+    // Per-host polling schedulers (auto-refresh intervals and manual refresh requests)
+    schedulers: HashMap<String, PollScheduler>,
+    // Fetch timestamps and session liveness for the selected host's sections
+    freshness: DataFreshness,
+    // Last rendered "updated … ago" labels, used to repaint only when one changes
+    age_labels: Vec<Option<String>>,
+    // Recent agent request round-trip times for the selected host (oldest first)
+    latency_history: VecDeque<Duration>,
+    // ssh connection setup time (spawn → HelloAck) of the current session
+    connect_time: Option<Duration>,
+    // The connected agent advertised read-only mode in its HelloAck
+    agent_read_only: bool,
+    // Persisted last-known data per host, shown (labeled as cached) until live data arrives
+    snapshots: SnapshotStore,
+    snapshot: HostSnapshot,
+    // Snapshots changed since the last write, and those being written, by alias
+    unsaved_snapshots: HashMap<String, HostSnapshot>,
+    writing_snapshots: HashMap<String, HostSnapshot>,
+    // The scheduled background write of `unsaved_snapshots`
+    snapshot_write: Option<Task<()>>,
 }
 
+/// On-disk schema version of `host_views.json` (0: the bare alias map).
+const VIEWS_VERSION: u32 = 1;
+
+/// Contents of `host_views.json`: the services view per host alias.
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct SavedViews {
+    hosts: HashMap<String, ServicesView>,
+}
+
+/// Version 0 was the alias map itself; an alias named "version" could not
+/// survive that, hence the `hosts` wrapper.
+fn migrate_views(version: u32, doc: serde_json::Value) -> anyhow::Result<serde_json::Value> {
+    match version {
+        0 => Ok(serde_json::json!({ "hosts": doc })),
+        _ => anyhow::bail!("no migration from version {}", version),
+    }
+}
+
+/// Recent hosts listed before "Show more".
+const RECENT_SHOWN: usize = 5;
+
+/// How long snapshot changes are collected before they are written together.
+const SNAPSHOT_WRITE_DELAY: Duration = Duration::from_secs(2);
+
+/// Number of round-trip samples kept for the latency sparkline.
+const LATENCY_HISTORY_LEN: usize = 30;
+
+/// Clock offset from NTP time beyond which Identity warns.
+const CLOCK_OFFSET_WARN_SECS: f64 = 0.1;
+
 impl HostPanel {
     /// Create a new HostPanel.
     pub fn new(cx: &mut Context<Self>, props: HostPanelProps) -> Self {
-        let (sd, sb) = Self::load_service_filter_prefs();
+        // Keep "updated … ago" labels current; repaints only when a label changes.
+        cx.spawn(async move |this, cx| loop {
+            cx.background_executor()
+                .timer(std::time::Duration::from_secs(1))
+                .await;
+            if this
+                .update(cx, |panel, cx| panel.tick_freshness(cx))
+                .is_err()
+            {
+                break;
+            }
+        })
+        .detach();
+        // Clicking a service opens its drawer.
+        let services_list = cx.new(ServicesList::new);
+        cx.subscribe(
+            &services_list,
+            |panel, _list, ev: &service_detail::OpenService, cx| {
+                panel.open_service(ev.0.clone(), cx);
+            },
+        )
+        .detach();
+        // Write snapshots still waiting for their debounced write before quitting.
+        cx.on_app_quit(|panel, cx| {
+            panel.snapshot_write = None;
+            let store = panel.snapshots.clone();
+            let unsaved = std::mem::take(&mut panel.unsaved_snapshots);
+            cx.background_executor().spawn(async move {
+                for (alias, snapshot) in &unsaved {
+                    let _ = store.save(alias, snapshot);
+                }
+            })
+        })
+        .detach();
         Self {
             focus: cx.focus_handle(),
             selected_alias: props.selected_alias,
@@ -66,15 +311,78 @@ impl HostPanel {
             last_progress: None,
             on_deploy: props.on_deploy,
             on_select_recent: None,
+            on_install_key: None,
+            on_clean_agents: None,
+            on_quick_action: None,
+            on_authenticate: None,
+            on_open_console: None,
+            on_preview: None,
+            action_preview: None,
+            on_retry: None,
+            auth_required: false,
+            serial_console: None,
+            suppressed: false,
+            host_tags: Vec::new(),
+            handoff: None,
             deploy_running: false,
             has_deployed: false,
             recent_hosts: Self::load_recent_hosts(),
+            show_all_recent: false,
+            known_hosts: Vec::new(),
+            fleet: HashMap::new(),
             sys_info: None,
+            agent_stats: None,
+            static_config: None,
+            sensors: None,
+            gpus: None,
+            inventory: None,
+            pressure: None,
+            kernel_modules: None,
+            sysctl: None,
+            kernel_baseline: None,
+            backups: None,
+            raid_arrays: None,
+            storage_stacks: None,
+            mounts: None,
+            listeners: None,
+            updates: None,
+            sessions: None,
+            connectivity: None,
+            dns_config: None,
+            routes: None,
+            sockets: None,
+            sshd_config: None,
+            auth_failures: None,
+            users: None,
             services: None,
-            baseline_names: Self::load_baseline_names(),
-            service_filter: ServiceFilter::All,
-            enabled_only: sd,
-            include_baseline: sb,
+            processes: None,
+            process_pid: None,
+            process_detail: None,
+            process_signal: processes::SIGTERM,
+            process_nice: None,
+            cgroups: None,
+            cgroups_toggled: HashSet::new(),
+            service_unit: None,
+            service_detail: None,
+            file_path: None,
+            file_window: None,
+            file_tail: None,
+            packages: None,
+            packages_pending: false,
+            agent_requests: Vec::new(),
+            services_list,
+            views: Self::load_views(),
+            schedulers: HashMap::new(),
+            freshness: DataFreshness::default(),
+            age_labels: Vec::new(),
+            latency_history: VecDeque::with_capacity(LATENCY_HISTORY_LEN),
+            connect_time: None,
+            agent_read_only: false,
+            snapshots: SnapshotStore::new(Self::snapshot_dir()),
+            snapshot: HostSnapshot::default(),
+            unsaved_snapshots: HashMap::new(),
+            writing_snapshots: HashMap::new(),
+            snapshot_write: None,
         }
     }
 
@@ -82,66 +390,221 @@ impl HostPanel {
     /// Call this from outside via entity.update to reflect host selection.
     pub fn set_selected_host(&mut self, alias: Option<String>, cx: &mut Context<Self>) {
         if let Some(a) = alias.as_ref() {
-            self.push_recent(a);
+            self.recent_hosts.touch(a, SystemTime::now());
             let _ = Self::save_recent_hosts(&self.recent_hosts);
         }
+        // Drop data fetched for a previously selected host.
+        if self.selected_alias != alias {
+            self.save_view_state(cx);
+            let view = alias.as_ref().and_then(|a| self.views.get(a).cloned());
+            self.services_list
+                .update(cx, |list, cx| list.set_view(view, cx));
+            self.sys_info = None;
+            self.agent_stats = None;
+            self.static_config = None;
+            self.sensors = None;
+            self.gpus = None;
+            self.inventory = None;
+            self.pressure = None;
+            self.kernel_modules = None;
+            self.sysctl = None;
+            self.kernel_baseline = alias.as_deref().and_then(kernel::load_baseline);
+            self.backups = None;
+            self.raid_arrays = None;
+            self.storage_stacks = None;
+            self.mounts = None;
+            self.listeners = None;
+            self.updates = None;
+            self.sessions = None;
+            self.connectivity = None;
+            self.dns_config = None;
+            self.routes = None;
+            self.sockets = None;
+            self.sshd_config = None;
+            self.auth_failures = None;
+            self.users = None;
+            self.services = None;
+            self.processes = None;
+            self.process_pid = None;
+            self.process_detail = None;
+            self.process_nice = None;
+            self.cgroups = None;
+            self.cgroups_toggled.clear();
+            self.service_unit = None;
+            self.service_detail = None;
+            self.file_path = None;
+            self.file_window = None;
+            self.file_tail = None;
+            self.packages = None;
+            self.packages_pending = false;
+            self.freshness = DataFreshness::default();
+            self.latency_history.clear();
+            self.connect_time = None;
+            self.agent_read_only = false;
+            self.snapshot = alias
+                .as_deref()
+                .and_then(|a| self.stored_snapshot(a))
+                .unwrap_or_default();
+        }
+        // A new selection always starts a new agent session.
+        self.freshness.set_session(SessionState::Connecting);
+        self.auth_required = false;
+        self.serial_console = None;
+        self.suppressed = false;
+        self.action_preview = None;
+        // Requests of the previous session would go to the new one.
+        self.agent_requests.clear();
+        self.host_tags.clear();
         self.selected_alias = alias;
+        self.sync_services_list(cx);
         cx.notify();
     }
 
     /// Update the remote status text (e.g., "connected vX", "not present", "outdated").
     pub fn set_status(&mut self, status: impl Into<SharedString>, cx: &mut Context<Self>) {
-        self.status = status.into();
-        cx.notify();
+        let status = status.into();
+        if self.status != status {
+            self.status = status;
+            cx.notify();
+        }
     }
 
     /// Toggle a lightweight "checking..." indicator.
     pub fn set_checking(&mut self, on: bool, cx: &mut Context<Self>) {
-        self.checking = on;
-        cx.notify();
+        if self.checking != on {
+            self.checking = on;
+            cx.notify();
+        }
     }
 
     /// Update the last progress message shown in the banner (optional).
     pub fn push_progress(&mut self, msg: impl Into<SharedString>, cx: &mut Context<Self>) {
-        self.last_progress = Some(msg.into());
-        cx.notify();
+        let msg = Some(msg.into());
+        if self.last_progress != msg {
+            self.last_progress = msg;
+            cx.notify();
+        }
     }
 
     /// Clear any progress message.
     pub fn clear_progress(&mut self, cx: &mut Context<Self>) {
-        self.last_progress = None;
+        if self.last_progress.take().is_some() {
+            cx.notify();
+        }
+    }
+
+    /// Push the services to show (live or cached) to the services list entity.
+    fn sync_services_list(&mut self, cx: &mut Context<Self>) {
+        let shown = self.shown_services().cloned();
+        self.services_list
+            .update(cx, |list, cx| list.set_services(shown, cx));
+    }
+
+    /// Remember the selected host's services filters and scroll position, and persist them.
+    pub fn save_view_state(&mut self, cx: &mut Context<Self>) {
+        let Some(alias) = self.selected_alias.clone() else {
+            return;
+        };
+        let view = self.services_list.read(cx).view();
+        self.views.insert(alias, view);
+        let _ = self.save_views();
+    }
+
+    fn views_state_path() -> std::path::PathBuf {
+        let mut p = Self::state_dir().unwrap_or_else(|| std::path::PathBuf::from("."));
+        let _ = std::fs::create_dir_all(&p);
+        p.push("host_views.json");
+        p
+    }
+
+    /// Saved services views; a file that fails to load is reported through
+    /// `slarti_state::problems`.
+    fn load_views() -> HashMap<String, ServicesView> {
+        slarti_state::load_versioned::<SavedViews>(
+            Self::views_state_path(),
+            VIEWS_VERSION,
+            migrate_views,
+        )
+        .ok()
+        .flatten()
+        .map(|saved| saved.hosts)
+        .unwrap_or_default()
+    }
+
+    fn save_views(&self) -> std::io::Result<()> {
+        let saved = SavedViews {
+            hosts: self.views.clone(),
+        };
+        slarti_state::save_versioned(Self::views_state_path(), VIEWS_VERSION, &saved)
+    }
+
+    /// Load recent hosts from state dir.
+    fn load_recent_hosts() -> RecentHosts {
+        slarti_state::read(Self::recent_state_path())
+            .ok()
+            .and_then(|bytes| RecentHosts::from_json(&bytes))
+            .unwrap_or_default()
+    }
+
+    /// Set the aliases in the ssh config: summarizes their cached health for the
+    /// start screen and drops recents that are no longer configured.
+    pub fn set_known_hosts(&mut self, aliases: Vec<String>, cx: &mut Context<Self>) {
+        let now = unix_now();
+        self.fleet = aliases
+            .iter()
+            .map(|a| {
+                let health = self
+                    .snapshots
+                    .load(a)
+                    .map(|s| HostHealth::from_snapshot(&s, now))
+                    .unwrap_or(HostHealth::UNKNOWN);
+                (a.clone(), health)
+            })
+            .collect();
+        if self.recent_hosts.retain_known(&aliases) {
+            let _ = Self::save_recent_hosts(&self.recent_hosts);
+        }
+        self.known_hosts = aliases;
         cx.notify();
     }
 
-    /// Append an alias to the MRU list (dedupe, cap at 5).
-    fn push_recent(&mut self, alias: &str) {
-        self.recent_hosts.retain(|h| h != alias);
-        self.recent_hosts.insert(0, alias.to_string());
-        if self.recent_hosts.len() > 5 {
-            self.recent_hosts.truncate(5);
+    /// The selected host alias, if any.
+    pub fn selected_alias(&self) -> Option<&str> {
+        self.selected_alias.as_deref()
+    }
+
+    /// Select `alias` the way clicking it in the hosts list would.
+    pub fn select_host(&mut self, alias: String, window: &mut Window, cx: &mut Context<Self>) {
+        match self.on_select_recent.clone() {
+            Some(cb) => (cb)(alias, window, cx),
+            // Fallback: update selection locally and persist recents.
+            None => self.set_selected_host(Some(alias), cx),
         }
     }
 
-    /// Load recent hosts from state dir.
-    fn load_recent_hosts() -> Vec<String> {
-        let path = Self::recent_state_path();
-        if let Ok(bytes) = std::fs::read(path) {
-            if let Ok(list) = serde_json::from_slice::<Vec<String>>(&bytes) {
-                return list;
-            }
+    /// Aliases in the recent list, in display order.
+    pub fn recent_aliases(&self) -> Vec<String> {
+        self.recent_hosts
+            .ordered()
+            .map(|e| e.alias.clone())
+            .collect()
+    }
+
+    fn toggle_recent_pin(&mut self, alias: &str, cx: &mut Context<Self>) {
+        if self.recent_hosts.toggle_pin(alias) {
+            let _ = Self::save_recent_hosts(&self.recent_hosts);
+            cx.notify();
         }
-        Vec::new()
     }
 
     /// Save recent hosts to state dir.
-    fn save_recent_hosts(list: &Vec<String>) -> std::io::Result<()> {
+    fn save_recent_hosts(recent: &RecentHosts) -> std::io::Result<()> {
         if let Some(dir) = Self::state_dir() {
             let _ = std::fs::create_dir_all(&dir);
             let mut p = dir;
             p.push("hosts_recent.json");
-            let data = serde_json::to_vec_pretty(list)
-                .unwrap_or_else(|_| serde_json::to_vec(list).unwrap());
-            std::fs::write(p, data)
+            let data = recent.to_json().map_err(std::io::Error::other)?;
+            slarti_state::write(p, data)
         } else {
             // Fallback: HOME not set; no-op
             Ok(())
@@ -149,7 +612,7 @@ impl HostPanel {
     }
 
     /// Determine state directory: $XDG_STATE_HOME/slarti or ~/.local/state/slarti
-    fn state_dir() -> Option<std::path::PathBuf> {
+    pub fn state_dir() -> Option<std::path::PathBuf> {
         if let Ok(xdg) = std::env::var("XDG_STATE_HOME") {
             let mut p = std::path::PathBuf::from(xdg);
             p.push("slarti");
@@ -165,110 +628,120 @@ impl HostPanel {
         None
     }
 
-    fn recent_state_path() -> std::path::PathBuf {
+    fn snapshot_dir() -> std::path::PathBuf {
         let mut p = Self::state_dir().unwrap_or_else(|| std::path::PathBuf::from("."));
-        let _ = std::fs::create_dir_all(&p);
-        p.push("hosts_recent.json");
+        p.push("hosts");
         p
     }
 
-    fn service_filter_prefs_path() -> std::path::PathBuf {
-        let mut p = Self::state_dir().unwrap_or_else(|| std::path::PathBuf::from("."));
-        let _ = std::fs::create_dir_all(&p);
-        p.push("services_filter_prefs.json");
-        p
+    fn save_snapshot(&mut self, cx: &mut Context<Self>) {
+        let Some(alias) = self.selected_alias.clone() else {
+            return;
+        };
+        if let Some(health) = self.fleet.get_mut(&alias) {
+            *health = HostHealth::from_snapshot(&self.snapshot, unix_now());
+        }
+        self.unsaved_snapshots.insert(alias, self.snapshot.clone());
+        self.schedule_snapshot_write(cx);
     }
 
-    fn baseline_config_path() -> std::path::PathBuf {
-        // Prefer XDG config: $XDG_CONFIG_HOME/slarti/baseline_services.yaml
-        if let Ok(xdg) = std::env::var("XDG_CONFIG_HOME") {
-            let mut p = std::path::PathBuf::from(xdg);
-            p.push("slarti");
-            p.push("baseline_services.yaml");
-            return p;
+    /// Last saved snapshot of an alias (none if it was never fetched), for use
+    /// off the UI thread since it reads from disk. Changes not yet written are
+    /// seen as they are now.
+    pub fn snapshot_lookup(&self) -> impl Fn(&str) -> Option<HostSnapshot> + Send + 'static {
+        let store = self.snapshots.clone();
+        let mut pending = self.writing_snapshots.clone();
+        pending.extend(self.unsaved_snapshots.clone());
+        move |alias| pending.get(alias).cloned().or_else(|| store.load(alias))
+    }
+
+    /// The snapshot of `alias` as last saved, including changes not yet on disk.
+    fn stored_snapshot(&self, alias: &str) -> Option<HostSnapshot> {
+        self.unsaved_snapshots
+            .get(alias)
+            .or_else(|| self.writing_snapshots.get(alias))
+            .cloned()
+            .or_else(|| self.snapshots.load(alias))
+    }
+
+    /// Write the unsaved snapshots on the background executor after
+    /// `SNAPSHOT_WRITE_DELAY`, so a burst of updates costs one write per host.
+    fn schedule_snapshot_write(&mut self, cx: &mut Context<Self>) {
+        if self.snapshot_write.is_some() {
+            return;
         }
-        // Fallback: ~/.config/slarti/baseline_services.yaml
-        if let Ok(home) = std::env::var("HOME") {
-            let mut p = std::path::PathBuf::from(home);
-            p.push(".config");
-            p.push("slarti");
-            p.push("baseline_services.yaml");
-            return p;
-        }
-        std::path::PathBuf::from("baseline_services.yaml")
-    }
-
-    fn load_baseline_names() -> HashSet<String> {
-        let path = Self::baseline_config_path();
-        if let Ok(s) = std::fs::read_to_string(&path) {
-            // Try JSON array first (YAML 1.2 superset of JSON)
-            if let Ok(list) = serde_json::from_str::<Vec<String>>(&s) {
-                return list
-                    .into_iter()
-                    .map(|n| n.trim().to_string())
-                    .filter(|n| !n.is_empty())
-                    .collect();
-            }
-            // Fallback: very simple YAML-like line-based list (accept "- name" or plain lines)
-            let mut set = HashSet::new();
-            for line in s.lines() {
-                let t = line.trim();
-                if t.is_empty() || t.starts_with('#') {
-                    continue;
-                }
-                let mut name = if t.starts_with('-') {
-                    t.trim_start_matches('-').trim()
-                } else {
-                    t
-                };
-                if name.starts_with('"') && name.ends_with('"') && name.len() >= 2 {
-                    name = &name[1..name.len() - 1];
-                }
-                if !name.is_empty() && !name.ends_with(':') {
-                    set.insert(name.to_string());
+        let store = self.snapshots.clone();
+        self.snapshot_write = Some(cx.spawn(async move |this, cx| {
+            cx.background_executor().timer(SNAPSHOT_WRITE_DELAY).await;
+            let Ok(batch) = this.update(cx, |panel, _| {
+                panel.writing_snapshots = std::mem::take(&mut panel.unsaved_snapshots);
+                panel.writing_snapshots.clone()
+            }) else {
+                return;
+            };
+            cx.background_executor()
+                .spawn(async move {
+                    for (alias, snapshot) in &batch {
+                        let _ = store.save(alias, snapshot);
+                    }
+                })
+                .await;
+            let _ = this.update(cx, |panel, cx| {
+                panel.writing_snapshots.clear();
+                panel.snapshot_write = None;
+                if !panel.unsaved_snapshots.is_empty() {
+                    panel.schedule_snapshot_write(cx);
                 }
-            }
-            return set;
+            });
+        }));
+    }
+
+    /// Capture time (unix secs) of cached data shown for `section` in place of live data.
+    ///
+    /// Applies until live data for the section arrives: right after selection
+    /// (before the probe settles) and while the host is unreachable.
+    fn cached_at(&self, section: Section) -> Option<u64> {
+        match section {
+            Section::SysInfo if self.sys_info.is_none() => self
+                .snapshot
+                .sys_info
+                .as_ref()
+                .and(self.snapshot.sys_info_at),
+            Section::Services if self.services.is_none() => self
+                .snapshot
+                .services
+                .as_ref()
+                .and(self.snapshot.services_at),
+            _ => None,
         }
-        HashSet::new()
     }
 
-    fn is_baseline(&self, name: &str) -> bool {
-        self.baseline_names.contains(name)
+    /// Whether `section` renders greyed out (session dropped, or showing cached data).
+    fn is_dimmed(&self, section: Section) -> bool {
+        self.freshness.is_stale(section) || self.cached_at(section).is_some()
     }
 
-    fn save_service_filter_prefs(
-        enabled_only: bool,
-        include_baseline: bool,
-    ) -> std::io::Result<()> {
-        #[derive(Serialize, Deserialize)]
-        struct Prefs {
-            enabled_only: bool,
-            include_baseline: bool,
+    /// Live SysInfo, or the cached snapshot until live data arrives.
+    fn shown_sys_info(&self) -> Option<&proto::SysInfo> {
+        match self.cached_at(Section::SysInfo) {
+            Some(_) => self.snapshot.sys_info.as_ref(),
+            None => self.sys_info.as_ref(),
         }
-        let prefs = Prefs {
-            enabled_only,
-            include_baseline,
-        };
-        let data = serde_json::to_vec_pretty(&prefs)
-            .unwrap_or_else(|_| serde_json::to_vec(&prefs).unwrap());
-        std::fs::write(Self::service_filter_prefs_path(), data)
     }
 
-    fn load_service_filter_prefs() -> (bool, bool) {
-        #[derive(Serialize, Deserialize)]
-        struct Prefs {
-            enabled_only: bool,
-            include_baseline: bool,
-        }
-        let path = Self::service_filter_prefs_path();
-        if let Ok(bytes) = std::fs::read(path) {
-            if let Ok(p) = serde_json::from_slice::<Prefs>(&bytes) {
-                return (p.enabled_only, p.include_baseline);
-            }
+    /// Live services, or the cached snapshot until live data arrives.
+    fn shown_services(&self) -> Option<&Vec<proto::ServiceInfo>> {
+        match self.cached_at(Section::Services) {
+            Some(_) => self.snapshot.services.as_ref(),
+            None => self.services.as_ref(),
         }
-        // Defaults: enabled_only=true, include_baseline=false
-        (true, false)
+    }
+
+    fn recent_state_path() -> std::path::PathBuf {
+        let mut p = Self::state_dir().unwrap_or_else(|| std::path::PathBuf::from("."));
+        let _ = std::fs::create_dir_all(&p);
+        p.push("hosts_recent.json");
+        p
     }
 
     /// Set or update the deploy callback used when clicking the "Deploy agent" button.
@@ -281,6 +754,16 @@ impl HostPanel {
         cx.notify();
     }
 
+    /// Set or update the start-screen quick action callback.
+    pub fn set_on_quick_action(
+        &mut self,
+        cb: Option<Arc<dyn Fn(QuickAction, &mut Window, &mut Context<HostPanel>) + Send + Sync>>,
+        cx: &mut Context<Self>,
+    ) {
+        self.on_quick_action = cb;
+        cx.notify();
+    }
+
     /// Set or update the recent-selection callback (invoked when clicking an item in Recents).
     pub fn set_on_select_recent(
         &mut self,
@@ -291,171 +774,1368 @@ impl HostPanel {
         cx.notify();
     }
 
-    /// Update the latest system info shown in the panel.
-    pub fn set_sys_info(&mut self, info: proto::SysInfo, cx: &mut Context<Self>) {
-        self.sys_info = Some(info);
+    /// Set or update the "Install my key" callback (invoked with the selected alias).
+    pub fn set_on_install_key(
+        &mut self,
+        cb: Option<Arc<dyn Fn(String, &mut Window, &mut Context<HostPanel>) + Send + Sync>>,
+        cx: &mut Context<Self>,
+    ) {
+        self.on_install_key = cb;
         cx.notify();
     }
 
-    /// Update the latest services list shown in the panel.
-    pub fn set_services(&mut self, services: Vec<proto::ServiceInfo>, cx: &mut Context<Self>) {
-        self.services = Some(services);
+    /// Set or update the "Clean old agents" callback (invoked with the selected alias).
+    pub fn set_on_clean_agents(
+        &mut self,
+        cb: Option<Arc<dyn Fn(String, &mut Window, &mut Context<HostPanel>) + Send + Sync>>,
+        cx: &mut Context<Self>,
+    ) {
+        self.on_clean_agents = cb;
         cx.notify();
     }
 
-    /// Update deployment running state (used to disable the button and animate the icon).
-    pub fn set_deploy_running(&mut self, running: bool, cx: &mut Context<Self>) {
-        self.deploy_running = running;
+    /// Set or update the "Authenticate interactively" callback (invoked with the selected alias).
+    pub fn set_on_authenticate(
+        &mut self,
+        cb: Option<Arc<dyn Fn(String, &mut Window, &mut Context<HostPanel>) + Send + Sync>>,
+        cx: &mut Context<Self>,
+    ) {
+        self.on_authenticate = cb;
         cx.notify();
     }
 
-    /// Mark that a deployment has completed at least once (changes button alt to Redeploy).
-    pub fn mark_deployed(&mut self, cx: &mut Context<Self>) {
-        self.has_deployed = true;
+    /// Set or update the "Open console" callback (invoked with the selected alias).
+    pub fn set_on_open_console(
+        &mut self,
+        cb: Option<Arc<dyn Fn(String, &mut Window, &mut Context<HostPanel>) + Send + Sync>>,
+        cx: &mut Context<Self>,
+    ) {
+        self.on_open_console = cb;
         cx.notify();
     }
 
-    fn render_section<'a>(
-        &self,
-        title: impl Into<SharedString>,
-        body: impl Into<SharedString>,
-        depth: f32,
-    ) -> impl IntoElement {
-        let border = gpui::opaque_grey(0.2, 0.7);
-        let fg_dim = gpui::opaque_grey(1.0, 0.85);
+    /// Set or update the callback that builds action previews (invoked with the action and selected alias).
+    pub fn set_on_preview(
+        &mut self,
+        cb: Option<
+            Arc<dyn Fn(PendingAction, String, &mut Window, &mut Context<HostPanel>) + Send + Sync>,
+        >,
+        cx: &mut Context<Self>,
+    ) {
+        self.on_preview = cb;
+        cx.notify();
+    }
 
-        div()
-            .flex()
-            .flex_col()
-            .gap_2()
-            .pl(px(depth))
-            .pr(px(8.0))
-            .py(px(8.0))
-            .border_b_1()
-            .border_color(border)
-            .child(div().text_color(gpui::white()).child(title.into()))
-            .child(div().text_color(fg_dim).child(body.into()))
+    /// Show (or clear) the preview of an action awaiting confirmation.
+    pub fn show_action_preview(&mut self, preview: Option<ActionPreview>, cx: &mut Context<Self>) {
+        self.action_preview = preview;
+        cx.notify();
     }
-}
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-enum ServiceFilter {
-    All,
-    Active,
-    Failed,
-    Inactive,
-}
+    /// Set the selected host's tags (ssh config `Tag`), which the action policy may restrict.
+    pub fn set_host_tags(&mut self, tags: Vec<String>, cx: &mut Context<Self>) {
+        self.host_tags = tags;
+        cx.notify();
+    }
 
-impl Focusable for HostPanel {
-    fn focus_handle(&self, _: &App) -> FocusHandle {
-        self.focus.clone()
+    /// Why the action policy forbids `action` on the selected host, if it does.
+    fn action_denied(&self, action: PendingAction, cx: &App) -> Option<String> {
+        let category = action.category();
+        if self.agent_read_only && category.needs_agent() {
+            return Some(format!(
+                "{} refused: the agent runs read-only",
+                category.label()
+            ));
+        }
+        ActionPolicy::check(cx, category, &self.host_tags)
     }
-}
 
-impl gpui::Render for HostPanel {
-    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
-        // Colors consistent with the rest of the app.
-        let bg = gpui::rgb(0x0b0b0b);
-        let border = gpui::opaque_grey(0.2, 0.7);
-        let fg = gpui::white();
-        let fg_dim = gpui::opaque_grey(1.0, 0.85);
+    /// Run `action`, or preview it first when the user setting requires that.
+    fn request_action(
+        &mut self,
+        action: PendingAction,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(reason) = self.action_denied(action, cx) {
+            self.push_progress(reason, cx);
+            return;
+        }
+        let preview = (PreviewActions::get(cx) || action.always_confirm())
+            .then(|| self.on_preview.clone().zip(self.selected_alias.clone()))
+            .flatten();
+        match preview {
+            Some((cb, alias)) => {
+                self.push_progress("preparing preview", cx);
+                (cb)(action, alias, window, cx);
+            }
+            None => self.run_action(action, window, cx),
+        }
+    }
 
-        let header = {
-            let title = match self.selected_alias.as_ref() {
-                Some(a) => format!("Host • {}", a),
-                None => "Host".to_string(),
-            };
+    /// Run `action` now, without a preview (still subject to the action policy).
+    pub fn run_action(
+        &mut self,
+        action: PendingAction,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(reason) = self.action_denied(action, cx) {
+            self.push_progress(reason, cx);
+            return;
+        }
+        match action {
+            PendingAction::Deploy => {
+                if self.deploy_running {
+                    return;
+                }
+                self.set_deploy_running(true, cx);
+                self.set_status(
+                    if self.has_deployed {
+                        "redeploying…"
+                    } else {
+                        "deploying…"
+                    },
+                    cx,
+                );
+                self.push_progress("uploading agent", cx);
+                if let Some(cb) = self.on_deploy.clone() {
+                    (cb)(window, cx);
+                }
+            }
+            PendingAction::InstallKey => {
+                let (Some(cb), Some(alias)) =
+                    (self.on_install_key.clone(), self.selected_alias.clone())
+                else {
+                    return;
+                };
+                self.push_progress("installing key (see terminal)", cx);
+                (cb)(alias, window, cx);
+            }
+            PendingAction::CleanAgents => {
+                let (Some(cb), Some(alias)) =
+                    (self.on_clean_agents.clone(), self.selected_alias.clone())
+                else {
+                    return;
+                };
+                self.push_progress("removing old agent versions", cx);
+                (cb)(alias, window, cx);
+            }
+            PendingAction::SignalProcess { .. } | PendingAction::ReniceProcess { .. } => {
+                self.run_process_action(action, cx)
+            }
+        }
+    }
+
+    /// Handoff bundle for the selected host (its cached snapshot and current alerts).
+    pub fn handoff_bundle(&self) -> Option<HandoffBundle> {
+        let alias = self.selected_alias.as_deref()?;
+        Some(HandoffBundle::new(
+            alias,
+            self.snapshot.clone(),
+            SystemTime::now(),
+        ))
+    }
+
+    /// Import a handoff bundle: keep its snapshot unless ours is newer, then
+    /// select its host and show its notes and alerts.
+    pub fn import_handoff(
+        &mut self,
+        bundle: HandoffBundle,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let alias = bundle.alias.clone();
+        let seen_at = |s: &HostSnapshot| s.sys_info_at.max(s.services_at);
+        let ours = self.stored_snapshot(&alias).unwrap_or_default();
+        if seen_at(&bundle.snapshot) > seen_at(&ours) {
+            self.unsaved_snapshots
+                .insert(alias.clone(), bundle.snapshot.clone());
+            self.schedule_snapshot_write(cx);
+            if let Some(health) = self.fleet.get_mut(&alias) {
+                *health = HostHealth::from_snapshot(&bundle.snapshot, unix_now());
+            }
+        }
+        self.handoff = Some(bundle);
+        self.select_host(alias, window, cx);
+        cx.notify();
+    }
 
+    /// Card with the imported handoff's notes and alerts, while its host is selected.
+    fn render_handoff(&self, cx: &mut Context<Self>) -> Option<gpui::Div> {
+        let handoff = self
+            .handoff
+            .as_ref()
+            .filter(|h| self.selected_alias.as_deref() == Some(h.alias.as_str()))?;
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        Some(
             div()
                 .flex()
-                .items_center()
-                .justify_between()
-                .h(px(28.0))
-                .px(px(8.0))
-                .bg(bg)
+                .flex_col()
+                .gap_1()
+                .p(ap.px(8.0))
                 .border_b_1()
-                .border_color(border)
-                .text_color(fg)
-                .child(title)
-        };
+                .border_color(pal.border)
+                .child(
+                    div()
+                        .flex()
+                        .items_center()
+                        .justify_between()
+                        .child(div().text_color(pal.fg).child(handoff.summary()))
+                        .child(
+                            div()
+                                .px(ap.px(8.0))
+                                .h(ap.px(18.0))
+                                .rounded_sm()
+                                .border_1()
+                                .border_color(pal.border)
+                                .cursor_pointer()
+                                .text_color(pal.fg)
+                                .child("Dismiss")
+                                .on_mouse_up(
+                                    MouseButton::Left,
+                                    cx.listener(|this: &mut Self, _ev, _window, cx| {
+                                        this.handoff = None;
+                                        cx.notify();
+                                    }),
+                                ),
+                        ),
+                )
+                .when(!handoff.notes.is_empty(), |d| {
+                    d.child(div().child(handoff.notes.clone()))
+                })
+                .children(handoff.alerts.iter().map(|alert| {
+                    div()
+                        .text_color(gpui::hsla(0.0, 0.8, 0.6, 1.0))
+                        .child(format!("• {}", alert))
+                })),
+        )
+    }
 
-        // Status banner: instantaneous render; updated by background tasks via setters.
-        let status_banner = {
-            let base = if self.checking {
-                format!("Remote: {} (checking…)", self.status)
-            } else {
-                format!("Remote: {}", self.status)
-            };
-            let text = if let Some(p) = &self.last_progress {
-                format!("{} — {}", base, p)
-            } else {
-                base
-            };
-            let row = div()
+    /// Preview card of the pending action with Run/Cancel.
+    fn render_action_preview(&self, cx: &mut Context<Self>) -> Option<gpui::Div> {
+        let preview = self.action_preview.as_ref()?;
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let button = |label: &'static str| {
+            div()
+                .px(ap.px(8.0))
+                .h(ap.px(18.0))
+                .rounded_sm()
+                .border_1()
+                .border_color(pal.border)
+                .cursor_pointer()
+                .text_color(pal.fg)
+                .child(label)
+        };
+        Some(
+            div()
                 .flex()
-                .items_center()
-                .justify_between()
-                .h(px(22.0))
-                .px(px(8.0))
+                .flex_col()
+                .gap_1()
+                .p(ap.px(8.0))
                 .border_b_1()
-                .border_color(border)
-                .text_color(fg_dim)
-                .child(text);
-            if !self.checking {
-                // Visible icon button (deploy/redeploy)
-                let ms = (std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_millis()
-                    % 1000) as f32;
-                let phase = ms / 1000.0;
-                let icon_alpha = if self.deploy_running {
-                    0.4 + 0.6 * ((phase * std::f32::consts::PI * 2.0).sin().abs())
-                } else {
-                    1.0
-                };
-                let icon_color = gpui::hsla(0.6, 0.7, 0.7, icon_alpha);
-                let btn = div()
-                    .px(px(8.0))
-                    .h(px(18.0))
-                    .rounded_sm()
-                    .border_1()
-                    .border_color(border)
-                    .text_color(gpui::white())
-                    .when(!self.deploy_running, |d| d.cursor_pointer())
-                    .child(
-                        UiVector::new("assets/terminal_alt.svg")
-                            .square(px(14.0))
-                            .color(icon_color)
+                .border_color(pal.border)
+                .child(div().text_color(pal.fg).child(preview.title.clone()))
+                .children(preview.lines.iter().map(|line| div().child(line.clone())))
+                .child(
+                    div()
+                        .flex()
+                        .justify_end()
+                        .gap_2()
+                        .child(button("Cancel").on_mouse_up(
+                            MouseButton::Left,
+                            cx.listener(|this: &mut Self, _ev, _window, cx| {
+                                this.show_action_preview(None, cx);
+                                this.push_progress("cancelled", cx);
+                            }),
+                        ))
+                        .child(button("Run").on_mouse_up(
+                            MouseButton::Left,
+                            cx.listener(|this: &mut Self, _ev, window, cx| {
+                                if let Some(preview) = this.action_preview.take() {
+                                    if this.selected_alias.as_deref() == Some(&preview.alias) {
+                                        this.run_action(preview.action, window, cx);
+                                    } else {
+                                        this.push_progress("host changed; action not run", cx);
+                                    }
+                                }
+                                cx.notify();
+                            }),
+                        )),
+                ),
+        )
+    }
+
+    /// Set or update the "Retry now" callback for suppressed hosts (invoked with the selected alias).
+    pub fn set_on_retry(
+        &mut self,
+        cb: Option<Arc<dyn Fn(String, &mut Window, &mut Context<HostPanel>) + Send + Sync>>,
+        cx: &mut Context<Self>,
+    ) {
+        self.on_retry = cb;
+        cx.notify();
+    }
+
+    /// Mark whether probing the selected host is suppressed after repeated timeouts.
+    pub fn set_suppressed(&mut self, suppressed: bool, cx: &mut Context<Self>) {
+        self.suppressed = suppressed;
+        cx.notify();
+    }
+
+    /// Mark the selected host as a serial console host (e.g. "/dev/ttyUSB0 @ 115200"),
+    /// which replaces the agent actions with "Open console".
+    pub fn set_serial_console(
+        &mut self,
+        line: Option<impl Into<SharedString>>,
+        cx: &mut Context<Self>,
+    ) {
+        self.serial_console = line.map(Into::into);
+        cx.notify();
+    }
+
+    /// Mark whether the selected host needs interactive (password/keyboard-interactive) auth.
+    pub fn set_auth_required(&mut self, required: bool, cx: &mut Context<Self>) {
+        self.auth_required = required;
+        cx.notify();
+    }
+
+    /// Update the latest system info shown in the panel.
+    pub fn set_sys_info(&mut self, info: proto::SysInfo, cx: &mut Context<Self>) {
+        let now = SystemTime::now();
+        self.snapshot.set_sys_info(info.clone(), now);
+        self.save_snapshot(cx);
+        self.sys_info = Some(info);
+        self.freshness.mark(Section::SysInfo, now);
+        if let Some(sched) = self.scheduler_mut() {
+            sched.mark_fetched(Section::SysInfo, Instant::now());
+        }
+        cx.notify();
+    }
+
+    /// Update the agent's own resource use shown under Identity.
+    pub fn set_agent_stats(&mut self, stats: proto::AgentStats, cx: &mut Context<Self>) {
+        self.agent_stats = Some(stats);
+        cx.notify();
+    }
+
+    /// Update the latest services list shown in the panel.
+    pub fn set_services(&mut self, services: Vec<proto::ServiceInfo>, cx: &mut Context<Self>) {
+        let now = SystemTime::now();
+        self.snapshot.set_services(services.clone(), now);
+        self.save_snapshot(cx);
+        let was_dimmed = self.is_dimmed(Section::Services);
+        self.services = Some(services);
+        self.freshness.mark(Section::Services, now);
+        if let Some(sched) = self.scheduler_mut() {
+            sched.mark_fetched(Section::Services, Instant::now());
+        }
+        // Only the list and (maybe) its header change; leave the rest of the panel alone.
+        self.sync_services_list(cx);
+        self.tick_freshness(cx);
+        if was_dimmed != self.is_dimmed(Section::Services) {
+            cx.notify();
+        }
+    }
+
+    /// Show that fetching `section` failed; the next successful fetch clears it.
+    pub fn set_section_error(
+        &mut self,
+        section: Section,
+        message: impl Into<String>,
+        cx: &mut Context<Self>,
+    ) {
+        self.freshness.fail(section, message.into());
+        cx.notify();
+    }
+
+    /// Update the state of the agent session feeding the panel.
+    ///
+    /// When the session drops, sections keep their data but render greyed out as stale.
+    pub fn set_session_state(&mut self, state: SessionState, cx: &mut Context<Self>) {
+        if self.freshness.session() != state {
+            self.freshness.set_session(state);
+            cx.notify();
+        }
+    }
+
+    /// Repaint if any "updated … ago" label would change. Call periodically (e.g. once a second).
+    pub fn tick_freshness(&mut self, cx: &mut Context<Self>) {
+        let now = SystemTime::now();
+        let labels: Vec<Option<String>> = Section::ALL
+            .iter()
+            .map(|s| self.freshness.age_label(*s, now))
+            .collect();
+        if labels != self.age_labels {
+            self.age_labels = labels;
+            cx.notify();
+        }
+    }
+
+    /// Polling scheduler for the selected host (created on first use).
+    fn scheduler_mut(&mut self) -> Option<&mut PollScheduler> {
+        let alias = self.selected_alias.clone()?;
+        Some(self.schedulers.entry(alias).or_default())
+    }
+
+    /// Sections of the selected host that are due for a refresh.
+    ///
+    /// Called periodically by the app's session loop; interval polling is paused
+    /// when `window_focused` is false, manual refresh requests are always returned.
+    pub fn take_due_sections(&mut self, window_focused: bool) -> Vec<Section> {
+        match self.scheduler_mut() {
+            Some(sched) => sched.take_due(Instant::now(), window_focused),
+            None => Vec::new(),
+        }
+    }
+
+    /// Ask for an immediate refresh of a section of the selected host.
+    pub fn request_refresh(&mut self, section: Section, cx: &mut Context<Self>) {
+        if let Some(sched) = self.scheduler_mut() {
+            sched.request(section);
+        }
+        cx.notify();
+    }
+
+    /// Advance the auto-refresh interval of a section (off → 5s → 30s → 5m).
+    pub fn cycle_refresh_interval(&mut self, section: Section, cx: &mut Context<Self>) {
+        if let Some(sched) = self.scheduler_mut() {
+            let next = sched.interval(section).next();
+            sched.set_interval(section, next);
+        }
+        cx.notify();
+    }
+
+    /// Refresh button and auto-refresh interval toggle shown in a section header.
+    fn render_section_controls(
+        &self,
+        section: Section,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let border = pal.border;
+        let interval = self
+            .selected_alias
+            .as_ref()
+            .and_then(|a| self.schedulers.get(a))
+            .map(|s| s.interval(section))
+            .unwrap_or_default();
+
+        let age = match self.cached_at(section) {
+            Some(at) => Some(format!("cached from {}", format_timestamp(at))),
+            None => self.freshness.age_label(section, SystemTime::now()),
+        };
+        let stale = self.freshness.is_stale(section);
+        let failure = self.freshness.failure(section).map(str::to_string);
+
+        div()
+            .flex()
+            .items_center()
+            .gap_2()
+            .when_some(age, |d, age| {
+                d.child(div().text_color(pal.muted).child(if stale {
+                    format!("{} (session lost)", age)
+                } else {
+                    age
+                }))
+            })
+            .when_some(failure, |d, failure| {
+                d.child(
+                    div()
+                        .text_color(gpui::hsla(0.0, 0.8, 0.6, 1.0))
+                        .child(format!("fetch failed: {}", failure)),
+                )
+            })
+            .child(
+                div()
+                    .px(ap.px(6.0))
+                    .rounded_sm()
+                    .border_1()
+                    .border_color(border)
+                    .cursor_pointer()
+                    .text_color(pal.fg)
+                    .on_mouse_up(
+                        MouseButton::Left,
+                        cx.listener(move |this: &mut Self, _ev, _w, cx| {
+                            this.request_refresh(section, cx);
+                        }),
+                    )
+                    .child("⟳"),
+            )
+            .child(
+                div()
+                    .px(ap.px(6.0))
+                    .rounded_sm()
+                    .border_1()
+                    .border_color(border)
+                    .cursor_pointer()
+                    .text_color(if interval == RefreshInterval::Off {
+                        pal.muted
+                    } else {
+                        pal.fg
+                    })
+                    .on_mouse_up(
+                        MouseButton::Left,
+                        cx.listener(move |this: &mut Self, _ev, _w, cx| {
+                            this.cycle_refresh_interval(section, cx);
+                        }),
+                    )
+                    .child(format!("auto: {}", interval.label())),
+            )
+            .child(
+                div()
+                    .px(ap.px(6.0))
+                    .rounded_sm()
+                    .border_1()
+                    .border_color(border)
+                    .cursor_pointer()
+                    .text_color(pal.fg)
+                    .on_mouse_up(
+                        MouseButton::Left,
+                        cx.listener(move |this: &mut Self, _ev, _w, cx| {
+                            this.copy_section(section, cx);
+                        }),
+                    )
+                    .child("Copy image"),
+            )
+            .children(CsvTable::for_section(section).map(|table| {
+                div()
+                    .px(ap.px(6.0))
+                    .rounded_sm()
+                    .border_1()
+                    .border_color(border)
+                    .cursor_pointer()
+                    .text_color(pal.fg)
+                    .on_mouse_up(
+                        MouseButton::Left,
+                        cx.listener(move |this: &mut Self, _ev, _w, cx| {
+                            this.export_csv(table, cx);
+                        }),
+                    )
+                    .child("CSV")
+            }))
+    }
+
+    /// Identity section body: alias plus SysInfo (live or cached).
+    fn identity_text(&self) -> String {
+        match (self.selected_alias.as_ref(), self.shown_sys_info()) {
+            (Some(a), Some(info)) => {
+                let mut s = format!(
+                    "alias: {}\nhostname: {}\nos: {}\nkernel: {}\narch: {}\nuptime: {}s",
+                    a, info.hostname, info.os, info.kernel, info.arch, info.uptime_secs
+                );
+                if let Some(platform) = &info.platform {
+                    s.push_str(&format!("\nplatform: {}", platform_text(platform)));
+                }
+                if let Some(sync) = &info.time_sync {
+                    s.push_str(&format!("\nclock: {}", time_sync_text(sync)));
+                }
+                if let Some(reboot) = info.reboot.as_ref().filter(|r| r.required()) {
+                    s.push_str(&format!("\nreboot: {}", reboot_text(reboot, &info.kernel)));
+                }
+                if let Some(stats) = &self.agent_stats {
+                    s.push_str(&format!("\nagent: {}", agent_stats_text(stats)));
+                }
+                s
+            }
+            (Some(a), None) => {
+                let mut s = format!(
+                    "alias: {}\nhostname: (pending)\nos: (pending)\nkernel: (pending)\narch: (pending)\nuptime: (pending)",
+                    a
+                );
+                if let Some(p) = &self.last_progress {
+                    s.push_str(&format!("\nstatus: {}", p));
+                }
+                s
+            }
+            (None, _) => "No host selected.".into(),
+        }
+    }
+
+    /// Status banner line: remote status, "checking…", and the last progress message.
+    fn status_text(&self) -> String {
+        let base = if self.checking {
+            format!("Remote: {} (checking…)", self.status)
+        } else {
+            format!("Remote: {}", self.status)
+        };
+        match &self.last_progress {
+            Some(p) => format!("{} — {}", base, p),
+            None => base,
+        }
+    }
+
+    /// Plain-text rendering of `section` (tables aligned, charts as block characters)
+    /// for pasting into chat.
+    fn section_text(&self, section: Section, cx: &App) -> Option<String> {
+        let alias = self.selected_alias.as_deref()?;
+        let when = match self.cached_at(section) {
+            Some(at) => format!("cached from {}", format_timestamp(at)),
+            None => format!("as of {}", format_timestamp(unix_now())),
+        };
+        let body = match section {
+            Section::SysInfo => {
+                let mut s = self.identity_text();
+                if let Some(last) = self.latency_history.back() {
+                    let spark = Sparkline::new(
+                        self.latency_history
+                            .iter()
+                            .map(|d| d.as_secs_f32() * 1000.0),
+                    );
+                    s.push_str(&format!(
+                        "\nrtt: {} ms {}",
+                        last.as_millis(),
+                        spark.to_text()
+                    ));
+                }
+                if let Some(summary) = self.uptime_load_summary() {
+                    s.push_str(&format!("\n{}", summary));
+                }
+                s
+            }
+            Section::Services => self.services_list.read(cx).to_text()?,
+            Section::Processes => processes::summary_text(self.processes.as_ref()?),
+            Section::Cgroups => cgroups::tree_text(self.cgroups.as_ref()?),
+            Section::Pressure => pressure::pressure_text(self.pressure.as_ref()?),
+            Section::Hardware => hardware::hardware_text(
+                self.static_config.as_ref(),
+                self.gpus.as_ref(),
+                self.sensors.as_ref(),
+            )?,
+            Section::Inventory => inventory::inventory_text(self.inventory.as_ref()?),
+            Section::Storage => storage::storage_text(
+                self.raid_arrays.as_ref(),
+                self.storage_stacks.as_ref(),
+                self.mounts.as_ref(),
+            )?,
+            Section::Kernel => kernel::kernel_text(
+                self.kernel_modules.as_ref(),
+                self.sysctl.as_ref(),
+                self.kernel_baseline.as_ref(),
+            )?,
+            Section::Backups => backups::backups_text(self.backups.as_ref()?),
+            Section::Updates => updates::updates_text(self.updates.as_ref()?),
+            Section::Sessions => sessions::sessions_text(self.sessions.as_ref()?),
+            Section::Access => access::access_text(self.users.as_ref()?),
+            Section::Security => security::security_text(
+                self.sshd_config.as_ref()?.as_ref(),
+                self.auth_failures.as_ref(),
+            ),
+            Section::Connectivity => connectivity::connectivity_text(
+                self.connectivity.as_ref(),
+                self.dns_config.as_ref(),
+                self.routes.as_ref(),
+                self.sockets.as_ref(),
+            )?,
+            Section::Listeners => listeners::listeners_text(self.listeners.as_ref()?),
+        };
+        let title = match section {
+            Section::SysInfo => "identity",
+            Section::Services => "services",
+            Section::Processes => "processes",
+            Section::Cgroups => "cgroups",
+            Section::Pressure => "pressure",
+            Section::Hardware => "hardware",
+            Section::Inventory => "inventory",
+            Section::Storage => "storage",
+            Section::Kernel => "kernel",
+            Section::Backups => "backups",
+            Section::Updates => "updates",
+            Section::Sessions => "sessions",
+            Section::Connectivity => "connectivity",
+            Section::Listeners => "listeners",
+            Section::Security => "security",
+            Section::Access => "access",
+        };
+        Some(format!(
+            "{} {} ({})\n{}",
+            alias,
+            title,
+            when,
+            body.trim_end()
+        ))
+    }
+
+    /// Health of every configured host as CSV, in config order.
+    fn fleet_csv(&self) -> String {
+        let mut out = String::new();
+        csv::push_record(
+            &mut out,
+            [
+                "host",
+                "health",
+                "failed_services",
+                "pending_updates",
+                "reboot_required",
+                "last_seen",
+            ],
+        );
+        for alias in &self.known_hosts {
+            let health = self
+                .fleet
+                .get(alias)
+                .copied()
+                .unwrap_or(HostHealth::UNKNOWN);
+            csv::push_record(
+                &mut out,
+                [
+                    alias.clone(),
+                    format!("{:?}", health.health).to_lowercase(),
+                    health.failed_services.to_string(),
+                    health
+                        .pending_updates
+                        .map(|n| n.to_string())
+                        .unwrap_or_default(),
+                    health.reboot_required.to_string(),
+                    health.seen_at.map(format_timestamp).unwrap_or_default(),
+                ],
+            );
+        }
+        out
+    }
+
+    /// Ask for a file and write `table` to it as CSV.
+    fn export_csv(&mut self, table: CsvTable, cx: &mut Context<Self>) {
+        let alias = self.selected_alias.as_deref();
+        let export = match table {
+            CsvTable::Services => {
+                let list = self.services_list.read(cx);
+                alias.zip(list.to_csv()).map(|(alias, data)| {
+                    (
+                        format!("{}-services-{}.csv", alias, list.filter_label()),
+                        data,
+                    )
+                })
+            }
+            CsvTable::Processes => alias.zip(self.processes.as_ref()).map(|(alias, summary)| {
+                (
+                    format!("{}-processes.csv", alias),
+                    processes::summary_csv(summary),
+                )
+            }),
+            CsvTable::Listeners => {
+                alias
+                    .zip(self.listeners.as_deref())
+                    .map(|(alias, listeners)| {
+                        (
+                            format!("{}-listeners.csv", alias),
+                            listeners::listeners_csv(listeners),
+                        )
+                    })
+            }
+            CsvTable::Fleet => Some(("fleet.csv".to_string(), self.fleet_csv())),
+        };
+        let Some((name, data)) = export else {
+            self.push_progress("nothing to export yet", cx);
+            return;
+        };
+        let dir = std::env::var("HOME")
+            .map(std::path::PathBuf::from)
+            .unwrap_or_else(|_| std::path::PathBuf::from("."));
+        let path = cx.prompt_for_new_path(&dir, Some(&name));
+        cx.spawn(async move |this, cx| {
+            let Ok(Ok(Some(path))) = path.await else {
+                return;
+            };
+            let msg = match std::fs::write(&path, data) {
+                Ok(()) => format!("exported to {}", path.display()),
+                Err(e) => format!("export failed: {}", e),
+            };
+            let _ = this.update(cx, |panel, cx| panel.push_progress(msg, cx));
+        })
+        .detach();
+    }
+
+    /// Copy `section` to the clipboard as a picture, for pasting into chat.
+    fn copy_section(&mut self, section: Section, cx: &mut Context<Self>) {
+        let Some(text) = self.section_text(section, cx) else {
+            self.push_progress("nothing to copy yet", cx);
+            return;
+        };
+        let pal = Appearance::get(cx).palette();
+        let colors = picture::Colors {
+            fg: pal.fg,
+            bg: pal.bg,
+        };
+        let png = cx
+            .background_executor()
+            .spawn(async move { picture::section_png(&text, colors) });
+        cx.spawn(async move |this, cx| {
+            let png = png.await;
+            let _ = this.update(cx, |panel, cx| match png {
+                Ok(bytes) => {
+                    let image = gpui::Image::from_bytes(gpui::ImageFormat::Png, bytes);
+                    cx.write_to_clipboard(gpui::ClipboardItem::new_image(&image));
+                    panel.push_progress("copied to clipboard as an image", cx);
+                }
+                Err(e) => panel.push_progress(format!("copy failed: {:#}", e), cx),
+            });
+        })
+        .detach();
+    }
+
+    /// Update deployment running state (used to disable the button and animate the icon).
+    pub fn set_deploy_running(&mut self, running: bool, cx: &mut Context<Self>) {
+        self.deploy_running = running;
+        cx.notify();
+    }
+
+    /// Mark that a deployment has completed at least once (changes button alt to Redeploy).
+    pub fn mark_deployed(&mut self, cx: &mut Context<Self>) {
+        self.has_deployed = true;
+        cx.notify();
+    }
+
+    /// Record the round-trip time of an agent request (shown with a history sparkline).
+    pub fn record_latency(&mut self, rtt: Duration, cx: &mut Context<Self>) {
+        if self.latency_history.len() == LATENCY_HISTORY_LEN {
+            self.latency_history.pop_front();
+        }
+        self.latency_history.push_back(rtt);
+        cx.notify();
+    }
+
+    /// Record the ssh connection setup time of the current session.
+    pub fn set_connect_time(&mut self, setup: Duration, cx: &mut Context<Self>) {
+        self.connect_time = Some(setup);
+        cx.notify();
+    }
+
+    /// Record whether the session's agent runs read-only (`--read-only`).
+    pub fn set_agent_read_only(&mut self, read_only: bool, cx: &mut Context<Self>) {
+        self.agent_read_only = read_only;
+        cx.notify();
+    }
+
+    /// Latency readout for the status banner: last round-trip plus a sparkline of recent ones.
+    fn render_latency(&self) -> Option<impl IntoElement> {
+        let last = self.latency_history.back()?;
+        let mut text = format!("rtt {} ms", last.as_millis());
+        if let Some(setup) = self.connect_time {
+            text.push_str(&format!(" (setup {} ms)", setup.as_millis()));
+        }
+        Some(
+            div()
+                .flex()
+                .items_center()
+                .gap_1()
+                .text_color(gpui::opaque_grey(1.0, 0.6))
+                .child(
+                    Sparkline::new(
+                        self.latency_history
+                            .iter()
+                            .map(|d| d.as_secs_f32() * 1000.0),
+                    )
+                    .with_size(px(45.0), px(12.0))
+                    .color(gpui::hsla(0.6, 0.7, 0.7, 1.0))
+                    .render(),
+                )
+                .child(text),
+        )
+    }
+
+    /// Short uptime/load summary for the status banner, e.g. "up 14d, load 0.42 0.38 0.31".
+    fn uptime_load_summary(&self) -> Option<String> {
+        let info = self.sys_info.as_ref()?;
+        let mut s = format!("up {}", format_uptime(info.uptime_secs));
+        if let Some([l1, l5, l15]) = info.load_avg {
+            s.push_str(&format!(", load {:.2} {:.2} {:.2}", l1, l5, l15));
+        }
+        Some(s)
+    }
+
+    fn render_section(
+        &self,
+        section: Section,
+        title: impl Into<SharedString>,
+        body: impl Into<SharedString>,
+        depth: f32,
+        cx: &mut Context<Self>,
+    ) -> Div {
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let border = pal.border;
+        let fg_dim = pal.fg_dim;
+
+        div()
+            .flex()
+            .flex_col()
+            .gap_2()
+            .pl(ap.px(depth))
+            .pr(ap.px(8.0))
+            .py(ap.px(8.0))
+            .border_b_1()
+            .border_color(border)
+            // Grey out data whose session has dropped.
+            .when(self.is_dimmed(section), |d| d.opacity(0.5))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .child(div().text_color(pal.fg).child(title.into()))
+                    .child(self.render_section_controls(section, cx)),
+            )
+            .child(div().text_color(fg_dim).child(body.into()))
+    }
+}
+
+/// `$XDG_CONFIG_HOME/slarti/<file>`, else `~/.config/slarti/<file>`.
+fn config_path(file: &str) -> std::path::PathBuf {
+    if let Ok(xdg) = std::env::var("XDG_CONFIG_HOME") {
+        return std::path::PathBuf::from(xdg).join("slarti").join(file);
+    }
+    if let Ok(home) = std::env::var("HOME") {
+        return std::path::PathBuf::from(home)
+            .join(".config")
+            .join("slarti")
+            .join(file);
+    }
+    std::path::PathBuf::from(file)
+}
+
+/// Entries of a user-edited list file: a JSON array of strings, or one entry
+/// per line ("- entry" or plain, optionally quoted; '#' starts a comment
+/// line). Empty when the file is missing.
+fn load_list(path: &std::path::Path) -> Vec<String> {
+    let Ok(s) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    // Try JSON array first (YAML 1.2 superset of JSON)
+    if let Ok(list) = serde_json::from_str::<Vec<String>>(&s) {
+        return list
+            .into_iter()
+            .map(|n| n.trim().to_string())
+            .filter(|n| !n.is_empty())
+            .collect();
+    }
+    // Fallback: very simple YAML-like line-based list (accept "- name" or plain lines)
+    let mut list = Vec::new();
+    for line in s.lines() {
+        let t = line.trim();
+        if t.is_empty() || t.starts_with('#') {
+            continue;
+        }
+        let mut name = if t.starts_with('-') {
+            t.trim_start_matches('-').trim()
+        } else {
+            t
+        };
+        if name.starts_with('"') && name.ends_with('"') && name.len() >= 2 {
+            name = &name[1..name.len() - 1];
+        }
+        if !name.is_empty() && !name.ends_with(':') {
+            list.push(name.to_string());
+        }
+    }
+    list
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// "synchronized via chrony to ntp.example.org (stratum 3), offset +0.2 ms"
+fn time_sync_text(sync: &proto::TimeSync) -> String {
+    let mut s = String::from(if sync.synchronized {
+        "synchronized"
+    } else {
+        "not synchronized"
+    });
+    if let Some(source) = &sync.source {
+        s.push_str(&format!(" via {}", source));
+    }
+    if let Some(server) = &sync.server {
+        s.push_str(&format!(" to {}", server));
+    }
+    if let Some(stratum) = sync.stratum {
+        s.push_str(&format!(" (stratum {})", stratum));
+    }
+    if let Some(offset) = sync.offset_secs {
+        s.push_str(&format!(", offset {:+.1} ms", offset * 1000.0));
+    }
+    s
+}
+
+/// Why the clock needs attention: not synchronized, or off by more than
+/// `CLOCK_OFFSET_WARN_SECS`.
+fn clock_warning(sync: &proto::TimeSync) -> Option<String> {
+    if !sync.synchronized {
+        return Some("Clock is not synchronized (NTP)".to_string());
+    }
+    let offset = sync.offset_secs?;
+    (offset.abs() > CLOCK_OFFSET_WARN_SECS).then(|| {
+        format!(
+            "Clock is off by {:+.0} ms (more than {:.0} ms)",
+            offset * 1000.0,
+            CLOCK_OFFSET_WARN_SECS * 1000.0
+        )
+    })
+}
+
+/// Why a reboot is due: "kernel 6.1.0-21-amd64 installed, 6.1.0-18-amd64
+/// running; requested by libc6, linux-image-amd64".
+fn reboot_text(reboot: &proto::RebootStatus, running: &str) -> String {
+    let mut parts = Vec::new();
+    if let Some(newer) = &reboot.newer_kernel {
+        parts.push(format!("kernel {} installed, {} running", newer, running));
+    }
+    if reboot.flagged {
+        parts.push(if reboot.packages.is_empty() {
+            "requested by an update".to_string()
+        } else {
+            format!("requested by {}", reboot.packages.join(", "))
+        });
+    }
+    parts.join("; ")
+}
+
+/// "aws t3.micro in eu-west-1 (eu-west-1a), kvm vm, docker container", or
+/// "bare metal".
+fn platform_text(p: &proto::Platform) -> String {
+    let mut parts = Vec::new();
+    if let Some(provider) = &p.provider {
+        let mut cloud = provider.clone();
+        if let Some(instance_type) = &p.instance_type {
+            cloud.push_str(&format!(" {}", instance_type));
+        }
+        if let Some(region) = &p.region {
+            cloud.push_str(&format!(" in {}", region));
+        }
+        if let Some(zone) = &p.zone {
+            cloud.push_str(&format!(" ({})", zone));
+        }
+        parts.push(cloud);
+    }
+    if let Some(vm) = &p.vm {
+        parts.push(format!("{} vm", vm));
+    }
+    if let Some(container) = &p.container {
+        parts.push(format!("{} container", container));
+    }
+    if parts.is_empty() {
+        "bare metal".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+/// "9.8 MiB (peak 12 MiB), 0.4s CPU + 1.2s tools, 4 threads; limits 256 MiB,
+/// nice 10, watchdog 300s; 1 of 42 commands aborted"
+fn agent_stats_text(stats: &proto::AgentStats) -> String {
+    let secs = |ms: u64| format!("{:.1}s", ms as f64 / 1000.0);
+    let mut s = format!(
+        "{} (peak {}), {} CPU + {} tools, {} threads",
+        units::format_bytes(stats.rss_bytes),
+        units::format_bytes(stats.peak_rss_bytes),
+        secs(stats.cpu_user_ms + stats.cpu_system_ms),
+        secs(stats.children_cpu_ms),
+        stats.threads
+    );
+    let limits = &stats.limits;
+    let mut applied = Vec::new();
+    if let Some(bytes) = limits.memory_bytes {
+        applied.push(units::format_bytes(bytes));
+    }
+    if let Some(percent) = limits.cpu_percent {
+        applied.push(format!("{}% CPU", percent));
+    }
+    if let Some(nice) = limits.nice {
+        applied.push(format!("nice {}", nice));
+    }
+    if let Some(ms) = limits.command_timeout_ms {
+        applied.push(format!("watchdog {}s", ms / 1000));
+    }
+    if !applied.is_empty() {
+        s.push_str(&format!("; limits {}", applied.join(", ")));
+        if limits.cgroup {
+            s.push_str(" (cgroup)");
+        }
+    }
+    if stats.watchdog_aborts > 0 {
+        s.push_str(&format!(
+            "; {} of {} commands aborted",
+            stats.watchdog_aborts, stats.commands
+        ));
+    }
+    s
+}
+
+/// Format an uptime in seconds using its largest whole unit (e.g. "14d", "3h", "12m").
+fn format_uptime(secs: u64) -> String {
+    if secs >= 86_400 {
+        format!("{}d", secs / 86_400)
+    } else if secs >= 3_600 {
+        format!("{}h", secs / 3_600)
+    } else if secs >= 60 {
+        format!("{}m", secs / 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+impl Focusable for HostPanel {
+    fn focus_handle(&self, _: &App) -> FocusHandle {
+        self.focus.clone()
+    }
+}
+
+impl gpui::Render for HostPanel {
+    fn render(&mut self, window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        // Colors and sizes follow the app-wide appearance (scale, high contrast).
+        let ap = Appearance::get(_cx);
+        let pal = ap.palette();
+        let bg = pal.bg;
+        let border = pal.border;
+        let fg = pal.fg;
+        let fg_dim = pal.fg_dim;
+
+        let header = {
+            let title = match self.selected_alias.as_ref() {
+                Some(a) => format!("Host • {}", a),
+                None => "Host".to_string(),
+            };
+
+            div()
+                .debug_selector(|| "host-header".into())
+                .flex()
+                .items_center()
+                .justify_between()
+                .h(ap.px(28.0))
+                .px(ap.px(8.0))
+                .bg(bg)
+                .border_b_1()
+                .border_color(border)
+                .text_color(fg)
+                .child(title)
+        };
+
+        // Status banner: instantaneous render; updated by background tasks via setters.
+        let reboot_required = self
+            .shown_sys_info()
+            .and_then(|info| info.reboot.as_ref())
+            .is_some_and(|r| r.required());
+        let status_banner = {
+            let text = self.status_text();
+            let row = div()
+                .debug_selector(|| "host-status".into())
+                .flex()
+                .items_center()
+                .justify_between()
+                .h(ap.px(22.0))
+                .px(ap.px(8.0))
+                .border_b_1()
+                .border_color(border)
+                .text_color(fg_dim)
+                .child(
+                    div()
+                        .flex()
+                        .items_center()
+                        .gap_2()
+                        .child(text)
+                        .when_some(self.render_latency(), |d, latency| d.child(latency))
+                        .when_some(self.uptime_load_summary(), |d, summary| {
+                            d.child(div().text_color(pal.muted).child(summary))
+                        })
+                        .when(reboot_required, |d| {
+                            d.child(
+                                div()
+                                    .px(ap.px(6.0))
+                                    .rounded_sm()
+                                    .border_1()
+                                    .border_color(gpui::hsla(0.13, 0.8, 0.6, 1.0))
+                                    .text_color(gpui::hsla(0.13, 0.8, 0.6, 1.0))
+                                    .child("reboot required"),
+                            )
+                        }),
+                );
+            if !self.checking {
+                // Visible icon button (deploy/redeploy)
+                let ms = (std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis()
+                    % 1000) as f32;
+                let phase = ms / 1000.0;
+                let icon_alpha = if self.deploy_running {
+                    0.4 + 0.6 * ((phase * std::f32::consts::PI * 2.0).sin().abs())
+                } else {
+                    1.0
+                };
+                let icon_color = gpui::hsla(0.6, 0.7, 0.7, icon_alpha);
+                // Actions the policy forbids on this host are not offered at all.
+                let deploy_allowed = self.action_denied(PendingAction::Deploy, _cx).is_none();
+                let install_key_allowed =
+                    self.action_denied(PendingAction::InstallKey, _cx).is_none();
+                let btn = div()
+                    .debug_selector(|| "host-deploy".into())
+                    .px(ap.px(8.0))
+                    .h(ap.px(18.0))
+                    .rounded_sm()
+                    .border_1()
+                    .border_color(border)
+                    .text_color(pal.fg)
+                    .when(!self.deploy_running, |d| d.cursor_pointer())
+                    .child(
+                        UiVector::new("assets/terminal_alt.svg")
+                            .square(px(14.0))
+                            .color(icon_color)
                             .render(),
                     )
-                    .on_mouse_up(MouseButton::Left, {
-                        let cb = self.on_deploy.clone();
+                    .on_mouse_up(
+                        MouseButton::Left,
                         _cx.listener(
-                            move |this: &mut Self,
-                                  _ev: &gpui::MouseUpEvent,
-                                  window: &mut Window,
-                                  cx: &mut Context<HostPanel>| {
-                                if this.deploy_running {
-                                    return;
-                                }
-                                this.set_deploy_running(true, cx);
-                                this.set_status(
-                                    if this.has_deployed {
-                                        "redeploying…"
-                                    } else {
-                                        "deploying…"
-                                    },
-                                    cx,
-                                );
-                                this.push_progress("uploading agent", cx);
-                                if let Some(cb) = cb.as_ref() {
-                                    (cb)(window, cx);
+                            |this: &mut Self,
+                             _ev: &gpui::MouseUpEvent,
+                             window: &mut Window,
+                             cx: &mut Context<HostPanel>| {
+                                if !this.deploy_running {
+                                    this.request_action(PendingAction::Deploy, window, cx);
                                 }
                             },
+                        ),
+                    );
+                // Install the user's public key (ssh-copy-id) for password-only hosts.
+                let install_key = self.on_install_key.as_ref().map(|_| {
+                    div()
+                        .debug_selector(|| "host-install-key".into())
+                        .px(ap.px(8.0))
+                        .h(ap.px(18.0))
+                        .rounded_sm()
+                        .border_1()
+                        .border_color(border)
+                        .cursor_pointer()
+                        .text_color(pal.fg)
+                        .child("Install my key")
+                        .on_mouse_up(
+                            MouseButton::Left,
+                            _cx.listener(|this: &mut Self, _ev, window, cx| {
+                                this.request_action(PendingAction::InstallKey, window, cx);
+                            }),
+                        )
+                });
+                // Older agent versions left by earlier deploys; the preview lists what goes.
+                let clean_agents = self.on_clean_agents.as_ref().map(|_| {
+                    div()
+                        .debug_selector(|| "host-clean-agents".into())
+                        .px(ap.px(8.0))
+                        .h(ap.px(18.0))
+                        .rounded_sm()
+                        .border_1()
+                        .border_color(border)
+                        .cursor_pointer()
+                        .text_color(pal.fg)
+                        .child("Clean old agents")
+                        .on_mouse_up(
+                            MouseButton::Left,
+                            _cx.listener(|this: &mut Self, _ev, window, cx| {
+                                if !this.deploy_running {
+                                    this.request_action(PendingAction::CleanAgents, window, cx);
+                                }
+                            }),
+                        )
+                });
+                // Password/keyboard-interactive login in the terminal when BatchMode auth failed.
+                let authenticate = self
+                    .on_authenticate
+                    .as_ref()
+                    .filter(|_| self.auth_required)
+                    .map(|_| {
+                        div()
+                            .px(ap.px(8.0))
+                            .h(ap.px(18.0))
+                            .rounded_sm()
+                            .border_1()
+                            .border_color(border)
+                            .cursor_pointer()
+                            .text_color(gpui::yellow())
+                            .child("Authenticate interactively")
+                            .on_mouse_up(
+                                MouseButton::Left,
+                                _cx.listener(|this: &mut Self, _ev, window, cx| {
+                                    let (Some(cb), Some(alias)) =
+                                        (this.on_authenticate.clone(), this.selected_alias.clone())
+                                    else {
+                                        return;
+                                    };
+                                    this.push_progress("waiting for login (see terminal)", cx);
+                                    (cb)(alias, window, cx);
+                                }),
+                            )
+                    });
+                // Probing stopped after repeated timeouts: retry on demand instead of waiting out the backoff.
+                let retry = self.on_retry.as_ref().filter(|_| self.suppressed).map(|_| {
+                    div()
+                        .px(ap.px(8.0))
+                        .h(ap.px(18.0))
+                        .rounded_sm()
+                        .border_1()
+                        .border_color(border)
+                        .cursor_pointer()
+                        .text_color(gpui::yellow())
+                        .child("Retry now")
+                        .on_mouse_up(
+                            MouseButton::Left,
+                            _cx.listener(|this: &mut Self, _ev, window, cx| {
+                                let (Some(cb), Some(alias)) =
+                                    (this.on_retry.clone(), this.selected_alias.clone())
+                                else {
+                                    return;
+                                };
+                                this.push_progress("retrying", cx);
+                                (cb)(alias, window, cx);
+                            }),
                         )
+                });
+                // Serial hosts have no agent; their only action opens the console in the terminal.
+                let open_console = self
+                    .on_open_console
+                    .as_ref()
+                    .zip(self.serial_console.as_ref())
+                    .map(|(_, line)| {
+                        div()
+                            .px(ap.px(8.0))
+                            .h(ap.px(18.0))
+                            .rounded_sm()
+                            .border_1()
+                            .border_color(border)
+                            .cursor_pointer()
+                            .text_color(pal.fg)
+                            .child(format!("Open console ({})", line))
+                            .on_mouse_up(
+                                MouseButton::Left,
+                                _cx.listener(|this: &mut Self, _ev, window, cx| {
+                                    let (Some(cb), Some(alias)) =
+                                        (this.on_open_console.clone(), this.selected_alias.clone())
+                                    else {
+                                        return;
+                                    };
+                                    this.push_progress("console opened in terminal", cx);
+                                    (cb)(alias, window, cx);
+                                }),
+                            )
                     });
-                row.child(btn)
+                let serial = self.serial_console.is_some();
+                row.child(
+                    div()
+                        .flex()
+                        .items_center()
+                        .gap_2()
+                        .children(open_console)
+                        .when(!serial, |d| {
+                            d.children(retry)
+                                .children(authenticate)
+                                .when(self.selected_alias.is_some() && install_key_allowed, |d| {
+                                    d.children(install_key)
+                                })
+                                .when(deploy_allowed, |d| d.children(clean_agents).child(btn))
+                        }),
+                )
             } else {
                 row
             }
@@ -463,56 +2143,245 @@ impl gpui::Render for HostPanel {
 
         // If no host selected, show invitation and recent hosts only.
         if self.selected_alias.is_none() {
+            // Start screen: fleet summary from cached snapshots, quick actions, recents.
+            let count = |health: Health| self.fleet.values().filter(|f| f.health == health).count();
+            let failed_services: usize = self.fleet.values().map(|f| f.failed_services).sum();
+            let tile = |value: usize, label: String, color: gpui::Hsla| {
+                div()
+                    .flex()
+                    .flex_col()
+                    .flex_1()
+                    .gap_1()
+                    .p(ap.px(8.0))
+                    .rounded_md()
+                    .border_1()
+                    .border_color(border)
+                    .child(
+                        div()
+                            .text_size(ap.px(20.0))
+                            .text_color(color)
+                            .child(value.to_string()),
+                    )
+                    .child(div().text_color(pal.fg_dim).child(label))
+            };
+            let tiles = div()
+                .flex()
+                .gap_2()
+                .child(tile(self.known_hosts.len(), "hosts".into(), pal.fg))
+                .child(tile(
+                    count(Health::Healthy),
+                    "healthy".into(),
+                    Health::Healthy.color(),
+                ))
+                .child(tile(
+                    count(Health::Degraded),
+                    format!("degraded ({} failed services)", failed_services),
+                    Health::Degraded.color(),
+                ))
+                .child(tile(
+                    count(Health::Stale) + count(Health::Unknown),
+                    "stale or never seen".into(),
+                    Health::Stale.color(),
+                ));
+
+            let action = |label: &'static str, action: QuickAction| {
+                div()
+                    .px(ap.px(10.0))
+                    .h(ap.px(24.0))
+                    .flex()
+                    .items_center()
+                    .rounded_sm()
+                    .border_1()
+                    .border_color(border)
+                    .cursor_pointer()
+                    .text_color(pal.fg)
+                    .child(label)
+                    .on_mouse_up(
+                        MouseButton::Left,
+                        _cx.listener(move |this: &mut Self, _ev, window, cx| {
+                            if let Some(cb) = this.on_quick_action.clone() {
+                                (cb)(action, window, cx);
+                            }
+                        }),
+                    )
+            };
+            let actions = div()
+                .flex()
+                .gap_2()
+                .child(action("Add host", QuickAction::AddHost))
+                .child(action("Open local terminal", QuickAction::OpenTerminal))
+                .child(action("Settings", QuickAction::Settings))
+                .child(
+                    div()
+                        .px(ap.px(10.0))
+                        .h(ap.px(24.0))
+                        .flex()
+                        .items_center()
+                        .rounded_sm()
+                        .border_1()
+                        .border_color(border)
+                        .cursor_pointer()
+                        .text_color(pal.fg)
+                        .child("Export fleet CSV")
+                        .on_mouse_up(
+                            MouseButton::Left,
+                            _cx.listener(|this: &mut Self, _ev, _window, cx| {
+                                this.export_csv(CsvTable::Fleet, cx);
+                            }),
+                        ),
+                );
+
             let invite = div()
+                .debug_selector(|| "host-start".into())
                 .flex()
-                .items_center()
-                .h(px(36.0))
-                .px(px(8.0))
-                .text_color(gpui::white())
-                .child("No host selected. Select a host from the left to view details.");
+                .flex_col()
+                .gap_3()
+                .p(ap.px(8.0))
+                .border_b_1()
+                .border_color(border)
+                .child(
+                    div()
+                        .text_color(pal.fg)
+                        .child("Select a host on the left to view details."),
+                )
+                .child(tiles)
+                .child(actions);
 
-            // Recent list (up to 5)
+            // Recent list: pinned first, then by recency; "Show more" lists them all.
             let recent_list = {
+                let now = SystemTime::now();
+                let shown = if self.show_all_recent {
+                    self.recent_hosts.len()
+                } else {
+                    RECENT_SHOWN
+                };
                 let mut rows = Vec::new();
-                for alias in self.recent_hosts.iter().take(5) {
-                    let a = alias.clone();
+                for entry in self.recent_hosts.ordered().take(shown) {
+                    let a = entry.alias.clone();
+                    let when = (entry.selected_at > 0).then(|| {
+                        format_age(
+                            now.duration_since(from_unix_secs(entry.selected_at))
+                                .unwrap_or_default(),
+                        )
+                    });
+                    let pin = div()
+                        .w(ap.px(16.0))
+                        .cursor_pointer()
+                        .text_color(if entry.pinned { pal.accent } else { pal.muted })
+                        .child(if entry.pinned { "★" } else { "☆" })
+                        .on_mouse_up(MouseButton::Left, {
+                            let alias2 = a.clone();
+                            _cx.listener(move |this: &mut Self, _ev, _w, cx| {
+                                // Pinning does not select the host.
+                                cx.stop_propagation();
+                                this.toggle_recent_pin(&alias2, cx);
+                            })
+                        });
                     rows.push(
                         div()
                             .flex()
                             .items_center()
                             .justify_between()
-                            .h(px(28.0))
-                            .px(px(8.0))
+                            .h(ap.px(28.0))
+                            .px(ap.px(8.0))
                             .rounded_sm()
                             .border_1()
                             .border_color(border)
                             .cursor_pointer()
-                            .text_color(gpui::opaque_grey(1.0, 0.85))
-                            .child(a.clone())
+                            .text_color(pal.fg_dim)
+                            .child(
+                                div()
+                                    .flex()
+                                    .items_center()
+                                    .gap_2()
+                                    .child(pin)
+                                    // Last known health (cached)
+                                    .child(
+                                        div()
+                                            .size(ap.px(8.0))
+                                            .rounded_full()
+                                            .bg(self
+                                                .fleet
+                                                .get(&a)
+                                                .unwrap_or(&HostHealth::UNKNOWN)
+                                                .health
+                                                .color()),
+                                    )
+                                    .child(a.clone())
+                                    // Pending updates at the last check (cached)
+                                    .children(
+                                        self.fleet
+                                            .get(&a)
+                                            .and_then(|h| h.pending_updates)
+                                            .filter(|&n| n > 0)
+                                            .map(|n| {
+                                                div()
+                                                    .text_color(pal.accent)
+                                                    .child(format!("↑{}", n))
+                                            }),
+                                    )
+                                    // Reboot pending at the last check (cached)
+                                    .when(
+                                        self.fleet.get(&a).is_some_and(|h| h.reboot_required),
+                                        |d| {
+                                            d.child(
+                                                div()
+                                                    .text_color(gpui::hsla(0.13, 0.8, 0.6, 1.0))
+                                                    .child("reboot"),
+                                            )
+                                        },
+                                    ),
+                            )
+                            .children(when.map(|w| div().text_color(pal.muted).child(w)))
                             .on_mouse_up(MouseButton::Left, {
                                 let alias2 = a.clone();
                                 _cx.listener(move |this: &mut Self, _ev: &gpui::MouseUpEvent, w: &mut Window, cx: &mut Context<HostPanel>| {
-                                    if let Some(cb) = this.on_select_recent.as_ref() {
-                                        (cb)(alias2.clone(), w, cx);
-                                    } else {
-                                        // Fallback: update selection locally and persist recents.
-                                        this.set_selected_host(Some(alias2.clone()), cx);
-                                    }
+                                    this.select_host(alias2.clone(), w, cx);
                                 })
                             }),
                     );
                 }
+                let more = (self.recent_hosts.len() > RECENT_SHOWN).then(|| {
+                    div()
+                        .cursor_pointer()
+                        .text_color(pal.accent)
+                        .child(if self.show_all_recent {
+                            "Show less".to_string()
+                        } else {
+                            format!("Show more ({})", self.recent_hosts.len() - RECENT_SHOWN)
+                        })
+                        .on_mouse_up(
+                            MouseButton::Left,
+                            _cx.listener(|this: &mut Self, _ev, _w, cx| {
+                                this.show_all_recent = !this.show_all_recent;
+                                cx.notify();
+                            }),
+                        )
+                });
                 div()
+                    .debug_selector(|| "host-recent".into())
                     .flex()
                     .flex_col()
+                    .flex_1()
+                    .min_h_0()
                     .gap_2()
-                    .pl(px(8.0))
-                    .pr(px(8.0))
-                    .py(px(8.0))
+                    .pl(ap.px(8.0))
+                    .pr(ap.px(8.0))
+                    .py(ap.px(8.0))
                     .border_b_1()
                     .border_color(border)
-                    .child(div().text_color(gpui::white()).child("Recent"))
-                    .child(div().flex().flex_col().gap_2().children(rows))
+                    .child(div().text_color(pal.fg).child("Recent"))
+                    .child(
+                        div()
+                            .id("RecentScroll")
+                            .flex()
+                            .flex_col()
+                            .min_h_0()
+                            .gap_2()
+                            .overflow_y_scroll()
+                            .children(rows)
+                            .children(more),
+                    )
             };
 
             return div()
@@ -529,308 +2398,126 @@ impl gpui::Render for HostPanel {
 
         // Default (host selected): keep existing layout for now.
         // Minimal identity section while selected: show SysInfo when available.
-        let identity = self.render_section(
-            "Identity",
-            match (self.selected_alias.as_ref(), self.sys_info.as_ref()) {
-                (Some(a), Some(info)) => {
-                    format!(
-                        "alias: {}\nhostname: {}\nos: {}\nkernel: {}\narch: {}\nuptime: {}s",
-                        a,
-                        info.hostname,
-                        info.os,
-                        info.kernel,
-                        info.arch,
-                        info.uptime_secs
-                    )
-                }
-                (Some(a), None) => {
-                    let mut s = format!(
-                        "alias: {}\nhostname: (pending)\nos: (pending)\nkernel: (pending)\narch: (pending)\nuptime: (pending)",
-                        a
-                    );
-                    if let Some(p) = &self.last_progress {
-                        s.push_str(&format!("\nstatus: {}", p));
-                    }
-                    s
-                }
-                (None, _) => "No host selected.".into(),
-            },
-            8.0,
-        );
-
-        // Services filter controls and list (scrollable area handles overflow)
-        let services_brief = if let Some(list) = &self.services {
-            // Filter buttons
-            let mk_filter_btn = |_label: &str, active: bool| {
+        let clock = self
+            .shown_sys_info()
+            .and_then(|info| info.time_sync.as_ref())
+            .and_then(clock_warning);
+        let reboot = self.shown_sys_info().and_then(|info| {
+            let reboot = info.reboot.as_ref().filter(|r| r.required())?;
+            Some(format!(
+                "Reboot required: {}",
+                reboot_text(reboot, &info.kernel)
+            ))
+        });
+        let identity = self
+            .render_section(Section::SysInfo, "Identity", self.identity_text(), 8.0, _cx)
+            .debug_selector(|| "host-identity".into())
+            .children(clock.map(|warning| {
                 div()
-                    .px(px(6.0))
-                    .py(px(2.0))
-                    .rounded_sm()
-                    .border_1()
-                    .border_color(border)
-                    .text_color(if active {
-                        gpui::white()
-                    } else {
-                        gpui::opaque_grey(1.0, 0.8)
-                    })
-                    .bg(if active {
-                        gpui::opaque_grey(0.2, 0.3)
-                    } else {
-                        gpui::hsla(0.0, 0.0, 0.07, 1.0)
-                    })
-            };
-
-            let filter_bar = div()
-                .flex()
-                .items_center()
-                .gap_2()
-                .px(px(8.0))
-                .py(px(6.0))
-                .child(
-                    mk_filter_btn("All", matches!(self.service_filter, ServiceFilter::All))
-                        .cursor_pointer()
-                        .on_mouse_up(MouseButton::Left, {
-                            _cx.listener(|this: &mut Self, _ev, _w, cx| {
-                                this.service_filter = ServiceFilter::All;
-                                cx.notify();
-                            })
-                        })
-                        .child("All"),
-                )
-                .child(
-                    mk_filter_btn(
-                        "Active",
-                        matches!(self.service_filter, ServiceFilter::Active),
-                    )
-                    .cursor_pointer()
-                    .on_mouse_up(MouseButton::Left, {
-                        _cx.listener(|this: &mut Self, _ev, _w, cx| {
-                            this.service_filter = ServiceFilter::Active;
-                            cx.notify();
-                        })
-                    })
-                    .child("Active"),
-                )
-                .child(
-                    mk_filter_btn(
-                        "Failed",
-                        matches!(self.service_filter, ServiceFilter::Failed),
-                    )
-                    .cursor_pointer()
-                    .on_mouse_up(MouseButton::Left, {
-                        _cx.listener(|this: &mut Self, _ev, _w, cx| {
-                            this.service_filter = ServiceFilter::Failed;
-                            cx.notify();
-                        })
-                    })
-                    .child("Failed"),
-                )
-                .child(
-                    mk_filter_btn(
-                        "Inactive",
-                        matches!(self.service_filter, ServiceFilter::Inactive),
-                    )
-                    .cursor_pointer()
-                    .on_mouse_up(MouseButton::Left, {
-                        _cx.listener(|this: &mut Self, _ev, _w, cx| {
-                            this.service_filter = ServiceFilter::Inactive;
-                            cx.notify();
-                        })
-                    })
-                    .child("Inactive"),
-                )
-                .child(
-                    div()
-                        .px(px(8.0))
-                        .py(px(2.0))
-                        .rounded_sm()
-                        .border_1()
-                        .border_color(border)
-                        .cursor_pointer()
-                        .text_color(gpui::white())
-                        .on_mouse_up(MouseButton::Left, {
-                            _cx.listener(|this: &mut Self, _ev, _w, cx| {
-                                this.enabled_only = !this.enabled_only;
-                                let _ = Self::save_service_filter_prefs(
-                                    this.enabled_only,
-                                    this.include_baseline,
-                                );
-                                cx.notify();
-                            })
-                        })
-                        .child(if self.enabled_only {
-                            "Enabled only: on"
-                        } else {
-                            "Enabled only: off"
-                        }),
-                )
-                .child(
+                    .text_color(gpui::hsla(0.13, 0.8, 0.6, 1.0))
+                    .child(warning)
+            }))
+            .children(reboot.map(|reason| {
+                div()
+                    .text_color(gpui::hsla(0.13, 0.8, 0.6, 1.0))
+                    .child(reason)
+            }))
+            // Self-limits the agent was asked for but could not apply.
+            .children(self.agent_stats.iter().flat_map(|stats| {
+                stats.limits.notes.iter().map(|note| {
                     div()
-                        .px(px(8.0))
-                        .py(px(2.0))
-                        .rounded_sm()
-                        .border_1()
-                        .border_color(border)
-                        .cursor_pointer()
-                        .text_color(gpui::white())
-                        .on_mouse_up(MouseButton::Left, {
-                            _cx.listener(|this: &mut Self, _ev, _w, cx| {
-                                this.include_baseline = !this.include_baseline;
-                                let _ = Self::save_service_filter_prefs(
-                                    this.enabled_only,
-                                    this.include_baseline,
-                                );
-                                cx.notify();
-                            })
-                        })
-                        .child(if self.include_baseline {
-                            "Include baseline: on"
-                        } else {
-                            "Include baseline: off"
-                        }),
-                );
-
-            // Apply filters
-            let filtered: Vec<&proto::ServiceInfo> = list
-                .iter()
-                // Enabled checkbox semantics:
-                // - when enabled_only == true: include only explicitly enabled (enabled == Some(true))
-                // - when enabled_only == false: include all services (no enabled filter)
-                .filter(|s| {
-                    if self.enabled_only {
-                        s.enabled == Some(true)
-                    } else {
-                        true
-                    }
-                })
-                // Baseline checkbox semantics:
-                // - when include_baseline == true: include even if baseline
-                // - when include_baseline == false: exclude if baseline
-                .filter(|s| self.include_baseline || !self.is_baseline(&s.name))
-                // State filter (composes with the above)
-                .filter(|s| match self.service_filter {
-                    ServiceFilter::All => true,
-                    ServiceFilter::Active => s.active_state == "active",
-                    ServiceFilter::Failed => s.active_state == "failed",
-                    ServiceFilter::Inactive => s.active_state == "inactive",
+                        .text_color(gpui::hsla(0.13, 0.8, 0.6, 1.0))
+                        .child(format!("Agent: {}", note))
                 })
-                .collect();
-
-            // Stats
-            let _total = filtered.len();
-            let _active_cnt = filtered
-                .iter()
-                .filter(|s| s.active_state == "active")
-                .count();
-            let _failed_cnt = filtered
-                .iter()
-                .filter(|s| s.active_state == "failed")
-                .count();
-
-            // Render rows
-            let mut rows = Vec::new();
-            for s in filtered {
-                // Colorize by active state
-                let color = if s.active_state == "active" {
-                    gpui::green()
-                } else if s.active_state == "failed" {
-                    gpui::hsla(0.0, 0.8, 0.6, 1.0) // red-ish
-                } else if s.active_state == "activating" || s.active_state == "deactivating" {
-                    gpui::hsla(0.13, 0.8, 0.6, 1.0) // orange-ish
-                } else {
-                    gpui::opaque_grey(1.0, 0.85)
-                };
+            }));
 
-                let mut line = format!("{} — {} {}", s.name, s.active_state, s.sub_state);
-                if s.enabled == Some(false) {
-                    line.push_str(" [disabled]");
-                } else if s.enabled == Some(true) {
-                    line.push_str(" [enabled]");
-                }
+        let sessions = self.render_sessions(_cx);
+        let hardware = self.render_hardware(_cx);
+        let inventory = self.render_inventory(_cx);
+        let storage = self.render_storage(_cx);
+        let pressure = self.render_pressure(_cx);
+        let kernel = self.render_kernel(_cx);
+        let backups = self.render_backups(_cx);
+        let updates = self.render_updates(_cx);
+        let connectivity = self.render_connectivity(_cx);
+        let listeners = self.render_listeners(_cx);
+        let security = self.render_security(_cx);
+        let access = self.render_access(_cx);
+        let processes = self.render_processes(_cx);
+        let cgroups = self.render_cgroups(_cx);
 
-                let enabled_str = match s.enabled {
-                    Some(true) => "enabled",
-                    Some(false) => "disabled",
-                    None => "n/a",
-                };
-                rows.push(
-                    div()
-                        .flex()
-                        .items_center()
-                        .h(px(20.0))
-                        .px(px(8.0))
-                        .justify_between()
-                        // name (left, flexible)
-                        .child(
-                            div()
-                                .text_color(if s.enabled == Some(false) {
-                                    gpui::opaque_grey(1.0, 0.6)
-                                } else {
-                                    gpui::white()
-                                })
-                                .child(s.name.clone()),
-                        )
-                        // fixed-width right container for aligned columns
-                        .child(
-                            div()
-                                .flex()
-                                .w(px(220.0))
-                                .justify_between()
-                                // state column (fixed width, colored)
-                                .child(
-                                    div()
-                                        .w(px(120.0))
-                                        .text_color(color)
-                                        .child(s.active_state.clone()),
-                                )
-                                // enabled column (fixed width, dim if disabled)
-                                .child(
-                                    div()
-                                        .w(px(100.0))
-                                        .text_color(if s.enabled == Some(false) {
-                                            gpui::opaque_grey(1.0, 0.6)
-                                        } else {
-                                            gpui::opaque_grey(1.0, 0.85)
-                                        })
-                                        .child(enabled_str),
-                                ),
-                        ),
-                );
-            }
+        let services_header = div()
+            .flex()
+            .items_center()
+            .justify_between()
+            .child(div().text_color(pal.fg).child("Services"))
+            .child(self.render_section_controls(Section::Services, _cx));
 
-            // Compose services section
-            div()
-                .flex()
-                .flex_col()
-                .gap_2()
-                .pl(px(8.0))
-                .pr(px(8.0))
-                .py(px(8.0))
-                .border_b_1()
-                .border_color(border)
-                .child(filter_bar)
-                .child(div().flex().flex_col().gap_1().children(rows))
-        } else {
-            div()
-        };
+        // Services: header here, list in its own cached entity (repaints only when it notifies)
+        let services = div()
+            .debug_selector(|| "host-services".into())
+            .flex()
+            .flex_col()
+            .flex_1()
+            .min_h_0()
+            .gap_2()
+            .pl(ap.px(8.0))
+            .pr(ap.px(8.0))
+            .py(ap.px(8.0))
+            .when(self.is_dimmed(Section::Services), |d| d.opacity(0.5))
+            .child(services_header)
+            .child(
+                AnyView::from(self.services_list.clone())
+                    .cached(StyleRefinement::default().flex_1().min_h_0()),
+            );
 
+        // Outlined while focused so keyboard users can see where input goes.
         div()
+            .track_focus(&self.focus)
+            .on_mouse_down(
+                MouseButton::Left,
+                _cx.listener(|this: &mut Self, _ev, window, _cx| window.focus(&this.focus)),
+            )
             .flex()
             .flex_col()
             .size_full()
             .bg(bg)
+            .border_1()
+            .border_color(if self.focus.is_focused(window) {
+                pal.focus_ring
+            } else {
+                gpui::transparent_black()
+            })
             .text_color(fg_dim)
             .child(header)
             .child(status_banner)
+            .children(self.render_action_preview(_cx))
+            .children(self.render_handoff(_cx))
+            .children(self.render_process_drawer(_cx))
+            .children(self.render_service_drawer(_cx))
+            .children(self.render_file_drawer(_cx))
             .child(
                 div()
-                    .id("HostPanelScroll")
                     .flex()
                     .flex_col()
                     .size_full()
-                    .overflow_y_scroll()
+                    .min_h_0()
                     .child(identity)
-                    .child(services_brief),
+                    .child(sessions)
+                    .child(hardware)
+                    .child(inventory)
+                    .child(storage)
+                    .child(pressure)
+                    .child(kernel)
+                    .child(backups)
+                    .child(updates)
+                    .child(connectivity)
+                    .child(listeners)
+                    .child(security)
+                    .child(access)
+                    .child(processes)
+                    .child(cgroups)
+                    .child(services),
             )
     }
 }
@@ -0,0 +1,124 @@
+//! Listeners section: the host's open ports, listening TCP and UDP sockets
+//! with the process behind each, so an unexpected service stands out.
+//! Loopback-only ports are muted.
+
+use crate::{csv, HostPanel, Section};
+use gpui::{div, prelude::*, Context};
+use slarti_proto as proto;
+use slarti_ui::Appearance;
+use std::time::{Instant, SystemTime};
+
+/// Whether only the host itself can connect.
+fn loopback_only(listener: &proto::NetListener) -> bool {
+    listener.address.starts_with("127.") || listener.address == "::1"
+}
+
+/// "0.0.0.0:22", "[::]:443"
+fn local_text(listener: &proto::NetListener) -> String {
+    if listener.address.contains(':') {
+        format!("[{}]:{}", listener.address, listener.port)
+    } else {
+        format!("{}:{}", listener.address, listener.port)
+    }
+}
+
+/// "tcp 0.0.0.0:22 sshd (pid 812)", or "… (process not visible)" when the
+/// socket belongs to another user and the agent is not root.
+pub(crate) fn listener_text(listener: &proto::NetListener) -> String {
+    let owner = match (&listener.process, listener.pid) {
+        (Some(process), Some(pid)) => format!("{} (pid {})", process, pid),
+        (Some(process), None) => process.clone(),
+        (None, Some(pid)) => format!("pid {}", pid),
+        (None, None) => "(process not visible)".to_string(),
+    };
+    format!("{} {} {}", listener.protocol, local_text(listener), owner)
+}
+
+/// Plain-text open ports, for Copy.
+pub(crate) fn listeners_text(listeners: &[proto::NetListener]) -> String {
+    if listeners.is_empty() {
+        return "No listening sockets.\n".to_string();
+    }
+    let mut out = String::new();
+    for listener in listeners {
+        out.push_str(&format!("{}\n", listener_text(listener)));
+    }
+    out
+}
+
+/// Open ports as CSV, in the order shown.
+pub(crate) fn listeners_csv(listeners: &[proto::NetListener]) -> String {
+    let mut out = String::new();
+    csv::push_record(&mut out, ["protocol", "address", "port", "pid", "process"]);
+    for l in listeners {
+        csv::push_record(
+            &mut out,
+            [
+                l.protocol.clone(),
+                l.address.clone(),
+                l.port.to_string(),
+                l.pid.map(|pid| pid.to_string()).unwrap_or_default(),
+                l.process.clone().unwrap_or_default(),
+            ],
+        );
+    }
+    out
+}
+
+impl HostPanel {
+    /// Update the open ports shown in the panel.
+    pub fn set_listeners(&mut self, listeners: Vec<proto::NetListener>, cx: &mut Context<Self>) {
+        self.listeners = Some(listeners);
+        self.freshness.mark(Section::Listeners, SystemTime::now());
+        if let Some(sched) = self.scheduler_mut() {
+            sched.mark_fetched(Section::Listeners, Instant::now());
+        }
+        cx.notify();
+    }
+
+    /// Listeners section: header with controls, then one row per open port.
+    pub(crate) fn render_listeners(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let section = div()
+            .flex()
+            .flex_col()
+            .gap_2()
+            .pl(ap.px(8.0))
+            .pr(ap.px(8.0))
+            .py(ap.px(8.0))
+            .border_b_1()
+            .border_color(pal.border)
+            .when(self.is_dimmed(Section::Listeners), |d| d.opacity(0.5))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .child(div().text_color(pal.fg).child("Open ports"))
+                    .child(self.render_section_controls(Section::Listeners, cx)),
+            );
+        let Some(listeners) = &self.listeners else {
+            return section.child("Not loaded yet: press ⟳.");
+        };
+        if listeners.is_empty() {
+            return section.child(div().text_color(pal.muted).child("No listening sockets."));
+        }
+
+        section.child(
+            div()
+                .flex()
+                .flex_col()
+                .children(listeners.iter().map(|listener| {
+                    div()
+                        .pl(ap.px(8.0))
+                        .text_color(if loopback_only(listener) {
+                            pal.muted
+                        } else {
+                            pal.fg_dim
+                        })
+                        .child(listener_text(listener))
+                })),
+        )
+    }
+}
@@ -0,0 +1,145 @@
+//! Installed packages, under the Updates section: fetched on request a page
+//! at a time, since a host can have thousands.
+
+use crate::{AgentRequest, HostPanel};
+use gpui::{div, prelude::*, Context, MouseButton};
+use slarti_proto as proto;
+use slarti_ui::Appearance;
+
+/// Packages asked for per request.
+pub(crate) const PAGE: usize = 500;
+
+/// "bash 5.2.15-2+b8 (amd64)"
+fn package_text(p: &proto::Package) -> String {
+    match &p.arch {
+        Some(arch) => format!("{} {} ({})", p.name, p.version, arch),
+        None => format!("{} {}", p.name, p.version),
+    }
+}
+
+impl HostPanel {
+    /// Fetch the installed packages from the first page.
+    fn load_packages(&mut self, cx: &mut Context<Self>) {
+        self.packages = None;
+        self.packages_pending = true;
+        self.agent_requests
+            .push(AgentRequest::Packages { offset: 0 });
+        cx.notify();
+    }
+
+    /// Fetch the page after the ones shown.
+    fn load_more_packages(&mut self, cx: &mut Context<Self>) {
+        let Some(list) = &self.packages else {
+            return;
+        };
+        if self.packages_pending {
+            return;
+        }
+        let offset = list.packages.len();
+        self.packages_pending = true;
+        self.agent_requests.push(AgentRequest::Packages { offset });
+        cx.notify();
+    }
+
+    /// Append a page of packages (a first page starts over).
+    pub(crate) fn apply_packages_response(
+        &mut self,
+        offset: usize,
+        response: proto::Response,
+        cx: &mut Context<Self>,
+    ) {
+        self.packages_pending = false;
+        match response {
+            proto::Response::PackagesOk { packages: page, .. } => {
+                if offset == 0 {
+                    self.packages = Some(page);
+                } else if let Some(list) = self
+                    .packages
+                    .as_mut()
+                    .filter(|list| list.packages.len() == offset)
+                {
+                    list.total = page.total;
+                    list.packages.extend(page.packages);
+                }
+                // Otherwise the page is for a list reloaded meanwhile.
+            }
+            proto::Response::Error { message, .. } => {
+                self.push_progress(format!("packages: {}", message), cx)
+            }
+            _ => {}
+        }
+    }
+
+    /// The installed packages block: a button until loaded, then the count,
+    /// the list, and a button for the next page while there is one.
+    pub(crate) fn render_packages(&self, cx: &mut Context<Self>) -> gpui::Div {
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let button = |label: String| {
+            div()
+                .px(ap.px(6.0))
+                .rounded_sm()
+                .border_1()
+                .border_color(pal.border)
+                .cursor_pointer()
+                .text_color(pal.fg)
+                .child(label)
+        };
+        let block = div().flex().flex_col().gap_1();
+        let Some(list) = &self.packages else {
+            if self.packages_pending {
+                return block.child(
+                    div()
+                        .text_color(pal.muted)
+                        .child("Loading installed packages…"),
+                );
+            }
+            return block.child(div().flex().child(
+                button("Show installed packages".to_string()).on_mouse_up(
+                    MouseButton::Left,
+                    cx.listener(|this: &mut Self, _ev, _w, cx| this.load_packages(cx)),
+                ),
+            ));
+        };
+        let Some(manager) = list.manager else {
+            return block.child(
+                div()
+                    .text_color(pal.muted)
+                    .child("No supported package database found."),
+            );
+        };
+
+        let manager = format!("{:?}", manager).to_lowercase();
+        let shown = list.packages.len();
+        block
+            .child(
+                div()
+                    .text_color(pal.fg_dim)
+                    .child(format!("{} installed ({})", list.total, manager)),
+            )
+            .child(
+                div()
+                    .id("PackagesScroll")
+                    .flex()
+                    .flex_col()
+                    .max_h(ap.px(200.0))
+                    .overflow_y_scroll()
+                    .children(list.packages.iter().map(|p| {
+                        div()
+                            .pl(ap.px(8.0))
+                            .text_color(pal.fg_dim)
+                            .child(package_text(p))
+                    })),
+            )
+            .when(shown < list.total, |d| {
+                d.child(div().flex().child(if self.packages_pending {
+                    button(format!("Loading… ({} of {})", shown, list.total))
+                } else {
+                    button(format!("More ({} of {})", shown, list.total)).on_mouse_up(
+                        MouseButton::Left,
+                        cx.listener(|this: &mut Self, _ev, _w, cx| this.load_more_packages(cx)),
+                    )
+                }))
+            })
+    }
+}
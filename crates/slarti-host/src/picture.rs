@@ -0,0 +1,134 @@
+//! Section pictures: a section's copy text drawn as a PNG in the panel's
+//! colours, so it can be pasted into chat as an image during an incident.
+//!
+//! gpui cannot render an element subtree offscreen, so the picture is laid
+//! out as an SVG of monospace lines (which keeps tables and sparklines
+//! aligned) and rasterised with resvg.
+
+use anyhow::Context as _;
+use gpui::{Hsla, Rgba};
+use resvg::{tiny_skia, usvg};
+
+/// Font size of a line, in SVG units.
+const FONT_SIZE: f32 = 13.0;
+/// Distance between baselines.
+const LINE_HEIGHT: f32 = 18.0;
+/// Advance of one monospace cell (0.6em covers the common monospace fonts).
+const CELL_WIDTH: f32 = FONT_SIZE * 0.6;
+/// Margin around the text.
+const PADDING: f32 = 12.0;
+/// Pixels per SVG unit, so the picture stays sharp on HiDPI screens.
+const SCALE: f32 = 2.0;
+
+/// Colours for a picture, from the panel's palette.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Colors {
+    pub fg: Hsla,
+    pub bg: Hsla,
+}
+
+/// SVG of `text`, one `<text>` element per line.
+pub(crate) fn section_svg(text: &str, fg: &str, bg: &str) -> String {
+    let lines: Vec<&str> = text.trim_end().lines().collect();
+    let cols = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let width = (PADDING * 2.0 + cols as f32 * CELL_WIDTH).ceil();
+    let height = (PADDING * 2.0 + lines.len().max(1) as f32 * LINE_HEIGHT).ceil();
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+         viewBox=\"0 0 {w} {h}\">\n\
+         <rect width=\"{w}\" height=\"{h}\" fill=\"{bg}\"/>\n\
+         <g font-family=\"DejaVu Sans Mono, Menlo, Consolas, monospace\" \
+         font-size=\"{size}\" fill=\"{fg}\" xml:space=\"preserve\">\n",
+        w = width,
+        h = height,
+        size = FONT_SIZE,
+    );
+    for (i, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        // Baseline sits about 3/4 of the way down the line box.
+        let y = PADDING + i as f32 * LINE_HEIGHT + LINE_HEIGHT * 0.75;
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\">{}</text>\n",
+            PADDING,
+            y,
+            escape(line)
+        ));
+    }
+    svg.push_str("</g>\n</svg>\n");
+    svg
+}
+
+/// Rasterise `text` as a PNG in `colors`. Loads the system fonts, so call it
+/// off the UI thread.
+pub(crate) fn section_png(text: &str, colors: Colors) -> anyhow::Result<Vec<u8>> {
+    let svg = section_svg(text, &hex(colors.fg), &hex(colors.bg));
+    let mut options = usvg::Options::default();
+    options.fontdb_mut().load_system_fonts();
+    let tree = usvg::Tree::from_str(&svg, &options).context("laying out the picture")?;
+    let size = tree.size().to_int_size();
+    let width = (size.width() as f32 * SCALE).ceil() as u32;
+    let height = (size.height() as f32 * SCALE).ceil() as u32;
+    let mut pixmap =
+        tiny_skia::Pixmap::new(width, height).context("picture has no area to draw")?;
+    resvg::render(
+        &tree,
+        tiny_skia::Transform::from_scale(SCALE, SCALE),
+        &mut pixmap.as_mut(),
+    );
+    pixmap.encode_png().context("encoding the picture")
+}
+
+/// "#rrggbb" for an opaque colour.
+fn hex(color: Hsla) -> String {
+    let rgba = Rgba::from(color);
+    let byte = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        byte(rgba.r),
+        byte(rgba.g),
+        byte(rgba.b)
+    )
+}
+
+fn escape(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    for c in line.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c if c.is_control() && c != '\t' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picture_is_sized_to_the_longest_line() {
+        let svg = section_svg(
+            "web-1 · services\nnginx.service  failed\n",
+            "#ffffff",
+            "#0b0b0b",
+        );
+        // 21 cells wide, two lines tall, plus padding on each side.
+        let width = (PADDING * 2.0 + 21.0 * CELL_WIDTH).ceil();
+        let height = (PADDING * 2.0 + 2.0 * LINE_HEIGHT).ceil();
+        assert!(svg.contains(&format!("width=\"{}\" height=\"{}\"", width, height)));
+        assert_eq!(svg.matches("<text ").count(), 2);
+        assert!(svg.contains("fill=\"#0b0b0b\""));
+    }
+
+    #[test]
+    fn markup_in_the_text_is_escaped() {
+        let svg = section_svg("a <b> & \"c\"", "#ffffff", "#000000");
+        assert!(svg.contains(">a &lt;b&gt; &amp; &quot;c&quot;</text>"));
+    }
+}
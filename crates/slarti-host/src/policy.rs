@@ -0,0 +1,238 @@
+//! Action permissions: a policy file that disables categories of mutating
+//! actions, globally or for hosts carrying a tag.
+//!
+//! ```json
+//! {
+//!   "deny": ["exec"],
+//!   "tags": {
+//!     "prod": { "deny": ["service_control", "file_edit", "deploy"] },
+//!     "shared": { "read_only": true }
+//!   }
+//! }
+//! ```
+//!
+//! `"read_only": true` denies every category (an "observer" install). Host
+//! tags come from `Tag` in the host's ssh config entry.
+
+use anyhow::{Context as _, Result};
+use gpui::{App, Global};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Categories of mutating actions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionCategory {
+    /// Start/stop/restart services.
+    ServiceControl,
+    /// Write or edit files on the host.
+    FileEdit,
+    /// Run arbitrary commands.
+    Exec,
+    /// Upload or update the agent.
+    Deploy,
+    /// Install the user's public key.
+    KeyInstall,
+    /// Signal (kill) or renice processes.
+    ProcessControl,
+}
+
+impl ActionCategory {
+    pub fn label(self) -> &'static str {
+        match self {
+            ActionCategory::ServiceControl => "service control",
+            ActionCategory::FileEdit => "file edits",
+            ActionCategory::Exec => "exec",
+            ActionCategory::Deploy => "deploy",
+            ActionCategory::KeyInstall => "key install",
+            ActionCategory::ProcessControl => "process control",
+        }
+    }
+
+    /// Whether the agent carries the action out (and so refuses it when
+    /// read-only); deploys and key installs go over plain ssh.
+    pub fn needs_agent(self) -> bool {
+        matches!(
+            self,
+            ActionCategory::ServiceControl
+                | ActionCategory::FileEdit
+                | ActionCategory::Exec
+                | ActionCategory::ProcessControl
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+struct Rule {
+    read_only: bool,
+    deny: Vec<ActionCategory>,
+}
+
+impl Rule {
+    fn denies(&self, category: ActionCategory) -> bool {
+        self.read_only || self.deny.contains(&category)
+    }
+}
+
+/// Loaded action policy (default: everything allowed).
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ActionPolicy {
+    #[serde(flatten)]
+    global: Rule,
+    tags: HashMap<String, Rule>,
+}
+
+impl Global for ActionPolicy {}
+
+impl ActionPolicy {
+    /// A policy that denies everything.
+    pub fn read_only() -> Self {
+        ActionPolicy {
+            global: Rule {
+                read_only: true,
+                deny: Vec::new(),
+            },
+            tags: HashMap::new(),
+        }
+    }
+
+    /// Read the policy at `path` (a missing file allows everything).
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text).with_context(|| format!("parse {:?}", path)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("read {:?}", path)),
+        }
+    }
+
+    /// Combine with `other`: an action is denied if either policy denies it.
+    pub fn merge(mut self, other: ActionPolicy) -> Self {
+        self.global.read_only |= other.global.read_only;
+        self.global.deny.extend(other.global.deny);
+        for (tag, rule) in other.tags {
+            let mine = self.tags.entry(tag).or_default();
+            mine.read_only |= rule.read_only;
+            mine.deny.extend(rule.deny);
+        }
+        self
+    }
+
+    /// Why `category` is denied on a host with `tags`, or None if it is allowed.
+    pub fn denial(&self, category: ActionCategory, tags: &[String]) -> Option<String> {
+        if self.global.denies(category) {
+            return Some(format!("{} disabled by policy", category.label()));
+        }
+        tags.iter()
+            .find(|t| self.tags.get(*t).is_some_and(|r| r.denies(category)))
+            .map(|t| format!("{} disabled by policy for tag {}", category.label(), t))
+    }
+
+    /// `denial` against the app's installed policy (none installed: allowed).
+    pub fn check(cx: &App, category: ActionCategory, tags: &[String]) -> Option<String> {
+        cx.try_global::<ActionPolicy>()
+            .and_then(|p| p.denial(category, tags))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn needs_agent_covers_every_category() {
+        let all = [
+            ActionCategory::ServiceControl,
+            ActionCategory::FileEdit,
+            ActionCategory::Exec,
+            ActionCategory::Deploy,
+            ActionCategory::KeyInstall,
+            ActionCategory::ProcessControl,
+        ];
+        for category in all {
+            // No wildcard: a new category does not compile here until it is
+            // classified (and listed above).
+            let via_agent = match category {
+                ActionCategory::ServiceControl
+                | ActionCategory::FileEdit
+                | ActionCategory::Exec
+                | ActionCategory::ProcessControl => true,
+                ActionCategory::Deploy | ActionCategory::KeyInstall => false,
+            };
+            assert_eq!(category.needs_agent(), via_agent, "{}", category.label());
+        }
+    }
+
+    fn policy(json: &str) -> ActionPolicy {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn tags(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn a_tag_denies_what_the_global_rule_allows() {
+        let p = policy(r#"{"tags": {"prod": {"deny": ["exec"]}}}"#);
+        assert_eq!(p.denial(ActionCategory::Exec, &tags(&[])), None);
+        assert_eq!(p.denial(ActionCategory::Exec, &tags(&["staging"])), None);
+        assert_eq!(
+            p.denial(ActionCategory::Exec, &tags(&["web", "prod"]))
+                .as_deref(),
+            Some("exec disabled by policy for tag prod")
+        );
+        assert_eq!(p.denial(ActionCategory::Deploy, &tags(&["prod"])), None);
+    }
+
+    #[test]
+    fn global_denials_win_over_tags() {
+        let p = policy(r#"{"deny": ["deploy"], "tags": {"prod": {"read_only": true}}}"#);
+        assert_eq!(
+            p.denial(ActionCategory::Deploy, &tags(&["prod"]))
+                .as_deref(),
+            Some("deploy disabled by policy")
+        );
+        // A read-only tag denies every category, listed or not.
+        assert_eq!(
+            p.denial(ActionCategory::KeyInstall, &tags(&["prod"]))
+                .as_deref(),
+            Some("key install disabled by policy for tag prod")
+        );
+        assert_eq!(p.denial(ActionCategory::KeyInstall, &tags(&[])), None);
+    }
+
+    #[test]
+    fn merge_denies_what_either_policy_denies() {
+        let base = policy(r#"{"deny": ["exec"], "tags": {"prod": {"deny": ["deploy"]}}}"#);
+        let extra = policy(
+            r#"{"deny": ["file_edit"], "tags": {"prod": {"deny": ["service_control"]},
+                "shared": {"read_only": true}}}"#,
+        );
+        let merged = base.merge(extra);
+        for category in [ActionCategory::Exec, ActionCategory::FileEdit] {
+            assert!(merged.denial(category, &tags(&[])).is_some());
+        }
+        for category in [ActionCategory::Deploy, ActionCategory::ServiceControl] {
+            assert!(merged.denial(category, &tags(&[])).is_none());
+            assert!(merged.denial(category, &tags(&["prod"])).is_some());
+        }
+        assert!(merged
+            .denial(ActionCategory::ProcessControl, &tags(&["shared"]))
+            .is_some());
+        assert!(merged
+            .denial(ActionCategory::ProcessControl, &tags(&["prod"]))
+            .is_none());
+    }
+
+    #[test]
+    fn read_only_denies_everything() {
+        let p = ActionPolicy::default().merge(ActionPolicy::read_only());
+        assert_eq!(
+            p.denial(ActionCategory::ProcessControl, &tags(&[]))
+                .as_deref(),
+            Some("process control disabled by policy")
+        );
+    }
+}
@@ -0,0 +1,185 @@
+//! Per-host polling scheduler for HostPanel sections.
+//!
+//! The scheduler only decides *when* a section is due; the app's background
+//! session loop asks for due sections and performs the actual agent requests.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// A HostPanel section backed by an agent request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Section {
+    /// Identity (SysInfo: hostname, kernel, uptime, load)
+    SysInfo,
+    /// Services list (systemd)
+    Services,
+    /// Busiest processes by CPU and memory
+    Processes,
+    /// Cgroup hierarchy with CPU and memory use
+    Cgroups,
+    /// Pressure stall information and OOM kills
+    Pressure,
+    /// CPU, memory and CPU topology (StaticConfig), GPUs, temperatures and fans
+    Hardware,
+    /// DMI identity, PCI devices and block devices
+    Inventory,
+    /// Software RAID (md) arrays, ZFS pools, btrfs filesystems, LVM and
+    /// filesystem space
+    Storage,
+    /// Backup jobs and the age of their last successful run
+    Backups,
+    /// Pending package updates
+    Updates,
+    /// Users logged in to the host
+    Sessions,
+    /// Outbound reachability of the user's endpoints
+    Connectivity,
+    /// Listening TCP and UDP sockets and their processes
+    Listeners,
+    /// sshd settings audited for risky values
+    Security,
+    /// Login accounts, admin groups and sudo rules
+    Access,
+    /// Loaded kernel modules and watched kernel parameters
+    Kernel,
+}
+
+impl Section {
+    pub const ALL: [Section; 16] = [
+        Section::SysInfo,
+        Section::Sessions,
+        Section::Hardware,
+        Section::Inventory,
+        Section::Storage,
+        Section::Pressure,
+        Section::Kernel,
+        Section::Backups,
+        Section::Updates,
+        Section::Connectivity,
+        Section::Listeners,
+        Section::Security,
+        Section::Access,
+        Section::Services,
+        Section::Processes,
+        Section::Cgroups,
+    ];
+}
+
+/// Auto-refresh interval selectable per section.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RefreshInterval {
+    #[default]
+    Off,
+    FiveSeconds,
+    ThirtySeconds,
+    FiveMinutes,
+}
+
+impl RefreshInterval {
+    pub fn duration(self) -> Option<Duration> {
+        match self {
+            RefreshInterval::Off => None,
+            RefreshInterval::FiveSeconds => Some(Duration::from_secs(5)),
+            RefreshInterval::ThirtySeconds => Some(Duration::from_secs(30)),
+            RefreshInterval::FiveMinutes => Some(Duration::from_secs(300)),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RefreshInterval::Off => "off",
+            RefreshInterval::FiveSeconds => "5s",
+            RefreshInterval::ThirtySeconds => "30s",
+            RefreshInterval::FiveMinutes => "5m",
+        }
+    }
+
+    /// Next interval in the off → 5s → 30s → 5m → off cycle (used by the toggle button).
+    pub fn next(self) -> Self {
+        match self {
+            RefreshInterval::Off => RefreshInterval::FiveSeconds,
+            RefreshInterval::FiveSeconds => RefreshInterval::ThirtySeconds,
+            RefreshInterval::ThirtySeconds => RefreshInterval::FiveMinutes,
+            RefreshInterval::FiveMinutes => RefreshInterval::Off,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct SectionPoll {
+    interval: RefreshInterval,
+    last_fetch: Option<Instant>,
+    requested: bool,
+}
+
+/// Polling state for a single host: per-section interval, last fetch time and
+/// pending manual refresh requests.
+#[derive(Clone, Debug)]
+pub struct PollScheduler {
+    sections: HashMap<Section, SectionPoll>,
+}
+
+impl Default for PollScheduler {
+    fn default() -> Self {
+        let mut sections = HashMap::new();
+        // Uptime/load in the banner stays fresh by default; heavier sections are manual.
+        sections.insert(
+            Section::SysInfo,
+            SectionPoll {
+                interval: RefreshInterval::ThirtySeconds,
+                ..Default::default()
+            },
+        );
+        sections.insert(Section::Services, SectionPoll::default());
+        Self { sections }
+    }
+}
+
+impl PollScheduler {
+    pub fn interval(&self, section: Section) -> RefreshInterval {
+        self.sections
+            .get(&section)
+            .map(|p| p.interval)
+            .unwrap_or_default()
+    }
+
+    pub fn set_interval(&mut self, section: Section, interval: RefreshInterval) {
+        self.sections.entry(section).or_default().interval = interval;
+    }
+
+    /// Request an immediate refresh of a section (picked up on the next scheduler tick).
+    pub fn request(&mut self, section: Section) {
+        self.sections.entry(section).or_default().requested = true;
+    }
+
+    /// Record that fresh data for `section` arrived at `at`.
+    pub fn mark_fetched(&mut self, section: Section, at: Instant) {
+        let p = self.sections.entry(section).or_default();
+        p.last_fetch = Some(at);
+        p.requested = false;
+    }
+
+    /// Return the sections that should be fetched now and clear their manual requests.
+    ///
+    /// Manual requests are always honored; interval-based polling is paused while
+    /// the window is not focused.
+    pub fn take_due(&mut self, now: Instant, focused: bool) -> Vec<Section> {
+        let mut due = Vec::new();
+        for section in Section::ALL {
+            let p = self.sections.entry(section).or_default();
+            let interval_due = focused
+                && match (p.interval.duration(), p.last_fetch) {
+                    (Some(d), Some(last)) => now.duration_since(last) >= d,
+                    (Some(_), None) => true,
+                    (None, _) => false,
+                };
+            if p.requested || interval_due {
+                p.requested = false;
+                // Avoid re-issuing while the request is in flight.
+                p.last_fetch = Some(now);
+                due.push(section);
+            }
+        }
+        due
+    }
+}
@@ -0,0 +1,178 @@
+//! Pressure section: PSI gauges (share of time tasks stalled on CPU, IO and
+//! memory) with alert thresholds, and the OOM kills of the current boot.
+
+use crate::snapshot::format_timestamp;
+use crate::{HostPanel, Section};
+use gpui::{div, prelude::*, relative, Context, Hsla};
+use slarti_proto as proto;
+use slarti_ui::Appearance;
+use std::time::{Instant, SystemTime};
+
+/// avg10 (percent stalled) from which a gauge turns orange.
+const WARN_PERCENT: f32 = 10.0;
+/// avg10 from which a gauge turns red.
+const ALERT_PERCENT: f32 = 40.0;
+/// OOM kills listed (the newest).
+const OOM_SHOWN: usize = 5;
+
+/// Orange or red once `avg10` crosses a threshold, None below both.
+fn level_color(avg10: f32) -> Option<Hsla> {
+    if avg10 >= ALERT_PERCENT {
+        Some(gpui::hsla(0.0, 0.8, 0.6, 1.0))
+    } else if avg10 >= WARN_PERCENT {
+        Some(gpui::hsla(0.13, 0.8, 0.6, 1.0))
+    } else {
+        None
+    }
+}
+
+/// The three resources with their labels, skipping those without PSI.
+fn resources(p: &proto::Pressure) -> impl Iterator<Item = (&'static str, &proto::PressureStall)> {
+    [("cpu", &p.cpu), ("io", &p.io), ("memory", &p.memory)]
+        .into_iter()
+        .filter_map(|(name, stall)| Some((name, stall.as_ref()?)))
+}
+
+/// Gauges of one resource: "some", then "full" when reported.
+fn gauges(
+    stall: &proto::PressureStall,
+) -> impl Iterator<Item = (&'static str, &proto::PressureAverages)> {
+    std::iter::once(("some", &stall.some)).chain(stall.full.as_ref().map(|f| ("full", f)))
+}
+
+/// "3.2%  1.1%  0.4%" (10s, 60s, 300s)
+fn averages_text(a: &proto::PressureAverages) -> String {
+    format!("{:.1}%  {:.1}%  {:.1}%", a.avg10, a.avg60, a.avg300)
+}
+
+/// "2024-05-01 12:30 UTC  4242 java"
+fn oom_text(kill: &proto::OomKill) -> String {
+    format!(
+        "{}  {} {}",
+        format_timestamp(kill.realtime_usec / 1_000_000),
+        kill.pid
+            .map(|p| p.to_string())
+            .unwrap_or_else(|| "?".to_string()),
+        kill.comm.as_deref().unwrap_or("?")
+    )
+}
+
+/// Plain-text gauges and OOM kills, for Copy.
+pub(crate) fn pressure_text(p: &proto::Pressure) -> String {
+    let mut out = String::from("PSI (avg10 avg60 avg300)\n");
+    for (name, stall) in resources(p) {
+        for (kind, a) in gauges(stall) {
+            out.push_str(&format!("{:<7} {:<5} {}\n", name, kind, averages_text(a)));
+        }
+    }
+    match &p.oom_kills {
+        None => out.push_str("OOM kills: kernel log unavailable\n"),
+        Some(kills) if kills.is_empty() => out.push_str("OOM kills: none this boot\n"),
+        Some(kills) => {
+            out.push_str(&format!("OOM kills ({}):\n", kills.len()));
+            for kill in kills {
+                out.push_str(&format!("  {}\n", oom_text(kill)));
+            }
+        }
+    }
+    out
+}
+
+impl HostPanel {
+    /// Update the pressure gauges and OOM kills shown in the panel.
+    pub fn set_pressure(&mut self, pressure: proto::Pressure, cx: &mut Context<Self>) {
+        self.pressure = Some(pressure);
+        self.freshness.mark(Section::Pressure, SystemTime::now());
+        if let Some(sched) = self.scheduler_mut() {
+            sched.mark_fetched(Section::Pressure, Instant::now());
+        }
+        cx.notify();
+    }
+
+    /// Pressure section: header with controls, gauges, then OOM kills.
+    pub(crate) fn render_pressure(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let section = div()
+            .flex()
+            .flex_col()
+            .gap_2()
+            .pl(ap.px(8.0))
+            .pr(ap.px(8.0))
+            .py(ap.px(8.0))
+            .border_b_1()
+            .border_color(pal.border)
+            .when(self.is_dimmed(Section::Pressure), |d| d.opacity(0.5))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .child(div().text_color(pal.fg).child("Pressure"))
+                    .child(self.render_section_controls(Section::Pressure, cx)),
+            );
+        let Some(p) = &self.pressure else {
+            return section.child("Not loaded yet: press ⟳.");
+        };
+
+        let mut rows = Vec::new();
+        for (name, stall) in resources(p) {
+            for (kind, a) in gauges(stall) {
+                let color = level_color(a.avg10);
+                rows.push(
+                    div()
+                        .flex()
+                        .items_center()
+                        .gap_2()
+                        .h(ap.px(18.0))
+                        .child(div().w(ap.px(56.0)).text_color(pal.fg).child(name))
+                        .child(div().w(ap.px(32.0)).text_color(pal.muted).child(kind))
+                        .child(
+                            div().w(ap.px(120.0)).h(ap.px(8.0)).bg(pal.border).child(
+                                div()
+                                    .h_full()
+                                    .w(relative((a.avg10 / 100.0).clamp(0.0, 1.0)))
+                                    .bg(color.unwrap_or(pal.accent)),
+                            ),
+                        )
+                        .child(
+                            div()
+                                .text_color(color.unwrap_or(pal.fg_dim))
+                                .child(averages_text(a)),
+                        ),
+                );
+            }
+        }
+        if rows.is_empty() {
+            rows.push(div().child("No pressure stall information (kernel without PSI)."));
+        }
+
+        let ooms = match &p.oom_kills {
+            None => div()
+                .text_color(pal.muted)
+                .child("OOM kills: kernel log unavailable"),
+            Some(kills) if kills.is_empty() => div()
+                .text_color(pal.muted)
+                .child("OOM kills: none this boot"),
+            Some(kills) => div()
+                .flex()
+                .flex_col()
+                .child(
+                    div()
+                        .text_color(gpui::hsla(0.0, 0.8, 0.6, 1.0))
+                        .child(format!("OOM kills: {} this boot", kills.len())),
+                )
+                .children(
+                    kills
+                        .iter()
+                        .rev()
+                        .take(OOM_SHOWN)
+                        .map(|kill| div().pl(ap.px(8.0)).child(oom_text(kill))),
+                ),
+        };
+
+        section
+            .child(div().flex().flex_col().children(rows))
+            .child(ooms)
+    }
+}
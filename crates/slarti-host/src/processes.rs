@@ -0,0 +1,456 @@
+//! Processes section: the busiest processes of the selected host, and a
+//! drawer with one process's details (command line, environment, working
+//! directory, open files, sockets) and its kill/renice actions.
+//!
+use crate::units::format_bytes;
+use crate::{csv, AgentRequest, HostPanel, PendingAction, Section};
+use gpui::{div, prelude::*, Context, MouseButton, Window};
+use slarti_proto as proto;
+use slarti_ui::Appearance;
+use std::time::{Instant, SystemTime};
+
+/// Signals offered by the picker, as the agent's kill(2) takes them. These
+/// numbers are the same on every Linux architecture (SIGSTOP/SIGCONT are not).
+pub const SIGNALS: [(i32, &str); 5] = [
+    (SIGTERM, "SIGTERM"),
+    (9, "SIGKILL"),
+    (1, "SIGHUP"),
+    (2, "SIGINT"),
+    (3, "SIGQUIT"),
+];
+/// Sent by a process row's "kill" and preselected in the picker.
+pub const SIGTERM: i32 = 15;
+/// Range of nice values (lowest is highest priority).
+const NICE_RANGE: std::ops::RangeInclusive<i32> = -20..=19;
+
+/// "SIGTERM" for the picker's signals, "signal N" for others.
+pub fn signal_name(signal: i32) -> String {
+    SIGNALS
+        .iter()
+        .find(|(n, _)| *n == signal)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| format!("signal {}", signal))
+}
+
+/// One aligned row of a process list.
+fn process_row(p: &proto::ProcessInfo) -> String {
+    format!(
+        "{:>7}  {:<10} {:>6.1}%  {:>8}  {}",
+        p.pid,
+        p.user,
+        p.cpu_percent,
+        format_bytes(p.rss_bytes),
+        p.comm
+    )
+}
+
+/// Plain-text rendering of a summary (both lists), for Copy.
+pub(crate) fn summary_text(summary: &proto::ProcessesSummary) -> String {
+    let mut s = format!("{} processes\n", summary.total);
+    for (title, list) in [
+        ("by CPU", &summary.by_cpu),
+        ("by memory", &summary.by_memory),
+    ] {
+        s.push_str(&format!(
+            "{}:\n{:>7}  {:<10} {:>7}  {:>8}  command\n",
+            title, "pid", "user", "cpu", "rss"
+        ));
+        for p in list {
+            s.push_str(&process_row(p));
+            s.push('\n');
+        }
+    }
+    s
+}
+
+/// Both lists as CSV, one record per row in the order shown.
+pub(crate) fn summary_csv(summary: &proto::ProcessesSummary) -> String {
+    let mut out = String::new();
+    csv::push_record(
+        &mut out,
+        [
+            "list",
+            "rank",
+            "pid",
+            "user",
+            "cpu_percent",
+            "rss_bytes",
+            "command",
+        ],
+    );
+    for (list_name, list) in [("cpu", &summary.by_cpu), ("memory", &summary.by_memory)] {
+        for (rank, p) in list.iter().enumerate() {
+            csv::push_record(
+                &mut out,
+                [
+                    list_name.to_string(),
+                    (rank + 1).to_string(),
+                    p.pid.to_string(),
+                    p.user.clone(),
+                    format!("{:.1}", p.cpu_percent),
+                    p.rss_bytes.to_string(),
+                    p.comm.clone(),
+                ],
+            );
+        }
+    }
+    out
+}
+
+impl HostPanel {
+    /// Update the busiest processes shown in the panel.
+    pub fn set_processes(&mut self, summary: proto::ProcessesSummary, cx: &mut Context<Self>) {
+        self.processes = Some(summary);
+        self.freshness.mark(Section::Processes, SystemTime::now());
+        if let Some(sched) = self.scheduler_mut() {
+            sched.mark_fetched(Section::Processes, Instant::now());
+        }
+        cx.notify();
+    }
+
+    /// Show the agent's answer to a process detail, kill or renice request.
+    pub(crate) fn apply_process_response(
+        &mut self,
+        request: AgentRequest,
+        response: proto::Response,
+        cx: &mut Context<Self>,
+    ) {
+        match (request, response) {
+            (AgentRequest::ProcessDetail(pid), proto::Response::ProcessDetailOk { detail, .. }) => {
+                // Ignore a late answer for a drawer closed or switched meanwhile.
+                if self.process_pid == Some(pid) {
+                    self.process_detail = Some(detail);
+                }
+            }
+            (AgentRequest::ProcessDetail(pid), proto::Response::Error { message, .. }) => {
+                if self.process_pid == Some(pid) {
+                    self.process_pid = None;
+                    self.process_detail = None;
+                }
+                self.push_progress(format!("process {}: {}", pid, message), cx);
+            }
+            (
+                AgentRequest::SignalProcess { pid, signal },
+                proto::Response::ProcessActionOk { .. },
+            ) => {
+                self.push_progress(format!("sent {} to {}", signal_name(signal), pid), cx);
+                self.after_process_action(pid, cx);
+            }
+            (
+                AgentRequest::ReniceProcess { pid, nice },
+                proto::Response::ProcessActionOk { .. },
+            ) => {
+                self.push_progress(format!("reniced {} to {}", pid, nice), cx);
+                self.process_nice = None;
+                self.after_process_action(pid, cx);
+            }
+            (_, proto::Response::Error { message, .. }) => self.push_progress(message, cx),
+            _ => {}
+        }
+    }
+
+    /// Re-read the process (it may be gone) and the lists after kill/renice.
+    fn after_process_action(&mut self, pid: u32, cx: &mut Context<Self>) {
+        if self.process_pid == Some(pid) {
+            self.agent_requests.push(AgentRequest::ProcessDetail(pid));
+        }
+        self.request_refresh(Section::Processes, cx);
+    }
+
+    /// Open the drawer for `pid` and fetch its details.
+    fn open_process(&mut self, pid: u32, cx: &mut Context<Self>) {
+        self.process_pid = Some(pid);
+        self.process_detail = None;
+        self.process_nice = None;
+        self.agent_requests.push(AgentRequest::ProcessDetail(pid));
+        cx.notify();
+    }
+
+    fn close_process(&mut self, cx: &mut Context<Self>) {
+        self.process_pid = None;
+        self.process_detail = None;
+        self.process_nice = None;
+        cx.notify();
+    }
+
+    /// Queue a kill/renice approved by the action policy (and preview, if required).
+    pub(crate) fn run_process_action(&mut self, action: PendingAction, cx: &mut Context<Self>) {
+        let request = match action {
+            PendingAction::SignalProcess { pid, signal } => {
+                AgentRequest::SignalProcess { pid, signal }
+            }
+            PendingAction::ReniceProcess { pid, nice } => AgentRequest::ReniceProcess { pid, nice },
+            _ => return,
+        };
+        self.agent_requests.push(request);
+        cx.notify();
+    }
+
+    /// Processes section: both lists; clicking a row opens its drawer, its
+    /// "kill" sends SIGTERM.
+    pub(crate) fn render_processes(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let mut body = div().flex().flex_col();
+        match &self.processes {
+            None => body = body.child("Not loaded yet: press ⟳."),
+            Some(summary) => {
+                body = body.child(format!("{} processes", summary.total));
+                for (title, list) in [
+                    ("By CPU", &summary.by_cpu),
+                    ("By memory", &summary.by_memory),
+                ] {
+                    body = body.child(div().pt(ap.px(4.0)).text_color(pal.fg).child(title));
+                    for p in list {
+                        let pid = p.pid;
+                        let selected = self.process_pid == Some(pid);
+                        let kill = div()
+                            .px(ap.px(6.0))
+                            .rounded_sm()
+                            .border_1()
+                            .border_color(pal.border)
+                            .text_color(pal.fg)
+                            .child("kill")
+                            .on_mouse_up(
+                                MouseButton::Left,
+                                cx.listener(move |this: &mut Self, _ev, window, cx| {
+                                    // Killing does not open the drawer.
+                                    cx.stop_propagation();
+                                    let signal = SIGTERM;
+                                    let action = PendingAction::SignalProcess { pid, signal };
+                                    this.request_action(action, window, cx);
+                                }),
+                            );
+                        body = body.child(
+                            div()
+                                .flex()
+                                .items_center()
+                                .justify_between()
+                                .cursor_pointer()
+                                .text_color(if selected { pal.accent } else { pal.fg_dim })
+                                .child(process_row(p))
+                                .child(kill)
+                                .on_mouse_up(
+                                    MouseButton::Left,
+                                    cx.listener(move |this: &mut Self, _ev, _w, cx| {
+                                        this.open_process(pid, cx);
+                                    }),
+                                ),
+                        );
+                    }
+                }
+            }
+        }
+        div()
+            .flex()
+            .flex_col()
+            .gap_2()
+            .pl(ap.px(8.0))
+            .pr(ap.px(8.0))
+            .py(ap.px(8.0))
+            .border_b_1()
+            .border_color(pal.border)
+            .when(self.is_dimmed(Section::Processes), |d| d.opacity(0.5))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .child(div().text_color(pal.fg).child("Processes"))
+                    .child(self.render_section_controls(Section::Processes, cx)),
+            )
+            .child(body)
+    }
+
+    /// Drawer with the open process's details and actions.
+    pub(crate) fn render_process_drawer(&self, cx: &mut Context<Self>) -> Option<gpui::Div> {
+        let pid = self.process_pid?;
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let button = |label: &'static str| {
+            div()
+                .px(ap.px(8.0))
+                .h(ap.px(18.0))
+                .rounded_sm()
+                .border_1()
+                .border_color(pal.border)
+                .cursor_pointer()
+                .text_color(pal.fg)
+                .child(label)
+        };
+        let heading = |label: &'static str| div().pt(ap.px(4.0)).text_color(pal.fg).child(label);
+        let denied = || div().text_color(pal.muted).child("(not permitted)");
+
+        let mut body = div()
+            .id("ProcessDrawer")
+            .flex()
+            .flex_col()
+            .max_h(ap.px(240.0))
+            .overflow_y_scroll();
+        let title = match &self.process_detail {
+            None => {
+                body = body.child(div().text_color(pal.muted).child("loading…"));
+                format!("Process {}", pid)
+            }
+            Some(d) => {
+                body = body
+                    .child(heading("Command line"))
+                    .child(if d.cmdline.is_empty() {
+                        div().text_color(pal.muted).child("(kernel thread)")
+                    } else {
+                        div().child(d.cmdline.join(" "))
+                    })
+                    .child(heading("Working directory"))
+                    .child(match &d.cwd {
+                        Some(cwd) => div().child(cwd.clone()),
+                        None => denied(),
+                    })
+                    .child(heading("Environment"))
+                    .child(match &d.environ {
+                        Some(env) => div()
+                            .flex()
+                            .flex_col()
+                            .children(env.iter().map(|e| div().child(e.clone()))),
+                        None => denied(),
+                    })
+                    .child(heading("Open files"))
+                    .child(match &d.open_files {
+                        Some(files) => div().flex().flex_col().children(files.iter().map(|f| {
+                            let row = div().child(format!("{:>4}  {}", f.fd, f.target));
+                            // Paths open in the file drawer; pipes and the like do not.
+                            if !f.target.starts_with('/') {
+                                return row;
+                            }
+                            let path = f.target.clone();
+                            row.cursor_pointer().text_color(pal.accent).on_mouse_up(
+                                MouseButton::Left,
+                                cx.listener(move |this: &mut Self, _ev, _window, cx| {
+                                    this.open_file(path.clone(), cx);
+                                }),
+                            )
+                        })),
+                        None => denied(),
+                    })
+                    .child(heading("Sockets"))
+                    .children(d.sockets.iter().map(|s| {
+                        let mut line = format!("{:>4}  {} {}", s.fd, s.protocol, s.local);
+                        if let Some(remote) = &s.remote {
+                            line.push_str(&format!(" → {}", remote));
+                        }
+                        if !s.state.is_empty() {
+                            line.push_str(&format!(" ({})", s.state));
+                        }
+                        div().child(line)
+                    }));
+                format!(
+                    "{} ({}): user {}, state {}, nice {}",
+                    d.comm, d.pid, d.user, d.state, d.nice
+                )
+            }
+        };
+        // Kill/renice go through the action policy and a confirmation.
+        let act = |label: &'static str, action: PendingAction| {
+            button(label).on_mouse_up(
+                MouseButton::Left,
+                cx.listener(move |this: &mut Self, _ev, window: &mut Window, cx| {
+                    this.request_action(action, window, cx);
+                }),
+            )
+        };
+
+        let chosen = self.process_signal;
+        let signals = div()
+            .flex()
+            .items_center()
+            .gap_2()
+            .child(div().w(ap.px(48.0)).text_color(pal.muted).child("Signal"))
+            .children(SIGNALS.iter().map(|&(signal, name)| {
+                button(name)
+                    .when(signal == chosen, |d| d.border_color(pal.accent))
+                    .on_mouse_up(
+                        MouseButton::Left,
+                        cx.listener(move |this: &mut Self, _ev, _window, cx| {
+                            this.process_signal = signal;
+                            cx.notify();
+                        }),
+                    )
+            }))
+            .child(act(
+                "Send",
+                PendingAction::SignalProcess {
+                    pid,
+                    signal: chosen,
+                },
+            ));
+
+        // Renice slider: one cell per nice value, filled up to the chosen one.
+        let renice = self.process_detail.as_ref().map(|d| {
+            let target = self.process_nice.unwrap_or(d.nice);
+            div()
+                .flex()
+                .items_center()
+                .gap_2()
+                .child(div().w(ap.px(48.0)).text_color(pal.muted).child("Nice"))
+                .child(
+                    div()
+                        .flex()
+                        .flex_1()
+                        .h(ap.px(12.0))
+                        .gap_0p5()
+                        .children(NICE_RANGE.map(|n| {
+                            div()
+                                .flex_1()
+                                .h_full()
+                                .cursor_pointer()
+                                .bg(if n <= target { pal.accent } else { pal.border })
+                                .when(n == d.nice, |c| c.border_1().border_color(pal.fg))
+                                .on_mouse_up(
+                                    MouseButton::Left,
+                                    cx.listener(move |this: &mut Self, _ev, _window, cx| {
+                                        this.process_nice = Some(n);
+                                        cx.notify();
+                                    }),
+                                )
+                        })),
+                )
+                .child(div().w(ap.px(64.0)).child(if target == d.nice {
+                    format!("{}", d.nice)
+                } else {
+                    format!("{} → {}", d.nice, target)
+                }))
+                .when(target != d.nice, |row| {
+                    // Lowering it (raising priority) needs root; the agent reports EPERM.
+                    row.child(act(
+                        "Apply",
+                        PendingAction::ReniceProcess { pid, nice: target },
+                    ))
+                })
+        });
+
+        Some(
+            div()
+                .flex()
+                .flex_col()
+                .gap_1()
+                .p(ap.px(8.0))
+                .border_b_1()
+                .border_color(pal.border)
+                .child(
+                    div()
+                        .flex()
+                        .items_center()
+                        .justify_between()
+                        .child(div().text_color(pal.fg).child(title))
+                        .child(button("Close").on_mouse_up(
+                            MouseButton::Left,
+                            cx.listener(|this: &mut Self, _ev, _window, cx| {
+                                this.close_process(cx);
+                            }),
+                        )),
+                )
+                .child(signals)
+                .children(renice)
+                .child(body),
+        )
+    }
+}
@@ -0,0 +1,199 @@
+//! Recently selected hosts, persisted as `hosts_recent.json`.
+//!
+//! Entries carry the time they were last selected and can be pinned. Pinned
+//! entries are listed first and are never dropped by the cap; entries whose
+//! alias disappeared from the ssh config are pruned.
+
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+
+use crate::snapshot::unix_secs;
+
+/// Unpinned entries kept (pinned ones come on top of this).
+const MAX_RECENT: usize = 20;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecentHost {
+    pub alias: String,
+    /// When the host was last selected (unix seconds; 0 if unknown).
+    #[serde(default)]
+    pub selected_at: u64,
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+/// On-disk format: entries, or the plain alias list written by earlier versions.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Stored {
+    Entries(Vec<RecentHost>),
+    Aliases(Vec<String>),
+}
+
+/// Recent hosts, most recently selected first.
+#[derive(Clone, Debug, Default)]
+pub struct RecentHosts {
+    entries: Vec<RecentHost>,
+}
+
+impl RecentHosts {
+    pub fn from_json(bytes: &[u8]) -> Option<Self> {
+        let entries = match serde_json::from_slice(bytes).ok()? {
+            Stored::Entries(entries) => entries,
+            Stored::Aliases(aliases) => aliases
+                .into_iter()
+                .map(|alias| RecentHost {
+                    alias,
+                    selected_at: 0,
+                    pinned: false,
+                })
+                .collect(),
+        };
+        Some(Self { entries })
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<Vec<u8>> {
+        serde_json::to_vec_pretty(&self.entries)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Record a selection of `alias` at `at`, moving it to the front.
+    pub fn touch(&mut self, alias: &str, at: SystemTime) {
+        let pinned = self.entries.iter().any(|e| e.alias == alias && e.pinned);
+        self.entries.retain(|e| e.alias != alias);
+        self.entries.insert(
+            0,
+            RecentHost {
+                alias: alias.to_string(),
+                selected_at: unix_secs(at),
+                pinned,
+            },
+        );
+        let mut unpinned = 0;
+        self.entries.retain(|e| {
+            if !e.pinned {
+                unpinned += 1;
+            }
+            e.pinned || unpinned <= MAX_RECENT
+        });
+    }
+
+    /// Pin or unpin `alias`; returns false if it is not in the list.
+    pub fn toggle_pin(&mut self, alias: &str) -> bool {
+        match self.entries.iter_mut().find(|e| e.alias == alias) {
+            Some(e) => {
+                e.pinned = !e.pinned;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Drop entries whose alias is not in `known`; returns true if any were dropped.
+    pub fn retain_known(&mut self, known: &[String]) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|e| known.contains(&e.alias));
+        self.entries.len() != before
+    }
+
+    /// Entries in display order: pinned first, then by recency.
+    pub fn ordered(&self) -> impl Iterator<Item = &RecentHost> {
+        let pinned = self.entries.iter().filter(|e| e.pinned);
+        pinned.chain(self.entries.iter().filter(|e| !e.pinned))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    fn aliases(recent: &RecentHosts) -> Vec<&str> {
+        recent.ordered().map(|e| e.alias.as_str()).collect()
+    }
+
+    #[test]
+    fn touch_moves_the_host_to_the_front() {
+        let mut recent = RecentHosts::default();
+        recent.touch("web1", at(10));
+        recent.touch("db1", at(20));
+        recent.touch("web1", at(30));
+        assert_eq!(aliases(&recent), ["web1", "db1"]);
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent.ordered().next().unwrap().selected_at, 30);
+    }
+
+    #[test]
+    fn pinned_hosts_come_first_and_stay_pinned() {
+        let mut recent = RecentHosts::default();
+        for (i, alias) in ["a", "b", "c"].into_iter().enumerate() {
+            recent.touch(alias, at(i as u64));
+        }
+        assert!(recent.toggle_pin("a"));
+        assert!(!recent.toggle_pin("unknown"));
+        assert_eq!(aliases(&recent), ["a", "c", "b"]);
+
+        recent.touch("a", at(10));
+        recent.touch("b", at(11));
+        assert_eq!(aliases(&recent), ["a", "b", "c"]);
+        assert!(recent.ordered().next().unwrap().pinned);
+
+        assert!(recent.toggle_pin("a"));
+        assert_eq!(aliases(&recent), ["b", "a", "c"]);
+    }
+
+    #[test]
+    fn the_cap_drops_the_oldest_unpinned_hosts_only() {
+        let mut recent = RecentHosts::default();
+        recent.touch("pinned", at(0));
+        recent.toggle_pin("pinned");
+        for i in 0..MAX_RECENT + 5 {
+            recent.touch(&format!("h{}", i), at(i as u64 + 1));
+        }
+        assert_eq!(recent.len(), MAX_RECENT + 1);
+        let shown = aliases(&recent);
+        assert_eq!(shown[0], "pinned");
+        assert_eq!(shown[1], format!("h{}", MAX_RECENT + 4));
+        assert_eq!(shown[MAX_RECENT], "h5");
+    }
+
+    #[test]
+    fn retain_known_prunes_removed_aliases() {
+        let mut recent = RecentHosts::default();
+        recent.touch("old", at(1));
+        recent.touch("web1", at(2));
+        assert!(recent.retain_known(&["web1".to_string()]));
+        assert!(!recent.retain_known(&["web1".to_string()]));
+        assert_eq!(aliases(&recent), ["web1"]);
+    }
+
+    #[test]
+    fn reads_entries_and_the_older_alias_list() {
+        let mut recent = RecentHosts::default();
+        recent.touch("web1", at(5));
+        recent.toggle_pin("web1");
+        let back = RecentHosts::from_json(&recent.to_json().unwrap()).unwrap();
+        let entry = back.ordered().next().unwrap();
+        assert_eq!(
+            (entry.alias.as_str(), entry.selected_at, entry.pinned),
+            ("web1", 5, true)
+        );
+
+        let old = RecentHosts::from_json(br#"["db1", "web1"]"#).unwrap();
+        assert_eq!(aliases(&old), ["db1", "web1"]);
+        assert!(old.ordered().all(|e| e.selected_at == 0 && !e.pinned));
+
+        assert!(RecentHosts::from_json(b"{}").is_none());
+    }
+}
@@ -0,0 +1,152 @@
+//! Render snapshots of the host panel in its main states, so refactors of the
+//! render code can be checked (see `slarti_ui::test_support`).
+
+use gpui::{px, size, Entity, Modifiers, TestAppContext, VisualTestContext};
+use slarti_proto as proto;
+use slarti_ui::test_support::{assert_snapshot, layout, TempState};
+
+use crate::{HostPanel, HostPanelProps};
+
+const SNAPSHOTS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots");
+
+/// Regions tagged with `debug_selector` in the panel and its services list.
+const REGIONS: &[&str] = &[
+    "host-header",
+    "host-status",
+    "host-deploy",
+    "host-install-key",
+    "host-start",
+    "host-recent",
+    "host-identity",
+    "host-services",
+    "services-filter-All",
+    "services-filter-Failed",
+    "service-nginx.service",
+    "service-cron.service",
+    "service-backup.service",
+];
+
+fn open(cx: &mut TestAppContext) -> (Entity<HostPanel>, &mut VisualTestContext) {
+    let (panel, cx) = cx.add_window_view(|_window, cx| {
+        HostPanel::new(
+            cx,
+            HostPanelProps {
+                selected_alias: None,
+                on_deploy: None,
+            },
+        )
+    });
+    cx.simulate_resize(size(px(720.0), px(1200.0)));
+    cx.run_until_parked();
+    (panel, cx)
+}
+
+/// Status line, identity and services text, then the painted regions.
+fn snapshot(panel: &Entity<HostPanel>, cx: &mut VisualTestContext) -> String {
+    cx.run_until_parked();
+    let text = cx.update(|_window, cx| {
+        let panel = panel.read(cx);
+        let services = panel
+            .services_list
+            .read(cx)
+            .to_text()
+            .unwrap_or_else(|| "(pending)\n".into());
+        format!(
+            "{}\n\n{}\n\n{}",
+            panel.status_text(),
+            panel.identity_text(),
+            services
+        )
+    });
+    format!("{}\n{}", text, layout(cx, REGIONS))
+}
+
+fn select_web1(panel: &Entity<HostPanel>, cx: &mut VisualTestContext) {
+    let service = |name: &str, state: &str| proto::ServiceInfo {
+        name: name.to_string(),
+        active_state: state.to_string(),
+        sub_state: if state == "active" { "running" } else { state }.to_string(),
+        enabled: Some(true),
+        ..Default::default()
+    };
+    panel.update(cx, |panel, cx| {
+        panel.set_known_hosts(vec!["web1".into(), "db1".into()], cx);
+        panel.set_selected_host(Some("web1".into()), cx);
+        panel.set_status("connected v0.1.0", cx);
+        panel.set_sys_info(
+            proto::SysInfo {
+                hostname: "web1".into(),
+                os: "Debian GNU/Linux 12".into(),
+                kernel: "6.1.0-18-amd64".into(),
+                arch: "x86_64".into(),
+                uptime_secs: 86_400,
+                ..Default::default()
+            },
+            cx,
+        );
+        panel.set_services(
+            vec![
+                service("nginx.service", "active"),
+                service("cron.service", "active"),
+                service("backup.service", "failed"),
+            ],
+            cx,
+        );
+    });
+}
+
+#[gpui::test]
+fn no_selection(cx: &mut TestAppContext) {
+    let _state = TempState::new("host-panel");
+    let (panel, cx) = open(cx);
+    panel.update(cx, |panel, cx| {
+        panel.set_known_hosts(vec!["web1".into(), "db1".into()], cx);
+        panel.set_status("unknown", cx);
+    });
+    assert_snapshot(SNAPSHOTS, "host_panel_no_selection", &snapshot(&panel, cx));
+}
+
+#[gpui::test]
+fn selected_with_services(cx: &mut TestAppContext) {
+    let _state = TempState::new("host-panel");
+    let (panel, cx) = open(cx);
+    select_web1(&panel, cx);
+    assert_snapshot(
+        SNAPSHOTS,
+        "host_panel_selected_with_services",
+        &snapshot(&panel, cx),
+    );
+}
+
+#[gpui::test]
+fn failed_deploy(cx: &mut TestAppContext) {
+    let _state = TempState::new("host-panel");
+    let (panel, cx) = open(cx);
+    // The app's deploy flow on an rsync/scp failure.
+    panel.update(cx, |panel, cx| {
+        panel.set_selected_host(Some("web1".into()), cx);
+        panel.set_status("not present", cx);
+        panel.set_deploy_running(true, cx);
+        panel.push_progress("deploying agent", cx);
+        panel.set_status("deploy failed: Permission denied (publickey)", cx);
+        panel.set_deploy_running(false, cx);
+    });
+    assert_snapshot(SNAPSHOTS, "host_panel_failed_deploy", &snapshot(&panel, cx));
+}
+
+#[gpui::test]
+fn filters_applied(cx: &mut TestAppContext) {
+    let _state = TempState::new("host-panel");
+    let (panel, cx) = open(cx);
+    select_web1(&panel, cx);
+    cx.run_until_parked();
+    let failed = cx
+        .debug_bounds("services-filter-Failed")
+        .expect("services filter bar is painted");
+    cx.simulate_click(failed.center(), Modifiers::none());
+    assert_snapshot(
+        SNAPSHOTS,
+        "host_panel_filters_applied",
+        &snapshot(&panel, cx),
+    );
+}
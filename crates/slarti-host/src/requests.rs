@@ -0,0 +1,96 @@
+//! One-off agent requests queued by the panel's drawers (process and service
+//! details, kill, renice, file windows and follows) and the installed
+//! packages list.
+//!
+//! The app's session loop drains them with `take_agent_requests`, sends each
+//! over the live agent session and hands the answer back through
+//! `apply_agent_response`. A `TailFile` starts a stream instead: the loop
+//! reports its id with `stream_started` and passes its frames to
+//! `apply_stream_frames`; `TailStop` ends it.
+
+use crate::{file_view, packages, HostPanel};
+use gpui::Context;
+use slarti_proto as proto;
+
+/// An agent request queued by the panel.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AgentRequest {
+    ProcessDetail(u32),
+    SignalProcess { pid: u32, signal: i32 },
+    ReniceProcess { pid: u32, nice: i32 },
+    ServiceDetail(String),
+    Packages { offset: usize },
+    ReadFile { path: String, offset: u64 },
+    TailFile { path: String },
+    TailStop { id: u64 },
+}
+
+impl AgentRequest {
+    pub fn command(&self, id: u64) -> proto::Command {
+        match *self {
+            AgentRequest::ProcessDetail(pid) => proto::Command::ProcessDetail { id, pid },
+            AgentRequest::SignalProcess { pid, signal } => {
+                proto::Command::SignalProcess { id, pid, signal }
+            }
+            AgentRequest::ReniceProcess { pid, nice } => {
+                proto::Command::ReniceProcess { id, pid, nice }
+            }
+            AgentRequest::ServiceDetail(ref unit) => proto::Command::ServiceDetail {
+                id,
+                unit: unit.clone(),
+            },
+            AgentRequest::Packages { offset } => proto::Command::Packages {
+                id,
+                offset: Some(offset),
+                limit: Some(packages::PAGE),
+            },
+            AgentRequest::ReadFile { ref path, offset } => proto::Command::ReadFile {
+                id,
+                path: path.clone(),
+                offset: Some(offset),
+                len: Some(file_view::WINDOW),
+            },
+            AgentRequest::TailFile { ref path } => proto::Command::TailFile {
+                id,
+                path: path.clone(),
+                lines: Some(file_view::TAIL_LINES),
+                follow: true,
+            },
+            // Addressed to the stream, not to a new request.
+            AgentRequest::TailStop { id } => proto::Command::TailStop { id },
+        }
+    }
+
+    /// Whether the command starts a stream (sent with `start_stream`).
+    pub fn starts_stream(&self) -> bool {
+        matches!(self, AgentRequest::TailFile { .. })
+    }
+
+    /// Whether the command ends a stream (sent with `stop_stream`, no answer).
+    pub fn stops_stream(&self) -> bool {
+        matches!(self, AgentRequest::TailStop { .. })
+    }
+}
+
+impl HostPanel {
+    /// Queued agent requests, oldest first.
+    pub fn take_agent_requests(&mut self) -> Vec<AgentRequest> {
+        std::mem::take(&mut self.agent_requests)
+    }
+
+    /// Show the agent's answer to a queued request.
+    pub fn apply_agent_response(
+        &mut self,
+        request: AgentRequest,
+        response: proto::Response,
+        cx: &mut Context<Self>,
+    ) {
+        match request {
+            AgentRequest::ServiceDetail(unit) => self.apply_service_response(unit, response, cx),
+            AgentRequest::Packages { offset } => self.apply_packages_response(offset, response, cx),
+            AgentRequest::ReadFile { path, .. } => self.apply_file_response(path, response, cx),
+            other => self.apply_process_response(other, response, cx),
+        }
+        cx.notify();
+    }
+}
@@ -0,0 +1,336 @@
+//! Security section: sshd settings audited for risky values (root or
+//! password logins, empty passwords, weak MACs, ciphers and key exchange),
+//! recent failed logins by source and user, and fail2ban's bans.
+
+use crate::freshness::format_age;
+use crate::{format_uptime, unix_now, HostPanel, Section};
+use gpui::{div, prelude::*, Context, Hsla};
+use slarti_proto as proto;
+use slarti_ui::Appearance;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Offending addresses and targeted users listed in the section.
+const SHOWN_OFFENDERS: usize = 5;
+
+/// MAC, cipher and key exchange algorithms considered weak: fragments of
+/// their names.
+const WEAK_MACS: [&str; 5] = ["md5", "sha1", "-96", "umac-64", "ripemd"];
+const WEAK_CIPHERS: [&str; 4] = ["-cbc", "3des", "arcfour", "blowfish"];
+const WEAK_KEX: [&str; 2] = ["group1-", "-sha1"];
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Severity {
+    High,
+    Medium,
+    Low,
+}
+
+struct Finding {
+    severity: Severity,
+    text: String,
+}
+
+/// The value of `key`, with sshd's default applied when the settings come
+/// from the config files and leave it unset.
+fn setting<'a>(config: &'a proto::SshdConfig, key: &str, default: &'a str) -> &'a str {
+    match config.settings.iter().find(|s| s.key == key) {
+        Some(s) => &s.value,
+        None if config.effective => "",
+        None => default,
+    }
+}
+
+/// Algorithms of a comma-separated list matching one of `weak`; lists that
+/// remove algorithms ("-name") enable none.
+fn weak_algorithms(list: &str, weak: &[&str]) -> Vec<String> {
+    if list.starts_with('-') {
+        return Vec::new();
+    }
+    list.trim_start_matches(['+', '^'])
+        .split(',')
+        .filter(|alg| weak.iter().any(|w| alg.contains(w)))
+        .map(str::to_string)
+        .collect()
+}
+
+fn findings(config: &proto::SshdConfig) -> Vec<Finding> {
+    let mut out = Vec::new();
+    let mut flag = |severity, text: String| out.push(Finding { severity, text });
+    let yes = |value: &str| value.eq_ignore_ascii_case("yes");
+    if yes(setting(config, "permitrootlogin", "prohibit-password")) {
+        flag(
+            Severity::High,
+            "PermitRootLogin yes: root can log in with a password".to_string(),
+        );
+    }
+    if yes(setting(config, "permitemptypasswords", "no")) {
+        flag(
+            Severity::High,
+            "PermitEmptyPasswords yes: accounts without a password can log in".to_string(),
+        );
+    }
+    if yes(setting(config, "passwordauthentication", "yes")) {
+        flag(
+            Severity::Medium,
+            "PasswordAuthentication yes: passwords can be guessed over the network".to_string(),
+        );
+    }
+    for (key, name, weak) in [
+        ("macs", "MACs", &WEAK_MACS[..]),
+        ("ciphers", "Ciphers", &WEAK_CIPHERS[..]),
+        ("kexalgorithms", "KexAlgorithms", &WEAK_KEX[..]),
+    ] {
+        let algorithms = weak_algorithms(setting(config, key, ""), weak);
+        if !algorithms.is_empty() {
+            flag(
+                Severity::Medium,
+                format!("{} allows weak {}", name, algorithms.join(", ")),
+            );
+        }
+    }
+    if yes(setting(config, "x11forwarding", "no")) {
+        flag(
+            Severity::Low,
+            "X11Forwarding yes: clients can forward X11 to the host".to_string(),
+        );
+    }
+    out.sort_by_key(|f| f.severity);
+    out
+}
+
+fn severity_color(severity: Severity, fg_dim: Hsla) -> Hsla {
+    match severity {
+        Severity::High => gpui::hsla(0.0, 0.8, 0.6, 1.0),
+        Severity::Medium => gpui::hsla(0.13, 0.8, 0.6, 1.0),
+        Severity::Low => fg_dim,
+    }
+}
+
+/// "effective settings (sshd -T)", or which files were read and why.
+fn source_text(config: &proto::SshdConfig) -> String {
+    if config.effective {
+        return "Effective settings (sshd -T).".to_string();
+    }
+    let mut s = format!(
+        "Read from {} (Match blocks skipped)",
+        if config.files.is_empty() {
+            "no readable config file".to_string()
+        } else {
+            config.files.join(", ")
+        }
+    );
+    if let Some(error) = &config.effective_error {
+        s.push_str(&format!("; sshd -T: {}", error));
+    }
+    s.push('.');
+    s
+}
+
+/// "Failed logins in the last 1d: 148 (journal)"
+fn failures_text(summary: &proto::AuthFailures) -> String {
+    let window = format_uptime(summary.window_secs);
+    match &summary.source {
+        Some(source) => format!(
+            "Failed logins in the last {}: {} ({})",
+            window, summary.total, source
+        ),
+        None => "Failed logins: no journal or auth log readable".to_string(),
+    }
+}
+
+/// "203.0.113.7: 120, last 3m ago"
+fn offender_text(count: &proto::AuthFailureCount, now: u64) -> String {
+    let age = Duration::from_secs(now.saturating_sub(count.last_usec / 1_000_000));
+    format!("{}: {}, last {}", count.key, count.count, format_age(age))
+}
+
+/// "sshd: 2 banned now, 12 since start, 150 failures (203.0.113.7 198.51.100.2)"
+fn jail_text(jail: &proto::Fail2banJail) -> String {
+    let mut s = format!(
+        "{}: {} banned now, {} since start, {} failures",
+        jail.name, jail.currently_banned, jail.total_banned, jail.total_failed
+    );
+    if !jail.banned.is_empty() {
+        s.push_str(&format!(" ({})", jail.banned.join(" ")));
+    }
+    s
+}
+
+/// Plain-text sshd audit and login failures, for Copy.
+pub(crate) fn security_text(
+    config: Option<&proto::SshdConfig>,
+    failures: Option<&proto::AuthFailures>,
+) -> String {
+    let mut out = String::new();
+    match config {
+        None => out.push_str("sshd is not installed.\n"),
+        Some(config) => {
+            out.push_str(&format!("{}\n", source_text(config)));
+            let findings = findings(config);
+            if findings.is_empty() {
+                out.push_str("No risky sshd settings found.\n");
+            }
+            for finding in findings {
+                let severity = match finding.severity {
+                    Severity::High => "high",
+                    Severity::Medium => "medium",
+                    Severity::Low => "low",
+                };
+                out.push_str(&format!("  [{}] {}\n", severity, finding.text));
+            }
+        }
+    }
+    let Some(summary) = failures else {
+        return out;
+    };
+    let now = unix_now();
+    out.push_str(&format!("{}\n", failures_text(summary)));
+    for (title, counts) in [
+        ("by address", &summary.by_address),
+        ("by user", &summary.by_user),
+    ] {
+        if counts.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("  {}:\n", title));
+        for count in counts {
+            out.push_str(&format!("    {}\n", offender_text(count, now)));
+        }
+    }
+    if let Some(fail2ban) = &summary.fail2ban {
+        out.push_str("fail2ban:\n");
+        for jail in &fail2ban.jails {
+            out.push_str(&format!("  {}\n", jail_text(jail)));
+        }
+        if let Some(error) = &fail2ban.error {
+            out.push_str(&format!("  ({})\n", error));
+        }
+    }
+    out
+}
+
+impl HostPanel {
+    /// Update the sshd settings audited in the panel (None: sshd is not
+    /// installed).
+    pub fn set_sshd_config(&mut self, config: Option<proto::SshdConfig>, cx: &mut Context<Self>) {
+        self.sshd_config = Some(config);
+        self.freshness.mark(Section::Security, SystemTime::now());
+        if let Some(sched) = self.scheduler_mut() {
+            sched.mark_fetched(Section::Security, Instant::now());
+        }
+        cx.notify();
+    }
+
+    /// Update the failed logins and fail2ban bans shown in the Security
+    /// section (fetched together with the sshd settings).
+    pub fn set_auth_failures(&mut self, summary: proto::AuthFailures, cx: &mut Context<Self>) {
+        self.auth_failures = Some(summary);
+        cx.notify();
+    }
+
+    /// Security section: header with controls, where the sshd settings came
+    /// from, then the risky ones, most severe first; then the top offending
+    /// addresses and targeted users of recent failed logins, and fail2ban's
+    /// jails.
+    pub(crate) fn render_security(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let section = div()
+            .flex()
+            .flex_col()
+            .gap_2()
+            .pl(ap.px(8.0))
+            .pr(ap.px(8.0))
+            .py(ap.px(8.0))
+            .border_b_1()
+            .border_color(pal.border)
+            .when(self.is_dimmed(Section::Security), |d| d.opacity(0.5))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .child(div().text_color(pal.fg).child("Security"))
+                    .child(self.render_section_controls(Section::Security, cx)),
+            );
+        let Some(config) = &self.sshd_config else {
+            return section.child("Not loaded yet: press ⟳.");
+        };
+
+        let section = match config {
+            None => section.child(div().text_color(pal.muted).child("sshd is not installed.")),
+            Some(config) => {
+                let findings = findings(config);
+                section
+                    .child(div().text_color(pal.muted).child(source_text(config)))
+                    .when(findings.is_empty(), |d| {
+                        d.child(
+                            div()
+                                .text_color(pal.fg_dim)
+                                .child("No risky sshd settings found."),
+                        )
+                    })
+                    .children(findings.into_iter().map(|finding| {
+                        div()
+                            .pl(ap.px(8.0))
+                            .text_color(severity_color(finding.severity, pal.fg_dim))
+                            .child(finding.text)
+                    }))
+            }
+        };
+        let Some(summary) = &self.auth_failures else {
+            return section;
+        };
+
+        let now = unix_now();
+        let offenders = |title: &'static str, counts: &[proto::AuthFailureCount]| {
+            div()
+                .flex()
+                .flex_col()
+                .pl(ap.px(8.0))
+                .when(!counts.is_empty(), |d| {
+                    d.child(div().text_color(pal.muted).child(title))
+                })
+                .children(counts.iter().take(SHOWN_OFFENDERS).map(|count| {
+                    div()
+                        .pl(ap.px(8.0))
+                        .text_color(pal.fg_dim)
+                        .child(offender_text(count, now))
+                }))
+        };
+        section
+            .child(div().text_color(pal.fg).child(failures_text(summary)))
+            .child(offenders("Top addresses", &summary.by_address))
+            .child(offenders("Top users", &summary.by_user))
+            .children(summary.fail2ban.as_ref().map(|fail2ban| {
+                div()
+                    .flex()
+                    .flex_col()
+                    .child(div().text_color(pal.fg).child("fail2ban"))
+                    .when(fail2ban.jails.is_empty() && fail2ban.error.is_none(), |d| {
+                        d.child(
+                            div()
+                                .pl(ap.px(8.0))
+                                .text_color(pal.muted)
+                                .child("No jails."),
+                        )
+                    })
+                    .children(fail2ban.jails.iter().map(|jail| {
+                        div()
+                            .pl(ap.px(8.0))
+                            .text_color(if jail.currently_banned > 0 {
+                                gpui::hsla(0.13, 0.8, 0.6, 1.0)
+                            } else {
+                                pal.fg_dim
+                            })
+                            .child(jail_text(jail))
+                    }))
+                    .children(fail2ban.error.as_ref().map(|error| {
+                        div()
+                            .pl(ap.px(8.0))
+                            .text_color(pal.muted)
+                            .child(error.clone())
+                    }))
+            }))
+    }
+}
@@ -0,0 +1,159 @@
+//! Service drawer: clicking a row of the services list shows the unit's main
+//! process, memory, restarts, ExecStart and unit file.
+
+use crate::units::format_bytes;
+use crate::{AgentRequest, HostPanel};
+use gpui::{div, prelude::*, Context, MouseButton};
+use slarti_proto as proto;
+use slarti_ui::Appearance;
+
+/// Emitted by the services list when a row is clicked.
+pub struct OpenService(pub String);
+
+impl HostPanel {
+    /// Open the drawer for `unit` and fetch its details.
+    pub(crate) fn open_service(&mut self, unit: String, cx: &mut Context<Self>) {
+        self.service_detail = None;
+        self.agent_requests
+            .push(AgentRequest::ServiceDetail(unit.clone()));
+        self.service_unit = Some(unit);
+        cx.notify();
+    }
+
+    fn close_service(&mut self, cx: &mut Context<Self>) {
+        self.service_unit = None;
+        self.service_detail = None;
+        cx.notify();
+    }
+
+    /// Show the agent's answer to a service detail request.
+    pub(crate) fn apply_service_response(
+        &mut self,
+        unit: String,
+        response: proto::Response,
+        cx: &mut Context<Self>,
+    ) {
+        // Ignore a late answer for a drawer closed or switched meanwhile.
+        if self.service_unit.as_ref() != Some(&unit) {
+            return;
+        }
+        match response {
+            proto::Response::ServiceDetailOk { detail, .. } => self.service_detail = Some(detail),
+            proto::Response::Error { message, .. } => {
+                self.service_unit = None;
+                self.push_progress(format!("{}: {}", unit, message), cx);
+            }
+            _ => {}
+        }
+    }
+
+    /// Drawer with the open unit's details.
+    pub(crate) fn render_service_drawer(&self, cx: &mut Context<Self>) -> Option<gpui::Div> {
+        let unit = self.service_unit.as_ref()?;
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let field = |label: &'static str, value: String| {
+            div()
+                .flex()
+                .gap_2()
+                .child(div().w(ap.px(96.0)).text_color(pal.muted).child(label))
+                .child(div().text_color(pal.fg_dim).child(value))
+        };
+
+        let mut body = div()
+            .id("ServiceDrawer")
+            .flex()
+            .flex_col()
+            .max_h(ap.px(240.0))
+            .overflow_y_scroll();
+        let title = match &self.service_detail {
+            None => {
+                body = body.child(div().text_color(pal.muted).child("loading…"));
+                unit.clone()
+            }
+            Some(d) => {
+                let none = || "-".to_string();
+                body = body
+                    .child(field(
+                        "State",
+                        format!(
+                            "{} ({}), {}",
+                            d.active_state, d.sub_state, d.unit_file_state
+                        ),
+                    ))
+                    .child(field(
+                        "Main PID",
+                        d.main_pid.map(|p| p.to_string()).unwrap_or_else(none),
+                    ))
+                    .child(field(
+                        "Memory",
+                        d.memory_bytes.map(format_bytes).unwrap_or_else(none),
+                    ))
+                    .child(field(
+                        "Restarts",
+                        d.restarts.map(|r| r.to_string()).unwrap_or_else(none),
+                    ))
+                    .child(field(
+                        "Active since",
+                        d.active_since.clone().unwrap_or_else(none),
+                    ))
+                    .children(
+                        d.exec_start
+                            .iter()
+                            .map(|cmd| field("ExecStart", cmd.clone())),
+                    )
+                    .child(field(
+                        "Unit file",
+                        d.fragment_path.clone().unwrap_or_else(none),
+                    ))
+                    .children(d.unit_file.as_ref().map(|text| {
+                        div()
+                            .pt(ap.px(4.0))
+                            .flex()
+                            .flex_col()
+                            .children(text.lines().map(|l| div().child(l.to_string())))
+                    }));
+                if d.description.is_empty() {
+                    d.unit.clone()
+                } else {
+                    format!("{}: {}", d.unit, d.description)
+                }
+            }
+        };
+
+        Some(
+            div()
+                .flex()
+                .flex_col()
+                .gap_1()
+                .p(ap.px(8.0))
+                .border_b_1()
+                .border_color(pal.border)
+                .child(
+                    div()
+                        .flex()
+                        .items_center()
+                        .justify_between()
+                        .child(div().text_color(pal.fg).child(title))
+                        .child(
+                            div()
+                                .px(ap.px(8.0))
+                                .h(ap.px(18.0))
+                                .rounded_sm()
+                                .border_1()
+                                .border_color(pal.border)
+                                .cursor_pointer()
+                                .text_color(pal.fg)
+                                .child("Close")
+                                .on_mouse_up(
+                                    MouseButton::Left,
+                                    cx.listener(|this: &mut Self, _ev, _window, cx| {
+                                        this.close_service(cx);
+                                    }),
+                                ),
+                        ),
+                )
+                .child(body),
+        )
+    }
+}
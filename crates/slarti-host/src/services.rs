@@ -0,0 +1,464 @@
+//! Services list shown by the host panel.
+//!
+//! Kept as its own entity so refreshed service data and filter toggles only
+//! repaint the list, not the whole host panel.
+
+use gpui::{div, point, prelude::*, px, Context, MouseButton, ScrollHandle, Window};
+use serde::{Deserialize, Serialize};
+use slarti_proto as proto;
+use slarti_ui::Appearance;
+use std::collections::HashSet;
+
+use crate::service_detail::OpenService;
+use crate::{csv, HostPanel};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum ServiceFilter {
+    All,
+    Active,
+    Failed,
+    Inactive,
+}
+
+/// Filters and scroll position of the list, kept per host by the host panel.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct ServicesView {
+    filter: ServiceFilter,
+    enabled_only: bool,
+    include_baseline: bool,
+    scroll_y: f32,
+}
+
+/// Filterable list of services reported by the remote agent.
+pub struct ServicesList {
+    // Services to show (live or cached); None while pending
+    services: Option<Vec<proto::ServiceInfo>>,
+    // Local baseline service names (from ~/.config/slarti/baseline_services.yaml)
+    baseline_names: HashSet<String>,
+    // Services filter state (by active state)
+    service_filter: ServiceFilter,
+    // When true (default), show only explicitly enabled services.
+    // When false, show services that are disabled or not explicitly enabled (enabled == Some(false) or None).
+    enabled_only: bool,
+    // When true, include baseline (system) services; when false (default), hide them.
+    include_baseline: bool,
+    scroll: ScrollHandle,
+    // Scroll offset to restore once the services have arrived and can be laid out
+    pending_scroll: Option<f32>,
+}
+
+impl gpui::EventEmitter<OpenService> for ServicesList {}
+
+impl ServicesList {
+    pub fn new(_cx: &mut Context<Self>) -> Self {
+        let (enabled_only, include_baseline) = Self::load_service_filter_prefs();
+        Self {
+            services: None,
+            baseline_names: Self::load_baseline_names(),
+            service_filter: ServiceFilter::All,
+            enabled_only,
+            include_baseline,
+            scroll: ScrollHandle::new(),
+            pending_scroll: None,
+        }
+    }
+
+    /// Current filters and scroll position.
+    pub(crate) fn view(&self) -> ServicesView {
+        ServicesView {
+            filter: self.service_filter,
+            enabled_only: self.enabled_only,
+            include_baseline: self.include_baseline,
+            scroll_y: self
+                .pending_scroll
+                .unwrap_or_else(|| self.scroll.offset().y.0),
+        }
+    }
+
+    /// Restore filters and scroll position; `None` resets to the saved defaults.
+    pub(crate) fn set_view(&mut self, view: Option<ServicesView>, cx: &mut Context<Self>) {
+        let view = view.unwrap_or_else(|| {
+            let (enabled_only, include_baseline) = Self::load_service_filter_prefs();
+            ServicesView {
+                filter: ServiceFilter::All,
+                enabled_only,
+                include_baseline,
+                scroll_y: 0.0,
+            }
+        });
+        self.service_filter = view.filter;
+        self.enabled_only = view.enabled_only;
+        self.include_baseline = view.include_baseline;
+        self.pending_scroll = Some(view.scroll_y);
+        cx.notify();
+    }
+
+    /// Replace the shown services; repaints only if the list actually changed.
+    pub fn set_services(
+        &mut self,
+        services: Option<Vec<proto::ServiceInfo>>,
+        cx: &mut Context<Self>,
+    ) {
+        if self.services != services {
+            self.services = services;
+            cx.notify();
+        }
+    }
+
+    /// Services passing the current filters, in list order.
+    fn filtered(&self) -> impl Iterator<Item = &proto::ServiceInfo> {
+        self.services
+            .iter()
+            .flatten()
+            // Enabled checkbox semantics:
+            // - when enabled_only == true: include only explicitly enabled (enabled == Some(true))
+            // - when enabled_only == false: include all services (no enabled filter)
+            .filter(|s| !self.enabled_only || s.enabled == Some(true))
+            // Baseline checkbox semantics:
+            // - when include_baseline == true: include even if baseline
+            // - when include_baseline == false: exclude if baseline
+            .filter(|s| self.include_baseline || !self.is_baseline(&s.name))
+            // State filter (composes with the above)
+            .filter(|s| match self.service_filter {
+                ServiceFilter::All => true,
+                ServiceFilter::Active => s.active_state == "active",
+                ServiceFilter::Failed => s.active_state == "failed",
+                ServiceFilter::Inactive => s.active_state == "inactive",
+            })
+    }
+
+    /// The shown (filtered) services as an aligned plain-text table, for the clipboard.
+    pub(crate) fn to_text(&self) -> Option<String> {
+        self.services.as_ref()?;
+        let rows: Vec<[&str; 3]> = self
+            .filtered()
+            .map(|s| {
+                [
+                    s.name.as_str(),
+                    s.active_state.as_str(),
+                    enabled_label(s.enabled),
+                ]
+            })
+            .collect();
+        let width = |col: usize, header: &str| {
+            rows.iter()
+                .map(|r| r[col].chars().count())
+                .chain([header.len()])
+                .max()
+                .unwrap_or(0)
+        };
+        let (name_w, state_w) = (width(0, "SERVICE"), width(1, "STATE"));
+        let mut out = format!("{:name_w$}  {:state_w$}  ENABLED\n", "SERVICE", "STATE");
+        for [name, state, enabled] in &rows {
+            out.push_str(&format!(
+                "{:name_w$}  {:state_w$}  {}\n",
+                name, state, enabled
+            ));
+        }
+        if rows.is_empty() {
+            out.push_str("(no services match the filters)\n");
+        }
+        Some(out)
+    }
+
+    /// The shown (filtered) services as CSV, in list order.
+    pub(crate) fn to_csv(&self) -> Option<String> {
+        self.services.as_ref()?;
+        let mut out = String::new();
+        csv::push_record(
+            &mut out,
+            ["service", "state", "sub_state", "enabled", "description"],
+        );
+        for s in self.filtered() {
+            csv::push_record(
+                &mut out,
+                [
+                    s.name.as_str(),
+                    s.active_state.as_str(),
+                    s.sub_state.as_str(),
+                    enabled_label(s.enabled),
+                    s.description.as_deref().unwrap_or(""),
+                ],
+            );
+        }
+        Some(out)
+    }
+
+    /// Short description of the applied filters for file names, e.g. "failed-enabled".
+    pub(crate) fn filter_label(&self) -> String {
+        let mut parts = vec![match self.service_filter {
+            ServiceFilter::All => "all",
+            ServiceFilter::Active => "active",
+            ServiceFilter::Failed => "failed",
+            ServiceFilter::Inactive => "inactive",
+        }];
+        if self.enabled_only {
+            parts.push("enabled");
+        }
+        if self.include_baseline {
+            parts.push("baseline");
+        }
+        parts.join("-")
+    }
+
+    fn set_filter(&mut self, filter: ServiceFilter, cx: &mut Context<Self>) {
+        if self.service_filter != filter {
+            self.service_filter = filter;
+            cx.notify();
+        }
+    }
+
+    fn service_filter_prefs_path() -> std::path::PathBuf {
+        let mut p = HostPanel::state_dir().unwrap_or_else(|| std::path::PathBuf::from("."));
+        let _ = std::fs::create_dir_all(&p);
+        p.push("services_filter_prefs.json");
+        p
+    }
+
+    fn load_baseline_names() -> HashSet<String> {
+        crate::load_list(&crate::config_path("baseline_services.yaml"))
+            .into_iter()
+            .collect()
+    }
+
+    fn is_baseline(&self, name: &str) -> bool {
+        self.baseline_names.contains(name)
+    }
+
+    fn save_service_filter_prefs(
+        enabled_only: bool,
+        include_baseline: bool,
+    ) -> std::io::Result<()> {
+        #[derive(Serialize, Deserialize)]
+        struct Prefs {
+            enabled_only: bool,
+            include_baseline: bool,
+        }
+        let prefs = Prefs {
+            enabled_only,
+            include_baseline,
+        };
+        let data = serde_json::to_vec_pretty(&prefs)
+            .unwrap_or_else(|_| serde_json::to_vec(&prefs).unwrap());
+        slarti_state::write(Self::service_filter_prefs_path(), data)
+    }
+
+    fn load_service_filter_prefs() -> (bool, bool) {
+        #[derive(Serialize, Deserialize)]
+        struct Prefs {
+            enabled_only: bool,
+            include_baseline: bool,
+        }
+        let path = Self::service_filter_prefs_path();
+        if let Ok(bytes) = slarti_state::read(path) {
+            if let Ok(p) = serde_json::from_slice::<Prefs>(&bytes) {
+                return (p.enabled_only, p.include_baseline);
+            }
+        }
+        // Defaults: enabled_only=true, include_baseline=false
+        (true, false)
+    }
+}
+
+impl gpui::Render for ServicesList {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let border = pal.border;
+
+        if self.services.is_none() {
+            return div()
+                .flex()
+                .flex_col()
+                .size_full()
+                .text_color(pal.fg_dim)
+                .child("(pending)");
+        }
+
+        if let Some(y) = self.pending_scroll.take() {
+            self.scroll.set_offset(point(px(0.0), px(y)));
+        }
+
+        // Filter buttons
+        let mk_filter_btn = |label: &'static str, filter: ServiceFilter| {
+            let active = self.service_filter == filter;
+            div()
+                .debug_selector(move || format!("services-filter-{}", label))
+                .px(ap.px(6.0))
+                .py(ap.px(2.0))
+                .rounded_sm()
+                .border_1()
+                .border_color(border)
+                .text_color(if active { pal.fg } else { pal.fg_dim })
+                .bg(if active {
+                    gpui::opaque_grey(0.2, 0.3)
+                } else {
+                    gpui::hsla(0.0, 0.0, 0.07, 1.0)
+                })
+                .cursor_pointer()
+                .on_mouse_up(
+                    MouseButton::Left,
+                    cx.listener(move |this: &mut Self, _ev, _w, cx| this.set_filter(filter, cx)),
+                )
+                .child(label)
+        };
+
+        let filter_bar = div()
+            .flex()
+            .items_center()
+            .gap_2()
+            .px(ap.px(8.0))
+            .py(ap.px(6.0))
+            .child(mk_filter_btn("All", ServiceFilter::All))
+            .child(mk_filter_btn("Active", ServiceFilter::Active))
+            .child(mk_filter_btn("Failed", ServiceFilter::Failed))
+            .child(mk_filter_btn("Inactive", ServiceFilter::Inactive))
+            .child(
+                div()
+                    .px(ap.px(8.0))
+                    .py(ap.px(2.0))
+                    .rounded_sm()
+                    .border_1()
+                    .border_color(border)
+                    .cursor_pointer()
+                    .text_color(pal.fg)
+                    .on_mouse_up(MouseButton::Left, {
+                        cx.listener(|this: &mut Self, _ev, _w, cx| {
+                            this.enabled_only = !this.enabled_only;
+                            let _ = Self::save_service_filter_prefs(
+                                this.enabled_only,
+                                this.include_baseline,
+                            );
+                            cx.notify();
+                        })
+                    })
+                    .child(if self.enabled_only {
+                        "Enabled only: on"
+                    } else {
+                        "Enabled only: off"
+                    }),
+            )
+            .child(
+                div()
+                    .px(ap.px(8.0))
+                    .py(ap.px(2.0))
+                    .rounded_sm()
+                    .border_1()
+                    .border_color(border)
+                    .cursor_pointer()
+                    .text_color(pal.fg)
+                    .on_mouse_up(MouseButton::Left, {
+                        cx.listener(|this: &mut Self, _ev, _w, cx| {
+                            this.include_baseline = !this.include_baseline;
+                            let _ = Self::save_service_filter_prefs(
+                                this.enabled_only,
+                                this.include_baseline,
+                            );
+                            cx.notify();
+                        })
+                    })
+                    .child(if self.include_baseline {
+                        "Include baseline: on"
+                    } else {
+                        "Include baseline: off"
+                    }),
+            );
+
+        // Render rows
+        let mut rows = Vec::new();
+        for s in self.filtered() {
+            // Colorize by active state
+            let color = if s.active_state == "active" {
+                gpui::green()
+            } else if s.active_state == "failed" {
+                gpui::hsla(0.0, 0.8, 0.6, 1.0) // red-ish
+            } else if s.active_state == "activating" || s.active_state == "deactivating" {
+                gpui::hsla(0.13, 0.8, 0.6, 1.0) // orange-ish
+            } else {
+                pal.fg_dim
+            };
+
+            let enabled_str = enabled_label(s.enabled);
+            let unit = s.name.clone();
+            rows.push(
+                div()
+                    .debug_selector(|| format!("service-{}", s.name))
+                    .flex()
+                    .items_center()
+                    .h(ap.px(20.0))
+                    .px(ap.px(8.0))
+                    .justify_between()
+                    .cursor_pointer()
+                    .on_mouse_up(
+                        MouseButton::Left,
+                        cx.listener(move |_this: &mut Self, _ev, _w, cx| {
+                            cx.emit(OpenService(unit.clone()));
+                        }),
+                    )
+                    // name (left, flexible)
+                    .child(
+                        div()
+                            .text_color(if s.enabled == Some(false) {
+                                pal.muted
+                            } else {
+                                pal.fg
+                            })
+                            .child(s.name.clone()),
+                    )
+                    // fixed-width right container for aligned columns
+                    .child(
+                        div()
+                            .flex()
+                            .w(ap.px(220.0))
+                            .justify_between()
+                            // state column (fixed width, colored)
+                            .child(
+                                div()
+                                    .w(ap.px(120.0))
+                                    .text_color(color)
+                                    .child(s.active_state.clone()),
+                            )
+                            // enabled column (fixed width, dim if disabled)
+                            .child(
+                                div()
+                                    .w(ap.px(100.0))
+                                    .text_color(if s.enabled == Some(false) {
+                                        pal.muted
+                                    } else {
+                                        pal.fg_dim
+                                    })
+                                    .child(enabled_str),
+                            ),
+                    ),
+            );
+        }
+
+        // Filters stay put; the rows scroll.
+        div()
+            .flex()
+            .flex_col()
+            .size_full()
+            .gap_2()
+            .child(filter_bar)
+            .child(
+                div()
+                    .id("ServicesScroll")
+                    .flex()
+                    .flex_col()
+                    .flex_1()
+                    .min_h_0()
+                    .gap_1()
+                    .overflow_y_scroll()
+                    .track_scroll(&self.scroll)
+                    .children(rows),
+            )
+    }
+}
+
+fn enabled_label(enabled: Option<bool>) -> &'static str {
+    match enabled {
+        Some(true) => "enabled",
+        Some(false) => "disabled",
+        None => "n/a",
+    }
+}
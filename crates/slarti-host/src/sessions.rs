@@ -0,0 +1,97 @@
+//! Sessions section: who is logged in to the host (utmp), so an operator can
+//! see who else is on the box before doing maintenance.
+
+use crate::freshness::format_age;
+use crate::{unix_now, HostPanel, Section};
+use gpui::{div, prelude::*, Context};
+use slarti_proto as proto;
+use slarti_ui::Appearance;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Where a login came from: "203.0.113.7", "bastion (203.0.113.7)", or
+/// "local".
+fn origin(session: &proto::LoginSession) -> String {
+    match (&session.host, &session.ip) {
+        (Some(host), Some(ip)) if host != ip => format!("{} ({})", host, ip),
+        (Some(host), _) => host.clone(),
+        (None, Some(ip)) => ip.clone(),
+        (None, None) => "local".to_string(),
+    }
+}
+
+/// "ops on pts/0 from 203.0.113.7, logged in 2h ago (pid 2290)"
+fn session_text(session: &proto::LoginSession, now: u64) -> String {
+    format!(
+        "{} on {} from {}, logged in {} (pid {})",
+        session.user,
+        session.tty,
+        origin(session),
+        format_age(Duration::from_secs(
+            now.saturating_sub(session.login_usec / 1_000_000)
+        )),
+        session.pid
+    )
+}
+
+/// Plain-text login sessions, for Copy.
+pub(crate) fn sessions_text(sessions: &[proto::LoginSession]) -> String {
+    if sessions.is_empty() {
+        return "Nobody logged in.\n".to_string();
+    }
+    let now = unix_now();
+    let mut out = String::new();
+    for session in sessions {
+        out.push_str(&format!("{}\n", session_text(session, now)));
+    }
+    out
+}
+
+impl HostPanel {
+    /// Update the login sessions shown in the panel.
+    pub fn set_sessions(&mut self, sessions: Vec<proto::LoginSession>, cx: &mut Context<Self>) {
+        self.sessions = Some(sessions);
+        self.freshness.mark(Section::Sessions, SystemTime::now());
+        if let Some(sched) = self.scheduler_mut() {
+            sched.mark_fetched(Section::Sessions, Instant::now());
+        }
+        cx.notify();
+    }
+
+    /// Sessions section: header with controls, then one row per login.
+    pub(crate) fn render_sessions(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let section = div()
+            .flex()
+            .flex_col()
+            .gap_2()
+            .pl(ap.px(8.0))
+            .pr(ap.px(8.0))
+            .py(ap.px(8.0))
+            .border_b_1()
+            .border_color(pal.border)
+            .when(self.is_dimmed(Section::Sessions), |d| d.opacity(0.5))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .child(div().text_color(pal.fg).child("Sessions"))
+                    .child(self.render_section_controls(Section::Sessions, cx)),
+            );
+        let Some(sessions) = &self.sessions else {
+            return section.child("Not loaded yet: press ⟳.");
+        };
+        if sessions.is_empty() {
+            return section.child(div().text_color(pal.muted).child("Nobody logged in."));
+        }
+
+        let now = unix_now();
+        section.child(div().flex().flex_col().children(sessions.iter().map(|s| {
+            div()
+                .pl(ap.px(8.0))
+                .text_color(pal.fg_dim)
+                .child(session_text(s, now))
+        })))
+    }
+}
@@ -0,0 +1,160 @@
+//! Snapshot store: last known data per host, persisted under the state dir.
+//!
+//! Every successful fetch updates the host's snapshot, written to disk shortly
+//! after off the UI thread, so that the panel can still show something useful
+//! (clearly labeled as cached) when the host is unreachable later.
+
+use serde::{Deserialize, Serialize};
+use slarti_proto as proto;
+#[cfg(test)]
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Last known data for a host, with per-section capture times (unix seconds).
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HostSnapshot {
+    pub sys_info: Option<proto::SysInfo>,
+    pub sys_info_at: Option<u64>,
+    pub services: Option<Vec<proto::ServiceInfo>>,
+    pub services_at: Option<u64>,
+    /// Count of pending package updates
+    pub pending_updates: Option<usize>,
+    pub pending_updates_at: Option<u64>,
+}
+
+impl HostSnapshot {
+    pub fn set_sys_info(&mut self, info: proto::SysInfo, at: SystemTime) {
+        self.sys_info = Some(info);
+        self.sys_info_at = Some(unix_secs(at));
+    }
+
+    pub fn set_services(&mut self, services: Vec<proto::ServiceInfo>, at: SystemTime) {
+        self.services = Some(services);
+        self.services_at = Some(unix_secs(at));
+    }
+
+    pub fn set_pending_updates(&mut self, count: usize, at: SystemTime) {
+        self.pending_updates = Some(count);
+        self.pending_updates_at = Some(unix_secs(at));
+    }
+}
+
+/// Reads and writes `HostSnapshot`s as `<dir>/<alias>.json`.
+#[derive(Clone, Debug)]
+pub struct SnapshotStore {
+    dir: PathBuf,
+}
+
+impl SnapshotStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, alias: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", file_stem(alias)))
+    }
+
+    /// Load the snapshot for `alias`, if one was saved.
+    pub fn load(&self, alias: &str) -> Option<HostSnapshot> {
+        let bytes = slarti_state::read(self.path(alias)).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    /// Persist the snapshot for `alias`.
+    pub fn save(&self, alias: &str, snapshot: &HostSnapshot) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let data = serde_json::to_vec_pretty(snapshot)
+            .unwrap_or_else(|_| serde_json::to_vec(snapshot).unwrap());
+        slarti_state::write(self.path(alias), data)
+    }
+}
+
+/// File name (without ".json") for `alias`: the alias itself when it is a
+/// plain name, else percent-encoded, so that no alias reaches outside the
+/// store's dir. Encoded names always contain '%', which plain ones cannot, so
+/// the two never collide.
+fn file_stem(alias: &str) -> String {
+    let plain = !alias.is_empty()
+        && !alias.starts_with('.')
+        && alias
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.@+".contains(c));
+    if plain {
+        return alias.to_string();
+    }
+    if alias.is_empty() {
+        return "%".to_string();
+    }
+    alias
+        .bytes()
+        .map(|b| {
+            if b.is_ascii_alphanumeric() || b"-_@+".contains(&b) {
+                (b as char).to_string()
+            } else {
+                format!("%{:02X}", b)
+            }
+        })
+        .collect()
+}
+
+pub(crate) fn unix_secs(at: SystemTime) -> u64 {
+    at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+/// Convert stored unix seconds back to a `SystemTime`.
+pub fn from_unix_secs(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+/// Format unix seconds as "YYYY-MM-DD HH:MM UTC".
+pub fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    // Civil-from-days (Howard Hinnant), valid for the proleptic Gregorian calendar.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        rem / 3_600,
+        (rem % 3_600) / 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn snapshot_paths_stay_in_the_store() {
+        let store = SnapshotStore::new("/state/snapshots");
+        assert_eq!(
+            store.path("web1.example.com"),
+            PathBuf::from("/state/snapshots/web1.example.com.json")
+        );
+        for alias in ["../../.config/x", "..", ".hidden", "a/b", "a\\b", "", "%41"] {
+            let path = store.path(alias);
+            assert_eq!(
+                path.parent(),
+                Some(Path::new("/state/snapshots")),
+                "{}",
+                alias
+            );
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            assert!(!name.starts_with('.') && !name.contains('/'), "{}", name);
+        }
+        assert_ne!(store.path("%41"), store.path("A"));
+    }
+}
@@ -0,0 +1,633 @@
+//! Storage section: software RAID (md) arrays with their state, members and
+//! rebuild progress, then ZFS pools, btrfs filesystems, LVM volume groups and
+//! the space on mounted filesystems, flagging degraded, erroring or nearly
+//! full ones.
+
+use crate::units::format_bytes;
+use crate::{HostPanel, Section};
+use gpui::{div, prelude::*, Context, Hsla};
+use slarti_proto as proto;
+use slarti_ui::Appearance;
+use std::time::{Instant, SystemTime};
+
+fn degraded_color() -> Hsla {
+    gpui::hsla(0.0, 0.8, 0.6, 1.0)
+}
+
+fn sync_color() -> Hsla {
+    gpui::hsla(0.13, 0.8, 0.6, 1.0)
+}
+
+/// "md0 raid1, 932 GiB, 1/2 working: clean, degraded, recovering"
+fn array_text(array: &proto::RaidArray) -> String {
+    if !array.active {
+        return format!("{} inactive", array.name);
+    }
+    let mut s = format!(
+        "{} {}, {}, {}/{} working",
+        array.name,
+        array.level.as_deref().unwrap_or("md"),
+        format_bytes(array.size_bytes),
+        array.working,
+        array.devices
+    );
+    if array.read_only {
+        s.push_str(", read-only");
+    }
+    match &array.state {
+        Some(state) => s.push_str(&format!(": {}", state)),
+        None if array.degraded => s.push_str(": degraded"),
+        None => {}
+    }
+    s
+}
+
+/// "1h 5m", "12m", "40s"
+fn remaining_text(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3_599 => format!("{}m", secs / 60),
+        _ => format!("{}h {}m", secs / 3_600, secs % 3_600 / 60),
+    }
+}
+
+/// "recovery 8.5%, 12m left at 12.5 MiB/s", or "resync pending"
+fn sync_text(sync: &proto::RaidSync) -> String {
+    let Some(percent) = sync.percent else {
+        return format!("{} pending", sync.action);
+    };
+    let mut s = format!("{} {:.1}%", sync.action, percent);
+    if let Some(secs) = sync.finish_secs {
+        s.push_str(&format!(", {} left", remaining_text(secs)));
+    }
+    if let Some(speed) = sync.speed_bytes_per_sec {
+        s.push_str(&format!(
+            " at {:.1} MiB/s",
+            speed as f64 / (1024.0 * 1024.0)
+        ));
+    }
+    s
+}
+
+/// "sdb1 [1] spare rebuilding" (mdadm's state, else the mdstat flags)
+fn member_text(member: &proto::RaidMember) -> String {
+    let state = member.state.clone().unwrap_or_else(|| {
+        if member.faulty {
+            "faulty".to_string()
+        } else if member.spare {
+            "spare".to_string()
+        } else {
+            "in sync".to_string()
+        }
+    });
+    format!("{} [{}] {}", member.device, member.slot, state)
+}
+
+/// "2 arrays, 1 degraded"
+fn summary(arrays: &[proto::RaidArray]) -> String {
+    let degraded = arrays.iter().filter(|a| a.degraded).count();
+    let mut s = format!(
+        "{} array{}",
+        arrays.len(),
+        if arrays.len() == 1 { "" } else { "s" }
+    );
+    if degraded > 0 {
+        s.push_str(&format!(", {} degraded", degraded));
+    }
+    s
+}
+
+/// Thin pools fuller than this are flagged.
+const THIN_POOL_WARN_PERCENT: f32 = 90.0;
+
+/// Filesystems fuller than this are flagged.
+const MOUNT_WARN_PERCENT: f32 = 90.0;
+
+fn used_percent(mount: &proto::MountInfo) -> f32 {
+    if mount.size_bytes == 0 {
+        return 0.0;
+    }
+    mount.used_bytes as f32 * 100.0 / mount.size_bytes as f32
+}
+
+/// "/home ext4 (/dev/sda2): 40 GiB of 100 GiB used (40%), 60 GiB free"
+fn mount_text(mount: &proto::MountInfo) -> String {
+    format!(
+        "{} {} ({}): {} of {} used ({:.0}%), {} free",
+        mount.mountpoint,
+        mount.fstype,
+        mount.device,
+        format_bytes(mount.used_bytes),
+        format_bytes(mount.size_bytes),
+        used_percent(mount),
+        format_bytes(mount.available_bytes)
+    )
+}
+
+fn zfs_pool_healthy(pool: &proto::ZfsPool) -> bool {
+    pool.state == "ONLINE" && pool.devices.iter().all(zfs_device_healthy)
+}
+
+fn zfs_device_healthy(device: &proto::ZfsDevice) -> bool {
+    // Spares are "AVAIL" or "INUSE", not "ONLINE".
+    matches!(device.state.as_str(), "ONLINE" | "AVAIL" | "INUSE" | "")
+        && device.read_errors + device.write_errors + device.checksum_errors == 0
+}
+
+/// "sdb UNAVAIL, 0 read, 0 write, 3 cksum errors: cannot open"
+fn zfs_device_text(device: &proto::ZfsDevice) -> String {
+    let mut s = format!("{} {}", device.name, device.state);
+    if device.read_errors + device.write_errors + device.checksum_errors > 0 {
+        s.push_str(&format!(
+            ", {} read, {} write, {} cksum errors",
+            device.read_errors, device.write_errors, device.checksum_errors
+        ));
+    }
+    if let Some(note) = &device.note {
+        s.push_str(&format!(": {}", note));
+    }
+    s
+}
+
+fn btrfs_device_errors(device: &proto::BtrfsDevice) -> u64 {
+    device.write_io_errs
+        + device.read_io_errs
+        + device.flush_io_errs
+        + device.corruption_errs
+        + device.generation_errs
+}
+
+/// "/dev/sda1: 2 corruption, 1 read errors", or "/dev/sda1: no errors"
+fn btrfs_device_text(device: &proto::BtrfsDevice) -> String {
+    let counts: Vec<String> = [
+        (device.write_io_errs, "write"),
+        (device.read_io_errs, "read"),
+        (device.flush_io_errs, "flush"),
+        (device.corruption_errs, "corruption"),
+        (device.generation_errs, "generation"),
+    ]
+    .into_iter()
+    .filter(|(n, _)| *n > 0)
+    .map(|(n, kind)| format!("{} {}", n, kind))
+    .collect();
+    if counts.is_empty() {
+        format!("{}: no errors", device.path)
+    } else {
+        format!("{}: {} errors", device.path, counts.join(", "))
+    }
+}
+
+fn btrfs_healthy(fs: &proto::BtrfsFilesystem) -> bool {
+    fs.devices.iter().all(|d| btrfs_device_errors(d) == 0)
+        && !fs.scrub.as_ref().is_some_and(|s| s.errors_found)
+}
+
+/// "scrub finished, started Sun Oct 12 00:00:01 2025, took 0:01:12: no errors found"
+fn scrub_text(scrub: &proto::BtrfsScrub) -> String {
+    if scrub.status == "never" {
+        return "never scrubbed".to_string();
+    }
+    let mut s = format!("scrub {}", scrub.status);
+    if let Some(started) = &scrub.started {
+        s.push_str(&format!(", started {}", started));
+    }
+    if let Some(duration) = &scrub.duration {
+        s.push_str(&format!(", took {}", duration));
+    }
+    if let Some(summary) = &scrub.error_summary {
+        s.push_str(&format!(": {}", summary));
+    }
+    s
+}
+
+/// "vg0, 100 GiB, 4.0 MiB free, 2 PVs, partial"
+fn vg_text(vg: &proto::LvmVolumeGroup) -> String {
+    let mut s = format!(
+        "{}, {}, {} free, {} PV{}",
+        vg.name,
+        format_bytes(vg.size_bytes),
+        format_bytes(vg.free_bytes),
+        vg.pv_count,
+        if vg.pv_count == 1 { "" } else { "s" }
+    );
+    if vg.partial {
+        s.push_str(", partial (PVs missing)");
+    }
+    s
+}
+
+fn lv_healthy(lv: &proto::LvmVolume) -> bool {
+    lv.health.is_none() && lv.data_percent.is_none_or(|p| p < THIN_POOL_WARN_PERCENT)
+}
+
+/// "pool thin-pool, 50 GiB, 81.5% data" or "data thin in pool, 10 GiB, inactive"
+fn lv_text(lv: &proto::LvmVolume) -> String {
+    let mut s = format!("{} {}", lv.name, lv.kind);
+    if let Some(pool) = &lv.pool {
+        s.push_str(&format!(" in {}", pool));
+    }
+    s.push_str(&format!(", {}", format_bytes(lv.size_bytes)));
+    if let Some(percent) = lv.data_percent {
+        s.push_str(&format!(", {:.1}% data", percent));
+    }
+    if !lv.active {
+        s.push_str(", inactive");
+    }
+    if let Some(health) = &lv.health {
+        s.push_str(&format!(": {}", health));
+    }
+    s
+}
+
+fn stacks_empty(stacks: &proto::StorageStacks) -> bool {
+    stacks.zfs.is_empty() && stacks.btrfs.is_empty() && stacks.lvm.is_empty()
+}
+
+/// Plain-text RAID arrays, ZFS pools, btrfs filesystems, LVM volume groups
+/// and mounted filesystems, for Copy.
+pub(crate) fn storage_text(
+    arrays: Option<&Vec<proto::RaidArray>>,
+    stacks: Option<&proto::StorageStacks>,
+    mounts: Option<&Vec<proto::MountInfo>>,
+) -> Option<String> {
+    let mut out = stacks_text(arrays, stacks);
+    if let Some(mounts) = mounts.filter(|m| !m.is_empty()) {
+        let out = out.get_or_insert_with(String::new);
+        out.push_str("Filesystems\n");
+        for mount in mounts {
+            out.push_str(&format!("  {}\n", mount_text(mount)));
+        }
+    }
+    out
+}
+
+fn stacks_text(
+    arrays: Option<&Vec<proto::RaidArray>>,
+    stacks: Option<&proto::StorageStacks>,
+) -> Option<String> {
+    if arrays.is_none() && stacks.is_none() {
+        return None;
+    }
+    let arrays = arrays.map(Vec::as_slice).unwrap_or_default();
+    if arrays.is_empty() && stacks.is_none_or(stacks_empty) {
+        return Some("No software RAID arrays, ZFS pools, btrfs or LVM.\n".to_string());
+    }
+    let mut out = String::new();
+    if !arrays.is_empty() {
+        out.push_str(&format!("{}\n", summary(arrays)));
+    }
+    for array in arrays {
+        out.push_str(&format!("  {}\n", array_text(array)));
+        if let Some(sync) = &array.sync {
+            out.push_str(&format!("    {}\n", sync_text(sync)));
+        }
+        for member in &array.members {
+            out.push_str(&format!("    {}\n", member_text(member)));
+        }
+    }
+    let Some(stacks) = stacks else {
+        return Some(out);
+    };
+    for pool in &stacks.zfs {
+        out.push_str(&format!("zpool {} {}\n", pool.name, pool.state));
+        for line in [&pool.status, &pool.scan].into_iter().flatten() {
+            out.push_str(&format!("  {}\n", line));
+        }
+        // The pool's own row repeats its name and state.
+        for device in pool.devices.iter().skip(1) {
+            let indent = "  ".repeat(device.depth as usize);
+            out.push_str(&format!("{}{}\n", indent, zfs_device_text(device)));
+        }
+        if let Some(errors) = &pool.errors {
+            out.push_str(&format!("  {}\n", errors));
+        }
+    }
+    for fs in &stacks.btrfs {
+        out.push_str(&format!("btrfs {}\n", fs.mountpoint));
+        for device in &fs.devices {
+            out.push_str(&format!("  {}\n", btrfs_device_text(device)));
+        }
+        if let Some(scrub) = &fs.scrub {
+            out.push_str(&format!("  {}\n", scrub_text(scrub)));
+        }
+    }
+    for vg in &stacks.lvm {
+        out.push_str(&format!("LVM {}\n", vg_text(vg)));
+        for lv in &vg.volumes {
+            out.push_str(&format!("  {}\n", lv_text(lv)));
+        }
+    }
+    Some(out)
+}
+
+impl HostPanel {
+    /// Update the software RAID arrays shown in the panel.
+    pub fn set_raid_arrays(&mut self, arrays: Vec<proto::RaidArray>, cx: &mut Context<Self>) {
+        self.raid_arrays = Some(arrays);
+        self.freshness.mark(Section::Storage, SystemTime::now());
+        if let Some(sched) = self.scheduler_mut() {
+            sched.mark_fetched(Section::Storage, Instant::now());
+        }
+        cx.notify();
+    }
+
+    /// Update the ZFS pools, btrfs filesystems and LVM volume groups shown in
+    /// the panel.
+    pub fn set_storage_stacks(&mut self, stacks: proto::StorageStacks, cx: &mut Context<Self>) {
+        self.storage_stacks = Some(stacks);
+        self.freshness.mark(Section::Storage, SystemTime::now());
+        if let Some(sched) = self.scheduler_mut() {
+            sched.mark_fetched(Section::Storage, Instant::now());
+        }
+        cx.notify();
+    }
+
+    /// Update the mounted filesystems and their space shown in the panel.
+    pub fn set_mounts(&mut self, mounts: Vec<proto::MountInfo>, cx: &mut Context<Self>) {
+        self.mounts = Some(mounts);
+        self.freshness.mark(Section::Storage, SystemTime::now());
+        if let Some(sched) = self.scheduler_mut() {
+            sched.mark_fetched(Section::Storage, Instant::now());
+        }
+        cx.notify();
+    }
+
+    /// Storage section: header with controls, a summary, then per RAID array
+    /// its state, rebuild progress and members (degraded ones in red),
+    /// followed by the ZFS, btrfs, LVM and filesystem blocks.
+    pub(crate) fn render_storage(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let section = div()
+            .flex()
+            .flex_col()
+            .gap_2()
+            .pl(ap.px(8.0))
+            .pr(ap.px(8.0))
+            .py(ap.px(8.0))
+            .border_b_1()
+            .border_color(pal.border)
+            .when(self.is_dimmed(Section::Storage), |d| d.opacity(0.5))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .child(div().text_color(pal.fg).child("Storage"))
+                    .child(self.render_section_controls(Section::Storage, cx)),
+            );
+        if self.raid_arrays.is_none() && self.storage_stacks.is_none() && self.mounts.is_none() {
+            return section.child("Not loaded yet: press ⟳.");
+        }
+        let stacks = [
+            self.render_zfs(cx),
+            self.render_btrfs(cx),
+            self.render_lvm(cx),
+            self.render_mounts(cx),
+        ];
+        let arrays = self.raid_arrays.as_deref().unwrap_or_default();
+        if arrays.is_empty() {
+            let empty = if self.storage_stacks.as_ref().is_none_or(stacks_empty) {
+                "No software RAID (md) arrays, ZFS pools, btrfs or LVM."
+            } else {
+                "No software RAID (md) arrays."
+            };
+            return section
+                .child(div().text_color(pal.muted).child(empty))
+                .children(stacks);
+        }
+
+        let any_degraded = arrays.iter().any(|a| a.degraded);
+        section
+            .child(
+                div()
+                    .text_color(if any_degraded {
+                        degraded_color()
+                    } else {
+                        pal.fg
+                    })
+                    .child(summary(arrays)),
+            )
+            .child(div().flex().flex_col().children(arrays.iter().map(|array| {
+                div()
+                    .flex()
+                    .flex_col()
+                    .pl(ap.px(8.0))
+                    .child(
+                        div()
+                            .text_color(if array.degraded {
+                                degraded_color()
+                            } else {
+                                pal.fg
+                            })
+                            .child(array_text(array)),
+                    )
+                    .children(array.sync.as_ref().map(|sync| {
+                        div()
+                            .pl(ap.px(8.0))
+                            .text_color(sync_color())
+                            .child(sync_text(sync))
+                    }))
+                    .children(array.members.iter().map(|member| {
+                        div()
+                            .pl(ap.px(8.0))
+                            .text_color(if member.faulty {
+                                degraded_color()
+                            } else if member.rebuilding {
+                                sync_color()
+                            } else {
+                                pal.fg_dim
+                            })
+                            .child(member_text(member))
+                    }))
+            })))
+            .children(stacks)
+    }
+
+    /// ZFS pools with their scan and config tree, unhealthy pools and devices
+    /// in red.
+    fn render_zfs(&self, cx: &mut Context<Self>) -> Option<gpui::Div> {
+        let pools = self
+            .storage_stacks
+            .as_ref()
+            .map(|s| &s.zfs)
+            .filter(|p| !p.is_empty())?;
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        Some(
+            div()
+                .flex()
+                .flex_col()
+                .child(div().text_color(pal.muted).child("ZFS"))
+                .children(pools.iter().map(|pool| {
+                    let healthy = zfs_pool_healthy(pool);
+                    div()
+                        .flex()
+                        .flex_col()
+                        .pl(ap.px(8.0))
+                        .child(
+                            div()
+                                .text_color(if healthy { pal.fg } else { degraded_color() })
+                                .child(format!("{} {}", pool.name, pool.state)),
+                        )
+                        .children(pool.status.clone().map(|status| {
+                            div()
+                                .pl(ap.px(8.0))
+                                .text_color(degraded_color())
+                                .child(status)
+                        }))
+                        .children(pool.scan.clone().map(|scan| {
+                            div()
+                                .pl(ap.px(8.0))
+                                .text_color(if scan.contains(" in progress") {
+                                    sync_color()
+                                } else {
+                                    pal.fg_dim
+                                })
+                                .child(scan)
+                        }))
+                        .children(pool.devices.iter().skip(1).map(|device| {
+                            div()
+                                .pl(ap.px(8.0 * device.depth as f32))
+                                .text_color(if zfs_device_healthy(device) {
+                                    pal.fg_dim
+                                } else {
+                                    degraded_color()
+                                })
+                                .child(zfs_device_text(device))
+                        }))
+                        .children(pool.errors.clone().map(|errors| {
+                            div()
+                                .pl(ap.px(8.0))
+                                .text_color(if errors == "No known data errors" {
+                                    pal.fg_dim
+                                } else {
+                                    degraded_color()
+                                })
+                                .child(errors)
+                        }))
+                })),
+        )
+    }
+
+    /// Mounted btrfs filesystems with their device error counters and last
+    /// scrub, errors in red.
+    fn render_btrfs(&self, cx: &mut Context<Self>) -> Option<gpui::Div> {
+        let filesystems = self
+            .storage_stacks
+            .as_ref()
+            .map(|s| &s.btrfs)
+            .filter(|f| !f.is_empty())?;
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        Some(
+            div()
+                .flex()
+                .flex_col()
+                .child(div().text_color(pal.muted).child("btrfs"))
+                .children(filesystems.iter().map(|fs| {
+                    div()
+                        .flex()
+                        .flex_col()
+                        .pl(ap.px(8.0))
+                        .child(
+                            div()
+                                .text_color(if btrfs_healthy(fs) {
+                                    pal.fg
+                                } else {
+                                    degraded_color()
+                                })
+                                .child(fs.mountpoint.clone()),
+                        )
+                        .children(fs.devices.iter().map(|device| {
+                            div()
+                                .pl(ap.px(8.0))
+                                .text_color(if btrfs_device_errors(device) == 0 {
+                                    pal.fg_dim
+                                } else {
+                                    degraded_color()
+                                })
+                                .child(btrfs_device_text(device))
+                        }))
+                        .children(fs.scrub.as_ref().map(|scrub| {
+                            div()
+                                .pl(ap.px(8.0))
+                                .text_color(if scrub.errors_found {
+                                    degraded_color()
+                                } else if scrub.status == "running" {
+                                    sync_color()
+                                } else {
+                                    pal.fg_dim
+                                })
+                                .child(scrub_text(scrub))
+                        }))
+                })),
+        )
+    }
+
+    /// LVM volume groups and their logical volumes; partial groups, unhealthy
+    /// volumes and nearly full thin pools stand out.
+    fn render_lvm(&self, cx: &mut Context<Self>) -> Option<gpui::Div> {
+        let groups = self
+            .storage_stacks
+            .as_ref()
+            .map(|s| &s.lvm)
+            .filter(|g| !g.is_empty())?;
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        Some(
+            div()
+                .flex()
+                .flex_col()
+                .child(div().text_color(pal.muted).child("LVM"))
+                .children(groups.iter().map(|vg| {
+                    div()
+                        .flex()
+                        .flex_col()
+                        .pl(ap.px(8.0))
+                        .child(
+                            div()
+                                .text_color(if vg.partial { degraded_color() } else { pal.fg })
+                                .child(vg_text(vg)),
+                        )
+                        .children(vg.volumes.iter().map(|lv| {
+                            div()
+                                .pl(ap.px(8.0))
+                                .text_color(if lv.health.is_some() {
+                                    degraded_color()
+                                } else if !lv_healthy(lv) {
+                                    sync_color()
+                                } else if lv.active {
+                                    pal.fg_dim
+                                } else {
+                                    pal.muted
+                                })
+                                .child(lv_text(lv))
+                        }))
+                })),
+        )
+    }
+
+    /// Mounted filesystems with their space, nearly full ones in red.
+    fn render_mounts(&self, cx: &mut Context<Self>) -> Option<gpui::Div> {
+        let mounts = self.mounts.as_ref().filter(|m| !m.is_empty())?;
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        Some(
+            div()
+                .flex()
+                .flex_col()
+                .child(div().text_color(pal.muted).child("Filesystems"))
+                .children(mounts.iter().map(|mount| {
+                    div()
+                        .pl(ap.px(8.0))
+                        .text_color(if used_percent(mount) >= MOUNT_WARN_PERCENT {
+                            degraded_color()
+                        } else {
+                            pal.fg_dim
+                        })
+                        .child(mount_text(mount))
+                })),
+        )
+    }
+}
@@ -0,0 +1,42 @@
+//! Byte counts as the sections show them, so a process RSS, a cgroup, a
+//! disk and a cache all read the same way.
+
+/// "512 B", "48 KiB", "1.2 GiB", "932 GiB"
+pub(crate) fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if value >= 10.0 || unit == 0 {
+        format!("{:.0} {}", value, UNITS[unit])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_counts_stay_in_bytes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+    }
+
+    #[test]
+    fn units_scale_with_one_decimal_below_ten() {
+        assert_eq!(format_bytes(48 * 1024), "48 KiB");
+        assert_eq!(format_bytes(2 * 1024 * 1024), "2.0 MiB");
+        assert_eq!(format_bytes(18_400_000), "18 MiB");
+        assert_eq!(format_bytes(1_288_490_189), "1.2 GiB");
+        assert_eq!(format_bytes(1_000_204_886_016), "932 GiB");
+        assert_eq!(format_bytes(3 << 50), "3.0 PiB");
+    }
+}
@@ -0,0 +1,122 @@
+//! Updates section: packages with a newer version available, as the host's
+//! package manager reports them, and the installed packages on request.
+
+use crate::{HostPanel, Section};
+use gpui::{div, prelude::*, Context};
+use slarti_proto as proto;
+use slarti_ui::Appearance;
+use std::time::{Instant, SystemTime};
+
+/// "bash 5.2.15-2+b8 → 5.2.15-2+b13" ("kernel.x86_64 → 6.8.9-300.fc40"
+/// where the installed version is not reported).
+fn update_text(u: &proto::PackageUpdate) -> String {
+    match &u.current {
+        Some(current) => format!("{} {} → {}", u.name, current, u.available),
+        None => format!("{} → {}", u.name, u.available),
+    }
+}
+
+/// "12 updates available (dpkg)"
+fn summary_text(updates: &proto::PendingUpdates) -> String {
+    let Some(manager) = updates.manager else {
+        return "No supported package manager found.".to_string();
+    };
+    let manager = format!("{:?}", manager).to_lowercase();
+    match updates.packages.len() {
+        0 => format!("Up to date ({})", manager),
+        1 => format!("1 update available ({})", manager),
+        n => format!("{} updates available ({})", n, manager),
+    }
+}
+
+/// Plain-text pending updates, for Copy.
+pub(crate) fn updates_text(updates: &proto::PendingUpdates) -> String {
+    let mut out = format!("{}\n", summary_text(updates));
+    for u in &updates.packages {
+        out.push_str(&format!("  {}\n", update_text(u)));
+    }
+    out
+}
+
+impl HostPanel {
+    /// Update the pending package updates shown in the panel; their count is
+    /// kept in the host's snapshot for the badge in the host list.
+    pub fn set_updates(&mut self, updates: proto::PendingUpdates, cx: &mut Context<Self>) {
+        let now = SystemTime::now();
+        self.snapshot
+            .set_pending_updates(updates.packages.len(), now);
+        self.save_snapshot(cx);
+        self.updates = Some(updates);
+        self.freshness.mark(Section::Updates, now);
+        if let Some(sched) = self.scheduler_mut() {
+            sched.mark_fetched(Section::Updates, Instant::now());
+        }
+        cx.notify();
+    }
+
+    /// Updates section: header with controls and count badge, then the
+    /// packages and their versions, then the installed packages block.
+    pub(crate) fn render_updates(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let count = self
+            .updates
+            .as_ref()
+            .map(|u| u.packages.len())
+            .filter(|&n| n > 0);
+        let section = div()
+            .flex()
+            .flex_col()
+            .gap_2()
+            .pl(ap.px(8.0))
+            .pr(ap.px(8.0))
+            .py(ap.px(8.0))
+            .border_b_1()
+            .border_color(pal.border)
+            .when(self.is_dimmed(Section::Updates), |d| d.opacity(0.5))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .gap_2()
+                            .child(div().text_color(pal.fg).child("Updates"))
+                            .children(count.map(|n| {
+                                div()
+                                    .px(ap.px(6.0))
+                                    .rounded_full()
+                                    .bg(pal.accent)
+                                    .text_color(pal.bg)
+                                    .child(n.to_string())
+                            })),
+                    )
+                    .child(self.render_section_controls(Section::Updates, cx)),
+            );
+        let packages = self.render_packages(cx);
+        let Some(updates) = &self.updates else {
+            return section.child("Not loaded yet: press ⟳.").child(packages);
+        };
+
+        section
+            .child(div().text_color(pal.fg_dim).child(summary_text(updates)))
+            .child(
+                div()
+                    .id("UpdatesScroll")
+                    .flex()
+                    .flex_col()
+                    .max_h(ap.px(200.0))
+                    .overflow_y_scroll()
+                    .children(updates.packages.iter().map(|u| {
+                        div()
+                            .pl(ap.px(8.0))
+                            .text_color(pal.fg_dim)
+                            .child(update_text(u))
+                    })),
+            )
+            .child(packages)
+    }
+}
@@ -4,7 +4,23 @@ use gpui::{
     div, prelude::*, px, AnyElement, App, Context, FocusHandle, Focusable, MouseButton,
     MouseUpEvent, Window,
 };
+use slarti_discovery::mdns::DiscoveredHost;
+use slarti_discovery::tailscale::TailscalePeer;
 use slarti_sshcfg::model::{ConfigTree, FileNode, HostEntry};
+use slarti_ui::Appearance;
+
+/// Alias of the built-in entry for the machine running slarti (its agent runs without ssh).
+///
+/// ssh config splits aliases on whitespace, so no `Host` line can declare this
+/// one; a user's own `Host localhost` stays an ordinary ssh host.
+pub const LOCAL_HOST: &str = "this machine";
+
+/// Expansion key of the containers group.
+const CONTAINERS_KEY: &str = "__containers__";
+/// Expansion key of the group of hosts found on the local network.
+const DISCOVERED_KEY: &str = "__discovered__";
+/// Expansion key of the group of tailnet peers without an ssh config entry.
+const TAILSCALE_KEY: &str = "__tailscale__";
 
 /// Input properties for the HostsPanel.
 pub struct HostsPanelProps {
@@ -18,6 +34,10 @@ pub struct HostsPanelProps {
 /// Renders an expandable tree of SSH hosts from an SSH config.
 /// - Top-level label is "hosts".
 /// - Each included file forms a group in the tree; hosts declared directly in the file appear as leaves.
+/// - Running containers and pods, once discovered, are listed in a "containers" group.
+/// - SSH servers found via mDNS are listed under "Discovered", each with an "add to config" action.
+/// - Tailnet peers mark matching hosts with their online state; the others get a "Tailscale" group.
+/// - Aliases defined more than once are flagged; the flag lists every definition.
 /// - Clicking a host leaf invokes the provided `on_select(alias)` callback.
 pub struct HostsPanel {
     focus: FocusHandle,
@@ -25,6 +45,22 @@ pub struct HostsPanel {
     on_select: Arc<dyn Fn(String, &mut Window, &mut Context<HostsPanel>) + Send + Sync>,
     // Persisted expand/collapse state keyed by canonical group path
     expanded_groups: std::collections::HashSet<String>,
+    // Aliases of running docker containers / kubernetes pods (see slarti_ssh::container)
+    containers: Vec<String>,
+    // SSH servers found via mDNS that are not in the ssh config yet
+    discovered: Vec<DiscoveredHost>,
+    // Last "add to config" failure, shown in the Discovered group
+    discovered_error: Option<String>,
+    // Peers of the local tailscale daemon
+    tailscale_peers: Vec<TailscalePeer>,
+    // Online state of ssh config aliases that are tailnet peers
+    tailnet: std::collections::HashMap<String, bool>,
+    // Peers with no ssh config entry, listed in their own group
+    tailscale_only: Vec<TailscalePeer>,
+    // Aliases defined by several Host entries -> (file, line) of each, first wins
+    duplicates: std::collections::HashMap<String, Vec<(std::path::PathBuf, usize)>>,
+    // Alias whose duplicate definitions are listed
+    duplicates_open: Option<String>,
 }
 
 impl HostsPanel {
@@ -37,11 +73,137 @@ impl HostsPanel {
                 expanded.insert(group_key(&group.path));
             }
         }
-        Self {
+        let mut panel = Self {
             focus: cx.focus_handle(),
             tree: props.tree,
             on_select: props.on_select,
             expanded_groups: expanded,
+            containers: Vec::new(),
+            discovered: Vec::new(),
+            discovered_error: None,
+            tailscale_peers: Vec::new(),
+            tailnet: std::collections::HashMap::new(),
+            tailscale_only: Vec::new(),
+            duplicates: std::collections::HashMap::new(),
+            duplicates_open: None,
+        };
+        panel.find_duplicates();
+        panel
+    }
+
+    /// Recompute the aliases defined more than once in the current tree.
+    fn find_duplicates(&mut self) {
+        self.duplicates = slarti_sshcfg::load::duplicate_aliases(&self.tree)
+            .into_iter()
+            .map(|d| (d.alias, d.definitions))
+            .collect();
+    }
+
+    /// Replace the tailnet peers. Peers matching an ssh config entry (by alias
+    /// or HostName) show their online state on that entry; the rest are listed
+    /// in a "Tailscale" group.
+    pub fn set_tailscale_peers(&mut self, peers: Vec<TailscalePeer>, cx: &mut Context<Self>) {
+        if self.tailscale_peers != peers {
+            self.tailscale_peers = peers;
+            self.merge_tailnet();
+            cx.notify();
+        }
+    }
+
+    fn merge_tailnet(&mut self) {
+        fn walk<'a>(node: &'a FileNode, out: &mut Vec<&'a HostEntry>) {
+            out.extend(node.hosts.iter());
+            for inc in &node.includes {
+                walk(inc, out);
+            }
+        }
+        let mut entries = Vec::new();
+        walk(&self.tree.root, &mut entries);
+
+        self.tailnet.clear();
+        self.tailscale_only.clear();
+        for peer in &self.tailscale_peers {
+            let mut matched = false;
+            for entry in &entries {
+                let Some(alias) = first_concrete_alias(entry) else {
+                    continue;
+                };
+                if peer.matches(alias) || entry.get("hostname").is_some_and(|h| peer.matches(h)) {
+                    self.tailnet.insert(alias.to_string(), peer.online);
+                    matched = true;
+                }
+            }
+            if !matched {
+                self.tailscale_only.push(peer.clone());
+            }
+        }
+    }
+
+    /// Replace the hosts found on the local network; ones already in the ssh
+    /// config (by alias or hostname) are left out.
+    pub fn set_discovered(&mut self, hosts: Vec<DiscoveredHost>, cx: &mut Context<Self>) {
+        let known = slarti_sshcfg::load::list_aliases(&self.tree);
+        let hosts: Vec<DiscoveredHost> = hosts
+            .into_iter()
+            .filter(|h| !known.contains(&h.alias()) && !known.contains(&h.hostname))
+            .collect();
+        if self.discovered != hosts {
+            self.discovered = hosts;
+            cx.notify();
+        }
+    }
+
+    /// Append a discovered host to ~/.ssh/config and reload the tree; the file
+    /// IO runs on the background executor.
+    fn add_discovered(&mut self, host: DiscoveredHost, cx: &mut Context<Self>) {
+        let alias = host.alias();
+        let params = host.config_params();
+        cx.spawn(async move |this, cx| {
+            let added = cx
+                .background_executor()
+                .spawn(async move {
+                    let home = dirs_next::home_dir()
+                        .ok_or_else(|| "could not determine home directory".to_string())?;
+                    slarti_sshcfg::edit::append_host(
+                        &home.join(".ssh").join("config"),
+                        &alias,
+                        &params,
+                    )
+                    .map_err(|e| format!("{:#}", e))?;
+                    slarti_sshcfg::load::load_user_config_tree().map_err(|e| format!("{:#}", e))
+                })
+                .await;
+            let _ = this.update(cx, |panel, cx| {
+                match added {
+                    Ok(tree) => {
+                        panel.tree = tree;
+                        panel.merge_tailnet();
+                        panel.find_duplicates();
+                        panel.discovered.retain(|h| h != &host);
+                        panel.discovered_error = None;
+                    }
+                    Err(e) => panel.discovered_error = Some(e),
+                }
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    /// Re-read ~/.ssh/config, e.g. after the app rewrote it.
+    pub fn reload(&mut self, cx: &mut Context<Self>) -> Result<(), String> {
+        self.tree = slarti_sshcfg::load::load_user_config_tree().map_err(|e| format!("{:#}", e))?;
+        self.merge_tailnet();
+        self.find_duplicates();
+        cx.notify();
+        Ok(())
+    }
+
+    /// Replace the listed containers and pods (`docker:`/`k8s:` aliases).
+    pub fn set_containers(&mut self, aliases: Vec<String>, cx: &mut Context<Self>) {
+        if self.containers != aliases {
+            self.containers = aliases;
+            cx.notify();
         }
     }
 
@@ -62,6 +224,11 @@ impl HostsPanel {
         cx.notify();
     }
 
+    /// Select a host alias as if its leaf had been clicked.
+    pub fn select(&mut self, alias: String, window: &mut Window, cx: &mut Context<Self>) {
+        (self.on_select)(alias, window, cx);
+    }
+
     fn on_select_host(
         &mut self,
         _: &MouseUpEvent,
@@ -78,23 +245,52 @@ impl HostsPanel {
         window: &mut Window,
         cx: &mut Context<Self>,
     ) -> impl IntoElement {
-        // Visual constants
-        let bg = gpui::rgb(0x0b0b0b);
-        let fg = gpui::white();
-        let border = gpui::opaque_grey(0.2, 0.7);
+        // Visual constants (follow the app-wide appearance)
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let bg = pal.bg;
+        let fg = pal.fg;
+        let border = pal.border;
 
         // Render root label and its children
         let mut children: Vec<AnyElement> = Vec::new();
 
+        // Built-in entry for this machine, above the ssh config hosts
+        children.push(
+            div()
+                .debug_selector(|| "hosts-local".into())
+                .flex()
+                .items_center()
+                .gap_2()
+                .h(ap.px(28.0))
+                .px(ap.px(8.0))
+                .bg(bg)
+                .border_b_1()
+                .border_color(border)
+                .text_color(fg)
+                .cursor_pointer()
+                .on_mouse_up(
+                    MouseButton::Left,
+                    cx.listener(|this, ev, win, cx| {
+                        this.on_select_host(ev, win, cx, LOCAL_HOST.to_string())
+                    }),
+                )
+                .child("⌂")
+                .child(LOCAL_HOST)
+                .child(div().text_color(pal.muted).child("no ssh"))
+                .into_any_element(),
+        );
+
         // Root header
         let root_key = "__root__".to_string();
         let root_expanded = self.expanded_groups.contains(&root_key);
         children.push(
             div()
+                .debug_selector(|| "hosts-root".into())
                 .flex()
                 .items_center()
-                .h(px(28.0))
-                .px(px(8.0))
+                .h(ap.px(28.0))
+                .px(ap.px(8.0))
                 .bg(bg)
                 .border_b_1()
                 .border_color(border)
@@ -152,14 +348,292 @@ impl HostsPanel {
             }
         }
 
-        // Container
+        // Containers and pods, selectable like hosts
+        if !self.containers.is_empty() {
+            children.push(self.render_containers(cx).into_any_element());
+        }
+
+        // Tailnet peers without an ssh config entry
+        if !self.tailscale_only.is_empty() {
+            children.push(self.render_tailscale(cx).into_any_element());
+        }
+
+        // SSH servers found on the local network
+        if !self.discovered.is_empty() || self.discovered_error.is_some() {
+            children.push(self.render_discovered(cx).into_any_element());
+        }
+
+        // Container; outlined while focused so keyboard users can see where input goes.
         div()
+            .track_focus(&self.focus)
+            .on_mouse_down(
+                MouseButton::Left,
+                cx.listener(|this, _ev, window, _cx| window.focus(&this.focus)),
+            )
             .flex()
             .flex_col()
             .size_full()
             .bg(bg)
+            .border_1()
+            .border_color(if self.focus.is_focused(window) {
+                pal.focus_ring
+            } else {
+                gpui::transparent_black()
+            })
             .children(children)
     }
+
+    fn render_containers(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let key = CONTAINERS_KEY.to_string();
+        let expanded = self.expanded_groups.contains(&key);
+
+        let mut items: Vec<AnyElement> = Vec::new();
+        items.push(
+            div()
+                .debug_selector(|| "hosts-containers".into())
+                .flex()
+                .items_center()
+                .h(ap.px(28.0))
+                .px(ap.px(8.0))
+                .text_color(pal.fg)
+                .cursor_pointer()
+                .on_mouse_up(
+                    MouseButton::Left,
+                    cx.listener(move |this, ev, win, cx| {
+                        this.on_toggle_group(ev, win, cx, key.clone())
+                    }),
+                )
+                .child(format!(
+                    "{} containers ({})",
+                    if expanded { "▾" } else { "▸" },
+                    self.containers.len()
+                ))
+                .into_any_element(),
+        );
+        if expanded {
+            for alias in &self.containers {
+                items.push(
+                    div()
+                        .debug_selector(|| format!("hosts-leaf-{}", alias))
+                        .flex()
+                        .items_center()
+                        .gap_2()
+                        .h(ap.px(22.0))
+                        .pl(ap.px(24.0))
+                        .pr(ap.px(8.0))
+                        .text_color(pal.fg)
+                        .cursor_pointer()
+                        .on_mouse_up(
+                            MouseButton::Left,
+                            cx.listener({
+                                let alias = alias.clone();
+                                move |this, ev, win, cx| {
+                                    this.on_select_host(ev, win, cx, alias.clone())
+                                }
+                            }),
+                        )
+                        .child(
+                            div()
+                                .w(ap.px(6.0))
+                                .h(ap.px(6.0))
+                                .rounded_full()
+                                .bg(agent_status_color(alias, pal.muted)),
+                        )
+                        .child(alias.clone())
+                        .into_any_element(),
+                );
+            }
+        }
+
+        div()
+            .flex()
+            .flex_col()
+            .border_b_1()
+            .border_color(pal.border)
+            .children(items)
+    }
+
+    fn render_tailscale(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let key = TAILSCALE_KEY.to_string();
+        let expanded = self.expanded_groups.contains(&key);
+        let online = self.tailscale_only.iter().filter(|p| p.online).count();
+
+        let mut items: Vec<AnyElement> = Vec::new();
+        items.push(
+            div()
+                .debug_selector(|| "hosts-tailscale".into())
+                .flex()
+                .items_center()
+                .h(ap.px(28.0))
+                .px(ap.px(8.0))
+                .text_color(pal.fg)
+                .cursor_pointer()
+                .on_mouse_up(
+                    MouseButton::Left,
+                    cx.listener(move |this, ev, win, cx| {
+                        this.on_toggle_group(ev, win, cx, key.clone())
+                    }),
+                )
+                .child(format!(
+                    "{} Tailscale ({}/{} online)",
+                    if expanded { "▾" } else { "▸" },
+                    online,
+                    self.tailscale_only.len()
+                ))
+                .into_any_element(),
+        );
+        if expanded {
+            for peer in &self.tailscale_only {
+                items.push(
+                    div()
+                        .debug_selector(|| format!("hosts-leaf-{}", peer.host_name))
+                        .flex()
+                        .items_center()
+                        .gap_2()
+                        .h(ap.px(22.0))
+                        .pl(ap.px(24.0))
+                        .pr(ap.px(8.0))
+                        .text_color(if peer.online { pal.fg } else { pal.muted })
+                        .cursor_pointer()
+                        .on_mouse_up(
+                            MouseButton::Left,
+                            cx.listener({
+                                let target = peer.target();
+                                move |this, ev, win, cx| {
+                                    this.on_select_host(ev, win, cx, target.clone())
+                                }
+                            }),
+                        )
+                        .child(div().w(ap.px(6.0)).h(ap.px(6.0)).rounded_full().bg(
+                            if peer.online {
+                                gpui::green()
+                            } else {
+                                pal.muted
+                            },
+                        ))
+                        .child(peer.host_name.clone())
+                        .child(div().text_color(pal.muted).child(peer.os.clone()))
+                        .into_any_element(),
+                );
+            }
+        }
+
+        div()
+            .flex()
+            .flex_col()
+            .border_b_1()
+            .border_color(pal.border)
+            .children(items)
+    }
+
+    fn render_discovered(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let key = DISCOVERED_KEY.to_string();
+        let expanded = self.expanded_groups.contains(&key);
+
+        let mut items: Vec<AnyElement> = Vec::new();
+        items.push(
+            div()
+                .debug_selector(|| "hosts-discovered".into())
+                .flex()
+                .items_center()
+                .h(ap.px(28.0))
+                .px(ap.px(8.0))
+                .text_color(pal.fg)
+                .cursor_pointer()
+                .on_mouse_up(
+                    MouseButton::Left,
+                    cx.listener(move |this, ev, win, cx| {
+                        this.on_toggle_group(ev, win, cx, key.clone())
+                    }),
+                )
+                .child(format!(
+                    "{} Discovered ({})",
+                    if expanded { "▾" } else { "▸" },
+                    self.discovered.len()
+                ))
+                .into_any_element(),
+        );
+        if expanded {
+            for host in &self.discovered {
+                let add = div()
+                    .px(ap.px(6.0))
+                    .rounded_sm()
+                    .border_1()
+                    .border_color(pal.border)
+                    .cursor_pointer()
+                    .text_color(pal.muted)
+                    .child("add to config")
+                    .on_mouse_up(MouseButton::Left, {
+                        let host = host.clone();
+                        cx.listener(move |this, _ev, _win, cx| {
+                            // Adding does not select the host.
+                            cx.stop_propagation();
+                            this.add_discovered(host.clone(), cx);
+                        })
+                    });
+                let endpoint = if host.port == 22 {
+                    host.hostname.clone()
+                } else {
+                    format!("{}:{}", host.hostname, host.port)
+                };
+                items.push(
+                    div()
+                        .debug_selector(|| format!("hosts-leaf-{}", host.name))
+                        .flex()
+                        .items_center()
+                        .justify_between()
+                        .gap_2()
+                        .h(ap.px(22.0))
+                        .pl(ap.px(24.0))
+                        .pr(ap.px(8.0))
+                        .text_color(pal.fg)
+                        .cursor_pointer()
+                        .on_mouse_up(
+                            MouseButton::Left,
+                            cx.listener({
+                                let hostname = host.hostname.clone();
+                                move |this, ev, win, cx| {
+                                    this.on_select_host(ev, win, cx, hostname.clone())
+                                }
+                            }),
+                        )
+                        .child(
+                            div()
+                                .flex()
+                                .items_center()
+                                .gap_2()
+                                .child(host.name.clone())
+                                .child(div().text_color(pal.muted).child(endpoint)),
+                        )
+                        .child(add)
+                        .into_any_element(),
+                );
+            }
+            if let Some(e) = &self.discovered_error {
+                items.push(
+                    div()
+                        .pl(ap.px(24.0))
+                        .pr(ap.px(8.0))
+                        .text_color(gpui::red())
+                        .child(format!("add to config failed: {}", e))
+                        .into_any_element(),
+                );
+            }
+        }
+
+        div()
+            .flex()
+            .flex_col()
+            .border_b_1()
+            .border_color(pal.border)
+            .children(items)
+    }
 }
 
 impl Focusable for HostsPanel {
@@ -202,7 +676,7 @@ fn expanded_state_path() -> std::path::PathBuf {
 
 fn load_expanded_groups() -> std::collections::HashSet<String> {
     let path = expanded_state_path();
-    if let Ok(bytes) = std::fs::read(path) {
+    if let Ok(bytes) = slarti_state::read(path) {
         if let Ok(vec) = serde_json::from_slice::<Vec<String>>(&bytes) {
             return vec.into_iter().collect();
         }
@@ -214,7 +688,7 @@ fn save_expanded_groups(set: &std::collections::HashSet<String>) -> std::io::Res
     let vec: Vec<String> = set.iter().cloned().collect();
     let bytes =
         serde_json::to_vec_pretty(&vec).unwrap_or_else(|_| serde_json::to_vec(&vec).unwrap());
-    std::fs::write(expanded_state_path(), bytes)
+    slarti_state::write(expanded_state_path(), bytes)
 }
 
 // -----------------
@@ -231,23 +705,26 @@ fn render_group_block(
     window: &mut Window,
     cx: &mut Context<HostsPanel>,
 ) -> impl IntoElement {
-    let fg = gpui::white();
-    let border = gpui::opaque_grey(0.2, 0.7);
+    let ap = Appearance::get(cx);
+    let pal = ap.palette();
+    let fg = pal.fg;
+    let border = pal.border;
 
     let expanded = panel.expanded_groups.contains(key);
-    let pad = px((depth as f32) * 16.0);
+    let pad = ap.px((depth as f32) * 16.0);
 
     let mut items: Vec<AnyElement> = Vec::new();
 
     // Group header
     items.push(
         div()
+            .debug_selector(|| format!("hosts-group-{}", label))
             .flex()
             .items_center()
             .gap_2()
-            .h(px(24.0))
+            .h(ap.px(24.0))
             .pl(pad)
-            .pr(px(8.0))
+            .pr(ap.px(8.0))
             .text_color(fg)
             .cursor_pointer()
             .on_mouse_up(
@@ -260,10 +737,10 @@ fn render_group_block(
             // status dot (placeholder color for now)
             .child(
                 div()
-                    .w(px(8.0))
-                    .h(px(8.0))
+                    .w(ap.px(8.0))
+                    .h(ap.px(8.0))
                     .rounded_full()
-                    .bg(gpui::opaque_grey(1.0, 0.5)),
+                    .bg(pal.muted),
             )
             .child(if expanded {
                 format!("▾ {}", label)
@@ -287,13 +764,14 @@ fn render_group_block(
                 );
                 items.push(
                     div()
+                        .debug_selector(|| format!("hosts-leaf-{}", alias))
                         .flex()
                         .items_center()
                         .gap_2()
-                        .h(px(22.0))
-                        .pl(px((depth as f32 + 1.0) * 24.0))
-                        .pr(px(8.0))
-                        .text_color(gpui::opaque_grey(1.0, 0.95))
+                        .h(ap.px(22.0))
+                        .pl(ap.px((depth as f32 + 1.0) * 24.0))
+                        .pr(ap.px(8.0))
+                        .text_color(pal.fg)
                         .cursor_pointer()
                         .on_mouse_up(
                             MouseButton::Left,
@@ -304,45 +782,97 @@ fn render_group_block(
                                 }
                             }),
                         )
-                        // status dot (placeholder color for now)
-                        .child({
-                            // Determine status color from cached agent state:
-                            // - green: last_seen_ok == true
-                            // - yellow: last_seen_ok == false && last_deployed_version present and != expected
-                            // - red: last_seen_ok == false && last_deployed_version present and == expected
-                            // - gray: no state
-                            let expected = env!("CARGO_PKG_VERSION");
-                            let color = (|| {
-                                if let Some(mut p) = dirs_next::data_local_dir() {
-                                    p.push("slarti");
-                                    p.push("agents");
-                                    p.push(format!("{}.json", alias));
-                                    if let Ok(s) = std::fs::read_to_string(p) {
-                                        #[derive(serde::Deserialize)]
-                                        struct AgentState {
-                                            last_seen_ok: bool,
-                                            last_deployed_version: Option<String>,
-                                        }
-                                        if let Ok(st) = serde_json::from_str::<AgentState>(&s) {
-                                            if st.last_seen_ok {
-                                                return gpui::green();
-                                            }
-                                            if let Some(ver) = st.last_deployed_version {
-                                                if ver != expected {
-                                                    return gpui::yellow();
-                                                }
-                                                return gpui::red();
+                        .child(
+                            div()
+                                .w(ap.px(6.0))
+                                .h(ap.px(6.0))
+                                .rounded_full()
+                                .bg(agent_status_color(alias, pal.muted)),
+                        )
+                        .child(display)
+                        // Tailnet peer: online state from the tailscale daemon
+                        .when_some(panel.tailnet.get(alias).copied(), |d, online| {
+                            d.child(
+                                div()
+                                    .text_color(if online { gpui::green() } else { pal.muted })
+                                    .child(if online {
+                                        "tailnet"
+                                    } else {
+                                        "tailnet (offline)"
+                                    }),
+                            )
+                        })
+                        // Defined more than once: this entry is read first or shadowed
+                        .when_some(panel.duplicates.get(alias), |d, defs| {
+                            let used = defs
+                                .first()
+                                .is_some_and(|(p, l)| *p == host.source && *l == host.line);
+                            d.child(
+                                div()
+                                    .text_color(gpui::hsla(0.13, 0.8, 0.6, 1.0))
+                                    .child(if used {
+                                        "⚠ duplicate"
+                                    } else {
+                                        "⚠ duplicate (shadowed)"
+                                    })
+                                    .on_mouse_up(
+                                        MouseButton::Left,
+                                        cx.listener({
+                                            let alias = alias.to_string();
+                                            move |this, _ev, _win, cx| {
+                                                cx.stop_propagation();
+                                                this.duplicates_open = if this
+                                                    .duplicates_open
+                                                    .as_ref()
+                                                    == Some(&alias)
+                                                {
+                                                    None
+                                                } else {
+                                                    Some(alias.clone())
+                                                };
+                                                cx.notify();
                                             }
-                                        }
-                                    }
-                                }
-                                gpui::opaque_grey(1.0, 0.5)
-                            })();
-                            div().w(px(6.0)).h(px(6.0)).rounded_full().bg(color)
+                                        }),
+                                    ),
+                            )
                         })
-                        .child(display)
                         .into_any_element(),
                 );
+                // Every definition of an open duplicate, each opening its file
+                if panel.duplicates_open.as_deref() == Some(alias) {
+                    for (i, (path, line)) in panel.duplicates[alias].iter().enumerate() {
+                        items.push(
+                            div()
+                                .h(ap.px(20.0))
+                                .pl(ap.px((depth as f32 + 2.0) * 24.0))
+                                .pr(ap.px(8.0))
+                                .text_color(pal.fg_dim)
+                                .cursor_pointer()
+                                .hover(|d| d.text_color(pal.fg))
+                                .child(format!(
+                                    "{}:{}{}",
+                                    path.display(),
+                                    line,
+                                    if i == 0 {
+                                        " (read first: its params win)"
+                                    } else {
+                                        " (only fills params left unset)"
+                                    }
+                                ))
+                                .on_mouse_up(
+                                    MouseButton::Left,
+                                    cx.listener({
+                                        let path = path.clone();
+                                        move |_this, _ev, _win, cx| {
+                                            cx.stop_propagation();
+                                            cx.open_with_system(&path);
+                                        }
+                                    }),
+                                )
+                                .into_any_element(),
+                        );
+                    }
+                }
             }
         }
 
@@ -376,6 +906,39 @@ fn render_group_block(
 // Misc helpers
 // -------------
 
+/// Status dot color from the cached agent state for `alias`:
+/// - green: last_seen_ok == true
+/// - yellow: last_seen_ok == false && last_deployed_version present and != expected
+/// - red: last_seen_ok == false && last_deployed_version present and == expected
+/// - `muted`: no state
+fn agent_status_color(alias: &str, muted: gpui::Hsla) -> gpui::Hsla {
+    let expected = env!("CARGO_PKG_VERSION");
+    if let Some(mut p) = dirs_next::data_local_dir() {
+        p.push("slarti");
+        p.push("agents");
+        p.push(format!("{}.json", alias));
+        if let Ok(s) = slarti_state::read_to_string(p) {
+            #[derive(serde::Deserialize)]
+            struct AgentState {
+                last_seen_ok: bool,
+                last_deployed_version: Option<String>,
+            }
+            if let Ok(st) = serde_json::from_str::<AgentState>(&s) {
+                if st.last_seen_ok {
+                    return gpui::green();
+                }
+                if let Some(ver) = st.last_deployed_version {
+                    if ver != expected {
+                        return gpui::yellow();
+                    }
+                    return gpui::red();
+                }
+            }
+        }
+    }
+    muted
+}
+
 fn first_concrete_alias(entry: &HostEntry) -> Option<&str> {
     entry
         .patterns
@@ -417,3 +980,130 @@ pub fn make_hosts_panel(
 ) -> impl FnOnce(&mut Context<HostsPanel>) -> HostsPanel {
     move |cx| HostsPanel::new(cx, props)
 }
+
+#[cfg(test)]
+mod tests {
+    //! Render snapshots of the hosts tree in its main states, so refactors of
+    //! the render code can be checked (see `slarti_ui::test_support`).
+
+    use super::*;
+    use gpui::{size, Entity, Modifiers, TestAppContext, VisualTestContext};
+    use slarti_ui::test_support::{assert_snapshot, layout, TempState};
+    use std::sync::Mutex;
+
+    const SNAPSHOTS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots");
+
+    /// Regions tagged with `debug_selector` in the tree.
+    const REGIONS: &[&str] = &[
+        "hosts-local",
+        "hosts-root",
+        "hosts-group-~/.ssh/config",
+        "hosts-leaf-web1",
+        "hosts-leaf-db1",
+        "hosts-containers",
+        "hosts-leaf-docker:redis",
+        "hosts-tailscale",
+        "hosts-leaf-nas",
+        "hosts-discovered",
+        "hosts-leaf-raspberrypi",
+    ];
+
+    const CONFIG: &str = "Host web1\n  HostName 10.0.0.5\n\nHost db1\n  User postgres\n";
+
+    type Selected = Arc<Mutex<Vec<String>>>;
+
+    fn open(
+        cx: &mut TestAppContext,
+        state: &TempState,
+    ) -> (Entity<HostsPanel>, Selected, &mut VisualTestContext) {
+        let tree = slarti_sshcfg::load::parse_config(CONFIG, &state.path().join("config"))
+            .expect("test config parses");
+        let selected = Selected::default();
+        let on_select = {
+            let selected = selected.clone();
+            Arc::new(
+                move |alias: String, _: &mut Window, _: &mut Context<HostsPanel>| {
+                    selected.lock().unwrap().push(alias);
+                },
+            )
+        };
+        let (panel, cx) = cx.add_window_view(|_window, cx| {
+            HostsPanel::new(cx, HostsPanelProps { tree, on_select })
+        });
+        cx.simulate_resize(size(px(320.0), px(800.0)));
+        cx.run_until_parked();
+        (panel, selected, cx)
+    }
+
+    fn click(cx: &mut VisualTestContext, selector: &'static str) {
+        let bounds = cx
+            .debug_bounds(selector)
+            .unwrap_or_else(|| panic!("{} is painted", selector));
+        cx.simulate_click(bounds.center(), Modifiers::none());
+        cx.run_until_parked();
+    }
+
+    #[gpui::test]
+    fn no_selection(cx: &mut TestAppContext) {
+        let state = TempState::new("hosts-panel");
+        let (_panel, selected, cx) = open(cx, &state);
+        assert_snapshot(SNAPSHOTS, "hosts_panel_initial", &layout(cx, REGIONS));
+        assert!(selected.lock().unwrap().is_empty());
+    }
+
+    #[gpui::test]
+    fn expand_and_select(cx: &mut TestAppContext) {
+        let state = TempState::new("hosts-panel");
+        let (_panel, selected, cx) = open(cx, &state);
+        click(cx, "hosts-group-~/.ssh/config");
+        assert_snapshot(SNAPSHOTS, "hosts_panel_expanded", &layout(cx, REGIONS));
+        click(cx, "hosts-leaf-db1");
+        assert_eq!(*selected.lock().unwrap(), vec!["db1".to_string()]);
+    }
+
+    #[gpui::test]
+    fn collapsed_root(cx: &mut TestAppContext) {
+        let state = TempState::new("hosts-panel");
+        let (_panel, _selected, cx) = open(cx, &state);
+        click(cx, "hosts-root");
+        assert_snapshot(SNAPSHOTS, "hosts_panel_collapsed", &layout(cx, REGIONS));
+    }
+
+    #[gpui::test]
+    fn containers_tailnet_and_discovered(cx: &mut TestAppContext) {
+        let state = TempState::new("hosts-panel");
+        let (panel, _selected, cx) = open(cx, &state);
+        panel.update(cx, |panel, cx| {
+            panel.set_containers(vec!["docker:redis".into()], cx);
+            panel.set_tailscale_peers(
+                vec![TailscalePeer {
+                    host_name: "nas".into(),
+                    dns_name: "nas.tail1234.ts.net".into(),
+                    ips: vec!["100.64.0.2".into()],
+                    os: "linux".into(),
+                    online: true,
+                }],
+                cx,
+            );
+            panel.set_discovered(
+                vec![DiscoveredHost {
+                    name: "raspberrypi".into(),
+                    hostname: "raspberrypi.local".into(),
+                    port: 22,
+                    addresses: Vec::new(),
+                }],
+                cx,
+            );
+        });
+        cx.run_until_parked();
+        assert_snapshot(SNAPSHOTS, "hosts_panel_groups", &layout(cx, REGIONS));
+        click(cx, "hosts-containers");
+        click(cx, "hosts-tailscale");
+        click(cx, "hosts-discovered");
+        assert_snapshot(
+            SNAPSHOTS,
+            "hosts_panel_groups_expanded",
+            &layout(cx, REGIONS),
+        );
+    }
+}
@@ -1,22 +1,410 @@
+//! Wire types for the client/agent protocol (newline-delimited JSON).
+//!
+//! Client and agent versions can differ (an outdated agent stays usable until
+//! redeployed), so the types evolve compatibly: unknown fields are ignored,
+//! fields added after the first release default when absent, and unknown
+//! commands, responses and capabilities decode to an `Unknown` variant instead
+//! of failing the whole line.
+
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub mod base64;
+
+/// A command line as sent by the client: the command plus the trace id the
+/// agent echoes in its reply and log frames, so client and agent logs of one
+/// request can be correlated.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Request {
+    #[serde(flatten)]
+    pub command: Command,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace: Option<String>,
+}
 
+/// A response line as sent by the agent, carrying the request's trace id.
 #[derive(Debug, Serialize, Deserialize)]
+pub struct Reply {
+    #[serde(flatten)]
+    pub response: Response,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "cmd", rename_all = "snake_case")]
 pub enum Command {
     /// Client-initiated handshake
-    Hello { id: u64, client_version: String },
+    Hello {
+        id: u64,
+        #[serde(default)]
+        client_version: String,
+    },
     /// Fetch basic system information
     SysInfo { id: u64 },
     /// Fetch static system configuration
     StaticConfig { id: u64 },
     /// List services from systemd
     ServicesList { id: u64 },
+    /// Properties and unit file of one systemd unit (e.g. "sshd.service")
+    ServiceDetail { id: u64, unit: String },
+    /// The cgroup (v2) hierarchy with CPU and memory use per node
+    CgroupTree { id: u64 },
+    /// Pressure stall information (/proc/pressure) and recent OOM kills
+    Pressure { id: u64 },
+    /// Software RAID (md) arrays from /proc/mdstat and `mdadm --detail`
+    RaidStatus { id: u64 },
+    /// ZFS pools (`zpool status`), btrfs filesystems (`btrfs device stats`
+    /// and `scrub status`) and LVM volume groups (`vgs`, `lvs`)
+    StorageStacks { id: u64 },
+    /// Backup jobs (borg, borgmatic, restic, rsnapshot) and their last runs
+    Backups { id: u64 },
+    /// Current logins, from utmp
+    Sessions { id: u64 },
+    /// Local users, members of the admin groups (sudo, wheel, admin) and the
+    /// rules of sudoers and its drop-ins
+    Users { id: u64 },
+    /// Effective sshd settings (`sshd -T` when permitted, else sshd_config
+    /// and the files it includes)
+    SshdConfig { id: u64 },
+    /// Failed SSH and other authentication attempts of the last `window_secs`
+    /// (agent default when None), counted per source address and per user
+    AuthFailures {
+        id: u64,
+        #[serde(default)]
+        window_secs: Option<u64>,
+    },
+    /// List docker and podman containers (running and stopped)
+    ContainersList { id: u64 },
+    /// Busiest processes by CPU and by memory, `limit` of each (agent default when None)
+    ProcessesSummary {
+        id: u64,
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+    /// Command line, environment, working directory, open files and sockets
+    /// of one process
+    ProcessDetail { id: u64, pid: u32 },
+    /// Send `signal` (its number, e.g. 15 for SIGTERM) to a process;
+    /// answered by `ProcessActionOk`
+    SignalProcess { id: u64, pid: u32, signal: i32 },
+    /// Set a process's nice value (-20 to 19; lowering it needs root);
+    /// answered by `ProcessActionOk`
+    ReniceProcess { id: u64, pid: u32, nice: i32 },
+    /// Listening TCP and UDP sockets
+    NetListeners { id: u64 },
+    /// Try a TCP connection from the host to each of `targets`, giving each
+    /// `timeout_ms` (agent default when None)
+    Reachability {
+        id: u64,
+        targets: Vec<Endpoint>,
+        #[serde(default)]
+        timeout_ms: Option<u64>,
+    },
+    /// Resolver configuration (resolv.conf, systemd-resolved) and a timed
+    /// lookup of `name` (an agent default when None) as the host resolves it
+    DnsCheck {
+        id: u64,
+        #[serde(default)]
+        name: Option<String>,
+    },
+    /// Resolver configuration alone: resolv.conf, systemd-resolved and the
+    /// search domains
+    DnsConfig { id: u64 },
+    /// The main routing table, IPv4 and IPv6
+    Routes { id: u64 },
+    /// Socket counts by kind and TCP state, and the processes holding the
+    /// most connections, like `ss -s`
+    Sockets { id: u64 },
+    /// Mounted filesystems with their size and free space
+    DiskUsage { id: u64 },
+    /// Firmware (DMI) identity, PCI devices and the block device tree
+    HardwareInventory { id: u64 },
+    /// Temperatures and fan speeds (hwmon, like `sensors`)
+    Sensors { id: u64 },
+    /// GPUs with their memory, utilization and driver (nvidia-smi, amdgpu)
+    GpuInfo { id: u64 },
+    /// The agent's own resource use, the limits it runs under and what its
+    /// watchdog aborted
+    AgentStats { id: u64 },
+    /// Loaded kernel modules from /proc/modules
+    KernelModules { id: u64 },
+    /// Kernel parameters from /proc/sys: each of `keys` is a parameter (e.g.
+    /// "vm.swappiness") or a namespace whose parameters are all returned
+    /// (e.g. "net.ipv4")
+    Sysctl { id: u64, keys: Vec<String> },
+    /// Installed packages by name, `limit` (agent default when None) from
+    /// `offset` (0 when None); page on with `offset + packages.len()` until
+    /// `total`
+    Packages {
+        id: u64,
+        #[serde(default)]
+        offset: Option<usize>,
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+    /// Packages with a newer version in the configured repositories, as the
+    /// package manager's dry-run check reports them (from its cached metadata)
+    UpdatesAvailable { id: u64 },
+    /// Live CPU, memory, load and IO figures, measured over `interval_ms`
+    /// (agent default when None)
+    MetricsSample {
+        id: u64,
+        #[serde(default)]
+        interval_ms: Option<u64>,
+    },
+    /// List services as a delta against the answer that returned token `since`
+    /// (the full list when `since` is None or no longer known to the agent)
+    ServicesDelta { id: u64, since: Option<u64> },
+    /// List listening sockets as a delta against the answer that returned
+    /// token `since` (all of them when `since` is None or no longer known)
+    ListenersDelta { id: u64, since: Option<u64> },
     ListDir {
         id: u64,
         path: String,
         max: Option<usize>,
         skip: Option<usize>,
     },
+    /// Up to `len` bytes of the file at `path` from `offset` (0 when None),
+    /// answered by `ReadFileOk`; the agent caps `len` (default when None).
+    /// Read further chunks at `offset + chunk.len` until `eof`.
+    ReadFile {
+        id: u64,
+        path: String,
+        #[serde(default)]
+        offset: Option<u64>,
+        #[serde(default)]
+        len: Option<u64>,
+    },
+    /// Digest of the whole file at `path`, answered by `ChecksumOk`
+    Checksum {
+        id: u64,
+        path: String,
+        algo: ChecksumAlgo,
+    },
+    /// Start writing the file at `path` (replacing it once complete), answered
+    /// by `WriteFileProgress`. `WriteFileChunk`s with the same id append to it
+    /// and `WriteFileEnd` saves it; until then the old file stays untouched.
+    /// `mode` defaults to the replaced file's (umask default for a new file).
+    WriteFileBegin {
+        id: u64,
+        path: String,
+        #[serde(default)]
+        mode: Option<u32>,
+    },
+    /// Append base64 encoded `data` to the write started with this id
+    WriteFileChunk { id: u64, data: String },
+    /// Save the write started with this id over its target (drop it instead
+    /// with `abort`); answered by `WriteFileDone`
+    WriteFileEnd {
+        id: u64,
+        #[serde(default)]
+        abort: bool,
+    },
+    /// Recent journal entries of `unit` (the whole journal when None), answered
+    /// by `JournalLines`. With `follow` the answer is a stream: the backlog,
+    /// then further `JournalLines` as entries arrive, until `JournalStop` (or
+    /// the session ends); its last frame has `end` set. Streams cannot be batched.
+    JournalTail {
+        id: u64,
+        #[serde(default)]
+        unit: Option<String>,
+        #[serde(default)]
+        lines: Option<usize>,
+        #[serde(default)]
+        follow: bool,
+    },
+    /// End the `JournalTail` stream with this id
+    JournalStop { id: u64 },
+    /// The last `lines` lines of the file at `path` (agent default when None),
+    /// answered by `FileLines`. With `follow` the answer is a stream like
+    /// `JournalTail`'s: further `FileLines` as the file grows (from the start
+    /// again when it is rotated or truncated), until `TailStop` (or the
+    /// session ends). Streams cannot be batched.
+    TailFile {
+        id: u64,
+        path: String,
+        #[serde(default)]
+        lines: Option<usize>,
+        #[serde(default)]
+        follow: bool,
+    },
+    /// End the `TailFile` stream with this id
+    TailStop { id: u64 },
+    /// Run `argv` (no shell) in `cwd` (the agent's when None) with `env` added
+    /// to the agent's environment. Answered by `ExecStarted`, then `ExecOutput`
+    /// frames as the process writes and a final `ExecExit`. The process is
+    /// killed after `timeout_ms` (agent default when None), on `ExecKill` or
+    /// when the session ends. Cannot be batched.
+    Exec {
+        id: u64,
+        argv: Vec<String>,
+        #[serde(default)]
+        cwd: Option<String>,
+        #[serde(default)]
+        env: BTreeMap<String, String>,
+        #[serde(default)]
+        timeout_ms: Option<u64>,
+    },
+    /// Kill the process of the `Exec` with this id; answered by its `ExecExit`
+    ExecKill { id: u64 },
+    /// Push `facet` every `interval_ms` (agent default when None) as `Event`
+    /// frames with this id (alert facets only when there is something to
+    /// report), until `Unsubscribe` (or the session ends).
+    /// Answered by `Subscribed`; subscriptions cannot be batched.
+    Subscribe {
+        id: u64,
+        facet: Facet,
+        #[serde(default)]
+        interval_ms: Option<u64>,
+    },
+    /// End the subscription with this id; answered by `Unsubscribed`
+    Unsubscribe { id: u64 },
+    /// Run several commands in one round trip; answered by a single `BatchOk`
+    /// holding one response per command, in order. Batches do not nest.
+    Batch {
+        id: u64,
+        #[serde(default)]
+        commands: Vec<Command>,
+    },
+    /// A command from a newer client; agents answer it with an `Error`
+    #[serde(other)]
+    Unknown,
+}
+
+impl Command {
+    /// Wire name of the command (its `cmd` tag), for logs.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::Hello { .. } => "hello",
+            Command::SysInfo { .. } => "sys_info",
+            Command::StaticConfig { .. } => "static_config",
+            Command::ServicesList { .. } => "services_list",
+            Command::ServicesDelta { .. } => "services_delta",
+            Command::ListenersDelta { .. } => "listeners_delta",
+            Command::ServiceDetail { .. } => "service_detail",
+            Command::CgroupTree { .. } => "cgroup_tree",
+            Command::Pressure { .. } => "pressure",
+            Command::RaidStatus { .. } => "raid_status",
+            Command::StorageStacks { .. } => "storage_stacks",
+            Command::Backups { .. } => "backups",
+            Command::Sessions { .. } => "sessions",
+            Command::Users { .. } => "users",
+            Command::SshdConfig { .. } => "sshd_config",
+            Command::AuthFailures { .. } => "auth_failures",
+            Command::ContainersList { .. } => "containers_list",
+            Command::ProcessesSummary { .. } => "processes_summary",
+            Command::ProcessDetail { .. } => "process_detail",
+            Command::SignalProcess { .. } => "signal_process",
+            Command::ReniceProcess { .. } => "renice_process",
+            Command::NetListeners { .. } => "net_listeners",
+            Command::Reachability { .. } => "reachability",
+            Command::DnsCheck { .. } => "dns_check",
+            Command::DnsConfig { .. } => "dns_config",
+            Command::Routes { .. } => "routes",
+            Command::Sockets { .. } => "sockets",
+            Command::DiskUsage { .. } => "disk_usage",
+            Command::HardwareInventory { .. } => "hardware_inventory",
+            Command::Sensors { .. } => "sensors",
+            Command::GpuInfo { .. } => "gpu_info",
+            Command::AgentStats { .. } => "agent_stats",
+            Command::KernelModules { .. } => "kernel_modules",
+            Command::Sysctl { .. } => "sysctl",
+            Command::Packages { .. } => "packages",
+            Command::UpdatesAvailable { .. } => "updates_available",
+            Command::MetricsSample { .. } => "metrics_sample",
+            Command::ListDir { .. } => "list_dir",
+            Command::ReadFile { .. } => "read_file",
+            Command::Checksum { .. } => "checksum",
+            Command::WriteFileBegin { .. } => "write_file_begin",
+            Command::WriteFileChunk { .. } => "write_file_chunk",
+            Command::WriteFileEnd { .. } => "write_file_end",
+            Command::JournalTail { .. } => "journal_tail",
+            Command::JournalStop { .. } => "journal_stop",
+            Command::TailFile { .. } => "tail_file",
+            Command::TailStop { .. } => "tail_stop",
+            Command::Exec { .. } => "exec",
+            Command::ExecKill { .. } => "exec_kill",
+            Command::Subscribe { .. } => "subscribe",
+            Command::Unsubscribe { .. } => "unsubscribe",
+            Command::Batch { .. } => "batch",
+            Command::Unknown => "unknown",
+        }
+    }
+
+    /// The id its responses carry.
+    pub fn id(&self) -> u64 {
+        match self {
+            Command::Hello { id, .. }
+            | Command::SysInfo { id }
+            | Command::StaticConfig { id }
+            | Command::ServicesList { id }
+            | Command::ServiceDetail { id, .. }
+            | Command::CgroupTree { id }
+            | Command::Pressure { id }
+            | Command::RaidStatus { id }
+            | Command::StorageStacks { id }
+            | Command::Backups { id }
+            | Command::Sessions { id }
+            | Command::Users { id }
+            | Command::SshdConfig { id }
+            | Command::AuthFailures { id, .. }
+            | Command::ContainersList { id }
+            | Command::ProcessesSummary { id, .. }
+            | Command::ProcessDetail { id, .. }
+            | Command::SignalProcess { id, .. }
+            | Command::ReniceProcess { id, .. }
+            | Command::NetListeners { id }
+            | Command::Reachability { id, .. }
+            | Command::DnsCheck { id, .. }
+            | Command::DnsConfig { id }
+            | Command::Routes { id }
+            | Command::Sockets { id }
+            | Command::DiskUsage { id }
+            | Command::HardwareInventory { id }
+            | Command::Sensors { id }
+            | Command::GpuInfo { id }
+            | Command::AgentStats { id }
+            | Command::KernelModules { id }
+            | Command::Sysctl { id, .. }
+            | Command::Packages { id, .. }
+            | Command::UpdatesAvailable { id }
+            | Command::MetricsSample { id, .. }
+            | Command::ServicesDelta { id, .. }
+            | Command::ListenersDelta { id, .. }
+            | Command::ListDir { id, .. }
+            | Command::ReadFile { id, .. }
+            | Command::Checksum { id, .. }
+            | Command::WriteFileBegin { id, .. }
+            | Command::WriteFileChunk { id, .. }
+            | Command::WriteFileEnd { id, .. }
+            | Command::JournalTail { id, .. }
+            | Command::JournalStop { id }
+            | Command::TailFile { id, .. }
+            | Command::TailStop { id }
+            | Command::Exec { id, .. }
+            | Command::ExecKill { id }
+            | Command::Subscribe { id, .. }
+            | Command::Unsubscribe { id }
+            | Command::Batch { id, .. } => *id,
+            Command::Unknown => 0,
+        }
+    }
+
+    /// Whether the command changes the host (signals, renices, writes files or
+    /// runs arbitrary commands); a read-only agent refuses these. A batch is
+    /// judged by its members.
+    pub fn is_mutating(&self) -> bool {
+        matches!(
+            self,
+            Command::SignalProcess { .. }
+                | Command::ReniceProcess { .. }
+                | Command::WriteFileBegin { .. }
+                | Command::WriteFileChunk { .. }
+                | Command::WriteFileEnd { .. }
+                | Command::Exec { .. }
+        )
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -26,35 +414,319 @@ pub enum Response {
     HelloAck {
         id: u64,
         agent_version: String,
+        #[serde(default)]
         capabilities: Vec<Capability>,
     },
     /// Basic system information
-    SysInfoOk {
-        id: u64,
-        info: SysInfo,
-    },
+    SysInfoOk { id: u64, info: SysInfo },
     /// Static system configuration
-    StaticConfigOk {
-        id: u64,
-        config: StaticConfig,
-    },
+    StaticConfigOk { id: u64, config: StaticConfig },
     /// Services list
     ServicesListOk {
         id: u64,
+        #[serde(default)]
         services: Vec<ServiceInfo>,
     },
+    /// One unit in detail
+    ServiceDetailOk { id: u64, detail: ServiceDetail },
+    /// The cgroup hierarchy, from its root
+    CgroupTreeOk { id: u64, root: CgroupNode },
+    /// CPU, IO and memory pressure, and the OOM kills of the kernel log
+    PressureOk { id: u64, pressure: Pressure },
+    /// The md arrays of the host (empty without the md driver)
+    RaidStatusOk {
+        id: u64,
+        #[serde(default)]
+        arrays: Vec<RaidArray>,
+    },
+    /// What of ZFS, btrfs and LVM the host uses (each empty when it does not,
+    /// or when its tools are missing or need root)
+    StorageStacksOk { id: u64, stacks: StorageStacks },
+    /// Backup jobs found on the host (empty when there is none)
+    BackupsOk {
+        id: u64,
+        #[serde(default)]
+        jobs: Vec<BackupJob>,
+    },
+    /// Users logged in to the host (empty when utmp records none)
+    SessionsOk {
+        id: u64,
+        #[serde(default)]
+        sessions: Vec<LoginSession>,
+    },
+    /// Who has access to the host
+    UsersOk { id: u64, users: UserInventory },
+    /// sshd's settings; None when sshd is not installed
+    SshdConfigOk {
+        id: u64,
+        #[serde(default)]
+        config: Option<SshdConfig>,
+    },
+    /// Failed authentication attempts
+    AuthFailuresOk { id: u64, summary: AuthFailures },
+    /// Containers of every runtime found on the host (empty when there is none)
+    ContainersListOk {
+        id: u64,
+        #[serde(default)]
+        containers: Vec<ContainerInfo>,
+    },
+    /// Process overview
+    ProcessesSummaryOk { id: u64, summary: ProcessesSummary },
+    /// One process in detail
+    ProcessDetailOk { id: u64, detail: ProcessDetail },
+    /// A `SignalProcess` or `ReniceProcess` succeeded
+    ProcessActionOk { id: u64, pid: u32 },
+    /// Open ports
+    NetListenersOk {
+        id: u64,
+        #[serde(default)]
+        listeners: Vec<NetListener>,
+    },
+    /// One result per target, in the order asked
+    ReachabilityOk {
+        id: u64,
+        #[serde(default)]
+        results: Vec<ReachResult>,
+    },
+    /// How the host resolves names
+    DnsCheckOk { id: u64, report: DnsReport },
+    /// How the host is set up to resolve names
+    DnsConfigOk { id: u64, config: DnsConfig },
+    /// IPv4 routes, then IPv6, in the kernel's order
+    RoutesOk {
+        id: u64,
+        #[serde(default)]
+        routes: Vec<Route>,
+    },
+    /// Socket counts of the agent's network namespace
+    SocketsOk { id: u64, summary: SocketSummary },
+    /// Mounted filesystems
+    DiskUsageOk {
+        id: u64,
+        #[serde(default)]
+        mounts: Vec<MountInfo>,
+    },
+    /// What the host is built from
+    HardwareInventoryOk {
+        id: u64,
+        inventory: HardwareInventory,
+    },
+    /// Current sensor readings (empty in most VMs and containers)
+    SensorsOk { id: u64, readings: SensorReadings },
+    /// GPUs by PCI address (empty when the host has none)
+    GpuInfoOk {
+        id: u64,
+        #[serde(default)]
+        gpus: Vec<Gpu>,
+    },
+    /// The agent process, as it sees itself
+    AgentStatsOk { id: u64, stats: AgentStats },
+    /// Loaded modules, by name (empty without module support)
+    KernelModulesOk {
+        id: u64,
+        #[serde(default)]
+        modules: Vec<KernelModule>,
+    },
+    /// Parameters in the order asked, namespaces expanded in name order
+    SysctlOk {
+        id: u64,
+        #[serde(default)]
+        values: Vec<SysctlValue>,
+    },
+    /// A page of the installed packages
+    PackagesOk { id: u64, packages: PackageList },
+    /// Pending package updates
+    UpdatesAvailableOk { id: u64, updates: PendingUpdates },
+    /// Live metrics
+    MetricsSampleOk { id: u64, sample: MetricsSample },
+    /// Services changed since the requested token
+    ServicesDeltaOk { id: u64, delta: ServicesDelta },
+    /// Listening sockets changed since the requested token
+    ListenersDeltaOk { id: u64, delta: ListenersDelta },
+    /// Part of a file, for a `ReadFile`
+    ReadFileOk { id: u64, chunk: FileChunk },
+    /// Digest of a file, lowercase hex
+    ChecksumOk {
+        id: u64,
+        path: String,
+        algo: ChecksumAlgo,
+        size: u64,
+        digest: String,
+    },
+    /// Bytes received so far by the write with this id
+    WriteFileProgress { id: u64, written: u64 },
+    /// The write with this id ended: `path` now holds its `size` bytes (or,
+    /// if not `saved`, it was aborted and `path` is unchanged)
+    WriteFileDone {
+        id: u64,
+        path: String,
+        size: u64,
+        saved: bool,
+    },
     ListDirOk {
         id: u64,
+        #[serde(default)]
         entries: Vec<DirEntry>,
+        #[serde(default)]
         eof: bool,
     },
+    /// Journal entries for a `JournalTail` (one frame of a stream when following)
+    JournalLines {
+        id: u64,
+        #[serde(default)]
+        entries: Vec<JournalEntry>,
+        /// Last frame for this id
+        #[serde(default)]
+        end: bool,
+    },
+    /// Lines of a `TailFile` (one frame of a stream when following)
+    FileLines {
+        id: u64,
+        #[serde(default)]
+        lines: Vec<String>,
+        /// Last frame for this id
+        #[serde(default)]
+        end: bool,
+    },
+    /// An `Exec` process started; its output and exit follow
+    ExecStarted { id: u64, pid: u32 },
+    /// Output of an `Exec` process, as written (invalid UTF-8 replaced)
+    ExecOutput {
+        id: u64,
+        stream: OutputStream,
+        data: String,
+    },
+    /// An `Exec` process ended; last frame for this id
+    ExecExit {
+        id: u64,
+        /// Exit code; None when killed by a signal
+        #[serde(default)]
+        code: Option<i32>,
+        #[serde(default)]
+        signal: Option<i32>,
+        /// Killed for running past its timeout
+        #[serde(default)]
+        timed_out: bool,
+    },
+    /// A subscription started; its events follow every `interval_ms`
+    Subscribed { id: u64, interval_ms: u64 },
+    /// A subscription ended; no more events for this id
+    Unsubscribed { id: u64 },
+    /// One pushed update of the subscription `id`; not an answer to the
+    /// request in flight
+    Event { id: u64, data: EventData },
+    /// Responses to a `Batch`, in command order (failed commands as `Error`)
+    BatchOk {
+        id: u64,
+        #[serde(default)]
+        responses: Vec<Response>,
+    },
     Error {
         id: u64,
+        #[serde(default)]
+        message: String,
+    },
+    /// Agent-side log line for the traced request it precedes (the trace id is
+    /// the `Reply`'s); not an answer, clients log it and keep reading. Only
+    /// sent for requests with a trace id.
+    AgentLog {
+        #[serde(default)]
+        level: String,
+        #[serde(default)]
         message: String,
     },
+    /// A response from a newer agent; clients skip it
+    #[serde(other)]
+    Unknown,
+}
+
+impl Response {
+    /// The id of the command it answers (or the stream or subscription it
+    /// belongs to); None for agent logs and unknown responses.
+    pub fn id(&self) -> Option<u64> {
+        match self {
+            Response::HelloAck { id, .. }
+            | Response::SysInfoOk { id, .. }
+            | Response::StaticConfigOk { id, .. }
+            | Response::ServicesListOk { id, .. }
+            | Response::ServiceDetailOk { id, .. }
+            | Response::CgroupTreeOk { id, .. }
+            | Response::PressureOk { id, .. }
+            | Response::RaidStatusOk { id, .. }
+            | Response::StorageStacksOk { id, .. }
+            | Response::BackupsOk { id, .. }
+            | Response::SessionsOk { id, .. }
+            | Response::UsersOk { id, .. }
+            | Response::SshdConfigOk { id, .. }
+            | Response::AuthFailuresOk { id, .. }
+            | Response::ContainersListOk { id, .. }
+            | Response::ProcessesSummaryOk { id, .. }
+            | Response::ProcessDetailOk { id, .. }
+            | Response::ProcessActionOk { id, .. }
+            | Response::NetListenersOk { id, .. }
+            | Response::ReachabilityOk { id, .. }
+            | Response::DnsCheckOk { id, .. }
+            | Response::DnsConfigOk { id, .. }
+            | Response::RoutesOk { id, .. }
+            | Response::SocketsOk { id, .. }
+            | Response::DiskUsageOk { id, .. }
+            | Response::HardwareInventoryOk { id, .. }
+            | Response::SensorsOk { id, .. }
+            | Response::GpuInfoOk { id, .. }
+            | Response::AgentStatsOk { id, .. }
+            | Response::KernelModulesOk { id, .. }
+            | Response::SysctlOk { id, .. }
+            | Response::PackagesOk { id, .. }
+            | Response::UpdatesAvailableOk { id, .. }
+            | Response::MetricsSampleOk { id, .. }
+            | Response::ServicesDeltaOk { id, .. }
+            | Response::ListenersDeltaOk { id, .. }
+            | Response::ReadFileOk { id, .. }
+            | Response::ChecksumOk { id, .. }
+            | Response::WriteFileProgress { id, .. }
+            | Response::WriteFileDone { id, .. }
+            | Response::ListDirOk { id, .. }
+            | Response::JournalLines { id, .. }
+            | Response::FileLines { id, .. }
+            | Response::ExecStarted { id, .. }
+            | Response::ExecOutput { id, .. }
+            | Response::ExecExit { id, .. }
+            | Response::Subscribed { id, .. }
+            | Response::Unsubscribed { id, .. }
+            | Response::Event { id, .. }
+            | Response::BatchOk { id, .. }
+            | Response::Error { id, .. } => Some(*id),
+            Response::AgentLog { .. } | Response::Unknown => None,
+        }
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// Part of a remote file.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct FileChunk {
+    /// Path read, `~` expanded
+    pub path: String,
+    pub offset: u64,
+    /// Bytes in this chunk
+    pub len: u64,
+    /// Size of the whole file
+    pub size: u64,
+    /// The bytes, base64 encoded (see `FileChunk::bytes`)
+    pub data: String,
+    /// The chunk ends at the end of the file
+    pub eof: bool,
+}
+
+impl FileChunk {
+    /// The decoded bytes of the chunk.
+    pub fn bytes(&self) -> anyhow::Result<Vec<u8>> {
+        base64::decode(&self.data)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
 pub struct DirEntry {
     pub name: String,
     pub path: String,
@@ -62,23 +734,139 @@ pub struct DirEntry {
     pub size: Option<u64>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
 pub struct SysInfo {
     pub os: String,
     pub kernel: String,
     pub arch: String,
     pub uptime_secs: u64,
     pub hostname: String,
+    /// 1, 5 and 15 minute load averages (from /proc/loadavg), if available
+    pub load_avg: Option<[f64; 3]>,
+    /// What the host runs on; None from an older agent
+    pub platform: Option<Platform>,
+    /// Clock synchronization; None when no NTP client could be asked
+    pub time_sync: Option<TimeSync>,
+    /// Whether a reboot is pending; None from an older agent
+    pub reboot: Option<RebootStatus>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// Signs that the host needs a reboot to finish applying updates.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct RebootStatus {
+    /// /var/run/reboot-required exists (Debian, Ubuntu)
+    pub flagged: bool,
+    /// Packages that asked for the reboot (/var/run/reboot-required.pkgs)
+    pub packages: Vec<String>,
+    /// The newest installed kernel, when it is newer than the running one
+    pub newer_kernel: Option<String>,
+}
+
+impl RebootStatus {
+    /// Any sign says a reboot is due.
+    pub fn required(&self) -> bool {
+        self.flagged || self.newer_kernel.is_some()
+    }
+}
+
+/// Clock synchronization as the host's NTP client reports it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct TimeSync {
+    /// "chrony" or "timesyncd"; None when only systemd's flag is known
+    pub source: Option<String>,
+    pub synchronized: bool,
+    /// Offset of the system clock from the server's (positive: ahead)
+    pub offset_secs: Option<f64>,
+    pub stratum: Option<u32>,
+    /// The server synchronized to
+    pub server: Option<String>,
+}
+
+/// Virtualization, container and cloud instance the agent runs in (all None
+/// on bare metal).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct Platform {
+    /// Hypervisor, in systemd-detect-virt's terms ("kvm", "vmware", "amazon", ...)
+    pub vm: Option<String>,
+    /// Container runtime, in systemd-detect-virt's terms ("docker", "lxc", ...)
+    pub container: Option<String>,
+    /// Cloud provider ("aws", "gcp"), from DMI
+    pub provider: Option<String>,
+    /// From the provider's metadata endpoint
+    pub instance_type: Option<String>,
+    pub region: Option<String>,
+    pub zone: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
 pub struct StaticConfig {
     pub os_release: Option<String>,
     pub cpu_count: u32,
     pub mem_total_bytes: u64,
+    /// From /sys/devices/system/cpu and /sys/devices/system/node; None when
+    /// /sys is not readable (or from an older agent)
+    pub topology: Option<CpuTopology>,
+}
+
+/// How the host's logical CPUs map onto sockets, cores, NUMA nodes and caches.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct CpuTopology {
+    /// "model name" of /proc/cpuinfo, if reported
+    pub model: Option<String>,
+    pub sockets: u32,
+    /// Physical cores, all sockets
+    pub cores: u32,
+    /// Online logical CPUs (hardware threads)
+    pub threads: u32,
+    /// Online logical CPUs, by id
+    pub cpus: Vec<LogicalCpu>,
+    /// Empty when the kernel has no NUMA support
+    pub numa_nodes: Vec<NumaNode>,
+    /// Cache levels of the first CPU (assumed the same for all), L1 first
+    pub caches: Vec<CpuCache>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct LogicalCpu {
+    pub id: u32,
+    /// physical_package_id
+    pub socket: u32,
+    /// core_id (unique within its socket only)
+    pub core: u32,
+    pub node: Option<u32>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct NumaNode {
+    pub id: u32,
+    pub cpus: Vec<u32>,
+    pub memory_bytes: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct CpuCache {
+    pub level: u32,
+    /// "Data", "Instruction" or "Unified"
+    pub kind: String,
+    /// Size of one instance
+    pub size_bytes: u64,
+    /// Logical CPUs sharing one instance
+    pub shared_by: u32,
+    /// Instances across the host
+    pub instances: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
 pub struct ServiceInfo {
     pub name: String,
     pub description: Option<String>,
@@ -88,13 +876,2393 @@ pub struct ServiceInfo {
     pub baseline: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// One systemd unit in detail, from `systemctl show` and `systemctl cat`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct ServiceDetail {
+    pub unit: String,
+    pub description: String,
+    /// e.g. "loaded" or "not-found"
+    pub load_state: String,
+    pub active_state: String,
+    pub sub_state: String,
+    /// e.g. "enabled", "disabled" or "static"
+    pub unit_file_state: String,
+    /// None while the unit has no main process
+    pub main_pid: Option<u32>,
+    /// Memory of the unit's cgroup; None when not accounted
+    pub memory_bytes: Option<u64>,
+    /// Automatic restarts since the unit was last started by hand
+    pub restarts: Option<u32>,
+    /// When the unit last became active, as systemd prints it
+    pub active_since: Option<String>,
+    /// Command lines of `ExecStart=`, in order
+    pub exec_start: Vec<String>,
+    /// Path of the unit file
+    pub fragment_path: Option<String>,
+    /// The unit file and its drop-ins (`systemctl cat`); None if unreadable
+    pub unit_file: Option<String>,
+}
+
+/// A cgroup and its descendants (systemd slices, services and scopes).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct CgroupNode {
+    /// Last path component, e.g. "sshd.service" ("/" for the root)
+    pub name: String,
+    /// Path below the cgroup root, e.g. "/system.slice/sshd.service"
+    pub path: String,
+    /// CPU use over the sampling interval, this cgroup and its descendants;
+    /// 100 is one core fully busy
+    pub cpu_percent: Option<f32>,
+    /// Memory charged to this cgroup and its descendants (not reported for the root)
+    pub memory_bytes: Option<u64>,
+    /// Processes directly in this cgroup
+    pub processes: usize,
+    /// Sorted by name
+    pub children: Vec<CgroupNode>,
+}
+
+/// Pressure stall information and recent OOM kills of one host.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct Pressure {
+    /// None when the kernel has no PSI (older than 4.20, or booted with psi=0)
+    pub cpu: Option<PressureStall>,
+    pub io: Option<PressureStall>,
+    pub memory: Option<PressureStall>,
+    /// OOM kills of the current boot, oldest first; None when the kernel log
+    /// could not be read
+    pub oom_kills: Option<Vec<OomKill>>,
+}
+
+/// One /proc/pressure file.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct PressureStall {
+    /// Time at least one task stalled on the resource
+    pub some: PressureAverages,
+    /// Time all non-idle tasks stalled at once (absent for CPU before 5.13)
+    pub full: Option<PressureAverages>,
+}
+
+/// Share of wall time stalled, in percent, over the last 10, 60 and 300 seconds.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct PressureAverages {
+    pub avg10: f32,
+    pub avg60: f32,
+    pub avg300: f32,
+    /// Total stall time since boot
+    pub total_usec: u64,
+}
+
+/// A process killed by the kernel's OOM killer.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct OomKill {
+    /// Microseconds since the epoch
+    pub realtime_usec: u64,
+    pub pid: Option<u32>,
+    pub comm: Option<String>,
+    /// The kernel's "Out of memory: Killed process ..." line
+    pub message: String,
+}
+
+/// A software RAID array, as /proc/mdstat describes it, with the states
+/// `mdadm --detail` reports where it could run (it needs root).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct RaidArray {
+    /// e.g. "md0"
+    pub name: String,
+    /// e.g. "raid1", "raid5"; None while inactive
+    pub level: Option<String>,
+    pub active: bool,
+    pub read_only: bool,
+    /// mdadm's array state, e.g. "clean, degraded, recovering"
+    pub state: Option<String>,
+    pub uuid: Option<String>,
+    pub size_bytes: u64,
+    /// Devices the array is built from, and of those, how many work
+    pub devices: u32,
+    pub working: u32,
+    /// Fewer working devices than it is built from, a faulty member, or a
+    /// "degraded" state
+    pub degraded: bool,
+    /// By slot
+    pub members: Vec<RaidMember>,
+    /// Rebuild, resync or check in progress (or pending)
+    pub sync: Option<RaidSync>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct RaidMember {
+    /// e.g. "sda1"
+    pub device: String,
+    pub slot: u32,
+    pub faulty: bool,
+    pub spare: bool,
+    /// Being rebuilt onto (a spare taking a failed member's place)
+    pub rebuilding: bool,
+    /// mdadm's device state, e.g. "active sync", "spare rebuilding"
+    pub state: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct RaidSync {
+    /// "recovery", "resync", "reshape", "check" or "repair"
+    pub action: String,
+    /// None while delayed or pending
+    pub percent: Option<f32>,
+    /// The kernel's estimate of the time left
+    pub finish_secs: Option<u64>,
+    pub speed_bytes_per_sec: Option<u64>,
+}
+
+/// Volume managers and pooled filesystems of a host.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct StorageStacks {
+    pub zfs: Vec<ZfsPool>,
+    pub btrfs: Vec<BtrfsFilesystem>,
+    pub lvm: Vec<LvmVolumeGroup>,
+}
+
+/// A ZFS pool, as `zpool status` describes it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct ZfsPool {
+    pub name: String,
+    /// "ONLINE", "DEGRADED", "FAULTED", ...
+    pub state: String,
+    /// Why the pool needs attention (absent while healthy)
+    pub status: Option<String>,
+    /// Last or running scrub or resilver, e.g. "scrub repaired 0B in
+    /// 00:01:02 with 0 errors on Sun Oct 12 00:25:03 2025"
+    pub scan: Option<String>,
+    /// e.g. "No known data errors"
+    pub errors: Option<String>,
+    /// The config tree, depth-first: the pool itself, vdevs, disks
+    pub devices: Vec<ZfsDevice>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct ZfsDevice {
+    /// e.g. "mirror-0", "sda"
+    pub name: String,
+    /// 0 for the pool (or a "logs"/"cache"/"spares" group), 1 for its vdevs, ...
+    pub depth: u32,
+    pub state: String,
+    pub read_errors: u64,
+    pub write_errors: u64,
+    pub checksum_errors: u64,
+    /// Trailing note, e.g. "cannot open", "(resilvering)"
+    pub note: Option<String>,
+}
+
+/// A mounted btrfs filesystem with its per-device error counters and last scrub.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct BtrfsFilesystem {
+    /// First mount point found (subvolumes of one filesystem count once)
+    pub mountpoint: String,
+    pub devices: Vec<BtrfsDevice>,
+    pub scrub: Option<BtrfsScrub>,
+}
+
+/// `btrfs device stats` counters of one device.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct BtrfsDevice {
+    /// e.g. "/dev/sda1"
+    pub path: String,
+    pub write_io_errs: u64,
+    pub read_io_errs: u64,
+    pub flush_io_errs: u64,
+    pub corruption_errs: u64,
+    pub generation_errs: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct BtrfsScrub {
+    /// "finished", "running", "aborted", "interrupted" or "never"
+    pub status: String,
+    /// As btrfs prints it, e.g. "Sun Oct 12 00:00:01 2025"
+    pub started: Option<String>,
+    /// e.g. "0:01:12"
+    pub duration: Option<String>,
+    /// e.g. "no errors found", "csum=2 Corrected: 2 Uncorrectable: 0"
+    pub error_summary: Option<String>,
+    pub errors_found: bool,
+}
+
+/// An LVM volume group (`vgs`) and its logical volumes (`lvs`).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct LvmVolumeGroup {
+    pub name: String,
+    pub size_bytes: u64,
+    pub free_bytes: u64,
+    pub pv_count: u32,
+    /// Physical volumes are missing
+    pub partial: bool,
+    pub volumes: Vec<LvmVolume>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct LvmVolume {
+    pub name: String,
+    pub size_bytes: u64,
+    /// Segment type, e.g. "linear", "striped", "raid1", "thin-pool", "thin"
+    pub kind: String,
+    pub active: bool,
+    /// Thin pools and snapshots: how full their data is
+    pub data_percent: Option<f32>,
+    /// Thin volumes: their pool
+    pub pool: Option<String>,
+    /// lvm's health, e.g. "partial", "refresh needed", "mismatches exist"
+    pub health: Option<String>,
+}
+
+/// A backup job and the outcome of its recent runs.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct BackupJob {
+    /// "borg", "borgmatic", "restic" or "rsnapshot"
+    pub tool: String,
+    /// Where the runs were read from: a systemd service or a log file
+    pub source: String,
+    /// Ends of the last successful and the last failed run (microseconds
+    /// since the epoch); None when none is on record
+    pub last_success_usec: Option<u64>,
+    pub last_failure_usec: Option<u64>,
+    /// A run is in progress
+    pub running: bool,
+}
+
+/// Local accounts and who may administer the host.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct UserInventory {
+    /// Accounts of /etc/passwd, by uid
+    pub users: Vec<LocalUser>,
+    /// The admin groups present, with their members (listed in /etc/group
+    /// or having the group as primary group)
+    pub admin_groups: Vec<GroupMembers>,
+    /// User specifications of sudoers and the files it includes
+    pub sudo_rules: Vec<SudoRule>,
+    /// Why sudoers could not be read (it is readable by root only)
+    pub sudoers_error: Option<String>,
+}
+
+/// One account of /etc/passwd, with /etc/shadow details when readable.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct LocalUser {
+    pub name: String,
+    pub uid: u32,
+    pub gid: u32,
+    /// Full name field ("GECOS")
+    pub gecos: String,
+    pub home: String,
+    pub shell: String,
+    /// The shell is not nologin or false
+    pub can_login: bool,
+    /// Last password change, microseconds since the epoch (day resolution);
+    /// None when /etc/shadow is unreadable or does not say
+    pub password_changed_usec: Option<u64>,
+    /// The password is locked or unset ('!' or '*'), so only keys or other
+    /// methods let the user in; None when /etc/shadow is unreadable
+    pub password_locked: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct GroupMembers {
+    pub name: String,
+    pub gid: u32,
+    pub members: Vec<String>,
+}
+
+/// A sudoers user specification, e.g. "%admin ALL=(ALL) NOPASSWD: ALL".
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct SudoRule {
+    /// File the rule is in
+    pub file: String,
+    /// User, %group or alias the rule is for
+    pub who: String,
+    /// The rest of the rule: hosts, run-as users and commands
+    pub spec: String,
+    /// Some command runs without a password
+    pub nopasswd: bool,
+}
+
+/// sshd settings as the agent could read them.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct SshdConfig {
+    /// From `sshd -T`, so every setting is present with its effective value;
+    /// false when read from the config files, where unset settings are left
+    /// out (sshd's defaults apply) and `Match` blocks are skipped
+    pub effective: bool,
+    /// Why `sshd -T` could not be used (it needs root to read the host keys)
+    pub effective_error: Option<String>,
+    /// Config files read, when not `effective`
+    pub files: Vec<String>,
+    /// Settings in order, keywords lowercase; keywords that may repeat (e.g.
+    /// `listenaddress`) appear once per value
+    pub settings: Vec<SshdSetting>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct SshdSetting {
+    pub key: String,
+    pub value: String,
+}
+
+/// Failed authentication attempts over a time window, from the journal or
+/// the auth log.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct AuthFailures {
+    /// Seconds back from now the counts cover
+    pub window_secs: u64,
+    /// Where the attempts were read from: "journal" or a log file path; None
+    /// when neither could be read
+    pub source: Option<String>,
+    /// Failed attempts in the window
+    pub total: u64,
+    /// Most frequent source addresses ("local" for attempts without one) and
+    /// users, by count, descending; the agent caps both lists
+    pub by_address: Vec<AuthFailureCount>,
+    pub by_user: Vec<AuthFailureCount>,
+    /// fail2ban's jails; None when fail2ban is not installed
+    pub fail2ban: Option<Fail2ban>,
+}
+
+/// `fail2ban-client status` of every jail.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct Fail2ban {
+    pub jails: Vec<Fail2banJail>,
+    /// Why the jails could not be read (the fail2ban socket needs root)
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct Fail2banJail {
+    pub name: String,
+    /// Failures counted toward a ban now, and since fail2ban started
+    pub currently_failed: u64,
+    pub total_failed: u64,
+    /// Addresses banned now, and bans since fail2ban started
+    pub currently_banned: u64,
+    pub total_banned: u64,
+    pub banned: Vec<String>,
+}
+
+/// Failed attempts from one address or for one user.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct AuthFailureCount {
+    pub key: String,
+    pub count: u64,
+    /// The latest attempt, microseconds since the epoch
+    pub last_usec: u64,
+}
+
+/// A TCP endpoint: a host name or address, and a port.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash, Default)]
+#[serde(default)]
+pub struct Endpoint {
+    pub host: String,
+    pub port: u16,
+}
+
+/// Whether the host could connect to an endpoint.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct ReachResult {
+    pub endpoint: Endpoint,
+    /// The address connected to (the first of the name's addresses that
+    /// answered)
+    pub address: Option<String>,
+    /// Time to resolve and connect; None when unreachable
+    pub latency_ms: Option<f64>,
+    /// Why the endpoint is unreachable (resolution failed, refused, timed out)
+    pub error: Option<String>,
+}
+
+/// The host's resolver setup and one lookup through it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct DnsReport {
+    #[serde(flatten)]
+    pub config: DnsConfig,
+    pub lookup: DnsLookup,
+}
+
+/// The host's resolver setup.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct DnsConfig {
+    /// Contents of /etc/resolv.conf; None when it cannot be read
+    pub resolv_conf: Option<String>,
+    /// Where /etc/resolv.conf links to when it is a symlink (e.g. the
+    /// systemd-resolved stub file)
+    pub resolv_conf_target: Option<String>,
+    /// `nameserver` and `search` entries of resolv.conf
+    pub nameservers: Vec<String>,
+    pub search: Vec<String>,
+    /// `resolvectl status` output; None when systemd-resolved is not running
+    pub resolved_status: Option<String>,
+}
+
+/// A name resolved the way programs on the host resolve it (getaddrinfo, so
+/// /etc/hosts and nsswitch apply).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct DnsLookup {
+    pub name: String,
+    /// Addresses returned, in order, without duplicates
+    pub addresses: Vec<String>,
+    /// How long the lookup took (until it failed or timed out, on error)
+    pub elapsed_ms: f64,
+    /// Why the lookup failed; None on success
+    pub error: Option<String>,
+}
+
+/// One route of the main routing table.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct Route {
+    /// "default" or a prefix ("192.0.2.0/24", "fd00::/64")
+    pub destination: String,
+    /// Next hop; None for directly connected networks
+    pub gateway: Option<String>,
+    /// Outgoing interface
+    pub dev: String,
+    pub metric: Option<u32>,
+    /// What added it ("kernel", "dhcp", "static", ...); None when read from /proc
+    pub protocol: Option<String>,
+    /// Preferred source address
+    pub source: Option<String>,
+    pub ipv6: bool,
+}
+
+/// Socket counts in the style of `ss -s`, IPv4 and IPv6 together.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct SocketSummary {
+    /// Sockets of all kinds in use ("sockets: used" in /proc/net/sockstat)
+    pub used: Option<u64>,
+    /// TCP sockets in any state
+    pub tcp_total: u64,
+    pub tcp_established: u64,
+    pub tcp_time_wait: u64,
+    pub tcp_listen: u64,
+    /// TCP sockets no longer held by any process
+    pub tcp_orphaned: Option<u64>,
+    pub udp: u64,
+    pub unix: u64,
+    /// Processes holding the most established connections (then sockets),
+    /// most first; only those the agent may see (other users' need root)
+    pub top_processes: Vec<SocketUser>,
+}
+
+/// A process and the TCP and UDP sockets it holds.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct SocketUser {
+    pub pid: u32,
+    pub process: String,
+    /// Established TCP connections
+    pub established: u64,
+    /// TCP and UDP sockets in any state
+    pub sockets: u64,
+}
+
+/// One login as recorded in utmp.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct LoginSession {
+    pub user: String,
+    /// Terminal, e.g. "pts/0" or "tty1"
+    pub tty: String,
+    /// Where the login came from as recorded by the login program: a host
+    /// name, an address or an X display; None for local logins
+    pub host: Option<String>,
+    /// Remote address of the login, when recorded
+    pub ip: Option<String>,
+    /// Microseconds since the epoch
+    pub login_usec: u64,
+    /// The session's login process
+    pub pid: u32,
+}
+
+/// One container as reported by its runtime.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct ContainerInfo {
+    pub name: String,
+    pub image: String,
+    /// Runtime state, e.g. "running", "exited", "paused"
+    pub state: String,
+    /// Published ports, e.g. "0.0.0.0:8080->80/tcp"
+    pub ports: Vec<String>,
+    /// "docker" or "podman"
+    pub runtime: String,
+}
+
+/// One process as sampled from /proc.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub comm: String,
+    /// Owner (real uid), by name when known
+    pub user: String,
+    /// CPU use over the sampling interval; 100 is one core fully busy
+    pub cpu_percent: f32,
+    pub rss_bytes: u64,
+}
+
+/// The busiest processes of a host.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct ProcessesSummary {
+    /// Number of processes running
+    pub total: usize,
+    /// Highest CPU use first
+    pub by_cpu: Vec<ProcessInfo>,
+    /// Largest resident memory first
+    pub by_memory: Vec<ProcessInfo>,
+    /// Length of the CPU sampling interval
+    pub sample_ms: u64,
+}
+
+/// One process in detail, from /proc/<pid>.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct ProcessDetail {
+    pub pid: u32,
+    pub comm: String,
+    /// Owner (real uid), by name when known
+    pub user: String,
+    /// State letter as in ps, e.g. "S" or "R"
+    pub state: String,
+    pub nice: i32,
+    /// Arguments, starting with the program (empty for kernel threads)
+    pub cmdline: Vec<String>,
+    /// None when the agent may not see it (other users' processes need root)
+    pub cwd: Option<String>,
+    /// "NAME=value" entries; None when the agent may not read them
+    pub environ: Option<Vec<String>>,
+    /// Open descriptors other than sockets; None when the agent may not list them
+    pub open_files: Option<Vec<OpenFile>>,
+    pub sockets: Vec<ProcessSocket>,
+}
+
+/// An open file descriptor and what it refers to.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct OpenFile {
+    pub fd: u32,
+    /// A path, or e.g. "pipe:[1234]" or "anon_inode:[eventfd]"
+    pub target: String,
+}
+
+/// A socket held open by a process.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct ProcessSocket {
+    pub fd: u32,
+    /// "tcp", "udp" or "unix"
+    pub protocol: String,
+    /// "address:port", or the path of a unix socket (empty when unnamed)
+    pub local: String,
+    /// Peer "address:port" of a connected TCP or UDP socket
+    pub remote: Option<String>,
+    /// TCP state, e.g. "LISTEN" or "ESTABLISHED" (empty for others)
+    pub state: String,
+}
+
+/// A listening socket.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct NetListener {
+    /// "tcp" or "udp"
+    pub protocol: String,
+    /// Local address, e.g. "0.0.0.0", "::" or "127.0.0.1"
+    pub address: String,
+    pub port: u16,
+    /// Owning process, when the agent may see it (other users' need root)
+    pub pid: Option<u32>,
+    pub process: Option<String>,
+}
+
+impl NetListener {
+    /// What identifies a listener from one list to the next.
+    pub fn key(&self) -> (&str, &str, u16) {
+        (&self.protocol, &self.address, self.port)
+    }
+}
+
+/// A mounted filesystem and its space (pseudo filesystems are left out).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct MountInfo {
+    /// Source, e.g. "/dev/sda1" or "server:/export"
+    pub device: String,
+    pub mountpoint: String,
+    pub fstype: String,
+    pub size_bytes: u64,
+    pub used_bytes: u64,
+    /// Free space usable by unprivileged users
+    pub available_bytes: u64,
+}
+
+/// One kernel parameter, as `sysctl` shows it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct SysctlValue {
+    /// Dotted name, e.g. "net.ipv4.ip_forward"
+    pub key: String,
+    /// The value, fields separated by tabs (e.g. "32768\t60999"); None when
+    /// it could not be read
+    pub value: Option<String>,
+    /// Why the value could not be read (no such parameter, write-only)
+    pub error: Option<String>,
+}
+
+/// One loaded kernel module, as lsmod shows it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct KernelModule {
+    pub name: String,
+    /// Memory the module takes
+    pub size_bytes: u64,
+    /// References held on the module; None when the kernel does not count
+    /// them (built without module unloading)
+    pub use_count: Option<u32>,
+    /// Modules that depend on this one
+    pub used_by: Vec<String>,
+    /// "Live", "Loading" or "Unloading"
+    pub state: String,
+    /// Taint flags the module set, e.g. "OE" for an unsigned out-of-tree module
+    pub taint: Option<String>,
+}
+
+/// Hardware of one host, from /sys (like dmidecode, lspci and lsblk).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct HardwareInventory {
+    /// None without /sys/class/dmi (most ARM boards, some VMs)
+    pub dmi: Option<DmiInfo>,
+    /// By address
+    pub pci: Vec<PciDevice>,
+    /// Disks, with their partitions and the devices stacked on them (LVM,
+    /// RAID, dm-crypt) as children; empty loop devices are left out
+    pub block: Vec<BlockDevice>,
+}
+
+/// Temperatures and fan speeds of one host, by chip then channel.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct SensorReadings {
+    pub temperatures: Vec<Temperature>,
+    pub fans: Vec<Fan>,
+}
+
+/// What a temperature sensor measures, from its chip's driver.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SensorKind {
+    Cpu,
+    Gpu,
+    Nvme,
+    /// SATA/SAS drives (drivetemp)
+    Disk,
+    /// Boards, chipsets, batteries, and kinds this side does not know yet
+    #[default]
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct Temperature {
+    /// Driver name of the chip, e.g. "coretemp", "k10temp", "amdgpu", "nvme"
+    pub chip: String,
+    /// Device the chip belongs to, e.g. "nvme0" or a PCI address
+    pub device: Option<String>,
+    /// e.g. "Package id 0", "Tctl", "edge", "Composite"; "temp1" when unlabelled
+    pub label: String,
+    pub kind: SensorKind,
+    pub celsius: f32,
+    /// Where the chip reports "high" (temp*_max) and "critical" (temp*_crit)
+    pub high: Option<f32>,
+    pub critical: Option<f32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct Fan {
+    pub chip: String,
+    /// e.g. "CPU Fan"; "fan1" when unlabelled
+    pub label: String,
+    pub rpm: u32,
+    /// Alarm threshold, where the chip has one set
+    pub min_rpm: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GpuVendor {
+    Nvidia,
+    Amd,
+    Intel,
+    #[default]
+    #[serde(other)]
+    Other,
+}
+
+/// A GPU; what is known beyond its PCI identity depends on the vendor's
+/// tools and driver (nvidia-smi for NVIDIA, amdgpu's sysfs files for AMD).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct Gpu {
+    /// PCI address, e.g. "0000:01:00.0"
+    pub address: String,
+    pub vendor: GpuVendor,
+    /// e.g. "NVIDIA GeForce RTX 3080", else the pci.ids name or "10de:2206"
+    pub model: String,
+    /// Kernel driver bound to it, e.g. "nvidia", "amdgpu", "i915"
+    pub driver: Option<String>,
+    pub driver_version: Option<String>,
+    /// Dedicated memory (None for integrated GPUs and without vendor tools)
+    pub vram_total_bytes: Option<u64>,
+    pub vram_used_bytes: Option<u64>,
+    /// Busy share (0–100) as the driver last sampled it
+    pub utilization_percent: Option<f32>,
+}
+
+/// Resource use of the agent process (`slarti-remote` itself, and the tools
+/// it ran, which are counted once they exit).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct AgentStats {
+    pub pid: u32,
+    pub uptime_secs: u64,
+    pub rss_bytes: u64,
+    /// Highest resident set size so far
+    pub peak_rss_bytes: u64,
+    pub cpu_user_ms: u64,
+    pub cpu_system_ms: u64,
+    /// CPU time of the tools the agent ran (systemctl, journalctl, ...)
+    pub children_cpu_ms: u64,
+    pub threads: u32,
+    pub open_fds: u32,
+    /// Commands handled this session, and of those, aborted by the watchdog
+    pub commands: u64,
+    pub watchdog_aborts: u64,
+    pub limits: AgentLimits,
+}
+
+/// Self-limits the agent was started with (`slarti-remote --max-memory ...`),
+/// as far as it could apply them.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct AgentLimits {
+    pub memory_bytes: Option<u64>,
+    /// Share of one CPU, e.g. 25
+    pub cpu_percent: Option<u32>,
+    pub nice: Option<i32>,
+    /// Commands running longer are aborted; None when the watchdog is off
+    pub command_timeout_ms: Option<u64>,
+    /// Memory and CPU limits are also enforced by the agent's own cgroup
+    pub cgroup: bool,
+    /// Limits asked for that could not be applied, and why
+    pub notes: Vec<String>,
+}
+
+/// One page of the packages installed on a host.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct PackageList {
+    /// None when no supported package database was found
+    pub manager: Option<PackageManager>,
+    /// Installed packages, all pages
+    pub total: usize,
+    pub offset: usize,
+    /// By name
+    pub packages: Vec<Package>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PackageManager {
+    Dpkg,
+    Rpm,
+    Pacman,
+    Apk,
+    /// A package manager this side does not know yet
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct Package {
+    pub name: String,
+    /// As the package manager writes it, e.g. "1:2.39.2-1ubuntu1"
+    pub version: String,
+    /// e.g. "amd64", "x86_64", "noarch"; None where not recorded
+    pub arch: Option<String>,
+}
+
+/// Package updates waiting to be installed.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct PendingUpdates {
+    /// None when no supported package manager was found
+    pub manager: Option<PackageManager>,
+    /// By name
+    pub packages: Vec<PackageUpdate>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct PackageUpdate {
+    pub name: String,
+    /// Installed version, where the check reports it (dnf does not)
+    pub current: Option<String>,
+    pub available: String,
+}
+
+/// System, board and firmware identity from /sys/class/dmi/id.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct DmiInfo {
+    pub sys_vendor: Option<String>,
+    pub product_name: Option<String>,
+    /// Readable by root only
+    pub product_serial: Option<String>,
+    pub board_vendor: Option<String>,
+    pub board_name: Option<String>,
+    pub bios_vendor: Option<String>,
+    pub bios_version: Option<String>,
+    pub bios_date: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct PciDevice {
+    /// e.g. "0000:00:1f.2"
+    pub address: String,
+    /// e.g. "Network controller" (from the class code)
+    pub class: String,
+    pub vendor_id: u16,
+    pub device_id: u16,
+    /// From pci.ids, when installed on the host
+    pub vendor: Option<String>,
+    pub device: Option<String>,
+    /// Kernel driver bound to the device
+    pub driver: Option<String>,
+    /// Network interfaces of a NIC, e.g. ["eth0"]
+    pub interfaces: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct BlockDevice {
+    /// Kernel name, e.g. "nvme0n1p2" or "dm-0"
+    pub name: String,
+    /// "disk", "part", "lvm" (any device-mapper device), "raid", "loop" or "rom"
+    pub kind: String,
+    pub size_bytes: u64,
+    pub model: Option<String>,
+    pub serial: Option<String>,
+    /// Spinning disk (false for SSDs and most virtual disks)
+    pub rotational: bool,
+    pub removable: bool,
+    /// Where it is mounted, if it is
+    pub mountpoint: Option<String>,
+    pub children: Vec<BlockDevice>,
+}
+
+/// Host metrics at one moment; rates are averaged over `interval_ms`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct MetricsSample {
+    pub interval_ms: u64,
+    /// Busy share of each core (0–100), in core order
+    pub cpu_percent: Vec<f32>,
+    pub mem_total_bytes: u64,
+    pub mem_available_bytes: u64,
+    pub swap_total_bytes: u64,
+    pub swap_free_bytes: u64,
+    /// 1, 5 and 15 minute load averages, if available
+    pub load_avg: Option<[f64; 3]>,
+    /// Whole disks (no partitions)
+    pub disks: Vec<DiskIo>,
+    /// Network interfaces other than loopback
+    pub networks: Vec<NetIo>,
+}
+
+/// IO of one block device: counters since boot and rates over the sample interval.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct DiskIo {
+    pub name: String,
+    pub read_bytes: u64,
+    pub written_bytes: u64,
+    pub read_bytes_per_sec: f64,
+    pub write_bytes_per_sec: f64,
+}
+
+/// Traffic of one network interface: counters since boot and rates over the sample interval.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct NetIo {
+    pub name: String,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    pub rx_bytes_per_sec: f64,
+    pub tx_bytes_per_sec: f64,
+}
+
+/// Which pipe of an `Exec` process an `ExecOutput` was read from.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// Hash function of a `Checksum`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChecksumAlgo {
+    Sha256,
+    Blake3,
+    /// An algorithm this side does not know yet
+    #[serde(other)]
+    Unknown,
+}
+
+/// What a `Subscribe` pushes.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Facet {
+    /// `EventData::Metrics`, rates measured over the interval
+    Metrics,
+    /// `EventData::Processes`
+    Processes,
+    /// `EventData::NetListeners`
+    NetListeners,
+    /// `EventData::RaidDegraded`, only when arrays have degraded since the
+    /// last check (the first check reports those already degraded)
+    RaidDegraded,
+    /// A facet this side does not know yet
+    #[serde(other)]
+    Unknown,
+}
+
+/// Payload of an `Event`, by facet.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "facet", rename_all = "snake_case")]
+pub enum EventData {
+    Metrics {
+        sample: MetricsSample,
+    },
+    Processes {
+        summary: ProcessesSummary,
+    },
+    NetListeners {
+        #[serde(default)]
+        listeners: Vec<NetListener>,
+    },
+    RaidDegraded {
+        #[serde(default)]
+        arrays: Vec<RaidArray>,
+    },
+    /// A payload from a newer agent; clients skip it
+    #[serde(other)]
+    Unknown,
+}
+
+/// One journal entry.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct JournalEntry {
+    /// Wall-clock time (microseconds since the unix epoch)
+    pub realtime_usec: u64,
+    /// syslog priority (0 emerg … 7 debug), if recorded
+    pub priority: Option<u8>,
+    /// systemd unit that logged it, if any
+    pub unit: Option<String>,
+    pub message: String,
+}
+
+/// Services added, changed or removed since an earlier `ServicesDelta` answer.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct ServicesDelta {
+    /// Token to send as `since` in the next request
+    pub token: u64,
+    /// True when `changed` is the complete list rather than a delta
+    pub full: bool,
+    /// Added or changed services (every service when `full`)
+    pub changed: Vec<ServiceInfo>,
+    /// Names of services that no longer exist
+    pub removed: Vec<String>,
+}
+
+impl ServicesDelta {
+    /// Apply the delta to the list it was computed against (kept sorted by name).
+    pub fn apply(self, services: &mut Vec<ServiceInfo>) {
+        if self.full {
+            *services = self.changed;
+            return;
+        }
+        services.retain(|s| !self.removed.contains(&s.name));
+        for svc in self.changed {
+            match services.iter_mut().find(|s| s.name == svc.name) {
+                Some(existing) => *existing = svc,
+                None => services.push(svc),
+            }
+        }
+        services.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+}
+
+/// Listening sockets opened, changed or closed since an earlier
+/// `ListenersDelta` answer.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct ListenersDelta {
+    /// Token to send as `since` in the next request
+    pub token: u64,
+    /// True when `changed` is the complete list rather than a delta
+    pub full: bool,
+    /// Opened listeners and ones whose owner changed (every listener when `full`)
+    pub changed: Vec<NetListener>,
+    /// Closed listeners; only protocol, address and port are set
+    pub removed: Vec<NetListener>,
+}
+
+impl ListenersDelta {
+    /// Apply the delta to the list it was computed against (kept sorted by
+    /// port, protocol and address, as `NetListeners` answers).
+    pub fn apply(self, listeners: &mut Vec<NetListener>) {
+        if self.full {
+            *listeners = self.changed;
+            return;
+        }
+        listeners.retain(|l| !self.removed.iter().any(|r| r.key() == l.key()));
+        for listener in self.changed {
+            match listeners.iter_mut().find(|l| l.key() == listener.key()) {
+                Some(existing) => *existing = listener,
+                None => listeners.push(listener),
+            }
+        }
+        listeners.sort_by(|a, b| {
+            (a.port, &a.protocol, &a.address).cmp(&(b.port, &b.protocol, &b.address))
+        });
+    }
+}
+
+/// File a deploy leaves in `<root>/agent` (beside the version dirs) on hosts
+/// that may only be observed. An agent started from under that dir runs
+/// read-only whenever the file exists, whatever flags it was given.
+pub const AGENT_READ_ONLY_MARKER: &str = "read-only";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum Capability {
     SysInfo,
     StaticConfig,
     ServicesList,
+    /// Accepts `Command::ServiceDetail`
+    ServiceDetail,
+    /// Accepts `Command::CgroupTree`
+    CgroupTree,
+    /// Accepts `Command::Pressure`
+    Pressure,
+    /// Accepts `Command::RaidStatus` (and the `RaidDegraded` facet)
+    RaidStatus,
+    /// Accepts `Command::StorageStacks`
+    StorageStacks,
+    /// Accepts `Command::Backups`
+    Backups,
+    /// Accepts `Command::Sessions`
+    Sessions,
+    /// Accepts `Command::Users`
+    Users,
+    /// Accepts `Command::SshdConfig`
+    SshdConfig,
+    /// Accepts `Command::AuthFailures`
+    AuthFailures,
+    /// Accepts `Command::ContainersList`
     ContainersList,
+    /// Accepts `Command::NetListeners`
     NetListeners,
+    /// Accepts `Command::Reachability`
+    Reachability,
+    /// Accepts `Command::DnsCheck`
+    DnsCheck,
+    /// Accepts `Command::DnsConfig`
+    DnsConfig,
+    /// Accepts `Command::Routes`
+    Routes,
+    /// Accepts `Command::Sockets`
+    Sockets,
+    /// Accepts `Command::DiskUsage`
+    DiskUsage,
+    /// Accepts `Command::HardwareInventory`
+    HardwareInventory,
+    /// Accepts `Command::Sensors`
+    Sensors,
+    /// Accepts `Command::GpuInfo`
+    GpuInfo,
+    /// Accepts `Command::AgentStats`
+    AgentStats,
+    /// Accepts `Command::KernelModules`
+    KernelModules,
+    /// Accepts `Command::Sysctl`
+    Sysctl,
+    /// Accepts `Command::Packages`
+    Packages,
+    /// Accepts `Command::UpdatesAvailable`
+    UpdatesAvailable,
+    /// Accepts `Command::ProcessesSummary`
     ProcessesSummary,
+    /// Accepts `Command::ProcessDetail`
+    ProcessDetail,
+    /// Accepts `Command::SignalProcess` and `Command::ReniceProcess`
+    ProcessControl,
+    /// Accepts `Command::Batch`
+    Batch,
+    /// Accepts `Command::ServicesDelta`
+    ServicesDelta,
+    /// Accepts `Command::ListenersDelta`
+    ListenersDelta,
+    /// Accepts `Command::JournalTail` and `Command::JournalStop`
+    Journal,
+    /// Accepts `Command::MetricsSample`
+    MetricsSample,
+    /// Accepts `Command::Subscribe` and `Command::Unsubscribe`
+    Subscribe,
+    /// Accepts `Command::TailFile` and `Command::TailStop`
+    TailFile,
+    /// Accepts `Command::Exec` and `Command::ExecKill`
+    Exec,
+    /// Accepts `Command::ReadFile`
+    ReadFile,
+    /// Accepts `Command::Checksum`
+    Checksum,
+    /// Accepts `Command::WriteFileBegin`, `Command::WriteFileChunk` and `Command::WriteFileEnd`
+    WriteFile,
+    /// The agent runs read-only (`--read-only`, or the `AGENT_READ_ONLY_MARKER`
+    /// left by a deploy): it refuses every command for which
+    /// `Command::is_mutating` holds and advertises none of their capabilities
+    ReadOnly,
+    /// A capability this side does not know yet
+    #[serde(other)]
+    Unknown,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn newer_agent_response_fields_are_ignored() {
+        let line = r#"{"type":"sys_info_ok","id":2,"trace":"abc","info":{"os":"linux","kernel":"6.1","arch":"x86_64","uptime_secs":5,"hostname":"h","load_avg":[0.1,0.2,0.3],"cpu_temp":41.5}}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::SysInfoOk { id, info } => {
+                assert_eq!(id, 2);
+                assert_eq!(info.hostname, "h");
+                assert_eq!(info.load_avg, Some([0.1, 0.2, 0.3]));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn older_agent_response_fields_default() {
+        // An agent from before load averages and capabilities were added.
+        let line = r#"{"type":"sys_info_ok","id":2,"info":{"os":"linux","kernel":"5.4","arch":"aarch64","uptime_secs":9,"hostname":"pi"}}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::SysInfoOk { info, .. } => {
+                assert_eq!(info.load_avg, None);
+                assert_eq!(info.platform, None);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        let line = r#"{"type":"hello_ack","id":1,"agent_version":"0.0.1"}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::HelloAck { capabilities, .. } => assert!(capabilities.is_empty()),
+            other => panic!("unexpected {:?}", other),
+        }
+        let line = r#"{"type":"services_list_ok","id":4,"services":[{"name":"a.service","active_state":"active","sub_state":"running"}]}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::ServicesListOk { services, .. } => {
+                assert_eq!(services[0].enabled, None);
+                assert!(!services[0].baseline);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn sys_info_time_sync_flag_only() {
+        let line = r#"{"type":"sys_info_ok","id":2,"info":{"hostname":"db1","time_sync":{"synchronized":false}}}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::SysInfoOk { info, .. } => {
+                let sync = info.time_sync.unwrap();
+                assert!(!sync.synchronized);
+                assert_eq!(sync.source, None);
+                assert_eq!(sync.offset_secs, None);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn sys_info_reboot_newer_kernel() {
+        let line = r#"{"type":"sys_info_ok","id":2,"info":{"hostname":"db1","kernel":"6.1.0-18-amd64","reboot":{"newer_kernel":"6.1.0-21-amd64"}}}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::SysInfoOk { info, .. } => {
+                let reboot = info.reboot.unwrap();
+                assert!(reboot.required());
+                assert!(!reboot.flagged);
+                assert!(reboot.packages.is_empty());
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn sys_info_platform_partial() {
+        let line = r#"{"type":"sys_info_ok","id":2,"info":{"hostname":"web1","platform":{"vm":"kvm","provider":"gcp","zone":"europe-west1-b"}}}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::SysInfoOk { info, .. } => {
+                let platform = info.platform.unwrap();
+                assert_eq!(platform.vm.as_deref(), Some("kvm"));
+                assert_eq!(platform.container, None);
+                assert_eq!(platform.instance_type, None);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unknown_variants_fall_back() {
+        let line = r#"{"type":"firewall_rules_ok","id":7,"rules":[]}"#;
+        assert!(matches!(
+            serde_json::from_str::<Response>(line).unwrap(),
+            Response::Unknown
+        ));
+        let line = r#"{"cmd":"firewall_rules","id":7,"table":"filter"}"#;
+        assert!(matches!(
+            serde_json::from_str::<Command>(line).unwrap(),
+            Command::Unknown
+        ));
+        let line = r#"{"type":"hello_ack","id":1,"agent_version":"9.0.0","capabilities":["sys_info","firewall_rules","batch"]}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::HelloAck { capabilities, .. } => assert_eq!(
+                capabilities,
+                vec![Capability::SysInfo, Capability::Unknown, Capability::Batch]
+            ),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn batch_from_newer_client_keeps_known_commands() {
+        let line = r#"{"cmd":"batch","id":5,"commands":[{"cmd":"sys_info","id":2},{"cmd":"firewall_rules","id":3},{"cmd":"services_delta","id":4}]}"#;
+        match serde_json::from_str::<Command>(line).unwrap() {
+            Command::Batch { id, commands } => {
+                assert_eq!(id, 5);
+                assert!(matches!(commands[0], Command::SysInfo { id: 2 }));
+                assert!(matches!(commands[1], Command::Unknown));
+                assert!(matches!(
+                    commands[2],
+                    Command::ServicesDelta { id: 4, since: None }
+                ));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn trace_ids_are_optional_on_the_wire() {
+        // A client without trace ids, and one with them.
+        let req: Request = serde_json::from_str(r#"{"cmd":"sys_info","id":2}"#).unwrap();
+        assert!(matches!(req.command, Command::SysInfo { id: 2 }));
+        assert_eq!(req.trace, None);
+        let req: Request =
+            serde_json::from_str(r#"{"cmd":"sys_info","id":2,"trace":"t-1"}"#).unwrap();
+        assert_eq!(req.trace.as_deref(), Some("t-1"));
+        // An older agent reads a traced request as a plain command.
+        let cmd: Command =
+            serde_json::from_str(r#"{"cmd":"services_list","id":4,"trace":"t-2"}"#).unwrap();
+        assert!(matches!(cmd, Command::ServicesList { id: 4 }));
+        // An older client reads a traced reply as a plain response.
+        let line = serde_json::to_string(&Reply {
+            response: Response::Error {
+                id: 4,
+                message: "boom".into(),
+            },
+            trace: Some("t-2".into()),
+        })
+        .unwrap();
+        assert!(matches!(
+            serde_json::from_str::<Response>(&line).unwrap(),
+            Response::Error { id: 4, .. }
+        ));
+        let reply: Reply = serde_json::from_str(&line).unwrap();
+        assert_eq!(reply.trace.as_deref(), Some("t-2"));
+    }
+
+    #[test]
+    fn current_messages_round_trip() {
+        let cmd = Command::ListDir {
+            id: 3,
+            path: "~/".into(),
+            max: Some(10),
+            skip: None,
+        };
+        let back: Command = serde_json::from_str(&serde_json::to_string(&cmd).unwrap()).unwrap();
+        assert!(matches!(
+            back,
+            Command::ListDir {
+                id: 3,
+                max: Some(10),
+                skip: None,
+                ..
+            }
+        ));
+
+        let resp = Response::ServicesDeltaOk {
+            id: 4,
+            delta: ServicesDelta {
+                token: 9,
+                full: false,
+                changed: vec![ServiceInfo {
+                    name: "a.service".into(),
+                    ..Default::default()
+                }],
+                removed: vec!["b.service".into()],
+            },
+        };
+        let back: Response = serde_json::from_str(&serde_json::to_string(&resp).unwrap()).unwrap();
+        match back {
+            Response::ServicesDeltaOk { delta, .. } => {
+                assert_eq!(delta.token, 9);
+                assert_eq!(delta.removed, vec!["b.service".to_string()]);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    fn service(name: &str, active: &str) -> ServiceInfo {
+        ServiceInfo {
+            name: name.into(),
+            active_state: active.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn services_delta_apply_replaces_adds_and_removes() {
+        let mut services = vec![
+            service("a.service", "active"),
+            service("b.service", "active"),
+            service("c.service", "active"),
+        ];
+        ServicesDelta {
+            token: 2,
+            full: false,
+            changed: vec![
+                service("d.service", "active"),
+                service("a.service", "failed"),
+            ],
+            removed: vec!["b.service".into(), "gone.service".into()],
+        }
+        .apply(&mut services);
+        assert_eq!(
+            services,
+            vec![
+                service("a.service", "failed"),
+                service("c.service", "active"),
+                service("d.service", "active"),
+            ]
+        );
+
+        ServicesDelta {
+            token: 3,
+            full: true,
+            changed: vec![service("z.service", "active")],
+            removed: vec![],
+        }
+        .apply(&mut services);
+        assert_eq!(services, vec![service("z.service", "active")]);
+    }
+
+    fn listener(protocol: &str, address: &str, port: u16, process: Option<&str>) -> NetListener {
+        NetListener {
+            protocol: protocol.into(),
+            address: address.into(),
+            port,
+            pid: None,
+            process: process.map(String::from),
+        }
+    }
+
+    #[test]
+    fn listeners_delta_apply_matches_on_protocol_address_and_port() {
+        let mut listeners = vec![
+            listener("tcp", "0.0.0.0", 22, Some("sshd")),
+            listener("tcp", "0.0.0.0", 80, Some("nginx")),
+            listener("udp", "0.0.0.0", 80, None),
+        ];
+        ListenersDelta {
+            token: 5,
+            full: false,
+            changed: vec![
+                listener("tcp", "::", 443, Some("nginx")),
+                listener("tcp", "0.0.0.0", 22, Some("dropbear")),
+            ],
+            removed: vec![listener("tcp", "0.0.0.0", 80, None)],
+        }
+        .apply(&mut listeners);
+        assert_eq!(
+            listeners,
+            vec![
+                listener("tcp", "0.0.0.0", 22, Some("dropbear")),
+                listener("udp", "0.0.0.0", 80, None),
+                listener("tcp", "::", 443, Some("nginx")),
+            ]
+        );
+    }
+
+    #[test]
+    fn listeners_delta_round_trips() {
+        let cmd: Command =
+            serde_json::from_str(r#"{"cmd":"listeners_delta","id":8,"since":3}"#).unwrap();
+        assert!(matches!(
+            cmd,
+            Command::ListenersDelta {
+                id: 8,
+                since: Some(3)
+            }
+        ));
+        let line = r#"{"type":"listeners_delta_ok","id":8,"delta":{"token":4,"changed":[{"protocol":"tcp","address":"::","port":443}],"removed":[{"protocol":"tcp","address":"0.0.0.0","port":80}]}}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::ListenersDeltaOk { id, delta } => {
+                assert_eq!(id, 8);
+                assert!(!delta.full);
+                assert_eq!(delta.changed[0].key(), ("tcp", "::", 443));
+                assert_eq!(delta.removed[0].key(), ("tcp", "0.0.0.0", 80));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn containers_list_round_trips() {
+        let cmd: Command = serde_json::from_str(r#"{"cmd":"containers_list","id":6}"#).unwrap();
+        assert!(matches!(cmd, Command::ContainersList { id: 6 }));
+        let line = r#"{"type":"containers_list_ok","id":6,"containers":[{"name":"web","image":"nginx:1.27","state":"running","runtime":"podman"}]}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::ContainersListOk { containers, .. } => {
+                assert_eq!(containers[0].name, "web");
+                assert!(containers[0].ports.is_empty());
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn processes_summary_defaults() {
+        let cmd: Command = serde_json::from_str(r#"{"cmd":"processes_summary","id":9}"#).unwrap();
+        assert!(matches!(
+            cmd,
+            Command::ProcessesSummary { id: 9, limit: None }
+        ));
+        let line = r#"{"type":"processes_summary_ok","id":9,"summary":{"total":3,"by_cpu":[{"pid":1,"comm":"init","cpu_percent":0.5}]}}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::ProcessesSummaryOk { summary, .. } => {
+                assert_eq!(summary.total, 3);
+                assert_eq!(summary.by_cpu[0].rss_bytes, 0);
+                assert!(summary.by_memory.is_empty());
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn disk_usage_round_trips() {
+        let line = r#"{"type":"disk_usage_ok","id":8,"mounts":[{"device":"/dev/sda1","mountpoint":"/","fstype":"ext4","size_bytes":100,"used_bytes":40}]}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::DiskUsageOk { id, mounts } => {
+                assert_eq!(id, 8);
+                assert_eq!(mounts[0].mountpoint, "/");
+                assert_eq!(mounts[0].available_bytes, 0);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn cgroup_tree_nests() {
+        let line = r#"{"type":"cgroup_tree_ok","id":2,"root":{"name":"/","path":"/","cpu_percent":12.5,"children":[{"name":"system.slice","path":"/system.slice","memory_bytes":1024,"children":[{"name":"sshd.service","path":"/system.slice/sshd.service","processes":1}]}]}}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::CgroupTreeOk { root, .. } => {
+                assert_eq!(root.memory_bytes, None);
+                let slice = &root.children[0];
+                assert_eq!(slice.memory_bytes, Some(1024));
+                assert_eq!(slice.children[0].processes, 1);
+                assert_eq!(slice.children[0].cpu_percent, None);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn pressure_without_psi_or_kernel_log() {
+        let cmd: Command = serde_json::from_str(r#"{"cmd":"pressure","id":3}"#).unwrap();
+        assert_eq!(cmd.name(), "pressure");
+        let line = r#"{"type":"pressure_ok","id":3,"pressure":{"memory":{"some":{"avg10":1.5,"total_usec":42}}}}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::PressureOk { pressure, .. } => {
+                assert_eq!(pressure.cpu, None);
+                let memory = pressure.memory.unwrap();
+                assert_eq!(memory.some.avg10, 1.5);
+                assert_eq!(memory.some.total_usec, 42);
+                assert_eq!(memory.full, None);
+                assert_eq!(pressure.oom_kills, None);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn read_file_chunk_decodes() {
+        let cmd: Command =
+            serde_json::from_str(r#"{"cmd":"read_file","id":6,"path":"/etc/hostname"}"#).unwrap();
+        assert_eq!(cmd.name(), "read_file");
+        assert!(matches!(
+            cmd,
+            Command::ReadFile {
+                offset: None,
+                len: None,
+                ..
+            }
+        ));
+        let line = r#"{"type":"read_file_ok","id":6,"chunk":{"path":"/etc/hostname","len":5,"size":5,"data":"aG9zdAo=","eof":true}}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::ReadFileOk { chunk, .. } => {
+                assert_eq!(chunk.offset, 0);
+                assert_eq!(chunk.bytes().unwrap(), b"host\n");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        for bytes in [&b""[..], b"a", b"ab", b"abc", &[0, 255, 128, 7]] {
+            assert_eq!(base64::decode(&base64::encode(bytes)).unwrap(), bytes);
+        }
+        assert!(base64::decode("a*==").is_err());
+    }
+
+    #[test]
+    fn write_file_commands_share_an_id() {
+        let lines = [
+            r#"{"cmd":"write_file_begin","id":8,"path":"/etc/motd"}"#,
+            r#"{"cmd":"write_file_chunk","id":8,"data":"aGkK"}"#,
+            r#"{"cmd":"write_file_end","id":8}"#,
+        ];
+        let cmds: Vec<Command> = lines
+            .iter()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert!(matches!(
+            cmds[0],
+            Command::WriteFileBegin {
+                id: 8,
+                mode: None,
+                ..
+            }
+        ));
+        assert_eq!(cmds[1].name(), "write_file_chunk");
+        assert!(matches!(
+            cmds[2],
+            Command::WriteFileEnd {
+                id: 8,
+                abort: false
+            }
+        ));
+        let line = r#"{"type":"write_file_done","id":8,"path":"/etc/motd","size":3,"saved":true}"#;
+        assert!(matches!(
+            serde_json::from_str::<Response>(line).unwrap(),
+            Response::WriteFileDone {
+                size: 3,
+                saved: true,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn static_config_topology_is_optional() {
+        let line =
+            r#"{"type":"static_config_ok","id":3,"config":{"cpu_count":4,"mem_total_bytes":1024}}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::StaticConfigOk { config, .. } => assert_eq!(config.topology, None),
+            other => panic!("unexpected {:?}", other),
+        }
+        let line = r#"{"type":"static_config_ok","id":3,"config":{"topology":{"sockets":1,"cores":2,"threads":4,"cpus":[{"id":0,"node":0}],"caches":[{"level":2,"kind":"Unified","size_bytes":1048576,"shared_by":2,"instances":2}]}}}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::StaticConfigOk { config, .. } => {
+                let topology = config.topology.unwrap();
+                assert_eq!(topology.cpus[0].node, Some(0));
+                assert_eq!(topology.caches[0].instances, 2);
+                assert!(topology.numa_nodes.is_empty());
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn checksum_algos_decode() {
+        let cmd: Command =
+            serde_json::from_str(r#"{"cmd":"checksum","id":9,"path":"/bin/sh","algo":"blake3"}"#)
+                .unwrap();
+        assert_eq!(cmd.name(), "checksum");
+        assert!(matches!(
+            cmd,
+            Command::Checksum {
+                algo: ChecksumAlgo::Blake3,
+                ..
+            }
+        ));
+        let cmd: Command =
+            serde_json::from_str(r#"{"cmd":"checksum","id":9,"path":"/bin/sh","algo":"md5"}"#)
+                .unwrap();
+        assert!(matches!(
+            cmd,
+            Command::Checksum {
+                algo: ChecksumAlgo::Unknown,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn hardware_inventory_nests_block_devices() {
+        let line = r#"{"type":"hardware_inventory_ok","id":4,"inventory":{"pci":[{"address":"0000:00:04.0","class":"Ethernet controller","vendor_id":6900,"device_id":4161,"interfaces":["eth0"]}],"block":[{"name":"vda","kind":"disk","size_bytes":1024,"children":[{"name":"vda1","kind":"part","mountpoint":"/"}]}]}}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::HardwareInventoryOk { inventory, .. } => {
+                assert_eq!(inventory.dmi, None);
+                assert_eq!(inventory.pci[0].vendor_id, 0x1af4);
+                assert_eq!(inventory.pci[0].driver, None);
+                let part = &inventory.block[0].children[0];
+                assert_eq!(part.mountpoint.as_deref(), Some("/"));
+                assert!(part.children.is_empty());
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn sysctl_missing_key() {
+        let line = r#"{"type":"sysctl_ok","id":8,"values":[{"key":"vm.swappiness","value":"60"},{"key":"net.ipv4.nope","error":"no such parameter"}]}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::SysctlOk { values, .. } => {
+                assert_eq!(values[0].value.as_deref(), Some("60"));
+                assert_eq!(values[1].value, None);
+                assert!(values[1].error.is_some());
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn kernel_module_untainted() {
+        let line = r#"{"type":"kernel_modules_ok","id":7,"modules":[{"name":"nf_conntrack","size_bytes":172032,"use_count":2,"used_by":["nf_nat","xt_conntrack"],"state":"Live"}]}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::KernelModulesOk { modules, .. } => {
+                assert_eq!(modules[0].use_count, Some(2));
+                assert_eq!(modules[0].used_by.len(), 2);
+                assert_eq!(modules[0].taint, None);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn backup_never_failed() {
+        let line = r#"{"type":"backups_ok","id":5,"jobs":[{"tool":"restic","source":"restic-backup.service","last_success_usec":1714534201000000}]}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::BackupsOk { jobs, .. } => {
+                assert_eq!(jobs[0].last_success_usec, Some(1_714_534_201_000_000));
+                assert_eq!(jobs[0].last_failure_usec, None);
+                assert!(!jobs[0].running);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        let line = r#"{"type":"backups_ok","id":5}"#;
+        assert!(matches!(
+            serde_json::from_str::<Response>(line).unwrap(),
+            Response::BackupsOk { jobs, .. } if jobs.is_empty()
+        ));
+    }
+
+    #[test]
+    fn users_without_shadow() {
+        let line = r#"{"type":"users_ok","id":3,"users":{"users":[{"name":"ops","uid":1000,"gid":1000,"home":"/home/ops","shell":"/bin/bash","can_login":true}],"admin_groups":[{"name":"sudo","gid":27,"members":["ops"]}],"sudoers_error":"/etc/sudoers: Permission denied (os error 13)"}}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::UsersOk { users, .. } => {
+                assert_eq!(users.users[0].password_locked, None);
+                assert_eq!(users.admin_groups[0].members, ["ops"]);
+                assert!(users.sudo_rules.is_empty());
+                assert!(users.sudoers_error.is_some());
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn local_session_has_no_host() {
+        let line = r#"{"type":"sessions_ok","id":6,"sessions":[{"user":"root","tty":"tty1","login_usec":1714534201000000,"pid":812},{"user":"ops","tty":"pts/0","host":"203.0.113.7","ip":"203.0.113.7","login_usec":1714537801000000,"pid":2290}]}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::SessionsOk { sessions, .. } => {
+                assert_eq!(sessions[0].host, None);
+                assert_eq!(sessions[0].ip, None);
+                assert_eq!(sessions[1].tty, "pts/0");
+                assert_eq!(sessions[1].ip.as_deref(), Some("203.0.113.7"));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reachability_results() {
+        let cmd: Command = serde_json::from_str(
+            r#"{"cmd":"reachability","id":3,"targets":[{"host":"deb.debian.org","port":443}]}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            cmd,
+            Command::Reachability { ref targets, timeout_ms: None, .. } if targets[0].port == 443
+        ));
+        let line = r#"{"type":"reachability_ok","id":3,"results":[{"endpoint":{"host":"deb.debian.org","port":443},"address":"151.101.2.132","latency_ms":14.2},{"endpoint":{"host":"registry.internal","port":5000},"error":"connection timed out"}]}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::ReachabilityOk { results, .. } => {
+                assert_eq!(results[0].error, None);
+                assert_eq!(results[1].latency_ms, None);
+                assert_eq!(results[1].endpoint.host, "registry.internal");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn dns_check_failed_lookup() {
+        let cmd: Command = serde_json::from_str(r#"{"cmd":"dns_check","id":9}"#).unwrap();
+        assert!(matches!(cmd, Command::DnsCheck { name: None, .. }));
+        let line = r#"{"type":"dns_check_ok","id":9,"report":{"resolv_conf_target":"../run/systemd/resolve/stub-resolv.conf","nameservers":["127.0.0.53"],"lookup":{"name":"example.invalid","elapsed_ms":12.5,"error":"failed to lookup address information"}}}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::DnsCheckOk { report, .. } => {
+                assert_eq!(report.config.resolv_conf, None);
+                assert_eq!(report.config.nameservers, ["127.0.0.53"]);
+                assert!(report.config.search.is_empty());
+                assert!(report.lookup.addresses.is_empty());
+                assert!(report.lookup.error.is_some());
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn dns_config_search_domains() {
+        let line = r#"{"type":"dns_config_ok","id":5,"config":{"resolv_conf":"nameserver 10.0.0.2\nsearch corp.example\n","nameservers":["10.0.0.2"],"search":["corp.example"]}}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::DnsConfigOk { config, .. } => {
+                assert_eq!(config.search, ["corp.example"]);
+                assert_eq!(config.resolv_conf_target, None);
+                assert_eq!(config.resolved_status, None);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn routes_default_and_link() {
+        let cmd: Command = serde_json::from_str(r#"{"cmd":"routes","id":4}"#).unwrap();
+        assert_eq!(cmd.name(), "routes");
+        let line = r#"{"type":"routes_ok","id":4,"routes":[{"destination":"default","gateway":"192.0.2.1","dev":"eth0","metric":100},{"destination":"192.0.2.0/24","dev":"eth0","protocol":"kernel","source":"192.0.2.2"}]}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::RoutesOk { routes, .. } => {
+                assert_eq!(routes[0].gateway.as_deref(), Some("192.0.2.1"));
+                assert_eq!(routes[1].gateway, None);
+                assert_eq!(routes[1].metric, None);
+                assert!(!routes[1].ipv6);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn sockets_summary_top_processes() {
+        let cmd: Command = serde_json::from_str(r#"{"cmd":"sockets","id":6}"#).unwrap();
+        assert_eq!(cmd.name(), "sockets");
+        let line = r#"{"type":"sockets_ok","id":6,"summary":{"tcp_total":9,"tcp_established":5,"tcp_time_wait":2,"tcp_listen":2,"udp":3,"unix":40,"top_processes":[{"pid":812,"process":"nginx","established":4,"sockets":6}]}}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::SocketsOk { summary, .. } => {
+                assert_eq!(summary.used, None);
+                assert_eq!(summary.tcp_orphaned, None);
+                assert_eq!(summary.top_processes[0].process, "nginx");
+                assert_eq!(summary.top_processes[0].established, 4);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn sensors_readings_kinds() {
+        let cmd: Command = serde_json::from_str(r#"{"cmd":"sensors","id":7}"#).unwrap();
+        assert_eq!(cmd.name(), "sensors");
+        let line = r#"{"type":"sensors_ok","id":7,"readings":{"temperatures":[{"chip":"nvme","device":"nvme0","label":"Composite","kind":"nvme","celsius":41.85,"high":81.85},{"chip":"asus_ec","label":"Chipset","kind":"board","celsius":50.0}],"fans":[{"chip":"nct6798","label":"fan2","rpm":1120}]}}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::SensorsOk { readings, .. } => {
+                assert_eq!(readings.temperatures[0].kind, SensorKind::Nvme);
+                assert_eq!(readings.temperatures[0].critical, None);
+                // Kinds from a newer agent read as Other.
+                assert_eq!(readings.temperatures[1].kind, SensorKind::Other);
+                assert_eq!(readings.fans[0].rpm, 1120);
+                assert_eq!(readings.fans[0].min_rpm, None);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn gpu_info_vendor_and_memory() {
+        let cmd: Command = serde_json::from_str(r#"{"cmd":"gpu_info","id":8}"#).unwrap();
+        assert_eq!(cmd.name(), "gpu_info");
+        let line = r#"{"type":"gpu_info_ok","id":8,"gpus":[{"address":"0000:01:00.0","vendor":"nvidia","model":"NVIDIA A100-SXM4-40GB","driver":"nvidia","driver_version":"550.54.14","vram_total_bytes":42949672960,"vram_used_bytes":1048576,"utilization_percent":37.0},{"address":"0000:00:02.0","vendor":"intel","model":"Alder Lake-P GT2"}]}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::GpuInfoOk { gpus, .. } => {
+                assert_eq!(gpus[0].vendor, GpuVendor::Nvidia);
+                assert_eq!(gpus[0].vram_total_bytes, Some(40 << 30));
+                assert_eq!(gpus[1].vram_total_bytes, None);
+                assert_eq!(gpus[1].driver, None);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn read_only_agent_refuses_mutating_commands() {
+        let line = r#"{"type":"hello_ack","id":1,"agent_version":"0.1.0","capabilities":["sys_info","read_only"]}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::HelloAck { capabilities, .. } => {
+                assert_eq!(
+                    capabilities,
+                    vec![Capability::SysInfo, Capability::ReadOnly]
+                );
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        let signal: Command =
+            serde_json::from_str(r#"{"cmd":"signal_process","id":2,"pid":42,"signal":15}"#)
+                .unwrap();
+        assert!(signal.is_mutating());
+        let batch: Command = serde_json::from_str(
+            r#"{"cmd":"batch","id":3,"commands":[{"cmd":"sys_info","id":3}]}"#,
+        )
+        .unwrap();
+        assert!(!batch.is_mutating());
+    }
+
+    #[test]
+    fn is_mutating_covers_every_command() {
+        let lines = [
+            r#"{"cmd":"hello","id":1}"#,
+            r#"{"cmd":"sys_info","id":1}"#,
+            r#"{"cmd":"static_config","id":1}"#,
+            r#"{"cmd":"services_list","id":1}"#,
+            r#"{"cmd":"service_detail","id":1,"unit":"sshd.service"}"#,
+            r#"{"cmd":"cgroup_tree","id":1}"#,
+            r#"{"cmd":"pressure","id":1}"#,
+            r#"{"cmd":"raid_status","id":1}"#,
+            r#"{"cmd":"storage_stacks","id":1}"#,
+            r#"{"cmd":"backups","id":1}"#,
+            r#"{"cmd":"sessions","id":1}"#,
+            r#"{"cmd":"users","id":1}"#,
+            r#"{"cmd":"sshd_config","id":1}"#,
+            r#"{"cmd":"auth_failures","id":1}"#,
+            r#"{"cmd":"containers_list","id":1}"#,
+            r#"{"cmd":"processes_summary","id":1}"#,
+            r#"{"cmd":"process_detail","id":1,"pid":42}"#,
+            r#"{"cmd":"signal_process","id":1,"pid":42,"signal":15}"#,
+            r#"{"cmd":"renice_process","id":1,"pid":42,"nice":5}"#,
+            r#"{"cmd":"net_listeners","id":1}"#,
+            r#"{"cmd":"reachability","id":1,"targets":[]}"#,
+            r#"{"cmd":"dns_check","id":1}"#,
+            r#"{"cmd":"dns_config","id":1}"#,
+            r#"{"cmd":"routes","id":1}"#,
+            r#"{"cmd":"sockets","id":1}"#,
+            r#"{"cmd":"disk_usage","id":1}"#,
+            r#"{"cmd":"hardware_inventory","id":1}"#,
+            r#"{"cmd":"sensors","id":1}"#,
+            r#"{"cmd":"gpu_info","id":1}"#,
+            r#"{"cmd":"agent_stats","id":1}"#,
+            r#"{"cmd":"kernel_modules","id":1}"#,
+            r#"{"cmd":"sysctl","id":1,"keys":[]}"#,
+            r#"{"cmd":"packages","id":1}"#,
+            r#"{"cmd":"updates_available","id":1}"#,
+            r#"{"cmd":"metrics_sample","id":1}"#,
+            r#"{"cmd":"services_delta","id":1,"since":null}"#,
+            r#"{"cmd":"listeners_delta","id":1,"since":7}"#,
+            r#"{"cmd":"list_dir","id":1,"path":"/"}"#,
+            r#"{"cmd":"read_file","id":1,"path":"/etc/hosts"}"#,
+            r#"{"cmd":"checksum","id":1,"path":"/etc/hosts","algo":"sha256"}"#,
+            r#"{"cmd":"write_file_begin","id":1,"path":"/tmp/x"}"#,
+            r#"{"cmd":"write_file_chunk","id":1,"data":""}"#,
+            r#"{"cmd":"write_file_end","id":1}"#,
+            r#"{"cmd":"journal_tail","id":1}"#,
+            r#"{"cmd":"journal_stop","id":1}"#,
+            r#"{"cmd":"tail_file","id":1,"path":"/var/log/syslog"}"#,
+            r#"{"cmd":"tail_stop","id":1}"#,
+            r#"{"cmd":"exec","id":1,"argv":["true"]}"#,
+            r#"{"cmd":"exec_kill","id":1}"#,
+            r#"{"cmd":"subscribe","id":1,"facet":"metrics"}"#,
+            r#"{"cmd":"unsubscribe","id":1}"#,
+            r#"{"cmd":"batch","id":1,"commands":[{"cmd":"exec","id":2,"argv":["true"]}]}"#,
+            r#"{"cmd":"something_newer","id":1}"#,
+        ];
+        let mut names = std::collections::HashSet::new();
+        for line in lines {
+            let cmd: Command = serde_json::from_str(line).unwrap();
+            // No wildcard: a new command does not compile here until it is
+            // classified (and listed above).
+            let mutating = match &cmd {
+                Command::SignalProcess { .. }
+                | Command::ReniceProcess { .. }
+                | Command::WriteFileBegin { .. }
+                | Command::WriteFileChunk { .. }
+                | Command::WriteFileEnd { .. }
+                | Command::Exec { .. } => true,
+                Command::Hello { .. }
+                | Command::SysInfo { .. }
+                | Command::StaticConfig { .. }
+                | Command::ServicesList { .. }
+                | Command::ServiceDetail { .. }
+                | Command::CgroupTree { .. }
+                | Command::Pressure { .. }
+                | Command::RaidStatus { .. }
+                | Command::StorageStacks { .. }
+                | Command::Backups { .. }
+                | Command::Sessions { .. }
+                | Command::Users { .. }
+                | Command::SshdConfig { .. }
+                | Command::AuthFailures { .. }
+                | Command::ContainersList { .. }
+                | Command::ProcessesSummary { .. }
+                | Command::ProcessDetail { .. }
+                | Command::NetListeners { .. }
+                | Command::Reachability { .. }
+                | Command::DnsCheck { .. }
+                | Command::DnsConfig { .. }
+                | Command::Routes { .. }
+                | Command::Sockets { .. }
+                | Command::DiskUsage { .. }
+                | Command::HardwareInventory { .. }
+                | Command::Sensors { .. }
+                | Command::GpuInfo { .. }
+                | Command::AgentStats { .. }
+                | Command::KernelModules { .. }
+                | Command::Sysctl { .. }
+                | Command::Packages { .. }
+                | Command::UpdatesAvailable { .. }
+                | Command::MetricsSample { .. }
+                | Command::ServicesDelta { .. }
+                | Command::ListenersDelta { .. }
+                | Command::ListDir { .. }
+                | Command::ReadFile { .. }
+                | Command::Checksum { .. }
+                | Command::JournalTail { .. }
+                | Command::JournalStop { .. }
+                | Command::TailFile { .. }
+                | Command::TailStop { .. }
+                | Command::ExecKill { .. }
+                | Command::Subscribe { .. }
+                | Command::Unsubscribe { .. }
+                | Command::Batch { .. }
+                | Command::Unknown => false,
+            };
+            assert_eq!(cmd.is_mutating(), mutating, "{}", cmd.name());
+            assert!(names.insert(cmd.name()), "{} listed twice", cmd.name());
+        }
+    }
+
+    #[test]
+    fn agent_stats_with_limits() {
+        let cmd: Command = serde_json::from_str(r#"{"cmd":"agent_stats","id":5}"#).unwrap();
+        assert_eq!(cmd.name(), "agent_stats");
+        let line = r#"{"type":"agent_stats_ok","id":5,"stats":{"pid":4242,"rss_bytes":10485760,"cpu_user_ms":320,"commands":12,"watchdog_aborts":1,"limits":{"memory_bytes":268435456,"cpu_percent":25,"command_timeout_ms":300000,"notes":["cpu limit needs a cgroup of its own; running at nice 10"]}}}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::AgentStatsOk { stats, .. } => {
+                assert_eq!(stats.watchdog_aborts, 1);
+                assert_eq!(stats.peak_rss_bytes, 0);
+                assert_eq!(stats.limits.memory_bytes, Some(256 << 20));
+                assert_eq!(stats.limits.nice, None);
+                assert!(!stats.limits.cgroup);
+                assert_eq!(stats.limits.notes.len(), 1);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn sshd_config_from_files() {
+        let line = r#"{"type":"sshd_config_ok","id":2,"config":{"effective_error":"sshd: no hostkeys available -- exiting.","files":["/etc/ssh/sshd_config"],"settings":[{"key":"permitrootlogin","value":"yes"}]}}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::SshdConfigOk {
+                config: Some(config),
+                ..
+            } => {
+                assert!(!config.effective);
+                assert_eq!(config.settings[0].key, "permitrootlogin");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        let line = r#"{"type":"sshd_config_ok","id":2}"#;
+        assert!(matches!(
+            serde_json::from_str::<Response>(line).unwrap(),
+            Response::SshdConfigOk { config: None, .. }
+        ));
+    }
+
+    #[test]
+    fn auth_failures_without_log() {
+        let line = r#"{"type":"auth_failures_ok","id":4,"summary":{"window_secs":86400}}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::AuthFailuresOk { summary, .. } => {
+                assert_eq!(summary.window_secs, 86_400);
+                assert_eq!(summary.source, None);
+                assert_eq!(summary.total, 0);
+                assert!(summary.by_address.is_empty());
+                assert_eq!(summary.fail2ban, None);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn raid_degraded_event() {
+        let cmd: Command =
+            serde_json::from_str(r#"{"cmd":"subscribe","id":8,"facet":"raid_degraded"}"#).unwrap();
+        assert!(matches!(
+            cmd,
+            Command::Subscribe {
+                facet: Facet::RaidDegraded,
+                ..
+            }
+        ));
+        let line = r#"{"type":"event","id":8,"data":{"facet":"raid_degraded","arrays":[{"name":"md1","level":"raid5","active":true,"devices":3,"working":2,"degraded":true,"members":[{"device":"sdb2","slot":1,"faulty":true}],"sync":{"action":"recovery","percent":8.5}}]}}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::Event {
+                data: EventData::RaidDegraded { arrays },
+                ..
+            } => {
+                assert!(arrays[0].degraded);
+                assert!(!arrays[0].members[0].spare);
+                let sync = arrays[0].sync.as_ref().unwrap();
+                assert_eq!(sync.finish_secs, None);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn storage_stacks_per_tool() {
+        let cmd: Command = serde_json::from_str(r#"{"cmd":"storage_stacks","id":9}"#).unwrap();
+        assert_eq!(cmd.name(), "storage_stacks");
+        let line = r#"{"type":"storage_stacks_ok","id":9,"stacks":{"zfs":[{"name":"tank","state":"DEGRADED","devices":[{"name":"tank","state":"DEGRADED"},{"name":"sdb","depth":2,"state":"UNAVAIL","note":"cannot open"}]}],"lvm":[{"name":"vg0","size_bytes":107374182400,"volumes":[{"name":"pool","kind":"thin-pool","active":true,"data_percent":81.5}]}]}}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::StorageStacksOk { stacks, .. } => {
+                assert!(stacks.btrfs.is_empty());
+                let pool = &stacks.zfs[0];
+                assert_eq!(pool.devices[1].depth, 2);
+                assert_eq!(pool.devices[1].read_errors, 0);
+                assert_eq!(pool.devices[1].note.as_deref(), Some("cannot open"));
+                let vg = &stacks.lvm[0];
+                assert!(!vg.partial);
+                assert_eq!(vg.volumes[0].data_percent, Some(81.5));
+                assert_eq!(vg.volumes[0].pool, None);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn raid_status_with_mdadm_detail() {
+        let line = r#"{"type":"raid_status_ok","id":3,"arrays":[{"name":"md0","level":"raid1","active":true,"state":"clean, degraded, recovering","devices":2,"working":1,"degraded":true,"members":[{"device":"sda1","slot":0,"state":"active sync"},{"device":"sdb1","slot":2,"spare":true,"rebuilding":true,"state":"spare rebuilding"}]}]}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::RaidStatusOk { arrays, .. } => {
+                assert_eq!(
+                    arrays[0].state.as_deref(),
+                    Some("clean, degraded, recovering")
+                );
+                assert_eq!(arrays[0].uuid, None);
+                let [sync_member, rebuilding] = &arrays[0].members[..] else {
+                    panic!("two members expected");
+                };
+                assert!(!sync_member.rebuilding);
+                assert!(rebuilding.spare && rebuilding.rebuilding);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn packages_page() {
+        let cmd: Command =
+            serde_json::from_str(r#"{"cmd":"packages","id":6,"offset":500}"#).unwrap();
+        assert_eq!(cmd.name(), "packages");
+        assert!(matches!(
+            cmd,
+            Command::Packages {
+                offset: Some(500),
+                limit: None,
+                ..
+            }
+        ));
+        let line = r#"{"type":"packages_ok","id":6,"packages":{"manager":"xbps","total":1,"packages":[{"name":"zsh","version":"5.9_1"}]}}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::PackagesOk { packages, .. } => {
+                assert_eq!(packages.manager, Some(PackageManager::Unknown));
+                assert_eq!(packages.offset, 0);
+                assert_eq!(packages.packages[0].arch, None);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn updates_available_without_current_version() {
+        let line = r#"{"type":"updates_available_ok","id":7,"updates":{"manager":"rpm","packages":[{"name":"kernel.x86_64","available":"6.8.9-300.fc40"}]}}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::UpdatesAvailableOk { updates, .. } => {
+                assert_eq!(updates.manager, Some(PackageManager::Rpm));
+                assert_eq!(updates.packages[0].current, None);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn exec_round_trip() {
+        let cmd: Command =
+            serde_json::from_str(r#"{"cmd":"exec","id":21,"argv":["uname","-r"]}"#).unwrap();
+        assert_eq!(cmd.name(), "exec");
+        match cmd {
+            Command::Exec {
+                argv,
+                cwd,
+                env,
+                timeout_ms,
+                ..
+            } => {
+                assert_eq!(argv, vec!["uname".to_string(), "-r".to_string()]);
+                assert_eq!(cwd, None);
+                assert!(env.is_empty());
+                assert_eq!(timeout_ms, None);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        let line = r#"{"type":"exec_output","id":21,"stream":"stderr","data":"oops\n"}"#;
+        assert!(matches!(
+            serde_json::from_str::<Response>(line).unwrap(),
+            Response::ExecOutput {
+                stream: OutputStream::Stderr,
+                ..
+            }
+        ));
+        let line = r#"{"type":"exec_exit","id":21,"signal":9}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::ExecExit {
+                code,
+                signal,
+                timed_out,
+                ..
+            } => {
+                assert_eq!(code, None);
+                assert_eq!(signal, Some(9));
+                assert!(!timed_out);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn tail_file_defaults_to_one_shot() {
+        let cmd: Command = serde_json::from_str(
+            r#"{"cmd":"tail_file","id":12,"path":"/var/log/nginx/access.log"}"#,
+        )
+        .unwrap();
+        assert_eq!(cmd.name(), "tail_file");
+        assert!(matches!(
+            cmd,
+            Command::TailFile {
+                lines: None,
+                follow: false,
+                ..
+            }
+        ));
+        let line = r#"{"type":"file_lines","id":12,"lines":["GET /"]}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::FileLines { lines, end, .. } => {
+                assert_eq!(lines, vec!["GET /".to_string()]);
+                assert!(!end);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn service_detail_round_trips() {
+        let cmd: Command =
+            serde_json::from_str(r#"{"cmd":"service_detail","id":5,"unit":"sshd.service"}"#)
+                .unwrap();
+        assert_eq!(cmd.name(), "service_detail");
+        let line = r#"{"type":"service_detail_ok","id":5,"detail":{"unit":"sshd.service","active_state":"active","main_pid":812,"exec_start":["/usr/sbin/sshd -D"]}}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::ServiceDetailOk { detail, .. } => {
+                assert_eq!(detail.main_pid, Some(812));
+                assert_eq!(detail.memory_bytes, None);
+                assert_eq!(detail.unit_file, None);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn process_detail_permission_gaps() {
+        // The agent could read the command line but not the environment or fds.
+        let line = r#"{"type":"process_detail_ok","id":3,"detail":{"pid":42,"comm":"nginx","cmdline":["nginx","-g","daemon off;"],"environ":null,"sockets":[{"fd":6,"protocol":"tcp","local":"0.0.0.0:80","state":"LISTEN"}]}}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::ProcessDetailOk { detail, .. } => {
+                assert_eq!(detail.cmdline.len(), 3);
+                assert!(detail.environ.is_none());
+                assert!(detail.open_files.is_none());
+                assert_eq!(detail.sockets[0].remote, None);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        let cmd: Command =
+            serde_json::from_str(r#"{"cmd":"signal_process","id":4,"pid":42,"signal":15}"#)
+                .unwrap();
+        assert_eq!(cmd.name(), "signal_process");
+    }
+
+    #[test]
+    fn metrics_sample_defaults() {
+        let cmd: Command = serde_json::from_str(r#"{"cmd":"metrics_sample","id":4}"#).unwrap();
+        assert!(matches!(
+            cmd,
+            Command::MetricsSample {
+                id: 4,
+                interval_ms: None
+            }
+        ));
+        let line = r#"{"type":"metrics_sample_ok","id":4,"sample":{"interval_ms":250,"cpu_percent":[12.5,0.0],"disks":[{"name":"sda","read_bytes":512}]}}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::MetricsSampleOk { sample, .. } => {
+                assert_eq!(sample.cpu_percent, vec![12.5, 0.0]);
+                assert_eq!(sample.disks[0].write_bytes_per_sec, 0.0);
+                assert!(sample.load_avg.is_none());
+                assert!(sample.networks.is_empty());
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn subscriptions_and_events() {
+        let cmd: Command =
+            serde_json::from_str(r#"{"cmd":"subscribe","id":6,"facet":"metrics"}"#).unwrap();
+        assert!(matches!(
+            cmd,
+            Command::Subscribe {
+                id: 6,
+                facet: Facet::Metrics,
+                interval_ms: None
+            }
+        ));
+        let cmd: Command =
+            serde_json::from_str(r#"{"cmd":"subscribe","id":7,"facet":"firewall_rules"}"#).unwrap();
+        assert!(matches!(
+            cmd,
+            Command::Subscribe {
+                facet: Facet::Unknown,
+                ..
+            }
+        ));
+        let line = r#"{"type":"event","id":6,"data":{"facet":"net_listeners","listeners":[{"protocol":"tcp","port":22}]}}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::Event {
+                id: 6,
+                data: EventData::NetListeners { listeners },
+            } => assert_eq!(listeners[0].port, 22),
+            other => panic!("unexpected {:?}", other),
+        }
+        let line = r#"{"type":"event","id":6,"data":{"facet":"firewall_rules","rules":[]}}"#;
+        assert!(matches!(
+            serde_json::from_str::<Response>(line).unwrap(),
+            Response::Event {
+                data: EventData::Unknown,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn journal_tail_defaults_and_stream_frames() {
+        let cmd: Command =
+            serde_json::from_str(r#"{"cmd":"journal_tail","id":8,"unit":"nginx.service"}"#)
+                .unwrap();
+        assert!(matches!(
+            cmd,
+            Command::JournalTail {
+                id: 8,
+                lines: None,
+                follow: false,
+                ..
+            }
+        ));
+        let line = r#"{"type":"journal_lines","id":8,"entries":[{"realtime_usec":1,"message":"started"}]}"#;
+        match serde_json::from_str::<Response>(line).unwrap() {
+            Response::JournalLines { entries, end, .. } => {
+                assert!(!end);
+                assert_eq!(entries[0].message, "started");
+                assert_eq!(entries[0].priority, None);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
 }
@@ -1,12 +1,991 @@
 use anyhow::{anyhow, Result};
-use slarti_proto::{Capability, Command, DirEntry, Response, ServiceInfo, StaticConfig, SysInfo};
+use slarti_proto::{
+    AgentLimits, AgentStats, AuthFailureCount, AuthFailures, BackupJob, BlockDevice, BtrfsDevice,
+    BtrfsFilesystem, BtrfsScrub, Capability, CgroupNode, ChecksumAlgo, Command, ContainerInfo,
+    CpuCache, CpuTopology, DirEntry, DiskIo, DmiInfo, DnsConfig, DnsLookup, DnsReport, Endpoint,
+    EventData, Facet, Fail2ban, Fail2banJail, Fan, FileChunk, Gpu, GpuVendor, GroupMembers,
+    HardwareInventory, JournalEntry, KernelModule, ListenersDelta, LocalUser, LogicalCpu,
+    LoginSession, LvmVolume, LvmVolumeGroup, MetricsSample, MountInfo, NetIo, NetListener,
+    NumaNode, OomKill, OpenFile, OutputStream, Package, PackageList, PackageManager, PackageUpdate,
+    PciDevice, PendingUpdates, Platform, Pressure, PressureAverages, PressureStall, ProcessDetail,
+    ProcessInfo, ProcessSocket, ProcessesSummary, RaidArray, RaidMember, RaidSync, ReachResult,
+    RebootStatus, Reply, Request, Response, Route, SensorKind, SensorReadings, ServiceDetail,
+    ServiceInfo, ServicesDelta, SocketSummary, SocketUser, SshdConfig, SshdSetting, StaticConfig,
+    StorageStacks, SudoRule, SysInfo, SysctlValue, Temperature, TimeSync, UserInventory, ZfsDevice,
+    ZfsPool, AGENT_READ_ONLY_MARKER,
+};
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::process::Stdio;
 use tokio::fs;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::process::Command as TokioCommand;
+use tokio::sync::mpsc::Sender;
+use tokio::task::JoinHandle;
 
 const AGENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Printed for `--help`.
+const USAGE: &str = "\
+usage: slarti-remote --stdio [options]
+
+Speaks the slarti protocol, one JSON line per message, on stdin and stdout.
+
+  --read-only             refuse every mutating command
+  --max-memory SIZE       cap memory (256M, 1G, ...) through RLIMIT_DATA and,
+                          when the agent has a cgroup to itself, memory.max.
+                          Tools the agent runs (systemctl, journalctl, apt, ...)
+                          inherit RLIMIT_DATA, so a small cap can break them
+  --max-cpu PERCENT       cap CPU to a percent of one CPU through the agent's
+                          cgroup, or run at nice 10 without one
+  --nice N                run at nice N (-20..19)
+  --command-timeout SECS  abort a command after SECS seconds (default 300,
+                          0 turns it off). The tools the command started are
+                          killed, but a blocking system call it made (e.g.
+                          statvfs on a hung NFS mount) keeps its thread until
+                          the kernel returns
+  -V, --version           print the agent version
+";
+/// Bytes returned when `ReadFile` does not say.
+const DEFAULT_READ_BYTES: u64 = 64 * 1024;
+const MAX_READ_BYTES: u64 = 1024 * 1024;
+/// Largest file `WriteFileBegin` accepts (it is meant for configs, not bulk transfer).
+const MAX_WRITE_BYTES: u64 = 16 * 1024 * 1024;
+/// Writes a session may have open at once.
+const MAX_UPLOADS: usize = 8;
+/// Frames queued for the writer before streams wait for the client to read.
+const OUT_QUEUE: usize = 64;
+
+/// Journal entries (and `TailFile` lines) returned when the command does not say.
+const DEFAULT_JOURNAL_LINES: usize = 100;
+const MAX_JOURNAL_LINES: usize = 10_000;
+/// Backup tools recognized in service names (borgmatic before the borg it runs).
+const BACKUP_TOOLS: [&str; 4] = ["borgmatic", "borg", "restic", "rsnapshot"];
+/// Journal message ids systemd logs when a unit finished successfully, or failed.
+const UNIT_SUCCESS_ID: &str = "7ad2d189f7e94e70a38c781354912448";
+const UNIT_FAILURE_ID: &str = "d9b373ed55a64feb8242e02dbe79a49c";
+const RSNAPSHOT_LOG: &str = "/var/log/rsnapshot.log";
+/// Where kernel parameters live, and how many one `Sysctl` answer holds
+/// (`sysctl -a` lists a few thousand).
+const SYSCTL_ROOT: &str = "/proc/sys";
+const MAX_SYSCTL_VALUES: usize = 10_000;
+/// Groups whose members may administer the host (Debian, Red Hat, older
+/// Ubuntu), and the main sudoers file.
+const ADMIN_GROUPS: [&str; 3] = ["sudo", "wheel", "admin"];
+const SUDOERS: &str = "/etc/sudoers";
+/// Flag file an update leaves when it needs a reboot (Debian, Ubuntu), and
+/// the packages that asked for it.
+const REBOOT_REQUIRED: &str = "/var/run/reboot-required";
+const REBOOT_REQUIRED_PKGS: &str = "/var/run/reboot-required.pkgs";
+/// Where installed kernels keep their modules, one directory per version.
+const MODULES_DIRS: [&str; 2] = ["/lib/modules", "/usr/lib/modules"];
+/// Where sshd may be installed (often outside a non-root PATH), and its
+/// main config file.
+const SSHD_BINARIES: [&str; 3] = ["/usr/sbin/sshd", "/usr/bin/sshd", "/usr/local/sbin/sshd"];
+const SSHD_CONFIG: &str = "/etc/ssh/sshd_config";
+/// Window `AuthFailures` covers when the client does not say, and its bound.
+const DEFAULT_AUTH_WINDOW_SECS: u64 = 86_400;
+const MAX_AUTH_WINDOW_SECS: u64 = 30 * 86_400;
+/// Syslog files with auth messages (Debian, Red Hat), read when the journal
+/// cannot be; only their last `AUTH_LOG_LINES` lines.
+const AUTH_LOGS: [&str; 2] = ["/var/log/auth.log", "/var/log/secure"];
+const AUTH_LOG_LINES: usize = 200_000;
+/// Addresses and users an `AuthFailures` answer lists at most.
+const AUTH_TOP: usize = 50;
+/// Processes a `Sockets` answer lists.
+const SOCKETS_TOP: usize = 10;
+/// Packages returned when `Packages` does not say, and at most.
+const DEFAULT_PACKAGES: usize = 500;
+const MAX_PACKAGES: usize = 5_000;
+/// How often a followed file is checked for growth.
+const TAIL_POLL: std::time::Duration = std::time::Duration::from_millis(250);
+/// Most bytes of a followed file read per check, so a file growing faster
+/// than the session can carry falls behind instead of flooding it.
+const TAIL_CHUNK: u64 = 256 * 1024;
+/// Longest line a followed file holds back waiting for its line break; a
+/// longer one is sent in pieces.
+const TAIL_MAX_LINE: usize = 64 * 1024;
+/// Most bytes `TailFile` reads back from the end for its first lines, so a
+/// large file with few line breaks is not read whole.
+const TAIL_MAX_BYTES: u64 = 4 * 1024 * 1024;
+/// Processes per list returned when `ProcessesSummary` does not say.
+const DEFAULT_PROCESSES: usize = 10;
+const MAX_PROCESSES: usize = 100;
+/// How long an `Exec` process may run when the command does not say, and at most.
+const DEFAULT_EXEC_MS: u64 = 60_000;
+const MAX_EXEC_MS: u64 = 3_600_000;
+/// Bytes read from an `Exec` pipe at once (the most one `ExecOutput` carries).
+const EXEC_CHUNK: usize = 16 * 1024;
+/// Time between the two /proc samples CPU use is measured over.
+const PROCESS_SAMPLE: std::time::Duration = std::time::Duration::from_millis(250);
+/// Interval `MetricsSample` measures over when the client does not say.
+const DEFAULT_METRICS_MS: u64 = 250;
+const MAX_METRICS_MS: u64 = 5_000;
+/// How long one mount may take to answer statvfs (hung network mounts never do).
+const STATVFS_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+/// Time `Reachability` gives each endpoint when the client does not say, its
+/// bound, and the endpoints one request may name.
+const DEFAULT_REACH_MS: u64 = 3_000;
+const MAX_REACH_MS: u64 = 30_000;
+const MAX_REACH_TARGETS: usize = 64;
+/// Name `DnsCheck` looks up when the client does not say, and how long the
+/// lookup may take (the resolver's own retries can run far longer).
+const DEFAULT_DNS_NAME: &str = "example.com";
+const DNS_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+/// Cloud instance metadata endpoint (link-local, plain HTTP), and how long it
+/// may take to connect or answer.
+const METADATA_ADDR: &str = "169.254.169.254:80";
+const METADATA_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(500);
+/// Where the cgroup v2 hierarchy may be mounted: alone, or beside v1
+/// controllers on hybrid systems (which then report CPU but not memory).
+const CGROUP_ROOTS: [&str; 2] = ["/sys/fs/cgroup", "/sys/fs/cgroup/unified"];
+/// Event interval of a `Subscribe` that does not say, and its bounds.
+const DEFAULT_SUBSCRIBE_MS: u64 = 2_000;
+const MIN_SUBSCRIBE_MS: u64 = 250;
+const MAX_SUBSCRIBE_MS: u64 = 3_600_000;
+/// /proc/diskstats counts 512-byte sectors whatever the device's block size.
+const SECTOR_BYTES: u64 = 512;
+/// How long one command may run before the watchdog aborts it, unless
+/// `--command-timeout` says otherwise (0 turns the watchdog off).
+const DEFAULT_COMMAND_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(300);
+/// Nice value taken when a CPU limit cannot be enforced by a cgroup.
+const CPU_LIMIT_FALLBACK_NICE: i32 = 10;
+/// Period of the cgroup `cpu.max` quota the CPU limit is written as.
+const CPU_PERIOD_USEC: u64 = 100_000;
+
+/// State kept for the lifetime of one client session.
+struct Session {
+    /// Last services list sent via `ServicesDelta`, by name, and its token
+    services: Snapshot<String, ServiceInfo>,
+    /// Last listeners sent via `ListenersDelta`, by protocol, address and port, and its token
+    listeners: Snapshot<(String, String, u16), NetListener>,
+    next_token: u64,
+    /// Output lines; streams write their frames here between replies
+    out: Sender<String>,
+    /// Trace id of the request being handled (echoed by streams it starts)
+    trace: Option<String>,
+    /// Running `JournalTail` follow streams, by request id
+    journals: HashMap<u64, JoinHandle<()>>,
+    /// Running `TailFile` follow streams, by request id
+    tails: HashMap<u64, JoinHandle<()>>,
+    /// Running `Exec` processes, by request id
+    execs: HashMap<u64, JoinHandle<()>>,
+    /// Running `Subscribe` event loops, by request id
+    subscriptions: HashMap<u64, JoinHandle<()>>,
+    /// Writes started by `WriteFileBegin` and not ended yet, by request id
+    uploads: HashMap<u64, Upload>,
+    /// Self-limits in effect, reported by `AgentStats`
+    limits: AgentLimits,
+    /// Started with `--read-only` or under a read-only marker: mutating
+    /// commands are refused
+    read_only: bool,
+    started: std::time::Instant,
+    /// Commands handled, and of those, aborted by the watchdog
+    commands: u64,
+    watchdog_aborts: u64,
+}
+
+impl Session {
+    fn new(out: Sender<String>, limits: AgentLimits, read_only: bool) -> Self {
+        // Tokens from an earlier agent process must not match this one's.
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        Session {
+            services: None,
+            listeners: None,
+            next_token: seed,
+            out,
+            trace: None,
+            journals: HashMap::new(),
+            tails: HashMap::new(),
+            execs: HashMap::new(),
+            subscriptions: HashMap::new(),
+            uploads: HashMap::new(),
+            limits,
+            read_only,
+            started: std::time::Instant::now(),
+            commands: 0,
+            watchdog_aborts: 0,
+        }
+    }
+
+    /// Stream journal entries after `cursor` (new ones only when None) as frames for `id`.
+    fn follow_journal(&mut self, id: u64, unit: Option<String>, cursor: Option<String>) {
+        self.journals.retain(|_, stream| !stream.is_finished());
+        if let Some(previous) = self.journals.remove(&id) {
+            previous.abort();
+        }
+        let out = self.out.clone();
+        let trace = self.trace.clone();
+        let stream = tokio::spawn(async move {
+            let frame = |entries, end| {
+                let reply = Reply {
+                    response: Response::JournalLines { id, entries, end },
+                    trace: trace.clone(),
+                };
+                serde_json::to_string(&reply).unwrap_or_default()
+            };
+            let mut cmd = journalctl(unit.as_deref());
+            match cursor {
+                Some(cursor) => cmd.arg(format!("--after-cursor={}", cursor)),
+                None => cmd.arg("--lines=0"),
+            };
+            let child = cmd
+                .arg("--follow")
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .kill_on_drop(true)
+                .spawn();
+            if let Ok(mut child) = child {
+                if let Some(stdout) = child.stdout.take() {
+                    let mut reader = BufReader::new(stdout);
+                    let mut entries = Vec::new();
+                    let mut line = String::new();
+                    loop {
+                        line.clear();
+                        match reader.read_line(&mut line).await {
+                            Ok(0) | Err(_) => break,
+                            Ok(_) => {}
+                        }
+                        entries.extend(parse_journal_line(&line).map(|(entry, _)| entry));
+                        // Send what arrived together as one frame.
+                        if reader.buffer().is_empty()
+                            && !entries.is_empty()
+                            && out
+                                .send(frame(std::mem::take(&mut entries), false))
+                                .await
+                                .is_err()
+                        {
+                            return;
+                        }
+                    }
+                }
+            }
+            let _ = out.send(frame(Vec::new(), true)).await;
+        });
+        self.journals.insert(id, stream);
+    }
+
+    /// End the follow stream for `id`; false if there is none.
+    fn stop_journal(&mut self, id: u64) -> bool {
+        match self.journals.remove(&id) {
+            Some(stream) => {
+                stream.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stream lines appended to `path` after `offset` as frames for `id`.
+    /// `inode` identifies the file read so far, to notice rotation.
+    fn follow_file(&mut self, id: u64, path: String, offset: u64, inode: u64) {
+        self.tails.retain(|_, stream| !stream.is_finished());
+        if let Some(previous) = self.tails.remove(&id) {
+            previous.abort();
+        }
+        let out = self.out.clone();
+        let trace = self.trace.clone();
+        let stream = tokio::spawn(async move {
+            use std::os::unix::fs::MetadataExt;
+            use tokio::io::{AsyncReadExt, AsyncSeekExt};
+            use tokio::sync::mpsc::error::TrySendError;
+            let frame = |lines| {
+                let reply = Reply {
+                    response: Response::FileLines {
+                        id,
+                        lines,
+                        end: false,
+                    },
+                    trace: trace.clone(),
+                };
+                serde_json::to_string(&reply).unwrap_or_default()
+            };
+            let (mut offset, mut inode) = (offset, inode);
+            // Bytes after the last line break, completed by a later read.
+            let mut partial: Vec<u8> = Vec::new();
+            let mut ticks = tokio::time::interval(TAIL_POLL);
+            ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticks.tick().await;
+                // Client behind: read nothing until the queue has room; the
+                // file keeps the lines meanwhile.
+                let permit = match out.try_reserve() {
+                    Ok(permit) => permit,
+                    Err(TrySendError::Full(())) => continue,
+                    Err(TrySendError::Closed(())) => return,
+                };
+                // Missing for a moment while being rotated.
+                let Ok(meta) = fs::metadata(&path).await else {
+                    continue;
+                };
+                if meta.ino() != inode || meta.len() < offset {
+                    inode = meta.ino();
+                    offset = 0;
+                    partial.clear();
+                }
+                if meta.len() == offset {
+                    continue;
+                }
+                let Ok(mut file) = fs::File::open(&path).await else {
+                    continue;
+                };
+                let mut bytes = Vec::new();
+                let read = async {
+                    file.seek(std::io::SeekFrom::Start(offset)).await?;
+                    (&mut file).take(TAIL_CHUNK).read_to_end(&mut bytes).await
+                };
+                if read.await.is_err() {
+                    continue;
+                }
+                offset += bytes.len() as u64;
+                partial.extend_from_slice(&bytes);
+                let cut = match partial.iter().rposition(|&b| b == b'\n') {
+                    Some(last_break) => last_break + 1,
+                    None if partial.len() >= TAIL_MAX_LINE => partial.len(),
+                    None => continue,
+                };
+                let rest = partial.split_off(cut);
+                let lines = String::from_utf8_lossy(&partial)
+                    .lines()
+                    .map(str::to_string)
+                    .collect();
+                partial = rest;
+                permit.send(frame(lines));
+            }
+        });
+        self.tails.insert(id, stream);
+    }
+
+    /// End the follow stream for `id`; false if there is none.
+    fn stop_tail(&mut self, id: u64) -> bool {
+        match self.tails.remove(&id) {
+            Some(stream) => {
+                stream.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stream the output of `child` as frames for `id`, then its exit status;
+    /// kill it once `timeout` has passed.
+    fn exec(&mut self, id: u64, mut child: tokio::process::Child, timeout: std::time::Duration) {
+        self.execs.retain(|_, process| !process.is_finished());
+        if let Some(previous) = self.execs.remove(&id) {
+            previous.abort();
+        }
+        let out = self.out.clone();
+        let trace = self.trace.clone();
+        let process = tokio::spawn(async move {
+            use std::os::unix::process::ExitStatusExt;
+            let frame = |response| {
+                let reply = Reply {
+                    response,
+                    trace: trace.clone(),
+                };
+                serde_json::to_string(&reply).unwrap_or_default()
+            };
+            let output = |stream, data| Response::ExecOutput { id, stream, data };
+            let (mut stdout, mut stderr) = (child.stdout.take(), child.stderr.take());
+            // Incomplete UTF-8 sequences ending the last read of each pipe.
+            let (mut stdout_rest, mut stderr_rest) = (Vec::new(), Vec::new());
+            let (mut stdout_buf, mut stderr_buf) = (vec![0; EXEC_CHUNK], vec![0; EXEC_CHUNK]);
+            let deadline = tokio::time::sleep(timeout);
+            tokio::pin!(deadline);
+            let mut timed_out = false;
+            while stdout.is_some() || stderr.is_some() {
+                let (stream, data) = tokio::select! {
+                    n = read_pipe(&mut stdout, &mut stdout_buf) => match n {
+                        Some(n) => (OutputStream::Stdout, utf8_chunk(&mut stdout_rest, &stdout_buf[..n])),
+                        None => {
+                            stdout = None;
+                            (OutputStream::Stdout, utf8_chunk(&mut stdout_rest, &[]))
+                        }
+                    },
+                    n = read_pipe(&mut stderr, &mut stderr_buf) => match n {
+                        Some(n) => (OutputStream::Stderr, utf8_chunk(&mut stderr_rest, &stderr_buf[..n])),
+                        None => {
+                            stderr = None;
+                            (OutputStream::Stderr, utf8_chunk(&mut stderr_rest, &[]))
+                        }
+                    },
+                    _ = &mut deadline => {
+                        timed_out = true;
+                        break;
+                    }
+                };
+                if !data.is_empty() && out.send(frame(output(stream, data))).await.is_err() {
+                    // Client gone: dropping the child kills it.
+                    return;
+                }
+            }
+            // The pipes can close before the process exits (daemons do).
+            let exited = if timed_out {
+                None
+            } else {
+                tokio::select! {
+                    status = child.wait() => Some(status),
+                    _ = &mut deadline => None,
+                }
+            };
+            let status = match exited {
+                Some(status) => status,
+                None => {
+                    timed_out = true;
+                    let _ = child.start_kill();
+                    child.wait().await
+                }
+            };
+            let (code, signal) = match status {
+                Ok(status) => (status.code(), status.signal()),
+                Err(_) => (None, None),
+            };
+            let _ = out
+                .send(frame(Response::ExecExit {
+                    id,
+                    code,
+                    signal,
+                    timed_out,
+                }))
+                .await;
+        });
+        self.execs.insert(id, process);
+    }
+
+    /// Kill the `Exec` process for `id`; false if there is none running.
+    fn kill_exec(&mut self, id: u64) -> bool {
+        match self.execs.remove(&id) {
+            // Dropping the child kills it.
+            Some(process) if !process.is_finished() => {
+                process.abort();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Push `facet` as `Event` frames for `id` every `interval` until unsubscribed.
+    fn subscribe(&mut self, id: u64, facet: Facet, interval: std::time::Duration) {
+        self.subscriptions.retain(|_, events| !events.is_finished());
+        if let Some(previous) = self.subscriptions.remove(&id) {
+            previous.abort();
+        }
+        let out = self.out.clone();
+        let trace = self.trace.clone();
+        let events = tokio::spawn(async move {
+            let frame = |response| {
+                serde_json::to_string(&Reply {
+                    response,
+                    trace: trace.clone(),
+                })
+                .unwrap_or_default()
+            };
+            // Metrics rates cover the whole interval, from one tick's counters to the next.
+            let mut counters = match facet {
+                Facet::Metrics => read_counters().await.ok(),
+                _ => None,
+            };
+            // Arrays degraded at the last check, so each degradation alerts once.
+            let mut degraded: Vec<String> = Vec::new();
+            let mut ticks = tokio::time::interval(interval);
+            ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            // The first tick is immediate.
+            ticks.tick().await;
+            loop {
+                ticks.tick().await;
+                let data = match facet {
+                    Facet::Metrics => match (counters.take(), read_counters().await) {
+                        (Some(before), Ok(after)) => {
+                            let sample =
+                                metrics_between(&before, &after, interval.as_millis() as u64).await;
+                            counters = Some(after);
+                            Ok(EventData::Metrics { sample })
+                        }
+                        (None, Ok(after)) => {
+                            // No baseline yet (first read failed); report from the next tick.
+                            counters = Some(after);
+                            continue;
+                        }
+                        (_, Err(e)) => Err(e),
+                    },
+                    Facet::Processes => processes_summary(DEFAULT_PROCESSES)
+                        .await
+                        .map(|summary| EventData::Processes { summary }),
+                    Facet::NetListeners => net_listeners()
+                        .await
+                        .map(|listeners| EventData::NetListeners { listeners }),
+                    // /proc/mdstat alone: cheap enough to poll, and it shows degradation.
+                    Facet::RaidDegraded => {
+                        let arrays: Vec<RaidArray> = mdstat_arrays()
+                            .await
+                            .into_iter()
+                            .filter(|a| a.degraded)
+                            .collect();
+                        let was_degraded = std::mem::replace(
+                            &mut degraded,
+                            arrays.iter().map(|a| a.name.clone()).collect(),
+                        );
+                        let arrays: Vec<RaidArray> = arrays
+                            .into_iter()
+                            .filter(|a| !was_degraded.contains(&a.name))
+                            .collect();
+                        if arrays.is_empty() {
+                            continue;
+                        }
+                        Ok(EventData::RaidDegraded { arrays })
+                    }
+                    Facet::Unknown => return,
+                };
+                let response = match data {
+                    Ok(data) => Response::Event { id, data },
+                    Err(e) => Response::Error {
+                        id,
+                        message: e.to_string(),
+                    },
+                };
+                if out.send(frame(response)).await.is_err() {
+                    return;
+                }
+            }
+        });
+        self.subscriptions.insert(id, events);
+    }
+
+    /// End the subscription `id`; false if there is none.
+    fn unsubscribe(&mut self, id: u64) -> bool {
+        match self.subscriptions.remove(&id) {
+            Some(events) => {
+                events.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Diff `current` against the snapshot behind `since` and remember it as the new snapshot.
+    fn services_delta(&mut self, since: Option<u64>, current: Vec<ServiceInfo>) -> ServicesDelta {
+        self.next_token = self.next_token.wrapping_add(1);
+        let token = self.next_token;
+        let delta = diff_snapshot(&mut self.services, token, since, current, |s| {
+            s.name.clone()
+        });
+        ServicesDelta {
+            token,
+            full: delta.full,
+            changed: delta.changed,
+            removed: delta.removed,
+        }
+    }
+
+    /// `services_delta` for listening sockets.
+    fn listeners_delta(&mut self, since: Option<u64>, current: Vec<NetListener>) -> ListenersDelta {
+        self.next_token = self.next_token.wrapping_add(1);
+        let token = self.next_token;
+        let delta = diff_snapshot(&mut self.listeners, token, since, current, |l| {
+            (l.protocol.clone(), l.address.clone(), l.port)
+        });
+        ListenersDelta {
+            token,
+            full: delta.full,
+            changed: delta.changed,
+            removed: delta
+                .removed
+                .into_iter()
+                .map(|(protocol, address, port)| NetListener {
+                    protocol,
+                    address,
+                    port,
+                    ..Default::default()
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The last list a delta command sent, by key, and the token it went out with.
+type Snapshot<K, T> = Option<(u64, HashMap<K, T>)>;
+
+/// What changed in a list since a snapshot.
+struct Diff<K, T> {
+    /// `changed` is the whole list: `since` did not name the snapshot
+    full: bool,
+    changed: Vec<T>,
+    removed: Vec<K>,
+}
+
+/// Diff `current` against `snapshot` if it is the one sent with token
+/// `since`, then keep `current` as the snapshot for `token`.
+fn diff_snapshot<K: Eq + std::hash::Hash, T: Clone + PartialEq>(
+    snapshot: &mut Snapshot<K, T>,
+    token: u64,
+    since: Option<u64>,
+    current: Vec<T>,
+    key: impl Fn(&T) -> K,
+) -> Diff<K, T> {
+    let previous = match snapshot.take() {
+        Some((t, prev)) if Some(t) == since => Some(prev),
+        _ => None,
+    };
+    let next: HashMap<K, T> = current
+        .iter()
+        .map(|item| (key(item), item.clone()))
+        .collect();
+    let diff = match previous {
+        Some(prev) => Diff {
+            full: false,
+            changed: current
+                .into_iter()
+                .filter(|item| prev.get(&key(item)) != Some(item))
+                .collect(),
+            removed: prev.into_keys().filter(|k| !next.contains_key(k)).collect(),
+        },
+        None => Diff {
+            full: true,
+            changed: current,
+            removed: Vec::new(),
+        },
+    };
+    *snapshot = Some((token, next));
+    diff
+}
+
+/// A file being written by `WriteFileChunk`s: the bytes go to a temporary
+/// file next to the target, renamed over it when the write ends.
+struct Upload {
+    /// Path as requested
+    path: PathBuf,
+    /// File actually replaced: `path` with symlinks resolved, so a link
+    /// keeps pointing at the new contents instead of being replaced
+    target: PathBuf,
+    temp: PathBuf,
+    file: fs::File,
+    written: u64,
+}
+
+impl Upload {
+    async fn begin(id: u64, path: String, mode: Option<u32>) -> Result<Upload> {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+        let path = PathBuf::from(path);
+        let target = match fs::canonicalize(&path).await {
+            Ok(target) => target,
+            // A new file: resolve its directory.
+            Err(_) => match (path.parent(), path.file_name()) {
+                (Some(dir), Some(name)) if !dir.as_os_str().is_empty() => fs::canonicalize(dir)
+                    .await
+                    .map(|dir| dir.join(name))
+                    .unwrap_or_else(|_| path.clone()),
+                _ => path.clone(),
+            },
+        };
+        let name = target
+            .file_name()
+            .ok_or_else(|| anyhow!("{}: not a file path", path.display()))?;
+        let existing = fs::metadata(&target).await.ok();
+        if existing.as_ref().is_some_and(|m| m.is_dir()) {
+            return Err(anyhow!("{}: is a directory", path.display()));
+        }
+        let temp = target.with_file_name(format!(
+            ".{}.slarti-{}-{}",
+            name.to_string_lossy(),
+            std::process::id(),
+            id
+        ));
+        let mode = match mode.or(existing.as_ref().map(|m| m.mode() & 0o7777)) {
+            Some(mode) => mode,
+            None => 0o666 & !umask().await,
+        };
+        // Private until the final mode is set, whatever the umask.
+        let file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&temp)
+            .await
+            .map_err(|e| anyhow!("{}: {}", temp.display(), e))?;
+        let upload = Upload {
+            path,
+            target,
+            temp,
+            file,
+            written: 0,
+        };
+        fs::set_permissions(&upload.temp, std::fs::Permissions::from_mode(mode)).await?;
+        if let Some(m) = &existing {
+            // Keep the owner of the replaced file (only possible as root).
+            let _ = std::os::unix::fs::chown(&upload.temp, Some(m.uid()), Some(m.gid()));
+        }
+        Ok(upload)
+    }
+
+    /// Append base64 `data`; the bytes written so far.
+    async fn append(&mut self, data: &str) -> Result<u64> {
+        let bytes = slarti_proto::base64::decode(data)?;
+        if self.written + bytes.len() as u64 > MAX_WRITE_BYTES {
+            return Err(anyhow!(
+                "{}: larger than {} bytes",
+                self.path.display(),
+                MAX_WRITE_BYTES
+            ));
+        }
+        self.file.write_all(&bytes).await?;
+        self.written += bytes.len() as u64;
+        Ok(self.written)
+    }
+
+    /// Replace the target with what was written; its size.
+    async fn finish(&mut self) -> Result<u64> {
+        self.file.sync_all().await?;
+        fs::rename(&self.temp, &self.target)
+            .await
+            .map_err(|e| anyhow!("{}: {}", self.path.display(), e))?;
+        Ok(self.written)
+    }
+}
+
+/// The agent's umask, from /proc/self/status (022 where it cannot be read).
+async fn umask() -> u32 {
+    fs::read_to_string("/proc/self/status")
+        .await
+        .ok()
+        .and_then(|status| {
+            status
+                .lines()
+                .find_map(|l| l.strip_prefix("Umask:"))
+                .and_then(|v| u32::from_str_radix(v.trim(), 8).ok())
+        })
+        .unwrap_or(0o022)
+}
+
+impl Drop for Upload {
+    fn drop(&mut self) {
+        // Gone already when the write was saved.
+        let _ = std::fs::remove_file(&self.temp);
+    }
+}
+
+/// Self-limits asked for on the command line:
+/// `--max-memory 256M`, `--max-cpu 25` (percent of one CPU), `--nice 10`
+/// and `--command-timeout 120` (seconds; 0 turns the watchdog off). See
+/// `USAGE` for what each limit does not cover.
+#[derive(Debug, Default)]
+struct LimitArgs {
+    memory_bytes: Option<u64>,
+    cpu_percent: Option<u32>,
+    nice: Option<i32>,
+    command_timeout: Option<std::time::Duration>,
+    /// Flags that could not be parsed
+    errors: Vec<String>,
+}
+
+impl LimitArgs {
+    fn parse(args: impl IntoIterator<Item = String>) -> Self {
+        let mut parsed = LimitArgs {
+            command_timeout: Some(DEFAULT_COMMAND_TIMEOUT),
+            ..Default::default()
+        };
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+            if !["--max-memory", "--max-cpu", "--nice", "--command-timeout"]
+                .contains(&flag.as_str())
+            {
+                continue;
+            }
+            let Some(value) = inline.or_else(|| args.next()) else {
+                parsed.errors.push(format!("{} needs a value", flag));
+                continue;
+            };
+            let ok = match flag.as_str() {
+                "--max-memory" => parse_size(&value).map(|b| parsed.memory_bytes = Some(b)),
+                "--max-cpu" => value
+                    .trim_end_matches('%')
+                    .parse()
+                    .ok()
+                    .filter(|p| *p > 0)
+                    .map(|p| parsed.cpu_percent = Some(p)),
+                "--nice" => value
+                    .parse()
+                    .ok()
+                    .filter(|n| (-20..=19).contains(n))
+                    .map(|n| parsed.nice = Some(n)),
+                _ => value.parse::<u64>().ok().map(|secs| {
+                    parsed.command_timeout =
+                        (secs > 0).then(|| std::time::Duration::from_secs(secs))
+                }),
+            };
+            if ok.is_none() {
+                parsed
+                    .errors
+                    .push(format!("ignored {} {:?}: invalid value", flag, value));
+            }
+        }
+        parsed
+    }
+}
+
+/// "256M", "1G", "512K" (powers of 1024) or plain bytes.
+fn parse_size(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let (number, shift) = match value.char_indices().last()? {
+        (i, 'K' | 'k') => (&value[..i], 10),
+        (i, 'M' | 'm') => (&value[..i], 20),
+        (i, 'G' | 'g') => (&value[..i], 30),
+        _ => (value, 0),
+    };
+    number
+        .parse::<u64>()
+        .ok()
+        .filter(|n| *n > 0)
+        .and_then(|n| n.checked_mul(1 << shift))
+}
+
+/// The agent's own cgroup v2 directory, if no other process shares it (a
+/// limit written there then applies to the agent and the tools it runs only).
+fn own_cgroup() -> Option<PathBuf> {
+    let line = std::fs::read_to_string("/proc/self/cgroup").ok()?;
+    let path = line.lines().find_map(|l| l.strip_prefix("0::"))?;
+    let dir = CGROUP_ROOTS
+        .iter()
+        .map(|root| PathBuf::from(root).join(path.trim_start_matches('/')))
+        .find(|dir| dir.join("cgroup.procs").exists())?;
+    let procs = std::fs::read_to_string(dir.join("cgroup.procs")).ok()?;
+    let me = std::process::id().to_string();
+    procs.lines().all(|pid| pid == me).then_some(dir)
+}
+
+fn set_nice(nice: i32) -> std::io::Result<()> {
+    // SAFETY: setpriority has no memory arguments.
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Apply the self-limits of `args`: memory through RLIMIT_DATA, nice through
+/// setpriority, and memory and CPU through the agent's cgroup when it has
+/// one of its own. What could not be applied is noted.
+fn apply_limits(args: LimitArgs) -> AgentLimits {
+    let mut limits = AgentLimits {
+        command_timeout_ms: args.command_timeout.map(|t| t.as_millis() as u64),
+        notes: args.errors,
+        ..Default::default()
+    };
+    if let Some(bytes) = args.memory_bytes {
+        let rlim = libc::rlimit {
+            rlim_cur: bytes as libc::rlim_t,
+            rlim_max: bytes as libc::rlim_t,
+        };
+        // SAFETY: rlim is a valid rlimit for the duration of the call.
+        if unsafe { libc::setrlimit(libc::RLIMIT_DATA, &rlim) } == 0 {
+            limits.memory_bytes = Some(bytes);
+        } else {
+            limits.notes.push(format!(
+                "memory limit not set: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+    }
+    if let Some(nice) = args.nice {
+        match set_nice(nice) {
+            Ok(()) => limits.nice = Some(nice),
+            Err(e) => limits.notes.push(format!("nice {} not set: {}", nice, e)),
+        }
+    }
+    let cgroup = (args.memory_bytes.is_some() || args.cpu_percent.is_some())
+        .then(own_cgroup)
+        .flatten();
+    if let Some(dir) = &cgroup {
+        let mut written = true;
+        if let Some(bytes) = args.memory_bytes {
+            written &= std::fs::write(dir.join("memory.max"), bytes.to_string()).is_ok();
+        }
+        if let Some(percent) = args.cpu_percent {
+            let quota = CPU_PERIOD_USEC * u64::from(percent) / 100;
+            let ok = std::fs::write(
+                dir.join("cpu.max"),
+                format!("{} {}", quota, CPU_PERIOD_USEC),
+            )
+            .is_ok();
+            if ok {
+                limits.cpu_percent = Some(percent);
+            }
+            written &= ok;
+        }
+        limits.cgroup = written;
+        if !written {
+            limits
+                .notes
+                .push(format!("cgroup limits not writable in {}", dir.display()));
+        }
+    }
+    if let Some(percent) = args.cpu_percent.filter(|_| limits.cpu_percent.is_none()) {
+        // Without a cgroup the agent can only yield to everything else.
+        let nice = limits.nice.unwrap_or(CPU_LIMIT_FALLBACK_NICE);
+        if limits.nice.is_none() && set_nice(nice).is_ok() {
+            limits.nice = Some(nice);
+        }
+        limits.notes.push(format!(
+            "cpu limit of {}% needs a cgroup of its own; running at nice {} instead",
+            percent, nice
+        ));
+    }
+    limits
+}
+
+/// Resource use of this process from getrusage(2) and /proc/self.
+async fn agent_stats(session: &Session) -> AgentStats {
+    fn rusage(who: libc::c_int) -> libc::rusage {
+        // SAFETY: getrusage fills the zeroed struct it is handed.
+        let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+        unsafe { libc::getrusage(who, &mut usage) };
+        usage
+    }
+    fn ms(tv: libc::timeval) -> u64 {
+        tv.tv_sec as u64 * 1_000 + tv.tv_usec as u64 / 1_000
+    }
+    let own = rusage(libc::RUSAGE_SELF);
+    let children = rusage(libc::RUSAGE_CHILDREN);
+    let status = fs::read_to_string("/proc/self/status")
+        .await
+        .unwrap_or_default();
+    let status_field = |key: &str| {
+        status
+            .lines()
+            .find_map(|l| l.strip_prefix(key))
+            .and_then(|v| v.split_whitespace().next())
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(0)
+    };
+    AgentStats {
+        pid: std::process::id(),
+        uptime_secs: session.started.elapsed().as_secs(),
+        rss_bytes: status_field("VmRSS:") * 1024,
+        peak_rss_bytes: status_field("VmHWM:") * 1024,
+        cpu_user_ms: ms(own.ru_utime),
+        cpu_system_ms: ms(own.ru_stime),
+        children_cpu_ms: ms(children.ru_utime) + ms(children.ru_stime),
+        threads: status_field("Threads:") as u32,
+        open_fds: std::fs::read_dir("/proc/self/fd")
+            .map(|dir| dir.count() as u32)
+            .unwrap_or(0),
+        commands: session.commands,
+        watchdog_aborts: session.watchdog_aborts,
+        limits: session.limits.clone(),
+    }
+}
+
+/// The `id` field of a request line that did not parse as a command, or 0.
+fn request_id(line: &str) -> u64 {
+    serde_json::from_str::<serde_json::Value>(line)
+        .ok()
+        .and_then(|v| v.get("id")?.as_u64())
+        .unwrap_or(0)
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -15,52 +994,210 @@ async fn main() -> Result<()> {
         println!("{}", AGENT_VERSION);
         return Ok(());
     }
+    if std::env::args().any(|a| a == "--help" || a == "-h") {
+        print!("{}", USAGE);
+        return Ok(());
+    }
+    let limits = apply_limits(LimitArgs::parse(std::env::args().skip(1)));
+    for note in &limits.notes {
+        eprintln!("slarti-remote: {}", note);
+    }
     let stdin = tokio::io::stdin();
     let stdout = tokio::io::stdout();
     let mut reader = BufReader::new(stdin).lines();
-    let mut writer = tokio::io::BufWriter::new(stdout);
+    // Replies and stream frames go through one writer task so lines never interleave.
+    // Bounded: streams wait for a slow client instead of queueing without limit.
+    let (out, mut lines) = tokio::sync::mpsc::channel::<String>(OUT_QUEUE);
+    let writer_task = tokio::spawn(async move {
+        let mut writer = tokio::io::BufWriter::new(stdout);
+        while let Some(line) = lines.recv().await {
+            write_line(&mut writer, &line).await?;
+        }
+        anyhow::Ok(())
+    });
+    // The marker makes the mode stick to the host; no flag turns it off.
+    let read_only = std::env::args().any(|a| a == "--read-only") || read_only_marker();
+    let mut session = Session::new(out.clone(), limits, read_only);
 
     while let Some(line) = reader.next_line().await? {
         if line.trim().is_empty() {
             continue;
         }
-        let resp = match serde_json::from_str::<Command>(&line) {
-            Ok(cmd) => handle_command(cmd).await,
-            Err(e) => Err(anyhow!("invalid json: {}", e)),
+        let (id, resp, trace) = match serde_json::from_str::<Request>(&line) {
+            Ok(Request { command, trace }) => {
+                let name = command.name();
+                let id = command.id();
+                let started = std::time::Instant::now();
+                session.trace = trace.clone();
+                session.commands += 1;
+                // The watchdog drops a runaway command (and kills the tools it runs).
+                // spawn_blocking work cannot be cancelled: its thread stays busy
+                // until the call returns, so blocking calls carry their own timeouts.
+                let resp = match session.limits.command_timeout_ms {
+                    Some(ms) => {
+                        let limit = std::time::Duration::from_millis(ms);
+                        match tokio::time::timeout(limit, handle_command(command, &mut session))
+                            .await
+                        {
+                            Ok(resp) => resp,
+                            Err(_) => {
+                                session.watchdog_aborts += 1;
+                                Ok(Response::Error {
+                                    id,
+                                    message: format!(
+                                        "{} aborted by the watchdog after {:?}",
+                                        name, limit
+                                    ),
+                                })
+                            }
+                        }
+                    }
+                    None => handle_command(command, &mut session).await,
+                };
+                // Traced requests get a log frame with the agent-side timing ahead of the reply.
+                if trace.is_some() {
+                    let (level, message) = match &resp {
+                        Ok(_) => ("debug", format!("{} took {:?}", name, started.elapsed())),
+                        Err(e) => (
+                            "warn",
+                            format!("{} failed after {:?}: {}", name, started.elapsed(), e),
+                        ),
+                    };
+                    let log = Reply {
+                        response: Response::AgentLog {
+                            level: level.to_string(),
+                            message,
+                        },
+                        trace: trace.clone(),
+                    };
+                    send_line(&out, serde_json::to_string(&log)?).await?;
+                }
+                (id, resp, trace)
+            }
+            // Answer with the request's id when it has one, so the client's
+            // pending call fails instead of waiting for a reply that never comes.
+            Err(e) => (request_id(&line), Err(anyhow!("invalid json: {}", e)), None),
         };
 
-        let json_line = match resp {
-            Ok(r) => serde_json::to_string(&r)?,
-            Err(e) => serde_json::to_string(&Response::Error {
-                id: 0,
-                message: e.to_string(),
-            })?,
-        };
-        writer.write_all(json_line.as_bytes()).await?;
-        writer.write_all(b"\n").await?;
-        writer.flush().await?;
+        let response = resp.unwrap_or_else(|e| Response::Error {
+            id,
+            message: e.to_string(),
+        });
+        send_line(&out, serde_json::to_string(&Reply { response, trace })?).await?;
     }
 
+    // Client gone: stop streams and flush what is queued.
+    for (_, stream) in session.journals.drain() {
+        stream.abort();
+    }
+    for (_, stream) in session.tails.drain() {
+        stream.abort();
+    }
+    for (_, process) in session.execs.drain() {
+        process.abort();
+    }
+    for (_, events) in session.subscriptions.drain() {
+        events.abort();
+    }
+    drop(session);
+    drop(out);
+    writer_task.await?
+}
+
+/// Whether a deploy marked this host read-only: the marker sits in
+/// `<root>/agent`, the parent of this binary's version dir.
+fn read_only_marker() -> bool {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| Some(exe.parent()?.parent()?.join(AGENT_READ_ONLY_MARKER)))
+        .is_some_and(|marker| marker.exists())
+}
+
+async fn send_line(out: &Sender<String>, line: String) -> Result<()> {
+    out.send(line).await.map_err(|_| anyhow!("output closed"))
+}
+
+async fn write_line<W: AsyncWriteExt + Unpin>(writer: &mut W, line: &str) -> Result<()> {
+    writer.write_all(line.as_bytes()).await?;
+    writer.write_all(b"\n").await?;
+    writer.flush().await?;
     Ok(())
 }
 
-async fn handle_command(cmd: Command) -> Result<Response> {
+async fn handle_command(cmd: Command, session: &mut Session) -> Result<Response> {
+    // Enforced here rather than trusted to the client; batches come back
+    // through this check member by member.
+    if session.read_only && cmd.is_mutating() {
+        return Ok(Response::Error {
+            id: cmd.id(),
+            message: format!("{} refused: the agent runs read-only", cmd.name()),
+        });
+    }
     match cmd {
         Command::Hello {
             id,
             client_version: _,
-        } => Ok(Response::HelloAck {
-            id,
-            agent_version: AGENT_VERSION.to_string(),
-            capabilities: vec![
+        } => {
+            let mut capabilities = vec![
                 Capability::SysInfo,
                 Capability::StaticConfig,
                 Capability::ServicesList,
+                Capability::ServiceDetail,
+                Capability::CgroupTree,
+                Capability::Pressure,
+                Capability::RaidStatus,
+                Capability::StorageStacks,
+                Capability::Backups,
+                Capability::Sessions,
+                Capability::Users,
+                Capability::SshdConfig,
+                Capability::AuthFailures,
                 Capability::ContainersList,
                 Capability::NetListeners,
+                Capability::Reachability,
+                Capability::DnsCheck,
+                Capability::DnsConfig,
+                Capability::Routes,
+                Capability::Sockets,
+                Capability::DiskUsage,
+                Capability::HardwareInventory,
+                Capability::Sensors,
+                Capability::GpuInfo,
+                Capability::AgentStats,
+                Capability::KernelModules,
+                Capability::Sysctl,
+                Capability::Packages,
+                Capability::UpdatesAvailable,
                 Capability::ProcessesSummary,
-            ],
-        }),
+                Capability::ProcessDetail,
+                Capability::ProcessControl,
+                Capability::MetricsSample,
+                Capability::Batch,
+                Capability::ServicesDelta,
+                Capability::ListenersDelta,
+                Capability::Journal,
+                Capability::Subscribe,
+                Capability::TailFile,
+                Capability::Exec,
+                Capability::ReadFile,
+                Capability::Checksum,
+                Capability::WriteFile,
+            ];
+            if session.read_only {
+                capabilities.retain(|c| {
+                    !matches!(
+                        c,
+                        Capability::ProcessControl | Capability::Exec | Capability::WriteFile
+                    )
+                });
+                capabilities.push(Capability::ReadOnly);
+            }
+            Ok(Response::HelloAck {
+                id,
+                agent_version: AGENT_VERSION.to_string(),
+                capabilities,
+            })
+        }
         Command::SysInfo { id } => {
             let info = sys_info().await?;
             Ok(Response::SysInfoOk { id, info })
@@ -73,6 +1210,188 @@ async fn handle_command(cmd: Command) -> Result<Response> {
             let services = services_list().await?;
             Ok(Response::ServicesListOk { id, services })
         }
+        Command::ServiceDetail { id, unit } => {
+            let detail = service_detail(&unit).await?;
+            Ok(Response::ServiceDetailOk { id, detail })
+        }
+        Command::CgroupTree { id } => {
+            let root = cgroup_tree().await?;
+            Ok(Response::CgroupTreeOk { id, root })
+        }
+        Command::Pressure { id } => Ok(Response::PressureOk {
+            id,
+            pressure: Pressure {
+                cpu: pressure_stall("cpu").await,
+                io: pressure_stall("io").await,
+                memory: pressure_stall("memory").await,
+                oom_kills: oom_kills().await,
+            },
+        }),
+        Command::RaidStatus { id } => Ok(Response::RaidStatusOk {
+            id,
+            arrays: raid_arrays().await,
+        }),
+        Command::StorageStacks { id } => Ok(Response::StorageStacksOk {
+            id,
+            stacks: storage_stacks().await,
+        }),
+        Command::Backups { id } => Ok(Response::BackupsOk {
+            id,
+            jobs: backup_jobs().await,
+        }),
+        Command::Sessions { id } => Ok(Response::SessionsOk {
+            id,
+            sessions: tokio::task::spawn_blocking(login_sessions).await?,
+        }),
+        Command::SshdConfig { id } => Ok(Response::SshdConfigOk {
+            id,
+            config: sshd_config().await,
+        }),
+        Command::Users { id } => Ok(Response::UsersOk {
+            id,
+            users: tokio::task::spawn_blocking(user_inventory).await?,
+        }),
+        Command::AuthFailures { id, window_secs } => {
+            let window_secs = window_secs
+                .unwrap_or(DEFAULT_AUTH_WINDOW_SECS)
+                .min(MAX_AUTH_WINDOW_SECS);
+            Ok(Response::AuthFailuresOk {
+                id,
+                summary: auth_failures(window_secs).await,
+            })
+        }
+        Command::ContainersList { id } => {
+            let containers = containers_list().await?;
+            Ok(Response::ContainersListOk { id, containers })
+        }
+        Command::ProcessesSummary { id, limit } => {
+            let limit = limit.unwrap_or(DEFAULT_PROCESSES).min(MAX_PROCESSES);
+            let summary = processes_summary(limit).await?;
+            Ok(Response::ProcessesSummaryOk { id, summary })
+        }
+        Command::ProcessDetail { id, pid } => {
+            let detail = process_detail(pid).await?;
+            Ok(Response::ProcessDetailOk { id, detail })
+        }
+        Command::SignalProcess { id, pid, signal } => {
+            signal_process(pid, signal)?;
+            Ok(Response::ProcessActionOk { id, pid })
+        }
+        Command::ReniceProcess { id, pid, nice } => {
+            renice_process(pid, nice)?;
+            Ok(Response::ProcessActionOk { id, pid })
+        }
+        Command::NetListeners { id } => {
+            let listeners = net_listeners().await?;
+            Ok(Response::NetListenersOk { id, listeners })
+        }
+        Command::Reachability {
+            id,
+            targets,
+            timeout_ms,
+        } => {
+            if targets.len() > MAX_REACH_TARGETS {
+                return Err(anyhow!(
+                    "{} endpoints requested, at most {} allowed",
+                    targets.len(),
+                    MAX_REACH_TARGETS
+                ));
+            }
+            let timeout_ms = timeout_ms.unwrap_or(DEFAULT_REACH_MS).min(MAX_REACH_MS);
+            Ok(Response::ReachabilityOk {
+                id,
+                results: reachability(targets, std::time::Duration::from_millis(timeout_ms)).await,
+            })
+        }
+        Command::DnsCheck { id, name } => Ok(Response::DnsCheckOk {
+            id,
+            report: dns_check(name.unwrap_or_else(|| DEFAULT_DNS_NAME.to_string())).await,
+        }),
+        Command::DnsConfig { id } => Ok(Response::DnsConfigOk {
+            id,
+            config: dns_config().await,
+        }),
+        Command::Routes { id } => Ok(Response::RoutesOk {
+            id,
+            routes: routes().await,
+        }),
+        Command::Sockets { id } => {
+            let summary = socket_summary().await?;
+            Ok(Response::SocketsOk { id, summary })
+        }
+        Command::DiskUsage { id } => {
+            let mounts = disk_usage().await?;
+            Ok(Response::DiskUsageOk { id, mounts })
+        }
+        Command::HardwareInventory { id } => {
+            let inventory = tokio::task::spawn_blocking(hardware_inventory).await?;
+            Ok(Response::HardwareInventoryOk { id, inventory })
+        }
+        Command::Sensors { id } => {
+            let mut readings = tokio::task::spawn_blocking(hwmon_readings).await?;
+            readings.temperatures.extend(nvidia_temperatures().await);
+            Ok(Response::SensorsOk { id, readings })
+        }
+        Command::GpuInfo { id } => Ok(Response::GpuInfoOk {
+            id,
+            gpus: gpu_info().await?,
+        }),
+        Command::KernelModules { id } => Ok(Response::KernelModulesOk {
+            id,
+            modules: kernel_modules().await,
+        }),
+        Command::Sysctl { id, keys } => Ok(Response::SysctlOk {
+            id,
+            values: tokio::task::spawn_blocking(move || sysctl_values(keys)).await?,
+        }),
+        Command::Packages { id, offset, limit } => {
+            let manager = package_manager();
+            let installed = match manager {
+                Some(manager) => installed_packages(manager).await?,
+                None => Vec::new(),
+            };
+            let total = installed.len();
+            let offset = offset.unwrap_or(0).min(total);
+            let limit = limit.unwrap_or(DEFAULT_PACKAGES).min(MAX_PACKAGES);
+            let packages = installed.into_iter().skip(offset).take(limit).collect();
+            Ok(Response::PackagesOk {
+                id,
+                packages: PackageList {
+                    manager,
+                    total,
+                    offset,
+                    packages,
+                },
+            })
+        }
+        Command::UpdatesAvailable { id } => {
+            let manager = package_manager();
+            let packages = match manager {
+                Some(manager) => pending_updates(manager).await?,
+                None => Vec::new(),
+            };
+            Ok(Response::UpdatesAvailableOk {
+                id,
+                updates: PendingUpdates { manager, packages },
+            })
+        }
+        Command::MetricsSample { id, interval_ms } => {
+            let interval_ms = interval_ms
+                .unwrap_or(DEFAULT_METRICS_MS)
+                .clamp(1, MAX_METRICS_MS);
+            let sample = metrics_sample(interval_ms).await?;
+            Ok(Response::MetricsSampleOk { id, sample })
+        }
+        Command::ServicesDelta { id, since } => {
+            let services = services_list().await?;
+            let delta = session.services_delta(since, services);
+            Ok(Response::ServicesDeltaOk { id, delta })
+        }
+        Command::ListenersDelta { id, since } => {
+            let listeners = net_listeners().await?;
+            let delta = session.listeners_delta(since, listeners);
+            Ok(Response::ListenersDeltaOk { id, delta })
+        }
         Command::ListDir {
             id,
             path,
@@ -116,88 +1435,4452 @@ async fn handle_command(cmd: Command) -> Result<Response> {
                 eof,
             })
         }
+        Command::ReadFile {
+            id,
+            path,
+            offset,
+            len,
+        } => {
+            let len = len.unwrap_or(DEFAULT_READ_BYTES).min(MAX_READ_BYTES);
+            let chunk = read_file(expand_tilde(path), offset.unwrap_or(0), len).await?;
+            Ok(Response::ReadFileOk { id, chunk })
+        }
+        Command::AgentStats { id } => Ok(Response::AgentStatsOk {
+            id,
+            stats: agent_stats(session).await,
+        }),
+        Command::Checksum { id, path, algo } => {
+            let path = expand_tilde(path);
+            let (size, digest) = checksum(path.clone(), algo).await?;
+            Ok(Response::ChecksumOk {
+                id,
+                path,
+                algo,
+                size,
+                digest,
+            })
+        }
+        Command::WriteFileBegin { id, path, mode } => {
+            if session.uploads.contains_key(&id) {
+                return Err(anyhow!("write {} already started", id));
+            }
+            if session.uploads.len() >= MAX_UPLOADS {
+                return Err(anyhow!("too many writes in progress (max {})", MAX_UPLOADS));
+            }
+            let upload = Upload::begin(id, expand_tilde(path), mode).await?;
+            session.uploads.insert(id, upload);
+            Ok(Response::WriteFileProgress { id, written: 0 })
+        }
+        Command::WriteFileChunk { id, data } => {
+            let upload = session
+                .uploads
+                .get_mut(&id)
+                .ok_or_else(|| anyhow!("no write {}", id))?;
+            match upload.append(&data).await {
+                Ok(written) => Ok(Response::WriteFileProgress { id, written }),
+                Err(e) => {
+                    // A failed chunk ends the write; the target stays as it was.
+                    session.uploads.remove(&id);
+                    Err(e)
+                }
+            }
+        }
+        Command::WriteFileEnd { id, abort } => {
+            let mut upload = session
+                .uploads
+                .remove(&id)
+                .ok_or_else(|| anyhow!("no write {}", id))?;
+            let path = upload.path.to_string_lossy().into_owned();
+            if abort {
+                return Ok(Response::WriteFileDone {
+                    id,
+                    path,
+                    size: upload.written,
+                    saved: false,
+                });
+            }
+            let size = upload.finish().await?;
+            Ok(Response::WriteFileDone {
+                id,
+                path,
+                size,
+                saved: true,
+            })
+        }
+        Command::JournalTail {
+            id,
+            unit,
+            lines,
+            follow,
+        } => {
+            let lines = lines
+                .unwrap_or(DEFAULT_JOURNAL_LINES)
+                .min(MAX_JOURNAL_LINES);
+            let (entries, cursor) = journal_tail(unit.as_deref(), lines).await?;
+            if follow {
+                session.follow_journal(id, unit, cursor);
+            }
+            Ok(Response::JournalLines {
+                id,
+                entries,
+                end: !follow,
+            })
+        }
+        Command::JournalStop { id } => {
+            if !session.stop_journal(id) {
+                return Err(anyhow!("no journal stream {}", id));
+            }
+            Ok(Response::JournalLines {
+                id,
+                entries: Vec::new(),
+                end: true,
+            })
+        }
+        Command::TailFile {
+            id,
+            path,
+            lines,
+            follow,
+        } => {
+            let lines = lines
+                .unwrap_or(DEFAULT_JOURNAL_LINES)
+                .min(MAX_JOURNAL_LINES);
+            let path = expand_tilde(path);
+            let (backlog, offset, inode) = tokio::task::spawn_blocking({
+                let path = path.clone();
+                move || tail_lines(&path, lines)
+            })
+            .await??;
+            if follow {
+                session.follow_file(id, path, offset, inode);
+            }
+            Ok(Response::FileLines {
+                id,
+                lines: backlog,
+                end: !follow,
+            })
+        }
+        Command::TailStop { id } => {
+            if !session.stop_tail(id) {
+                return Err(anyhow!("no tail stream {}", id));
+            }
+            Ok(Response::FileLines {
+                id,
+                lines: Vec::new(),
+                end: true,
+            })
+        }
+        Command::Exec {
+            id,
+            argv,
+            cwd,
+            env,
+            timeout_ms,
+        } => {
+            let (program, args) = argv.split_first().ok_or_else(|| anyhow!("empty argv"))?;
+            let mut cmd = TokioCommand::new(program);
+            cmd.args(args)
+                .envs(env)
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .kill_on_drop(true);
+            if let Some(cwd) = cwd {
+                cmd.current_dir(expand_tilde(cwd));
+            }
+            let child = cmd
+                .spawn()
+                .map_err(|e| anyhow!("failed to run {}: {}", program, e))?;
+            let pid = child.id().unwrap_or(0);
+            let timeout_ms = timeout_ms.unwrap_or(DEFAULT_EXEC_MS).min(MAX_EXEC_MS);
+            session.exec(id, child, std::time::Duration::from_millis(timeout_ms));
+            Ok(Response::ExecStarted { id, pid })
+        }
+        Command::ExecKill { id } => {
+            if !session.kill_exec(id) {
+                return Err(anyhow!("no running exec {}", id));
+            }
+            Ok(Response::ExecExit {
+                id,
+                code: None,
+                signal: Some(libc::SIGKILL),
+                timed_out: false,
+            })
+        }
+        Command::Subscribe {
+            id,
+            facet,
+            interval_ms,
+        } => {
+            if facet == Facet::Unknown {
+                return Err(anyhow!("unsupported facet (agent v{})", AGENT_VERSION));
+            }
+            let interval_ms = interval_ms
+                .unwrap_or(DEFAULT_SUBSCRIBE_MS)
+                .clamp(MIN_SUBSCRIBE_MS, MAX_SUBSCRIBE_MS);
+            session.subscribe(id, facet, std::time::Duration::from_millis(interval_ms));
+            Ok(Response::Subscribed { id, interval_ms })
+        }
+        Command::Unsubscribe { id } => {
+            if !session.unsubscribe(id) {
+                return Err(anyhow!("no subscription {}", id));
+            }
+            Ok(Response::Unsubscribed { id })
+        }
+        Command::Batch { id, commands } => {
+            let mut responses = Vec::with_capacity(commands.len());
+            for cmd in commands {
+                let cmd_id = cmd.id();
+                let resp = match cmd {
+                    Command::Batch { .. } => Err(anyhow!("nested batch")),
+                    Command::JournalTail { follow: true, .. } => {
+                        Err(anyhow!("journal streams cannot be batched"))
+                    }
+                    Command::TailFile { follow: true, .. } => {
+                        Err(anyhow!("tail streams cannot be batched"))
+                    }
+                    Command::Exec { .. } => Err(anyhow!("exec streams cannot be batched")),
+                    Command::Subscribe { .. } => Err(anyhow!("subscriptions cannot be batched")),
+                    cmd => Box::pin(handle_command(cmd, session)).await,
+                };
+                responses.push(resp.unwrap_or_else(|e| Response::Error {
+                    id: cmd_id,
+                    message: e.to_string(),
+                }));
+            }
+            Ok(Response::BatchOk { id, responses })
+        }
+        Command::Unknown => Err(anyhow!("unsupported command (agent v{})", AGENT_VERSION)),
+    }
+}
+
+/// The next read of `pipe` (None at its end); never ready once it is gone.
+async fn read_pipe<R: tokio::io::AsyncRead + Unpin>(
+    pipe: &mut Option<R>,
+    buf: &mut [u8],
+) -> Option<usize> {
+    use tokio::io::AsyncReadExt;
+    match pipe {
+        Some(pipe) => match pipe.read(buf).await {
+            Ok(0) | Err(_) => None,
+            Ok(n) => Some(n),
+        },
+        None => std::future::pending().await,
+    }
+}
+
+/// Text of `bytes` after `rest` (the incomplete UTF-8 sequence that ended the
+/// previous read), keeping the one ending `bytes` in `rest`. An empty `bytes`
+/// flushes `rest`.
+fn utf8_chunk(rest: &mut Vec<u8>, bytes: &[u8]) -> String {
+    rest.extend_from_slice(bytes);
+    let keep = match std::str::from_utf8(rest) {
+        Err(e) if e.error_len().is_none() && !bytes.is_empty() => rest.len() - e.valid_up_to(),
+        _ => 0,
+    };
+    let tail = rest.split_off(rest.len() - keep);
+    let text = String::from_utf8_lossy(rest).into_owned();
+    *rest = tail;
+    text
+}
+
+fn expand_tilde(path: String) -> String {
+    if let Some(stripped) = path.strip_prefix("~/") {
+        if let Some(home) = dirs_next::home_dir() {
+            return format!("{}/{}", home.display(), stripped);
+        }
+    }
+    path
+}
+
+async fn sys_info() -> Result<SysInfo> {
+    // OS and arch from Rust std
+    let os = std::env::consts::OS.to_string();
+    let arch = std::env::consts::ARCH.to_string();
+
+    // Kernel release from /proc (Linux)
+    let kernel = match fs::read_to_string("/proc/sys/kernel/osrelease").await {
+        Ok(s) => s.trim().to_string(),
+        Err(_) => "unknown".to_string(),
+    };
+
+    // Uptime in seconds (first field of /proc/uptime)
+    let uptime_secs = match fs::read_to_string("/proc/uptime").await {
+        Ok(s) => s
+            .split_whitespace()
+            .next()
+            .and_then(|v| v.parse::<f64>().ok())
+            .map(|f| f as u64)
+            .unwrap_or(0),
+        Err(_) => 0,
+    };
+
+    // Hostname from /proc, fallback to HOSTNAME env
+    let hostname = match fs::read_to_string("/proc/sys/kernel/hostname").await {
+        Ok(s) => s.trim().to_string(),
+        Err(_) => std::env::var("HOSTNAME").unwrap_or_else(|_| "unknown".to_string()),
+    };
+
+    let reboot = reboot_status(&kernel).await;
+    Ok(SysInfo {
+        os,
+        kernel,
+        arch,
+        uptime_secs,
+        hostname,
+        load_avg: load_avg().await,
+        platform: Some(platform().await),
+        time_sync: time_sync().await,
+        reboot: Some(reboot),
+    })
+}
+
+/// Whether the host waits for a reboot: the flag file Debian and Ubuntu
+/// leave, or a kernel newer than `running` installed.
+async fn reboot_status(running: &str) -> RebootStatus {
+    let flagged = fs::metadata(REBOOT_REQUIRED).await.is_ok();
+    let mut packages: Vec<String> = match fs::read_to_string(REBOOT_REQUIRED_PKGS).await {
+        Ok(s) => s
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(String::from)
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    packages.sort();
+    packages.dedup();
+    let newer_kernel = installed_kernels()
+        .await
+        .into_iter()
+        .filter(|v| kernel_flavor(v) == kernel_flavor(running))
+        .filter(|v| compare_versions(v, running) == std::cmp::Ordering::Greater)
+        .max_by(|a, b| compare_versions(a, b));
+    RebootStatus {
+        flagged,
+        packages,
+        newer_kernel,
+    }
+}
+
+/// Versions of the kernels installed: a modules directory with its
+/// modules.dep, and an image next to it or in /boot. Directories of removed
+/// kernels can linger with only out-of-tree modules in them.
+async fn installed_kernels() -> Vec<String> {
+    let mut out = Vec::new();
+    for dir in MODULES_DIRS {
+        let Ok(mut entries) = fs::read_dir(dir).await else {
+            continue;
+        };
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let version = entry.file_name().to_string_lossy().into_owned();
+            let path = entry.path();
+            let installed = fs::metadata(path.join("modules.dep")).await.is_ok()
+                && (fs::metadata(path.join("vmlinuz")).await.is_ok()
+                    || fs::metadata(format!("/boot/vmlinuz-{}", version))
+                        .await
+                        .is_ok());
+            if installed && !out.contains(&version) {
+                out.push(version);
+            }
+        }
+    }
+    out
+}
+
+/// "amd64" in "6.1.0-18-amd64", "generic" in "6.8.0-45-generic"; empty when
+/// the release names no flavor. Kernels of other flavors are not upgrades.
+fn kernel_flavor(release: &str) -> &str {
+    match release.rsplit_once('-') {
+        Some((_, last)) if last.starts_with(|c: char| c.is_ascii_alphabetic()) => last,
+        _ => "",
+    }
+}
+
+/// Compare versions run by run, digits numerically: "6.1.0-21" is newer
+/// than "6.1.0-9".
+fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    fn runs(s: &str) -> Vec<&str> {
+        let mut out = Vec::new();
+        let mut start = 0;
+        for (i, c) in s.char_indices().skip(1) {
+            let prev = s[..i].chars().next_back().unwrap_or(c);
+            if prev.is_ascii_digit() != c.is_ascii_digit() {
+                out.push(&s[start..i]);
+                start = i;
+            }
+        }
+        if start < s.len() {
+            out.push(&s[start..]);
+        }
+        out
+    }
+    for (x, y) in runs(a).into_iter().zip(runs(b)) {
+        let order = if x.starts_with(|c: char| c.is_ascii_digit())
+            && y.starts_with(|c: char| c.is_ascii_digit())
+        {
+            let (x, y) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+            x.len().cmp(&y.len()).then_with(|| x.cmp(y))
+        } else {
+            x.cmp(y)
+        };
+        if order != std::cmp::Ordering::Equal {
+            return order;
+        }
+    }
+    runs(a).len().cmp(&runs(b).len())
+}
+
+/// chrony's tracking data, else systemd-timesyncd's status, else just
+/// systemd's synchronized flag.
+async fn time_sync() -> Option<TimeSync> {
+    if let Ok(out) = tool_output("chronyc", &["-c", "tracking"], &[0]).await {
+        if let Some(sync) = parse_chrony_tracking(&out) {
+            return Some(sync);
+        }
+    }
+    let synchronized = tool_output(
+        "timedatectl",
+        &["show", "-p", "NTPSynchronized", "--value"],
+        &[0],
+    )
+    .await
+    .ok()
+    .map(|v| v.trim() == "yes");
+    let status = tool_output("timedatectl", &["timesync-status"], &[0]).await;
+    match (
+        status.ok().and_then(|s| parse_timesync_status(&s)),
+        synchronized,
+    ) {
+        (Some(sync), synchronized) => Some(TimeSync {
+            synchronized: synchronized.unwrap_or(sync.synchronized),
+            ..sync
+        }),
+        (None, Some(synchronized)) => Some(TimeSync {
+            synchronized,
+            ..Default::default()
+        }),
+        (None, None) => None,
+    }
+}
+
+/// `chronyc -c tracking`: reference id, server, stratum, reference time,
+/// system time correction (positive: clock slow), ..., leap status.
+fn parse_chrony_tracking(out: &str) -> Option<TimeSync> {
+    let fields: Vec<&str> = out.trim().split(',').collect();
+    if fields.len() < 14 {
+        return None;
+    }
+    Some(TimeSync {
+        source: Some("chrony".to_string()),
+        synchronized: fields[13] != "Not synchronised",
+        offset_secs: fields[4].parse::<f64>().ok().map(|correction| -correction),
+        stratum: fields[2].parse().ok(),
+        server: Some(fields[1].to_string()).filter(|s| !s.is_empty()),
+    })
+}
+
+/// `timedatectl timesync-status`: "Server: 185.125.190.56 (ntp.ubuntu.com)",
+/// "Stratum: 2", "Offset: -1.080ms", ...; None when it has no server.
+fn parse_timesync_status(out: &str) -> Option<TimeSync> {
+    let field = |key: &str| {
+        out.lines()
+            .find_map(|line| line.trim().strip_prefix(key)?.strip_prefix(':'))
+            .map(str::trim)
+    };
+    let server = field("Server")?;
+    // "185.125.190.56 (ntp.ubuntu.com)": the name, where there is one.
+    let server = match server.split_once(" (") {
+        Some((_, name)) => name.trim_end_matches(')'),
+        None => server,
+    };
+    let offset_secs = field("Offset").and_then(|offset| {
+        let (value, scale) = if let Some(v) = offset.strip_suffix("us") {
+            (v, 1e-6)
+        } else if let Some(v) = offset.strip_suffix("ms") {
+            (v, 1e-3)
+        } else {
+            (offset.strip_suffix('s')?, 1.0)
+        };
+        value.parse::<f64>().ok().map(|v| v * scale)
+    });
+    Some(TimeSync {
+        source: Some("timesyncd".to_string()),
+        synchronized: offset_secs.is_some(),
+        offset_secs,
+        stratum: field("Stratum").and_then(|s| s.parse().ok()),
+        server: Some(server.to_string()),
+    })
+}
+
+/// What the host runs on, detected by the first `SysInfo` of this agent: it
+/// does not change while the agent runs, and the metadata lookups are network
+/// round trips.
+static PLATFORM: tokio::sync::OnceCell<Platform> = tokio::sync::OnceCell::const_new();
+
+async fn platform() -> Platform {
+    PLATFORM.get_or_init(detect_platform).await.clone()
+}
+
+/// systemd-detect-virt, falling back to DMI and container markers where it is
+/// missing; then the instance details from the metadata endpoint of the cloud
+/// DMI names (never probed on other hosts).
+async fn detect_platform() -> Platform {
+    let dmi = dmi_info();
+    let vm = match detect_virt("--vm").await {
+        Some(vm) => vm,
+        None => dmi.as_ref().and_then(vm_from_dmi),
+    };
+    let container = match detect_virt("--container").await {
+        Some(container) => container,
+        None => container_from_markers(),
+    };
+    let platform = Platform {
+        provider: cloud_provider(dmi.as_ref()),
+        vm,
+        container,
+        ..Default::default()
+    };
+    match platform.provider {
+        Some(_) => tokio::task::spawn_blocking(move || instance_metadata(platform))
+            .await
+            .unwrap_or_default(),
+        None => platform,
+    }
+}
+
+/// systemd-detect-virt's answer (None inside it meaning "none"); None when
+/// the tool is not installed.
+async fn detect_virt(kind: &str) -> Option<Option<String>> {
+    // It exits non-zero when it prints "none".
+    let out = TokioCommand::new("systemd-detect-virt")
+        .arg(kind)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .ok()?;
+    let name = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    Some((!name.is_empty() && name != "none").then_some(name))
+}
+
+/// The hypervisor named by DMI, in systemd-detect-virt's terms.
+fn vm_from_dmi(dmi: &DmiInfo) -> Option<String> {
+    let vendor = dmi.sys_vendor.as_deref().unwrap_or("");
+    let product = dmi.product_name.as_deref().unwrap_or("");
+    let vm = match (vendor, product) {
+        (_, p) if p.starts_with("KVM") => "kvm",
+        ("QEMU", _) => "qemu",
+        ("VMware, Inc.", _) => "vmware",
+        ("innotek GmbH", _) => "oracle",
+        ("Xen", _) => "xen",
+        ("Microsoft Corporation", "Virtual Machine") => "microsoft",
+        ("Amazon EC2", _) => "amazon",
+        (_, "Google Compute Engine") => "google",
+        _ => return None,
+    };
+    Some(vm.to_string())
+}
+
+/// The container runtime named by its marker files or the `container`
+/// variable runtimes set.
+fn container_from_markers() -> Option<String> {
+    if std::path::Path::new("/.dockerenv").exists() {
+        return Some("docker".to_string());
+    }
+    if std::path::Path::new("/run/.containerenv").exists() {
+        return Some("podman".to_string());
+    }
+    std::env::var("container").ok().filter(|c| !c.is_empty())
+}
+
+/// "aws" or "gcp" when DMI (or the Xen UUID of older EC2 instances) says so.
+fn cloud_provider(dmi: Option<&DmiInfo>) -> Option<String> {
+    let vendor = dmi.and_then(|d| d.sys_vendor.as_deref()).unwrap_or("");
+    let product = dmi.and_then(|d| d.product_name.as_deref()).unwrap_or("");
+    let xen_uuid = sys_attr("/sys/hypervisor/uuid").unwrap_or_default();
+    if vendor == "Amazon EC2" || xen_uuid.starts_with("ec2") {
+        Some("aws".to_string())
+    } else if vendor == "Google" || product == "Google Compute Engine" {
+        Some("gcp".to_string())
+    } else {
+        None
+    }
+}
+
+/// Instance type, region and zone from the metadata endpoint of `provider`;
+/// left None where it does not answer.
+fn instance_metadata(mut platform: Platform) -> Platform {
+    match platform.provider.as_deref() {
+        Some("aws") => {
+            // IMDSv2 needs a session token; IMDSv1 (no token) where it is off.
+            let token = metadata_request(
+                "PUT",
+                "/latest/api/token",
+                &[("X-aws-ec2-metadata-token-ttl-seconds", "60")],
+            );
+            let headers: Vec<(&str, &str)> = token
+                .iter()
+                .map(|t| ("X-aws-ec2-metadata-token", t.as_str()))
+                .collect();
+            let get = |path: &str| {
+                metadata_request("GET", &format!("/latest/meta-data/{}", path), &headers)
+            };
+            platform.instance_type = get("instance-type");
+            platform.zone = get("placement/availability-zone");
+            platform.region = get("placement/region");
+        }
+        Some("gcp") => {
+            // Both answer with a path: "projects/123/machineTypes/e2-medium".
+            let get = |path: &str| {
+                let value = metadata_request(
+                    "GET",
+                    &format!("/computeMetadata/v1/instance/{}", path),
+                    &[("Metadata-Flavor", "Google")],
+                )?;
+                value.rsplit('/').next().map(str::to_string)
+            };
+            platform.instance_type = get("machine-type");
+            platform.zone = get("zone");
+            // Zone "europe-west1-b" is in region "europe-west1".
+            platform.region = platform
+                .zone
+                .as_deref()
+                .and_then(|z| z.rsplit_once('-'))
+                .map(|(region, _)| region.to_string());
+        }
+        _ => {}
+    }
+    platform
+}
+
+/// One plain HTTP request to the link-local metadata endpoint: the trimmed
+/// body of a 200 answer, None otherwise.
+fn metadata_request(method: &str, path: &str, headers: &[(&str, &str)]) -> Option<String> {
+    use std::io::{Read, Write};
+    let addr = METADATA_ADDR.parse().ok()?;
+    let mut stream = std::net::TcpStream::connect_timeout(&addr, METADATA_TIMEOUT).ok()?;
+    stream.set_read_timeout(Some(METADATA_TIMEOUT)).ok()?;
+    stream.set_write_timeout(Some(METADATA_TIMEOUT)).ok()?;
+    let mut request = format!(
+        "{} {} HTTP/1.0\r\nHost: {}\r\n",
+        method, path, METADATA_ADDR
+    );
+    for (name, value) in headers {
+        request.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    request.push_str("Content-Length: 0\r\n\r\n");
+    stream.write_all(request.as_bytes()).ok()?;
+    let mut answer = String::new();
+    stream.take(64 * 1024).read_to_string(&mut answer).ok()?;
+    let (head, body) = answer.split_once("\r\n\r\n")?;
+    let body = body.trim();
+    (head.split_whitespace().nth(1) == Some("200") && !body.is_empty()).then(|| body.to_string())
+}
+
+/// Load averages (first three fields of /proc/loadavg)
+async fn load_avg() -> Option<[f64; 3]> {
+    let s = fs::read_to_string("/proc/loadavg").await.ok()?;
+    let v: Vec<f64> = s
+        .split_whitespace()
+        .take(3)
+        .filter_map(|f| f.parse::<f64>().ok())
+        .collect();
+    if v.len() == 3 {
+        Some([v[0], v[1], v[2]])
+    } else {
+        None
+    }
+}
+
+async fn static_config() -> Result<StaticConfig> {
+    // /etc/os-release content (optional)
+    let os_release = match fs::read_to_string("/etc/os-release").await {
+        Ok(s) => Some(s),
+        Err(_) => None,
+    };
+
+    // CPU count from /proc/cpuinfo
+    let cpu_count = match fs::read_to_string("/proc/cpuinfo").await {
+        Ok(s) => s.lines().filter(|l| l.starts_with("processor")).count() as u32,
+        Err(_) => 0,
+    };
+
+    // MemTotal from /proc/meminfo (in kB) -> bytes
+    let mem_total_bytes = match fs::read_to_string("/proc/meminfo").await {
+        Ok(s) => s
+            .lines()
+            .find(|l| l.starts_with("MemTotal:"))
+            .and_then(|l| l.split_whitespace().nth(1))
+            .and_then(|kb| kb.parse::<u64>().ok())
+            .map(|kb| kb * 1024)
+            .unwrap_or(0),
+        Err(_) => 0,
+    };
+
+    let model = fs::read_to_string("/proc/cpuinfo")
+        .await
+        .ok()
+        .and_then(|s| {
+            s.lines()
+                .find(|l| l.starts_with("model name"))
+                .and_then(|l| l.split_once(':'))
+                .map(|(_, v)| v.trim().to_string())
+        });
+    let topology = tokio::task::spawn_blocking(cpu_topology)
+        .await
+        .ok()
+        .flatten()
+        .map(|topology| CpuTopology { model, ..topology });
+
+    Ok(StaticConfig {
+        os_release,
+        cpu_count,
+        mem_total_bytes,
+        topology,
+    })
+}
+
+/// Sockets, cores, NUMA nodes and caches of the online CPUs, from /sys.
+fn cpu_topology() -> Option<CpuTopology> {
+    let sys = std::path::Path::new("/sys/devices/system");
+    let read = |path: PathBuf| std::fs::read_to_string(path).ok();
+    let read_u32 = |path: PathBuf| read(path).and_then(|s| s.trim().parse::<u32>().ok());
+
+    let mut numa_nodes: Vec<NumaNode> = std::fs::read_dir(sys.join("node"))
+        .map(|dir| {
+            dir.flatten()
+                .filter_map(|e| {
+                    let id = e.file_name().to_str()?.strip_prefix("node")?.parse().ok()?;
+                    let path = e.path();
+                    // "Node 0 MemTotal:       16318848 kB"
+                    let memory_bytes = read(path.join("meminfo")).and_then(|s| {
+                        s.lines()
+                            .find(|l| l.contains("MemTotal:"))
+                            .and_then(|l| l.split_whitespace().nth(3))
+                            .and_then(|kb| kb.parse::<u64>().ok())
+                            .map(|kb| kb * 1024)
+                    });
+                    Some(NumaNode {
+                        id,
+                        cpus: parse_cpu_list(&read(path.join("cpulist")).unwrap_or_default()),
+                        memory_bytes,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    numa_nodes.sort_by_key(|n| n.id);
+
+    let cpu_dir = sys.join("cpu");
+    let cpus: Vec<LogicalCpu> = parse_cpu_list(&read(cpu_dir.join("online"))?)
+        .into_iter()
+        .map(|id| {
+            let topology = cpu_dir.join(format!("cpu{}/topology", id));
+            LogicalCpu {
+                id,
+                socket: read_u32(topology.join("physical_package_id")).unwrap_or(0),
+                core: read_u32(topology.join("core_id")).unwrap_or(id),
+                node: numa_nodes
+                    .iter()
+                    .find(|n| n.cpus.contains(&id))
+                    .map(|n| n.id),
+            }
+        })
+        .collect();
+    let first = cpus.first()?.id;
+
+    let mut caches = Vec::new();
+    for index in 0.. {
+        let dir = |cpu: u32| cpu_dir.join(format!("cpu{}/cache/index{}", cpu, index));
+        let Some(level) = read_u32(dir(first).join("level")) else {
+            break;
+        };
+        let shared_by =
+            parse_cpu_list(&read(dir(first).join("shared_cpu_list")).unwrap_or_default())
+                .len()
+                .max(1) as u32;
+        let instances: std::collections::HashSet<String> = cpus
+            .iter()
+            .filter_map(|c| read(dir(c.id).join("shared_cpu_list")))
+            .collect();
+        caches.push(CpuCache {
+            level,
+            kind: read(dir(first).join("type"))
+                .map(|t| t.trim().to_string())
+                .unwrap_or_default(),
+            size_bytes: read(dir(first).join("size"))
+                .and_then(|s| parse_cache_size(s.trim()))
+                .unwrap_or(0),
+            shared_by,
+            instances: instances.len().max(1) as u32,
+        });
+    }
+
+    let sockets: std::collections::HashSet<u32> = cpus.iter().map(|c| c.socket).collect();
+    let cores: std::collections::HashSet<(u32, u32)> =
+        cpus.iter().map(|c| (c.socket, c.core)).collect();
+    Some(CpuTopology {
+        model: None,
+        sockets: sockets.len() as u32,
+        cores: cores.len() as u32,
+        threads: cpus.len() as u32,
+        cpus,
+        numa_nodes,
+        caches,
+    })
+}
+
+/// "0-3,8,10-11" (a sysfs CPU list) as the ids it names.
+fn parse_cpu_list(list: &str) -> Vec<u32> {
+    list.trim()
+        .split(',')
+        .filter_map(|part| match part.split_once('-') {
+            Some((a, b)) => Some(a.parse().ok()?..=b.parse().ok()?),
+            None => {
+                let n = part.parse().ok()?;
+                Some(n..=n)
+            }
+        })
+        .flatten()
+        .collect()
+}
+
+/// "48K", "2048K", "32M" (sysfs cache sizes) in bytes.
+fn parse_cache_size(size: &str) -> Option<u64> {
+    let (digits, unit) = size.split_at(size.trim_end_matches(char::is_alphabetic).len());
+    let n: u64 = digits.parse().ok()?;
+    Some(match unit {
+        "" => n,
+        "K" => n * 1024,
+        "M" => n * 1024 * 1024,
+        "G" => n * 1024 * 1024 * 1024,
+        _ => return None,
+    })
+}
+
+/// Up to `len` bytes of the file at `path` from `offset`.
+async fn read_file(path: String, offset: u64, len: u64) -> Result<FileChunk> {
+    use tokio::io::{AsyncReadExt, AsyncSeekExt};
+    let mut file = fs::File::open(&path)
+        .await
+        .map_err(|e| anyhow!("{}: {}", path, e))?;
+    let meta = file.metadata().await?;
+    if meta.is_dir() {
+        return Err(anyhow!("{}: is a directory", path));
+    }
+    file.seek(std::io::SeekFrom::Start(offset)).await?;
+    let mut data = Vec::new();
+    file.take(len)
+        .read_to_end(&mut data)
+        .await
+        .map_err(|e| anyhow!("{}: {}", path, e))?;
+    let end = offset + data.len() as u64;
+    Ok(FileChunk {
+        path,
+        offset,
+        len: data.len() as u64,
+        size: meta.len(),
+        data: slarti_proto::base64::encode(&data),
+        // Files of /proc and /sys report size 0: end on a short read instead.
+        eof: if meta.len() > 0 {
+            end >= meta.len()
+        } else {
+            (data.len() as u64) < len
+        },
+    })
+}
+
+/// Size and lowercase hex digest of the file at `path`.
+async fn checksum(path: String, algo: ChecksumAlgo) -> Result<(u64, String)> {
+    use sha2::Digest;
+    use std::io::Read;
+    enum Hasher {
+        Sha256(sha2::Sha256),
+        Blake3(Box<blake3::Hasher>),
+    }
+    let mut hasher = match algo {
+        ChecksumAlgo::Sha256 => Hasher::Sha256(sha2::Sha256::new()),
+        ChecksumAlgo::Blake3 => Hasher::Blake3(Box::new(blake3::Hasher::new())),
+        ChecksumAlgo::Unknown => {
+            return Err(anyhow!("unsupported algorithm (agent v{})", AGENT_VERSION))
+        }
+    };
+    tokio::task::spawn_blocking(move || {
+        let mut file = std::fs::File::open(&path).map_err(|e| anyhow!("{}: {}", path, e))?;
+        if file.metadata()?.is_dir() {
+            return Err(anyhow!("{}: is a directory", path));
+        }
+        let mut buf = vec![0u8; 64 * 1024];
+        let mut size = 0u64;
+        loop {
+            let n = file
+                .read(&mut buf)
+                .map_err(|e| anyhow!("{}: {}", path, e))?;
+            if n == 0 {
+                break;
+            }
+            size += n as u64;
+            match &mut hasher {
+                Hasher::Sha256(h) => h.update(&buf[..n]),
+                Hasher::Blake3(h) => {
+                    h.update(&buf[..n]);
+                }
+            }
+        }
+        let digest = match hasher {
+            Hasher::Sha256(h) => h.finalize().iter().map(|b| format!("{:02x}", b)).collect(),
+            Hasher::Blake3(h) => h.finalize().to_hex().to_string(),
+        };
+        Ok((size, digest))
+    })
+    .await?
+}
+
+/// The last `n` lines of the file at `path`, the offset they end at and the
+/// file's inode. At most `TAIL_MAX_BYTES` are read, so the first line may
+/// be cut.
+fn tail_lines(path: &str, n: usize) -> Result<(Vec<String>, u64, u64)> {
+    use std::io::{Read, Seek, SeekFrom};
+    use std::os::unix::fs::MetadataExt;
+    let mut file = std::fs::File::open(path).map_err(|e| anyhow!("{}: {}", path, e))?;
+    let meta = file.metadata()?;
+    if meta.is_dir() {
+        return Err(anyhow!("{}: is a directory", path));
+    }
+    let end = meta.len();
+    // Read backwards from the end until more than `n` line breaks are in.
+    let mut start = end;
+    let mut tail: Vec<u8> = Vec::new();
+    let mut breaks = 0;
+    while start > 0 && breaks <= n && end - start < TAIL_MAX_BYTES {
+        let step = start.min(64 * 1024).min(TAIL_MAX_BYTES - (end - start));
+        start -= step;
+        let mut chunk = vec![0u8; step as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut chunk)
+            .map_err(|e| anyhow!("{}: {}", path, e))?;
+        breaks += chunk.iter().filter(|&&b| b == b'\n').count();
+        chunk.extend_from_slice(&tail);
+        tail = chunk;
+    }
+    let mut lines: Vec<String> = String::from_utf8_lossy(&tail)
+        .lines()
+        .map(str::to_string)
+        .collect();
+    lines.drain(..lines.len().saturating_sub(n));
+    Ok((lines, end, meta.ino()))
+}
+
+/// `journalctl` for `unit` (the whole journal when None) with JSON output.
+fn journalctl(unit: Option<&str>) -> TokioCommand {
+    let mut cmd = TokioCommand::new("journalctl");
+    cmd.arg("--output=json").arg("--no-pager").arg("--quiet");
+    if let Some(unit) = unit {
+        cmd.arg(format!("--unit={}", unit));
+    }
+    cmd
+}
+
+/// The last `lines` journal entries and the cursor of the newest one.
+async fn journal_tail(
+    unit: Option<&str>,
+    lines: usize,
+) -> Result<(Vec<JournalEntry>, Option<String>)> {
+    let out = journalctl(unit)
+        .arg(format!("--lines={}", lines))
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| anyhow!("journalctl: {}", e))?;
+    if !out.status.success() {
+        return Err(anyhow!(
+            "journalctl: {}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        ));
+    }
+    let mut cursor = None;
+    let entries = String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .filter_map(parse_journal_line)
+        .map(|(entry, c)| {
+            cursor = c.or(cursor.take());
+            entry
+        })
+        .collect();
+    Ok((entries, cursor))
+}
+
+/// Parse one line of `journalctl --output=json` into an entry and its cursor.
+fn parse_journal_line(line: &str) -> Option<(JournalEntry, Option<String>)> {
+    let v: serde_json::Value = serde_json::from_str(line.trim()).ok()?;
+    let field = |key: &str| v.get(key).and_then(|f| f.as_str());
+    // Non-UTF-8 messages are exported as byte arrays.
+    let message = match v.get("MESSAGE") {
+        Some(serde_json::Value::String(s)) => s.clone(),
+        Some(serde_json::Value::Array(bytes)) => String::from_utf8_lossy(
+            &bytes
+                .iter()
+                .filter_map(|b| b.as_u64().map(|b| b as u8))
+                .collect::<Vec<u8>>(),
+        )
+        .into_owned(),
+        _ => String::new(),
+    };
+    let entry = JournalEntry {
+        realtime_usec: field("__REALTIME_TIMESTAMP")
+            .and_then(|t| t.parse().ok())
+            .unwrap_or(0),
+        priority: field("PRIORITY").and_then(|p| p.parse().ok()),
+        unit: field("_SYSTEMD_UNIT").map(str::to_string),
+        message,
+    };
+    Some((entry, field("__CURSOR").map(str::to_string)))
+}
+
+/// Containers of docker and podman, whichever are installed. A runtime that
+/// is installed but fails (e.g. no access to the docker socket) is an error
+/// unless another one answered.
+async fn containers_list() -> Result<Vec<ContainerInfo>> {
+    let mut containers: Vec<ContainerInfo> = Vec::new();
+    let mut failures = Vec::new();
+    for runtime in ["docker", "podman"] {
+        // docker's format prints one object per line; podman's json is one array.
+        let format = if runtime == "docker" {
+            "{{json .}}"
+        } else {
+            "json"
+        };
+        let out = match TokioCommand::new(runtime)
+            .arg("ps")
+            .arg("--all")
+            .arg("--no-trunc")
+            .arg(format!("--format={}", format))
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .output()
+            .await
+        {
+            Ok(out) => out,
+            // Not installed
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => {
+                failures.push(format!("{}: {}", runtime, e));
+                continue;
+            }
+        };
+        if !out.status.success() {
+            failures.push(format!(
+                "{}: {}",
+                runtime,
+                String::from_utf8_lossy(&out.stderr).trim()
+            ));
+            continue;
+        }
+        let stdout = String::from_utf8_lossy(&out.stdout);
+        let found = if runtime == "docker" {
+            stdout.lines().filter_map(parse_docker_container).collect()
+        } else {
+            parse_podman_containers(&stdout)
+        };
+        // podman's docker emulation lists the same containers twice.
+        for c in found {
+            if !containers
+                .iter()
+                .any(|k| k.name == c.name && k.image == c.image)
+            {
+                containers.push(c);
+            }
+        }
+    }
+    if containers.is_empty() && !failures.is_empty() {
+        return Err(anyhow!("{}", failures.join("; ")));
+    }
+    containers.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(containers)
+}
+
+/// One line of `docker ps --format '{{json .}}'`.
+fn parse_docker_container(line: &str) -> Option<ContainerInfo> {
+    let v: serde_json::Value = serde_json::from_str(line.trim()).ok()?;
+    let field = |key: &str| v.get(key).and_then(|f| f.as_str()).unwrap_or_default();
+    // Docker before 20.10 has no State, only a Status like "Up 2 hours".
+    let state = match field("State") {
+        "" => match field("Status").split_whitespace().next() {
+            Some("Up") => "running".to_string(),
+            Some(word) => word.to_ascii_lowercase(),
+            None => String::new(),
+        },
+        state => state.to_string(),
+    };
+    Some(ContainerInfo {
+        name: field("Names")
+            .split(',')
+            .next()
+            .unwrap_or_default()
+            .to_string(),
+        image: field("Image").to_string(),
+        state,
+        ports: field("Ports")
+            .split(", ")
+            .filter(|p| !p.is_empty())
+            .map(str::to_string)
+            .collect(),
+        runtime: "docker".to_string(),
+    })
+}
+
+/// The output of `podman ps --format json`.
+fn parse_podman_containers(text: &str) -> Vec<ContainerInfo> {
+    let Ok(serde_json::Value::Array(items)) = serde_json::from_str(text.trim()) else {
+        return Vec::new();
+    };
+    items
+        .iter()
+        .map(|v| {
+            let field = |key: &str| v.get(key).and_then(|f| f.as_str()).unwrap_or_default();
+            let name = v
+                .get("Names")
+                .and_then(|n| n.get(0))
+                .and_then(|n| n.as_str())
+                .unwrap_or_default();
+            let ports = v
+                .get("Ports")
+                .and_then(|p| p.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|p| {
+                    let num = |key: &str| p.get(key).and_then(|n| n.as_u64());
+                    let (host, container) = (num("host_port")?, num("container_port")?);
+                    let ip = p
+                        .get("host_ip")
+                        .and_then(|i| i.as_str())
+                        .filter(|i| !i.is_empty())
+                        .unwrap_or("0.0.0.0");
+                    let proto = p.get("protocol").and_then(|i| i.as_str()).unwrap_or("tcp");
+                    Some(match num("range").unwrap_or(1) {
+                        0 | 1 => format!("{}:{}->{}/{}", ip, host, container, proto),
+                        n => format!(
+                            "{}:{}-{}->{}-{}/{}",
+                            ip,
+                            host,
+                            host + n - 1,
+                            container,
+                            container + n - 1,
+                            proto
+                        ),
+                    })
+                })
+                .collect();
+            ContainerInfo {
+                name: name.to_string(),
+                image: field("Image").to_string(),
+                state: field("State").to_string(),
+                ports,
+                runtime: "podman".to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Try each endpoint at once; results come back in the order asked.
+async fn reachability(targets: Vec<Endpoint>, timeout: std::time::Duration) -> Vec<ReachResult> {
+    let probes: Vec<_> = targets
+        .into_iter()
+        .map(|endpoint| tokio::task::spawn_blocking(move || reach(endpoint, timeout)))
+        .collect();
+    let mut results = Vec::with_capacity(probes.len());
+    for probe in probes {
+        if let Ok(result) = probe.await {
+            results.push(result);
+        }
+    }
+    results
+}
+
+/// Resolve `endpoint` and connect to its addresses in turn until one answers
+/// or `timeout` (for all of them) has passed.
+fn reach(endpoint: Endpoint, timeout: std::time::Duration) -> ReachResult {
+    use std::net::ToSocketAddrs;
+    let started = std::time::Instant::now();
+    let mut result = ReachResult {
+        endpoint,
+        ..Default::default()
+    };
+    let addrs = match (result.endpoint.host.as_str(), result.endpoint.port).to_socket_addrs() {
+        Ok(addrs) => addrs,
+        Err(e) => {
+            result.error = Some(e.to_string());
+            return result;
+        }
+    };
+    let mut error = "no addresses".to_string();
+    for addr in addrs {
+        let Some(left) = timeout
+            .checked_sub(started.elapsed())
+            .filter(|d| !d.is_zero())
+        else {
+            error = "connection timed out".to_string();
+            break;
+        };
+        match std::net::TcpStream::connect_timeout(&addr, left) {
+            Ok(_) => {
+                result.address = Some(addr.ip().to_string());
+                result.latency_ms = Some(started.elapsed().as_secs_f64() * 1000.0);
+                return result;
+            }
+            Err(e) => error = e.to_string(),
+        }
+    }
+    result.error = Some(error);
+    result
+}
+
+/// Resolver configuration and a timed lookup of `name`.
+async fn dns_check(name: String) -> DnsReport {
+    DnsReport {
+        config: dns_config().await,
+        lookup: dns_lookup(name).await,
+    }
+}
+
+/// resolv.conf (and what it links to), its nameservers and search domains,
+/// and systemd-resolved's view when it runs.
+async fn dns_config() -> DnsConfig {
+    let resolv_conf = fs::read_to_string("/etc/resolv.conf").await.ok();
+    let resolv_conf_target = fs::read_link("/etc/resolv.conf")
+        .await
+        .ok()
+        .map(|target| target.display().to_string());
+    let (nameservers, search) = resolv_conf
+        .as_deref()
+        .map(parse_resolv_conf)
+        .unwrap_or_default();
+    DnsConfig {
+        resolv_conf,
+        resolv_conf_target,
+        nameservers,
+        search,
+        resolved_status: tool_output("resolvectl", &["status", "--no-pager"], &[0])
+            .await
+            .ok(),
+    }
+}
+
+/// `nameserver` addresses and `search` domains of a resolv.conf; the last
+/// `search` (or `domain`) line wins, as for the resolver.
+fn parse_resolv_conf(text: &str) -> (Vec<String>, Vec<String>) {
+    let mut nameservers = Vec::new();
+    let mut search = Vec::new();
+    for line in text.lines() {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("nameserver") => nameservers.extend(words.next().map(str::to_string)),
+            Some("search" | "domain") => search = words.map(str::to_string).collect(),
+            _ => {}
+        }
+    }
+    (nameservers, search)
+}
+
+/// Resolve `name` through getaddrinfo, giving up after `DNS_TIMEOUT` (the
+/// blocking lookup is left to finish on its own).
+async fn dns_lookup(name: String) -> DnsLookup {
+    use std::net::ToSocketAddrs;
+    let started = std::time::Instant::now();
+    let result = tokio::time::timeout(
+        DNS_TIMEOUT,
+        tokio::task::spawn_blocking({
+            let name = name.clone();
+            move || (name.as_str(), 0).to_socket_addrs()
+        }),
+    )
+    .await;
+    let mut lookup = DnsLookup {
+        name,
+        elapsed_ms: started.elapsed().as_secs_f64() * 1000.0,
+        ..Default::default()
+    };
+    match result {
+        Ok(Ok(Ok(addrs))) => {
+            // One entry per socket type; keep each address once.
+            for addr in addrs {
+                let ip = addr.ip().to_string();
+                if !lookup.addresses.contains(&ip) {
+                    lookup.addresses.push(ip);
+                }
+            }
+        }
+        Ok(Ok(Err(e))) => lookup.error = Some(e.to_string()),
+        Ok(Err(e)) => lookup.error = Some(e.to_string()),
+        Err(_) => lookup.error = Some(format!("timed out after {:?}", DNS_TIMEOUT)),
+    }
+    lookup
+}
+
+/// The main routing table of both families from `ip -j route`, else from
+/// /proc/net/route and /proc/net/ipv6_route (without protocol or source).
+async fn routes() -> Vec<Route> {
+    let mut routes = Vec::new();
+    for (family, ipv6) in [("-4", false), ("-6", true)] {
+        let parsed = tool_output("ip", &["-j", family, "route", "show"], &[0])
+            .await
+            .ok()
+            .and_then(|out| parse_ip_routes(&out, ipv6));
+        match parsed {
+            Some(list) => routes.extend(list),
+            // No iproute2, or one too old for JSON output
+            None => {
+                routes.clear();
+                break;
+            }
+        }
+    }
+    if routes.is_empty() {
+        if let Ok(text) = fs::read_to_string("/proc/net/route").await {
+            routes.extend(parse_proc_routes(&text));
+        }
+        if let Ok(text) = fs::read_to_string("/proc/net/ipv6_route").await {
+            routes.extend(parse_proc_ipv6_routes(&text));
+        }
+    }
+    routes
+}
+
+/// The output of `ip -j route show`; None when it is not JSON.
+fn parse_ip_routes(text: &str, ipv6: bool) -> Option<Vec<Route>> {
+    let serde_json::Value::Array(items) = serde_json::from_str(text.trim()).ok()? else {
+        return None;
+    };
+    Some(
+        items
+            .iter()
+            .map(|v| {
+                let field = |key: &str| v.get(key).and_then(|f| f.as_str()).map(str::to_string);
+                Route {
+                    destination: field("dst").unwrap_or_else(|| "default".to_string()),
+                    gateway: field("gateway"),
+                    dev: field("dev").unwrap_or_default(),
+                    metric: v
+                        .get("metric")
+                        .and_then(|m| m.as_u64())
+                        .and_then(|m| u32::try_from(m).ok()),
+                    protocol: field("protocol"),
+                    source: field("prefsrc"),
+                    ipv6,
+                }
+            })
+            .collect(),
+    )
+}
+
+/// /proc/net/route: addresses and masks in host byte order, routes that are
+/// up only.
+fn parse_proc_routes(text: &str) -> Vec<Route> {
+    const RTF_UP: u32 = 0x1;
+    const RTF_GATEWAY: u32 = 0x2;
+    let mut routes = Vec::new();
+    for line in text.lines().skip(1) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [dev, dest, gateway, flags, _refcnt, _use, metric, mask, ..] = fields[..] else {
+            continue;
+        };
+        let Ok(flags) = u32::from_str_radix(flags, 16) else {
+            continue;
+        };
+        if flags & RTF_UP == 0 {
+            continue;
+        }
+        let address = |hex: &str| parse_proc_net_address(&format!("{}:0", hex)).map(|(a, _)| a);
+        let (Some(dest), Ok(mask)) = (address(dest), u32::from_str_radix(mask, 16)) else {
+            continue;
+        };
+        let prefix = mask.count_ones();
+        routes.push(Route {
+            destination: if prefix == 0 {
+                "default".to_string()
+            } else {
+                format!("{}/{}", dest, prefix)
+            },
+            gateway: (flags & RTF_GATEWAY != 0)
+                .then(|| address(gateway))
+                .flatten(),
+            dev: dev.to_string(),
+            metric: metric.parse().ok(),
+            protocol: None,
+            source: None,
+            ipv6: false,
+        });
+    }
+    routes
+}
+
+/// /proc/net/ipv6_route: addresses in network byte order. Local, rejected
+/// and multicast routes are left out, as `ip -6 route` does.
+fn parse_proc_ipv6_routes(text: &str) -> Vec<Route> {
+    const RTF_GATEWAY: u32 = 0x2;
+    const RTF_REJECT: u32 = 0x200;
+    const RTF_LOCAL: u32 = 0x8000_0000;
+    let mut routes = Vec::new();
+    for line in text.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [dest, prefix, _src, _src_prefix, next_hop, metric, _refcnt, _use, flags, dev] =
+            fields[..]
+        else {
+            continue;
+        };
+        let address = |hex: &str| {
+            u128::from_str_radix(hex, 16)
+                .ok()
+                .map(std::net::Ipv6Addr::from)
+        };
+        let (Some(dest), Ok(prefix), Ok(flags)) = (
+            address(dest),
+            u8::from_str_radix(prefix, 16),
+            u32::from_str_radix(flags, 16),
+        ) else {
+            continue;
+        };
+        if flags & (RTF_REJECT | RTF_LOCAL) != 0 || dest.is_multicast() {
+            continue;
+        }
+        routes.push(Route {
+            destination: if prefix == 0 {
+                "default".to_string()
+            } else {
+                format!("{}/{}", dest, prefix)
+            },
+            gateway: (flags & RTF_GATEWAY != 0)
+                .then(|| address(next_hop).map(|a| a.to_string()))
+                .flatten(),
+            dev: dev.to_string(),
+            metric: u32::from_str_radix(metric, 16).ok(),
+            protocol: None,
+            source: None,
+            ipv6: true,
+        });
+    }
+    routes
+}
+
+/// Listening sockets from /proc/net/{tcp,tcp6,udp,udp6}, with the owning
+/// process where its /proc/<pid>/fd is readable.
+async fn net_listeners() -> Result<Vec<NetListener>> {
+    // (protocol, file, state of a listening socket: TCP_LISTEN, or TCP_CLOSE for unconnected UDP)
+    const TABLES: [(&str, &str, &str); 4] = [
+        ("tcp", "/proc/net/tcp", "0A"),
+        ("tcp", "/proc/net/tcp6", "0A"),
+        ("udp", "/proc/net/udp", "07"),
+        ("udp", "/proc/net/udp6", "07"),
+    ];
+    let mut sockets: Vec<(u64, NetListener)> = Vec::new();
+    let mut read_any = false;
+    for (protocol, file, listen_state) in TABLES {
+        // tcp6/udp6 are missing when IPv6 is disabled.
+        let Ok(text) = fs::read_to_string(file).await else {
+            continue;
+        };
+        read_any = true;
+        sockets.extend(parse_proc_net_listeners(&text, protocol, listen_state));
+    }
+    if !read_any {
+        return Err(anyhow!("/proc/net is not readable"));
+    }
+
+    let owners = socket_owners().await;
+    let mut listeners: Vec<NetListener> = sockets
+        .into_iter()
+        .map(|(inode, mut l)| {
+            if let Some((pid, comm)) = owners.get(&inode) {
+                l.pid = Some(*pid);
+                l.process = Some(comm.clone());
+            }
+            l
+        })
+        .collect();
+    listeners
+        .sort_by(|a, b| (a.port, &a.protocol, &a.address).cmp(&(b.port, &b.protocol, &b.address)));
+    listeners.dedup();
+    Ok(listeners)
+}
+
+/// The (inode, listener) rows of one /proc/net table whose state is
+/// `listen_state`.
+fn parse_proc_net_listeners(
+    text: &str,
+    protocol: &str,
+    listen_state: &str,
+) -> Vec<(u64, NetListener)> {
+    let mut sockets = Vec::new();
+    // "sl local_address rem_address st tx_queue:rx_queue tr:tm->when retrnsmt uid timeout inode …"
+    for line in text.lines().skip(1) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 10 || fields[3] != listen_state {
+            continue;
+        }
+        let Some((address, port)) = parse_proc_net_address(fields[1]) else {
+            continue;
+        };
+        // A "listening" UDP socket is one with no remote end.
+        if protocol == "udp" && !fields[2].ends_with(":0000") {
+            continue;
+        }
+        sockets.push((
+            fields[9].parse().unwrap_or(0),
+            NetListener {
+                protocol: protocol.to_string(),
+                address,
+                port,
+                pid: None,
+                process: None,
+            },
+        ));
+    }
+    sockets
+}
+
+/// Socket counts from /proc/net like `ss -s`, and the processes holding the
+/// most connections.
+async fn socket_summary() -> Result<SocketSummary> {
+    let mut summary = SocketSummary::default();
+    // (inode, established) of every TCP and UDP socket, for the per-process counts
+    let mut inet: Vec<(u64, bool)> = Vec::new();
+    let mut read_any = false;
+    for (protocol, file) in [
+        ("tcp", "/proc/net/tcp"),
+        ("tcp", "/proc/net/tcp6"),
+        ("udp", "/proc/net/udp"),
+        ("udp", "/proc/net/udp6"),
+    ] {
+        let Ok(text) = fs::read_to_string(file).await else {
+            continue;
+        };
+        read_any = true;
+        for line in text.lines().skip(1) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 10 {
+                continue;
+            }
+            let established = protocol == "tcp" && fields[3] == "01";
+            if protocol == "udp" {
+                summary.udp += 1;
+            } else {
+                summary.tcp_total += 1;
+                match tcp_state(fields[3]) {
+                    "ESTABLISHED" => summary.tcp_established += 1,
+                    "TIME_WAIT" => summary.tcp_time_wait += 1,
+                    "LISTEN" => summary.tcp_listen += 1,
+                    _ => {}
+                }
+            }
+            inet.push((fields[9].parse().unwrap_or(0), established));
+        }
+    }
+    if !read_any {
+        return Err(anyhow!("/proc/net is not readable"));
+    }
+    if let Ok(text) = fs::read_to_string("/proc/net/unix").await {
+        summary.unix = text.lines().skip(1).count() as u64;
+    }
+    // "sockets: used 312" and "TCP: inuse 9 orphan 0 tw 2 alloc 14 mem 3"
+    if let Ok(text) = fs::read_to_string("/proc/net/sockstat").await {
+        for line in text.lines() {
+            let Some((kind, counts)) = line.split_once(':') else {
+                continue;
+            };
+            let counts: Vec<&str> = counts.split_whitespace().collect();
+            let count = |name: &str| {
+                counts
+                    .chunks(2)
+                    .find(|pair| pair[0] == name)
+                    .and_then(|pair| pair.get(1)?.parse().ok())
+            };
+            match kind {
+                "sockets" => summary.used = count("used"),
+                "TCP" => summary.tcp_orphaned = count("orphan"),
+                _ => {}
+            }
+        }
+    }
+
+    // TIME_WAIT sockets have no inode and no owner.
+    let owners = socket_owners().await;
+    let mut users: HashMap<u32, SocketUser> = HashMap::new();
+    for (inode, established) in inet {
+        let Some((pid, comm)) = owners.get(&inode).filter(|_| inode != 0) else {
+            continue;
+        };
+        let user = users.entry(*pid).or_insert_with(|| SocketUser {
+            pid: *pid,
+            process: comm.clone(),
+            ..Default::default()
+        });
+        user.sockets += 1;
+        if established {
+            user.established += 1;
+        }
+    }
+    let mut top: Vec<SocketUser> = users.into_values().collect();
+    top.sort_by(|a, b| (b.established, b.sockets, a.pid).cmp(&(a.established, a.sockets, b.pid)));
+    top.truncate(SOCKETS_TOP);
+    summary.top_processes = top;
+    Ok(summary)
+}
+
+/// "0100007F:0016" (or a 32-digit IPv6 address) into ("127.0.0.1", 22). The
+/// kernel prints each 32-bit word of the address in host byte order.
+fn parse_proc_net_address(field: &str) -> Option<(String, u16)> {
+    let (addr, port) = field.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+    let mut bytes = Vec::with_capacity(16);
+    for i in (0..addr.len()).step_by(8) {
+        let word = u32::from_str_radix(addr.get(i..i + 8)?, 16).ok()?;
+        bytes.extend_from_slice(&word.to_ne_bytes());
+    }
+    let address = match bytes.len() {
+        4 => std::net::Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3]).to_string(),
+        16 => {
+            let octets: [u8; 16] = bytes.try_into().ok()?;
+            std::net::Ipv6Addr::from(octets).to_string()
+        }
+        _ => return None,
+    };
+    Some((address, port))
+}
+
+/// Socket inode -> (pid, comm) for every process whose fds we may read.
+async fn socket_owners() -> HashMap<u64, (u32, String)> {
+    let mut owners = HashMap::new();
+    let Ok(mut procs) = fs::read_dir("/proc").await else {
+        return owners;
+    };
+    while let Ok(Some(ent)) = procs.next_entry().await {
+        let Some(pid) = ent.file_name().to_str().and_then(|n| n.parse::<u32>().ok()) else {
+            continue;
+        };
+        let Ok(mut fds) = fs::read_dir(ent.path().join("fd")).await else {
+            continue;
+        };
+        let mut comm = None;
+        while let Ok(Some(fd)) = fds.next_entry().await {
+            let Ok(target) = fs::read_link(fd.path()).await else {
+                continue;
+            };
+            let Some(inode) = target
+                .to_str()
+                .and_then(|t| t.strip_prefix("socket:["))
+                .and_then(|t| t.strip_suffix(']'))
+                .and_then(|t| t.parse::<u64>().ok())
+            else {
+                continue;
+            };
+            if comm.is_none() {
+                comm = Some(
+                    fs::read_to_string(ent.path().join("comm"))
+                        .await
+                        .map(|c| c.trim().to_string())
+                        .unwrap_or_default(),
+                );
+            }
+            owners
+                .entry(inode)
+                .or_insert_with(|| (pid, comm.clone().unwrap_or_default()));
+        }
+    }
+    owners
+}
+
+/// One process's CPU time (in clock ticks) at sampling time.
+struct ProcSample {
+    pid: u32,
+    comm: String,
+    ticks: u64,
+}
+
+/// Per-process CPU ticks and the total CPU ticks of all cores, from /proc.
+async fn sample_processes() -> Result<(Vec<ProcSample>, u64)> {
+    let stat = fs::read_to_string("/proc/stat")
+        .await
+        .map_err(|e| anyhow!("/proc/stat: {}", e))?;
+    let total = cpu_total_ticks(&stat);
+    let mut samples = Vec::new();
+    let mut dir = fs::read_dir("/proc").await?;
+    while let Some(ent) = dir.next_entry().await? {
+        let Some(pid) = ent.file_name().to_str().and_then(|n| n.parse::<u32>().ok()) else {
+            continue;
+        };
+        // The process may exit between listing and reading.
+        let Ok(text) = fs::read_to_string(ent.path().join("stat")).await else {
+            continue;
+        };
+        let Some((comm, ticks)) = parse_pid_stat(&text) else {
+            continue;
+        };
+        samples.push(ProcSample { pid, comm, ticks });
+    }
+    Ok((samples, total))
+}
+
+/// The ticks of all cores together: the "cpu " line of /proc/stat.
+fn cpu_total_ticks(stat: &str) -> u64 {
+    stat.lines()
+        .next()
+        .filter(|l| l.starts_with("cpu "))
+        .map(|l| {
+            l.split_whitespace()
+                .skip(1)
+                .filter_map(|v| v.parse::<u64>().ok())
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// The command name and CPU ticks (user + system) of /proc/<pid>/stat.
+fn parse_pid_stat(text: &str) -> Option<(String, u64)> {
+    // "pid (comm) state ppid …": comm may contain spaces and parentheses.
+    let (open, close) = (text.find('(')?, text.rfind(')')?);
+    let fields: Vec<&str> = text[close + 1..].split_whitespace().collect();
+    // utime and stime are fields 14 and 15; `fields` starts at field 3.
+    let ticks = fields
+        .get(11..13)
+        .map(|t| t.iter().filter_map(|v| v.parse::<u64>().ok()).sum())
+        .unwrap_or(0);
+    Some((text[open + 1..close].to_string(), ticks))
+}
+
+/// The `limit` processes using the most CPU (over `PROCESS_SAMPLE`) and the most memory.
+async fn processes_summary(limit: usize) -> Result<ProcessesSummary> {
+    let (before, total_before) = sample_processes().await?;
+    tokio::time::sleep(PROCESS_SAMPLE).await;
+    let (after, total_after) = sample_processes().await?;
+    let cores = fs::read_to_string("/proc/stat")
+        .await
+        .map(|s| {
+            s.lines()
+                .filter(|l| l.starts_with("cpu") && !l.starts_with("cpu "))
+                .count()
+        })
+        .unwrap_or(1)
+        .max(1);
+    // Ticks of one core over the interval, so 100% is one busy core.
+    let per_core = total_after.saturating_sub(total_before) as f32 / cores as f32;
+    let previous: HashMap<u32, u64> = before.iter().map(|p| (p.pid, p.ticks)).collect();
+    let users = user_names().await;
+
+    let mut processes = Vec::with_capacity(after.len());
+    for p in after {
+        // Processes started during the interval count from zero.
+        let used = p
+            .ticks
+            .saturating_sub(previous.get(&p.pid).copied().unwrap_or(0));
+        let status = fs::read_to_string(format!("/proc/{}/status", p.pid))
+            .await
+            .unwrap_or_default();
+        let status_field = |key: &str| {
+            status
+                .lines()
+                .find_map(|l| l.strip_prefix(key))
+                .and_then(|v| v.split_whitespace().next())
+        };
+        let uid = status_field("Uid:").unwrap_or_default();
+        processes.push(ProcessInfo {
+            pid: p.pid,
+            comm: p.comm,
+            user: users.get(uid).cloned().unwrap_or_else(|| uid.to_string()),
+            cpu_percent: if per_core > 0.0 {
+                used as f32 * 100.0 / per_core
+            } else {
+                0.0
+            },
+            // Kernel threads have no VmRSS.
+            rss_bytes: status_field("VmRSS:")
+                .and_then(|kb| kb.parse::<u64>().ok())
+                .map(|kb| kb * 1024)
+                .unwrap_or(0),
+        });
+    }
+
+    let total = processes.len();
+    processes.sort_by(|a, b| b.cpu_percent.total_cmp(&a.cpu_percent));
+    let by_cpu = processes.iter().take(limit).cloned().collect();
+    processes.sort_by_key(|p| std::cmp::Reverse(p.rss_bytes));
+    processes.truncate(limit);
+    Ok(ProcessesSummary {
+        total,
+        by_cpu,
+        by_memory: processes,
+        sample_ms: PROCESS_SAMPLE.as_millis() as u64,
+    })
+}
+
+/// Everything /proc/<pid> shows about one process that the agent may read.
+async fn process_detail(pid: u32) -> Result<ProcessDetail> {
+    let dir = format!("/proc/{}", pid);
+    let stat = fs::read_to_string(format!("{}/stat", dir))
+        .await
+        .map_err(|_| anyhow!("no such process"))?;
+    // "pid (comm) state ppid …": comm may contain spaces and parentheses.
+    let (Some(open), Some(close)) = (stat.find('('), stat.rfind(')')) else {
+        return Err(anyhow!("/proc/{}/stat: unexpected format", pid));
+    };
+    let fields: Vec<&str> = stat[close + 1..].split_whitespace().collect();
+    let status = fs::read_to_string(format!("{}/status", dir))
+        .await
+        .unwrap_or_default();
+    let uid = status
+        .lines()
+        .find_map(|l| l.strip_prefix("Uid:"))
+        .and_then(|v| v.split_whitespace().next())
+        .unwrap_or_default();
+    let users = user_names().await;
+    let nul_separated = |bytes: Vec<u8>| -> Vec<String> {
+        bytes
+            .split(|b| *b == 0)
+            .filter(|s| !s.is_empty())
+            .map(|s| String::from_utf8_lossy(s).into_owned())
+            .collect()
+    };
+
+    // Socket inodes are resolved against the process's own network namespace.
+    let mut open_files = None;
+    let mut socket_fds: HashMap<u64, u32> = HashMap::new();
+    if let Ok(mut fds) = fs::read_dir(format!("{}/fd", dir)).await {
+        let mut files = Vec::new();
+        while let Ok(Some(ent)) = fds.next_entry().await {
+            let Some(fd) = ent.file_name().to_str().and_then(|n| n.parse::<u32>().ok()) else {
+                continue;
+            };
+            let Ok(target) = fs::read_link(ent.path()).await else {
+                continue;
+            };
+            let target = target.to_string_lossy().into_owned();
+            match target
+                .strip_prefix("socket:[")
+                .and_then(|t| t.strip_suffix(']'))
+                .and_then(|t| t.parse::<u64>().ok())
+            {
+                Some(inode) => {
+                    socket_fds.insert(inode, fd);
+                }
+                None => files.push(OpenFile { fd, target }),
+            }
+        }
+        files.sort_by_key(|f| f.fd);
+        open_files = Some(files);
+    }
+    let mut sockets = process_sockets(&dir, &socket_fds).await;
+    sockets.sort_by_key(|s| s.fd);
+
+    Ok(ProcessDetail {
+        pid,
+        comm: stat[open + 1..close].to_string(),
+        user: users.get(uid).cloned().unwrap_or_else(|| uid.to_string()),
+        state: fields.first().map(|s| s.to_string()).unwrap_or_default(),
+        // nice is field 19; `fields` starts at field 3.
+        nice: fields.get(16).and_then(|n| n.parse().ok()).unwrap_or(0),
+        cmdline: fs::read(format!("{}/cmdline", dir))
+            .await
+            .map(nul_separated)
+            .unwrap_or_default(),
+        cwd: fs::read_link(format!("{}/cwd", dir))
+            .await
+            .ok()
+            .map(|p| p.to_string_lossy().into_owned()),
+        environ: fs::read(format!("{}/environ", dir))
+            .await
+            .ok()
+            .map(nul_separated),
+        open_files,
+        sockets,
+    })
+}
+
+/// The sockets among `fds` (inode -> fd), from the process's /proc/<pid>/net tables.
+async fn process_sockets(dir: &str, fds: &HashMap<u64, u32>) -> Vec<ProcessSocket> {
+    const TABLES: [(&str, &str); 4] = [
+        ("tcp", "tcp"),
+        ("tcp", "tcp6"),
+        ("udp", "udp"),
+        ("udp", "udp6"),
+    ];
+    let mut sockets = Vec::new();
+    if fds.is_empty() {
+        return sockets;
+    }
+    for (protocol, table) in TABLES {
+        let Ok(text) = fs::read_to_string(format!("{}/net/{}", dir, table)).await else {
+            continue;
+        };
+        // "sl local_address rem_address st tx_queue:rx_queue tr:tm->when retrnsmt uid timeout inode …"
+        for line in text.lines().skip(1) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 10 {
+                continue;
+            }
+            let Some(fd) = fields[9].parse::<u64>().ok().and_then(|i| fds.get(&i)) else {
+                continue;
+            };
+            let endpoint = |field: &str| {
+                parse_proc_net_address(field).map(|(address, port)| {
+                    (
+                        port,
+                        if address.contains(':') {
+                            format!("[{}]:{}", address, port)
+                        } else {
+                            format!("{}:{}", address, port)
+                        },
+                    )
+                })
+            };
+            sockets.push(ProcessSocket {
+                fd: *fd,
+                protocol: protocol.to_string(),
+                local: endpoint(fields[1]).map(|(_, e)| e).unwrap_or_default(),
+                // Listening and unconnected sockets have port 0 as their peer.
+                remote: endpoint(fields[2])
+                    .filter(|(port, _)| *port != 0)
+                    .map(|(_, e)| e),
+                state: if protocol == "tcp" {
+                    tcp_state(fields[3]).to_string()
+                } else {
+                    String::new()
+                },
+            });
+        }
+    }
+    // "Num RefCount Protocol Flags Type St Inode Path"
+    if let Ok(text) = fs::read_to_string(format!("{}/net/unix", dir)).await {
+        for line in text.lines().skip(1) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let Some(fd) = fields
+                .get(6)
+                .and_then(|i| i.parse::<u64>().ok())
+                .and_then(|i| fds.get(&i))
+            else {
+                continue;
+            };
+            sockets.push(ProcessSocket {
+                fd: *fd,
+                protocol: "unix".to_string(),
+                local: fields.get(7).map(|p| p.to_string()).unwrap_or_default(),
+                remote: None,
+                state: String::new(),
+            });
+        }
+    }
+    sockets
+}
+
+/// Name of a /proc/net/tcp state (hex, as in include/net/tcp_states.h).
+fn tcp_state(hex: &str) -> &'static str {
+    match hex {
+        "01" => "ESTABLISHED",
+        "02" => "SYN_SENT",
+        "03" => "SYN_RECV",
+        "04" => "FIN_WAIT1",
+        "05" => "FIN_WAIT2",
+        "06" => "TIME_WAIT",
+        "07" => "CLOSE",
+        "08" => "CLOSE_WAIT",
+        "09" => "LAST_ACK",
+        "0A" => "LISTEN",
+        "0B" => "CLOSING",
+        _ => "UNKNOWN",
+    }
+}
+
+/// A pid as kill(2) and setpriority(2) take it; 0 and negative values would
+/// address process groups, so they are refused.
+fn target_pid(pid: u32) -> Result<libc::pid_t> {
+    match libc::pid_t::try_from(pid) {
+        Ok(p) if p > 0 => Ok(p),
+        _ => Err(anyhow!("invalid pid {}", pid)),
+    }
+}
+
+fn signal_process(pid: u32, signal: i32) -> Result<()> {
+    let target = target_pid(pid)?;
+    // SAFETY: kill has no memory arguments.
+    if unsafe { libc::kill(target, signal) } != 0 {
+        return Err(anyhow!(
+            "kill {} (signal {}): {}",
+            pid,
+            signal,
+            std::io::Error::last_os_error()
+        ));
+    }
+    Ok(())
+}
+
+fn renice_process(pid: u32, nice: i32) -> Result<()> {
+    if !(-20..=19).contains(&nice) {
+        return Err(anyhow!("nice must be between -20 and 19, not {}", nice));
+    }
+    let target = target_pid(pid)?;
+    // SAFETY: setpriority has no memory arguments.
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, target as libc::id_t, nice) } != 0 {
+        return Err(anyhow!(
+            "renice {} to {}: {}",
+            pid,
+            nice,
+            std::io::Error::last_os_error()
+        ));
+    }
+    Ok(())
+}
+
+/// uid -> user name, from /etc/passwd.
+async fn user_names() -> HashMap<String, String> {
+    fs::read_to_string("/etc/passwd")
+        .await
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|l| {
+            let mut parts = l.split(':');
+            let name = parts.next()?;
+            let uid = parts.nth(1)?;
+            Some((uid.to_string(), name.to_string()))
+        })
+        .collect()
+}
+
+/// Mounted filesystems from /proc/mounts, sized with statvfs.
+async fn disk_usage() -> Result<Vec<MountInfo>> {
+    let text = fs::read_to_string("/proc/mounts")
+        .await
+        .map_err(|e| anyhow!("/proc/mounts: {}", e))?;
+    let mut mounts = Vec::new();
+    for line in text.lines() {
+        let f: Vec<&str> = line.split_whitespace().collect();
+        if f.len() < 3 {
+            continue;
+        }
+        let mountpoint = unescape_mount_field(f[1]);
+        let path = mountpoint.clone();
+        let stat = tokio::time::timeout(
+            STATVFS_TIMEOUT,
+            tokio::task::spawn_blocking(move || statvfs(&path)),
+        )
+        .await;
+        let Ok(Ok(Some((size_bytes, used_bytes, available_bytes)))) = stat else {
+            continue;
+        };
+        // Pseudo filesystems (proc, sysfs, cgroup, …) report no blocks.
+        if size_bytes == 0 {
+            continue;
+        }
+        mounts.push(MountInfo {
+            device: unescape_mount_field(f[0]),
+            mountpoint,
+            fstype: f[2].to_string(),
+            size_bytes,
+            used_bytes,
+            available_bytes,
+        });
+    }
+    Ok(mounts)
+}
+
+/// Values of `keys` (parameters or namespaces) from /proc/sys.
+fn sysctl_values(keys: Vec<String>) -> Vec<SysctlValue> {
+    let mut values = Vec::new();
+    for key in keys {
+        let key = key.trim_matches(['.', '/']);
+        // Keys written with slashes ("net/ipv4/conf/eth0.100/rp_filter")
+        // keep the dots that belong to a name, as with sysctl.
+        let relative = if key.contains('/') {
+            key.to_string()
+        } else {
+            key.replace('.', "/")
+        };
+        if key.is_empty()
+            || relative
+                .split('/')
+                .any(|part| part.is_empty() || part == "..")
+        {
+            values.push(SysctlValue {
+                key: key.to_string(),
+                error: Some("invalid parameter name".to_string()),
+                ..Default::default()
+            });
+            continue;
+        }
+        let path = std::path::Path::new(SYSCTL_ROOT).join(&relative);
+        if path.is_dir() {
+            let mut found = Vec::new();
+            sysctl_namespace(&path, &mut found);
+            found.sort_by(|a: &SysctlValue, b| a.key.cmp(&b.key));
+            values.extend(found);
+        } else {
+            values.push(read_sysctl(&path));
+        }
+        if values.len() >= MAX_SYSCTL_VALUES {
+            values.truncate(MAX_SYSCTL_VALUES);
+            break;
+        }
+    }
+    values
+}
+
+/// Readable parameters under `dir`, recursively; the ones that cannot be
+/// read (write-only ones such as net.ipv4.route.flush) are left out, as
+/// `sysctl -a` does.
+fn sysctl_namespace(dir: &std::path::Path, found: &mut Vec<SysctlValue>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        if found.len() >= MAX_SYSCTL_VALUES {
+            return;
+        }
+        let path = entry.path();
+        match entry.file_type() {
+            Ok(t) if t.is_dir() => sysctl_namespace(&path, found),
+            Ok(_) => {
+                let value = read_sysctl(&path);
+                if value.value.is_some() {
+                    found.push(value);
+                }
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+/// One parameter file under /proc/sys.
+fn read_sysctl(path: &std::path::Path) -> SysctlValue {
+    let key = path
+        .strip_prefix(SYSCTL_ROOT)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('/', ".");
+    match std::fs::read_to_string(path) {
+        Ok(text) => SysctlValue {
+            key,
+            value: Some(text.trim_end_matches('\n').to_string()),
+            error: None,
+        },
+        Err(e) => SysctlValue {
+            key,
+            value: None,
+            error: Some(match e.kind() {
+                std::io::ErrorKind::NotFound => "no such parameter".to_string(),
+                std::io::ErrorKind::PermissionDenied => "permission denied".to_string(),
+                _ => e.to_string(),
+            }),
+        },
+    }
+}
+
+/// Loaded kernel modules; none when /proc/modules is missing (a kernel
+/// without module support, or a container that hides it).
+async fn kernel_modules() -> Vec<KernelModule> {
+    let mut modules = fs::read_to_string("/proc/modules")
+        .await
+        .map(|text| parse_proc_modules(&text))
+        .unwrap_or_default();
+    modules.sort_by(|a, b| a.name.cmp(&b.name));
+    modules
+}
+
+/// "nf_nat 49152 1 nft_chain_nat, Live 0x0000000000000000 (OE)": name, size,
+/// use count ("-" without module unloading), dependents ("[permanent]" marks a
+/// module that cannot be unloaded), state, address and taint flags.
+fn parse_proc_modules(text: &str) -> Vec<KernelModule> {
+    text.lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let name = fields.next()?.to_string();
+            let size_bytes = fields.next()?.parse().ok()?;
+            let use_count = fields.next()?.parse().ok();
+            let used_by = fields
+                .next()?
+                .split(',')
+                .filter(|m| !m.is_empty() && *m != "-" && !m.starts_with('['))
+                .map(str::to_string)
+                .collect();
+            let state = fields.next()?.to_string();
+            // The load address (zeroed for unprivileged readers) comes first.
+            let taint = fields
+                .nth(1)
+                .map(|t| t.trim_matches(['(', ')']).to_string());
+            Some(KernelModule {
+                name,
+                size_bytes,
+                use_count,
+                used_by,
+                state,
+                taint,
+            })
+        })
+        .collect()
+}
+
+/// Firmware identity, PCI devices and block devices, from /sys.
+fn hardware_inventory() -> HardwareInventory {
+    HardwareInventory {
+        dmi: dmi_info(),
+        pci: pci_devices(),
+        block: block_devices(),
+    }
+}
+
+/// A sysfs attribute, trimmed; None when unreadable or empty.
+fn sys_attr(path: impl AsRef<std::path::Path>) -> Option<String> {
+    let text = std::fs::read_to_string(path).ok()?;
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Sorted entry names of a directory (empty when unreadable).
+fn dir_names(dir: impl AsRef<std::path::Path>) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|e| e.file_name().into_string().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+fn dmi_info() -> Option<DmiInfo> {
+    let dir = std::path::Path::new("/sys/class/dmi/id");
+    if !dir.is_dir() {
+        return None;
+    }
+    let field = |name: &str| sys_attr(dir.join(name));
+    Some(DmiInfo {
+        sys_vendor: field("sys_vendor"),
+        product_name: field("product_name"),
+        product_serial: field("product_serial"),
+        board_vendor: field("board_vendor"),
+        board_name: field("board_name"),
+        bios_vendor: field("bios_vendor"),
+        bios_version: field("bios_version"),
+        bios_date: field("bios_date"),
+    })
+}
+
+/// What a PCI class code (0xCCSSPP) names, like lspci without pci.ids.
+fn pci_class_name(class: u32) -> &'static str {
+    match class >> 8 {
+        0x0100 => "SCSI storage controller",
+        0x0101 => "IDE interface",
+        0x0104 => "RAID bus controller",
+        0x0106 => "SATA controller",
+        0x0107 => "Serial Attached SCSI controller",
+        0x0108 => "Non-Volatile memory controller",
+        0x0200 => "Ethernet controller",
+        0x0207 => "InfiniBand controller",
+        0x0280 => "Network controller",
+        0x0300 => "VGA compatible controller",
+        0x0302 => "3D controller",
+        0x0403 => "Audio device",
+        0x0600 => "Host bridge",
+        0x0601 => "ISA bridge",
+        0x0604 => "PCI bridge",
+        0x0c03 => "USB controller",
+        0x0c05 => "SMBus",
+        0x0d00..=0x0dff => "Wireless controller",
+        _ => match class >> 16 {
+            0x01 => "Mass storage controller",
+            0x02 => "Network controller",
+            0x03 => "Display controller",
+            0x04 => "Multimedia controller",
+            0x05 => "Memory controller",
+            0x06 => "Bridge",
+            0x07 => "Communication controller",
+            0x08 => "System peripheral",
+            0x0c => "Serial bus controller",
+            0x12 => "Processing accelerators",
+            _ => "Unclassified device",
+        },
+    }
+}
+
+fn pci_devices() -> Vec<PciDevice> {
+    let root = std::path::Path::new("/sys/bus/pci/devices");
+    let hex = |path: PathBuf| {
+        sys_attr(path).and_then(|v| u32::from_str_radix(v.trim_start_matches("0x"), 16).ok())
+    };
+    let mut devices: Vec<PciDevice> = dir_names(root)
+        .into_iter()
+        .map(|address| {
+            let dir = root.join(&address);
+            let class = hex(dir.join("class")).unwrap_or(0);
+            // NICs list their interfaces under net/ (virtio ones one level deeper).
+            let mut interfaces = dir_names(dir.join("net"));
+            for sub in dir_names(&dir).iter().filter(|n| n.starts_with("virtio")) {
+                interfaces.extend(dir_names(dir.join(sub).join("net")));
+            }
+            PciDevice {
+                class: pci_class_name(class).to_string(),
+                vendor_id: hex(dir.join("vendor")).unwrap_or(0) as u16,
+                device_id: hex(dir.join("device")).unwrap_or(0) as u16,
+                vendor: None,
+                device: None,
+                driver: std::fs::read_link(dir.join("driver"))
+                    .ok()
+                    .and_then(|d| Some(d.file_name()?.to_string_lossy().into_owned())),
+                interfaces,
+                address,
+            }
+        })
+        .collect();
+    pci_names(&mut devices);
+    devices
+}
+
+/// Fill in vendor and device names from pci.ids, if the host has it.
+fn pci_names(devices: &mut [PciDevice]) {
+    let Some(text) = ["/usr/share/hwdata/pci.ids", "/usr/share/misc/pci.ids"]
+        .iter()
+        .find_map(|p| std::fs::read_to_string(p).ok())
+    else {
+        return;
+    };
+    let mut names: HashMap<(u16, Option<u16>), String> = HashMap::new();
+    let mut vendor = None;
+    for line in text.lines() {
+        // Device classes follow the vendors; they are not needed.
+        if line.starts_with("C ") {
+            break;
+        }
+        let (id, name) = match line.trim_start().split_once("  ") {
+            Some((id, name)) if !line.starts_with('#') => (id, name),
+            _ => continue,
+        };
+        let Ok(id) = u16::from_str_radix(id, 16) else {
+            continue;
+        };
+        if !line.starts_with('\t') {
+            vendor = devices.iter().any(|d| d.vendor_id == id).then_some(id);
+            if vendor.is_some() {
+                names.insert((id, None), name.to_string());
+            }
+        } else if let (Some(v), false) = (vendor, line.starts_with("\t\t")) {
+            if devices
+                .iter()
+                .any(|d| d.vendor_id == v && d.device_id == id)
+            {
+                names.insert((v, Some(id)), name.to_string());
+            }
+        }
+    }
+    for d in devices {
+        d.vendor = names.get(&(d.vendor_id, None)).cloned();
+        d.device = names.get(&(d.vendor_id, Some(d.device_id))).cloned();
+    }
+}
+
+/// Top-level block devices (those not stacked on another) as trees.
+fn block_devices() -> Vec<BlockDevice> {
+    let mut mounts: HashMap<String, String> = HashMap::new();
+    for line in std::fs::read_to_string("/proc/mounts")
+        .unwrap_or_default()
+        .lines()
+    {
+        let mut f = line.split_whitespace();
+        let (Some(source), Some(mountpoint)) = (f.next(), f.next()) else {
+            continue;
+        };
+        // /dev/mapper/vg-root is a link to /dev/dm-0.
+        let Some(name) = std::fs::canonicalize(unescape_mount_field(source))
+            .ok()
+            .filter(|p| p.starts_with("/dev"))
+            .and_then(|p| Some(p.file_name()?.to_string_lossy().into_owned()))
+        else {
+            continue;
+        };
+        mounts
+            .entry(name)
+            .or_insert_with(|| unescape_mount_field(mountpoint));
+    }
+    let root = std::path::Path::new("/sys/block");
+    dir_names(root)
+        .into_iter()
+        .filter(|name| dir_names(root.join(name).join("slaves")).is_empty())
+        .filter_map(|name| block_device(&root.join(&name), name, false, &mounts))
+        .collect()
+}
+
+fn block_device(
+    dir: &std::path::Path,
+    name: String,
+    partition: bool,
+    mounts: &HashMap<String, String>,
+) -> Option<BlockDevice> {
+    let size_bytes = sys_attr(dir.join("size"))?.parse::<u64>().ok()? * SECTOR_BYTES;
+    let kind = if partition {
+        "part"
+    } else if name.starts_with("dm-") {
+        "lvm"
+    } else if name.starts_with("md") {
+        "raid"
+    } else if name.starts_with("loop") {
+        "loop"
+    } else if name.starts_with("sr") {
+        "rom"
+    } else {
+        "disk"
+    };
+    if kind == "loop" && size_bytes == 0 {
+        return None;
+    }
+    let mut children: Vec<BlockDevice> = dir_names(dir)
+        .into_iter()
+        .filter(|n| dir.join(n).join("partition").exists())
+        .filter_map(|n| block_device(&dir.join(&n), n, true, mounts))
+        .collect();
+    children.extend(
+        dir_names(dir.join("holders"))
+            .into_iter()
+            .filter_map(|n| block_device(&PathBuf::from("/sys/block").join(&n), n, false, mounts)),
+    );
+    Some(BlockDevice {
+        kind: kind.to_string(),
+        size_bytes,
+        model: sys_attr(dir.join("device/model")),
+        serial: sys_attr(dir.join("device/serial")).or_else(|| sys_attr(dir.join("serial"))),
+        rotational: sys_attr(dir.join("queue/rotational")).as_deref() == Some("1"),
+        removable: sys_attr(dir.join("removable")).as_deref() == Some("1"),
+        mountpoint: mounts.get(&name).cloned(),
+        children,
+        name,
+    })
+}
+
+/// What the sensors of a hwmon chip measure, by its driver name.
+fn sensor_kind(chip: &str) -> SensorKind {
+    match chip {
+        "coretemp" | "k10temp" | "k8temp" | "zenpower" | "cpu_thermal" | "via_cputemp" => {
+            SensorKind::Cpu
+        }
+        "amdgpu" | "radeon" | "nouveau" | "i915" | "xe" => SensorKind::Gpu,
+        "nvme" => SensorKind::Nvme,
+        "drivetemp" => SensorKind::Disk,
+        _ => SensorKind::Other,
+    }
+}
+
+/// Temperatures and fan speeds from /sys/class/hwmon, as `sensors` reads them.
+fn hwmon_readings() -> SensorReadings {
+    let root = std::path::Path::new("/sys/class/hwmon");
+    let mut readings = SensorReadings::default();
+    for entry in dir_names(root) {
+        let dir = root.join(&entry);
+        // Drivers older than 3.x keep the attributes on the device.
+        let attrs = if dir.join("name").exists() {
+            dir.clone()
+        } else {
+            dir.join("device")
+        };
+        let Some(chip) = sys_attr(attrs.join("name")) else {
+            continue;
+        };
+        let device = std::fs::read_link(dir.join("device"))
+            .ok()
+            .and_then(|d| Some(d.file_name()?.to_string_lossy().into_owned()));
+        let number = |path: String| sys_attr(attrs.join(path))?.parse::<i64>().ok();
+        // "temp2_input" → 2, in channel order (temp10 after temp9)
+        let channels = |prefix: &str| {
+            let mut channels: Vec<u32> = dir_names(&attrs)
+                .iter()
+                .filter_map(|n| n.strip_prefix(prefix)?.strip_suffix("_input")?.parse().ok())
+                .collect();
+            channels.sort_unstable();
+            channels
+        };
+        for n in channels("temp") {
+            // Unreadable (a sensor that is not wired up) or faulted.
+            let Some(milli) = number(format!("temp{}_input", n)) else {
+                continue;
+            };
+            let limit = |name: &str| {
+                number(format!("temp{}_{}", n, name))
+                    .filter(|&m| m > 0)
+                    .map(|m| m as f32 / 1000.0)
+            };
+            readings.temperatures.push(Temperature {
+                chip: chip.clone(),
+                device: device.clone(),
+                label: sys_attr(attrs.join(format!("temp{}_label", n)))
+                    .unwrap_or_else(|| format!("temp{}", n)),
+                kind: sensor_kind(&chip),
+                celsius: milli as f32 / 1000.0,
+                high: limit("max"),
+                critical: limit("crit"),
+            });
+        }
+        for n in channels("fan") {
+            let Some(rpm) = number(format!("fan{}_input", n)) else {
+                continue;
+            };
+            readings.fans.push(Fan {
+                chip: chip.clone(),
+                label: sys_attr(attrs.join(format!("fan{}_label", n)))
+                    .unwrap_or_else(|| format!("fan{}", n)),
+                rpm: rpm.max(0) as u32,
+                min_rpm: number(format!("fan{}_min", n))
+                    .filter(|&m| m > 0)
+                    .map(|m| m as u32),
+            });
+        }
+    }
+    readings
+}
+
+/// GPU temperatures from nvidia-smi: the proprietary driver has no hwmon
+/// chip. Empty without it.
+async fn nvidia_temperatures() -> Vec<Temperature> {
+    let Ok(out) = tool_output(
+        "nvidia-smi",
+        &[
+            "--query-gpu=pci.bus_id,name,temperature.gpu,temperature.gpu.tlimit",
+            "--format=csv,noheader,nounits",
+        ],
+        &[0],
+    )
+    .await
+    else {
+        return Vec::new();
+    };
+    // "00000000:01:00.0, NVIDIA GeForce RTX 3080, 47, 40"
+    out.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(", ").map(str::trim).collect();
+            let celsius = fields.get(2)?.parse::<f32>().ok()?;
+            // Degrees below the slowdown threshold, where the driver reports it
+            let high = fields
+                .get(3)
+                .and_then(|t| t.parse::<f32>().ok())
+                .map(|t| celsius + t);
+            Some(Temperature {
+                chip: "nvidia".to_string(),
+                device: fields.first().map(|b| b.to_lowercase()),
+                label: fields.get(1)?.to_string(),
+                kind: SensorKind::Gpu,
+                celsius,
+                high,
+                critical: None,
+            })
+        })
+        .collect()
+}
+
+/// GPUs among the PCI devices, with what nvidia-smi (NVIDIA) and amdgpu's
+/// sysfs files (AMD) add; Intel GPUs carry their PCI identity and driver.
+async fn gpu_info() -> Result<Vec<Gpu>> {
+    let mut gpus: Vec<Gpu> = tokio::task::spawn_blocking(|| {
+        pci_devices()
+            .into_iter()
+            .filter(|d| {
+                matches!(
+                    d.class.as_str(),
+                    "VGA compatible controller" | "3D controller" | "Display controller"
+                )
+            })
+            .map(|d| {
+                let dir = std::path::Path::new("/sys/bus/pci/devices").join(&d.address);
+                let number = |name: &str| sys_attr(dir.join(name))?.parse::<u64>().ok();
+                let amdgpu = d.driver.as_deref() == Some("amdgpu");
+                Gpu {
+                    vendor: match d.vendor_id {
+                        0x10de => GpuVendor::Nvidia,
+                        0x1002 => GpuVendor::Amd,
+                        0x8086 => GpuVendor::Intel,
+                        _ => GpuVendor::Other,
+                    },
+                    model: d
+                        .device
+                        .clone()
+                        .unwrap_or_else(|| format!("{:04x}:{:04x}", d.vendor_id, d.device_id)),
+                    // Out-of-tree drivers (nvidia, DKMS amdgpu) carry a version.
+                    driver_version: d
+                        .driver
+                        .as_ref()
+                        .and_then(|m| sys_attr(format!("/sys/module/{}/version", m))),
+                    vram_total_bytes: number("mem_info_vram_total").filter(|_| amdgpu),
+                    vram_used_bytes: number("mem_info_vram_used").filter(|_| amdgpu),
+                    utilization_percent: number("gpu_busy_percent")
+                        .filter(|_| amdgpu)
+                        .map(|p| p as f32),
+                    driver: d.driver,
+                    address: d.address,
+                }
+            })
+            .collect()
+    })
+    .await?;
+    if gpus.iter().any(|g| g.vendor == GpuVendor::Nvidia) {
+        nvidia_gpu_details(&mut gpus).await;
+    }
+    if gpus
+        .iter()
+        .any(|g| g.driver.as_deref() == Some("amdgpu") && g.driver_version.is_none())
+    {
+        // In-tree amdgpu has no module version; ROCm reports the one it runs on.
+        if let Ok(out) = tool_output("rocm-smi", &["--showdriverversion"], &[0]).await {
+            let version = out.lines().find_map(|line| {
+                let (key, value) = line.split_once(':')?;
+                key.to_lowercase()
+                    .contains("driver version")
+                    .then(|| value.trim().to_string())
+            });
+            for gpu in gpus
+                .iter_mut()
+                .filter(|g| g.driver.as_deref() == Some("amdgpu"))
+            {
+                gpu.driver_version = gpu.driver_version.take().or_else(|| version.clone());
+            }
+        }
+    }
+    Ok(gpus)
+}
+
+/// Model, memory, utilization and driver version of NVIDIA GPUs from
+/// nvidia-smi, matched to `gpus` by PCI address; left as they are without it.
+async fn nvidia_gpu_details(gpus: &mut [Gpu]) {
+    let Ok(out) = tool_output(
+        "nvidia-smi",
+        &[
+            "--query-gpu=pci.bus_id,name,memory.total,memory.used,utilization.gpu,driver_version",
+            "--format=csv,noheader,nounits",
+        ],
+        &[0],
+    )
+    .await
+    else {
+        return;
+    };
+    // "00000000:01:00.0, NVIDIA GeForce RTX 3080, 10240, 912, 37, 550.54.14"
+    // (memory in MiB; "[N/A]" where a GPU does not report a value)
+    for line in out.lines() {
+        let fields: Vec<&str> = line.split(", ").map(str::trim).collect();
+        let [bus_id, name, total, used, busy, version] = fields[..] else {
+            continue;
+        };
+        // nvidia-smi prints an eight-digit PCI domain.
+        let address = bus_id.to_lowercase();
+        let address = address
+            .get(address.len().saturating_sub(12)..)
+            .unwrap_or("");
+        let Some(gpu) = gpus.iter_mut().find(|g| g.address == address) else {
+            continue;
+        };
+        let mib = |v: &str| v.parse::<u64>().ok().map(|m| m << 20);
+        gpu.model = name.to_string();
+        gpu.vram_total_bytes = mib(total);
+        gpu.vram_used_bytes = mib(used);
+        gpu.utilization_percent = busy.parse().ok();
+        gpu.driver_version = Some(version.to_string());
+    }
+}
+
+/// The package manager whose database is on this host, found by its files
+/// rather than its tools (rpm, for one, installs fine on Debian).
+fn package_manager() -> Option<PackageManager> {
+    let exists = |path: &str| std::path::Path::new(path).exists();
+    if exists("/var/lib/dpkg/status") {
+        Some(PackageManager::Dpkg)
+    } else if exists("/var/lib/rpm") || exists("/usr/lib/sysimage/rpm") {
+        Some(PackageManager::Rpm)
+    } else if exists("/var/lib/pacman/local") {
+        Some(PackageManager::Pacman)
+    } else if exists("/lib/apk/db/installed") {
+        Some(PackageManager::Apk)
+    } else {
+        None
+    }
+}
+
+/// Packages installed through `manager`, by name (and architecture).
+async fn installed_packages(manager: PackageManager) -> Result<Vec<Package>> {
+    let package = |name: &str, version: &str, arch: Option<&str>| Package {
+        name: name.to_string(),
+        version: version.to_string(),
+        arch: arch
+            .filter(|a| !a.is_empty() && *a != "(none)")
+            .map(str::to_string),
+    };
+    let mut packages: Vec<Package> = match manager {
+        PackageManager::Dpkg => {
+            let out = tool_output(
+                "dpkg-query",
+                &[
+                    "--show",
+                    "--showformat=${db:Status-Abbrev}\t${Package}\t${Version}\t${Architecture}\n",
+                ],
+                &[0],
+            )
+            .await?;
+            out.lines()
+                .filter_map(|line| {
+                    let mut fields = line.split('\t');
+                    // "ii ": wanted and installed; removed ones keep their config ("rc ").
+                    let status = fields.next()?;
+                    if status.as_bytes().get(1) != Some(&b'i') {
+                        return None;
+                    }
+                    let (name, version) = (fields.next()?, fields.next()?);
+                    Some(package(name, version, fields.next()))
+                })
+                .collect()
+        }
+        PackageManager::Rpm => {
+            let out = tool_output(
+                "rpm",
+                &[
+                    "-qa",
+                    "--queryformat",
+                    "%{NAME}\t%{VERSION}-%{RELEASE}\t%{ARCH}\n",
+                ],
+                &[0],
+            )
+            .await?;
+            out.lines()
+                .filter_map(|line| {
+                    let mut fields = line.split('\t');
+                    let (name, version) = (fields.next()?, fields.next()?);
+                    Some(package(name, version, fields.next()))
+                })
+                .collect()
+        }
+        PackageManager::Pacman => {
+            let out = tool_output("pacman", &["-Q"], &[0]).await?;
+            out.lines()
+                .filter_map(|line| {
+                    let (name, version) = line.split_once(' ')?;
+                    Some(package(name, version, None))
+                })
+                .collect()
+        }
+        PackageManager::Apk => {
+            // One "P:name", "V:version", "A:arch" block per package.
+            let db = fs::read_to_string("/lib/apk/db/installed").await?;
+            db.split("\n\n")
+                .filter_map(|block| {
+                    let field = |key: &str| {
+                        block
+                            .lines()
+                            .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+                    };
+                    Some(package(field("P")?, field("V")?, field("A")))
+                })
+                .collect()
+        }
+        PackageManager::Unknown => Vec::new(),
+    };
+    packages.sort_by(|a, b| (&a.name, &a.arch).cmp(&(&b.name, &b.arch)));
+    Ok(packages)
+}
+
+/// Packages the dry-run check of `manager` would update, by name. Checks run
+/// against the metadata the host last downloaded; none is refreshed here.
+async fn pending_updates(manager: PackageManager) -> Result<Vec<PackageUpdate>> {
+    let update = |name: &str, current: Option<&str>, available: &str| PackageUpdate {
+        name: name.to_string(),
+        current: current.map(str::to_string),
+        available: available.to_string(),
+    };
+    let mut updates: Vec<PackageUpdate> = match manager {
+        PackageManager::Dpkg => {
+            // "bash/stable 5.2.15-2+b2 amd64 [upgradable from: 5.2.15-2+b1]"
+            let out = tool_output("apt", &["list", "--upgradable"], &[0]).await?;
+            out.lines()
+                .filter_map(|line| {
+                    let (name, rest) = line.split_once('/')?;
+                    let available = rest.split_whitespace().nth(1)?;
+                    let current = rest
+                        .split_once("[upgradable from: ")
+                        .and_then(|(_, c)| c.strip_suffix(']'));
+                    Some(update(name, current, available))
+                })
+                .collect()
+        }
+        PackageManager::Rpm => {
+            // "kernel.x86_64  6.8.9-300.fc40  updates"; exit status 100 when
+            // there are updates.
+            let args = ["check-update", "--quiet"];
+            let out = match tool_output("dnf", &args, &[0, 100]).await {
+                Ok(out) => out,
+                Err(_) => tool_output("yum", &args, &[0, 100]).await?,
+            };
+            out.lines()
+                .take_while(|line| !line.starts_with("Obsoleting"))
+                .filter_map(|line| {
+                    let fields: Vec<&str> = line.split_whitespace().collect();
+                    let [name, available, _repo] = fields[..] else {
+                        return None;
+                    };
+                    Some(update(name, None, available))
+                })
+                .collect()
+        }
+        PackageManager::Pacman => {
+            // "linux 6.8.9.arch1-1 -> 6.9.1.arch1-1"; exit status 1 when none.
+            let out = tool_output("pacman", &["-Qu"], &[0, 1]).await?;
+            out.lines()
+                .filter_map(|line| {
+                    let fields: Vec<&str> = line.split_whitespace().collect();
+                    let [name, current, "->", available, ..] = fields[..] else {
+                        return None;
+                    };
+                    Some(update(name, Some(current), available))
+                })
+                .collect()
+        }
+        PackageManager::Apk => {
+            // "busybox-1.36.1-r2  < 1.36.1-r5"
+            let out = tool_output("apk", &["version", "-l", "<"], &[0]).await?;
+            out.lines()
+                .filter_map(|line| {
+                    let fields: Vec<&str> = line.split_whitespace().collect();
+                    let [pkgver, "<", available] = fields[..] else {
+                        return None;
+                    };
+                    let (name, current) = apk_name_version(pkgver)?;
+                    Some(update(name, Some(current), available))
+                })
+                .collect()
+        }
+        PackageManager::Unknown => Vec::new(),
+    };
+    updates.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(updates)
+}
+
+/// "py3-six-1.16.0-r9" as ("py3-six", "1.16.0-r9"): the version is the last
+/// two dash-separated parts (names may contain dashes, versions do not
+/// besides the release).
+fn apk_name_version(pkgver: &str) -> Option<(&str, &str)> {
+    let (rest, _release) = pkgver.rsplit_once('-')?;
+    let (name, _) = rest.rsplit_once('-')?;
+    Some((name, &pkgver[name.len() + 1..]))
+}
+
+/// Stdout of `program`, which must exit with one of `ok_codes`.
+async fn tool_output(program: &str, args: &[&str], ok_codes: &[i32]) -> Result<String> {
+    let out = TokioCommand::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| anyhow!("failed to run {}: {}", program, e))?;
+    if !out
+        .status
+        .code()
+        .is_some_and(|code| ok_codes.contains(&code))
+    {
+        return Err(anyhow!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&out.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+/// Undo the octal escapes /proc/mounts uses for space, tab, newline and backslash.
+fn unescape_mount_field(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && i + 4 <= bytes.len() {
+            let digits = std::str::from_utf8(&bytes[i + 1..i + 4]).unwrap_or("");
+            if let Ok(c) = u8::from_str_radix(digits, 8) {
+                out.push(c);
+                i += 4;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// (size, used, available) bytes of the filesystem at `path`.
+fn statvfs(path: &str) -> Option<(u64, u64, u64)> {
+    let path = std::ffi::CString::new(path).ok()?;
+    let mut st: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `path` is NUL-terminated and `st` is a valid out-pointer.
+    if unsafe { libc::statvfs(path.as_ptr(), &mut st) } != 0 {
+        return None;
+    }
+    let frsize = st.f_frsize as u64;
+    let size = st.f_blocks as u64 * frsize;
+    let free = st.f_bfree as u64 * frsize;
+    Some((size, size.saturating_sub(free), st.f_bavail as u64 * frsize))
+}
+
+/// Cumulative counters read once; two of these make a `MetricsSample`.
+struct Counters {
+    /// (busy, total) ticks per core
+    cores: Vec<(u64, u64)>,
+    /// name -> (read, written) bytes
+    disks: Vec<(String, u64, u64)>,
+    /// name -> (received, transmitted) bytes
+    networks: Vec<(String, u64, u64)>,
+}
+
+async fn read_counters() -> Result<Counters> {
+    let stat = fs::read_to_string("/proc/stat")
+        .await
+        .map_err(|e| anyhow!("/proc/stat: {}", e))?;
+    let cores = parse_core_ticks(&stat);
+
+    let mut disks = Vec::new();
+    for l in fs::read_to_string("/proc/diskstats")
+        .await
+        .unwrap_or_default()
+        .lines()
+    {
+        let f: Vec<&str> = l.split_whitespace().collect();
+        if f.len() < 10 {
+            continue;
+        }
+        let name = f[2];
+        // Whole disks have a /sys/block entry; partitions don't.
+        if name.starts_with("loop")
+            || name.starts_with("ram")
+            || fs::metadata(format!("/sys/block/{}", name)).await.is_err()
+        {
+            continue;
+        }
+        let sectors = |i: usize| f[i].parse::<u64>().unwrap_or(0) * SECTOR_BYTES;
+        disks.push((name.to_string(), sectors(5), sectors(9)));
+    }
+
+    let networks = parse_net_dev(
+        &fs::read_to_string("/proc/net/dev")
+            .await
+            .unwrap_or_default(),
+    );
+
+    Ok(Counters {
+        cores,
+        disks,
+        networks,
+    })
+}
+
+/// (busy, total) ticks of each core's "cpuN" line in /proc/stat.
+fn parse_core_ticks(stat: &str) -> Vec<(u64, u64)> {
+    stat.lines()
+        .filter(|l| l.starts_with("cpu") && !l.starts_with("cpu "))
+        .map(|l| {
+            // user nice system idle iowait irq softirq steal (guest time is
+            // already in user and nice)
+            let v: Vec<u64> = l
+                .split_whitespace()
+                .skip(1)
+                .take(8)
+                .filter_map(|f| f.parse().ok())
+                .collect();
+            let total: u64 = v.iter().sum();
+            let idle = v.get(3).copied().unwrap_or(0) + v.get(4).copied().unwrap_or(0);
+            (total.saturating_sub(idle), total)
+        })
+        .collect()
+}
+
+/// (name, received, transmitted) bytes of each interface but loopback in
+/// /proc/net/dev.
+fn parse_net_dev(text: &str) -> Vec<(String, u64, u64)> {
+    let mut networks = Vec::new();
+    // Two header lines, then "name: rx_bytes … (8 fields) tx_bytes …"
+    for l in text.lines().skip(2) {
+        let Some((name, rest)) = l.split_once(':') else {
+            continue;
+        };
+        let name = name.trim();
+        if name == "lo" {
+            continue;
+        }
+        let f: Vec<u64> = rest
+            .split_whitespace()
+            .filter_map(|v| v.parse().ok())
+            .collect();
+        if f.len() < 9 {
+            continue;
+        }
+        networks.push((name.to_string(), f[0], f[8]));
+    }
+    networks
+}
+
+/// CPU, memory, load and IO, with utilisation and rates over `interval_ms`.
+async fn metrics_sample(interval_ms: u64) -> Result<MetricsSample> {
+    let before = read_counters().await?;
+    tokio::time::sleep(std::time::Duration::from_millis(interval_ms)).await;
+    let after = read_counters().await?;
+    Ok(metrics_between(&before, &after, interval_ms).await)
+}
+
+/// Metrics from two sets of counters read `interval_ms` apart.
+async fn metrics_between(before: &Counters, after: &Counters, interval_ms: u64) -> MetricsSample {
+    let secs = interval_ms.max(1) as f64 / 1000.0;
+
+    let cpu_percent = after
+        .cores
+        .iter()
+        .zip(&before.cores)
+        .map(|(&(busy, total), &(busy0, total0))| {
+            let total = total.saturating_sub(total0);
+            if total == 0 {
+                0.0
+            } else {
+                busy.saturating_sub(busy0) as f32 * 100.0 / total as f32
+            }
+        })
+        .collect();
+
+    // Devices that appeared during the interval count from zero.
+    let rate = |now: u64, then: Option<u64>| now.saturating_sub(then.unwrap_or(now)) as f64 / secs;
+    let disks = after
+        .disks
+        .iter()
+        .map(|(name, read, written)| {
+            let prev = before.disks.iter().find(|d| &d.0 == name);
+            DiskIo {
+                name: name.clone(),
+                read_bytes: *read,
+                written_bytes: *written,
+                read_bytes_per_sec: rate(*read, prev.map(|d| d.1)),
+                write_bytes_per_sec: rate(*written, prev.map(|d| d.2)),
+            }
+        })
+        .collect();
+    let networks = after
+        .networks
+        .iter()
+        .map(|(name, rx, tx)| {
+            let prev = before.networks.iter().find(|n| &n.0 == name);
+            NetIo {
+                name: name.clone(),
+                rx_bytes: *rx,
+                tx_bytes: *tx,
+                rx_bytes_per_sec: rate(*rx, prev.map(|n| n.1)),
+                tx_bytes_per_sec: rate(*tx, prev.map(|n| n.2)),
+            }
+        })
+        .collect();
+
+    let meminfo = fs::read_to_string("/proc/meminfo")
+        .await
+        .unwrap_or_default();
+    let mem = |key: &str| meminfo_bytes(&meminfo, key);
+
+    MetricsSample {
+        interval_ms,
+        cpu_percent,
+        mem_total_bytes: mem("MemTotal:"),
+        mem_available_bytes: mem("MemAvailable:"),
+        swap_total_bytes: mem("SwapTotal:"),
+        swap_free_bytes: mem("SwapFree:"),
+        load_avg: load_avg().await,
+        disks,
+        networks,
+    }
+}
+
+/// The value of `key` ("MemTotal:") in /proc/meminfo, in bytes; 0 when missing.
+fn meminfo_bytes(meminfo: &str, key: &str) -> u64 {
+    // /proc/meminfo values are in kB.
+    meminfo
+        .lines()
+        .find_map(|l| l.strip_prefix(key))
+        .and_then(|v| v.split_whitespace().next())
+        .and_then(|kb| kb.parse::<u64>().ok())
+        .map(|kb| kb * 1024)
+        .unwrap_or(0)
+}
+
+// Baseline filtering is handled on the client UI; no remote filtering or config required.
+
+/// The cgroup v2 hierarchy, with CPU use measured over `PROCESS_SAMPLE`.
+async fn cgroup_tree() -> Result<CgroupNode> {
+    let root = CGROUP_ROOTS
+        .iter()
+        .map(PathBuf::from)
+        .find(|r| r.join("cgroup.controllers").exists())
+        .ok_or_else(|| anyhow!("no cgroup v2 hierarchy (cgroup v1 only)"))?;
+    let before = tokio::task::spawn_blocking({
+        let root = root.clone();
+        move || {
+            let mut usage = HashMap::new();
+            cgroup_cpu_usage(&root, &mut usage);
+            usage
+        }
+    })
+    .await?;
+    let started = std::time::Instant::now();
+    tokio::time::sleep(PROCESS_SAMPLE).await;
+    Ok(tokio::task::spawn_blocking(move || {
+        let elapsed_usec = started.elapsed().as_micros().max(1) as f32;
+        cgroup_node(&root, &root, &before, elapsed_usec)
+    })
+    .await?)
+}
+
+/// `usage_usec` of cpu.stat for `dir` and every cgroup below it.
+fn cgroup_cpu_usage(dir: &std::path::Path, usage: &mut HashMap<PathBuf, u64>) {
+    if let Some(usec) = cgroup_usage_usec(dir) {
+        usage.insert(dir.to_path_buf(), usec);
+    }
+    for child in cgroup_children(dir) {
+        cgroup_cpu_usage(&child, usage);
+    }
+}
+
+fn cgroup_usage_usec(dir: &std::path::Path) -> Option<u64> {
+    std::fs::read_to_string(dir.join("cpu.stat"))
+        .ok()?
+        .lines()
+        .find_map(|l| l.strip_prefix("usage_usec "))
+        .and_then(|v| v.trim().parse().ok())
+}
+
+/// Child cgroups of `dir` (its subdirectories), sorted by name.
+fn cgroup_children(dir: &std::path::Path) -> Vec<PathBuf> {
+    let mut children: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+                .map(|e| e.path())
+                .collect()
+        })
+        .unwrap_or_default();
+    children.sort();
+    children
+}
+
+/// The cgroup at `dir` and its descendants; CPU use is the change since `before`.
+fn cgroup_node(
+    root: &std::path::Path,
+    dir: &std::path::Path,
+    before: &HashMap<PathBuf, u64>,
+    elapsed_usec: f32,
+) -> CgroupNode {
+    let path = dir
+        .strip_prefix(root)
+        .map(|p| format!("/{}", p.display()))
+        .unwrap_or_default();
+    // Cgroups created during the interval have no earlier sample.
+    let cpu_percent = cgroup_usage_usec(dir)
+        .zip(before.get(dir))
+        .map(|(after, before)| after.saturating_sub(*before) as f32 * 100.0 / elapsed_usec);
+    CgroupNode {
+        name: dir
+            .file_name()
+            .filter(|_| path != "/")
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "/".to_string()),
+        cpu_percent,
+        memory_bytes: std::fs::read_to_string(dir.join("memory.current"))
+            .ok()
+            .and_then(|m| m.trim().parse().ok()),
+        processes: std::fs::read_to_string(dir.join("cgroup.procs"))
+            .map(|p| p.lines().count())
+            .unwrap_or(0),
+        children: cgroup_children(dir)
+            .iter()
+            .map(|child| cgroup_node(root, child, before, elapsed_usec))
+            .collect(),
+        path,
+    }
+}
+
+/// /proc/pressure/<resource>, None when the kernel has no PSI.
+async fn pressure_stall(resource: &str) -> Option<PressureStall> {
+    let text = fs::read_to_string(format!("/proc/pressure/{}", resource))
+        .await
+        .ok()?;
+    parse_pressure(&text)
+}
+
+/// The "some" and "full" lines of a /proc/pressure file.
+fn parse_pressure(text: &str) -> Option<PressureStall> {
+    let mut stall = PressureStall::default();
+    let mut found = false;
+    // some avg10=0.00 avg60=0.00 avg300=0.00 total=0
+    for line in text.lines() {
+        let mut fields = line.split_whitespace();
+        let kind = fields.next();
+        let mut averages = PressureAverages::default();
+        for field in fields {
+            match field.split_once('=') {
+                Some(("avg10", v)) => averages.avg10 = v.parse().unwrap_or(0.0),
+                Some(("avg60", v)) => averages.avg60 = v.parse().unwrap_or(0.0),
+                Some(("avg300", v)) => averages.avg300 = v.parse().unwrap_or(0.0),
+                Some(("total", v)) => averages.total_usec = v.parse().unwrap_or(0),
+                _ => {}
+            }
+        }
+        match kind {
+            Some("some") => {
+                stall.some = averages;
+                found = true;
+            }
+            Some("full") => stall.full = Some(averages),
+            _ => {}
+        }
+    }
+    found.then_some(stall)
+}
+
+/// OOM kills in the kernel messages of the current boot (from the journal).
+async fn oom_kills() -> Option<Vec<OomKill>> {
+    let out = journalctl(None)
+        .arg("--dmesg")
+        .arg(format!("--lines={}", MAX_JOURNAL_LINES))
+        .output()
+        .await
+        .ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    Some(
+        String::from_utf8_lossy(&out.stdout)
+            .lines()
+            .filter_map(parse_journal_line)
+            .filter_map(|(entry, _)| parse_oom_kill(entry))
+            .collect(),
+    )
+}
+
+/// "Out of memory: Killed process 4242 (java) total-vm:..." (also "Memory
+/// cgroup out of memory: ..."), None for other kernel messages.
+fn parse_oom_kill(entry: JournalEntry) -> Option<OomKill> {
+    let (_, rest) = entry.message.split_once("Killed process ")?;
+    let (pid, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+    let comm = rest
+        .strip_prefix('(')
+        .and_then(|r| r.split_once(')'))
+        .map(|(comm, _)| comm.to_string());
+    Some(OomKill {
+        realtime_usec: entry.realtime_usec,
+        pid: pid.parse().ok(),
+        comm,
+        message: entry.message,
+    })
+}
+
+/// Arrays of /proc/mdstat (empty without the md driver).
+async fn mdstat_arrays() -> Vec<RaidArray> {
+    match fs::read_to_string("/proc/mdstat").await {
+        Ok(text) => parse_mdstat(&text),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Arrays of /proc/mdstat with the array and member states of `mdadm
+/// --detail`, where that runs (it needs root and the mdadm package).
+async fn raid_arrays() -> Vec<RaidArray> {
+    let mut arrays = mdstat_arrays().await;
+    for array in &mut arrays {
+        let device = format!("/dev/{}", array.name);
+        if let Ok(detail) = tool_output("mdadm", &["--detail", &device], &[0]).await {
+            apply_mdadm_detail(array, &detail);
+        }
+    }
+    arrays
+}
+
+/// `mdadm --detail`: "State : clean, degraded, recovering", "UUID : ...",
+/// "Rebuild Status : 8% complete", then a device table whose rows end in
+/// "spare rebuilding   /dev/sdb1".
+fn apply_mdadm_detail(array: &mut RaidArray, detail: &str) {
+    let mut in_table = false;
+    for line in detail.lines() {
+        let line = line.trim();
+        if in_table {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            // Number, Major, Minor, RaidDevice, state words, device
+            let Some(device) = words.last().and_then(|w| w.strip_prefix("/dev/")) else {
+                continue;
+            };
+            if words.len() < 6 {
+                continue;
+            }
+            let state = words[4..words.len() - 1].join(" ");
+            if let Some(member) = array.members.iter_mut().find(|m| m.device == device) {
+                member.faulty |= state.contains("faulty");
+                member.spare |= state.contains("spare");
+                member.rebuilding = state.contains("rebuilding");
+                member.state = Some(state);
+            }
+        } else if line.starts_with("Number") && line.contains("RaidDevice") {
+            in_table = true;
+        } else if let Some((key, value)) = line.split_once(" : ") {
+            let value = value.trim();
+            match key.trim() {
+                "State" => array.state = Some(value.to_string()),
+                "UUID" => array.uuid = Some(value.to_string()),
+                // mdstat's progress line is more precise; this covers its absence.
+                key if array.sync.is_none() => {
+                    let action = match key {
+                        "Rebuild Status" => "recovery",
+                        "Resync Status" => "resync",
+                        "Reshape Status" => "reshape",
+                        "Check Status" => "check",
+                        _ => continue,
+                    };
+                    array.sync = Some(RaidSync {
+                        action: action.to_string(),
+                        percent: value
+                            .split_whitespace()
+                            .next()
+                            .and_then(|p| p.strip_suffix('%'))
+                            .and_then(|p| p.parse().ok()),
+                        ..Default::default()
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+    array.degraded |= array.members.iter().any(|m| m.faulty)
+        || array
+            .state
+            .as_deref()
+            .is_some_and(|state| state.contains("degraded"));
+}
+
+/// /proc/mdstat: per array a "md0 : active raid1 sdb1[1] sda1[0]" line, then
+/// indented lines with its size and "[2/1] [U_]" health, and the progress of
+/// a rebuild.
+fn parse_mdstat(text: &str) -> Vec<RaidArray> {
+    let mut arrays: Vec<RaidArray> = Vec::new();
+    for line in text.lines() {
+        if let Some((name, rest)) = line.split_once(" : ") {
+            if name.starts_with("md") {
+                arrays.push(parse_md_array(name, rest));
+                continue;
+            }
+        }
+        let Some(array) = arrays.last_mut() else {
+            continue;
+        };
+        let line = line.trim();
+        if let Some((blocks, rest)) = line.split_once(" blocks") {
+            // 1K blocks
+            array.size_bytes = blocks.parse::<u64>().unwrap_or(0) * 1024;
+            let counts = rest.split_whitespace().find_map(|word| {
+                let (devices, working) =
+                    word.strip_prefix('[')?.strip_suffix(']')?.split_once('/')?;
+                Some((devices.parse().ok()?, working.parse().ok()?))
+            });
+            if let Some((devices, working)) = counts {
+                array.devices = devices;
+                array.working = working;
+            }
+        } else if let Some(sync) = parse_md_sync(line) {
+            array.sync = Some(sync);
+        }
+    }
+    for array in &mut arrays {
+        array.degraded = array.working < array.devices || array.members.iter().any(|m| m.faulty);
+    }
+    arrays
+}
+
+/// "active (auto-read-only) raid1 sdb1[1] sda1[0](F)" after "md0 : ".
+fn parse_md_array(name: &str, rest: &str) -> RaidArray {
+    let mut array = RaidArray {
+        name: name.to_string(),
+        ..Default::default()
+    };
+    for word in rest.split_whitespace() {
+        match word {
+            "active" => array.active = true,
+            "inactive" => {}
+            "(read-only)" | "(auto-read-only)" => array.read_only = true,
+            member if member.contains('[') => {
+                let Some((device, flags)) = member.split_once('[') else {
+                    continue;
+                };
+                let Some((slot, flags)) = flags.split_once(']') else {
+                    continue;
+                };
+                array.members.push(RaidMember {
+                    device: device.to_string(),
+                    slot: slot.parse().unwrap_or(0),
+                    faulty: flags.contains("(F)"),
+                    spare: flags.contains("(S)"),
+                    ..Default::default()
+                });
+            }
+            level => {
+                array.level.get_or_insert_with(|| level.to_string());
+            }
+        }
+    }
+    array.members.sort_by_key(|m| m.slot);
+    array
+}
+
+/// "[=>....]  recovery =  8.5% (89600/1046528) finish=1.2min speed=12800K/sec",
+/// or "resync=DELAYED".
+fn parse_md_sync(line: &str) -> Option<RaidSync> {
+    // The progress bar is drawn with '='.
+    let line = match line.strip_prefix('[') {
+        Some(bar) => bar.split_once(']')?.1,
+        None => line,
+    };
+    let (action, rest) = line.split_once('=')?;
+    let action = action.trim();
+    if !["recovery", "resync", "reshape", "check", "repair"].contains(&action) {
+        return None;
+    }
+    let field = |key: &str| rest.split_whitespace().find_map(|w| w.strip_prefix(key));
+    Some(RaidSync {
+        action: action.to_string(),
+        percent: rest
+            .split_whitespace()
+            .next()
+            .and_then(|w| w.strip_suffix('%'))
+            .and_then(|p| p.parse().ok()),
+        finish_secs: field("finish=")
+            .and_then(|f| f.strip_suffix("min"))
+            .and_then(|m| m.parse::<f64>().ok())
+            .map(|m| (m * 60.0) as u64),
+        speed_bytes_per_sec: field("speed=")
+            .and_then(|s| s.strip_suffix("K/sec"))
+            .and_then(|k| k.parse::<u64>().ok())
+            .map(|k| k * 1024),
+    })
+}
+
+/// ZFS, btrfs and LVM, each as far as its tools are installed and allowed
+/// to run (`btrfs device stats`, `vgs` and `lvs` want root).
+async fn storage_stacks() -> StorageStacks {
+    StorageStacks {
+        zfs: match tool_output("zpool", &["status", "-p"], &[0]).await {
+            Ok(text) => parse_zpool_status(&text),
+            Err(_) => Vec::new(),
+        },
+        btrfs: btrfs_filesystems().await,
+        lvm: lvm_volume_groups().await.unwrap_or_default(),
+    }
+}
+
+/// `zpool status -p`: per pool "  pool: tank", " state: DEGRADED", optional
+/// "status:" and "scan:" paragraphs (continued on tab-indented lines), a
+/// "config:" table whose names are indented two spaces per level, then
+/// "errors:".
+fn parse_zpool_status(text: &str) -> Vec<ZfsPool> {
+    let mut pools: Vec<ZfsPool> = Vec::new();
+    let mut in_config = false;
+    let mut last_key = "";
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if in_config {
+            if trimmed.is_empty() || trimmed.starts_with("NAME ") {
+                continue;
+            }
+            if let Some(row) = line.strip_prefix('\t') {
+                let Some(pool) = pools.last_mut() else {
+                    continue;
+                };
+                let depth = (row.len() - row.trim_start().len()) as u32 / 2;
+                let words: Vec<&str> = trimmed.split_whitespace().collect();
+                let count = |i: usize| words.get(i).and_then(|w| w.parse().ok()).unwrap_or(0);
+                let note = words.get(5..).map(|rest| rest.join(" "));
+                pool.devices.push(ZfsDevice {
+                    name: words[0].to_string(),
+                    depth,
+                    state: words.get(1).unwrap_or(&"").to_string(),
+                    read_errors: count(2),
+                    write_errors: count(3),
+                    checksum_errors: count(4),
+                    note: note.filter(|n| !n.is_empty()),
+                });
+                continue;
+            }
+            in_config = false;
+        }
+        if trimmed == "config:" {
+            in_config = true;
+            continue;
+        }
+        let Some((key, value)) = trimmed.split_once(':').filter(|_| !line.starts_with('\t')) else {
+            // A paragraph's continuation
+            if let Some(pool) = pools.last_mut() {
+                let field = match last_key {
+                    "status" => &mut pool.status,
+                    "scan" => &mut pool.scan,
+                    _ => continue,
+                };
+                if let Some(text) = field {
+                    text.push(' ');
+                    text.push_str(trimmed);
+                }
+            }
+            continue;
+        };
+        let value = value.trim().to_string();
+        last_key = key;
+        if key == "pool" {
+            pools.push(ZfsPool {
+                name: value,
+                ..Default::default()
+            });
+            continue;
+        }
+        let Some(pool) = pools.last_mut() else {
+            continue;
+        };
+        match key {
+            "state" => pool.state = value,
+            "status" => pool.status = Some(value),
+            "scan" => pool.scan = Some(value),
+            "errors" => pool.errors = Some(value),
+            _ => {}
+        }
+    }
+    pools
+}
+
+/// Mounted btrfs filesystems (once each, however many subvolumes are
+/// mounted) with their device error counters and last scrub.
+async fn btrfs_filesystems() -> Vec<BtrfsFilesystem> {
+    let mounts = fs::read_to_string("/proc/mounts").await.unwrap_or_default();
+    let mut seen = Vec::new();
+    let mut filesystems = Vec::new();
+    for line in mounts.lines() {
+        let f: Vec<&str> = line.split_whitespace().take(3).collect();
+        let [source, mountpoint, "btrfs"] = f[..] else {
+            continue;
+        };
+        if seen.contains(&source) {
+            continue;
+        }
+        seen.push(source);
+        let mountpoint = unescape_mount_field(mountpoint);
+        let stats = tool_output("btrfs", &["device", "stats", &mountpoint], &[0]).await;
+        let scrub = tool_output("btrfs", &["scrub", "status", &mountpoint], &[0]).await;
+        if stats.is_err() && scrub.is_err() {
+            continue;
+        }
+        filesystems.push(BtrfsFilesystem {
+            devices: stats
+                .map(|text| parse_btrfs_device_stats(&text))
+                .unwrap_or_default(),
+            scrub: scrub.ok().map(|text| parse_btrfs_scrub(&text)),
+            mountpoint,
+        });
+    }
+    filesystems
+}
+
+/// `btrfs device stats`: "[/dev/sda1].write_io_errs    0" per counter.
+fn parse_btrfs_device_stats(text: &str) -> Vec<BtrfsDevice> {
+    let mut devices: Vec<BtrfsDevice> = Vec::new();
+    for line in text.lines() {
+        let Some((path, rest)) = line
+            .strip_prefix('[')
+            .and_then(|line| line.split_once("]."))
+        else {
+            continue;
+        };
+        let mut words = rest.split_whitespace();
+        let (Some(counter), Some(value)) = (words.next(), words.next()) else {
+            continue;
+        };
+        let value = value.parse().unwrap_or(0);
+        if devices.last().is_none_or(|d| d.path != path) {
+            devices.push(BtrfsDevice {
+                path: path.to_string(),
+                ..Default::default()
+            });
+        }
+        let Some(device) = devices.last_mut() else {
+            continue;
+        };
+        match counter {
+            "write_io_errs" => device.write_io_errs = value,
+            "read_io_errs" => device.read_io_errs = value,
+            "flush_io_errs" => device.flush_io_errs = value,
+            "corruption_errs" => device.corruption_errs = value,
+            "generation_errs" => device.generation_errs = value,
+            _ => {}
+        }
+    }
+    devices
+}
+
+/// `btrfs scrub status`, as btrfs-progs 5.x+ prints it ("Status:",
+/// "Duration:", "Error summary:") or as older ones did ("scrub started at
+/// ... and finished after 00:01:12", "... with 0 errors").
+fn parse_btrfs_scrub(text: &str) -> BtrfsScrub {
+    let mut scrub = BtrfsScrub {
+        status: "never".to_string(),
+        ..Default::default()
+    };
+    for line in text.lines() {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("scrub started at ") {
+            let (started, outcome) = rest.split_once(" and ").unwrap_or((rest, ""));
+            scrub.started = Some(started.to_string());
+            for (phrase, status) in [
+                ("finished after ", "finished"),
+                ("was aborted after ", "aborted"),
+                ("interrupted after ", "interrupted"),
+                ("running for ", "running"),
+            ] {
+                if let Some(duration) = outcome.strip_prefix(phrase) {
+                    scrub.status = status.to_string();
+                    scrub.duration = Some(duration.to_string());
+                }
+            }
+        } else if let Some((_, errors)) = line.split_once(" with ") {
+            // "total bytes scrubbed: 1.00GiB with 0 errors"
+            scrub.errors_found = !errors.starts_with("0 ");
+            if scrub.error_summary.is_none() {
+                scrub.error_summary = Some(errors.to_string());
+            }
+        } else if let Some(details) = line.strip_prefix("error details: ") {
+            scrub.error_summary = Some(details.to_string());
+        } else if let Some((key, value)) = line.split_once(':') {
+            let value = value.trim().to_string();
+            match key {
+                "Scrub started" => scrub.started = Some(value),
+                "Status" => scrub.status = value,
+                "Duration" => scrub.duration = Some(value),
+                "Error summary" => {
+                    scrub.errors_found = value != "no errors found";
+                    scrub.error_summary = Some(value);
+                }
+                _ => {}
+            }
+        }
+    }
+    scrub
+}
+
+/// Volume groups from `vgs` with their logical volumes from `lvs` (empty
+/// without LVM; an error when its tools cannot read the metadata).
+async fn lvm_volume_groups() -> Result<Vec<LvmVolumeGroup>> {
+    let report = [
+        "--noheadings",
+        "--units",
+        "b",
+        "--nosuffix",
+        "--separator",
+        "|",
+    ];
+    let mut vgs_args = report.to_vec();
+    vgs_args.extend(["-o", "vg_name,vg_size,vg_free,pv_count,vg_attr"]);
+    let mut lvs_args = report.to_vec();
+    lvs_args.extend([
+        "-o",
+        "vg_name,lv_name,lv_size,segtype,lv_attr,data_percent,pool_lv",
+    ]);
+    let mut groups = parse_vgs(&tool_output("vgs", &vgs_args, &[0]).await?);
+    parse_lvs(&tool_output("lvs", &lvs_args, &[0]).await?, &mut groups);
+    Ok(groups)
+}
+
+/// `vgs` rows: "  vg0|107374182400|4194304|2|wz--n-"; the fourth attribute
+/// is 'p' while physical volumes are missing.
+fn parse_vgs(text: &str) -> Vec<LvmVolumeGroup> {
+    text.lines()
+        .filter_map(|line| {
+            let f: Vec<&str> = line.trim().split('|').collect();
+            let [name, size, free, pvs, attr] = f[..] else {
+                return None;
+            };
+            Some(LvmVolumeGroup {
+                name: name.to_string(),
+                size_bytes: size.parse().unwrap_or(0),
+                free_bytes: free.parse().unwrap_or(0),
+                pv_count: pvs.parse().unwrap_or(0),
+                partial: attr.chars().nth(3) == Some('p'),
+                volumes: Vec::new(),
+            })
+        })
+        .collect()
+}
+
+/// `lvs` rows: "  vg0|pool|53687091200|thin-pool|twi-aotz--|81.50|"; the
+/// fifth attribute is 'a' when active, the ninth lvm's health.
+fn parse_lvs(text: &str, groups: &mut [LvmVolumeGroup]) {
+    for line in text.lines() {
+        let f: Vec<&str> = line.trim().split('|').map(str::trim).collect();
+        let [vg, name, size, kind, attr, data_percent, pool] = f[..] else {
+            continue;
+        };
+        let Some(group) = groups.iter_mut().find(|g| g.name == vg) else {
+            continue;
+        };
+        let health = match attr.chars().nth(8) {
+            Some('p') => Some("partial"),
+            Some('r') => Some("refresh needed"),
+            Some('m') => Some("mismatches exist"),
+            Some('w') => Some("writemostly"),
+            Some('F') => Some("failed"),
+            Some('D') => Some("out of data space"),
+            Some('M') => Some("metadata read only"),
+            Some('X') => Some("unknown"),
+            _ => None,
+        };
+        group.volumes.push(LvmVolume {
+            name: name.to_string(),
+            size_bytes: size.parse().unwrap_or(0),
+            kind: kind.to_string(),
+            active: attr.chars().nth(4) == Some('a'),
+            data_percent: data_percent.parse().ok(),
+            pool: Some(pool).filter(|p| !p.is_empty()).map(str::to_string),
+            health: health.map(str::to_string),
+        });
+    }
+}
+
+/// Backup jobs run as systemd services, then rsnapshot's log file.
+async fn backup_jobs() -> Vec<BackupJob> {
+    let mut jobs = Vec::new();
+    let units = TokioCommand::new("systemctl")
+        .arg("list-units")
+        .arg("--type=service")
+        .arg("--all")
+        .arg("--no-legend")
+        .arg("--no-pager")
+        .arg("--plain")
+        .arg("--full")
+        .kill_on_drop(true)
+        .output()
+        .await;
+    if let Ok(out) = units {
+        for line in String::from_utf8_lossy(&out.stdout).lines() {
+            // unit load active sub description
+            let fields: Vec<&str> = line.split_whitespace().take(4).collect();
+            let [unit, _, _, sub] = fields[..] else {
+                continue;
+            };
+            let Some(tool) = BACKUP_TOOLS.iter().find(|tool| unit.contains(*tool)) else {
+                continue;
+            };
+            jobs.push(BackupJob {
+                tool: tool.to_string(),
+                source: unit.to_string(),
+                last_success_usec: last_unit_message(unit, UNIT_SUCCESS_ID).await,
+                last_failure_usec: last_unit_message(unit, UNIT_FAILURE_ID).await,
+                running: matches!(sub, "start" | "running"),
+            });
+        }
+    }
+    if let Ok(Some(job)) = tokio::task::spawn_blocking(rsnapshot_log).await {
+        jobs.push(job);
+    }
+    jobs
+}
+
+/// When systemd last logged the message `message_id` for `unit`.
+async fn last_unit_message(unit: &str, message_id: &str) -> Option<u64> {
+    let out = journalctl(None)
+        .arg(format!("UNIT={}", unit))
+        .arg(format!("MESSAGE_ID={}", message_id))
+        .arg("--lines=1")
+        .output()
+        .await
+        .ok()?;
+    String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .filter_map(parse_journal_line)
+        .map(|(entry, _)| entry.realtime_usec)
+        .next_back()
+}
+
+/// rsnapshot runs from its log (when it keeps one at the default path):
+/// "[2024-05-01T03:30:01] /usr/bin/rsnapshot daily: completed successfully".
+fn rsnapshot_log() -> Option<BackupJob> {
+    let (lines, _, _) = tail_lines(RSNAPSHOT_LOG, MAX_JOURNAL_LINES).ok()?;
+    let mut job = BackupJob {
+        tool: "rsnapshot".to_string(),
+        source: RSNAPSHOT_LOG.to_string(),
+        ..Default::default()
+    };
+    for line in &lines {
+        let Some((stamp, message)) = line.strip_prefix('[').and_then(|l| l.split_once("] ")) else {
+            continue;
+        };
+        if message.contains("completed successfully")
+            || message.contains("completed, but with some warnings")
+        {
+            job.last_success_usec = local_time_usec(stamp).or(job.last_success_usec);
+        } else if message.contains("ERROR") {
+            job.last_failure_usec = local_time_usec(stamp).or(job.last_failure_usec);
+        }
+    }
+    Some(job)
+}
+
+/// Microseconds since the epoch of a local "2024-05-01T03:30:01".
+fn local_time_usec(stamp: &str) -> Option<u64> {
+    let (date, time) = stamp.split_once('T')?;
+    let numbers = |text: &str, sep: char| -> Option<Vec<i32>> {
+        text.split(sep).map(|n| n.parse().ok()).collect()
+    };
+    let (date, time) = (numbers(date, '-')?, numbers(time, ':')?);
+    let ([year, month, day], [hour, min, sec]) = (&date[..], &time[..]) else {
+        return None;
+    };
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    tm.tm_year = year - 1900;
+    tm.tm_mon = month - 1;
+    tm.tm_mday = *day;
+    tm.tm_hour = *hour;
+    tm.tm_min = *min;
+    tm.tm_sec = *sec;
+    // Daylight saving time as in effect on that date.
+    tm.tm_isdst = -1;
+    // SAFETY: `tm` is a valid, initialized struct mktime may normalize.
+    let secs = unsafe { libc::mktime(&mut tm) };
+    (secs >= 0).then(|| secs as u64 * 1_000_000)
+}
+
+/// sshd's settings: effective ones from `sshd -T`, or else what the config
+/// files say; None when neither sshd nor its config is present.
+async fn sshd_config() -> Option<SshdConfig> {
+    let sshd = SSHD_BINARIES
+        .iter()
+        .find(|path| std::path::Path::new(path).exists());
+    if sshd.is_none() && !std::path::Path::new(SSHD_CONFIG).exists() {
+        return None;
+    }
+    let mut config = SshdConfig::default();
+    match sshd {
+        Some(sshd) => match tool_output(sshd, &["-T"], &[0]).await {
+            Ok(text) => {
+                config.effective = true;
+                config.settings = text
+                    .lines()
+                    .filter_map(|line| {
+                        let (key, value) = line.split_once(' ')?;
+                        Some(SshdSetting {
+                            key: key.to_string(),
+                            value: value.to_string(),
+                        })
+                    })
+                    .collect();
+                return Some(config);
+            }
+            Err(e) => config.effective_error = Some(e.to_string()),
+        },
+        None => config.effective_error = Some("sshd not found".to_string()),
+    }
+    tokio::task::spawn_blocking(move || {
+        let mut in_match = false;
+        read_sshd_config(SSHD_CONFIG, &mut config, &mut in_match);
+        config
+    })
+    .await
+    .ok()
+}
+
+/// Settings of one sshd config file, following `Include`s, into `config`.
+/// As in sshd the first value of a keyword wins (save for repeatable ones),
+/// and `Match` blocks, which only apply to some connections, are skipped.
+fn read_sshd_config(path: &str, config: &mut SshdConfig, in_match: &mut bool) {
+    const REPEATABLE: [&str; 11] = [
+        "acceptenv",
+        "allowgroups",
+        "allowusers",
+        "denygroups",
+        "denyusers",
+        "hostcertificate",
+        "hostkey",
+        "listenaddress",
+        "port",
+        "setenv",
+        "subsystem",
+    ];
+    // Include cycles (and runaway nesting) stop here.
+    if config.files.len() >= 64 || config.files.iter().any(|f| f == path) {
+        return;
+    }
+    let Ok(text) = std::fs::read_to_string(path) else {
+        return;
+    };
+    config.files.push(path.to_string());
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        // "Keyword value" or "Keyword=value"
+        let split = line
+            .find(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or(line.len());
+        let key = line[..split].to_ascii_lowercase();
+        let value = line[split..]
+            .trim_start_matches(|c: char| c.is_whitespace() || c == '=')
+            .trim_matches('"');
+        match key.as_str() {
+            "match" => *in_match = !value.eq_ignore_ascii_case("all"),
+            "include" => {
+                for pattern in value.split_whitespace() {
+                    for file in sshd_include(pattern) {
+                        read_sshd_config(&file, config, in_match);
+                    }
+                }
+            }
+            _ if *in_match => {}
+            _ if !REPEATABLE.contains(&key.as_str())
+                && config.settings.iter().any(|s| s.key == key) => {}
+            _ => config.settings.push(SshdSetting {
+                key,
+                value: value.to_string(),
+            }),
+        }
+    }
+}
+
+/// Files an `Include` names, relative to /etc/ssh; a '*' in the file name
+/// matches like a shell glob, in sorted order.
+fn sshd_include(pattern: &str) -> Vec<String> {
+    let path = std::path::Path::new("/etc/ssh").join(pattern);
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let Some((prefix, suffix)) = name.split_once('*') else {
+        return vec![path.display().to_string()];
+    };
+    let Some(dir) = path.parent() else {
+        return Vec::new();
+    };
+    let mut files: Vec<String> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            name.len() >= prefix.len() + suffix.len()
+                && name.starts_with(prefix)
+                && name.ends_with(suffix)
+        })
+        .map(|entry| entry.path().display().to_string())
+        .collect();
+    files.sort();
+    files
+}
+
+/// Failed authentication attempts of the last `window_secs`, from the
+/// journal (auth and authpriv facilities) or else the first auth log found,
+/// and fail2ban's jails.
+async fn auth_failures(window_secs: u64) -> AuthFailures {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let since_usec = now.saturating_sub(window_secs) * 1_000_000;
+    let since = format!("--since=@{}", now.saturating_sub(window_secs));
+    let journal = tool_output(
+        "journalctl",
+        &[
+            "-q",
+            "--no-pager",
+            "-o",
+            "short-unix",
+            &since,
+            "SYSLOG_FACILITY=4",
+            "SYSLOG_FACILITY=10",
+        ],
+        &[0],
+    )
+    .await;
+    let (source, lines) = match journal {
+        Ok(text) => (
+            Some("journal".to_string()),
+            text.lines().map(str::to_string).collect(),
+        ),
+        Err(_) => tokio::task::spawn_blocking(|| {
+            AUTH_LOGS
+                .iter()
+                .find_map(|path| {
+                    let (lines, _, _) = tail_lines(path, AUTH_LOG_LINES).ok()?;
+                    Some((Some(path.to_string()), lines))
+                })
+                .unwrap_or_default()
+        })
+        .await
+        .unwrap_or_default(),
+    };
+
+    let year = current_year();
+    let mut summary = AuthFailures {
+        window_secs,
+        source,
+        ..Default::default()
+    };
+    let mut by_address: HashMap<String, (u64, u64)> = HashMap::new();
+    let mut by_user: HashMap<String, (u64, u64)> = HashMap::new();
+    for line in &lines {
+        // Year-less stamps in the future are from last year.
+        let Some((at, message)) = parse_auth_line(line, year)
+            .filter(|&(at, _)| at <= (now + 86_400) * 1_000_000)
+            .or_else(|| parse_auth_line(line, year - 1))
+        else {
+            continue;
+        };
+        if at < since_usec {
+            continue;
+        }
+        let Some((user, address)) = parse_auth_failure(message) else {
+            continue;
+        };
+        summary.total += 1;
+        let count = |map: &mut HashMap<String, (u64, u64)>, key: &str| {
+            let entry = map.entry(key.to_string()).or_default();
+            entry.0 += 1;
+            entry.1 = entry.1.max(at);
+        };
+        count(&mut by_address, address.unwrap_or("local"));
+        if let Some(user) = user.filter(|u| !u.is_empty()) {
+            count(&mut by_user, user);
+        }
+    }
+    let top = |map: HashMap<String, (u64, u64)>| {
+        let mut counts: Vec<AuthFailureCount> = map
+            .into_iter()
+            .map(|(key, (count, last_usec))| AuthFailureCount {
+                key,
+                count,
+                last_usec,
+            })
+            .collect();
+        counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.key.cmp(&b.key)));
+        counts.truncate(AUTH_TOP);
+        counts
+    };
+    summary.by_address = top(by_address);
+    summary.by_user = top(by_user);
+    summary.fail2ban = fail2ban_status().await;
+    summary
+}
+
+/// fail2ban's jails and their counters; None when fail2ban is not installed.
+async fn fail2ban_status() -> Option<Fail2ban> {
+    let mut status = Fail2ban::default();
+    let out = match TokioCommand::new("fail2ban-client")
+        .arg("status")
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await
+    {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(e) => {
+            status.error = Some(format!("failed to run fail2ban-client: {}", e));
+            return Some(status);
+        }
+        Ok(out) if !out.status.success() => {
+            status.error = Some(String::from_utf8_lossy(&out.stderr).trim().to_string());
+            return Some(status);
+        }
+        Ok(out) => String::from_utf8_lossy(&out.stdout).into_owned(),
+    };
+    // "`- Jail list:\tsshd, nginx-http-auth"
+    let jails = out
+        .lines()
+        .find_map(|line| line.split_once("Jail list:"))
+        .map(|(_, list)| list.split(',').map(str::trim).filter(|j| !j.is_empty()))
+        .into_iter()
+        .flatten();
+    for jail in jails {
+        match tool_output("fail2ban-client", &["status", jail], &[0]).await {
+            Ok(text) => status.jails.push(parse_fail2ban_jail(jail, &text)),
+            Err(e) => status.error = Some(e.to_string()),
+        }
     }
+    Some(status)
 }
 
-fn expand_tilde(path: String) -> String {
-    if let Some(stripped) = path.strip_prefix("~/") {
-        if let Some(home) = dirs_next::home_dir() {
-            return format!("{}/{}", home.display(), stripped);
+/// Counters of `fail2ban-client status <jail>`, a tree of "Key:\tvalue" lines.
+fn parse_fail2ban_jail(name: &str, text: &str) -> Fail2banJail {
+    let mut jail = Fail2banJail {
+        name: name.to_string(),
+        ..Default::default()
+    };
+    for line in text.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let key = key.trim_start_matches(['|', '`', '-', ' ']);
+        let value = value.trim();
+        let count = || value.parse().unwrap_or(0);
+        match key {
+            "Currently failed" => jail.currently_failed = count(),
+            "Total failed" => jail.total_failed = count(),
+            "Currently banned" => jail.currently_banned = count(),
+            "Total banned" => jail.total_banned = count(),
+            "Banned IP list" => {
+                jail.banned = value.split_whitespace().map(str::to_string).collect()
+            }
+            _ => {}
         }
     }
-    path
+    jail
 }
 
-async fn sys_info() -> Result<SysInfo> {
-    // OS and arch from Rust std
-    let os = std::env::consts::OS.to_string();
-    let arch = std::env::consts::ARCH.to_string();
-
-    // Kernel release from /proc (Linux)
-    let kernel = match fs::read_to_string("/proc/sys/kernel/osrelease").await {
-        Ok(s) => s.trim().to_string(),
-        Err(_) => "unknown".to_string(),
+/// The time (microseconds since the epoch) and message of a syslog line:
+/// "1714534201.123456 host sshd[812]: …" (journalctl -o short-unix),
+/// "2024-05-01T03:30:01.123456+02:00 host sshd[812]: …" (RFC 3339, read as
+/// local time) or "May  1 03:30:01 host sshd[812]: …" (in `year`).
+fn parse_auth_line(line: &str, year: i32) -> Option<(u64, &str)> {
+    let mut words = line.split_whitespace();
+    let first = words.next()?;
+    let (at, rest) = if let Ok(secs) = first.parse::<f64>() {
+        (
+            (secs * 1_000_000.0) as u64,
+            line[first.len()..].trim_start(),
+        )
+    } else if first.len() >= 19 && first.as_bytes()[10] == b'T' {
+        (
+            local_time_usec(&first[..19])?,
+            line[first.len()..].trim_start(),
+        )
+    } else {
+        const MONTHS: [&str; 12] = [
+            "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+        ];
+        let month = MONTHS.iter().position(|&m| m == first)? + 1;
+        let day: u32 = words.next()?.parse().ok()?;
+        let time = words.next()?;
+        let stamp = format!("{}-{:02}-{:02}T{}", year, month, day, time);
+        let rest = line.split_once(time)?.1.trim_start();
+        (local_time_usec(&stamp)?, rest)
     };
+    // "host ident[pid]: message"
+    let (_, message) = rest.split_once(": ")?;
+    Some((at, message))
+}
 
-    // Uptime in seconds (first field of /proc/uptime)
-    let uptime_secs = match fs::read_to_string("/proc/uptime").await {
-        Ok(s) => s
+/// The user and source address of a failed attempt: sshd's "Failed password
+/// for [invalid user] bob from 203.0.113.7 port 4242 ssh2", or a PAM
+/// "authentication failure; … rhost=… user=bob" of another service (sshd's
+/// own PAM lines duplicate its "Failed" ones).
+fn parse_auth_failure(message: &str) -> Option<(Option<&str>, Option<&str>)> {
+    if let Some(rest) = message.strip_prefix("Failed ") {
+        let (_, rest) = rest.split_once(" for ")?;
+        let rest = rest.strip_prefix("invalid user ").unwrap_or(rest);
+        let (user, from) = rest.rsplit_once(" from ")?;
+        return Some((Some(user), from.split_whitespace().next()));
+    }
+    let (head, fields) = message.split_once("authentication failure;")?;
+    if head.contains("(sshd:") {
+        return None;
+    }
+    let field = |name: &str| {
+        fields
             .split_whitespace()
-            .next()
-            .and_then(|v| v.parse::<f64>().ok())
-            .map(|f| f as u64)
-            .unwrap_or(0),
-        Err(_) => 0,
+            .find_map(|kv| kv.strip_prefix(name)?.strip_prefix('='))
+            .filter(|v| !v.is_empty())
     };
+    Some((field("user").or(field("ruser")), field("rhost")))
+}
 
-    // Hostname from /proc, fallback to HOSTNAME env
-    let hostname = match fs::read_to_string("/proc/sys/kernel/hostname").await {
-        Ok(s) => s.trim().to_string(),
-        Err(_) => std::env::var("HOSTNAME").unwrap_or_else(|_| "unknown".to_string()),
-    };
+/// The current year in local time.
+fn current_year() -> i32 {
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    // SAFETY: time accepts a null pointer; both pointers passed to the
+    // reentrant localtime_r are valid for the call.
+    unsafe {
+        let now = libc::time(std::ptr::null_mut());
+        libc::localtime_r(&now, &mut tm);
+    }
+    tm.tm_year + 1900
+}
 
-    Ok(SysInfo {
-        os,
-        kernel,
-        arch,
-        uptime_secs,
-        hostname,
-    })
+/// Local accounts, admin group members and sudo rules.
+fn user_inventory() -> UserInventory {
+    let passwd = std::fs::read_to_string("/etc/passwd").unwrap_or_default();
+    // name:password:lastchg:… (lastchg in days since the epoch)
+    let shadow: HashMap<String, (String, Option<u64>)> = std::fs::read_to_string("/etc/shadow")
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?.to_string();
+            let password = fields.next()?.to_string();
+            let changed = fields.next()?.parse::<u64>().ok().filter(|&d| d > 0);
+            Some((name, (password, changed.map(|d| d * 86_400 * 1_000_000))))
+        })
+        .collect();
+
+    let mut inventory = UserInventory::default();
+    // name:password:uid:gid:gecos:home:shell
+    for line in passwd.lines() {
+        let fields: Vec<&str> = line.split(':').collect();
+        let [name, _, uid, gid, gecos, home, shell] = fields[..] else {
+            continue;
+        };
+        let (Ok(uid), Ok(gid)) = (uid.parse(), gid.parse()) else {
+            continue;
+        };
+        let entry = shadow.get(name);
+        inventory.users.push(LocalUser {
+            name: name.to_string(),
+            uid,
+            gid,
+            gecos: gecos.to_string(),
+            home: home.to_string(),
+            shell: shell.to_string(),
+            can_login: !shell.ends_with("/nologin") && !shell.ends_with("/false"),
+            password_changed_usec: entry.and_then(|(_, changed)| *changed),
+            password_locked: entry.map(|(password, _)| {
+                password.is_empty() || password.starts_with('!') || password.starts_with('*')
+            }),
+        });
+    }
+    inventory.users.sort_by_key(|u| u.uid);
+
+    // name:password:gid:member,member
+    for line in std::fs::read_to_string("/etc/group")
+        .unwrap_or_default()
+        .lines()
+    {
+        let fields: Vec<&str> = line.split(':').collect();
+        let [name, _, gid, members] = fields[..] else {
+            continue;
+        };
+        let Ok(gid) = gid.parse() else {
+            continue;
+        };
+        if !ADMIN_GROUPS.contains(&name) {
+            continue;
+        }
+        let mut members: Vec<String> = members
+            .split(',')
+            .filter(|m| !m.is_empty())
+            .map(str::to_string)
+            .collect();
+        for user in inventory.users.iter().filter(|u| u.gid == gid) {
+            if !members.contains(&user.name) {
+                members.push(user.name.clone());
+            }
+        }
+        inventory.admin_groups.push(GroupMembers {
+            name: name.to_string(),
+            gid,
+            members,
+        });
+    }
+
+    match std::fs::read_to_string(SUDOERS) {
+        Ok(_) => read_sudoers(SUDOERS, &mut inventory.sudo_rules, 0),
+        Err(e) => inventory.sudoers_error = Some(format!("{}: {}", SUDOERS, e)),
+    }
+    inventory
 }
 
-async fn static_config() -> Result<StaticConfig> {
-    // /etc/os-release content (optional)
-    let os_release = match fs::read_to_string("/etc/os-release").await {
-        Ok(s) => Some(s),
-        Err(_) => None,
+/// User specifications of a sudoers file and the files it includes
+/// (`@include`/`#include`, `@includedir`/`#includedir`).
+fn read_sudoers(path: &str, rules: &mut Vec<SudoRule>, depth: usize) {
+    // sudo itself gives up on nesting this deep (and on include loops).
+    if depth > 8 {
+        return;
+    }
+    let Ok(text) = std::fs::read_to_string(path) else {
+        return;
     };
+    let dir = std::path::Path::new(path)
+        .parent()
+        .unwrap_or(std::path::Path::new("/"));
+    // Join lines continued with a trailing backslash.
+    let mut logical = String::new();
+    for line in text.lines() {
+        if !logical.is_empty() {
+            logical.push(' ');
+        }
+        if let Some(head) = line.strip_suffix('\\') {
+            logical.push_str(head.trim());
+            continue;
+        }
+        logical.push_str(line.trim());
+        let line = std::mem::take(&mut logical);
+        let line = line.trim();
+        let include = |name: &str| {
+            line.strip_prefix('@')
+                .or_else(|| line.strip_prefix('#'))
+                .and_then(|l| l.strip_prefix(name))
+                .filter(|rest| rest.starts_with(char::is_whitespace))
+                .map(|rest| dir.join(rest.trim()).display().to_string())
+        };
+        if let Some(sub) = include("includedir") {
+            // As sudo: skip names with a '.' or ending in '~' (editor and
+            // package manager leftovers), in sorted order.
+            let mut files: Vec<String> = std::fs::read_dir(&sub)
+                .into_iter()
+                .flatten()
+                .flatten()
+                .filter(|e| {
+                    let name = e.file_name().to_string_lossy().into_owned();
+                    !name.contains('.') && !name.ends_with('~')
+                })
+                .map(|e| e.path().display().to_string())
+                .collect();
+            files.sort();
+            for file in files {
+                read_sudoers(&file, rules, depth + 1);
+            }
+            continue;
+        }
+        if let Some(file) = include("include") {
+            read_sudoers(&file, rules, depth + 1);
+            continue;
+        }
+        if line.is_empty() || line.starts_with('#') || line.starts_with("Defaults") {
+            continue;
+        }
+        let (who, spec) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        // User_Alias, Runas_Alias, Host_Alias, Cmnd_Alias definitions
+        if who.ends_with("_Alias") {
+            continue;
+        }
+        rules.push(SudoRule {
+            file: path.to_string(),
+            who: who.to_string(),
+            spec: spec.trim().to_string(),
+            nopasswd: spec.contains("NOPASSWD:"),
+        });
+    }
+}
 
-    // CPU count from /proc/cpuinfo
-    let cpu_count = match fs::read_to_string("/proc/cpuinfo").await {
-        Ok(s) => s.lines().filter(|l| l.starts_with("processor")).count() as u32,
-        Err(_) => 0,
-    };
+/// User logins recorded in utmp, as `who` lists them.
+fn login_sessions() -> Vec<LoginSession> {
+    /// A fixed-size, NUL-padded utmp field.
+    fn field(chars: &[libc::c_char]) -> String {
+        let bytes: Vec<u8> = chars
+            .iter()
+            .take_while(|&&c| c != 0)
+            .map(|&c| c as u8)
+            .collect();
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
 
-    // MemTotal from /proc/meminfo (in kB) -> bytes
-    let mem_total_bytes = match fs::read_to_string("/proc/meminfo").await {
-        Ok(s) => s
-            .lines()
-            .find(|l| l.starts_with("MemTotal:"))
-            .and_then(|l| l.split_whitespace().nth(1))
-            .and_then(|kb| kb.parse::<u64>().ok())
-            .map(|kb| kb * 1024)
-            .unwrap_or(0),
-        Err(_) => 0,
-    };
+    let mut sessions = Vec::new();
+    // SAFETY: getutxent walks the utmp database with libc's internal state;
+    // this runs on one blocking thread from setutxent to endutxent, and each
+    // entry is copied out before the next call.
+    unsafe {
+        libc::setutxent();
+        loop {
+            let entry = libc::getutxent();
+            if entry.is_null() {
+                break;
+            }
+            let entry = &*entry;
+            if entry.ut_type != libc::USER_PROCESS {
+                continue;
+            }
+            let host = field(&entry.ut_host);
+            sessions.push(LoginSession {
+                user: field(&entry.ut_user),
+                tty: field(&entry.ut_line),
+                host: (!host.is_empty()).then_some(host),
+                ip: utmp_address(entry.ut_addr_v6.map(|word| word as u32)),
+                login_usec: (entry.ut_tv.tv_sec as u64) * 1_000_000 + entry.ut_tv.tv_usec as u64,
+                pid: entry.ut_pid as u32,
+            });
+        }
+        libc::endutxent();
+    }
+    sessions
+}
 
-    Ok(StaticConfig {
-        os_release,
-        cpu_count,
-        mem_total_bytes,
-    })
+/// The remote address of a utmp entry; None for a local login.
+fn utmp_address(words: [u32; 4]) -> Option<String> {
+    match words {
+        [0, 0, 0, 0] => None,
+        // The address is stored in network byte order; IPv4 in the first
+        // word only.
+        [v4, 0, 0, 0] => Some(std::net::Ipv4Addr::from(v4.to_ne_bytes()).to_string()),
+        words => {
+            let mut octets = [0u8; 16];
+            for (chunk, word) in octets.chunks_mut(4).zip(words) {
+                chunk.copy_from_slice(&word.to_ne_bytes());
+            }
+            Some(std::net::Ipv6Addr::from(octets).to_string())
+        }
+    }
 }
 
-// Baseline filtering is handled on the client UI; no remote filtering or config required.
+/// `systemctl show` properties and `systemctl cat` of one unit.
+async fn service_detail(unit: &str) -> Result<ServiceDetail> {
+    const PROPERTIES: &str = "Description,LoadState,ActiveState,SubState,UnitFileState,\
+        MainPID,MemoryCurrent,NRestarts,ActiveEnterTimestamp,ExecStart,FragmentPath";
+    let out = TokioCommand::new("systemctl")
+        .args(["show", "--no-pager", "-p", PROPERTIES, "--"])
+        .arg(unit)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| anyhow!("systemctl: {}", e))?;
+    if !out.status.success() {
+        return Err(anyhow!(
+            "systemctl show {}: {}",
+            unit,
+            String::from_utf8_lossy(&out.stderr).trim()
+        ));
+    }
+    let mut detail = ServiceDetail {
+        unit: unit.to_string(),
+        ..Default::default()
+    };
+    // "Key=value" per line; unset values print as "" or "[not set]".
+    for line in String::from_utf8_lossy(&out.stdout).lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let set = (!value.is_empty() && value != "[not set]").then(|| value.to_string());
+        match key {
+            "Description" => detail.description = value.to_string(),
+            "LoadState" => detail.load_state = value.to_string(),
+            "ActiveState" => detail.active_state = value.to_string(),
+            "SubState" => detail.sub_state = value.to_string(),
+            "UnitFileState" => detail.unit_file_state = value.to_string(),
+            "MainPID" => detail.main_pid = value.parse().ok().filter(|pid| *pid != 0),
+            // u64::MAX means "infinity" (no accounting).
+            "MemoryCurrent" => detail.memory_bytes = value.parse().ok().filter(|m| *m != u64::MAX),
+            "NRestarts" => detail.restarts = value.parse().ok(),
+            "ActiveEnterTimestamp" => detail.active_since = set,
+            "FragmentPath" => detail.fragment_path = set,
+            // "{ path=/usr/sbin/sshd ; argv[]=/usr/sbin/sshd -D ; ignore_errors=no ; … }"
+            "ExecStart" => detail.exec_start.extend(
+                value
+                    .split("argv[]=")
+                    .skip(1)
+                    .filter_map(|rest| rest.split(" ;").next())
+                    .map(|argv| argv.trim().to_string()),
+            ),
+            _ => {}
+        }
+    }
+    if detail.load_state == "not-found" {
+        return Err(anyhow!("{}: no such unit", unit));
+    }
+    detail.unit_file = TokioCommand::new("systemctl")
+        .args(["cat", "--no-pager", "--"])
+        .arg(unit)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .ok()
+        .filter(|out| out.status.success())
+        .map(|out| String::from_utf8_lossy(&out.stdout).into_owned());
+    Ok(detail)
+}
 
 async fn services_list() -> Result<Vec<ServiceInfo>> {
     // Build enabled/disabled map from unit files
@@ -209,6 +5892,7 @@ async fn services_list() -> Result<Vec<ServiceInfo>> {
         .arg("--no-pager")
         .arg("--plain")
         .arg("--full")
+        .kill_on_drop(true)
         .output()
         .await
     {
@@ -241,6 +5925,7 @@ async fn services_list() -> Result<Vec<ServiceInfo>> {
         .arg("--all")
         .arg("--plain")
         .arg("--full")
+        .kill_on_drop(true)
         .output()
         .await
     {
@@ -282,3 +5967,978 @@ async fn services_list() -> Result<Vec<ServiceInfo>> {
 
     Ok(services)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit_args(args: &[&str]) -> LimitArgs {
+        LimitArgs::parse(args.iter().map(|a| a.to_string()))
+    }
+
+    /// A fresh empty directory for one test.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("slarti-remote-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn request_id_of_unparsed_lines() {
+        assert_eq!(request_id(r#"{"cmd":"read_file","id":7,"path":1}"#), 7);
+        assert_eq!(request_id(r#"{"cmd":"read_file","path":"/"}"#), 0);
+        assert_eq!(request_id("not json"), 0);
+    }
+
+    #[test]
+    fn parse_journal_line_reads_journalctl_json() {
+        let line = r#"{"__CURSOR":"s=3b1e6c0c5d2f4f0e9a0b7c1d2e3f4a5b;i=1a2f;b=9f8e7d6c5b4a39281706f5e4d3c2b1a0;m=2e1c3a4b;t=60f1c2d3e4f5a;x=7c6b5a4938271605","__REALTIME_TIMESTAMP":"1700000000123456","__MONOTONIC_TIMESTAMP":"773665355","_BOOT_ID":"9f8e7d6c5b4a39281706f5e4d3c2b1a0","PRIORITY":"6","SYSLOG_FACILITY":"3","SYSLOG_IDENTIFIER":"systemd","_PID":"1","_COMM":"systemd","_SYSTEMD_UNIT":"init.scope","UNIT":"nginx.service","MESSAGE":"Started nginx.service - A high performance web server and a reverse proxy server.","_HOSTNAME":"web1"}"#;
+        let (entry, cursor) = parse_journal_line(&format!("{}\n", line)).unwrap();
+        assert_eq!(
+            entry,
+            JournalEntry {
+                realtime_usec: 1_700_000_000_123_456,
+                priority: Some(6),
+                unit: Some("init.scope".into()),
+                message: "Started nginx.service - A high performance web server and a reverse proxy server.".into(),
+            }
+        );
+        assert_eq!(
+            cursor.as_deref(),
+            Some("s=3b1e6c0c5d2f4f0e9a0b7c1d2e3f4a5b;i=1a2f;b=9f8e7d6c5b4a39281706f5e4d3c2b1a0;m=2e1c3a4b;t=60f1c2d3e4f5a;x=7c6b5a4938271605")
+        );
+    }
+
+    #[test]
+    fn parse_journal_line_decodes_byte_array_messages() {
+        // journalctl exports a message that is not valid UTF-8 as its bytes.
+        let line = r#"{"__REALTIME_TIMESTAMP":"1700000000000000","PRIORITY":"3","MESSAGE":[98,97,100,32,255,10]}"#;
+        let (entry, cursor) = parse_journal_line(line).unwrap();
+        assert_eq!(entry.message, "bad \u{fffd}\n");
+        assert_eq!(entry.priority, Some(3));
+        assert_eq!(entry.unit, None);
+        assert_eq!(cursor, None);
+    }
+
+    #[test]
+    fn parse_journal_line_tolerates_missing_fields_and_rejects_garbage() {
+        let (entry, _) = parse_journal_line("{}").unwrap();
+        assert_eq!(entry.realtime_usec, 0);
+        assert_eq!(entry.priority, None);
+        assert_eq!(entry.message, "");
+        assert!(parse_journal_line("-- No entries --").is_none());
+        assert!(parse_journal_line("").is_none());
+    }
+
+    #[test]
+    fn tail_lines_returns_the_last_lines() {
+        let dir = scratch_dir("tail-lines");
+        let path = dir.join("app.log");
+        let log: String = (1..=5000).map(|i| format!("line {}\n", i)).collect();
+        std::fs::write(&path, &log).unwrap();
+        let (lines, end, _) = tail_lines(path.to_str().unwrap(), 3).unwrap();
+        assert_eq!(lines, ["line 4998", "line 4999", "line 5000"]);
+        assert_eq!(end, log.len() as u64);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn tail_lines_reads_a_bounded_amount_without_line_breaks() {
+        let dir = scratch_dir("tail-unbroken");
+        let path = dir.join("blob.log");
+        let size = TAIL_MAX_BYTES as usize * 2;
+        std::fs::write(&path, vec![b'x'; size]).unwrap();
+        let (lines, end, _) = tail_lines(path.to_str().unwrap(), 10).unwrap();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].len(), TAIL_MAX_BYTES as usize);
+        assert_eq!(end, size as u64);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    // /proc/mdstat of a host with a healthy mirror, a mirror rebuilding onto
+    // a replaced disk and a RAID5 with a failed member.
+    const MDSTAT: &str = r#"Personalities : [raid1] [raid6] [raid5] [raid4]
+md1 : active raid1 sdb2[1] sda2[0]
+      136448 blocks [2/2] [UU]
+
+md2 : active raid1 sdb3[2] sda3[0]
+      129596288 blocks [2/1] [U_]
+      [>....................]  recovery =  2.6% (3468928/129596288) finish=47.5min speed=44228K/sec
+
+md0 : active raid5 sde1[4](F) sdd1[2] sdc1[1] sdb1[0]
+      2930279808 blocks super 1.2 level 5, 512k chunk, algorithm 2 [4/3] [UUU_]
+      bitmap: 0/8 pages [0KB], 65536KB chunk
+
+unused devices: <none>
+"#;
+
+    // `mdadm --detail /dev/md2` for the rebuilding mirror above.
+    const MDADM_DETAIL: &str = r#"/dev/md2:
+           Version : 1.2
+     Creation Time : Mon Mar  4 10:12:31 2024
+        Raid Level : raid1
+        Array Size : 129596288 (123.59 GiB 132.71 GB)
+     Used Dev Size : 129596288 (123.59 GiB 132.71 GB)
+      Raid Devices : 2
+     Total Devices : 2
+       Persistence : Superblock is persistent
+
+     Intent Bitmap : Internal
+
+       Update Time : Tue Mar  5 08:01:12 2024
+             State : clean, degraded, recovering
+    Active Devices : 1
+   Working Devices : 2
+    Failed Devices : 0
+     Spare Devices : 1
+
+Consistency Policy : bitmap
+
+    Rebuild Status : 2% complete
+
+              Name : web1:2  (local to host web1)
+              UUID : 3f1c7a2e:9b0d4e51:6a8f2c13:d47e9b05
+            Events : 1187
+
+    Number   Major   Minor   RaidDevice State
+       0       8        3        0      active sync   /dev/sda3
+       2       8       19        1      spare rebuilding   /dev/sdb3
+"#;
+
+    fn member(device: &str, slot: u32) -> RaidMember {
+        RaidMember {
+            device: device.into(),
+            slot,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parse_mdstat_reads_health_members_and_progress() {
+        let arrays = parse_mdstat(MDSTAT);
+        assert_eq!(
+            arrays.iter().map(|a| a.name.as_str()).collect::<Vec<_>>(),
+            ["md1", "md2", "md0"]
+        );
+        assert_eq!(
+            arrays[0],
+            RaidArray {
+                name: "md1".into(),
+                level: Some("raid1".into()),
+                active: true,
+                size_bytes: 136_448 * 1024,
+                devices: 2,
+                working: 2,
+                members: vec![member("sda2", 0), member("sdb2", 1)],
+                ..Default::default()
+            }
+        );
+        assert!(arrays[1].degraded);
+        assert_eq!((arrays[1].devices, arrays[1].working), (2, 1));
+        assert_eq!(
+            arrays[1].sync,
+            Some(RaidSync {
+                action: "recovery".into(),
+                percent: Some(2.6),
+                finish_secs: Some(2850),
+                speed_bytes_per_sec: Some(44_228 * 1024),
+            })
+        );
+        let md0 = &arrays[2];
+        assert_eq!(md0.level.as_deref(), Some("raid5"));
+        assert_eq!(md0.size_bytes, 2_930_279_808 * 1024);
+        assert_eq!((md0.devices, md0.working), (4, 3));
+        assert!(md0.degraded && md0.sync.is_none());
+        assert_eq!(
+            md0.members.last(),
+            Some(&RaidMember {
+                faulty: true,
+                ..member("sde1", 4)
+            })
+        );
+    }
+
+    #[test]
+    fn parse_mdstat_reads_read_only_and_delayed_arrays() {
+        let text = "md127 : active (auto-read-only) raid1 sdd1[1] sdc1[0]\n      \
+                    976630464 blocks super 1.2 [2/2] [UU]\n      \tresync=DELAYED\n";
+        let arrays = parse_mdstat(text);
+        assert!(arrays[0].read_only && !arrays[0].degraded);
+        assert_eq!(
+            arrays[0].sync,
+            Some(RaidSync {
+                action: "resync".into(),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn mdadm_detail_adds_states_and_uuid() {
+        let mut arrays = parse_mdstat(MDSTAT);
+        let md2 = &mut arrays[1];
+        apply_mdadm_detail(md2, MDADM_DETAIL);
+        assert_eq!(md2.state.as_deref(), Some("clean, degraded, recovering"));
+        assert_eq!(
+            md2.uuid.as_deref(),
+            Some("3f1c7a2e:9b0d4e51:6a8f2c13:d47e9b05")
+        );
+        assert_eq!(
+            md2.members,
+            [
+                RaidMember {
+                    state: Some("active sync".into()),
+                    ..member("sda3", 0)
+                },
+                RaidMember {
+                    spare: true,
+                    rebuilding: true,
+                    state: Some("spare rebuilding".into()),
+                    ..member("sdb3", 2)
+                },
+            ]
+        );
+        // mdstat's progress is kept over the rounded "Rebuild Status".
+        assert_eq!(md2.sync.as_ref().and_then(|s| s.percent), Some(2.6));
+
+        let mut bare = RaidArray {
+            name: "md2".into(),
+            ..Default::default()
+        };
+        apply_mdadm_detail(&mut bare, MDADM_DETAIL);
+        assert!(bare.degraded);
+        assert_eq!(
+            bare.sync,
+            Some(RaidSync {
+                action: "recovery".into(),
+                percent: Some(2.0),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn zpool_status_reads_pools_paragraphs_and_device_tree() {
+        let text = concat!(
+            "  pool: tank\n",
+            " state: DEGRADED\n",
+            "status: One or more devices could not be opened.  Sufficient replicas exist for\n",
+            "\tthe pool to continue functioning in a degraded state.\n",
+            "action: Attach the missing device and online it using 'zpool online'.\n",
+            "   see: https://openzfs.github.io/openzfs-docs/msg/ZFS-8000-2Q\n",
+            "  scan: scrub repaired 0B in 00:00:02 with 0 errors on Sun Mar  3 00:24:03 2024\n",
+            "config:\n",
+            "\n",
+            "\tNAME        STATE     READ WRITE CKSUM\n",
+            "\ttank        DEGRADED     0     0     0\n",
+            "\t  mirror-0  DEGRADED     0     0     0\n",
+            "\t    sda     ONLINE       0     0     0\n",
+            "\t    sdb     UNAVAIL      0     0     0  cannot open\n",
+            "\n",
+            "errors: No known data errors\n",
+        );
+        let pools = parse_zpool_status(text);
+        assert_eq!(pools.len(), 1);
+        let tank = &pools[0];
+        assert_eq!(
+            (tank.name.as_str(), tank.state.as_str()),
+            ("tank", "DEGRADED")
+        );
+        assert_eq!(
+            tank.status.as_deref(),
+            Some(
+                "One or more devices could not be opened.  Sufficient replicas exist for \
+                 the pool to continue functioning in a degraded state."
+            )
+        );
+        assert_eq!(
+            tank.scan.as_deref(),
+            Some("scrub repaired 0B in 00:00:02 with 0 errors on Sun Mar  3 00:24:03 2024")
+        );
+        assert_eq!(tank.errors.as_deref(), Some("No known data errors"));
+        let tree: Vec<(&str, u32, &str)> = tank
+            .devices
+            .iter()
+            .map(|d| (d.name.as_str(), d.depth, d.state.as_str()))
+            .collect();
+        assert_eq!(
+            tree,
+            [
+                ("tank", 0, "DEGRADED"),
+                ("mirror-0", 1, "DEGRADED"),
+                ("sda", 2, "ONLINE"),
+                ("sdb", 2, "UNAVAIL"),
+            ]
+        );
+        assert_eq!(tank.devices[3].note.as_deref(), Some("cannot open"));
+        assert_eq!(tank.devices[2].note, None);
+    }
+
+    #[test]
+    fn btrfs_device_stats_per_device() {
+        let text = "[/dev/sda1].write_io_errs    0\n\
+                    [/dev/sda1].read_io_errs     0\n\
+                    [/dev/sda1].flush_io_errs    0\n\
+                    [/dev/sda1].corruption_errs  3\n\
+                    [/dev/sda1].generation_errs  0\n\
+                    [/dev/sdb1].write_io_errs    0\n\
+                    [/dev/sdb1].read_io_errs     12\n\
+                    [/dev/sdb1].flush_io_errs    0\n\
+                    [/dev/sdb1].corruption_errs  0\n\
+                    [/dev/sdb1].generation_errs  0\n";
+        assert_eq!(
+            parse_btrfs_device_stats(text),
+            [
+                BtrfsDevice {
+                    path: "/dev/sda1".into(),
+                    corruption_errs: 3,
+                    ..Default::default()
+                },
+                BtrfsDevice {
+                    path: "/dev/sdb1".into(),
+                    read_io_errs: 12,
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn btrfs_scrub_in_both_output_formats() {
+        let current = "UUID:             6a2f9c1e-3b7d-4e58-9a0c-1d2e3f4a5b6c\n\
+                       Scrub started:    Sun Mar  3 01:00:01 2024\n\
+                       Status:           finished\n\
+                       Duration:         0:01:12\n\
+                       Total to scrub:   41.23GiB\n\
+                       Rate:             586.35MiB/s\n\
+                       Error summary:    csum=3\n  \
+                       Corrected:      3\n  \
+                       Uncorrectable:  0\n  \
+                       Unverified:     0\n";
+        assert_eq!(
+            parse_btrfs_scrub(current),
+            BtrfsScrub {
+                status: "finished".into(),
+                started: Some("Sun Mar  3 01:00:01 2024".into()),
+                duration: Some("0:01:12".into()),
+                error_summary: Some("csum=3".into()),
+                errors_found: true,
+            }
+        );
+        let old = "scrub status for 6a2f9c1e-3b7d-4e58-9a0c-1d2e3f4a5b6c\n\
+                   \tscrub started at Sun Mar  3 01:00:01 2024 and finished after 00:01:12\n\
+                   \ttotal bytes scrubbed: 41.23GiB with 0 errors\n";
+        assert_eq!(
+            parse_btrfs_scrub(old),
+            BtrfsScrub {
+                status: "finished".into(),
+                started: Some("Sun Mar  3 01:00:01 2024".into()),
+                duration: Some("00:01:12".into()),
+                error_summary: Some("0 errors".into()),
+                errors_found: false,
+            }
+        );
+        assert_eq!(
+            parse_btrfs_scrub("scrub status for 6a2f9c1e\n\tno stats available\n").status,
+            "never"
+        );
+    }
+
+    #[test]
+    fn vgs_and_lvs_rows() {
+        let vgs = "  vg0|107369988096|4194304|2|wz--n-\n  vg1|53682896896|0|1|wz-pn-\n";
+        let lvs = "  vg0|root|32212254720|linear|-wi-ao----||\n  \
+                   vg0|pool|53687091200|thin-pool|twi-aotz--|81.50|\n  \
+                   vg0|data|21474836480|thin|Vwi-aotz--|12.00|pool\n  \
+                   vg1|backup|53682896896|raid1|rwi-a-r-p-||\n  \
+                   vg9|stray|1|linear|-wi-a-----||\n";
+        let mut groups = parse_vgs(vgs);
+        parse_lvs(lvs, &mut groups);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(
+            (
+                groups[0].size_bytes,
+                groups[0].free_bytes,
+                groups[0].pv_count
+            ),
+            (107_369_988_096, 4_194_304, 2)
+        );
+        assert!(!groups[0].partial && groups[1].partial);
+        assert_eq!(
+            groups[0].volumes[2],
+            LvmVolume {
+                name: "data".into(),
+                size_bytes: 21_474_836_480,
+                kind: "thin".into(),
+                active: true,
+                data_percent: Some(12.0),
+                pool: Some("pool".into()),
+                health: None,
+            }
+        );
+        assert_eq!(groups[0].volumes[0].data_percent, None);
+        assert_eq!(groups[1].volumes[0].health.as_deref(), Some("partial"));
+    }
+
+    #[test]
+    fn proc_modules_lines() {
+        let text = "nf_nat 49152 1 nft_chain_nat, Live 0x0000000000000000\n\
+                    nft_chain_nat 12288 1 - Live 0x0000000000000000\n\
+                    zfs 6152192 6 - Live 0x0000000000000000 (POE)\n\
+                    crc16 12288 2 ext4,jbd2, Live 0xffffffffc0a1b000\n\
+                    nls_ascii 12288 1 [permanent], Live 0x0000000000000000\n\
+                    kvm_intel 409600 - - Unloading 0x0000000000000000\n";
+        let modules = parse_proc_modules(text);
+        assert_eq!(
+            modules[0],
+            KernelModule {
+                name: "nf_nat".into(),
+                size_bytes: 49_152,
+                use_count: Some(1),
+                used_by: vec!["nft_chain_nat".into()],
+                state: "Live".into(),
+                taint: None,
+            }
+        );
+        assert!(modules[1].used_by.is_empty());
+        assert_eq!(modules[2].taint.as_deref(), Some("POE"));
+        assert_eq!(modules[3].used_by, ["ext4", "jbd2"]);
+        assert!(modules[4].used_by.is_empty());
+        assert_eq!(
+            (modules[5].use_count, modules[5].state.as_str()),
+            (None, "Unloading")
+        );
+    }
+
+    #[test]
+    fn routes_from_ip_json_and_proc() {
+        let json = r#"[{"dst":"default","gateway":"192.0.2.1","dev":"eth0","flags":[]},{"dst":"192.0.2.0/24","dev":"eth0","protocol":"kernel","scope":"link","prefsrc":"192.0.2.2","flags":[]}]"#;
+        let routes = parse_ip_routes(json, false).unwrap();
+        assert_eq!(
+            routes[1],
+            Route {
+                destination: "192.0.2.0/24".into(),
+                gateway: None,
+                dev: "eth0".into(),
+                metric: None,
+                protocol: Some("kernel".into()),
+                source: Some("192.0.2.2".into()),
+                ipv6: false,
+            }
+        );
+        assert_eq!(routes[0].gateway.as_deref(), Some("192.0.2.1"));
+        assert!(parse_ip_routes("Object \"route\" is unknown", false).is_none());
+
+        let proc = concat!(
+            "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT\n",
+            "eth0\t00000000\t010200C0\t0003\t0\t0\t0\t00000000\t0\t0\t0\n",
+            "eth0\t000200C0\t00000000\t0001\t0\t0\t0\t00FFFFFF\t0\t0\t0\n",
+            "eth1\t0000A8C0\t00000000\t0000\t0\t0\t0\t0000FFFF\t0\t0\t0\n",
+        );
+        let routes = parse_proc_routes(proc);
+        let summary: Vec<(&str, Option<&str>)> = routes
+            .iter()
+            .map(|r| (r.destination.as_str(), r.gateway.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            [("default", Some("192.0.2.1")), ("192.0.2.0/24", None)]
+        );
+        assert_eq!(routes[0].metric, Some(0));
+    }
+
+    #[test]
+    fn proc_ipv6_routes_skip_local_reject_and_multicast() {
+        let text = "\
+fd000000000000000000000000000000 40 00000000000000000000000000000000 00 00000000000000000000000000000000 00000100 00000001 00000000 00000001     eth0
+fe800000000000000000000000000000 40 00000000000000000000000000000000 00 00000000000000000000000000000000 00000100 00000002 00000000 00000001     eth0
+00000000000000000000000000000000 00 00000000000000000000000000000000 00 fd000000000000000000000000000001 00000400 00000001 00000000 00000003     eth0
+00000000000000000000000000000001 80 00000000000000000000000000000000 00 00000000000000000000000000000000 00000000 00000003 00000000 80200001       lo
+fd000000000000000000000000000002 80 00000000000000000000000000000000 00 00000000000000000000000000000000 00000000 00000002 00000000 80200001     eth0
+ff000000000000000000000000000000 08 00000000000000000000000000000000 00 00000000000000000000000000000000 00000100 00000004 00000000 00000001     eth0
+00000000000000000000000000000000 00 00000000000000000000000000000000 00 00000000000000000000000000000000 ffffffff 00000001 00000000 00200200       lo
+";
+        let routes = parse_proc_ipv6_routes(text);
+        let summary: Vec<(&str, Option<&str>, Option<u32>)> = routes
+            .iter()
+            .map(|r| (r.destination.as_str(), r.gateway.as_deref(), r.metric))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("fd00::/64", None, Some(256)),
+                ("fe80::/64", None, Some(256)),
+                ("default", Some("fd00::1"), Some(1024)),
+            ]
+        );
+        assert!(routes.iter().all(|r| r.ipv6 && r.dev == "eth0"));
+    }
+
+    #[test]
+    fn proc_net_listeners_and_addresses() {
+        assert_eq!(
+            parse_proc_net_address("0100007F:0016"),
+            Some(("127.0.0.1".into(), 22))
+        );
+        assert_eq!(
+            parse_proc_net_address("00000000000000000000000001000000:0277"),
+            Some(("::1".into(), 631))
+        );
+        assert_eq!(parse_proc_net_address("0100007F"), None);
+
+        let tcp = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n   \
+                   0: 00000000:07E8 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 662 1 0000000012a775e8 100 0 0 10 0\n   \
+                   1: 0100007F:BC8F 00000000:0000 0A 00000000:00000000 00:00000000 00000000 65534        0 1075 1 000000004f203f03 100 0 0 10 0\n   \
+                   2: 0100007F:ACE8 0100007F:BC8F 01 00000000:00000000 02:000012A5 00000000     0        0 281042 2 00000000c987ed74 20 4 0 18 -1\n";
+        let found: Vec<(u64, String, u16)> = parse_proc_net_listeners(tcp, "tcp", "0A")
+            .into_iter()
+            .map(|(inode, l)| (inode, l.address, l.port))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (662, "0.0.0.0".into(), 2024),
+                (1075, "127.0.0.1".into(), 48271)
+            ]
+        );
+
+        let udp = "   sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode ref pointer drops\n  \
+                   123: 00000000:0044 00000000:0000 07 00000000:00000000 00:00000000 00000000     0        0 15032 2 0000000000000000 0\n  \
+                   124: 0100007F:A1B2 0100007F:0035 07 00000000:00000000 00:00000000 00000000     0        0 15033 2 0000000000000000 0\n";
+        let found = parse_proc_net_listeners(udp, "udp", "07");
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].1.protocol.as_str(), found[0].1.port), ("udp", 68));
+        assert_eq!(tcp_state("0A"), "LISTEN");
+        assert_eq!(tcp_state("06"), "TIME_WAIT");
+    }
+
+    #[test]
+    fn proc_stat_ticks() {
+        let stat = "cpu  541169 0 79771 1776805 5968 0 48 24062 0 0\n\
+                    cpu0 541169 0 79771 1776805 5968 0 48 24062 0 0\n\
+                    intr 2715735 0 0 0\n";
+        assert_eq!(cpu_total_ticks(stat), 2_427_823);
+        // busy = total - idle - iowait
+        assert_eq!(parse_core_ticks(stat), [(645_050, 2_427_823)]);
+        assert_eq!(cpu_total_ticks("intr 1 2 3\n"), 0);
+
+        let kthread = "14 (ksoftirqd/0) S 2 0 0 0 -1 69238848 0 0 0 0 14 195 0 0 20 0 1 0 5 0 0 \
+                       18446744073709551615 0 0 0 0 0 0 0 2147483647 0 1 0 0 17 0 0 0 0 0 0 0 0 0 0 0 0 0 0";
+        assert_eq!(parse_pid_stat(kthread), Some(("ksoftirqd/0".into(), 209)));
+        let odd =
+            "4242 (tmux: (server)) S 1 4242 4242 0 -1 4194368 2219 0 0 0 150 73 0 0 20 0 1 0 5321";
+        assert_eq!(parse_pid_stat(odd), Some(("tmux: (server)".into(), 223)));
+        assert_eq!(parse_pid_stat(""), None);
+    }
+
+    #[test]
+    fn net_dev_and_meminfo() {
+        let dev = "Inter-|   Receive                                                |  Transmit\n \
+                   face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed\n    \
+                   lo: 553343018   54598    0    0    0     0          0         0 553343018   54598    0    0    0     0       0          0\n  \
+                   ifb0:       0       0    0    0    0     0          0         0        0       0    0    0    0     0       0          0\n  \
+                   eth0:   33304     520    0    0    0     0          0         0    46237     542    0    0    0     0       0          0\n";
+        assert_eq!(
+            parse_net_dev(dev),
+            [
+                ("ifb0".to_string(), 0, 0),
+                ("eth0".to_string(), 33_304, 46_237)
+            ]
+        );
+
+        let meminfo = "MemTotal:        6158152 kB\n\
+                       MemFree:          380256 kB\n\
+                       MemAvailable:    5523440 kB\n\
+                       SwapTotal:             0 kB\n";
+        assert_eq!(meminfo_bytes(meminfo, "MemTotal:"), 6_158_152 * 1024);
+        assert_eq!(meminfo_bytes(meminfo, "MemAvailable:"), 5_523_440 * 1024);
+        assert_eq!(meminfo_bytes(meminfo, "SwapFree:"), 0);
+    }
+
+    #[test]
+    fn pressure_and_oom_kills() {
+        let cpu = "some avg10=1.60 avg60=3.48 avg300=2.97 total=677643859\n\
+                   full avg10=0.00 avg60=0.00 avg300=0.00 total=0\n";
+        let stall = parse_pressure(cpu).unwrap();
+        assert_eq!(
+            stall.some,
+            PressureAverages {
+                avg10: 1.6,
+                avg60: 3.48,
+                avg300: 2.97,
+                total_usec: 677_643_859,
+            }
+        );
+        assert_eq!(stall.full, Some(PressureAverages::default()));
+        // CPU pressure before 5.13 has no "full" line.
+        let old = parse_pressure("some avg10=0.00 avg60=0.00 avg300=0.00 total=5754185\n").unwrap();
+        assert_eq!((old.some.total_usec, old.full), (5_754_185, None));
+        assert_eq!(parse_pressure(""), None);
+
+        let entry = |message: &str| JournalEntry {
+            realtime_usec: 1_700_000_000_000_000,
+            message: message.to_string(),
+            ..Default::default()
+        };
+        let kill = parse_oom_kill(entry(
+            "Out of memory: Killed process 4242 (java) total-vm:8123456kB, anon-rss:6012344kB, \
+             file-rss:0kB, shmem-rss:0kB, UID:1000 pgtables:12345kB oom_score_adj:0",
+        ))
+        .unwrap();
+        assert_eq!((kill.pid, kill.comm.as_deref()), (Some(4242), Some("java")));
+        let cgroup = parse_oom_kill(entry(
+            "Memory cgroup out of memory: Killed process 999 (node) total-vm:1204480kB, \
+             anon-rss:524288kB, file-rss:0kB, shmem-rss:0kB, UID:0 pgtables:1200kB oom_score_adj:0",
+        ))
+        .unwrap();
+        assert_eq!(
+            (cgroup.pid, cgroup.comm.as_deref()),
+            (Some(999), Some("node"))
+        );
+        assert!(parse_oom_kill(entry(
+            "oom_reaper: reaped process 4242 (java), now anon-rss:0kB, file-rss:0kB, shmem-rss:0kB"
+        ))
+        .is_none());
+    }
+
+    #[test]
+    fn cgroup_tree_from_a_hierarchy() {
+        let root = scratch_dir("cgroup");
+        let slice = root.join("system.slice");
+        let service = slice.join("nginx.service");
+        std::fs::create_dir_all(&service).unwrap();
+        std::fs::write(
+            root.join("cpu.stat"),
+            "usage_usec 900000\nuser_usec 600000\nsystem_usec 300000\n",
+        )
+        .unwrap();
+        std::fs::write(
+            service.join("cpu.stat"),
+            "usage_usec 150000\nuser_usec 100000\nsystem_usec 50000\nnr_periods 0\n",
+        )
+        .unwrap();
+        std::fs::write(service.join("memory.current"), "8388608\n").unwrap();
+        std::fs::write(service.join("cgroup.procs"), "812\n813\n").unwrap();
+
+        let mut before = HashMap::new();
+        cgroup_cpu_usage(&root, &mut before);
+        assert_eq!(before.get(&service), Some(&150_000));
+        assert_eq!(before.get(&slice), None);
+        std::fs::write(service.join("cpu.stat"), "usage_usec 200000\n").unwrap();
+
+        let tree = cgroup_node(&root, &root, &before, 100_000.0);
+        assert_eq!((tree.name.as_str(), tree.path.as_str()), ("/", "/"));
+        let node = &tree.children[0].children[0];
+        assert_eq!(node.name, "nginx.service");
+        assert_eq!(node.path, "/system.slice/nginx.service");
+        assert_eq!(node.cpu_percent, Some(50.0));
+        assert_eq!(node.memory_bytes, Some(8_388_608));
+        assert_eq!(node.processes, 2);
+        assert_eq!(tree.children[0].cpu_percent, None);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn utmp_addresses() {
+        assert_eq!(utmp_address([0; 4]), None);
+        let v4 = u32::from_ne_bytes([203, 0, 113, 7]);
+        assert_eq!(utmp_address([v4, 0, 0, 0]).as_deref(), Some("203.0.113.7"));
+        let v6 = "2001:db8::1"
+            .parse::<std::net::Ipv6Addr>()
+            .unwrap()
+            .octets();
+        let words: Vec<u32> = v6
+            .chunks(4)
+            .map(|c| u32::from_ne_bytes(c.try_into().unwrap()))
+            .collect();
+        assert_eq!(
+            utmp_address(words.try_into().unwrap()).as_deref(),
+            Some("2001:db8::1")
+        );
+    }
+
+    #[test]
+    fn auth_lines_and_failures() {
+        let line = "1714534201.500000 web1 sshd[812]: Failed password for invalid user admin \
+                    from 203.0.113.7 port 50514 ssh2";
+        let (at, message) = parse_auth_line(line, 2024).unwrap();
+        assert_eq!(at, 1_714_534_201_500_000);
+        assert_eq!(
+            parse_auth_failure(message),
+            Some((Some("admin"), Some("203.0.113.7")))
+        );
+        // Syslog and RFC 3339 stamps are both local time.
+        let syslog = "May  1 03:30:01 web1 sshd[812]: Failed publickey for root from 2001:db8::7 port 40022 ssh2";
+        let rfc3339 = "2024-05-01T03:30:01.123456+02:00 web1 sshd[812]: Failed publickey for root from 2001:db8::7 port 40022 ssh2";
+        let (syslog_at, message) = parse_auth_line(syslog, 2024).unwrap();
+        assert_eq!(parse_auth_line(rfc3339, 1999).unwrap().0, syslog_at);
+        assert_eq!(
+            parse_auth_failure(message),
+            Some((Some("root"), Some("2001:db8::7")))
+        );
+        assert!(parse_auth_line("-- Boot 4f1c… --", 2024).is_none());
+
+        let su = "pam_unix(su:auth): authentication failure; logname=bob uid=1000 euid=0 \
+                  tty=/dev/pts/0 ruser=bob rhost=  user=root";
+        assert_eq!(parse_auth_failure(su), Some((Some("root"), None)));
+        let sshd_pam = "pam_unix(sshd:auth): authentication failure; logname= uid=0 euid=0 \
+                        tty=ssh ruser= rhost=203.0.113.7  user=root";
+        assert_eq!(parse_auth_failure(sshd_pam), None);
+        assert_eq!(
+            parse_auth_failure("Accepted publickey for bob from 198.51.100.4 port 4242 ssh2"),
+            None
+        );
+    }
+
+    #[test]
+    fn fail2ban_jail_status() {
+        let text = "Status for the jail: sshd\n\
+                    |- Filter\n\
+                    |  |- Currently failed:\t2\n\
+                    |  |- Total failed:\t57\n\
+                    |  `- File list:\t/var/log/auth.log\n\
+                    `- Actions\n   \
+                    |- Currently banned:\t2\n   \
+                    |- Total banned:\t9\n   \
+                    `- Banned IP list:\t203.0.113.7 198.51.100.23\n";
+        assert_eq!(
+            parse_fail2ban_jail("sshd", text),
+            Fail2banJail {
+                name: "sshd".into(),
+                currently_failed: 2,
+                total_failed: 57,
+                currently_banned: 2,
+                total_banned: 9,
+                banned: vec!["203.0.113.7".into(), "198.51.100.23".into()],
+            }
+        );
+    }
+
+    #[test]
+    fn docker_and_podman_container_lists() {
+        let docker = r#"{"Command":"\"/docker-entrypoint.…\"","CreatedAt":"2024-03-01 10:00:00 +0000 UTC","ID":"3f2a9c1e7b4d","Image":"nginx:1.25","Labels":"","LocalVolumes":"0","Mounts":"","Names":"web","Networks":"bridge","Ports":"0.0.0.0:8080->80/tcp, :::8080->80/tcp","RunningFor":"2 days ago","Size":"0B","State":"running","Status":"Up 2 days"}"#;
+        assert_eq!(
+            parse_docker_container(docker),
+            Some(ContainerInfo {
+                name: "web".into(),
+                image: "nginx:1.25".into(),
+                state: "running".into(),
+                ports: vec!["0.0.0.0:8080->80/tcp".into(), ":::8080->80/tcp".into()],
+                runtime: "docker".into(),
+            })
+        );
+        // Docker before 20.10 has only a Status.
+        let old = r#"{"ID":"9b1d","Image":"redis:6","Names":"cache","Ports":"","Status":"Exited (0) 3 hours ago"}"#;
+        let old = parse_docker_container(old).unwrap();
+        assert_eq!((old.state.as_str(), old.ports.len()), ("exited", 0));
+        assert!(parse_docker_container("Cannot connect to the Docker daemon").is_none());
+
+        let podman = r#"[{"AutoRemove":false,"Command":["postgres"],"Id":"c0ffee","Image":"docker.io/library/postgres:16","Names":["db"],"Ports":[{"host_ip":"","container_port":5432,"host_port":5432,"range":1,"protocol":"tcp"}],"State":"running","Status":"Up 5 minutes"},{"Id":"beef","Image":"localhost/app:latest","Names":["app"],"Ports":[{"host_ip":"127.0.0.1","container_port":9000,"host_port":9000,"range":3,"protocol":"udp"}],"State":"exited"}]"#;
+        let found = parse_podman_containers(podman);
+        assert_eq!(found[0].ports, ["0.0.0.0:5432->5432/tcp"]);
+        assert_eq!(found[0].runtime, "podman");
+        assert_eq!(found[1].state, "exited");
+        assert_eq!(found[1].ports, ["127.0.0.1:9000-9002->9000-9002/udp"]);
+        assert!(parse_podman_containers("").is_empty());
+    }
+
+    #[tokio::test]
+    async fn upload_through_a_symlink_replaces_its_target() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = scratch_dir("upload-link");
+        let real = dir.join("real.conf");
+        let link = dir.join("link.conf");
+        std::fs::write(&real, "old").unwrap();
+        std::fs::set_permissions(&real, std::fs::Permissions::from_mode(0o640)).unwrap();
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        let path = link.to_string_lossy().into_owned();
+        let mut upload = Upload::begin(1, path, None).await.unwrap();
+        let temp_mode = std::fs::metadata(&upload.temp)
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(temp_mode & 0o777, 0o640);
+        upload
+            .append(&slarti_proto::base64::encode(b"new"))
+            .await
+            .unwrap();
+        assert_eq!(upload.finish().await.unwrap(), 3);
+
+        assert!(std::fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(std::fs::read_to_string(&real).unwrap(), "new");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn parse_size_reads_binary_suffixes() {
+        assert_eq!(parse_size("512"), Some(512));
+        assert_eq!(parse_size("512K"), Some(512 << 10));
+        assert_eq!(parse_size(" 256m "), Some(256 << 20));
+        assert_eq!(parse_size("1G"), Some(1 << 30));
+        for bad in ["", "0", "0M", "M", "1.5G", "-1M", "12T", "99999999999G"] {
+            assert_eq!(parse_size(bad), None, "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn limit_args_defaults_to_the_command_timeout_only() {
+        let args = limit_args(&["--stdio", "--read-only"]);
+        assert_eq!(args.memory_bytes, None);
+        assert_eq!(args.cpu_percent, None);
+        assert_eq!(args.nice, None);
+        assert_eq!(args.command_timeout, Some(DEFAULT_COMMAND_TIMEOUT));
+        assert!(args.errors.is_empty());
+    }
+
+    #[test]
+    fn limit_args_parses_separate_and_inline_values() {
+        let args = limit_args(&[
+            "--stdio",
+            "--max-memory",
+            "256M",
+            "--max-cpu=25%",
+            "--nice",
+            "-5",
+            "--command-timeout=0",
+        ]);
+        assert_eq!(args.memory_bytes, Some(256 << 20));
+        assert_eq!(args.cpu_percent, Some(25));
+        assert_eq!(args.nice, Some(-5));
+        assert_eq!(args.command_timeout, None);
+        assert!(args.errors.is_empty());
+
+        let args = limit_args(&["--command-timeout", "120"]);
+        assert_eq!(
+            args.command_timeout,
+            Some(std::time::Duration::from_secs(120))
+        );
+    }
+
+    #[test]
+    fn limit_args_notes_invalid_and_missing_values() {
+        let args = limit_args(&[
+            "--max-memory",
+            "lots",
+            "--max-cpu=0",
+            "--nice",
+            "20",
+            "--command-timeout",
+        ]);
+        assert_eq!(args.memory_bytes, None);
+        assert_eq!(args.cpu_percent, None);
+        assert_eq!(args.nice, None);
+        assert_eq!(args.command_timeout, Some(DEFAULT_COMMAND_TIMEOUT));
+        assert_eq!(
+            args.errors,
+            [
+                "ignored --max-memory \"lots\": invalid value",
+                "ignored --max-cpu \"0\": invalid value",
+                "ignored --nice \"20\": invalid value",
+                "--command-timeout needs a value",
+            ]
+        );
+    }
+
+    fn session() -> Session {
+        Session::new(
+            tokio::sync::mpsc::channel(1).0,
+            AgentLimits::default(),
+            false,
+        )
+    }
+
+    fn service(name: &str, active: &str) -> ServiceInfo {
+        ServiceInfo {
+            name: name.into(),
+            active_state: active.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn services_delta_sends_only_changes_against_the_last_token() {
+        let mut session = session();
+        let first =
+            session.services_delta(None, vec![service("a", "active"), service("b", "active")]);
+        assert!(first.full);
+        assert_eq!(first.changed.len(), 2);
+
+        let second = session.services_delta(
+            Some(first.token),
+            vec![service("a", "failed"), service("c", "active")],
+        );
+        assert!(!second.full);
+        assert_ne!(second.token, first.token);
+        assert_eq!(
+            second.changed,
+            vec![service("a", "failed"), service("c", "active")]
+        );
+        assert_eq!(second.removed, vec!["b".to_string()]);
+
+        let third = session.services_delta(
+            Some(second.token),
+            vec![service("a", "failed"), service("c", "active")],
+        );
+        assert!(!third.full);
+        assert!(third.changed.is_empty() && third.removed.is_empty());
+
+        // A token the agent no longer holds gets the whole list.
+        let stale = session.services_delta(Some(first.token), vec![service("a", "failed")]);
+        assert!(stale.full);
+        assert_eq!(stale.changed, vec![service("a", "failed")]);
+    }
+
+    #[test]
+    fn listeners_delta_keys_on_protocol_address_and_port() {
+        let listener = |protocol: &str, port: u16, process: &str| NetListener {
+            protocol: protocol.into(),
+            address: "0.0.0.0".into(),
+            port,
+            pid: None,
+            process: Some(process.into()),
+        };
+        let mut session = session();
+        let first = session.listeners_delta(
+            None,
+            vec![
+                listener("tcp", 22, "sshd"),
+                listener("tcp", 80, "nginx"),
+                listener("udp", 80, "x"),
+            ],
+        );
+        assert!(first.full);
+
+        let mut listeners = first.changed.clone();
+        let current = vec![
+            listener("tcp", 22, "dropbear"),
+            listener("udp", 80, "x"),
+            listener("tcp", 443, "nginx"),
+        ];
+        let second = session.listeners_delta(Some(first.token), current.clone());
+        assert!(!second.full);
+        assert_eq!(
+            second.changed,
+            vec![
+                listener("tcp", 22, "dropbear"),
+                listener("tcp", 443, "nginx")
+            ]
+        );
+        assert_eq!(
+            second.removed,
+            vec![NetListener {
+                protocol: "tcp".into(),
+                address: "0.0.0.0".into(),
+                port: 80,
+                ..Default::default()
+            }]
+        );
+        second.apply(&mut listeners);
+        assert_eq!(listeners, current);
+    }
+}
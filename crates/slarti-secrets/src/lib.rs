@@ -0,0 +1,247 @@
+/*!
+Secret-manager integration for Slarti.
+
+Secrets are never written to slarti's state files. Instead they are named by a
+*reference* and resolved on demand through an external secret manager:
+
+- `pass:<path>` — first line of `pass show <path>`
+- `op://<vault>/<item>/<field>` — `op read <reference>` (1Password CLI)
+- `secret-tool:<attr>=<value>[,<attr>=<value>...]` — `secret-tool lookup ...` (libsecret)
+
+slarti-state uses this to keep the state encryption key in the OS keychain.
+Resolved values are only kept in memory as `Secret`, whose `Debug` output is
+redacted and whose buffer is zeroized on drop.
+*/
+
+use anyhow::{anyhow, bail, Context, Result};
+use std::fmt;
+use std::process::Stdio;
+use std::str::FromStr;
+use tokio::process::Command;
+use zeroize::{Zeroize, Zeroizing};
+
+/// A reference to a secret held by an external secret manager.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SecretRef {
+    /// `pass` password-store entry path.
+    Pass(String),
+    /// 1Password secret reference (`op://vault/item/field`).
+    OnePassword(String),
+    /// libsecret lookup attributes.
+    Libsecret(Vec<(String, String)>),
+}
+
+impl SecretRef {
+    /// Name of the CLI used to resolve this reference.
+    pub fn program(&self) -> &'static str {
+        match self {
+            SecretRef::Pass(_) => "pass",
+            SecretRef::OnePassword(_) => "op",
+            SecretRef::Libsecret(_) => "secret-tool",
+        }
+    }
+}
+
+impl FromStr for SecretRef {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if s.starts_with("op://") {
+            return Ok(SecretRef::OnePassword(s.to_string()));
+        }
+        if let Some(path) = s.strip_prefix("pass:") {
+            if path.is_empty() {
+                bail!("empty pass path");
+            }
+            return Ok(SecretRef::Pass(path.to_string()));
+        }
+        if let Some(attrs) = s.strip_prefix("secret-tool:") {
+            let attrs = attrs
+                .split(',')
+                .map(|kv| {
+                    kv.split_once('=')
+                        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+                        .ok_or_else(|| anyhow!("expected attr=value, got {:?}", kv))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            if attrs.is_empty() {
+                bail!("secret-tool reference needs at least one attribute");
+            }
+            return Ok(SecretRef::Libsecret(attrs));
+        }
+        Err(anyhow!(
+            "unsupported secret reference {:?} (expected pass:, op:// or secret-tool:)",
+            s
+        ))
+    }
+}
+
+impl fmt::Display for SecretRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecretRef::Pass(path) => write!(f, "pass:{}", path),
+            SecretRef::OnePassword(r) => write!(f, "{}", r),
+            SecretRef::Libsecret(attrs) => {
+                let attrs: Vec<String> =
+                    attrs.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+                write!(f, "secret-tool:{}", attrs.join(","))
+            }
+        }
+    }
+}
+
+/// A resolved secret value. `Debug` is redacted and the buffer is wiped on drop.
+#[derive(Clone)]
+pub struct Secret(Zeroizing<String>);
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Self {
+        Secret(Zeroizing::new(value.into()))
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(****)")
+    }
+}
+
+/// Resolve a reference by running the corresponding secret-manager CLI.
+pub async fn resolve(reference: &SecretRef) -> Result<Secret> {
+    let mut cmd = Command::new(reference.program());
+    match reference {
+        SecretRef::Pass(path) => {
+            cmd.arg("show").arg(path);
+        }
+        SecretRef::OnePassword(r) => {
+            cmd.arg("read").arg("--no-newline").arg(r);
+        }
+        SecretRef::Libsecret(attrs) => {
+            cmd.arg("lookup");
+            for (k, v) in attrs {
+                cmd.arg(k).arg(v);
+            }
+        }
+    }
+    let out = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .await
+        .with_context(|| format!("failed to run {}", reference.program()))?;
+    if !out.status.success() {
+        bail!(
+            "{} failed for {}: {}",
+            reference.program(),
+            reference,
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+    }
+    let mut stdout = String::from_utf8(out.stdout).context("secret is not valid UTF-8")?;
+    // pass stores the password on the first line; the others have no trailing data.
+    let value = match reference {
+        SecretRef::Pass(_) => stdout.lines().next().unwrap_or_default().to_string(),
+        _ => stdout.trim_end_matches(['\r', '\n']).to_string(),
+    };
+    // Wipe the raw output too.
+    stdout.zeroize();
+    Ok(Secret::new(value))
+}
+
+/// Store `value` in the OS keychain (libsecret) under `attrs`, replacing any existing item.
+pub async fn store_libsecret(attrs: &[(&str, &str)], label: &str, value: &Secret) -> Result<()> {
+    let mut cmd = Command::new("secret-tool");
+    cmd.arg("store").arg(format!("--label={}", label));
+    for (k, v) in attrs {
+        cmd.arg(k).arg(v);
+    }
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("failed to run secret-tool")?;
+    // secret-tool reads the value from stdin so it never appears in argv.
+    if let Some(mut stdin) = child.stdin.take() {
+        use tokio::io::AsyncWriteExt;
+        stdin.write_all(value.expose().as_bytes()).await?;
+    }
+    let out = child.wait_with_output().await?;
+    if !out.status.success() {
+        bail!(
+            "secret-tool store failed: {}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_each_reference_kind() {
+        assert_eq!(
+            "pass:hosts/web1/sudo".parse::<SecretRef>().unwrap(),
+            SecretRef::Pass("hosts/web1/sudo".into())
+        );
+        assert_eq!(
+            " op://ops/grafana/token ".parse::<SecretRef>().unwrap(),
+            SecretRef::OnePassword("op://ops/grafana/token".into())
+        );
+        assert_eq!(
+            "secret-tool:service=slarti, key=state"
+                .parse::<SecretRef>()
+                .unwrap(),
+            SecretRef::Libsecret(vec![
+                ("service".into(), "slarti".into()),
+                ("key".into(), "state".into()),
+            ])
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_references() {
+        for bad in ["pass:", "secret-tool:service", "vault:foo", ""] {
+            assert!(bad.parse::<SecretRef>().is_err(), "{bad:?} parsed");
+        }
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for text in [
+            "pass:hosts/web1/sudo",
+            "op://ops/grafana/token",
+            "secret-tool:service=slarti,key=state",
+        ] {
+            let reference: SecretRef = text.parse().unwrap();
+            assert_eq!(reference.to_string(), text);
+            assert_eq!(
+                reference.to_string().parse::<SecretRef>().unwrap(),
+                reference
+            );
+        }
+    }
+
+    #[test]
+    fn program_matches_the_manager() {
+        assert_eq!(SecretRef::Pass("x".into()).program(), "pass");
+        assert_eq!(SecretRef::OnePassword("op://a/b/c".into()).program(), "op");
+        assert_eq!(SecretRef::Libsecret(vec![]).program(), "secret-tool");
+    }
+
+    #[test]
+    fn secret_debug_is_redacted() {
+        let secret = Secret::new("hunter2");
+        assert_eq!(secret.expose(), "hunter2");
+        assert_eq!(format!("{:?}", secret), "Secret(****)");
+        assert!(!format!("{:?}", Some(secret.clone())).contains("hunter2"));
+    }
+}
@@ -0,0 +1,265 @@
+//! SSH_ASKPASS bridge: surface ssh passphrase and security-key prompts in the app.
+//!
+//! Without a terminal, ssh either fails (BatchMode) or writes prompts such as
+//! "Enter passphrase for key" or "Confirm user presence for key" to an inherited
+//! stderr nobody sees. Instead, the app starts a small server on a unix socket
+//! (`start`) and every ssh spawned by this crate runs with `SSH_ASKPASS` pointing
+//! at the app's own executable. When ssh needs input it runs that executable as
+//! a helper (`run_helper`), which forwards the prompt over the socket and prints
+//! the user's answer back to ssh.
+
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, OnceLock};
+use tokio::process::Command as TokioCommand;
+
+/// Environment variable carrying the askpass socket path to the helper process.
+pub const SOCKET_ENV: &str = "SLARTI_ASKPASS_SOCK";
+
+/// Socket path of the running server (set by `start`).
+static SOCKET: OnceLock<PathBuf> = OnceLock::new();
+
+/// What ssh expects from a prompt (derived from `SSH_ASKPASS_PROMPT`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PromptKind {
+    /// Secret entry, e.g. a key passphrase or PIN.
+    Secret,
+    /// Yes/no confirmation (`SSH_ASKPASS_PROMPT=confirm`).
+    Confirm,
+    /// Informational only, e.g. "touch your security key" (`SSH_ASKPASS_PROMPT=none`);
+    /// ssh dismisses it by killing the helper once the operation completes.
+    Notify,
+}
+
+impl PromptKind {
+    fn from_env(value: Option<&str>) -> Self {
+        match value {
+            Some("confirm") => PromptKind::Confirm,
+            Some("none") => PromptKind::Notify,
+            _ => PromptKind::Secret,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AskpassRequest {
+    /// Prompt text as provided by ssh.
+    pub prompt: String,
+    pub kind: PromptKind,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct AskpassReply {
+    /// `None` means the user cancelled.
+    answer: Option<String>,
+}
+
+/// A prompt waiting for the user. Dropping it without answering cancels it.
+pub struct PendingPrompt {
+    pub request: AskpassRequest,
+    reply: mpsc::Sender<Option<String>>,
+    done: Arc<AtomicBool>,
+}
+
+impl PendingPrompt {
+    /// Send the answer (`None` to cancel) back to ssh.
+    pub fn answer(self, answer: Option<String>) {
+        let _ = self.reply.send(answer);
+    }
+
+    /// True once ssh no longer waits for this prompt (helper exited or was killed).
+    pub fn is_done(&self) -> bool {
+        self.done.load(Ordering::SeqCst)
+    }
+}
+
+/// Start the askpass server for this process and return the stream of prompts.
+///
+/// Subsequent ssh invocations made by this crate are configured to use it.
+/// The socket lives in the private runtime dir, is mode 0600 and only serves
+/// processes of this user.
+pub fn start() -> std::io::Result<mpsc::Receiver<PendingPrompt>> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut path = crate::runtime_dir()?;
+    path.push(format!("askpass-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path)?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let tx = tx.clone();
+            std::thread::spawn(move || {
+                if let Err(e) = serve(stream, &tx) {
+                    tracing::debug!(target: "slarti_ssh", "askpass: {}", e);
+                }
+            });
+        }
+    });
+    let _ = SOCKET.set(path);
+    Ok(rx)
+}
+
+fn serve(stream: UnixStream, tx: &mpsc::Sender<PendingPrompt>) -> std::io::Result<()> {
+    // SAFETY: geteuid has no arguments and cannot fail.
+    let uid = unsafe { libc::geteuid() };
+    let peer = peer_uid(&stream)?;
+    if peer != uid {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            format!("refusing prompt from uid {}", peer),
+        ));
+    }
+    let mut line = String::new();
+    BufReader::new(stream.try_clone()?).read_line(&mut line)?;
+    let request: AskpassRequest = serde_json::from_str(&line)?;
+
+    // The helper stays connected until it gets a reply or ssh kills it; EOF means
+    // the prompt is no longer relevant.
+    let done = Arc::new(AtomicBool::new(false));
+    {
+        let done = done.clone();
+        let mut watch = BufReader::new(stream.try_clone()?);
+        std::thread::spawn(move || {
+            let mut buf = String::new();
+            let _ = watch.read_line(&mut buf);
+            done.store(true, Ordering::SeqCst);
+        });
+    }
+
+    let (reply_tx, reply_rx) = mpsc::channel();
+    let pending = PendingPrompt {
+        request,
+        reply: reply_tx,
+        done,
+    };
+    if tx.send(pending).is_err() {
+        return Ok(());
+    }
+    let reply = AskpassReply {
+        answer: reply_rx.recv().unwrap_or(None),
+    };
+    let mut out = stream;
+    out.write_all(serde_json::to_string(&reply)?.as_bytes())?;
+    out.write_all(b"\n")?;
+    Ok(())
+}
+
+/// Uid of the process at the other end of `stream`.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn peer_uid(stream: &UnixStream) -> std::io::Result<u32> {
+    let mut cred = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    // SAFETY: `cred` and `len` are valid out-pointers of the sizes passed.
+    let rc = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut cred as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if rc != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(cred.uid)
+}
+
+/// Uid of the process at the other end of `stream`.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn peer_uid(stream: &UnixStream) -> std::io::Result<u32> {
+    let (mut uid, mut gid) = (0, 0);
+    // SAFETY: `uid` and `gid` are valid out-pointers.
+    if unsafe { libc::getpeereid(stream.as_raw_fd(), &mut uid, &mut gid) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(uid)
+}
+
+/// True if this process was started by ssh as the askpass helper.
+pub fn is_helper() -> bool {
+    std::env::var_os(SOCKET_ENV).is_some()
+}
+
+/// Askpass helper entry point: forward the prompt to the app and print the answer.
+///
+/// Returns the process exit code (non-zero when cancelled or the app is gone).
+pub fn run_helper() -> i32 {
+    let Some(path) = std::env::var_os(SOCKET_ENV) else {
+        return 1;
+    };
+    let request = AskpassRequest {
+        prompt: std::env::args().nth(1).unwrap_or_default(),
+        kind: PromptKind::from_env(std::env::var("SSH_ASKPASS_PROMPT").ok().as_deref()),
+    };
+    let Ok(mut stream) = UnixStream::connect(path) else {
+        return 1;
+    };
+    let Ok(req) = serde_json::to_string(&request) else {
+        return 1;
+    };
+    if stream.write_all(req.as_bytes()).is_err() || stream.write_all(b"\n").is_err() {
+        return 1;
+    }
+    let mut line = String::new();
+    if BufReader::new(stream).read_line(&mut line).is_err() {
+        return 1;
+    }
+    let reply: AskpassReply = serde_json::from_str(&line).unwrap_or_default();
+    match (request.kind, reply.answer) {
+        (PromptKind::Notify, _) => 0,
+        (PromptKind::Confirm, Some(_)) => {
+            println!("yes");
+            0
+        }
+        (PromptKind::Secret, Some(answer)) => {
+            println!("{}", answer);
+            0
+        }
+        (_, None) => 1,
+    }
+}
+
+/// True once `start` succeeded in this process.
+pub fn is_active() -> bool {
+    SOCKET.get().is_some()
+}
+
+/// `BatchMode` option for ssh: prompts are allowed only when they can be surfaced.
+pub(crate) fn batch_mode_option() -> &'static str {
+    if is_active() {
+        "BatchMode=no"
+    } else {
+        "BatchMode=yes"
+    }
+}
+
+/// Extra ssh options used while askpass is active: share one authenticated
+/// connection so the user is prompted once rather than for every ssh invocation.
+pub(crate) fn multiplex_options() -> Vec<&'static str> {
+    if is_active() {
+        vec!["-o", "ControlMaster=auto", "-o", "ControlPersist=60"]
+    } else {
+        Vec::new()
+    }
+}
+
+/// Point ssh at the askpass helper (no-op unless the server is running).
+pub(crate) fn configure(cmd: &mut TokioCommand) {
+    let (Some(path), Ok(exe)) = (SOCKET.get(), std::env::current_exe()) else {
+        return;
+    };
+    cmd.env("SSH_ASKPASS", exe)
+        .env("SSH_ASKPASS_REQUIRE", "force")
+        .env(SOCKET_ENV, path);
+}
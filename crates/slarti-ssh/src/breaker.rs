@@ -0,0 +1,246 @@
+//! Circuit breaker for hosts that keep timing out.
+//!
+//! After [`THRESHOLD`] consecutive connection timeouts a host is suppressed:
+//! ssh commands and agent sessions to it fail fast with [`Suppressed`]
+//! instead of spawning ssh and waiting out another timeout. Once the cooldown passes one trial
+//! command is let through (half-open); success closes the breaker, another
+//! timeout reopens it with a doubled cooldown. [`reset`] retries right away.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Consecutive timeouts before a host is suppressed.
+pub const THRESHOLD: u32 = 3;
+const BASE_COOLDOWN: Duration = Duration::from_secs(30);
+const MAX_COOLDOWN: Duration = Duration::from_secs(10 * 60);
+/// A half-open trial not reported back within this is assumed abandoned.
+const TRIAL_TIMEOUT: Duration = Duration::from_secs(2 * 60);
+
+/// Error returned instead of running ssh against a suppressed host.
+#[derive(Debug, Clone)]
+pub struct Suppressed {
+    pub target: String,
+    /// Consecutive timeouts so far.
+    pub failures: u32,
+    /// Until the next automatic trial (zero while one is running).
+    pub retry_in: Duration,
+}
+
+impl std::fmt::Display for Suppressed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} suppressed after {} timeouts (retry in {}s)",
+            self.target,
+            self.failures,
+            self.retry_in.as_secs()
+        )
+    }
+}
+
+impl std::error::Error for Suppressed {}
+
+#[derive(Clone, Copy, Debug)]
+enum State {
+    Closed,
+    Open { until: Instant },
+    HalfOpen { since: Instant },
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Breaker {
+    state: State,
+    failures: u32,
+}
+
+fn breakers() -> &'static Mutex<HashMap<String, Breaker>> {
+    static BREAKERS: OnceLock<Mutex<HashMap<String, Breaker>>> = OnceLock::new();
+    BREAKERS.get_or_init(Default::default)
+}
+
+fn cooldown(failures: u32) -> Duration {
+    let doublings = failures.saturating_sub(THRESHOLD).min(8);
+    (BASE_COOLDOWN * 2u32.pow(doublings)).min(MAX_COOLDOWN)
+}
+
+/// Whether a command to `target` may run now.
+pub(crate) fn admit(target: &str) -> Result<(), Suppressed> {
+    let mut map = breakers().lock().unwrap_or_else(|e| e.into_inner());
+    let Some(b) = map.get_mut(target) else {
+        return Ok(());
+    };
+    let now = Instant::now();
+    match b.state {
+        State::Closed => Ok(()),
+        State::Open { until } if now < until => Err(Suppressed {
+            target: target.to_string(),
+            failures: b.failures,
+            retry_in: until - now,
+        }),
+        State::HalfOpen { since } if now.duration_since(since) < TRIAL_TIMEOUT => Err(Suppressed {
+            target: target.to_string(),
+            failures: b.failures,
+            retry_in: Duration::ZERO,
+        }),
+        // Cooldown over (or the last trial went missing): let one command through.
+        _ => {
+            b.state = State::HalfOpen { since: now };
+            Ok(())
+        }
+    }
+}
+
+/// Record the outcome of a command to `target`.
+pub(crate) fn record(target: &str, timed_out: bool) {
+    let mut map = breakers().lock().unwrap_or_else(|e| e.into_inner());
+    if !timed_out {
+        map.remove(target);
+        return;
+    }
+    let b = map.entry(target.to_string()).or_insert(Breaker {
+        state: State::Closed,
+        failures: 0,
+    });
+    b.failures += 1;
+    if b.failures >= THRESHOLD {
+        b.state = State::Open {
+            until: Instant::now() + cooldown(b.failures),
+        };
+        tracing::info!(
+            target: "slarti_ssh",
+            "{} timed out {} times in a row; suppressing for {:?}",
+            target,
+            b.failures,
+            cooldown(b.failures)
+        );
+    }
+}
+
+/// Forget `target`'s failures so the next command runs (manual retry).
+pub fn reset(target: &str) {
+    breakers()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(target);
+}
+
+/// True if ssh stderr reports a connection timeout (rather than e.g. an auth failure).
+pub fn is_timeout(stderr: &str) -> bool {
+    stderr.lines().any(|l| {
+        let l = l.to_ascii_lowercase();
+        l.contains("connection timed out")
+            || l.contains("operation timed out")
+            || l.contains("timeout, server")
+            || l.contains("timed out during banner exchange")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spawn::{scope, MockSpawner};
+
+    /// Move `target`'s cooldown (or running trial) into the past.
+    fn expire(target: &str) {
+        let mut map = breakers().lock().unwrap();
+        let b = map.get_mut(target).unwrap();
+        let past = Instant::now() - TRIAL_TIMEOUT - MAX_COOLDOWN;
+        b.state = match b.state {
+            State::Open { .. } => State::Open { until: past },
+            State::HalfOpen { .. } => State::HalfOpen { since: past },
+            State::Closed => State::Closed,
+        };
+    }
+
+    #[test]
+    fn opens_after_threshold_timeouts() {
+        let target = "breaker-opens";
+        for _ in 1..THRESHOLD {
+            record(target, true);
+            assert!(admit(target).is_ok());
+        }
+        record(target, true);
+        let err = admit(target).unwrap_err();
+        assert_eq!(err.failures, THRESHOLD);
+        assert!(err.retry_in > Duration::ZERO && err.retry_in <= BASE_COOLDOWN);
+
+        reset(target);
+        assert!(admit(target).is_ok());
+    }
+
+    #[test]
+    fn other_outcomes_clear_the_count() {
+        let target = "breaker-clears";
+        record(target, true);
+        record(target, true);
+        record(target, false);
+        record(target, true);
+        record(target, true);
+        assert!(admit(target).is_ok());
+        reset(target);
+    }
+
+    #[test]
+    fn half_open_lets_one_trial_through() {
+        let target = "breaker-half-open";
+        for _ in 0..THRESHOLD {
+            record(target, true);
+        }
+        expire(target);
+        assert!(admit(target).is_ok());
+        // The trial is running: everything else still waits.
+        assert_eq!(admit(target).unwrap_err().retry_in, Duration::ZERO);
+
+        // It timed out too: open again, with a doubled cooldown.
+        record(target, true);
+        let err = admit(target).unwrap_err();
+        assert_eq!(err.failures, THRESHOLD + 1);
+        assert!(err.retry_in > BASE_COOLDOWN);
+
+        // A trial that never reports back is given up on.
+        expire(target);
+        assert!(admit(target).is_ok());
+        expire(target);
+        assert!(admit(target).is_ok());
+        record(target, false);
+        assert!(admit(target).is_ok());
+    }
+
+    #[test]
+    fn cooldown_doubles_up_to_the_cap() {
+        assert_eq!(cooldown(THRESHOLD), BASE_COOLDOWN);
+        assert_eq!(cooldown(THRESHOLD + 1), BASE_COOLDOWN * 2);
+        assert_eq!(cooldown(THRESHOLD + 2), BASE_COOLDOWN * 4);
+        assert_eq!(cooldown(THRESHOLD + 20), MAX_COOLDOWN);
+    }
+
+    #[test]
+    fn recognises_ssh_timeouts() {
+        assert!(is_timeout(
+            "ssh: connect to host 10.0.0.9 port 22: Connection timed out"
+        ));
+        assert!(is_timeout("Connection timed out during banner exchange"));
+        assert!(is_timeout("Timeout, server web1 not responding."));
+        assert!(!is_timeout("Permission denied (publickey)."));
+        assert!(!is_timeout("ssh: Could not resolve hostname web1"));
+    }
+
+    #[tokio::test]
+    async fn run_agent_fails_fast_while_suppressed() {
+        let target = "breaker-run-agent";
+        for _ in 0..THRESHOLD {
+            record(target, true);
+        }
+        let mock = MockSpawner::new();
+        let result = scope(
+            mock.shared(),
+            crate::run_agent(target, "/opt/slarti-remote"),
+        )
+        .await;
+        let err = result.err().expect("suppressed host ran ssh");
+        assert!(err.downcast_ref::<Suppressed>().is_some());
+        assert!(mock.calls().is_empty());
+        reset(target);
+    }
+}
@@ -0,0 +1,383 @@
+//! Agent transports over container runtimes.
+//!
+//! Docker containers and Kubernetes pods are addressed by pseudo host aliases
+//! (`docker:<container>`, `k8s:<namespace>:<pod>`). The agent is copied into
+//! the container and run with `docker exec -i` / `kubectl exec -i` instead of
+//! ssh; the stdio protocol is the same.
+
+use anyhow::{anyhow, Context as _, Result};
+use std::path::Path;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::process::Command as TokioCommand;
+use tracing::debug;
+
+use crate::plan::ActionPlan;
+use crate::{shell_quote, spawn, AgentClient, AgentStatus, DeployResult};
+
+const DOCKER_PREFIX: &str = "docker:";
+const K8S_PREFIX: &str = "k8s:";
+
+/// A container or pod the agent can run in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Container {
+    Docker { name: String },
+    Pod { namespace: String, pod: String },
+}
+
+impl Container {
+    /// Parse a `docker:` or `k8s:` alias; None for anything else (ssh hosts).
+    pub fn from_alias(alias: &str) -> Option<Self> {
+        if let Some(name) = alias.strip_prefix(DOCKER_PREFIX) {
+            return (!name.is_empty()).then(|| Container::Docker {
+                name: name.to_string(),
+            });
+        }
+        let rest = alias.strip_prefix(K8S_PREFIX)?;
+        let (namespace, pod) = rest.split_once(':')?;
+        if namespace.is_empty() || pod.is_empty() {
+            return None;
+        }
+        Some(Container::Pod {
+            namespace: namespace.to_string(),
+            pod: pod.to_string(),
+        })
+    }
+
+    /// The alias this container is listed under.
+    pub fn alias(&self) -> String {
+        match self {
+            Container::Docker { name } => format!("{DOCKER_PREFIX}{name}"),
+            Container::Pod { namespace, pod } => format!("{K8S_PREFIX}{namespace}:{pod}"),
+        }
+    }
+
+    /// The runtime CLI used to reach it ("docker" or "kubectl").
+    pub fn runtime(&self) -> &'static str {
+        match self {
+            Container::Docker { .. } => "docker",
+            Container::Pod { .. } => "kubectl",
+        }
+    }
+
+    /// `docker exec -i <name> <args..>` / `kubectl exec -i -n <ns> <pod> -- <args..>`.
+    fn exec(&self, args: &[&str]) -> TokioCommand {
+        let mut cmd = match self {
+            Container::Docker { name } => {
+                let mut cmd = TokioCommand::new("docker");
+                cmd.args(["exec", "-i", name]);
+                cmd
+            }
+            Container::Pod { namespace, pod } => {
+                let mut cmd = TokioCommand::new("kubectl");
+                cmd.args(["exec", "-i", "-n", namespace, pod, "--"]);
+                cmd
+            }
+        };
+        cmd.args(args);
+        cmd
+    }
+
+    /// Copy a local file to `dest` inside the container.
+    fn copy_in(&self, src: &Path, dest: &str) -> TokioCommand {
+        match self {
+            Container::Docker { name } => {
+                let mut cmd = TokioCommand::new("docker");
+                cmd.arg("cp").arg(src).arg(format!("{name}:{dest}"));
+                cmd
+            }
+            Container::Pod { namespace, pod } => {
+                let mut cmd = TokioCommand::new("kubectl");
+                cmd.args(["cp", "-n", namespace])
+                    .arg(src)
+                    .arg(format!("{pod}:{dest}"));
+                cmd
+            }
+        }
+    }
+}
+
+/// Where the agent is installed inside containers. Images rarely have a usable
+/// $HOME, so this lives under /tmp; the path is absolute because the agent is
+/// exec'd directly, without a shell.
+pub fn container_agent_path(version: &str) -> String {
+    format!("/tmp/slarti/agent/{version}/slarti-remote")
+}
+
+async fn capture(
+    mut cmd: TokioCommand,
+    dur: Duration,
+) -> Result<(std::process::ExitStatus, String, String)> {
+    let started = Instant::now();
+    cmd.stdin(Stdio::null()).kill_on_drop(true);
+    let run = spawn::output(&mut cmd);
+    let output = tokio::time::timeout(dur, run)
+        .await
+        .map_err(|_| anyhow!("{:?} timed out", cmd.as_std().get_program()))?
+        .with_context(|| format!("spawn {:?}", cmd.as_std().get_program()))?;
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    debug!(
+        target: "slarti_ssh",
+        "container: {:?} status={:?} elapsed={:?} stderr={}",
+        cmd.as_std(),
+        output.status.code(),
+        started.elapsed(),
+        stderr.trim()
+    );
+    Ok((output.status, stdout, stderr))
+}
+
+/// Check the agent inside `container` (`<remote_path> --version` via exec).
+///
+/// A missing binary is reported as not present; failing to reach the
+/// container at all (not running, no such pod, runtime missing) is an error.
+pub async fn check_container_agent(
+    container: &Container,
+    remote_path: &str,
+    dur: Duration,
+) -> Result<AgentStatus> {
+    let (status, stdout, stderr) =
+        capture(container.exec(&[remote_path, "--version"]), dur).await?;
+    // An exec that could not start the binary exits 126/127 with an OCI error;
+    // any other failure means the container itself could not be reached.
+    let exec_failed = matches!(status.code(), Some(126) | Some(127))
+        || stderr.contains("executable file not found")
+        || stderr.contains("no such file or directory");
+    if !status.success() && !exec_failed {
+        return Err(anyhow!(
+            "{} unreachable: {}",
+            container.alias(),
+            stderr.trim()
+        ));
+    }
+    let version = status
+        .success()
+        .then(|| {
+            stdout
+                .lines()
+                .map(|s| s.trim())
+                .find(|s| !s.is_empty())
+                .map(|s| s.to_string())
+        })
+        .flatten();
+    Ok(AgentStatus {
+        present: version.is_some(),
+        version,
+        remote_path: remote_path.to_string(),
+        can_run: status.success(),
+        stdout,
+        stderr,
+    })
+}
+
+/// Run the agent inside `container` (`<remote_path> --stdio` via exec).
+pub async fn run_container_agent(container: &Container, remote_path: &str) -> Result<AgentClient> {
+    debug!(
+        target: "slarti_ssh",
+        "run_container_agent: container={} path={}",
+        container.alias(),
+        remote_path
+    );
+    let started = Instant::now();
+    let mut cmd = container.exec(&[remote_path, "--stdio"]);
+    cmd.stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .kill_on_drop(true);
+    let process =
+        spawn::spawn(&mut cmd).with_context(|| format!("exec agent in {}", container.alias()))?;
+    Ok(AgentClient::from_process(process, started))
+}
+
+/// Copy the agent binary `local_artifact` into `container` at
+/// [`container_agent_path`]. Needs `mkdir` and `chmod` in the image (and `tar`
+/// for pods, which `kubectl cp` relies on).
+pub async fn deploy_container_agent(
+    container: &Container,
+    local_artifact: &Path,
+    version: &str,
+    dur: Duration,
+) -> Result<DeployResult> {
+    let remote_path = container_agent_path(version);
+    let remote_dir = match remote_path.rsplit_once('/') {
+        Some((dir, _)) => dir,
+        None => "/tmp",
+    };
+    debug!(
+        target: "slarti_ssh",
+        "deploy: container={} artifact={:?} remote_path={}",
+        container.alias(),
+        local_artifact,
+        remote_path
+    );
+
+    let (st, _, se) = capture(container.exec(&["mkdir", "-p", remote_dir]), dur).await?;
+    if !st.success() {
+        return Err(anyhow!("mkdir {} failed: {}", remote_dir, se.trim()));
+    }
+    let (st, _, se) = capture(container.copy_in(local_artifact, &remote_path), dur).await?;
+    if !st.success() {
+        return Err(anyhow!(
+            "copy to {} failed: {}",
+            container.alias(),
+            se.trim()
+        ));
+    }
+    let (st, _, se) = capture(container.exec(&["chmod", "0755", &remote_path]), dur).await?;
+    if !st.success() {
+        return Err(anyhow!("chmod {} failed: {}", remote_path, se.trim()));
+    }
+    Ok(DeployResult {
+        remote_path,
+        used_rsync: false,
+    })
+}
+
+/// Plan for `deploy_container_agent(container, local_artifact, version, ..)`.
+pub fn deploy_container_plan(
+    container: &Container,
+    local_artifact: &Path,
+    version: &str,
+) -> ActionPlan {
+    let remote_path = container_agent_path(version);
+    let remote_dir = match remote_path.rsplit_once('/') {
+        Some((dir, _)) => dir,
+        None => "/tmp",
+    };
+    let commands = [
+        container.exec(&["mkdir", "-p", remote_dir]),
+        container.copy_in(local_artifact, &remote_path),
+        container.exec(&["chmod", "0755", &remote_path]),
+    ]
+    .iter()
+    .map(|cmd| {
+        let cmd = cmd.as_std();
+        std::iter::once(cmd.get_program())
+            .chain(cmd.get_args())
+            .map(|a| shell_quote(&a.to_string_lossy()))
+            .collect::<Vec<_>>()
+            .join(" ")
+    })
+    .collect();
+    ActionPlan {
+        action: format!("Deploy agent v{}", version),
+        host: container.alias(),
+        user: "(container default)".to_string(),
+        escalation: None,
+        commands,
+    }
+}
+
+/// Running docker containers and kubernetes pods, for listing alongside ssh
+/// hosts. A runtime that is not installed or not reachable contributes nothing.
+pub async fn list_containers(dur: Duration) -> Vec<Container> {
+    let mut out = Vec::new();
+
+    let mut docker = TokioCommand::new("docker");
+    docker.args(["ps", "--format", "{{.Names}}"]);
+    if let Ok((st, stdout, _)) = capture(docker, dur).await {
+        if st.success() {
+            out.extend(parse_docker_names(&stdout));
+        }
+    }
+
+    let mut kubectl = TokioCommand::new("kubectl");
+    kubectl.args([
+        "get",
+        "pods",
+        "--all-namespaces",
+        "--field-selector=status.phase=Running",
+        "-o",
+        "jsonpath={range .items[*]}{.metadata.namespace} {.metadata.name}{\"\\n\"}{end}",
+    ]);
+    if let Ok((st, stdout, _)) = capture(kubectl, dur).await {
+        if st.success() {
+            out.extend(parse_pods(&stdout));
+        }
+    }
+
+    out
+}
+
+/// Containers from `docker ps --format {{.Names}}` (one name per line).
+fn parse_docker_names(stdout: &str) -> impl Iterator<Item = Container> + '_ {
+    stdout
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(|name| Container::Docker {
+            name: name.to_string(),
+        })
+}
+
+/// Pods from the `kubectl get pods` jsonpath above ("<namespace> <pod>" lines).
+fn parse_pods(stdout: &str) -> impl Iterator<Item = Container> + '_ {
+    stdout.lines().filter_map(|l| {
+        let (namespace, pod) = l.trim().split_once(' ')?;
+        Some(Container::Pod {
+            namespace: namespace.to_string(),
+            pod: pod.to_string(),
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn docker(name: &str) -> Container {
+        Container::Docker {
+            name: name.to_string(),
+        }
+    }
+
+    fn pod(namespace: &str, pod: &str) -> Container {
+        Container::Pod {
+            namespace: namespace.to_string(),
+            pod: pod.to_string(),
+        }
+    }
+
+    #[test]
+    fn aliases_round_trip() {
+        for container in [
+            docker("redis"),
+            pod("kube-system", "coredns-5d78c9869d-x2v7q"),
+        ] {
+            assert_eq!(Container::from_alias(&container.alias()), Some(container));
+        }
+        assert_eq!(docker("redis").alias(), "docker:redis");
+        assert_eq!(pod("default", "web-0").alias(), "k8s:default:web-0");
+        // Namespaces cannot contain ':', so the first one ends it.
+        assert_eq!(Container::from_alias("k8s:ns:a:b"), Some(pod("ns", "a:b")));
+    }
+
+    #[test]
+    fn other_aliases_are_not_containers() {
+        for alias in [
+            "web1",
+            "docker:",
+            "k8s:",
+            "k8s:default",
+            "k8s::web-0",
+            "k8s:default:",
+            "podman:web",
+        ] {
+            assert_eq!(Container::from_alias(alias), None, "{}", alias);
+        }
+    }
+
+    #[test]
+    fn parses_runtime_listings() {
+        let names: Vec<_> = parse_docker_names("redis\n  web  \n\n").collect();
+        assert_eq!(names, [docker("redis"), docker("web")]);
+
+        let pods: Vec<_> =
+            parse_pods("default web-0\nkube-system coredns-1\nmalformed\n\n").collect();
+        assert_eq!(
+            pods,
+            [pod("default", "web-0"), pod("kube-system", "coredns-1")]
+        );
+    }
+}
@@ -0,0 +1,127 @@
+//! The effective ssh config of a target, as `ssh -G` prints it (the config
+//! ssh would use; no connection made), cached per target until [`forget`].
+
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::{Mutex, OnceLock};
+
+use tokio::process::Command;
+
+use crate::spawn;
+
+use tracing::debug;
+
+/// The parts of a target's effective config this crate acts on.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Effective {
+    /// Login user
+    pub user: Option<String>,
+    /// `ProxyJump` as configured (possibly a chain); None when unset
+    pub proxy_jump: Option<String>,
+    /// Whether the config sets its own `ControlPath` or `ControlMaster`
+    pub control: bool,
+}
+
+static CACHE: OnceLock<Mutex<HashMap<String, Effective>>> = OnceLock::new();
+
+/// The effective config of `target`; the default when `ssh -G` fails.
+///
+/// Scripted lookups (inside [`spawn::scope`]) are not cached, so one test's
+/// answers never reach another.
+pub(crate) async fn effective(target: &str) -> Effective {
+    let cache = CACHE.get_or_init(Default::default);
+    let scripted = spawn::scoped();
+    if !scripted {
+        if let Some(found) = cache.lock().ok().and_then(|c| c.get(target).cloned()) {
+            return found;
+        }
+    }
+    let found = resolve(target).await;
+    debug!(target: "slarti_ssh", "effective config: target={} {:?}", target, found);
+    if !scripted {
+        if let Ok(mut c) = cache.lock() {
+            c.insert(target.to_string(), found.clone());
+        }
+    }
+    found
+}
+
+/// Drop every cached answer, so the next lookup re-runs `ssh -G` against
+/// the config as it is now.
+pub(crate) fn forget() {
+    if let Some(Ok(mut c)) = CACHE.get().map(Mutex::lock) {
+        c.clear();
+    }
+}
+
+async fn resolve(target: &str) -> Effective {
+    let mut cmd = Command::new("ssh");
+    cmd.arg("-G")
+        .arg(target)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null());
+    spawn::output(&mut cmd)
+        .await
+        .ok()
+        .filter(|out| out.status.success())
+        .map(|out| parse(&String::from_utf8_lossy(&out.stdout)))
+        .unwrap_or_default()
+}
+
+/// Read the `ssh -G` output lines ("keyword value", keywords lowercased).
+fn parse(stdout: &str) -> Effective {
+    let mut effective = Effective::default();
+    for line in stdout.lines() {
+        let Some((key, value)) = line.split_once(' ') else {
+            continue;
+        };
+        let value = value.trim();
+        let set = !value.is_empty() && !value.eq_ignore_ascii_case("none");
+        match key {
+            "user" if !value.is_empty() => effective.user = Some(value.to_string()),
+            "proxyjump" if set => effective.proxy_jump = Some(value.to_string()),
+            "controlpath" if set => effective.control = true,
+            "controlmaster" if value != "false" && value != "no" => effective.control = true,
+            _ => {}
+        }
+    }
+    effective
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ssh_g_output() {
+        let plain = "user alice\nhostname web1\ncontrolmaster false\ncontrolpersist no\n";
+        assert_eq!(
+            parse(plain),
+            Effective {
+                user: Some("alice".to_string()),
+                proxy_jump: None,
+                control: false,
+            }
+        );
+
+        let own = "user bob\nproxyjump bastion,inner\ncontrolmaster auto\n\
+                   controlpath /home/bob/.ssh/cm-%r@%h\n";
+        let own = parse(own);
+        assert_eq!(own.proxy_jump.as_deref(), Some("bastion,inner"));
+        assert!(own.control);
+
+        let none = "proxyjump none\ncontrolpath none\ncontrolmaster false\n";
+        assert_eq!(parse(none), Effective::default());
+    }
+
+    #[test]
+    fn forget_empties_the_cache() {
+        let cache = CACHE.get_or_init(Default::default);
+        cache
+            .lock()
+            .unwrap()
+            .insert("forget-me".to_string(), Effective::default());
+        forget();
+        assert!(!cache.lock().unwrap().contains_key("forget-me"));
+    }
+}
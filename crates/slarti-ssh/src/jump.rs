@@ -0,0 +1,68 @@
+//! Shared bastion connections.
+//!
+//! With `ProxyJump bastion` in the ssh config, every connection to a leaf host
+//! opens (and authenticates) its own connection to the bastion. Leaf
+//! connections made here instead get a ProxyCommand that forwards stdio
+//! (`ssh -W`) over a control master to the bastion, so a sweep of hosts behind
+//! one bastion shares a single jump connection.
+//!
+//! Only single-hop jumps are rerouted; chains (`ProxyJump a,b`) are left to
+//! ssh. Set `SLARTI_SHARED_JUMP=0` to leave ProxyJump to ssh everywhere.
+
+use crate::{askpass, control_path_for, effective, shell_quote};
+
+/// How long the bastion master stays up after its last leaf connection closes.
+const BASTION_PERSIST: &str = "10m";
+
+fn enabled() -> bool {
+    std::env::var("SLARTI_SHARED_JUMP").map_or(true, |v| v != "0")
+}
+
+/// ssh options routing `target` through the shared connection to its bastion;
+/// empty if it has no (single-hop) ProxyJump.
+pub(crate) async fn proxy_options(target: &str) -> Vec<String> {
+    if !enabled() {
+        return Vec::new();
+    }
+    match bastion(target).await {
+        Some(jump) => vec![
+            "-o".to_string(),
+            format!("ProxyCommand={}", proxy_command(&jump).await),
+        ],
+        None => Vec::new(),
+    }
+}
+
+/// The single-hop ProxyJump in effect for `target`.
+async fn bastion(target: &str) -> Option<String> {
+    effective::effective(target)
+        .await
+        .proxy_jump
+        .filter(|jump| !jump.contains(','))
+}
+
+/// `ssh -W %h:%p` to `jump` over a control master (`[ssh://][user@]host[:port]`).
+async fn proxy_command(jump: &str) -> String {
+    let spec = jump.strip_prefix("ssh://").unwrap_or(jump);
+    let (dest, port) = match spec.rsplit_once(':') {
+        Some((dest, port)) if port.parse::<u16>().is_ok() => (dest, Some(port)),
+        _ => (spec, None),
+    };
+    let mut cmd = format!(
+        "ssh -o ControlMaster=auto -o ControlPersist={} -o {}",
+        BASTION_PERSIST,
+        askpass::batch_mode_option()
+    );
+    // ssh expands %-tokens in ProxyCommand; keep the control path's for the inner ssh.
+    if let Some(path) = control_path_for(dest).await {
+        cmd.push_str(&format!(
+            " -o {}",
+            shell_quote(&format!("ControlPath={}", path.replace('%', "%%")))
+        ));
+    }
+    if let Some(port) = port {
+        cmd.push_str(&format!(" -p {}", port));
+    }
+    cmd.push_str(&format!(" -W %h:%p {}", shell_quote(dest)));
+    cmd
+}
@@ -6,12 +6,24 @@ This crate provides a small async API for:
 - Running the agent via `ssh -T "<remote>/slarti-remote --stdio"`.
 - Performing a versioned Hello/HelloAck handshake using slarti-proto.
 - Sending/receiving JSON line-delimited commands and responses.
+- Subscribing to periodic updates the agent pushes (`AgentClient::subscribe`),
+  delivered on the `AgentClient::events` channel.
+- Running the agent inside docker containers and kubernetes pods via
+  `docker exec -i` / `kubectl exec -i` (see [`container`]).
+- Building console command lines for hosts on local serial ports (see [`serial`]).
+- Previewing what mutating actions will run before they run (see [`plan`]).
+- Building scp/rsync command lines from a host's effective config (see [`transfer`]).
+- Scripting the processes it starts, to test all of the above without a
+  network (see [`spawn`]).
 
 Notes:
 - This library shells out to the system `ssh` binary and thus inherits
   the user's SSH config (keys, ProxyJump, etc).
 - All remote commands funnel through a generic ssh runner that captures
   stdout/stderr and emits structured debug logs.
+- Hosts behind a ProxyJump bastion share one connection to it (see `jump`).
+- ssh invocations are limited in number, with interactive work served before
+  background work (see [`queue`]).
 
 Example:
 
@@ -38,47 +50,84 @@ async fn main() -> anyhow::Result<()> {
 "#]
 
 use anyhow::{anyhow, Context as _, Result};
-use slarti_proto::{Command, Response};
+use slarti_proto::{Command, EventData, Facet, Response};
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
-use std::time::Duration;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter};
-use tokio::process::{Child, ChildStdin, ChildStdout, Command as TokioCommand};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, BufWriter};
+use tokio::process::{Child, Command as TokioCommand};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::task::JoinHandle;
 use tracing::debug;
 
+pub mod askpass;
+pub mod breaker;
+pub mod container;
+mod effective;
+mod jump;
+pub mod plan;
+pub mod queue;
+pub mod serial;
+pub mod spawn;
+pub mod transfer;
+
+/// Arguments of a non-interactive `ssh` running `script` on `target`: the
+/// options every such invocation here shares (shared control socket, bastion
+/// ProxyCommand), then the target and script. Previews render the same list.
+pub(crate) async fn ssh_script_args(
+    target: &str,
+    script: &str,
+    connect_timeout: Duration,
+) -> Vec<String> {
+    let mut args: Vec<String> = [
+        "-o",
+        askpass::batch_mode_option(),
+        "-o",
+        "StrictHostKeyChecking=accept-new",
+        "-o",
+        &format!("ConnectTimeout={}", connect_timeout.as_secs()),
+        "-o",
+        "ConnectionAttempts=1",
+        "-o",
+        "Compression=yes",
+    ]
+    .iter()
+    .map(|a| a.to_string())
+    .collect();
+    args.extend(control_options(target).await);
+    args.extend(jump::proxy_options(target).await);
+    args.extend(["-T", target, "--", script].map(String::from));
+    args
+}
+
 async fn ssh_run_capture(
     target: &str,
     script: &str,
     dur: std::time::Duration,
 ) -> anyhow::Result<(std::process::ExitStatus, String, String)> {
-    let connect_timeout = format!("ConnectTimeout={}", dur.as_secs());
+    breaker::admit(target)?;
+    let _permit = queue::acquire().await;
     let started = std::time::Instant::now();
 
     let mut cmd = tokio::process::Command::new("ssh");
     cmd.envs(std::env::vars());
-    cmd.arg("-o")
-        .arg("BatchMode=yes")
-        .arg("-o")
-        .arg("StrictHostKeyChecking=accept-new")
-        .arg("-o")
-        .arg(&connect_timeout)
-        .arg("-o")
-        .arg("ConnectionAttempts=1")
-        .arg("-o")
-        .arg("Compression=yes")
-        .arg("-T")
-        .arg(target)
-        .arg("--")
-        .arg(script)
+    askpass::configure(&mut cmd);
+    cmd.args(ssh_script_args(target, script, dur).await)
         .stdin(std::process::Stdio::null())
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped());
 
-    let out = cmd.output().await.context("failed to run ssh")?;
+    let out = spawn::output(&mut cmd).await.context("failed to run ssh")?;
 
     let stdout = String::from_utf8_lossy(&out.stdout).to_string();
     let stderr = String::from_utf8_lossy(&out.stderr).to_string();
     let exit_code = out.status.code();
+    breaker::record(
+        target,
+        exit_code == Some(255) && breaker::is_timeout(&stderr),
+    );
     #[cfg(unix)]
     let exit_signal = std::os::unix::process::ExitStatusExt::signal(&out.status);
     #[cfg(not(unix))]
@@ -108,6 +157,203 @@ async fn ssh_run_capture(
     Ok((out.status, stdout, stderr))
 }
 
+/// Error returned when ssh rejected non-interactive (BatchMode) authentication.
+///
+/// Callers can detect it with `err.downcast_ref::<AuthRequired>()` and offer an
+/// interactive login (see `interactive_master_command`).
+#[derive(Debug, Clone)]
+pub struct AuthRequired {
+    pub target: String,
+    /// Raw ssh stderr (e.g. "Permission denied (publickey,password).")
+    pub stderr: String,
+}
+
+impl std::fmt::Display for AuthRequired {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "authentication required for {}", self.target)
+    }
+}
+
+impl std::error::Error for AuthRequired {}
+
+/// Forget what `ssh -G` said about every target (user, bastion, own control
+/// socket); call after ~/.ssh/config changes so later connections see the edit.
+pub fn config_changed() {
+    effective::forget();
+}
+
+/// True if ssh stderr indicates that authentication (not the remote command) failed.
+pub fn is_auth_failure(stderr: &str) -> bool {
+    stderr.lines().any(|l| {
+        let l = l.trim();
+        // "user@host: Permission denied (publickey,password)."
+        (l.contains("Permission denied (") && l.ends_with(")."))
+            || l.contains("Too many authentication failures")
+    })
+}
+
+/// Control socket path for `target`, shared by all ssh invocations so a
+/// master connection established interactively (see
+/// `interactive_master_command`) is reused by BatchMode commands.
+///
+/// None when the target's config sets up its own multiplexing (`ControlPath`
+/// or `ControlMaster`), which is then left to it, or when there is no private
+/// directory for the socket.
+pub(crate) async fn control_path_for(target: &str) -> Option<String> {
+    if effective::effective(target).await.control {
+        return None;
+    }
+    match runtime_dir() {
+        // %C: hash of local host, remote host, port and user (keeps the path short).
+        Ok(dir) => Some(format!("{}/cm-%C", dir.display())),
+        Err(e) => {
+            debug!(target: "slarti_ssh", "no control socket for {}: {}", target, e);
+            None
+        }
+    }
+}
+
+/// ssh options for the shared control socket of `target` (see
+/// `control_path_for`); empty when its config handles multiplexing.
+pub(crate) async fn control_options(target: &str) -> Vec<String> {
+    let Some(path) = control_path_for(target).await else {
+        return Vec::new();
+    };
+    let mut options = vec!["-o".to_string(), format!("ControlPath={}", path)];
+    options.extend(askpass::multiplex_options().into_iter().map(String::from));
+    options
+}
+
+/// Private per-user directory for sockets: `$XDG_RUNTIME_DIR/slarti`, falling
+/// back to `slarti-<uid>` in the temp dir.
+///
+/// Created mode 0700; an existing directory must be a real directory owned by
+/// this user and closed to others, or it is refused.
+pub(crate) fn runtime_dir() -> std::io::Result<PathBuf> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+    // SAFETY: geteuid has no arguments and cannot fail.
+    let uid = unsafe { libc::geteuid() };
+    let dir = match std::env::var_os("XDG_RUNTIME_DIR").filter(|v| !v.is_empty()) {
+        Some(base) => PathBuf::from(base).join("slarti"),
+        None => std::env::temp_dir().join(format!("slarti-{}", uid)),
+    };
+    match std::fs::DirBuilder::new().mode(0o700).create(&dir) {
+        Err(e) if e.kind() != std::io::ErrorKind::AlreadyExists => return Err(e),
+        _ => {}
+    }
+    let meta = std::fs::symlink_metadata(&dir)?;
+    if !meta.is_dir() || meta.uid() != uid || meta.mode() & 0o077 != 0 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            format!("{} is not a private directory of this user", dir.display()),
+        ));
+    }
+    Ok(dir)
+}
+
+/// Shell command line that opens an interactive ssh master connection to `target`.
+///
+/// Meant to be run in an interactive terminal: ssh prompts for password /
+/// keyboard-interactive auth, then backgrounds itself (`-fN`) and keeps the
+/// control socket open so subsequent BatchMode connections succeed.
+pub async fn interactive_master_command(target: &str) -> String {
+    let jump: Vec<String> = jump::proxy_options(target)
+        .await
+        .iter()
+        .map(|o| format!(" {}", shell_quote(o)))
+        .collect();
+    let control = control_path_for(target)
+        .await
+        .map(|path| format!(" -o {}", shell_quote(&format!("ControlPath={}", path))))
+        .unwrap_or_default();
+    format!(
+        "ssh -o ControlMaster=yes -o ControlPersist=10m{}{} -fN {}",
+        control,
+        jump.concat(),
+        shell_quote(target)
+    )
+}
+
+/// Check whether a control master connection to `target` is up (`ssh -O check`).
+pub async fn control_master_alive(target: &str) -> bool {
+    let mut cmd = TokioCommand::new("ssh");
+    if let Some(path) = control_path_for(target).await {
+        cmd.arg("-o").arg(format!("ControlPath={}", path));
+    }
+    cmd.arg("-O")
+        .arg("check")
+        .arg(target)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    spawn::output(&mut cmd)
+        .await
+        .map(|out| out.status.success())
+        .unwrap_or(false)
+}
+
+/// ssh-related processes currently open on behalf of the app (for diagnostics).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SshProcesses {
+    /// ssh/scp/rsync processes descended from this process, including ones run in the
+    /// embedded terminal. `None` where the process table can't be read (non-Linux).
+    pub children: Option<usize>,
+    /// Live control master sockets (masters detach, so they are not children).
+    pub masters: usize,
+}
+
+/// Count open ssh processes and control masters.
+pub fn ssh_processes() -> SshProcesses {
+    let masters = runtime_dir()
+        .and_then(std::fs::read_dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.file_name().to_string_lossy().starts_with("cm-"))
+                .count()
+        })
+        .unwrap_or(0);
+    SshProcesses {
+        children: ssh_descendants(),
+        masters,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn ssh_descendants() -> Option<usize> {
+    // (pid, ppid, comm) for every process, from /proc/<pid>/stat: "pid (comm) state ppid ...".
+    let procs: Vec<(u32, u32, String)> = std::fs::read_dir("/proc")
+        .ok()?
+        .flatten()
+        .filter_map(|e| {
+            let pid: u32 = e.file_name().to_str()?.parse().ok()?;
+            let stat = std::fs::read_to_string(e.path().join("stat")).ok()?;
+            let (head, rest) = stat.rsplit_once(')')?;
+            let comm = head.split_once('(')?.1.to_string();
+            let ppid = rest.split_whitespace().nth(1)?.parse().ok()?;
+            Some((pid, ppid, comm))
+        })
+        .collect();
+    let mut family = vec![std::process::id()];
+    let mut count = 0;
+    while let Some(parent) = family.pop() {
+        for (pid, ppid, comm) in &procs {
+            if *ppid == parent {
+                family.push(*pid);
+                if matches!(comm.as_str(), "ssh" | "scp" | "rsync") {
+                    count += 1;
+                }
+            }
+        }
+    }
+    Some(count)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn ssh_descendants() -> Option<usize> {
+    None
+}
+
 /// Result of checking a remote agent via `ssh -T <target> -- <remote_path> --version`
 #[derive(Debug, Clone)]
 pub struct AgentStatus {
@@ -127,22 +373,93 @@ pub struct AgentStatus {
 
 /// A running agent session via `ssh -T` with JSON-over-stdio.
 ///
-/// Use `hello` to perform the handshake, then `send_command`/`read_response` for request/response.
+/// Use `hello` to perform the handshake, then `request` for request/response.
+/// Frames of open streams and subscriptions are routed by id to `streams` and
+/// `events`, so they never stand in for the answer to a request; ids must be
+/// unique within the session.
 ///
 /// The session owns the ssh child process. Dropping it will terminate the session.
 pub struct AgentClient {
-    child: Child,
-    reader: BufReader<ChildStdout>,
-    writer: BufWriter<ChildStdin>,
+    /// `None` for a scripted session (see [`spawn::MockSpawner`])
+    child: Option<Child>,
+    /// Responses in arrival order, from the task reading the agent's stdout
+    responses: UnboundedReceiver<Result<Response>>,
+    /// Subscription events and errors, until taken by `events`
+    events: Option<UnboundedReceiver<(u64, Result<EventData>)>>,
+    /// Frames of streams started with `start_stream`, until taken by `streams`
+    streams: Option<UnboundedReceiver<Response>>,
+    /// Ids whose frames the reader routes away from `responses`
+    routes: Arc<Mutex<Routes>>,
+    reader: JoinHandle<()>,
+    writer: BufWriter<Box<dyn AsyncWrite + Send + Unpin>>,
+    /// When the ssh subprocess was spawned (start of connection setup)
+    spawned_at: Instant,
+    /// Time from spawn until the first successful HelloAck
+    setup_time: Option<Duration>,
+    /// Round-trip time of the most recent request/response (including Hello)
+    last_rtt: Option<Duration>,
+    /// ssh queue slot held while the connection is set up; released by `hello`.
+    permit: Option<queue::Permit>,
+    /// Host whose circuit breaker a successful `hello` closes (set by `run_agent`)
+    breaker_target: Option<String>,
+    /// Capabilities advertised in the agent's HelloAck
+    capabilities: Vec<slarti_proto::Capability>,
+    /// Per-session prefix of the trace ids sent with each command
+    trace_prefix: String,
+    next_trace: u64,
+    /// Trace id of the most recently sent command
+    last_trace: Option<String>,
 }
 
 impl AgentClient {
+    /// Wrap a spawned agent process (ssh, local or container exec).
+    fn from_process(process: spawn::Process, spawned_at: Instant) -> Self {
+        let spawn::Process {
+            stdin,
+            stdout,
+            child,
+        } = process;
+        let (responses_tx, responses) = unbounded_channel();
+        let (events_tx, events) = unbounded_channel();
+        let (streams_tx, streams) = unbounded_channel();
+        let routes = Arc::new(Mutex::new(Routes::default()));
+        let reader = tokio::spawn(read_agent_lines(
+            BufReader::new(stdout),
+            routes.clone(),
+            Channels {
+                responses: responses_tx,
+                events: events_tx,
+                streams: streams_tx,
+            },
+        ));
+        AgentClient {
+            child,
+            responses,
+            events: Some(events),
+            streams: Some(streams),
+            routes,
+            reader,
+            writer: BufWriter::new(stdin),
+            spawned_at,
+            setup_time: None,
+            last_rtt: None,
+            permit: None,
+            breaker_target: None,
+            capabilities: Vec::new(),
+            trace_prefix: trace_prefix(),
+            next_trace: 0,
+            last_trace: None,
+        }
+    }
+
     /// Perform Hello/HelloAck handshake and return the parsed HelloAck response.
     pub async fn hello(
         &mut self,
         client_version: &str,
         read_timeout: Option<Duration>,
     ) -> Result<HelloAck> {
+        // Connection setup ends with the handshake, whatever its outcome.
+        let _permit = self.permit.take();
         let id = 1;
         let cmd = Command::Hello {
             id,
@@ -153,11 +470,12 @@ impl AgentClient {
             target: "slarti_ssh",
             "hello: sending Hello id={} client_version={}", id, client_version
         );
+        let started = Instant::now();
         self.send_command(&cmd).await?;
         debug!(target: "slarti_ssh", "hello: Hello sent, awaiting HelloAck (timeout={:?})", read_timeout);
 
         let resp = if let Some(dur) = read_timeout {
-            match tokio::time::timeout(dur, self.read_response_line()).await {
+            match tokio::time::timeout(dur, self.read_reply(id)).await {
                 Ok(Ok(r)) => {
                     debug!(target: "slarti_ssh", "hello: received response within {:?}", dur);
                     r
@@ -172,7 +490,7 @@ impl AgentClient {
                 }
             }
         } else {
-            match self.read_response_line().await {
+            match self.read_reply(id).await {
                 Ok(r) => {
                     debug!(target: "slarti_ssh", "hello: received response (no timeout)");
                     r
@@ -190,12 +508,20 @@ impl AgentClient {
                 agent_version,
                 capabilities,
             } if rid == id => {
+                self.last_rtt = Some(started.elapsed());
+                self.setup_time = Some(self.spawned_at.elapsed());
+                self.capabilities = capabilities.clone();
+                if let Some(target) = &self.breaker_target {
+                    breaker::record(target, false);
+                }
                 debug!(
                     target: "slarti_ssh",
-                    "hello: ack ok id={} agent_version={} caps={}",
+                    "hello: ack ok id={} agent_version={} caps={} rtt={:?} setup={:?}",
                     rid,
                     agent_version,
-                    capabilities.len()
+                    capabilities.len(),
+                    self.last_rtt,
+                    self.setup_time
                 );
                 Ok(HelloAck {
                     agent_version,
@@ -213,9 +539,17 @@ impl AgentClient {
         }
     }
 
-    /// Send a JSON command line to the agent (newline-delimited).
+    /// Send a JSON command line to the agent (newline-delimited), tagged with a new trace id.
     pub async fn send_command(&mut self, cmd: &Command) -> Result<()> {
-        let line = serde_json::to_string(cmd).context("serialize command to JSON")? + "\n";
+        self.next_trace += 1;
+        let trace = format!("{}-{}", self.trace_prefix, self.next_trace);
+        debug!(target: "slarti_ssh", "send: trace={} cmd={}", trace, cmd.name());
+        let req = slarti_proto::Request {
+            command: cmd.clone(),
+            trace: Some(trace.clone()),
+        };
+        self.last_trace = Some(trace);
+        let line = serde_json::to_string(&req).context("serialize command to JSON")? + "\n";
         self.writer
             .write_all(line.as_bytes())
             .await
@@ -224,20 +558,203 @@ impl AgentClient {
         Ok(())
     }
 
+    /// Send a command and read its response, recording the round-trip time.
+    pub async fn request(&mut self, cmd: &Command) -> Result<Response> {
+        let started = Instant::now();
+        self.send_command(cmd).await?;
+        let resp = self.read_reply(cmd.id()).await?;
+        let rtt = started.elapsed();
+        self.last_rtt = Some(rtt);
+        debug!(
+            target: "slarti_ssh",
+            "request: trace={} rtt={:?}",
+            self.last_trace.as_deref().unwrap_or("-"),
+            rtt
+        );
+        Ok(resp)
+    }
+
+    /// Run `commands` in one round trip and return their responses in order.
+    ///
+    /// Agents without `Capability::Batch` get the commands pipelined instead
+    /// (sent back to back, responses read in order).
+    pub async fn batch(&mut self, id: u64, commands: Vec<Command>) -> Result<Vec<Response>> {
+        let started = Instant::now();
+        let responses = if self.supports(&slarti_proto::Capability::Batch) {
+            self.send_command(&Command::Batch { id, commands }).await?;
+            match self.read_reply(id).await? {
+                Response::BatchOk { id: rid, responses } if rid == id => responses,
+                Response::Error { message, .. } => {
+                    return Err(anyhow!("agent batch error: {}", message))
+                }
+                other => return Err(anyhow!("unexpected response to Batch: {:?}", other)),
+            }
+        } else {
+            for cmd in &commands {
+                self.send_command(cmd).await?;
+            }
+            let mut responses = Vec::with_capacity(commands.len());
+            for cmd in &commands {
+                responses.push(self.read_reply(cmd.id()).await?);
+            }
+            responses
+        };
+        let rtt = started.elapsed();
+        self.last_rtt = Some(rtt);
+        debug!(
+            target: "slarti_ssh",
+            "batch: trace={} commands={} rtt={:?}",
+            self.last_trace.as_deref().unwrap_or("-"),
+            responses.len(),
+            rtt
+        );
+        Ok(responses)
+    }
+
+    /// The first load after connecting: SysInfo, StaticConfig, the services
+    /// (a `ServicesDelta` from scratch when the agent supports it) and the
+    /// mounted filesystems' space (when it supports DiskUsage) in one round
+    /// trip.
+    pub async fn initial_load(&mut self) -> Result<Vec<Response>> {
+        let services = if self.supports(&slarti_proto::Capability::ServicesDelta) {
+            Command::ServicesDelta { id: 4, since: None }
+        } else {
+            Command::ServicesList { id: 4 }
+        };
+        let mut commands = vec![
+            Command::SysInfo { id: 2 },
+            Command::StaticConfig { id: 3 },
+            services,
+        ];
+        if self.supports(&slarti_proto::Capability::DiskUsage) {
+            commands.push(Command::DiskUsage { id: 6 });
+        }
+        self.batch(5, commands).await
+    }
+
+    /// Trace id of the most recently sent command, as echoed in agent logs.
+    pub fn last_trace(&self) -> Option<&str> {
+        self.last_trace.as_deref()
+    }
+
+    /// Whether the agent advertised `cap` in its HelloAck.
+    pub fn supports(&self, cap: &slarti_proto::Capability) -> bool {
+        self.capabilities.contains(cap)
+    }
+
+    /// Round-trip time of the most recent request (or Hello), if any.
+    pub fn last_rtt(&self) -> Option<Duration> {
+        self.last_rtt
+    }
+
+    /// Connection setup time: from spawning ssh until the HelloAck arrived.
+    ///
+    /// Dominated by ssh negotiation (and any ProxyJump hops).
+    pub fn setup_time(&self) -> Option<Duration> {
+        self.setup_time
+    }
+
     /// Read a single response (newline-delimited JSON).
+    ///
+    /// Agent log frames ahead of it are logged (target `slarti_agent`) and
+    /// skipped; subscription events go to the `events` channel instead.
     pub async fn read_response_line(&mut self) -> Result<Response> {
-        let mut line = String::new();
-        let n = self
-            .reader
-            .read_line(&mut line)
+        self.responses
+            .recv()
             .await
-            .context("read agent stdout")?;
-        if n == 0 {
-            return Err(anyhow!("agent stdout closed"));
+            .unwrap_or_else(|| Err(anyhow!("agent stdout closed")))
+    }
+
+    /// Read responses until the one answering the command `id`.
+    ///
+    /// Anything else (a late answer to an earlier command, or a response
+    /// from a newer agent) is logged and skipped. An error with id 0 is
+    /// returned as `Err`: older agents answered every failed command that
+    /// way, so waiting for the real id would never end.
+    async fn read_reply(&mut self, id: u64) -> Result<Response> {
+        loop {
+            let resp = self.read_response_line().await?;
+            if resp.id() == Some(id) {
+                return Ok(resp);
+            }
+            if let Response::Error { id: 0, message } = resp {
+                return Err(anyhow!("agent error: {}", message));
+            }
+            debug!(target: "slarti_ssh", "skipping response not for id={}: {:?}", id, resp);
+        }
+    }
+
+    /// The `Event` frames of this session's subscriptions, as (subscription id,
+    /// data), with the errors the agent reports for them; None once taken.
+    /// Events are read alongside responses, so they arrive while the runtime
+    /// driving this client runs.
+    pub fn events(&mut self) -> Option<UnboundedReceiver<(u64, Result<EventData>)>> {
+        self.events.take()
+    }
+
+    /// The frames of streams started with `start_stream`, in arrival order;
+    /// None once taken.
+    pub fn streams(&mut self) -> Option<UnboundedReceiver<Response>> {
+        self.streams.take()
+    }
+
+    /// Send `cmd` (a following `JournalTail` or `TailFile`, or an `Exec`) and
+    /// have all its frames, the first and any error included, go to
+    /// `streams` until its last one.
+    pub async fn start_stream(&mut self, cmd: &Command) -> Result<()> {
+        let id = cmd.id();
+        self.lock_routes().streams.insert(id);
+        let sent = self.send_command(cmd).await;
+        if sent.is_err() {
+            self.lock_routes().streams.remove(&id);
+        }
+        sent
+    }
+
+    /// Send `cmd` (`JournalStop`, `TailStop` or `ExecKill`) to end its
+    /// stream, whose last frame then arrives on `streams`. Nothing is sent
+    /// for a stream that already ended.
+    pub async fn stop_stream(&mut self, cmd: &Command) -> Result<()> {
+        if !self.lock_routes().streams.contains(&cmd.id()) {
+            return Ok(());
+        }
+        self.send_command(cmd).await
+    }
+
+    fn lock_routes(&self) -> std::sync::MutexGuard<'_, Routes> {
+        self.routes.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Have the agent push `facet` every `interval_ms` (agent default when
+    /// None) to `events` under `id`; returns the interval it settled on.
+    pub async fn subscribe(
+        &mut self,
+        id: u64,
+        facet: Facet,
+        interval_ms: Option<u64>,
+    ) -> Result<u64> {
+        let cmd = Command::Subscribe {
+            id,
+            facet,
+            interval_ms,
+        };
+        match self.request(&cmd).await? {
+            Response::Subscribed {
+                id: rid,
+                interval_ms,
+            } if rid == id => Ok(interval_ms),
+            Response::Error { message, .. } => Err(anyhow!("agent subscribe error: {}", message)),
+            other => Err(anyhow!("unexpected response to Subscribe: {:?}", other)),
+        }
+    }
+
+    /// End the subscription `id`.
+    pub async fn unsubscribe(&mut self, id: u64) -> Result<()> {
+        match self.request(&Command::Unsubscribe { id }).await? {
+            Response::Unsubscribed { id: rid } if rid == id => Ok(()),
+            Response::Error { message, .. } => Err(anyhow!("agent unsubscribe error: {}", message)),
+            other => Err(anyhow!("unexpected response to Unsubscribe: {:?}", other)),
         }
-        let resp: Response =
-            serde_json::from_str(line.trim()).context("parse JSON response from agent")?;
-        Ok(resp)
     }
 
     /// Attempt to gracefully terminate the ssh subprocess.
@@ -247,13 +764,16 @@ impl AgentClient {
         let _ = tokio::io::AsyncWriteExt::shutdown(&mut self.writer).await;
 
         // Attempt to wait briefly; otherwise kill the child.
-        if tokio::time::timeout(Duration::from_millis(500), self.child.wait())
+        let Some(child) = self.child.as_mut() else {
+            return Ok(());
+        };
+        if tokio::time::timeout(Duration::from_millis(500), child.wait())
             .await
             .is_ok()
         {
             Ok(())
         } else {
-            let _ = self.child.kill().await;
+            let _ = child.kill().await;
             Ok(())
         }
     }
@@ -261,19 +781,120 @@ impl AgentClient {
 
 impl Drop for AgentClient {
     fn drop(&mut self) {
+        self.reader.abort();
+        let Some(child) = self.child.as_mut() else {
+            return;
+        };
         // Best-effort kill if still running, without blocking.
         #[cfg(unix)]
         {
             use std::os::unix::process::ExitStatusExt;
-            if let Ok(Some(status)) = self.child.try_wait() {
+            if let Ok(Some(status)) = child.try_wait() {
                 let _ = status.signal(); // just consume
             } else {
-                let _ = self.child.start_kill();
+                let _ = child.start_kill();
             }
         }
         #[cfg(not(unix))]
         {
-            let _ = self.child.start_kill();
+            let _ = child.start_kill();
+        }
+    }
+}
+
+/// Ids of a session's open streams and subscriptions.
+#[derive(Debug, Default)]
+struct Routes {
+    /// Registered by `start_stream`, until their last frame
+    streams: HashSet<u64>,
+    /// From `Subscribed` until `Unsubscribed`
+    subscriptions: HashSet<u64>,
+}
+
+/// Where `read_agent_lines` sends what it reads.
+struct Channels {
+    responses: UnboundedSender<Result<Response>>,
+    events: UnboundedSender<(u64, Result<EventData>)>,
+    streams: UnboundedSender<Response>,
+}
+
+/// Whether `resp` is the last frame of its stream.
+fn ends_stream(resp: &Response) -> bool {
+    match resp {
+        Response::JournalLines { end, .. } | Response::FileLines { end, .. } => *end,
+        Response::ExecExit { .. } | Response::Error { .. } => true,
+        _ => false,
+    }
+}
+
+/// Read agent stdout until it closes, routing responses, stream frames and
+/// events by id.
+async fn read_agent_lines(
+    mut reader: BufReader<Box<dyn AsyncRead + Send + Unpin>>,
+    routes: Arc<Mutex<Routes>>,
+    out: Channels,
+) {
+    loop {
+        let mut line = String::new();
+        match reader.read_line(&mut line).await {
+            Ok(0) => return,
+            Ok(_) => {}
+            Err(e) => {
+                let _ = out
+                    .responses
+                    .send(Err(anyhow!(e).context("read agent stdout")));
+                return;
+            }
+        }
+        let reply = match serde_json::from_str::<slarti_proto::Reply>(line.trim()) {
+            Ok(reply) => reply,
+            Err(e) => {
+                let err = anyhow!(e).context("parse JSON response from agent");
+                if out.responses.send(Err(err)).is_err() {
+                    return;
+                }
+                continue;
+            }
+        };
+        let trace = reply.trace.as_deref().unwrap_or("-");
+        let resp = match reply.response {
+            Response::AgentLog { level, message } => {
+                debug!(target: "slarti_agent", "[{}] {}: {}", trace, level, message);
+                continue;
+            }
+            Response::Event { id, data } => {
+                // Nobody listening is fine: the events are dropped.
+                let _ = out.events.send((id, Ok(data)));
+                continue;
+            }
+            resp => resp,
+        };
+        {
+            let mut routes = routes.lock().unwrap_or_else(|e| e.into_inner());
+            let id = resp.id().unwrap_or_default();
+            if routes.streams.contains(&id) {
+                if ends_stream(&resp) {
+                    routes.streams.remove(&id);
+                }
+                let _ = out.streams.send(resp);
+                continue;
+            }
+            match resp {
+                Response::Error { id, message } if routes.subscriptions.contains(&id) => {
+                    let _ = out.events.send((id, Err(anyhow!(message))));
+                    continue;
+                }
+                Response::Subscribed { id, .. } => {
+                    routes.subscriptions.insert(id);
+                }
+                Response::Unsubscribed { id } => {
+                    routes.subscriptions.remove(&id);
+                }
+                _ => {}
+            }
+        }
+        if out.responses.send(Ok(resp)).is_err() {
+            return;
         }
     }
 }
@@ -285,6 +906,18 @@ pub struct HelloAck {
     pub capabilities: Vec<slarti_proto::Capability>,
 }
 
+/// Short id distinguishing this session's trace ids from other sessions'.
+fn trace_prefix() -> String {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    format!(
+        "{:06x}",
+        (nanos ^ std::process::id().rotate_left(16)) & 0xff_ffff
+    )
+}
+
 /// Check if the agent is present/runnable at the given remote path by invoking:
 /// `ssh -T <target> -- <remote_path> --version`
 ///
@@ -322,6 +955,16 @@ pub async fn check_agent(target: &str, remote_path: &str, dur: Duration) -> Resu
     }
 
     if !status.success() {
+        // ssh itself exits with 255; an auth rejection must not be mistaken for a
+        // missing agent (which also matches "Permission denied").
+        if exit_code == Some(255) && is_auth_failure(&stderr) {
+            return Err(AuthRequired {
+                target: target.to_string(),
+                stderr: stderr_trimmed.to_string(),
+            }
+            .into());
+        }
+
         // Normalize common "missing/not executable" cases to a non-fatal status so the UI can offer Deploy.
         let looks_missing_or_not_exec = matches!(exit_code, Some(126) | Some(127))
             || stderr.contains("No such file or directory")
@@ -372,52 +1015,113 @@ pub async fn check_agent(target: &str, remote_path: &str, dur: Duration) -> Resu
 /// This does not perform the handshake automatically so the caller can decide how to handle
 /// version/capability mismatches.
 pub async fn run_agent(target: &str, remote_path: &str) -> Result<AgentClient> {
+    run_agent_with_flags(target, remote_path, &[]).await
+}
+
+/// [`run_agent`] with extra agent flags after `--stdio` (e.g. its self-limits,
+/// `--max-memory 256M`), each quoted for the remote shell.
+pub async fn run_agent_with_flags(
+    target: &str,
+    remote_path: &str,
+    flags: &[String],
+) -> Result<AgentClient> {
+    breaker::admit(target)?;
+    let permit = queue::acquire().await;
     let mut cmd = TokioCommand::new("ssh");
-    let started = std::time::Instant::now();
+    let started = Instant::now();
     cmd.envs(std::env::vars());
+    askpass::configure(&mut cmd);
     debug!(target: "slarti_ssh", "run_agent: target={} remote_path={}", target, remote_path);
-    cmd.arg("-o")
-        .arg("BatchMode=yes")
-        .arg("-o")
-        .arg("StrictHostKeyChecking=accept-new")
-        .arg("-o")
-        .arg("ConnectTimeout=5")
-        .arg("-o")
-        .arg("ConnectionAttempts=1")
-        .arg("-o")
-        .arg("Compression=yes")
-        .arg("-T")
-        .arg(target)
-        .arg("--")
-        .arg(format!("{} --stdio", remote_path));
+    let script = format!(
+        "{} --stdio{}",
+        remote_path,
+        flags
+            .iter()
+            .map(|f| format!(" {}", shell_quote(f)))
+            .collect::<String>()
+    );
+    cmd.args(ssh_script_args(target, &script, Duration::from_secs(5)).await);
 
     debug!(target: "slarti_ssh", "run_agent: spawning (started {:?})", started);
 
     cmd.stdin(Stdio::piped())
         .stdout(Stdio::piped())
-        .stderr(Stdio::inherit());
+        .stderr(Stdio::piped());
 
-    let mut child = cmd.spawn().context("spawn ssh -T for agent")?;
-
-    let stdin: ChildStdin = child
-        .stdin
-        .take()
-        .ok_or_else(|| anyhow!("agent stdin not available"))?;
-    let stdout: ChildStdout = child
-        .stdout
-        .take()
-        .ok_or_else(|| anyhow!("agent stdout not available"))?;
+    let mut process = spawn::spawn(&mut cmd).context("spawn ssh -T for agent")?;
+    if let Some(stderr) = process.child.as_mut().and_then(|c| c.stderr.take()) {
+        tokio::spawn(watch_agent_stderr(target.to_string(), stderr));
+    }
+    let mut client = AgentClient::from_process(process, started);
+    client.permit = Some(permit);
+    client.breaker_target = Some(target.to_string());
+    Ok(client)
+}
 
-    let reader = BufReader::new(stdout);
-    let writer = BufWriter::new(stdin);
+/// Pass an agent session's ssh stderr through to ours and, once ssh exits,
+/// tell `target`'s circuit breaker whether the connection timed out.
+async fn watch_agent_stderr(target: String, stderr: tokio::process::ChildStderr) {
+    let mut lines = BufReader::new(stderr).lines();
+    let mut timed_out = false;
+    while let Ok(Some(line)) = lines.next_line().await {
+        eprintln!("{}", line);
+        timed_out |= breaker::is_timeout(&line);
+    }
+    breaker::record(&target, timed_out);
+}
 
-    Ok(AgentClient {
-        child,
-        reader,
-        writer,
+/// Check the agent binary at `path` on this machine (`<path> --version`, no ssh).
+pub async fn check_local_agent(path: &Path, dur: Duration) -> Result<AgentStatus> {
+    let mut cmd = TokioCommand::new(path);
+    cmd.arg("--version").stdin(Stdio::null()).kill_on_drop(true);
+    let run = spawn::output(&mut cmd);
+    let missing = |stderr: String| AgentStatus {
+        present: false,
+        version: None,
+        remote_path: path.display().to_string(),
+        can_run: false,
+        stdout: String::new(),
+        stderr,
+    };
+    let output = match tokio::time::timeout(dur, run).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => return Ok(missing(e.to_string())),
+        Err(_) => return Err(anyhow!("{} --version timed out", path.display())),
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    if !output.status.success() {
+        return Ok(missing(stderr));
+    }
+    let version = stdout
+        .lines()
+        .map(|s| s.trim())
+        .find(|s| !s.is_empty())
+        .map(|s| s.to_string());
+    Ok(AgentStatus {
+        present: version.is_some(),
+        version,
+        remote_path: path.display().to_string(),
+        can_run: true,
+        stdout,
+        stderr,
     })
 }
 
+/// Run the agent binary at `path` on this machine (`<path> --stdio`, no ssh).
+pub async fn run_local_agent(path: &Path) -> Result<AgentClient> {
+    debug!(target: "slarti_ssh", "run_local_agent: path={}", path.display());
+    let started = Instant::now();
+    let mut cmd = TokioCommand::new(path);
+    cmd.arg("--stdio")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit());
+    let process =
+        spawn::spawn(&mut cmd).with_context(|| format!("spawn {} --stdio", path.display()))?;
+    Ok(AgentClient::from_process(process, started))
+}
+
 /// Determine if the remote user is root by querying `id -u` over SSH.
 /// Returns true if the UID is 0.
 pub async fn remote_user_is_root(target: &str, dur: Duration) -> Result<bool> {
@@ -432,9 +1136,148 @@ pub struct DeployResult {
     pub used_rsync: bool,
 }
 
-/// Deploy the agent to a hard-coded path on the remote host:
-/// - Non-root: $HOME/.local/share/slarti/agent/<version>/slarti-remote
-/// - Root:     /usr/local/lib/slarti/agent/<version>/slarti-remote
+/// Host entry keyword for the agent install root (params are lowercased by
+/// slarti-sshcfg); ssh skips it once listed in `IgnoreUnknown`.
+pub const AGENT_ROOT_KEYWORD: &str = "slartiagentroot";
+/// Host entry keyword for extra agent flags, e.g. its self-limits or
+/// read-only mode (`SlartiAgentFlags --max-memory 256M --read-only`).
+pub const AGENT_FLAGS_KEYWORD: &str = "slartiagentflags";
+/// Host entry keyword that makes deploys mark the host read-only
+/// (`SlartiAgentReadOnly yes`; see [`mark_agent_read_only`]).
+pub const AGENT_READ_ONLY_KEYWORD: &str = "slartiagentreadonly";
+/// Install roots when none is configured, for a root and a non-root remote user.
+pub const SYSTEM_AGENT_ROOT: &str = "/usr/local/lib/slarti";
+pub const USER_AGENT_ROOT: &str = "$HOME/.local/share/slarti";
+
+/// Whether `root` can be used as an agent install root: an absolute path, or
+/// one under the home directory (`~/…` or `$HOME/…`), that needs no quoting
+/// in the remote shell.
+pub fn valid_install_root(root: &str) -> bool {
+    let rest = root
+        .strip_prefix("~/")
+        .or_else(|| root.strip_prefix("$HOME/"))
+        .or_else(|| root.strip_prefix('/'));
+    rest.is_some_and(|rest| {
+        !rest.is_empty()
+            && !rest.split('/').any(|part| part == "..")
+            && rest
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "@%+=:,./_-".contains(c))
+    })
+}
+
+/// Directory (for the shell) holding one subdirectory per deployed agent
+/// version: `<root>/agent`, under `install_root` or the default for a root or
+/// non-root remote user.
+pub(crate) fn agents_dir(is_root: bool, install_root: Option<&str>) -> String {
+    let root = match install_root.map(|r| r.trim_end_matches('/')) {
+        // The shell does not expand "~" inside the quoted scripts; $HOME it does.
+        Some(root) => match root.strip_prefix("~/") {
+            Some(rest) => format!("$HOME/{}", rest),
+            None => root.to_string(),
+        },
+        None if is_root => SYSTEM_AGENT_ROOT.to_string(),
+        None => USER_AGENT_ROOT.to_string(),
+    };
+    format!("{}/agent", root)
+}
+
+/// Agent install locations under `install_root` (the default for a root or
+/// non-root remote user when None): (dir for the shell, rsync destination
+/// dir, agent path). Agents live in `<root>/agent/<version>/slarti-remote`.
+pub(crate) fn install_dirs(
+    is_root: bool,
+    version: &str,
+    install_root: Option<&str>,
+) -> (String, String, String) {
+    let dir = format!("{}/{}", agents_dir(is_root, install_root), version);
+    // rsync does not expand $HOME either: give it the path relative to home.
+    let rsync_dst = dir.strip_prefix("$HOME/").unwrap_or(&dir).to_string();
+    let path = format!("{}/slarti-remote", dir);
+    (dir, rsync_dst, path)
+}
+
+/// Where [`deploy_agent`] installs `version` for a root or non-root remote
+/// user, to pass to [`check_agent`] and [`run_agent`].
+pub fn agent_path(is_root: bool, version: &str, install_root: Option<&str>) -> String {
+    install_dirs(is_root, version, install_root).2
+}
+
+/// The commands of a deploy, shared by [`deploy_agent`] and its preview
+/// ([`plan::deploy_plan`]): remote scripts for `ssh` and argument lists for
+/// the uploads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DeploySteps {
+    /// Creates the version dir
+    pub mkdir: String,
+    /// rsync arguments of the upload (preferred)
+    pub rsync: Vec<String>,
+    /// scp arguments of the upload when rsync fails
+    pub scp: Vec<String>,
+    /// Moves an artifact not named `slarti-remote` into place
+    pub rename: Option<String>,
+    /// Makes an scp upload executable (rsync sets the mode itself)
+    pub chmod: String,
+    /// Where the agent ends up
+    pub agent_path: String,
+}
+
+/// The [`DeploySteps`] installing `local_artifact` as `version` on `target`.
+pub(crate) async fn deploy_steps(
+    target: &str,
+    local_artifact: &Path,
+    is_root: bool,
+    version: &str,
+    install_root: Option<&str>,
+) -> DeploySteps {
+    let (dir, rsync_dir, agent_path) = install_dirs(is_root, version, install_root);
+    let file_name = local_artifact
+        .file_name()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "slarti-remote".to_string());
+    let artifact = local_artifact.to_string_lossy().into_owned();
+    // Uploads run over the same control socket and bastion route as ssh.
+    let transport: Vec<String> = control_options(target)
+        .await
+        .into_iter()
+        .chain(jump::proxy_options(target).await)
+        .collect();
+    let rsync_ssh = std::iter::once("ssh".to_string())
+        .chain(transport.iter().map(|o| shell_quote(o)))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let rsync = vec![
+        "-e".to_string(),
+        rsync_ssh,
+        "-az".to_string(),
+        "--chmod=755".to_string(),
+        artifact.clone(),
+        format!("{}:{}", target, rsync_dir),
+    ];
+    let mut scp = transport;
+    scp.push(artifact);
+    scp.push(format!("{}:{}/{}", target, rsync_dir, file_name));
+    let rename = (file_name != "slarti-remote").then(|| {
+        format!(
+            "mv -- {dir}/{name} {dir}/slarti-remote && chmod 755 -- {dir}/slarti-remote",
+            dir = dir,
+            name = file_name
+        )
+    });
+    DeploySteps {
+        mkdir: format!("mkdir -p {}", dir),
+        rsync,
+        scp,
+        rename,
+        chmod: format!("chmod 755 -- {}", agent_path),
+        agent_path,
+    }
+}
+
+/// Deploy the agent to `<root>/agent/<version>/slarti-remote` on the remote
+/// host, where root is `install_root` or else, for the remote user:
+/// - Non-root: $HOME/.local/share/slarti
+/// - Root:     /usr/local/lib/slarti
 ///
 /// The `local_artifact` can be a binary or a .tar.gz archive containing
 /// `bin/slarti-remote`. rsync is preferred; scp is used as a fallback.
@@ -442,110 +1285,259 @@ pub async fn deploy_agent(
     target: &str,
     local_artifact: &Path,
     version: &str,
+    install_root: Option<&str>,
     timeout: Duration,
 ) -> Result<DeployResult> {
+    if let Some(root) = install_root.filter(|r| !valid_install_root(r)) {
+        return Err(anyhow!("invalid agent install root {:?}", root));
+    }
     // Decide install dir based on remote user.
     let is_root = remote_user_is_root(target, timeout).await.unwrap_or(false);
-    let (remote_dir_abs, remote_dir_rsync_dst, remote_path_for_agent) = if is_root {
-        let dir = format!("/usr/local/lib/slarti/agent/{}", version);
-        (dir.clone(), dir.clone(), format!("{}/slarti-remote", dir))
-    } else {
-        // For rsync, use relative-to-home path; for mkdir/mv/chmod use $HOME via the shell.
-        let rel = format!(".local/share/slarti/agent/{}", version);
-        (
-            format!("$HOME/{}", rel),
-            rel.clone(),
-            format!("$HOME/{}/slarti-remote", rel.clone()),
-        )
-    };
-
+    let steps = deploy_steps(target, local_artifact, is_root, version, install_root).await;
     debug!(
         target: "slarti_ssh",
-        "deploy: target={} version={} remote_dir_abs={} rsync_dst={} artifact={:?}",
-        target, version, remote_dir_abs, remote_dir_rsync_dst, local_artifact
+        "deploy: target={} version={} artifact={:?} steps={:?}",
+        target, version, local_artifact, steps
     );
 
     // Ensure target directory exists (shell expansion handles $HOME for non-root)
-    let mkdir_script = format!("'mkdir -p {remote_dir_abs}'");
-    let (st_mkdir, _so_mkdir, _se_mkdir) = ssh_run_capture(target, &mkdir_script, timeout).await?;
+    let (st_mkdir, _so_mkdir, _se_mkdir) = ssh_run_capture(target, &steps.mkdir, timeout).await?;
     if !st_mkdir.success() {
         return Err(anyhow!("remote mkdir failed on {}", target));
     }
 
     // Upload via rsync to directory (relative for non-root, absolute for root)
-    let rsync_dst = format!("{}:{}", target, remote_dir_rsync_dst);
-    debug!(target: "slarti_ssh", "deploy: rsync {:?} -> {}", local_artifact, rsync_dst);
-    let rsync_status = TokioCommand::new("rsync")
-        .arg("-az")
-        .arg("--chmod=755")
-        .arg(local_artifact.as_os_str())
-        .arg(&rsync_dst)
+    let permit = queue::acquire().await;
+    let mut rsync = TokioCommand::new("rsync");
+    askpass::configure(&mut rsync);
+    rsync
+        .args(&steps.rsync)
         .stdin(Stdio::null())
         .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .status();
+        .stderr(Stdio::inherit());
     let mut uploaded = false;
     let mut used_rsync = false;
-    if let Ok(status) = rsync_status.await {
-        debug!(target: "slarti_ssh", "deploy: rsync status={}", status);
-        if status.success() {
+    if let Ok(out) = spawn::output(&mut rsync).await {
+        debug!(target: "slarti_ssh", "deploy: rsync status={}", out.status);
+        if out.status.success() {
             used_rsync = true;
             uploaded = true;
         }
     }
 
     // Fallback to scp if needed
-    let file_name = PathBuf::from(local_artifact)
-        .file_name()
-        .map(|s| s.to_string_lossy().into_owned())
-        .unwrap_or_else(|| "slarti-remote".to_string());
     if !uploaded {
         debug!(target: "slarti_ssh", "deploy: rsync failed, falling back to scp");
-        let scp_dst = format!("{}:{}/{}", target, remote_dir_rsync_dst, file_name);
-        let scp_status = TokioCommand::new("scp")
-            .arg(local_artifact.as_os_str())
-            .arg(&scp_dst)
+        let mut scp = TokioCommand::new("scp");
+        askpass::configure(&mut scp);
+        scp.args(&steps.scp)
             .stdin(Stdio::null())
             .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit())
-            .status()
+            .stderr(Stdio::inherit());
+        let scp_status = spawn::output(&mut scp)
             .await
-            .context("scp failed to run")?;
+            .context("scp failed to run")?
+            .status;
         debug!(target: "slarti_ssh", "deploy: scp status={}", scp_status);
         if scp_status.success() {
             uploaded = true;
         }
     }
 
+    // Release the upload slot before the follow-up ssh commands queue for their own.
+    drop(permit);
     if !uploaded {
         return Err(anyhow!("failed to upload agent (rsync/scp) to {}", target));
     }
 
     // If uploaded basename differs, move and chmod in a single remote script.
-    if file_name != "slarti-remote" {
-        let mv_script = format!(
-            "mv -- {dir}/{name} {dir}/slarti-remote && chmod 755 -- {dir}/slarti-remote",
-            dir = remote_dir_abs,
-            name = file_name
-        );
-        debug!(target: "slarti_ssh", "deploy: {}", mv_script);
-        let (st_mv, _so_mv, _se_mv) = ssh_run_capture(target, &mv_script, timeout).await?;
+    if let Some(rename) = &steps.rename {
+        let (st_mv, _so_mv, _se_mv) = ssh_run_capture(target, rename, timeout).await?;
         if !st_mv.success() {
             return Err(anyhow!("remote move/chmod failed on {}", target));
         }
     } else if !used_rsync {
         // Ensure perms if we used scp
-        let chmod_script = format!("chmod 755 -- {}", remote_path_for_agent);
-        debug!(target: "slarti_ssh", "deploy: {}", chmod_script);
         let (st_chmod, _so_chmod, _se_chmod) =
-            ssh_run_capture(target, &chmod_script, timeout).await?;
+            ssh_run_capture(target, &steps.chmod, timeout).await?;
         if !st_chmod.success() {
             return Err(anyhow!("remote chmod failed on {}", target));
         }
     }
 
     Ok(DeployResult {
-        remote_path: remote_path_for_agent,
+        remote_path: steps.agent_path,
         used_rsync,
     })
 }
+
+/// Leave the read-only marker in `<root>/agent` on `target`, so that every
+/// agent version there refuses mutating commands whatever flags a client
+/// starts it with. Nothing here removes the marker: turning the mode off
+/// takes deleting the file on the host.
+pub async fn mark_agent_read_only(
+    target: &str,
+    install_root: Option<&str>,
+    timeout: Duration,
+) -> Result<()> {
+    if let Some(root) = install_root.filter(|r| !valid_install_root(r)) {
+        return Err(anyhow!("invalid agent install root {:?}", root));
+    }
+    let is_root = remote_user_is_root(target, timeout).await.unwrap_or(false);
+    let script = read_only_marker_script(&agents_dir(is_root, install_root));
+    let (st, _so, _se) = ssh_run_capture(target, &script, timeout).await?;
+    if !st.success() {
+        return Err(anyhow!("marking the agent read-only failed on {}", target));
+    }
+    Ok(())
+}
+
+/// Remote script creating the read-only marker in the agents dir `dir`.
+pub(crate) fn read_only_marker_script(dir: &str) -> String {
+    format!(
+        "mkdir -p -- {dir} && touch -- {dir}/{}",
+        slarti_proto::AGENT_READ_ONLY_MARKER
+    )
+}
+
+/// Agent versions kept on a host after a deploy, the deployed one included.
+pub const KEEP_AGENT_VERSIONS: usize = 3;
+
+/// Version directories under `<root>/agent`, newest first: those kept and
+/// those removed (or, for a dry run, that would be removed).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AgentCleanup {
+    /// `<root>/agent` as the remote shell sees it.
+    pub dir: String,
+    pub kept: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl AgentCleanup {
+    /// The command that removes [`AgentCleanup::removed`] (None: nothing to remove).
+    pub fn remove_script(&self) -> Option<String> {
+        (!self.removed.is_empty()).then(|| {
+            let paths: Vec<String> = self
+                .removed
+                .iter()
+                .map(|v| format!("{}/{}", self.dir, v))
+                .collect();
+            format!("rm -rf -- {}", paths.join(" "))
+        })
+    }
+}
+
+/// Whether a directory name under `<root>/agent` is a version slarti deployed
+/// (anything else there is left alone).
+fn is_agent_version(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_digit())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || ".+_-".contains(c))
+}
+
+/// Split `listing` (`ls -1t`, newest first) into the `keep` newest versions,
+/// always including `current`, and the rest.
+pub(crate) fn select_old_agents(
+    dir: String,
+    listing: &str,
+    current: &str,
+    keep: usize,
+) -> AgentCleanup {
+    let versions: Vec<&str> = listing
+        .lines()
+        .map(str::trim)
+        .filter(|name| is_agent_version(name))
+        .collect();
+    let mut kept: Vec<String> = Vec::new();
+    let mut removed = Vec::new();
+    // The current version counts against `keep` wherever it sorts.
+    let mut room = keep.saturating_sub(usize::from(versions.contains(&current)));
+    for v in versions {
+        if v == current {
+            kept.push(v.to_string());
+        } else if room > 0 {
+            room -= 1;
+            kept.push(v.to_string());
+        } else {
+            removed.push(v.to_string());
+        }
+    }
+    AgentCleanup { dir, kept, removed }
+}
+
+/// Remove all but the `keep` most recently deployed agent versions under the
+/// install root (see [`deploy_agent`]), never `current_version`. With
+/// `dry_run` only lists what would be removed.
+pub async fn clean_old_agents(
+    target: &str,
+    current_version: &str,
+    install_root: Option<&str>,
+    keep: usize,
+    dry_run: bool,
+    timeout: Duration,
+) -> Result<AgentCleanup> {
+    if let Some(root) = install_root.filter(|r| !valid_install_root(r)) {
+        return Err(anyhow!("invalid agent install root {:?}", root));
+    }
+    let is_root = remote_user_is_root(target, timeout).await.unwrap_or(false);
+    let dir = agents_dir(is_root, install_root);
+    // A missing directory lists nothing: no agent was deployed there yet.
+    let list_script = format!("ls -1t -- {} 2>/dev/null || true", dir);
+    let (st_ls, listing, _se_ls) = ssh_run_capture(target, &list_script, timeout).await?;
+    if !st_ls.success() {
+        return Err(anyhow!("listing agent versions failed on {}", target));
+    }
+    let cleanup = select_old_agents(dir, &listing, current_version, keep);
+    debug!(
+        target: "slarti_ssh",
+        "clean agents: target={} dir={} kept={:?} removed={:?} dry_run={}",
+        target, cleanup.dir, cleanup.kept, cleanup.removed, dry_run
+    );
+    if let Some(rm_script) = cleanup.remove_script().filter(|_| !dry_run) {
+        let (st_rm, _so_rm, _se_rm) = ssh_run_capture(target, &rm_script, timeout).await?;
+        if !st_rm.success() {
+            return Err(anyhow!("removing old agent versions failed on {}", target));
+        }
+    }
+    Ok(cleanup)
+}
+
+/// Quote a string for safe use as a single POSIX shell word.
+pub fn shell_quote(s: &str) -> String {
+    if !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "@%+=:,./_-".contains(c))
+    {
+        return s.to_string();
+    }
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+/// Whether `program` (a bare name looked up on PATH, or a path) is an executable file.
+pub fn on_path(program: &str) -> bool {
+    if program.contains('/') {
+        return is_executable(Path::new(program));
+    }
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| is_executable(&dir.join(program))))
+        .unwrap_or(false)
+}
+
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path)
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+/// Shell command line that installs the user's public key on `target` via `ssh-copy-id`.
+///
+/// Meant to be run in an interactive terminal: ssh-copy-id prompts for the
+/// password (or other interactive auth) once, after which key auth works.
+pub fn ssh_copy_id_command(target: &str) -> String {
+    format!(
+        "ssh-copy-id -o StrictHostKeyChecking=accept-new {}",
+        shell_quote(target)
+    )
+}
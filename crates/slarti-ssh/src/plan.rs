@@ -0,0 +1,135 @@
+//! Previews of mutating actions: what will run, where, as whom.
+//!
+//! Built without changing anything on the host (at most read-only probes such
+//! as `id -u` or listing agent versions), so the UI can show the exact command
+//! lines for approval first.
+
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::{
+    clean_old_agents, deploy_steps, remote_user_is_root, shell_quote, ssh_copy_id_command,
+    ssh_script_args,
+};
+
+/// What a mutating action will execute.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ActionPlan {
+    /// Short action name, e.g. "Deploy agent v0.1.0".
+    pub action: String,
+    pub host: String,
+    /// Remote login user, as resolved from the ssh config.
+    pub user: String,
+    /// Privilege escalation used (e.g. "sudo"); None when commands run as `user`.
+    pub escalation: Option<String>,
+    /// Command lines in execution order, as run from this machine.
+    pub commands: Vec<String>,
+}
+
+impl ActionPlan {
+    /// Human-readable lines: host, user, escalation, then each command.
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("host: {}", self.host),
+            format!("user: {}", self.user),
+            format!(
+                "escalation: {}",
+                self.escalation.as_deref().unwrap_or("none")
+            ),
+        ];
+        lines.extend(self.commands.iter().map(|c| format!("$ {}", c)));
+        lines
+    }
+}
+
+/// Login user for `target` from `ssh -G` (the effective config; no connection made).
+pub async fn remote_user(target: &str) -> String {
+    crate::effective::effective(target)
+        .await
+        .user
+        .unwrap_or_else(|| "(unknown)".to_string())
+}
+
+/// Plan for `deploy_agent(target, local_artifact, version, install_root, ..)`.
+///
+/// Probes the remote uid (like the deploy itself) to pick the install dir,
+/// and lists the commands the deploy builds, fallbacks included.
+pub async fn deploy_plan(
+    target: &str,
+    local_artifact: &Path,
+    version: &str,
+    install_root: Option<&str>,
+    timeout: Duration,
+) -> ActionPlan {
+    let is_root = remote_user_is_root(target, timeout).await.unwrap_or(false);
+    let steps = deploy_steps(target, local_artifact, is_root, version, install_root).await;
+    let mut commands = vec![
+        ssh_line(target, &steps.mkdir, timeout).await,
+        command_line("rsync", &steps.rsync),
+        format!("if rsync fails: {}", command_line("scp", &steps.scp)),
+    ];
+    match &steps.rename {
+        Some(rename) => commands.push(ssh_line(target, rename, timeout).await),
+        None => commands.push(format!(
+            "after scp: {}",
+            ssh_line(target, &steps.chmod, timeout).await
+        )),
+    }
+    ActionPlan {
+        action: format!("Deploy agent v{}", version),
+        host: target.to_string(),
+        user: remote_user(target).await,
+        escalation: None,
+        commands,
+    }
+}
+
+/// The ssh command line running `script` on `target`.
+async fn ssh_line(target: &str, script: &str, timeout: Duration) -> String {
+    command_line("ssh", &ssh_script_args(target, script, timeout).await)
+}
+
+/// `program` and `args` as one shell command line.
+fn command_line(program: &str, args: &[String]) -> String {
+    std::iter::once(program.to_string())
+        .chain(args.iter().map(|a| shell_quote(a)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Plan for `clean_old_agents(target, current_version, install_root, keep, ..)`:
+/// a dry run listing the versions kept, and the removal of the others (None
+/// when there is nothing to remove).
+pub async fn clean_agents_plan(
+    target: &str,
+    current_version: &str,
+    install_root: Option<&str>,
+    keep: usize,
+    timeout: Duration,
+) -> Result<Option<ActionPlan>> {
+    let cleanup =
+        clean_old_agents(target, current_version, install_root, keep, true, timeout).await?;
+    let Some(rm) = cleanup.remove_script() else {
+        return Ok(None);
+    };
+    Ok(Some(ActionPlan {
+        action: format!("Clean old agents (keeping {})", cleanup.kept.join(", ")),
+        host: target.to_string(),
+        user: remote_user(target).await,
+        escalation: None,
+        commands: vec![ssh_line(target, &rm, timeout).await],
+    }))
+}
+
+/// Plan for installing the user's public key (`ssh_copy_id_command`).
+pub async fn install_key_plan(target: &str) -> ActionPlan {
+    ActionPlan {
+        action: "Install my key".to_string(),
+        host: target.to_string(),
+        user: remote_user(target).await,
+        escalation: None,
+        commands: vec![ssh_copy_id_command(target)],
+    }
+}
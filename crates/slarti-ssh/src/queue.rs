@@ -0,0 +1,342 @@
+//! Concurrency limit for ssh operations, with priority classes.
+//!
+//! Every ssh/rsync/scp invocation takes a slot. Background work (polling,
+//! fleet sweeps) is capped at the limit (`SLARTI_MAX_SSH`, default 8);
+//! interactive work (a host the user just clicked, a deploy, a login) may use
+//! a few reserved slots beyond it and is always served before queued
+//! background work, so it never waits behind a saturated sweep.
+//!
+//! Work runs as background unless wrapped in [`interactive`].
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Mutex, OnceLock};
+use std::task::{Context, Poll, Waker};
+
+/// Slots only interactive work may use.
+const INTERACTIVE_RESERVE: usize = 2;
+const DEFAULT_LIMIT: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Priority {
+    /// User-initiated: preempts queued background work.
+    Interactive,
+    /// Polling and sweeps.
+    Background,
+}
+
+tokio::task_local! {
+    static PRIORITY: Priority;
+}
+
+/// Run `fut` with interactive priority for the ssh operations it starts.
+pub async fn interactive<F: Future>(fut: F) -> F::Output {
+    PRIORITY.scope(Priority::Interactive, fut).await
+}
+
+fn current_priority() -> Priority {
+    PRIORITY.try_with(|p| *p).unwrap_or(Priority::Background)
+}
+
+/// Snapshot of the queue (for diagnostics).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueStats {
+    pub active: usize,
+    pub waiting_interactive: usize,
+    pub waiting_background: usize,
+}
+
+pub fn stats() -> QueueStats {
+    queue().stats()
+}
+
+#[derive(Debug)]
+struct State {
+    limit: usize,
+    active: usize,
+    next_id: u64,
+    /// Waiters by arrival order.
+    waiters: BTreeMap<u64, (Priority, Waker)>,
+}
+
+impl State {
+    fn waiting(&self, priority: Priority) -> usize {
+        self.waiters
+            .values()
+            .filter(|(p, _)| *p == priority)
+            .count()
+    }
+
+    /// Whether waiter `id` may take a slot now: there is room for its class and
+    /// no earlier waiter of its class (or any interactive one, for background) is queued.
+    fn can_start(&self, id: u64, priority: Priority) -> bool {
+        let cap = match priority {
+            Priority::Interactive => self.limit + INTERACTIVE_RESERVE,
+            Priority::Background => self.limit,
+        };
+        self.active < cap
+            && !self.waiters.iter().any(|(other, (p, _))| {
+                *other != id
+                    && match priority {
+                        Priority::Interactive => *p == Priority::Interactive && *other < id,
+                        Priority::Background => *p == Priority::Interactive || *other < id,
+                    }
+            })
+    }
+}
+
+/// The slots and their waiters; one per process (see [`queue`]).
+#[derive(Debug)]
+struct Queue {
+    state: Mutex<State>,
+}
+
+impl Queue {
+    fn new(limit: usize) -> Self {
+        Queue {
+            state: Mutex::new(State {
+                limit,
+                active: 0,
+                next_id: 0,
+                waiters: BTreeMap::new(),
+            }),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn stats(&self) -> QueueStats {
+        let state = self.state();
+        QueueStats {
+            active: state.active,
+            waiting_interactive: state.waiting(Priority::Interactive),
+            waiting_background: state.waiting(Priority::Background),
+        }
+    }
+
+    fn acquire(&'static self, priority: Priority) -> Acquire {
+        Acquire {
+            queue: self,
+            priority,
+            id: None,
+        }
+    }
+}
+
+fn queue() -> &'static Queue {
+    static QUEUE: OnceLock<Queue> = OnceLock::new();
+    QUEUE.get_or_init(|| {
+        let limit = std::env::var("SLARTI_MAX_SSH")
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(DEFAULT_LIMIT);
+        Queue::new(limit)
+    })
+}
+
+/// A held slot; released on drop.
+#[derive(Debug)]
+pub struct Permit(&'static Queue);
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let mut state = self.0.state();
+        state.active -= 1;
+        // Few waiters; let each re-check its turn.
+        for (_, waker) in state.waiters.values() {
+            waker.wake_by_ref();
+        }
+    }
+}
+
+/// Wait for a slot at the current task's priority.
+pub(crate) fn acquire() -> Acquire {
+    queue().acquire(current_priority())
+}
+
+pub(crate) struct Acquire {
+    queue: &'static Queue,
+    priority: Priority,
+    /// Set once queued.
+    id: Option<u64>,
+}
+
+impl Future for Acquire {
+    type Output = Permit;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Permit> {
+        let queue = self.queue;
+        let mut state = queue.state();
+        let id = match self.id {
+            Some(id) => id,
+            None => {
+                let id = state.next_id;
+                state.next_id += 1;
+                self.id = Some(id);
+                id
+            }
+        };
+        if state.can_start(id, self.priority) {
+            state.waiters.remove(&id);
+            state.active += 1;
+            self.id = None;
+            // Later waiters may be startable too (e.g. a background one behind a started interactive one).
+            for (_, waker) in state.waiters.values() {
+                waker.wake_by_ref();
+            }
+            return Poll::Ready(Permit(queue));
+        }
+        state
+            .waiters
+            .insert(id, (self.priority, cx.waker().clone()));
+        Poll::Pending
+    }
+}
+
+impl Drop for Acquire {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            let mut state = self.queue.state();
+            if state.waiters.remove(&id).is_some() {
+                for (_, waker) in state.waiters.values() {
+                    waker.wake_by_ref();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::Wake;
+
+    /// A queue of its own, so tests do not share slots with each other.
+    fn queue(limit: usize) -> &'static Queue {
+        Box::leak(Box::new(Queue::new(limit)))
+    }
+
+    /// Counts its wake-ups.
+    #[derive(Default)]
+    struct Wakes(AtomicUsize);
+
+    impl Wake for Wakes {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn poll(acquire: &mut Acquire) -> Option<Permit> {
+        poll_with(acquire, &Arc::new(Wakes::default()))
+    }
+
+    fn poll_with(acquire: &mut Acquire, wakes: &Arc<Wakes>) -> Option<Permit> {
+        let waker = wakes.clone().into();
+        match Pin::new(acquire).poll(&mut Context::from_waker(&waker)) {
+            Poll::Ready(permit) => Some(permit),
+            Poll::Pending => None,
+        }
+    }
+
+    #[test]
+    fn background_waits_in_arrival_order() {
+        let q = queue(1);
+        let held = poll(&mut q.acquire(Priority::Background)).unwrap();
+        let mut first = q.acquire(Priority::Background);
+        let mut second = q.acquire(Priority::Background);
+        assert!(poll(&mut first).is_none());
+        assert!(poll(&mut second).is_none());
+        assert_eq!(q.stats().waiting_background, 2);
+
+        drop(held);
+        // The later waiter cannot overtake, even when polled first.
+        assert!(poll(&mut second).is_none());
+        let first = poll(&mut first).unwrap();
+        assert!(poll(&mut second).is_none());
+        drop(first);
+        assert!(poll(&mut second).is_some());
+        assert_eq!(q.stats(), QueueStats::default());
+    }
+
+    #[test]
+    fn interactive_uses_the_reserve_and_goes_first() {
+        let q = queue(2);
+        let bg: Vec<Permit> = (0..2)
+            .map(|_| poll(&mut q.acquire(Priority::Background)).unwrap())
+            .collect();
+        let mut queued_bg = q.acquire(Priority::Background);
+        assert!(poll(&mut queued_bg).is_none());
+
+        // Background is at its cap; interactive work gets the reserved slots.
+        let reserved: Vec<Permit> = (0..INTERACTIVE_RESERVE)
+            .map(|_| poll(&mut q.acquire(Priority::Interactive)).unwrap())
+            .collect();
+        let mut queued_int = q.acquire(Priority::Interactive);
+        assert!(poll(&mut queued_int).is_none());
+        assert_eq!(
+            q.stats(),
+            QueueStats {
+                active: 2 + INTERACTIVE_RESERVE,
+                waiting_interactive: 1,
+                waiting_background: 1,
+            }
+        );
+
+        // A freed slot goes to the interactive waiter that queued later.
+        drop(bg);
+        assert!(poll(&mut queued_bg).is_none());
+        let int = poll(&mut queued_int).unwrap();
+        // Background still waits while interactive work fills its cap.
+        assert!(poll(&mut queued_bg).is_none());
+        drop(reserved);
+        let bg = poll(&mut queued_bg).unwrap();
+        assert_eq!(q.stats().active, 2);
+        drop((int, bg));
+    }
+
+    #[test]
+    fn queued_background_yields_to_any_queued_interactive() {
+        let q = queue(1);
+        let held: Vec<Permit> = (0..1 + INTERACTIVE_RESERVE)
+            .map(|_| poll(&mut q.acquire(Priority::Interactive)).unwrap())
+            .collect();
+        let mut bg = q.acquire(Priority::Background);
+        let mut int = q.acquire(Priority::Interactive);
+        assert!(poll(&mut bg).is_none());
+        assert!(poll(&mut int).is_none());
+        drop(held);
+        assert!(poll(&mut bg).is_none());
+        let int = poll(&mut int).unwrap();
+        // The interactive permit fills the background cap of one.
+        assert!(poll(&mut bg).is_none());
+        drop(int);
+        assert!(poll(&mut bg).is_some());
+    }
+
+    #[test]
+    fn releasing_or_abandoning_wakes_waiters() {
+        let q = queue(1);
+        let held = poll(&mut q.acquire(Priority::Background)).unwrap();
+        let mut first = q.acquire(Priority::Background);
+        let mut second = q.acquire(Priority::Background);
+        let wakes = Arc::new(Wakes::default());
+        assert!(poll(&mut first).is_none());
+        assert!(poll_with(&mut second, &wakes).is_none());
+
+        // A waiter that gives up does not hold the line.
+        drop(first);
+        assert_eq!(wakes.0.load(Ordering::SeqCst), 1);
+        assert_eq!(q.stats().waiting_background, 1);
+        assert!(poll_with(&mut second, &wakes).is_none());
+
+        drop(held);
+        assert_eq!(wakes.0.load(Ordering::SeqCst), 2);
+        assert!(poll(&mut second).is_some());
+    }
+}
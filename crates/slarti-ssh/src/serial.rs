@@ -0,0 +1,78 @@
+//! Serial consoles for devices on local serial ports (network gear, embedded
+//! boards).
+//!
+//! A host entry in the ssh config becomes a serial host with slarti-specific
+//! keywords, which ssh skips once they are listed in `IgnoreUnknown`:
+//!
+//! ```text
+//! IgnoreUnknown SlartiSerial,SlartiBaud
+//!
+//! Host switch1
+//!     SlartiSerial /dev/ttyUSB0
+//!     SlartiBaud 9600
+//! ```
+//!
+//! There is no agent on the other end of the line, so the agent protocol is
+//! not spoken; the console is opened in the terminal with whichever of
+//! picocom, minicom or screen is installed.
+
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+
+use crate::{on_path, shell_quote};
+
+/// Host entry keyword naming the serial device (params are lowercased by slarti-sshcfg).
+pub const SERIAL_KEYWORD: &str = "slartiserial";
+/// Host entry keyword for the line speed.
+pub const BAUD_KEYWORD: &str = "slartibaud";
+/// Line speed when the entry does not set one.
+pub const DEFAULT_BAUD: u32 = 115_200;
+
+/// A serial device and the speed to open it at.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SerialConsole {
+    pub device: PathBuf,
+    pub baud: u32,
+}
+
+impl SerialConsole {
+    /// Build from a host entry's `SlartiSerial`/`SlartiBaud` values.
+    pub fn new(device: &str, baud: Option<&str>) -> Result<Self> {
+        let baud = match baud {
+            Some(b) => b
+                .trim()
+                .parse()
+                .map_err(|_| anyhow!("invalid SlartiBaud {:?}", b))?,
+            None => DEFAULT_BAUD,
+        };
+        Ok(Self {
+            device: PathBuf::from(device),
+            baud,
+        })
+    }
+
+    /// e.g. "/dev/ttyUSB0 @ 115200".
+    pub fn describe(&self) -> String {
+        format!("{} @ {}", self.device.display(), self.baud)
+    }
+
+    /// Command line that opens the console, using the first console program found on PATH.
+    pub fn command(&self) -> Result<String> {
+        if !self.device.exists() {
+            return Err(anyhow!("{} not found", self.device.display()));
+        }
+        let device = shell_quote(&self.device.to_string_lossy());
+        let baud = self.baud;
+        if on_path("picocom") {
+            Ok(format!("picocom -b {baud} {device}"))
+        } else if on_path("minicom") {
+            Ok(format!("minicom -b {baud} -D {device}"))
+        } else if on_path("screen") {
+            Ok(format!("screen {device} {baud}"))
+        } else {
+            Err(anyhow!(
+                "no serial console program found (install picocom, minicom or screen)"
+            ))
+        }
+    }
+}
@@ -0,0 +1,818 @@
+//! Process spawning, behind a trait so it can be scripted in tests.
+//!
+//! Every ssh, rsync, scp and docker/kubectl exec this crate starts goes
+//! through [`output`] (run to completion, output captured) or [`spawn`]
+//! (agent sessions, stdin/stdout piped). They use the OS ([`System`]) unless
+//! the calling task runs inside [`scope`], which substitutes another
+//! [`Spawner`], typically a [`MockSpawner`] with canned exit codes, output,
+//! delays and agent replies:
+//!
+//! ```ignore
+//! let mock = MockSpawner::new();
+//! mock.on("ssh", "--version", Reply::exit(127).stderr("sh: 1: slarti-remote: not found"));
+//! let status = spawn::scope(mock.shared(), check_agent("web1", "/opt/slarti-remote", timeout)).await?;
+//! assert!(!status.present);
+//! ```
+//!
+//! Only process spawning is substituted: the ssh queue and circuit breaker
+//! still apply. `ssh -G` config lookups go through the spawner too, so a
+//! scripted run reads no ssh config.
+
+use slarti_proto::{Command, Request, Response};
+use std::future::Future;
+use std::io;
+use std::os::unix::process::ExitStatusExt;
+use std::pin::Pin;
+use std::process::{ExitStatus, Output};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command as TokioCommand};
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Starts processes for this crate.
+pub trait Spawner: Send + Sync {
+    /// Run `cmd` to completion, as `Command::output` does.
+    fn output<'a>(&'a self, cmd: &'a mut TokioCommand) -> BoxFuture<'a, io::Result<Output>>;
+
+    /// Start `cmd` with its stdin and stdout piped.
+    fn spawn(&self, cmd: &mut TokioCommand) -> io::Result<Process>;
+}
+
+/// A started process: its piped stdin and stdout.
+pub struct Process {
+    pub stdin: Box<dyn AsyncWrite + Send + Unpin>,
+    pub stdout: Box<dyn AsyncRead + Send + Unpin>,
+    /// The OS process; `None` for scripted ones, which end when both pipes close.
+    pub child: Option<Child>,
+}
+
+/// Spawns real processes.
+pub struct System;
+
+impl Spawner for System {
+    fn output<'a>(&'a self, cmd: &'a mut TokioCommand) -> BoxFuture<'a, io::Result<Output>> {
+        Box::pin(cmd.output())
+    }
+
+    fn spawn(&self, cmd: &mut TokioCommand) -> io::Result<Process> {
+        let mut child = cmd
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .spawn()?;
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| io::Error::other("stdin not available"))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| io::Error::other("stdout not available"))?;
+        Ok(Process {
+            stdin: Box::new(stdin),
+            stdout: Box::new(stdout),
+            child: Some(child),
+        })
+    }
+}
+
+tokio::task_local! {
+    static SPAWNER: Arc<dyn Spawner>;
+}
+
+/// Run `fut` with `spawner` starting the processes it needs.
+pub async fn scope<F: Future>(spawner: Arc<dyn Spawner>, fut: F) -> F::Output {
+    SPAWNER.scope(spawner, fut).await
+}
+
+fn current() -> Arc<dyn Spawner> {
+    SPAWNER
+        .try_with(Arc::clone)
+        .unwrap_or_else(|_| Arc::new(System))
+}
+
+/// Whether the calling task runs inside [`scope`].
+pub(crate) fn scoped() -> bool {
+    SPAWNER.try_with(|_| ()).is_ok()
+}
+
+/// Run `cmd` to completion with the current spawner.
+pub(crate) async fn output(cmd: &mut TokioCommand) -> io::Result<Output> {
+    current().output(cmd).await
+}
+
+/// Start `cmd` with the current spawner, stdin and stdout piped.
+pub(crate) fn spawn(cmd: &mut TokioCommand) -> io::Result<Process> {
+    current().spawn(cmd)
+}
+
+/// Answers an agent command with the responses to send back.
+pub type AgentHandler = Arc<dyn Fn(&Command) -> Vec<Response> + Send + Sync>;
+
+/// What a scripted process does.
+#[derive(Clone)]
+pub struct Reply {
+    code: i32,
+    stdout: String,
+    stderr: String,
+    delay: Duration,
+    /// Fail to start instead (e.g. `NotFound`, a missing binary)
+    spawn_error: Option<io::ErrorKind>,
+    agent: Option<AgentHandler>,
+}
+
+impl Reply {
+    /// Exit with `code`, printing nothing.
+    pub fn exit(code: i32) -> Self {
+        Self {
+            code,
+            stdout: String::new(),
+            stderr: String::new(),
+            delay: Duration::ZERO,
+            spawn_error: None,
+            agent: None,
+        }
+    }
+
+    /// Fail to start with `kind`, as spawning a missing program does.
+    pub fn spawn_error(kind: io::ErrorKind) -> Self {
+        Self {
+            spawn_error: Some(kind),
+            ..Self::exit(0)
+        }
+    }
+
+    /// Speak the agent protocol: each command line on stdin is answered with
+    /// `handler`'s responses (after the delay, if any).
+    pub fn agent(handler: impl Fn(&Command) -> Vec<Response> + Send + Sync + 'static) -> Self {
+        Self {
+            agent: Some(Arc::new(handler)),
+            ..Self::exit(0)
+        }
+    }
+
+    pub fn stdout(mut self, stdout: impl Into<String>) -> Self {
+        self.stdout = stdout.into();
+        self
+    }
+
+    pub fn stderr(mut self, stderr: impl Into<String>) -> Self {
+        self.stderr = stderr.into();
+        self
+    }
+
+    /// Take `delay` before exiting (or before each agent answer).
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    fn start_error(&self, cmd: &str) -> Option<io::Error> {
+        self.spawn_error
+            .map(|kind| io::Error::new(kind, format!("scripted spawn failure: {}", cmd)))
+    }
+}
+
+struct Rule {
+    program: String,
+    needle: String,
+    /// Replies in order; the last one repeats
+    replies: Vec<Reply>,
+    used: usize,
+}
+
+#[derive(Default)]
+struct MockState {
+    rules: Vec<Rule>,
+    calls: Vec<String>,
+}
+
+/// A [`Spawner`] with scripted replies, for tests.
+///
+/// A command is answered by the first rule whose program matches its file
+/// name and whose needle occurs in one of its arguments. Scripting the same
+/// program and needle again queues another reply: successive runs get the
+/// replies in order, and the last one repeats. Unmatched commands fail to
+/// start with `NotFound`, except `ssh -G` config lookups: those print
+/// nothing (no ProxyJump, no ControlPath) and are left out of [`calls`].
+///
+/// [`calls`]: MockSpawner::calls
+#[derive(Clone, Default)]
+pub struct MockSpawner {
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockSpawner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer `program` runs with an argument containing `needle` (`""`
+    /// matches any) with `reply`.
+    pub fn on(&self, program: &str, needle: &str, reply: Reply) -> &Self {
+        let mut state = self.lock();
+        match state
+            .rules
+            .iter_mut()
+            .find(|r| r.program == program && r.needle == needle)
+        {
+            Some(rule) => rule.replies.push(reply),
+            None => state.rules.push(Rule {
+                program: program.to_string(),
+                needle: needle.to_string(),
+                replies: vec![reply],
+                used: 0,
+            }),
+        }
+        self
+    }
+
+    /// Command lines run so far ("ssh -o ... -T web1 -- id -u"), oldest first.
+    pub fn calls(&self) -> Vec<String> {
+        self.lock().calls.clone()
+    }
+
+    /// This mock as the spawner for [`scope`].
+    pub fn shared(&self) -> Arc<dyn Spawner> {
+        Arc::new(self.clone())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Record `cmd` and pick its reply.
+    fn reply(&self, cmd: &TokioCommand) -> io::Result<Reply> {
+        let std = cmd.as_std();
+        let program = std::path::Path::new(std.get_program())
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let args: Vec<String> = std
+            .get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect();
+        let line = std::iter::once(program.clone())
+            .chain(args.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut state = self.lock();
+        let found = state
+            .rules
+            .iter()
+            .position(|r| r.program == program && args.iter().any(|a| a.contains(&r.needle)));
+        let Some(found) = found else {
+            if program == "ssh" && args.first().is_some_and(|a| a == "-G") {
+                return Ok(Reply::exit(0));
+            }
+            state.calls.push(line.clone());
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no scripted reply for {}", line),
+            ));
+        };
+        state.calls.push(line.clone());
+        let rule = &mut state.rules[found];
+        let reply = rule.replies[rule.used.min(rule.replies.len() - 1)].clone();
+        rule.used += 1;
+        match reply.start_error(&line) {
+            Some(e) => Err(e),
+            None => Ok(reply),
+        }
+    }
+}
+
+impl Spawner for MockSpawner {
+    fn output<'a>(&'a self, cmd: &'a mut TokioCommand) -> BoxFuture<'a, io::Result<Output>> {
+        let reply = self.reply(cmd);
+        Box::pin(async move {
+            let reply = reply?;
+            tokio::time::sleep(reply.delay).await;
+            Ok(Output {
+                status: ExitStatus::from_raw(reply.code << 8),
+                stdout: reply.stdout.into_bytes(),
+                stderr: reply.stderr.into_bytes(),
+            })
+        })
+    }
+
+    fn spawn(&self, cmd: &mut TokioCommand) -> io::Result<Process> {
+        let reply = self.reply(cmd)?;
+        let (stdin, agent_in) = tokio::io::duplex(64 * 1024);
+        let (mut agent_out, stdout) = tokio::io::duplex(64 * 1024);
+        tokio::spawn(async move {
+            if !reply.stdout.is_empty() {
+                let _ = agent_out.write_all(reply.stdout.as_bytes()).await;
+            }
+            let Some(handler) = reply.agent else {
+                tokio::time::sleep(reply.delay).await;
+                return;
+            };
+            let mut lines = BufReader::new(agent_in).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let Ok(request) = serde_json::from_str::<Request>(&line) else {
+                    continue;
+                };
+                tokio::time::sleep(reply.delay).await;
+                for response in handler(&request.command) {
+                    let reply = slarti_proto::Reply {
+                        response,
+                        trace: request.trace.clone(),
+                    };
+                    let Ok(json) = serde_json::to_string(&reply) else {
+                        continue;
+                    };
+                    if agent_out
+                        .write_all(format!("{}\n", json).as_bytes())
+                        .await
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+            }
+        });
+        Ok(Process {
+            stdin: Box::new(stdin),
+            stdout: Box::new(stdout),
+            child: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan::deploy_plan;
+    use crate::{
+        check_agent, clean_old_agents, deploy_agent, mark_agent_read_only, run_agent,
+        run_agent_with_flags, AuthRequired,
+    };
+    use slarti_proto::{Capability, EventData, Facet, SysInfo};
+
+    const TIMEOUT: Duration = Duration::from_secs(3);
+    const AGENT: &str = "/usr/local/lib/slarti/agent/0.1.0/slarti-remote";
+
+    fn agent(cmd: &Command) -> Vec<Response> {
+        match cmd {
+            Command::Hello { id, .. } => vec![Response::HelloAck {
+                id: *id,
+                agent_version: "0.1.0".to_string(),
+                capabilities: vec![Capability::SysInfo],
+            }],
+            Command::SysInfo { id } => vec![Response::SysInfoOk {
+                id: *id,
+                info: SysInfo {
+                    hostname: "web1".to_string(),
+                    ..Default::default()
+                },
+            }],
+            other => vec![Response::Error {
+                id: other.id(),
+                message: format!("unexpected {}", other.name()),
+            }],
+        }
+    }
+
+    #[tokio::test]
+    async fn check_agent_reports_missing_binary_then_version() {
+        let mock = MockSpawner::new();
+        mock.on(
+            "ssh",
+            "--version",
+            Reply::exit(127).stderr("sh: 1: slarti-remote: not found"),
+        )
+        .on("ssh", "--version", Reply::exit(0).stdout("0.1.0\n"));
+
+        let missing = scope(mock.shared(), check_agent("mock-check", AGENT, TIMEOUT))
+            .await
+            .unwrap();
+        assert!(!missing.present && !missing.can_run);
+        let present = scope(mock.shared(), check_agent("mock-check", AGENT, TIMEOUT))
+            .await
+            .unwrap();
+        assert!(present.present && present.can_run);
+        assert_eq!(present.version.as_deref(), Some("0.1.0"));
+        assert!(mock.calls()[0].ends_with(&format!("-T mock-check -- {} --version", AGENT)));
+    }
+
+    #[tokio::test]
+    async fn check_agent_auth_failure_is_not_a_missing_agent() {
+        let mock = MockSpawner::new();
+        mock.on(
+            "ssh",
+            "--version",
+            Reply::exit(255).stderr("root@mock-auth: Permission denied (publickey,password)."),
+        );
+        let err = scope(mock.shared(), check_agent("mock-auth", AGENT, TIMEOUT))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<AuthRequired>().unwrap().target,
+            "mock-auth"
+        );
+    }
+
+    #[tokio::test]
+    async fn run_agent_hello_then_request() {
+        let mock = MockSpawner::new();
+        mock.on("ssh", "--stdio", Reply::agent(agent));
+        let mut client = scope(mock.shared(), run_agent("mock-run", AGENT))
+            .await
+            .unwrap();
+        let hello = client.hello("0.1.0", Some(TIMEOUT)).await.unwrap();
+        assert_eq!(hello.agent_version, "0.1.0");
+        assert!(client.supports(&Capability::SysInfo));
+        match client.request(&Command::SysInfo { id: 2 }).await.unwrap() {
+            Response::SysInfoOk { id, info } => {
+                assert_eq!((id, info.hostname.as_str()), (2, "web1"))
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        client.terminate().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn failed_commands_answer_instead_of_hanging() {
+        let mock = MockSpawner::new();
+        mock.on(
+            "ssh",
+            "--stdio",
+            Reply::agent(|cmd| match cmd {
+                Command::ReadFile { id, .. } => vec![Response::Error {
+                    id: *id,
+                    message: "No such file or directory".to_string(),
+                }],
+                // How older agents answered any failed command.
+                Command::ServicesList { .. } => vec![Response::Error {
+                    id: 0,
+                    message: "systemctl not found".to_string(),
+                }],
+                other => agent(other),
+            }),
+        );
+        let mut client = scope(mock.shared(), run_agent("mock-fail", AGENT))
+            .await
+            .unwrap();
+        client.hello("0.1.0", Some(TIMEOUT)).await.unwrap();
+        let read = Command::ReadFile {
+            id: 7,
+            path: "/nonexistent".to_string(),
+            offset: None,
+            len: None,
+        };
+        match tokio::time::timeout(TIMEOUT, client.request(&read))
+            .await
+            .unwrap()
+            .unwrap()
+        {
+            Response::Error { id, message } => {
+                assert_eq!((id, message.as_str()), (7, "No such file or directory"))
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        let err = tokio::time::timeout(TIMEOUT, client.request(&Command::ServicesList { id: 8 }))
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(err.to_string(), "agent error: systemctl not found");
+        // The session carries on after both.
+        assert!(matches!(
+            client.request(&Command::SysInfo { id: 9 }).await.unwrap(),
+            Response::SysInfoOk { id: 9, .. }
+        ));
+        client.terminate().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn stream_and_subscription_frames_are_routed_by_id() {
+        let mock = MockSpawner::new();
+        mock.on(
+            "ssh",
+            "--stdio",
+            Reply::agent(|cmd| match cmd {
+                Command::Subscribe { id, .. } => vec![Response::Subscribed {
+                    id: *id,
+                    interval_ms: 1000,
+                }],
+                Command::JournalTail { id, .. } => vec![Response::JournalLines {
+                    id: *id,
+                    entries: Vec::new(),
+                    end: false,
+                }],
+                // Frames of the subscription and the stream arrive ahead of
+                // the answer, followed by a stale answer to the stream's id.
+                Command::SysInfo { id } => vec![
+                    Response::Error {
+                        id: 5,
+                        message: "sampling failed".to_string(),
+                    },
+                    Response::Event {
+                        id: 5,
+                        data: EventData::NetListeners {
+                            listeners: Vec::new(),
+                        },
+                    },
+                    Response::Error {
+                        id: 7,
+                        message: "journalctl exited".to_string(),
+                    },
+                    Response::Error {
+                        id: 7,
+                        message: "no journal stream 7".to_string(),
+                    },
+                    Response::SysInfoOk {
+                        id: *id,
+                        info: SysInfo::default(),
+                    },
+                ],
+                other => agent(other),
+            }),
+        );
+        let mut client = scope(mock.shared(), run_agent("mock-routes", AGENT))
+            .await
+            .unwrap();
+        let mut events = client.events().unwrap();
+        let mut streams = client.streams().unwrap();
+        client.hello("0.1.0", Some(TIMEOUT)).await.unwrap();
+        let subscribe = Command::Subscribe {
+            id: 5,
+            facet: Facet::NetListeners,
+            interval_ms: None,
+        };
+        assert!(matches!(
+            client.request(&subscribe).await.unwrap(),
+            Response::Subscribed { id: 5, .. }
+        ));
+        let tail = Command::JournalTail {
+            id: 7,
+            unit: None,
+            lines: None,
+            follow: true,
+        };
+        client.start_stream(&tail).await.unwrap();
+        match client.request(&Command::SysInfo { id: 9 }).await.unwrap() {
+            Response::SysInfoOk { id, .. } => assert_eq!(id, 9),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let (id, error) = events.recv().await.unwrap();
+        assert_eq!(
+            (id, error.unwrap_err().to_string()),
+            (5, "sampling failed".to_string())
+        );
+        assert!(matches!(events.recv().await.unwrap(), (5, Ok(_))));
+        assert!(matches!(
+            streams.recv().await.unwrap(),
+            Response::JournalLines {
+                id: 7,
+                end: false,
+                ..
+            }
+        ));
+        assert!(matches!(
+            streams.recv().await.unwrap(),
+            Response::Error { id: 7, .. }
+        ));
+        // The stream ended with its error, so stopping it sends nothing.
+        client
+            .stop_stream(&Command::JournalStop { id: 7 })
+            .await
+            .unwrap();
+        assert!(streams.try_recv().is_err());
+        client.terminate().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn run_agent_passes_quoted_flags() {
+        let mock = MockSpawner::new();
+        mock.on(
+            "ssh",
+            "--stdio --max-memory 256M --nice 'ten apples'",
+            Reply::agent(agent),
+        );
+        let flags = ["--max-memory", "256M", "--nice", "ten apples"].map(String::from);
+        let mut client = scope(
+            mock.shared(),
+            run_agent_with_flags("mock-flags", AGENT, &flags),
+        )
+        .await
+        .unwrap();
+        client.hello("0.1.0", Some(TIMEOUT)).await.unwrap();
+        client.terminate().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn slow_agent_times_out_hello() {
+        let mock = MockSpawner::new();
+        mock.on(
+            "ssh",
+            "--stdio",
+            Reply::agent(agent).delay(Duration::from_secs(5)),
+        );
+        let mut client = scope(mock.shared(), run_agent("mock-slow", AGENT))
+            .await
+            .unwrap();
+        let err = client
+            .hello("0.1.0", Some(Duration::from_millis(50)))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("hello timeout"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn deploy_agent_falls_back_to_scp() {
+        let mock = MockSpawner::new();
+        mock.on("ssh", "id -u", Reply::exit(0).stdout("1000\n"))
+            .on("ssh", "mkdir -p", Reply::exit(0))
+            .on("rsync", "", Reply::spawn_error(io::ErrorKind::NotFound))
+            .on("scp", "", Reply::exit(0))
+            .on("ssh", "chmod 755", Reply::exit(0));
+        let deployed = scope(
+            mock.shared(),
+            deploy_agent(
+                "mock-deploy",
+                std::path::Path::new("target/slarti-remote"),
+                "0.1.0",
+                None,
+                TIMEOUT,
+            ),
+        )
+        .await
+        .unwrap();
+        assert!(!deployed.used_rsync);
+        assert_eq!(
+            deployed.remote_path,
+            "$HOME/.local/share/slarti/agent/0.1.0/slarti-remote"
+        );
+        let programs: Vec<String> = mock
+            .calls()
+            .iter()
+            .map(|c| c.split(' ').next().unwrap_or_default().to_string())
+            .collect();
+        assert_eq!(programs, ["ssh", "ssh", "rsync", "scp", "ssh"]);
+    }
+
+    #[tokio::test]
+    async fn deploy_plan_lists_the_commands_deploy_runs() {
+        let mock = MockSpawner::new();
+        mock.on("ssh", "id -u", Reply::exit(0).stdout("1000\n"))
+            .on("ssh", "mkdir -p", Reply::exit(0))
+            .on("rsync", "", Reply::spawn_error(io::ErrorKind::NotFound))
+            .on("scp", "", Reply::exit(0))
+            .on("ssh", "chmod 755", Reply::exit(0));
+        let artifact = std::path::Path::new("target/slarti-remote");
+        let plan = scope(
+            mock.shared(),
+            deploy_plan("mock-plan", artifact, "0.1.0", None, TIMEOUT),
+        )
+        .await;
+        let probes = mock.calls().len();
+        scope(
+            mock.shared(),
+            deploy_agent("mock-plan", artifact, "0.1.0", None, TIMEOUT),
+        )
+        .await
+        .unwrap();
+        // Every command of the deploy but its uid probe is in the plan, as run
+        // (the plan quotes for the shell; the recorded calls do not).
+        let calls = mock.calls();
+        let run: Vec<&String> = calls[probes..]
+            .iter()
+            .filter(|c| !c.ends_with("id -u"))
+            .collect();
+        assert_eq!(run.len(), 4, "{:#?}", calls);
+        for call in run {
+            assert!(
+                plan.commands
+                    .iter()
+                    .any(|c| c.replace('\'', "").ends_with(call.as_str())),
+                "{} not in {:#?}",
+                call,
+                plan.commands
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn deploy_agent_to_configured_root() {
+        let mock = MockSpawner::new();
+        mock.on("ssh", "id -u", Reply::exit(0).stdout("0\n"))
+            .on("ssh", "mkdir -p", Reply::exit(0))
+            .on("rsync", "", Reply::exit(0));
+        let deployed = scope(
+            mock.shared(),
+            deploy_agent(
+                "mock-deploy",
+                std::path::Path::new("target/slarti-remote"),
+                "0.1.0",
+                Some("~/apps/slarti/"),
+                TIMEOUT,
+            ),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            deployed.remote_path,
+            "$HOME/apps/slarti/agent/0.1.0/slarti-remote"
+        );
+        let calls = mock.calls();
+        assert!(
+            calls[1].contains("mkdir -p $HOME/apps/slarti/agent/0.1.0"),
+            "{}",
+            calls[1]
+        );
+        // rsync gets the path relative to the home directory.
+        assert!(
+            calls[2].ends_with("mock-deploy:apps/slarti/agent/0.1.0"),
+            "{}",
+            calls[2]
+        );
+
+        let err = scope(
+            mock.shared(),
+            deploy_agent(
+                "mock-deploy",
+                std::path::Path::new("target/slarti-remote"),
+                "0.1.0",
+                Some("/opt/my agents"),
+                TIMEOUT,
+            ),
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            err.to_string().contains("invalid agent install root"),
+            "{}",
+            err
+        );
+        assert_eq!(mock.calls().len(), 3, "nothing runs for an invalid root");
+    }
+
+    #[tokio::test]
+    async fn clean_old_agents_keeps_newest_and_current() {
+        let mock = MockSpawner::new();
+        let listing = "0.4.0\n0.3.1\nnotes\n0.3.0\n0.1.0\n0.2.0\n";
+        mock.on("ssh", "id -u", Reply::exit(0).stdout("0\n"))
+            .on("ssh", "ls -1t", Reply::exit(0).stdout(listing))
+            .on("ssh", "rm -rf", Reply::exit(0));
+        let cleanup = scope(
+            mock.shared(),
+            clean_old_agents("mock-clean", "0.1.0", None, 3, true, TIMEOUT),
+        )
+        .await
+        .unwrap();
+        assert_eq!(cleanup.kept, ["0.4.0", "0.3.1", "0.1.0"]);
+        assert_eq!(cleanup.removed, ["0.3.0", "0.2.0"]);
+        assert_eq!(mock.calls().len(), 2, "a dry run removes nothing");
+
+        scope(
+            mock.shared(),
+            clean_old_agents("mock-clean", "0.1.0", None, 3, false, TIMEOUT),
+        )
+        .await
+        .unwrap();
+        let calls = mock.calls();
+        assert!(
+            calls[4].contains(
+                "rm -rf -- /usr/local/lib/slarti/agent/0.3.0 /usr/local/lib/slarti/agent/0.2.0"
+            ),
+            "{}",
+            calls[4]
+        );
+    }
+
+    #[tokio::test]
+    async fn mark_agent_read_only_touches_marker() {
+        let mock = MockSpawner::new();
+        mock.on("ssh", "id -u", Reply::exit(0).stdout("1000\n"))
+            .on("ssh", "touch", Reply::exit(0));
+        scope(
+            mock.shared(),
+            mark_agent_read_only("mock-ro", Some("/opt/slarti"), TIMEOUT),
+        )
+        .await
+        .unwrap();
+        let calls = mock.calls();
+        assert!(
+            calls[1]
+                .contains("mkdir -p -- /opt/slarti/agent && touch -- /opt/slarti/agent/read-only"),
+            "{}",
+            calls[1]
+        );
+    }
+
+    #[tokio::test]
+    async fn unscripted_command_fails_to_start() {
+        let mock = MockSpawner::new();
+        let err = scope(mock.shared(), check_agent("mock-none", AGENT, TIMEOUT))
+            .await
+            .unwrap_err();
+        assert!(
+            format!("{:#}", err).contains("no scripted reply"),
+            "{:#}",
+            err
+        );
+    }
+}
@@ -0,0 +1,171 @@
+//! scp/rsync command lines between this machine and a host.
+//!
+//! The destination is the host's alias: scp and rsync (through ssh) read
+//! ssh_config themselves, so ProxyCommand, HostKeyAlias, certificates and
+//! every other option apply as they do for `ssh <alias>`.
+
+use crate::shell_quote;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Tool {
+    #[default]
+    Scp,
+    Rsync,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Direction {
+    /// Local to host
+    #[default]
+    Upload,
+    /// Host to local
+    Download,
+}
+
+/// One copy between a local path and a path on the host.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Transfer {
+    pub tool: Tool,
+    pub direction: Direction,
+    pub local: String,
+    pub remote: String,
+    /// Copy directories (scp -r; rsync -a implies it)
+    pub recursive: bool,
+    /// Keep modification times and modes (scp -p; rsync -a)
+    pub preserve: bool,
+    pub compress: bool,
+    /// rsync only: show what would be copied
+    pub dry_run: bool,
+}
+
+impl Transfer {
+    /// The command line copying to or from the host `alias`.
+    pub fn command(&self, alias: &str) -> String {
+        let remote = shell_quote(&format!("{}:{}", alias, self.remote));
+        let local = local_path(&self.local);
+        let mut words = match self.tool {
+            Tool::Scp => {
+                let mut words = vec!["scp".to_string()];
+                for (on, flag) in [
+                    (self.recursive, "-r"),
+                    (self.preserve, "-p"),
+                    (self.compress, "-C"),
+                ] {
+                    if on {
+                        words.push(flag.to_string());
+                    }
+                }
+                words
+            }
+            Tool::Rsync => {
+                let mut flags = String::from("-");
+                if self.preserve {
+                    flags.push('a');
+                } else if self.recursive {
+                    flags.push('r');
+                }
+                if self.compress {
+                    flags.push('z');
+                }
+                flags.push('v');
+                let mut words = vec!["rsync".to_string(), flags, "--progress".to_string()];
+                if self.dry_run {
+                    words.push("--dry-run".to_string());
+                }
+                words
+            }
+        };
+        match self.direction {
+            Direction::Upload => words.extend([local, remote]),
+            Direction::Download => words.extend([remote, local]),
+        }
+        words.join(" ")
+    }
+}
+
+/// `path` quoted for the local shell, with a leading `~/` left outside the
+/// quotes so the shell still expands it.
+fn local_path(path: &str) -> String {
+    match path.strip_prefix("~/") {
+        Some("") => "~/".to_string(),
+        Some(rest) => format!("~/{}", shell_quote(rest)),
+        None if path == "~" => path.to_string(),
+        None => shell_quote(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(tool: Tool, direction: Direction, local: &str, remote: &str) -> Transfer {
+        Transfer {
+            tool,
+            direction,
+            local: local.to_string(),
+            remote: remote.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn scp_copies_to_the_alias() {
+        let t = transfer(Tool::Scp, Direction::Upload, "/tmp/app.conf", "/etc/app/");
+        assert_eq!(t.command("web1"), "scp /tmp/app.conf web1:/etc/app/");
+
+        let t = Transfer {
+            recursive: true,
+            preserve: true,
+            compress: true,
+            ..transfer(Tool::Scp, Direction::Download, "/srv/backup", "/var/log")
+        };
+        assert_eq!(
+            t.command("db.prod"),
+            "scp -r -p -C db.prod:/var/log /srv/backup"
+        );
+    }
+
+    #[test]
+    fn rsync_flags_follow_the_options() {
+        let t = Transfer {
+            recursive: true,
+            dry_run: true,
+            ..transfer(Tool::Rsync, Direction::Upload, "site/", "/var/www/")
+        };
+        assert_eq!(
+            t.command("web1"),
+            "rsync -rv --progress --dry-run site/ web1:/var/www/"
+        );
+
+        // -a implies -r.
+        let t = Transfer {
+            recursive: true,
+            preserve: true,
+            compress: true,
+            ..transfer(Tool::Rsync, Direction::Download, "logs", "/var/log/nginx")
+        };
+        assert_eq!(
+            t.command("web1"),
+            "rsync -azv --progress web1:/var/log/nginx logs"
+        );
+    }
+
+    #[test]
+    fn local_home_paths_still_expand() {
+        let t = transfer(Tool::Scp, Direction::Download, "~/My Notes/", "~/notes.txt");
+        assert_eq!(t.command("web1"), "scp 'web1:~/notes.txt' ~/'My Notes/'");
+        assert_eq!(local_path("~/"), "~/");
+        assert_eq!(local_path("~"), "~");
+        assert_eq!(local_path("~/src"), "~/src");
+        assert_eq!(local_path("~alice/src"), "'~alice/src'");
+    }
+
+    #[test]
+    fn paths_with_shell_characters_are_quoted() {
+        let t = transfer(Tool::Scp, Direction::Upload, "/tmp/it's here", "/srv/a b");
+        assert_eq!(
+            t.command("web1"),
+            "scp '/tmp/it'\\''s here' 'web1:/srv/a b'"
+        );
+    }
+}
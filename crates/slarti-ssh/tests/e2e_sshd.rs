@@ -0,0 +1,299 @@
+//! End-to-end check of the deploy pipeline against a throwaway sshd container.
+//!
+//! Builds slarti-remote, starts sshd in a docker container, deploys the agent
+//! with `deploy_agent`, then runs Hello, SysInfo and ListDir through
+//! `run_agent`, the way the app does. Needs docker, ssh, ssh-keygen and
+//! rsync on this machine:
+//!
+//! ```text
+//! cargo test -p slarti-ssh --features e2e --test e2e_sshd
+//! ```
+//!
+//! ssh reaches the container through a wrapper first on PATH that adds a
+//! generated config (`-F`), so the user's own ssh config and known_hosts are
+//! left alone.
+//!
+//! The agent is built for this machine, so its libc must not be newer than
+//! the container's (Debian stable); point `SLARTI_E2E_AGENT` at a static
+//! build (e.g. for x86_64-unknown-linux-musl) when it is.
+
+#![cfg(feature = "e2e")]
+
+use slarti_proto::{Capability, Command, Response};
+use slarti_ssh::{check_agent, deploy_agent, run_agent};
+use std::io::Read;
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+/// ssh config alias of the container.
+const TARGET: &str = "slarti-e2e";
+const IMAGE: &str = "slarti-e2e-sshd";
+/// sshd as root, with the key from `AUTHORIZED_KEY`.
+const DOCKERFILE: &str = r#"FROM debian:stable-slim
+RUN apt-get update \
+ && apt-get install -y --no-install-recommends openssh-server rsync \
+ && rm -rf /var/lib/apt/lists/* \
+ && mkdir -p /run/sshd /root/.ssh && chmod 700 /root/.ssh
+EXPOSE 22
+CMD ["sh", "-c", "printf '%s\n' \"$AUTHORIZED_KEY\" > /root/.ssh/authorized_keys && exec /usr/sbin/sshd -D -e"]
+"#;
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Stdout of `program`; panics with its stderr when it fails.
+fn run(program: &str, args: &[&str]) -> String {
+    let out = std::process::Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .unwrap_or_else(|e| panic!("failed to run {}: {}", program, e));
+    assert!(
+        out.status.success(),
+        "{} {:?} failed: {}",
+        program,
+        args,
+        String::from_utf8_lossy(&out.stderr).trim()
+    );
+    String::from_utf8_lossy(&out.stdout).into_owned()
+}
+
+/// `name` on PATH.
+fn find_in_path(name: &str) -> PathBuf {
+    std::env::var_os("PATH")
+        .iter()
+        .flat_map(std::env::split_paths)
+        .map(|dir| dir.join(name))
+        .find(|p| p.is_file())
+        .unwrap_or_else(|| panic!("{} not found on PATH", name))
+}
+
+/// `SLARTI_E2E_AGENT`, else a fresh debug build of slarti-remote.
+fn agent_binary() -> PathBuf {
+    if let Some(path) = std::env::var_os("SLARTI_E2E_AGENT") {
+        return PathBuf::from(path);
+    }
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let out = run(
+        &cargo,
+        &[
+            "build",
+            "-p",
+            "slarti-remote",
+            "--message-format=json-render-diagnostics",
+        ],
+    );
+    out.lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter(|msg| msg["target"]["name"] == "slarti-remote")
+        .find_map(|msg| msg["executable"].as_str().map(PathBuf::from))
+        .expect("cargo build did not report the slarti-remote executable")
+}
+
+/// A running sshd container and the scratch dir holding its key and ssh
+/// config; both are removed on drop.
+struct Sshd {
+    dir: PathBuf,
+    container: String,
+}
+
+impl Sshd {
+    fn start() -> Self {
+        for tool in ["docker", "ssh", "ssh-keygen", "rsync"] {
+            find_in_path(tool);
+        }
+        let dir = std::env::temp_dir().join(format!("slarti-e2e-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("bin")).expect("create scratch dir");
+        let key = dir.join("id_ed25519");
+        run(
+            "ssh-keygen",
+            &[
+                "-q",
+                "-t",
+                "ed25519",
+                "-N",
+                "",
+                "-f",
+                &key.to_string_lossy(),
+            ],
+        );
+        let public = std::fs::read_to_string(dir.join("id_ed25519.pub")).expect("public key");
+
+        build_image();
+        let container = run(
+            "docker",
+            &[
+                "run",
+                "-d",
+                "--rm",
+                "-p",
+                "127.0.0.1::22",
+                "-e",
+                &format!("AUTHORIZED_KEY={}", public.trim()),
+                IMAGE,
+            ],
+        )
+        .trim()
+        .to_string();
+        let sshd = Self { dir, container };
+
+        let mapped = run("docker", &["port", &sshd.container, "22/tcp"]);
+        let port: u16 = mapped
+            .lines()
+            .next()
+            .and_then(|l| l.rsplit_once(':'))
+            .and_then(|(_, port)| port.trim().parse().ok())
+            .unwrap_or_else(|| panic!("unexpected docker port output: {}", mapped));
+        wait_for_banner(port);
+        sshd.route_ssh(port, &key);
+        sshd
+    }
+
+    /// Put an `ssh` first on PATH that adds a config for `TARGET`; rsync and
+    /// slarti-ssh's own ssh runs find it there.
+    fn route_ssh(&self, port: u16, key: &Path) {
+        let config = self.dir.join("ssh_config");
+        std::fs::write(
+            &config,
+            format!(
+                "Host {TARGET}\n  HostName 127.0.0.1\n  Port {port}\n  User root\n  IdentityFile {}\n  IdentitiesOnly yes\n  UserKnownHostsFile {}\n  StrictHostKeyChecking no\n  LogLevel ERROR\n",
+                key.display(),
+                self.dir.join("known_hosts").display(),
+            ),
+        )
+        .expect("write ssh config");
+        let wrapper = self.dir.join("bin/ssh");
+        std::fs::write(
+            &wrapper,
+            format!(
+                "#!/bin/sh\nexec '{}' -F '{}' \"$@\"\n",
+                find_in_path("ssh").display(),
+                config.display()
+            ),
+        )
+        .expect("write ssh wrapper");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&wrapper, std::fs::Permissions::from_mode(0o755))
+                .expect("chmod ssh wrapper");
+        }
+        let path = std::env::var_os("PATH").unwrap_or_default();
+        let paths = std::iter::once(self.dir.join("bin")).chain(std::env::split_paths(&path));
+        std::env::set_var("PATH", std::env::join_paths(paths).expect("join PATH"));
+    }
+}
+
+impl Drop for Sshd {
+    fn drop(&mut self) {
+        let _ = std::process::Command::new("docker")
+            .args(["rm", "-f", &self.container])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Build `IMAGE` from `DOCKERFILE` (cached by docker after the first run).
+fn build_image() {
+    let mut child = std::process::Command::new("docker")
+        .args(["build", "-q", "-t", IMAGE, "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .expect("failed to run docker build");
+    {
+        use std::io::Write;
+        let mut stdin = child.stdin.take().expect("docker build stdin");
+        stdin
+            .write_all(DOCKERFILE.as_bytes())
+            .expect("write Dockerfile");
+    }
+    assert!(
+        child.wait().expect("docker build").success(),
+        "docker build failed"
+    );
+}
+
+/// Wait until sshd answers on `port` (docker accepts connections before
+/// sshd listens, so wait for its banner).
+fn wait_for_banner(port: u16) {
+    let started = Instant::now();
+    while started.elapsed() < TIMEOUT {
+        if let Ok(mut stream) = TcpStream::connect(("127.0.0.1", port)) {
+            let _ = stream.set_read_timeout(Some(Duration::from_secs(1)));
+            let mut banner = [0u8; 4];
+            if stream.read_exact(&mut banner).is_ok() && &banner == b"SSH-" {
+                return;
+            }
+        }
+        std::thread::sleep(Duration::from_millis(200));
+    }
+    panic!("sshd did not come up on port {}", port);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn deploy_then_hello_sys_info_list_dir() {
+    let agent = agent_binary();
+    let sshd = Sshd::start();
+    let version = env!("CARGO_PKG_VERSION");
+
+    let deployed = deploy_agent(TARGET, &agent, version, None, TIMEOUT)
+        .await
+        .expect("deploy_agent");
+    assert_eq!(
+        deployed.remote_path,
+        format!("/usr/local/lib/slarti/agent/{}/slarti-remote", version)
+    );
+
+    let status = check_agent(TARGET, &deployed.remote_path, TIMEOUT)
+        .await
+        .expect("check_agent");
+    assert!(status.present && status.can_run, "{:?}", status);
+
+    let mut client = run_agent(TARGET, &deployed.remote_path)
+        .await
+        .expect("run_agent");
+    let hello = client.hello(version, Some(TIMEOUT)).await.expect("hello");
+    assert_eq!(Some(&hello.agent_version), status.version.as_ref());
+    assert!(hello.capabilities.contains(&Capability::SysInfo));
+
+    match client.request(&Command::SysInfo { id: 1 }).await {
+        Ok(Response::SysInfoOk { id, info }) => {
+            assert_eq!(id, 1);
+            // docker names the container's host after its id
+            assert!(
+                sshd.container.starts_with(&info.hostname),
+                "hostname {:?} is not the container's",
+                info.hostname
+            );
+        }
+        other => panic!("unexpected SysInfo answer: {:?}", other),
+    }
+
+    match client
+        .request(&Command::ListDir {
+            id: 2,
+            path: "/etc/ssh".to_string(),
+            max: None,
+            skip: None,
+        })
+        .await
+    {
+        Ok(Response::ListDirOk { id, entries, eof }) => {
+            assert_eq!(id, 2);
+            assert!(eof);
+            let config = entries
+                .iter()
+                .find(|e| e.name == "sshd_config")
+                .expect("sshd_config listed");
+            assert_eq!(config.path, "/etc/ssh/sshd_config");
+            assert!(!config.is_dir);
+            assert!(config.size.is_some_and(|s| s > 0));
+        }
+        other => panic!("unexpected ListDir answer: {:?}", other),
+    }
+
+    client.terminate().await.expect("terminate");
+}
@@ -0,0 +1,180 @@
+//! The ssh side of deploying an agent and then selecting its host, against
+//! scripted processes: check the agent, deploy it when missing, check again,
+//! connect, then [`AgentClient::initial_load`], the first batch the app sends
+//! after a host is selected. No network and no ssh config needed.
+
+use slarti_proto::{
+    Capability, Command, MountInfo, Response, ServiceInfo, ServicesDelta, StaticConfig, SysInfo,
+};
+use slarti_ssh::spawn::{scope, MockSpawner, Reply};
+use slarti_ssh::{check_agent, deploy_agent, run_agent_with_flags, AgentClient};
+use std::path::Path;
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(3);
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+fn nginx() -> ServiceInfo {
+    ServiceInfo {
+        name: "nginx.service".to_string(),
+        active_state: "active".to_string(),
+        sub_state: "running".to_string(),
+        ..Default::default()
+    }
+}
+
+/// One answer per command; a `Batch` gets its commands' answers in a
+/// `BatchOk`. `newer` says whether the agent advertises `ServicesDelta` and
+/// `DiskUsage`.
+fn agent(newer: bool) -> impl Fn(&Command) -> Vec<Response> + Send + Sync + 'static {
+    fn answer(cmd: &Command, newer: bool) -> Response {
+        match cmd {
+            Command::Hello { id, .. } => {
+                let mut capabilities = vec![
+                    Capability::SysInfo,
+                    Capability::StaticConfig,
+                    Capability::ServicesList,
+                    Capability::Batch,
+                ];
+                if newer {
+                    capabilities.push(Capability::ServicesDelta);
+                    capabilities.push(Capability::DiskUsage);
+                }
+                Response::HelloAck {
+                    id: *id,
+                    agent_version: VERSION.to_string(),
+                    capabilities,
+                }
+            }
+            Command::Batch { id, commands } => Response::BatchOk {
+                id: *id,
+                responses: commands.iter().map(|c| answer(c, newer)).collect(),
+            },
+            Command::SysInfo { id } => Response::SysInfoOk {
+                id: *id,
+                info: SysInfo {
+                    hostname: "web1".to_string(),
+                    ..Default::default()
+                },
+            },
+            Command::StaticConfig { id } => Response::StaticConfigOk {
+                id: *id,
+                config: StaticConfig::default(),
+            },
+            Command::ServicesList { id } => Response::ServicesListOk {
+                id: *id,
+                services: vec![nginx()],
+            },
+            Command::ServicesDelta { id, since: None } => Response::ServicesDeltaOk {
+                id: *id,
+                delta: ServicesDelta {
+                    token: 7,
+                    full: true,
+                    changed: vec![nginx()],
+                    removed: Vec::new(),
+                },
+            },
+            Command::DiskUsage { id } => Response::DiskUsageOk {
+                id: *id,
+                mounts: vec![MountInfo {
+                    device: "/dev/sda1".to_string(),
+                    mountpoint: "/".to_string(),
+                    fstype: "ext4".to_string(),
+                    ..Default::default()
+                }],
+            },
+            other => Response::Error {
+                id: 0,
+                message: format!("unexpected {}", other.name()),
+            },
+        }
+    }
+    move |cmd| vec![answer(cmd, newer)]
+}
+
+async fn connect(remote_path: &str) -> AgentClient {
+    let mut client = run_agent_with_flags("web1", remote_path, &[])
+        .await
+        .unwrap();
+    let hello = client.hello(VERSION, Some(TIMEOUT)).await.unwrap();
+    assert_eq!(hello.agent_version, VERSION);
+    client
+}
+
+#[tokio::test]
+async fn deploy_then_select_loads_the_host() {
+    let mock = MockSpawner::new();
+    mock.on("ssh", "id -u", Reply::exit(0).stdout("0\n"))
+        .on("ssh", "mkdir -p", Reply::exit(0))
+        .on("rsync", "", Reply::exit(0).delay(Duration::from_millis(20)))
+        .on(
+            "ssh",
+            "--version",
+            Reply::exit(127).stderr("sh: 1: slarti-remote: not found"),
+        )
+        .on(
+            "ssh",
+            "--version",
+            Reply::exit(0).stdout(format!("{VERSION}\n")),
+        )
+        .on("ssh", "--stdio", Reply::agent(agent(false)));
+    let remote_path = format!("/usr/local/lib/slarti/agent/{VERSION}/slarti-remote");
+
+    let responses = scope(mock.shared(), async {
+        let status = check_agent("web1", &remote_path, TIMEOUT).await.unwrap();
+        assert!(!status.present, "agent should be reported missing");
+
+        let deployed = deploy_agent("web1", Path::new("slarti-remote"), VERSION, None, TIMEOUT)
+            .await
+            .unwrap();
+        assert!(deployed.used_rsync);
+        assert_eq!(deployed.remote_path, remote_path);
+
+        let status = check_agent("web1", &remote_path, TIMEOUT).await.unwrap();
+        assert_eq!(status.version.as_deref(), Some(VERSION));
+
+        let mut client = connect(&remote_path).await;
+        let responses = client.initial_load().await.unwrap();
+        client.terminate().await.unwrap();
+        responses
+    })
+    .await;
+
+    match responses.as_slice() {
+        [Response::SysInfoOk { info, .. }, Response::StaticConfigOk { .. }, Response::ServicesListOk { services, .. }] =>
+        {
+            assert_eq!(info.hostname, "web1");
+            assert_eq!(services[0].name, "nginx.service");
+        }
+        other => panic!("unexpected initial load: {:?}", other),
+    }
+    let calls = mock.calls();
+    assert!(calls.iter().any(|c| c.starts_with("rsync ")));
+    assert!(!calls.iter().any(|c| c.starts_with("scp ")));
+    assert!(!calls.iter().any(|c| c.contains(" -G ")));
+}
+
+#[tokio::test]
+async fn initial_load_asks_for_a_services_delta_and_disk_usage_when_supported() {
+    let mock = MockSpawner::new();
+    mock.on("ssh", "--stdio", Reply::agent(agent(true)));
+    let remote_path = format!("/usr/local/lib/slarti/agent/{VERSION}/slarti-remote");
+
+    let responses = scope(mock.shared(), async {
+        let mut client = connect(&remote_path).await;
+        let responses = client.initial_load().await.unwrap();
+        client.terminate().await.unwrap();
+        responses
+    })
+    .await;
+
+    match responses.as_slice() {
+        [Response::SysInfoOk { .. }, Response::StaticConfigOk { .. }, Response::ServicesDeltaOk { delta, .. }, Response::DiskUsageOk { mounts, .. }] =>
+        {
+            assert!(delta.full);
+            assert_eq!(delta.token, 7);
+            assert_eq!(mounts[0].mountpoint, "/");
+        }
+        other => panic!("unexpected initial load: {:?}", other),
+    }
+}
@@ -0,0 +1,24 @@
+//! Arbitrary text as an ssh config: parsing, then the queries the app runs
+//! on the tree, must not panic.
+//!
+//! ```text
+//! cd crates/slarti-sshcfg && cargo +nightly fuzz run parse_config
+//! ```
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use slarti_sshcfg::load;
+use std::path::Path;
+
+fuzz_target!(|text: &str| {
+    // Relative Includes resolve under a directory that does not exist.
+    let Ok(tree) = load::parse_config(text, Path::new("/nonexistent/slarti-fuzz/config")) else {
+        return;
+    };
+    for alias in load::list_aliases(&tree) {
+        let _ = load::host_entry_for_alias(&tree, &alias);
+        let _ = load::effective_user_for_alias(&tree, &alias);
+    }
+    let _ = load::duplicate_aliases(&tree);
+});
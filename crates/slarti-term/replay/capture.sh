@@ -0,0 +1,16 @@
+#!/bin/sh
+# Record one replay stream: capture.sh <out.bytes> <command...>
+#
+# Runs the command under script(1) at 80x24 with TERM=xterm-256color and
+# strips the "Script started/done" lines. Interactive programs (vim, htop)
+# are killed after a few seconds so the alternate screen is still up, e.g.
+#   ./capture.sh htop.bytes timeout -s KILL 3 htop -d 10 -p "$(pgrep -o nginx)"
+set -eu
+
+out=$1
+shift
+raw=$(mktemp)
+trap 'rm -f "$raw"' EXIT
+
+TERM=xterm-256color script -q -c "stty rows 24 cols 80; $*" "$raw" </dev/null >/dev/null || true
+sed -e '1{/^Script started/d}' -e '${/^Script done/d}' "$raw" >"$out"
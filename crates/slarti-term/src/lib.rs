@@ -2,6 +2,7 @@ use std::{
     io::{Read, Write},
     sync::{Arc, Mutex},
     thread,
+    time::Duration,
 };
 
 use anyhow::Result;
@@ -9,14 +10,15 @@ use gpui::{
     div, prelude::*, px, relative, App, Bounds, Context, Element, ElementId, FocusHandle,
     Focusable, GlobalElementId, LayoutId, Pixels, SharedString, Style, TextRun, Window,
 };
-use portable_pty::{native_pty_system, CommandBuilder, MasterPty, PtySize};
+use portable_pty::{native_pty_system, Child, CommandBuilder, MasterPty, PtySize};
+use slarti_ui::Appearance;
 
 use alacritty_terminal::{
     event::VoidListener,
     grid::Dimensions,
     index::{Column, Line},
     term::{Config, Term},
-    vte::ansi::Processor,
+    vte::ansi::{Color, Processor},
 };
 
 fn rgb_to_hsl(r: u8, g: u8, b: u8) -> (f32, f32, f32) {
@@ -116,10 +118,32 @@ pub struct Engine {
     term: Term<VoidListener>,
     processor: Option<Processor>,
     rx_buf: Arc<Mutex<Vec<u8>>>,
-    master: Arc<Mutex<Box<dyn MasterPty + Send>>>,
+    // None for a detached engine (no PTY)
+    master: Option<Arc<Mutex<Box<dyn MasterPty + Send>>>>,
+    // The shell running in the PTY
+    child: Option<Box<dyn Child + Send + Sync>>,
 }
 
 impl Engine {
+    /// Create an engine without a shell or PTY, fed only through
+    /// [`Engine::process_bytes`] (e.g. to replay recorded output).
+    pub fn detached(cols: usize, rows: usize) -> Self {
+        Self {
+            term: Term::new(
+                Config::default(),
+                &TermSize {
+                    columns: cols,
+                    screen_lines: rows,
+                },
+                VoidListener,
+            ),
+            processor: Some(Processor::new()),
+            rx_buf: Arc::new(Mutex::new(Vec::new())),
+            master: None,
+            child: None,
+        }
+    }
+
     /// Create a new engine with an initial (cols, rows) size. Spawns the user's shell in a PTY and
     /// a background reader thread to accumulate PTY bytes into `rx_buf`.
     pub fn new(
@@ -154,7 +178,7 @@ impl Engine {
             CommandBuilder::new(std::env::var("SHELL").unwrap_or_else(|_| "bash".to_string()))
         };
         let _ = cmd.cwd(std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from(".")));
-        let _child = pair.slave.spawn_command(cmd)?;
+        let child = pair.slave.spawn_command(cmd)?;
         drop(pair.slave);
 
         // Hold master for resize and I/O
@@ -191,7 +215,8 @@ impl Engine {
                 term,
                 processor,
                 rx_buf,
-                master,
+                master: Some(master),
+                child: Some(child),
             },
             writer,
         ))
@@ -217,13 +242,28 @@ impl Engine {
         self.processor.replace(processor);
     }
 
+    /// Hang up the shell (SIGHUP, then SIGKILL if it lingers) and reap it.
+    pub fn terminate(&mut self) {
+        let Some(child) = self.child.as_mut() else {
+            return;
+        };
+        if let Ok(None) = child.try_wait() {
+            if child.kill().is_ok() {
+                let _ = child.wait();
+            }
+        }
+    }
+
     /// Resize both the terminal and the PTY.
     pub fn resize(&mut self, cols: usize, rows: usize) {
         self.term.resize(TermSize {
             columns: cols,
             screen_lines: rows,
         });
-        let _ = self.master.lock().ok().map(|m| {
+        let Some(master) = &self.master else {
+            return;
+        };
+        let _ = master.lock().ok().map(|m| {
             let _ = m.resize(PtySize {
                 rows: rows as u16,
                 cols: cols as u16,
@@ -234,6 +274,9 @@ impl Engine {
     }
 }
 
+/// How often to check the PTY for new output.
+const OUTPUT_POLL_INTERVAL: Duration = Duration::from_millis(16);
+
 /// A collapsible panel hosting a terminal canvas.
 pub struct TerminalView {
     focus: FocusHandle,
@@ -249,6 +292,22 @@ impl TerminalView {
     pub fn new(cx: &mut Context<Self>, config: TerminalConfig) -> Self {
         let (engine, writer) = Engine::new(80, 24).expect("create terminal engine");
 
+        // Repaint only when the PTY produced output (not every frame).
+        let rx_buf = engine.rx_buf.clone();
+        cx.spawn(async move |this, cx| loop {
+            cx.background_executor().timer(OUTPUT_POLL_INTERVAL).await;
+            let pending = rx_buf.lock().map(|b| !b.is_empty()).unwrap_or(false);
+            let alive = this.update(cx, |_, cx| {
+                if pending {
+                    cx.notify();
+                }
+            });
+            if alive.is_err() {
+                break;
+            }
+        })
+        .detach();
+
         Self {
             focus: cx.focus_handle(),
             title: config.title,
@@ -266,6 +325,24 @@ impl TerminalView {
         }
     }
 
+    /// Terminate the shell; used when the app shuts down.
+    pub fn shutdown(&self) {
+        if let Ok(mut engine) = self.engine.lock() {
+            engine.terminate();
+        }
+    }
+
+    /// Type a command line into the shell and press Enter.
+    ///
+    /// Any partially typed input is cleared first (Ctrl-U) so the command runs as given.
+    pub fn run_command(&self, command: &str) {
+        let mut bytes = Vec::with_capacity(command.len() + 2);
+        bytes.push(0x15);
+        bytes.extend_from_slice(command.as_bytes());
+        bytes.push(b'\r');
+        self.write_bytes(&bytes);
+    }
+
     /// Drain any pending PTY bytes and advance the terminal processor.
     /// Locks are explicitly scoped to avoid overlapping borrows:
     /// 1) Clone rx_buf under a short engine lock.
@@ -312,24 +389,19 @@ impl Focusable for TerminalView {
 
 impl gpui::Render for TerminalView {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        // Drain pending PTY bytes quickly; if progress was made, keep animating until idle.
-        let mut progressed = false;
+        // Drain pending PTY bytes; anything arriving later is picked up by the output poller.
         for _ in 0..8 {
-            if self.drain_and_advance() {
-                progressed = true;
-            } else {
+            if !self.drain_and_advance() {
                 break;
             }
         }
-        if progressed {
-            // Ensure another frame is produced promptly while data continues to arrive.
-            window.request_animation_frame();
-            cx.notify();
-        }
 
         let theme = self.theme;
         let bg = gpui::hsla(theme.bg.0, theme.bg.1, theme.bg.2, theme.bg.3);
         let fg = gpui::hsla(theme.fg.0, theme.fg.1, theme.fg.2, theme.fg.3);
+        // Chrome follows the app-wide appearance; the grid keeps the terminal theme.
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
 
         // Header
         let header = div()
@@ -337,16 +409,16 @@ impl gpui::Render for TerminalView {
             .flex_row()
             .items_center()
             .justify_between()
-            .h(px(28.))
-            .px(px(8.))
+            .h(ap.px(28.))
+            .px(ap.px(8.))
             .bg(bg)
             .child(
                 div()
-                    .w(px(28.))
-                    .h(px(18.))
+                    .w(ap.px(28.))
+                    .h(ap.px(18.))
                     .rounded_sm()
                     .border_1()
-                    .border_color(gpui::opaque_grey(0.2, 0.7))
+                    .border_color(pal.border)
                     .cursor_default()
                     .child("≡"),
             )
@@ -383,15 +455,42 @@ impl gpui::Render for TerminalView {
         div()
             .key_context("TerminalView")
             .track_focus(&self.focus_handle(cx))
+            .on_mouse_down(
+                gpui::MouseButton::Left,
+                cx.listener(|this: &mut Self, _ev, window, _cx| window.focus(&this.focus)),
+            )
             .flex()
             .flex_col()
             .size_full()
             .bg(bg)
+            .border_1()
+            .border_color(if self.focus.is_focused(window) {
+                pal.focus_ring
+            } else {
+                gpui::transparent_black()
+            })
             .child(header)
             .child(content)
     }
 }
 
+/// Text of screen line `y` and its foreground runs as (byte length, color),
+/// adjacent cells with the same color merged.
+fn line_runs(term: &Term<VoidListener>, y: usize) -> (String, Vec<(usize, Color)>) {
+    let cols = term.columns();
+    let mut text = String::with_capacity(cols);
+    let mut runs: Vec<(usize, Color)> = Vec::new();
+    for x in 0..cols {
+        let cell = &term.grid()[Line(y as i32)][Column(x)];
+        text.push(cell.c);
+        match runs.last_mut() {
+            Some((len, color)) if *color == cell.fg => *len += cell.c.len_utf8(),
+            _ => runs.push((cell.c.len_utf8(), cell.fg)),
+        }
+    }
+    (text, runs)
+}
+
 /// A simple canvas element that renders the terminal grid as text and draws a cursor.
 struct TerminalCanvasElement {
     engine: Arc<Mutex<Engine>>,
@@ -545,10 +644,9 @@ impl Element for TerminalCanvasElement {
         };
 
         // Lock engine once to compute damage and palette
-        let (rows_to_shape, palette, rows_count, cols_count, cursor_point) =
+        let (rows_to_shape, palette, rows_count, cursor_point) =
             if let Ok(mut eng) = self.engine.lock() {
                 let rows_count = eng.term.screen_lines();
-                let cols_count = eng.term.columns();
 
                 // Build damage map
                 let mut damage = vec![false; rows_count];
@@ -581,7 +679,7 @@ impl Element for TerminalCanvasElement {
                 // Reset damage now that we've captured it
                 eng.term.reset_damage();
 
-                (damage, pal, rows_count, cols_count, cur)
+                (damage, pal, rows_count, cur)
             } else {
                 return;
             };
@@ -589,62 +687,31 @@ impl Element for TerminalCanvasElement {
         for y in 0..rows_count {
             // Build text and color runs if damaged, otherwise paint from cache
             if rows_to_shape.get(y).copied().unwrap_or(false) {
-                // Collect line text and simple foreground color per cell (merge runs)
-                let mut line_text = String::with_capacity(cols_count);
-                let mut runs: Vec<TextRun> = Vec::new();
-                let mut run_len = 0usize;
-                let mut run_color = fg;
-
-                if let Ok(eng) = self.engine.lock() {
-                    for x in 0..cols_count {
-                        let cell = &eng.term.grid()[Line(y as i32)][Column(x)];
-                        let ch = cell.c;
-                        line_text.push(ch);
-
-                        // Resolve fg color: prefer Spec/Named/Indexed mapping, fallback to theme fg
-                        let fg_resolved = match cell.fg {
-                            alacritty_terminal::vte::ansi::Color::Spec(rgb) => to_color(Some(rgb)),
-                            alacritty_terminal::vte::ansi::Color::Named(named) => {
-                                to_color(palette[named])
-                            }
-                            alacritty_terminal::vte::ansi::Color::Indexed(i) => {
-                                to_color(palette[i as usize])
-                            }
-                        };
-
-                        // Merge color runs
-                        if run_len == 0 {
-                            run_color = fg_resolved;
-                            run_len = ch.len_utf8();
-                        } else if fg_resolved == run_color {
-                            run_len += ch.len_utf8();
-                        } else {
-                            runs.push(TextRun {
-                                len: run_len,
-                                font: window.text_style().font(),
-                                color: run_color,
-                                background_color: None,
-                                underline: None,
-                                strikethrough: None,
-                            });
-                            run_color = fg_resolved;
-                            run_len = ch.len_utf8();
-                        }
+                // Line text and foreground runs, merged again once resolved to colors
+                let (line_text, spans) = match self.engine.lock() {
+                    Ok(eng) => line_runs(&eng.term, y),
+                    Err(_) => (String::new(), Vec::new()),
+                };
+                let mut runs: Vec<TextRun> = Vec::with_capacity(spans.len());
+                for (len, color) in spans {
+                    let color = match color {
+                        Color::Spec(rgb) => to_color(Some(rgb)),
+                        Color::Named(named) => to_color(palette[named]),
+                        Color::Indexed(i) => to_color(palette[i as usize]),
+                    };
+                    match runs.last_mut() {
+                        Some(last) if last.color == color => last.len += len,
+                        _ => runs.push(TextRun {
+                            len,
+                            font: window.text_style().font(),
+                            color,
+                            background_color: None,
+                            underline: None,
+                            strikethrough: None,
+                        }),
                     }
                 }
 
-                // Flush last run
-                if run_len > 0 {
-                    runs.push(TextRun {
-                        len: run_len,
-                        font: window.text_style().font(),
-                        color: run_color,
-                        background_color: None,
-                        underline: None,
-                        strikethrough: None,
-                    });
-                }
-
                 // Shape the line with color runs
                 let shaped = window.text_system().shape_line(
                     SharedString::from(line_text),
@@ -652,6 +719,7 @@ impl Element for TerminalCanvasElement {
                     &runs,
                     None,
                 );
+                slarti_ui::diagnostics::add_shaped_lines(1);
 
                 // Update cursor position if on this row
                 if y == cursor_point.line.0.max(0) as usize {
@@ -716,3 +784,6 @@ impl Element for TerminalCanvasElement {
         ));
     }
 }
+
+#[cfg(test)]
+mod replay_tests;
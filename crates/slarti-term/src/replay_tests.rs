@@ -0,0 +1,296 @@
+//! Recorded terminal output replayed through [`Engine`] and [`line_runs`],
+//! checking the grid and the color runs the renderer paints from.
+//!
+//! The streams in `replay/` were captured at 80x24 with `TERM=xterm-256color`,
+//! e.g. `script -q -c "stty rows 24 cols 80; ls --color=always" ls.raw`, minus
+//! the "Script started/done" lines `script` adds:
+//! - `ls_color.bytes`: `ls --color=always`, then `ls -l` (dirs, a symlink, an executable)
+//! - `vim_session.bytes`: `vim -u NONE -c 'syntax on'` editing a shell script,
+//!   killed mid-session so the alternate screen is still up
+//! - `top.bytes`: `top -d 1` for one process over two refreshes, killed likewise
+//! - `htop.bytes`: `htop` for one process, killed likewise (not committed yet;
+//!   record it with `replay/capture.sh`, which runs the command as above)
+
+use alacritty_terminal::term::cell::Flags;
+use alacritty_terminal::vte::ansi::NamedColor;
+
+use super::*;
+
+const LS: &[u8] = include_bytes!("../replay/ls_color.bytes");
+const VIM: &[u8] = include_bytes!("../replay/vim_session.bytes");
+const TOP: &[u8] = include_bytes!("../replay/top.bytes");
+
+const FG: Color = Color::Named(NamedColor::Foreground);
+
+fn replay(bytes: &[u8]) -> Engine {
+    let mut engine = Engine::detached(80, 24);
+    engine.process_bytes(bytes);
+    engine
+}
+
+/// Screen lines with trailing blanks trimmed.
+fn screen(engine: &Engine) -> Vec<String> {
+    (0..engine.term.screen_lines())
+        .map(|y| line_runs(&engine.term, y).0.trim_end().to_string())
+        .collect()
+}
+
+/// Line `y` split into its runs' text and color.
+fn runs(engine: &Engine, y: usize) -> Vec<(String, Color)> {
+    let (text, spans) = line_runs(&engine.term, y);
+    let mut at = 0;
+    spans
+        .into_iter()
+        .map(|(len, color)| {
+            let run = text[at..at + len].to_string();
+            at += len;
+            (run, color)
+        })
+        .collect()
+}
+
+fn run(text: &str, color: Color) -> (String, Color) {
+    (text.to_string(), color)
+}
+
+fn flags(engine: &Engine, y: i32, x: usize) -> Flags {
+    engine.term.grid()[Line(y)][Column(x)].flags
+}
+
+fn cursor(engine: &Engine) -> (i32, usize) {
+    let point = engine.term.grid().cursor.point;
+    (point.line.0, point.column.0)
+}
+
+#[test]
+fn ls_color_grid_and_runs() {
+    let engine = replay(LS);
+    let screen = screen(&engine);
+    assert_eq!(
+        &screen[..9],
+        [
+            "Cargo.toml  archive.tar.gz  bin  link  notes.txt  run.sh  src",
+            "-rw-r--r-- 1 root root    0  Cargo.toml",
+            "-rw-r--r-- 1 root root    0  archive.tar.gz",
+            "drwxr-xr-x 2 root root 4096  bin",
+            "lrwxrwxrwx 1 root root    6  link -> run.sh",
+            "-rw-r--r-- 1 root root    0  notes.txt",
+            "-rwxr-xr-x 1 root root   10  run.sh",
+            "drwxr-xr-x 2 root root 4096  src",
+            "",
+        ]
+    );
+    assert!(screen[9..].iter().all(|l| l.is_empty()));
+    assert_eq!(cursor(&engine), (8, 0));
+
+    let line = runs(&engine, 0);
+    assert_eq!(
+        &line[..8],
+        [
+            run("Cargo.toml  archive.tar.gz  ", FG),
+            run("bin", Color::Named(NamedColor::Blue)),
+            run("  ", FG),
+            run("link", Color::Named(NamedColor::Cyan)),
+            run("  notes.txt  ", FG),
+            run("run.sh", Color::Named(NamedColor::Green)),
+            run("  ", FG),
+            run("src", Color::Named(NamedColor::Blue)),
+        ]
+    );
+    // The rest of the line is one blank run.
+    assert_eq!(line.len(), 9);
+    assert_eq!(line[8].1, FG);
+    assert!(flags(&engine, 0, 28).contains(Flags::BOLD));
+    assert!(!flags(&engine, 0, 31).contains(Flags::BOLD));
+
+    assert_eq!(
+        runs(&engine, 4)[1..3],
+        [
+            run("link", Color::Named(NamedColor::Cyan)),
+            run(&format!("{:47}", " -> run.sh"), FG),
+        ]
+    );
+}
+
+#[test]
+fn vim_session_grid_and_runs() {
+    let engine = replay(VIM);
+    let screen = screen(&engine);
+    assert_eq!(
+        &screen[..5],
+        [
+            "#!/bin/sh",
+            "# greet the user",
+            "name=\"world\" # the default",
+            "echo \"hello $name\"",
+            "exit 0",
+        ]
+    );
+    assert!(screen[5..22].iter().all(|l| l == "~"));
+    assert_eq!(
+        screen[22],
+        format!("{:<62}{:<15}All", "demo.sh [+]", "3,26")
+    );
+    assert_eq!(screen[23], "");
+    assert_eq!(cursor(&engine), (2, 25));
+    // Status line in reverse video; the typed comment is highlighted like the others.
+    assert!(flags(&engine, 22, 0).contains(Flags::INVERSE));
+    assert!(!flags(&engine, 2, 0).contains(Flags::INVERSE));
+
+    let string = Color::Indexed(130);
+    let line = runs(&engine, 2);
+    assert_eq!(
+        &line[..7],
+        [
+            run("name", Color::Named(NamedColor::Cyan)),
+            run("=", FG),
+            run("\"", string),
+            run("world", Color::Named(NamedColor::Red)),
+            run("\"", string),
+            run(" ", FG),
+            run("# the default", Color::Named(NamedColor::Blue)),
+        ]
+    );
+    assert_eq!(line.len(), 8);
+    assert_eq!(
+        runs(&engine, 3)[..6],
+        [
+            run("echo", string),
+            run(" ", Color::Named(NamedColor::Red)),
+            run("\"", string),
+            run("hello ", Color::Named(NamedColor::Red)),
+            run("$name", Color::Named(NamedColor::Magenta)),
+            run("\"", string),
+        ]
+    );
+    // Filler lines are written out in full, padding included.
+    assert_eq!(
+        runs(&engine, 5),
+        [run(
+            &format!("{:80}", "~"),
+            Color::Named(NamedColor::BrightBlue)
+        )]
+    );
+}
+
+#[test]
+fn top_incremental_refresh() {
+    let engine = replay(TOP);
+    let screen = screen(&engine);
+    // The second refresh skips the unchanged Tasks line and clears below the process.
+    assert_eq!(
+        &screen[..9],
+        [
+            "top - 02:15:26 up  5:04,  0 user,  load average: 0.06, 0.09, 0.20",
+            "Tasks:   1 total,   0 running,   1 sleeping,   0 stopped,   0 zombie",
+            "%Cpu(s):  0.0 us,  1.0 sy,  0.0 ni, 98.0 id,  0.0 wa,  0.0 hi,  0.0 si,  1.0 st",
+            "MiB Mem :   6013.8 total,    607.4 free,    605.0 used,   5101.0 buff/cache",
+            "MiB Swap:      0.0 total,      0.0 free,      0.0 used.   5408.8 avail Mem",
+            "",
+            "  PID USER      PR  NI    VIRT    RES    SHR S  %CPU  %MEM     TIME+ COMMAND",
+            "25657 root      20   0    2500   1516   1416 S   0.0   0.0   0:00.00 nginx",
+            "",
+        ]
+    );
+    assert!(screen[9..].iter().all(|l| l.is_empty()));
+    assert_eq!(cursor(&engine), (23, 0));
+    // Values bold, labels plain, column header in reverse video.
+    assert!(flags(&engine, 1, 9).contains(Flags::BOLD));
+    assert!(!flags(&engine, 1, 11).contains(Flags::BOLD));
+    assert!(flags(&engine, 6, 2).contains(Flags::INVERSE));
+    assert!(!flags(&engine, 7, 0).contains(Flags::INVERSE));
+    // No colors: every line is one run.
+    for y in 0..24 {
+        assert_eq!(runs(&engine, y), [run(&line_runs(&engine.term, y).0, FG)]);
+    }
+}
+
+/// htop draws its meters and header with colors and background fills, which
+/// none of the other streams do. Only properties every htop 3 screen has are
+/// checked, so a fresh capture needs no edits here.
+#[test]
+#[ignore = "needs replay/htop.bytes, recorded with replay/capture.sh"]
+fn htop_colored_meters_and_header() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/replay/htop.bytes");
+    let bytes = std::fs::read(path).unwrap_or_else(|e| panic!("{}: {}", path, e));
+    let engine = replay(&bytes);
+    let screen = screen(&engine);
+
+    // Function key bar on the last line, process table header above the rows.
+    assert!(screen[23].starts_with("F1Help"), "{:?}", screen[23]);
+    assert!(screen[23].contains("F10Quit"), "{:?}", screen[23]);
+    let header = screen
+        .iter()
+        .position(|l| l.trim_start().starts_with("PID USER"))
+        .expect("process table header");
+    assert!(header > 0 && header < 22);
+
+    // Meters above the header mix several colors on one line.
+    assert!(
+        (0..header).any(|y| {
+            let mut colors: Vec<Color> = runs(&engine, y).into_iter().map(|(_, c)| c).collect();
+            colors.dedup();
+            colors.len() >= 3
+        }),
+        "no colored meter in {:?}",
+        &screen[..header]
+    );
+    assert!(screen[..header].iter().any(|l| l.contains("Mem[")));
+
+    for y in 0..24 {
+        let (text, spans) = line_runs(&engine.term, y);
+        assert_eq!(text.chars().count(), 80, "line {} width", y);
+        assert_eq!(spans.iter().map(|(len, _)| len).sum::<usize>(), text.len());
+    }
+}
+
+/// Feed each stream in small chunks while the size keeps changing, then check
+/// the grid stays consistent and a redraw after the storm matches a clean replay.
+#[test]
+fn resize_storm() {
+    const SIZES: [(usize, usize); 6] = [(40, 10), (120, 40), (1, 1), (80, 24), (20, 5), (81, 23)];
+    for (name, stream) in [("ls", LS), ("vim", VIM), ("top", TOP)] {
+        let mut engine = Engine::detached(80, 24);
+        for (i, chunk) in stream.chunks(13).enumerate() {
+            let (cols, rows) = SIZES[i % SIZES.len()];
+            engine.resize(cols, rows);
+            engine.process_bytes(chunk);
+
+            let (lines, columns) = (engine.term.screen_lines(), engine.term.columns());
+            let (line, column) = cursor(&engine);
+            assert!(
+                line >= 0 && (line as usize) < lines && column < columns,
+                "{}: cursor ({}, {}) outside {}x{} after chunk {}",
+                name,
+                line,
+                column,
+                columns,
+                lines,
+                i
+            );
+            for y in 0..lines {
+                let (text, spans) = line_runs(&engine.term, y);
+                assert_eq!(text.chars().count(), columns, "{}: line {} width", name, y);
+                assert_eq!(spans.iter().map(|(len, _)| len).sum::<usize>(), text.len());
+            }
+        }
+
+        // Back to 80x24, leave the alternate screen, clear, and draw the stream again.
+        engine.resize(80, 24);
+        engine.process_bytes(b"\x1b[?1049l\x1b[0m\x1b[H\x1b[2J");
+        engine.process_bytes(stream);
+        let clean = replay(stream);
+        assert_eq!(
+            screen(&engine),
+            screen(&clean),
+            "{}: redraw after storm",
+            name
+        );
+        assert_eq!(
+            cursor(&engine),
+            cursor(&clean),
+            "{}: cursor after storm",
+            name
+        );
+    }
+}
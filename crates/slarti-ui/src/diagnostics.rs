@@ -0,0 +1,48 @@
+//! Process-wide counters for the debug overlay.
+//!
+//! Cheap enough to update unconditionally: panels bump them while rendering
+//! and the overlay reads (and resets) them once per frame.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static SHAPED_LINES: AtomicUsize = AtomicUsize::new(0);
+static TASKS: AtomicUsize = AtomicUsize::new(0);
+
+/// Record `n` text lines shaped during the current frame.
+pub fn add_shaped_lines(n: usize) {
+    SHAPED_LINES.fetch_add(n, Ordering::Relaxed);
+}
+
+/// Lines shaped since the last call.
+pub fn take_shaped_lines() -> usize {
+    SHAPED_LINES.swap(0, Ordering::Relaxed)
+}
+
+/// Background tasks currently running.
+pub fn outstanding_tasks() -> usize {
+    TASKS.load(Ordering::Relaxed)
+}
+
+/// Counts a background task as outstanding for as long as it is alive.
+///
+/// Create one at the start of a spawned task's body.
+pub struct TaskGuard(());
+
+impl TaskGuard {
+    pub fn new() -> Self {
+        TASKS.fetch_add(1, Ordering::Relaxed);
+        TaskGuard(())
+    }
+}
+
+impl Default for TaskGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        TASKS.fetch_sub(1, Ordering::Relaxed);
+    }
+}
@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use gpui::{prelude::*, px, svg, Hsla, Pixels};
+use gpui::{div, prelude::*, px, svg, Hsla, Pixels};
 use std::{
     env,
     path::{Path, PathBuf},
@@ -187,6 +187,90 @@ impl Vector {
     }
 }
 
+/// Sparkline renders a tiny bar chart of recent values (e.g. a latency history).
+///
+/// Bars are scaled against the largest value in the series and laid out
+/// left-to-right (oldest first). Like `Vector`, call `.render()` to obtain an element.
+pub struct Sparkline {
+    values: Vec<f32>,
+    width: Pixels,
+    height: Pixels,
+    color: Option<Hsla>,
+}
+
+impl Sparkline {
+    /// Create a sparkline for the given series (oldest first).
+    pub fn new(values: impl IntoIterator<Item = f32>) -> Self {
+        Self {
+            values: values.into_iter().collect(),
+            width: px(60.0),
+            height: px(14.0),
+            color: None,
+        }
+    }
+
+    /// Set an explicit width and height.
+    pub fn with_size(mut self, width: Pixels, height: Pixels) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Set the bar color.
+    pub fn color(mut self, color: Hsla) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// The sparkline as block characters (e.g. "▁▂▅█▃"), for pasting as text.
+    pub fn to_text(&self) -> String {
+        const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+        let max = self.values.iter().cloned().fold(0.0f32, f32::max);
+        self.values
+            .iter()
+            .map(|v| {
+                let frac = if max > 0.0 {
+                    (v / max).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                BLOCKS[(frac * 7.0).round() as usize]
+            })
+            .collect()
+    }
+
+    /// Render the sparkline as a row of bottom-aligned bars.
+    pub fn render(self) -> impl IntoElement {
+        let color = self.color.unwrap_or_else(|| gpui::opaque_grey(1.0, 0.6));
+        let max = self.values.iter().cloned().fold(0.0f32, f32::max);
+        let full = self.height.0;
+        let bars = self.values.iter().map(|v| {
+            let frac = if max > 0.0 {
+                (v / max).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            // Keep a 1px floor so zero samples remain visible.
+            div().flex_1().h(px((full * frac).max(1.0))).bg(color)
+        });
+
+        div()
+            .flex()
+            .flex_row()
+            .items_end()
+            .gap_px()
+            .w(self.width)
+            .h(self.height)
+            .children(bars)
+    }
+}
+
+pub mod diagnostics;
+#[cfg(feature = "test-support")]
+pub mod test_support;
+pub mod theme;
+pub use theme::{palette, ui_px, Appearance, Palette};
+
 // Re-export commonly used items so consumers of `slarti-ui` can avoid importing gpui directly.
 pub use gpui::{px as pixels, Hsla as VectorColor, Pixels as VectorPixels};
 
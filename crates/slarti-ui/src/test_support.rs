@@ -0,0 +1,116 @@
+//! Helpers for the panels' render snapshot tests (feature `test-support`).
+//!
+//! Panels tag their main regions with `debug_selector`s; a test drives a panel
+//! into some state, then compares a text snapshot of it (which regions were
+//! painted, top to bottom, plus whatever text the test adds) with the file
+//! committed under the crate's `snapshots/` directory.
+//!
+//! A missing or differing snapshot fails the test. Run with
+//! `SLARTI_UPDATE_SNAPSHOTS=1` to write the current output, then review and
+//! commit the `.snap` files.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use gpui::VisualTestContext;
+
+static STATE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Empty state, config and data directories for one test; removed on drop.
+///
+/// Panels read and persist recents, filters and snapshots there, so tests
+/// holding one run one at a time and never see the user's files.
+pub struct TempState {
+    dir: PathBuf,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl TempState {
+    pub fn new(name: &str) -> Self {
+        let lock = STATE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = std::env::temp_dir().join(format!("slarti-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        for (var, sub) in [
+            ("XDG_STATE_HOME", "state"),
+            ("XDG_CONFIG_HOME", "config"),
+            ("XDG_DATA_HOME", "data"),
+        ] {
+            let path = dir.join(sub);
+            let _ = std::fs::create_dir_all(&path);
+            std::env::set_var(var, path);
+        }
+        Self { dir, _lock: lock }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl Drop for TempState {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Which of `selectors` were painted in the last frame (top to bottom, then
+/// left to right) and which were not.
+///
+/// Positions are left out on purpose: they depend on the fonts installed,
+/// while presence and order are what a render refactor must keep.
+pub fn layout(cx: &mut VisualTestContext, selectors: &[&'static str]) -> String {
+    let mut painted = Vec::new();
+    let mut absent = Vec::new();
+    for selector in selectors {
+        match cx.debug_bounds(selector) {
+            Some(bounds) => painted.push((bounds.origin.y, bounds.origin.x, *selector)),
+            None => absent.push(*selector),
+        }
+    }
+    painted.sort_by(|a, b| {
+        (a.0, a.1)
+            .partial_cmp(&(b.0, b.1))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    let mut out = String::from("painted:\n");
+    for (_, _, selector) in painted {
+        out.push_str(&format!("  {}\n", selector));
+    }
+    if !absent.is_empty() {
+        out.push_str("absent:\n");
+        for selector in absent {
+            out.push_str(&format!("  {}\n", selector));
+        }
+    }
+    out
+}
+
+/// Compare `actual` with `<dir>/<name>.snap` (see the module docs).
+pub fn assert_snapshot(dir: impl AsRef<Path>, name: &str, actual: &str) {
+    let path = dir.as_ref().join(format!("{}.snap", name));
+    let update = std::env::var_os("SLARTI_UPDATE_SNAPSHOTS").is_some();
+    if !update {
+        let expected = match std::fs::read_to_string(&path) {
+            Ok(expected) => expected,
+            Err(e) => panic!(
+                "snapshot {} missing ({}); run with SLARTI_UPDATE_SNAPSHOTS=1 to write it\n--- actual\n{}",
+                path.display(),
+                e,
+                actual
+            ),
+        };
+        assert!(
+            expected == actual,
+            "snapshot {} changed (rerun with SLARTI_UPDATE_SNAPSHOTS=1 to accept)\n--- expected\n{}--- actual\n{}",
+            path.display(),
+            expected,
+            actual
+        );
+        return;
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).expect("create snapshots dir");
+    }
+    std::fs::write(&path, actual).expect("write snapshot");
+    eprintln!("wrote snapshot {}", path.display());
+}
@@ -0,0 +1,134 @@
+//! App-wide appearance: UI scale factor and color palette (default or high contrast).
+//!
+//! The app installs an `Appearance` global; panels read it when rendering so a
+//! change to the scale or theme applies everywhere on the next frame. Without
+//! a global installed, the defaults (scale 1.0, normal contrast) are used.
+
+use gpui::{px, App, Global, Hsla, Pixels};
+
+/// Smallest/largest supported UI scale factor.
+pub const MIN_SCALE: f32 = 0.75;
+pub const MAX_SCALE: f32 = 2.0;
+/// Step used by zoom in/out.
+pub const SCALE_STEP: f32 = 0.1;
+/// Base rem size (in px) at scale 1.0.
+pub const BASE_REM: f32 = 16.0;
+
+/// Colors shared by the app chrome and panels.
+#[derive(Clone, Copy, Debug)]
+pub struct Palette {
+    /// Primary text
+    pub fg: Hsla,
+    /// Body text that should recede slightly from headings
+    pub fg_dim: Hsla,
+    /// Secondary text (labels, ages, hints)
+    pub muted: Hsla,
+    /// Panel content background
+    pub bg: Hsla,
+    /// Title bar / footer background
+    pub chrome_bg: Hsla,
+    /// Dividers and button outlines
+    pub border: Hsla,
+    /// Accent for active toggles and links
+    pub accent: Hsla,
+    /// Outline drawn around the focused panel or control
+    pub focus_ring: Hsla,
+}
+
+impl Palette {
+    pub fn default_dark() -> Self {
+        Self {
+            fg: gpui::white(),
+            fg_dim: gpui::opaque_grey(1.0, 0.85),
+            muted: gpui::opaque_grey(1.0, 0.6),
+            bg: gpui::rgb(0x0b0b0b).into(),
+            chrome_bg: gpui::rgb(0x141414).into(),
+            border: gpui::opaque_grey(0.2, 0.7),
+            accent: gpui::hsla(0.58, 0.6, 0.65, 1.0),
+            focus_ring: gpui::hsla(0.58, 0.6, 0.65, 0.8),
+        }
+    }
+
+    /// Pure black/white with bright borders and a yellow focus ring.
+    pub fn high_contrast() -> Self {
+        Self {
+            fg: gpui::white(),
+            fg_dim: gpui::white(),
+            muted: gpui::opaque_grey(1.0, 0.9),
+            bg: gpui::black(),
+            chrome_bg: gpui::black(),
+            border: gpui::opaque_grey(0.85, 1.0),
+            accent: gpui::hsla(0.53, 1.0, 0.6, 1.0),
+            focus_ring: gpui::hsla(0.15, 1.0, 0.55, 1.0),
+        }
+    }
+}
+
+/// Global appearance settings.
+#[derive(Clone, Copy, Debug)]
+pub struct Appearance {
+    pub scale: f32,
+    pub high_contrast: bool,
+}
+
+impl Default for Appearance {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            high_contrast: false,
+        }
+    }
+}
+
+impl Global for Appearance {}
+
+impl Appearance {
+    pub fn new(scale: f32, high_contrast: bool) -> Self {
+        Self {
+            scale: clamp_scale(scale),
+            high_contrast,
+        }
+    }
+
+    /// Current appearance (defaults if the app did not install one).
+    pub fn get(cx: &App) -> Self {
+        cx.try_global::<Appearance>().copied().unwrap_or_default()
+    }
+
+    pub fn palette(&self) -> Palette {
+        if self.high_contrast {
+            Palette::high_contrast()
+        } else {
+            Palette::default_dark()
+        }
+    }
+
+    /// Scale a design-time pixel size.
+    pub fn px(&self, v: f32) -> Pixels {
+        px((v * self.scale).round())
+    }
+
+    /// Rem size to apply to the window so text scales with the UI.
+    pub fn rem_size(&self) -> Pixels {
+        px(BASE_REM * self.scale)
+    }
+}
+
+/// Clamp a scale factor to the supported range (non-finite values reset to 1.0).
+pub fn clamp_scale(scale: f32) -> f32 {
+    if scale.is_finite() {
+        scale.clamp(MIN_SCALE, MAX_SCALE)
+    } else {
+        1.0
+    }
+}
+
+/// Scale a design-time pixel size by the current UI scale.
+pub fn ui_px(cx: &App, v: f32) -> Pixels {
+    Appearance::get(cx).px(v)
+}
+
+/// Current palette.
+pub fn palette(cx: &App) -> Palette {
+    Appearance::get(cx).palette()
+}
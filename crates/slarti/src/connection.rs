@@ -0,0 +1,107 @@
+//! Connection manager: live state of the agent session for the selected host.
+//!
+//! The selection flow updates this entity as it probes, connects and polls the
+//! agent; UI elements (e.g. the footer status) observe it instead of holding
+//! their own copies of the connection state.
+
+use gpui::{Context, Hsla};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Live/known remote agent status for a host.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum RemoteAgentStatus {
+    Unknown,
+    NotPresent,
+    Outdated { remote_version: Option<String> },
+    Connecting,
+    Connected { agent_version: String },
+    Error { message: String },
+}
+
+/// State of the connection to the selected host's agent.
+pub struct ConnectionManager {
+    alias: Option<String>,
+    status: RemoteAgentStatus,
+    /// How the host is reached: `ssh`, `local`, `docker`, `kubectl` or `serial`.
+    transport: &'static str,
+    /// Round-trip time of the most recent agent request.
+    last_ping: Option<Duration>,
+}
+
+impl ConnectionManager {
+    pub fn new() -> Self {
+        Self {
+            alias: None,
+            status: RemoteAgentStatus::Unknown,
+            transport: "ssh",
+            last_ping: None,
+        }
+    }
+
+    /// Start tracking a new host selection; resets status and latency.
+    pub fn set_alias(&mut self, alias: Option<String>, cx: &mut Context<Self>) {
+        self.status = if alias.is_some() {
+            RemoteAgentStatus::Connecting
+        } else {
+            RemoteAgentStatus::Unknown
+        };
+        self.alias = alias;
+        self.last_ping = None;
+        cx.notify();
+    }
+
+    pub fn set_transport(&mut self, transport: &'static str, cx: &mut Context<Self>) {
+        self.transport = transport;
+        cx.notify();
+    }
+
+    pub fn status(&self) -> &RemoteAgentStatus {
+        &self.status
+    }
+
+    pub fn set_status(&mut self, status: RemoteAgentStatus, cx: &mut Context<Self>) {
+        self.status = status;
+        cx.notify();
+    }
+
+    /// Record the round-trip time of an agent request.
+    pub fn record_ping(&mut self, rtt: Duration, cx: &mut Context<Self>) {
+        self.last_ping = Some(rtt);
+        cx.notify();
+    }
+
+    /// Status dot color for the footer indicator.
+    pub fn color(&self) -> Hsla {
+        match self.status {
+            RemoteAgentStatus::Connected { .. } => gpui::green(),
+            RemoteAgentStatus::Connecting => gpui::hsla(0.13, 0.8, 0.6, 1.0),
+            RemoteAgentStatus::Outdated { .. } => gpui::yellow(),
+            RemoteAgentStatus::NotPresent | RemoteAgentStatus::Error { .. } => gpui::red(),
+            RemoteAgentStatus::Unknown => gpui::opaque_grey(1.0, 0.5),
+        }
+    }
+
+    /// One-line summary, e.g. "mitko • ssh • agent v0.1.0 • 42 ms".
+    pub fn summary(&self) -> String {
+        let Some(alias) = self.alias.as_ref() else {
+            return "no host selected".to_string();
+        };
+        let status = match &self.status {
+            RemoteAgentStatus::Unknown => "unknown".to_string(),
+            RemoteAgentStatus::NotPresent => "agent not present".to_string(),
+            RemoteAgentStatus::Outdated { remote_version } => match remote_version {
+                Some(v) => format!("agent v{} (update required)", v),
+                None => "agent update required".to_string(),
+            },
+            RemoteAgentStatus::Connecting => "connecting…".to_string(),
+            RemoteAgentStatus::Connected { agent_version } => format!("agent v{}", agent_version),
+            RemoteAgentStatus::Error { message } => format!("error: {}", message),
+        };
+        let mut s = format!("{} • {} • {}", alias, self.transport, status);
+        if let Some(rtt) = self.last_ping {
+            s.push_str(&format!(" • {} ms", rtt.as_millis()));
+        }
+        s
+    }
+}
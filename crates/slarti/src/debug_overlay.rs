@@ -0,0 +1,135 @@
+//! Diagnostics overlay (toggle with ctrl-alt-d): frame times, text shaping,
+//! outstanding background tasks, open ssh processes and the ssh queue.
+//!
+//! While visible it requests a frame every frame, so frame times reflect the
+//! interval between consecutive draws.
+
+use gpui::{div, prelude::*, Context, Window};
+use slarti_ssh::SshProcesses;
+use slarti_ui::{diagnostics, Appearance, Sparkline};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Number of frames kept for averages and the sparkline.
+const SAMPLES: usize = 120;
+/// Scanning the process table every frame would skew the numbers it reports.
+const SSH_SCAN_INTERVAL: Duration = Duration::from_millis(500);
+
+pub struct DebugOverlay {
+    last_frame: Option<Instant>,
+    // Interval between consecutive frames (oldest first)
+    frame_times: VecDeque<Duration>,
+    // Lines shaped per frame (oldest first)
+    shaped_lines: VecDeque<usize>,
+    ssh: SshProcesses,
+    ssh_scanned_at: Option<Instant>,
+}
+
+impl DebugOverlay {
+    pub fn new(_cx: &mut Context<Self>) -> Self {
+        // Discard counts accumulated while hidden.
+        diagnostics::take_shaped_lines();
+        Self {
+            last_frame: None,
+            frame_times: VecDeque::with_capacity(SAMPLES),
+            shaped_lines: VecDeque::with_capacity(SAMPLES),
+            ssh: SshProcesses::default(),
+            ssh_scanned_at: None,
+        }
+    }
+
+    fn sample(&mut self) {
+        let now = Instant::now();
+        if let Some(prev) = self.last_frame.replace(now) {
+            push_sample(&mut self.frame_times, now - prev);
+            push_sample(&mut self.shaped_lines, diagnostics::take_shaped_lines());
+        }
+        if self
+            .ssh_scanned_at
+            .is_none_or(|at| at.elapsed() >= SSH_SCAN_INTERVAL)
+        {
+            self.ssh = slarti_ssh::ssh_processes();
+            self.ssh_scanned_at = Some(now);
+        }
+    }
+}
+
+fn push_sample<T>(samples: &mut VecDeque<T>, value: T) {
+    if samples.len() == SAMPLES {
+        samples.pop_front();
+    }
+    samples.push_back(value);
+}
+
+fn ms(d: Duration) -> f32 {
+    d.as_secs_f32() * 1000.0
+}
+
+impl gpui::Render for DebugOverlay {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        self.sample();
+        window.request_animation_frame();
+
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+
+        let frames = self.frame_times.len().max(1) as f32;
+        let last = self.frame_times.back().copied().map(ms).unwrap_or(0.0);
+        let avg = self.frame_times.iter().copied().map(ms).sum::<f32>() / frames;
+        let max = self.frame_times.iter().copied().map(ms).fold(0.0, f32::max);
+        let shaped_last = self.shaped_lines.back().copied().unwrap_or(0);
+        let shaped_avg = self.shaped_lines.iter().sum::<usize>() as f32 / frames;
+        let ssh_children = match self.ssh.children {
+            Some(n) => n.to_string(),
+            None => "n/a".to_string(),
+        };
+
+        div()
+            .absolute()
+            .top(ap.px(40.0))
+            .right(ap.px(8.0))
+            .flex()
+            .flex_col()
+            .gap_1()
+            .p(ap.px(8.0))
+            .rounded_sm()
+            .border_1()
+            .border_color(pal.border)
+            .bg(gpui::hsla(0.0, 0.0, 0.0, 0.85))
+            .text_color(pal.fg_dim)
+            .child(div().text_color(pal.fg).child("Diagnostics"))
+            .child(format!(
+                "frame {:.1} ms (avg {:.1}, max {:.1})",
+                last, avg, max
+            ))
+            .child(
+                Sparkline::new(self.frame_times.iter().copied().map(ms))
+                    .with_size(ap.px(180.0), ap.px(24.0))
+                    .color(if max > 33.0 {
+                        gpui::hsla(0.0, 0.8, 0.6, 1.0)
+                    } else {
+                        pal.accent
+                    })
+                    .render(),
+            )
+            .child(format!(
+                "shaped lines {} / frame (avg {:.0})",
+                shaped_last, shaped_avg
+            ))
+            .child(format!(
+                "background tasks {}",
+                diagnostics::outstanding_tasks()
+            ))
+            .child(format!(
+                "ssh processes {}, control masters {}",
+                ssh_children, self.ssh.masters
+            ))
+            .child({
+                let queue = slarti_ssh::queue::stats();
+                format!(
+                    "ssh queue {} active, {} interactive / {} background waiting",
+                    queue.active, queue.waiting_interactive, queue.waiting_background
+                )
+            })
+    }
+}
@@ -0,0 +1,104 @@
+//! Open the selected host's remote folder in a local editor (≡ → Open in …).
+//!
+//! The folder comes from the host entry's `SlartiFolder` keyword (an absolute
+//! path); without one the editor opens a bare remote window on the host:
+//!
+//! ```text
+//! Host web1
+//!     SlartiFolder /srv/app
+//! ```
+//!
+//! VS Code, Cursor and Zed are known; `editors` in the UI settings adds or
+//! overrides commands by name, with `{alias}` and `{path}` filled in per host.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use slarti_ssh::on_path;
+use std::process::{Command, Stdio};
+
+/// Host entry keyword naming the folder to open (params are lowercased by slarti-sshcfg).
+pub const FOLDER_KEYWORD: &str = "slartifolder";
+
+/// How to open a host's folder in one editor.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EditorCommand {
+    /// Shown in the menu ("Open in <name>")
+    pub name: String,
+    pub program: String,
+    /// `{alias}` and `{path}` are replaced; arguments left empty (no folder) are dropped
+    pub args: Vec<String>,
+}
+
+impl EditorCommand {
+    fn new(name: &str, program: &str, args: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            program: program.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+
+    /// Editors slarti knows how to open remote folders with.
+    pub fn builtin() -> Vec<Self> {
+        vec![
+            Self::new(
+                "VS Code",
+                "code",
+                &["--remote", "ssh-remote+{alias}", "{path}"],
+            ),
+            Self::new(
+                "Cursor",
+                "cursor",
+                &["--remote", "ssh-remote+{alias}", "{path}"],
+            ),
+            Self::new("Zed", "zed", &["ssh://{alias}{path}"]),
+        ]
+    }
+
+    pub fn installed(&self) -> bool {
+        on_path(&self.program)
+    }
+
+    /// Arguments for `alias`, opening `folder` if given.
+    pub fn args_for(&self, alias: &str, folder: Option<&str>) -> Vec<String> {
+        self.args
+            .iter()
+            .map(|a| {
+                a.replace("{alias}", alias)
+                    .replace("{path}", folder.unwrap_or(""))
+            })
+            .filter(|a| !a.is_empty())
+            .collect()
+    }
+
+    /// Start the editor on `alias` without waiting for it.
+    pub fn launch(&self, alias: &str, folder: Option<&str>) -> Result<()> {
+        if let Some(folder) = folder {
+            if !folder.starts_with('/') {
+                return Err(anyhow!("SlartiFolder must be an absolute path: {}", folder));
+            }
+        }
+        let mut child = Command::new(&self.program)
+            .args(self.args_for(alias, folder))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| anyhow!("{}: {}", self.program, e))?;
+        // Editors' launchers hand off to a running instance and exit; reap them.
+        std::thread::spawn(move || child.wait());
+        Ok(())
+    }
+}
+
+/// The configured editors (overriding built-ins of the same name) that are on PATH.
+pub fn installed_editors(custom: &[EditorCommand]) -> Vec<EditorCommand> {
+    let mut editors = custom.to_vec();
+    for editor in EditorCommand::builtin() {
+        if !editors.iter().any(|e| e.name == editor.name) {
+            editors.push(editor);
+        }
+    }
+    editors.retain(EditorCommand::installed);
+    editors
+}
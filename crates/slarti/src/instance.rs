@@ -0,0 +1,209 @@
+//! Single-instance mode.
+//!
+//! The first slarti takes a lock in the state dir and listens on a unix socket
+//! next to it. Later invocations find the lock held, forward their arguments
+//! (e.g. a host alias to select) over the socket and exit, instead of running
+//! alongside it and racing it on state file writes.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::{mpsc, OnceLock};
+use std::time::{Duration, Instant};
+
+/// How long a second invocation keeps trying to reach the running instance,
+/// which may have taken the lock but not bound its socket yet.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Held for the lifetime of the running instance.
+static LOCK: OnceLock<File> = OnceLock::new();
+
+#[derive(Serialize, Deserialize)]
+struct Forward {
+    args: Vec<String>,
+}
+
+pub enum Claim {
+    /// This is the running instance; argument lists forwarded by later invocations
+    /// arrive on the receiver.
+    Primary(mpsc::Receiver<Vec<String>>),
+    /// The arguments were handed to the running instance; this process should exit.
+    Forwarded,
+    /// Another instance holds the lock but could not be reached.
+    Busy(io::Error),
+    /// The lock or socket could not be set up; run without single-instance mode.
+    Unavailable(io::Error),
+}
+
+fn lock_path() -> PathBuf {
+    crate::slarti_state_dir().join("instance.lock")
+}
+
+fn socket_path() -> PathBuf {
+    crate::slarti_state_dir().join("instance.sock")
+}
+
+/// Become the running instance, or forward `args` to the one already running.
+pub fn claim(args: &[String]) -> Claim {
+    let lock = match open_lock() {
+        Ok(lock) => lock,
+        Err(e) => return Claim::Unavailable(e),
+    };
+    match lock.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => {
+            return match forward(args) {
+                Ok(()) => Claim::Forwarded,
+                Err(e) => Claim::Busy(e),
+            }
+        }
+        Err(TryLockError::Error(e)) => return Claim::Unavailable(e),
+    }
+    match listen() {
+        Ok(rx) => {
+            let _ = LOCK.set(lock);
+            Claim::Primary(rx)
+        }
+        Err(e) => Claim::Unavailable(e),
+    }
+}
+
+/// Take the instance lock without becoming the running instance, for
+/// commands that rewrite state files (`encrypt-state`). Fails while a
+/// slarti is running, so the two cannot race on the same files.
+pub fn hold() -> io::Result<()> {
+    let lock = open_lock()?;
+    match lock.try_lock() {
+        Ok(()) => {
+            let _ = LOCK.set(lock);
+            Ok(())
+        }
+        Err(TryLockError::WouldBlock) => Err(io::Error::other("slarti is running; quit it first")),
+        Err(TryLockError::Error(e)) => Err(e),
+    }
+}
+
+fn open_lock() -> io::Result<File> {
+    let path = lock_path();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)
+}
+
+fn listen() -> io::Result<mpsc::Receiver<Vec<String>>> {
+    let path = socket_path();
+    // Only the lock holder binds, so an existing socket was left by an instance that crashed.
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path)?;
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            // Each on its own thread: a client that never writes only stalls itself.
+            let tx = tx.clone();
+            std::thread::spawn(move || {
+                if let Err(e) = serve(stream, &tx) {
+                    tracing::debug!("instance: {}", e);
+                }
+            });
+        }
+    });
+    Ok(rx)
+}
+
+fn serve(stream: UnixStream, tx: &mpsc::Sender<Vec<String>>) -> io::Result<()> {
+    // The socket is reachable by anyone who can enter the state dir; only
+    // the user's own invocations may drive the app.
+    let uid = peer_uid(&stream)?;
+    if uid != unsafe { libc::getuid() } {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("connection from uid {} refused", uid),
+        ));
+    }
+    stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+    let mut line = String::new();
+    BufReader::new(stream.try_clone()?).read_line(&mut line)?;
+    let forward: Forward = serde_json::from_str(&line)?;
+    tracing::info!("arguments from another invocation: {:?}", forward.args);
+    let _ = tx.send(forward.args);
+    let mut out = stream;
+    out.write_all(b"ok\n")
+}
+
+/// Uid of the process at the other end of `stream`.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn peer_uid(stream: &UnixStream) -> io::Result<libc::uid_t> {
+    use std::os::fd::AsRawFd;
+    let mut cred = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    let ret = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut cred as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(cred.uid)
+}
+
+/// Uid of the process at the other end of `stream`.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn peer_uid(stream: &UnixStream) -> io::Result<libc::uid_t> {
+    use std::os::fd::AsRawFd;
+    let (mut uid, mut gid) = (0, 0);
+    if unsafe { libc::getpeereid(stream.as_raw_fd(), &mut uid, &mut gid) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(uid)
+}
+
+fn forward(args: &[String]) -> io::Result<()> {
+    let path = socket_path();
+    let started = Instant::now();
+    let stream = loop {
+        match UnixStream::connect(&path) {
+            Ok(stream) => break stream,
+            Err(_) if started.elapsed() < CONNECT_TIMEOUT => {
+                std::thread::sleep(Duration::from_millis(100))
+            }
+            Err(e) => return Err(e),
+        }
+    };
+    stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+    let msg = serde_json::to_string(&Forward {
+        args: args.to_vec(),
+    })?;
+    let mut out = stream.try_clone()?;
+    out.write_all(msg.as_bytes())?;
+    out.write_all(b"\n")?;
+    let mut ack = String::new();
+    BufReader::new(stream).read_line(&mut ack)?;
+    if ack.trim() == "ok" {
+        Ok(())
+    } else {
+        Err(io::Error::other("running instance did not acknowledge"))
+    }
+}
+
+/// The host alias named on the command line (`slarti [<alias>]`), if any.
+pub fn alias_arg(args: &[String]) -> Option<&str> {
+    args.iter()
+        .map(String::as_str)
+        .find(|a| !a.starts_with('-'))
+}
@@ -0,0 +1,521 @@
+//! Diagnostics panel: tails the app's own log file (see `logging`) with
+//! level and target filters, so ssh and UI problems can be investigated
+//! without restarting from a terminal with `RUST_LOG` set. Its "SSH config"
+//! view lists risky ssh config settings with their suggested fixes.
+
+use gpui::{
+    div, prelude::*, Context, EventEmitter, FocusHandle, Focusable, KeyDownEvent, MouseButton,
+    SharedString, Window,
+};
+use slarti_sshcfg::lint::{self, Finding, Severity};
+use slarti_ui::Appearance;
+use std::collections::VecDeque;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Entries kept in memory.
+const MAX_ENTRIES: usize = 5000;
+/// Entries rendered (the newest matching ones).
+const MAX_SHOWN: usize = 500;
+/// How much of an existing log is read when the panel opens.
+const INITIAL_TAIL_BYTES: u64 = 256 * 1024;
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    const ALL: [Level; 5] = [
+        Level::Error,
+        Level::Warn,
+        Level::Info,
+        Level::Debug,
+        Level::Trace,
+    ];
+
+    fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "ERROR" => Level::Error,
+            "WARN" => Level::Warn,
+            "INFO" => Level::Info,
+            "DEBUG" => Level::Debug,
+            "TRACE" => Level::Trace,
+            _ => return None,
+        })
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+
+    fn color(self) -> gpui::Hsla {
+        match self {
+            Level::Error => gpui::hsla(0.0, 0.8, 0.6, 1.0),
+            Level::Warn => gpui::hsla(0.13, 0.8, 0.6, 1.0),
+            Level::Info => gpui::hsla(0.58, 0.6, 0.65, 1.0),
+            Level::Debug | Level::Trace => gpui::opaque_grey(1.0, 0.5),
+        }
+    }
+}
+
+/// One log event (continuation lines such as backtraces are folded into `message`).
+struct Entry {
+    time: String,
+    level: Level,
+    target: String,
+    message: String,
+}
+
+/// Parse a `tracing_subscriber::fmt` line: `<time> <LEVEL> <target>: <message>`.
+fn parse_line(line: &str) -> Option<Entry> {
+    let mut parts = line.splitn(2, ' ');
+    let time = parts.next()?;
+    let rest = parts.next()?.trim_start();
+    let (level, rest) = rest.split_once(' ')?;
+    let level = Level::parse(level)?;
+    let (target, message) = rest.trim_start().split_once(": ")?;
+    Some(Entry {
+        time: time.to_string(),
+        level,
+        target: target.to_string(),
+        message: message.to_string(),
+    })
+}
+
+/// What was appended to the log at `path` since `offset` (`None`: its last
+/// `INITIAL_TAIL_BYTES`), with the offset it starts at. A file shorter than
+/// `offset` was rotated or truncated and is read from the start.
+fn read_appended(path: &Path, offset: Option<u64>) -> Option<(u64, Vec<u8>)> {
+    let mut file = std::fs::File::open(path).ok()?;
+    let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let start = match offset {
+        None => len.saturating_sub(INITIAL_TAIL_BYTES),
+        Some(offset) if len < offset => 0,
+        Some(offset) => offset,
+    };
+    let mut buf = Vec::new();
+    if len > start {
+        file.seek(SeekFrom::Start(start)).ok()?;
+        file.read_to_end(&mut buf).ok()?;
+    }
+    Some((start, buf))
+}
+
+/// Emitted when the user closes the panel.
+pub struct Dismissed;
+
+pub struct LogViewer {
+    focus: FocusHandle,
+    path: PathBuf,
+    // Read position in the current log file (None until the first read)
+    offset: Option<u64>,
+    // Incomplete last line, completed by the next read
+    partial: String,
+    entries: VecDeque<Entry>,
+    min_level: Level,
+    target_filter: String,
+    // Showing the ssh config lint instead of the log
+    showing_lint: bool,
+    findings: Vec<Finding>,
+    // Config load or fix failure, shown above the findings
+    lint_error: Option<String>,
+}
+
+impl EventEmitter<Dismissed> for LogViewer {}
+
+impl LogViewer {
+    pub fn new(cx: &mut Context<Self>) -> Self {
+        // Read the log off the UI thread: now, then every POLL_INTERVAL.
+        cx.spawn(async move |this, cx| loop {
+            let Ok((path, offset)) =
+                this.update(cx, |viewer, _| (viewer.path.clone(), viewer.offset))
+            else {
+                break;
+            };
+            let read = cx
+                .background_executor()
+                .spawn(async move { read_appended(&path, offset) })
+                .await;
+            if this.update(cx, |viewer, cx| viewer.poll(read, cx)).is_err() {
+                break;
+            }
+            cx.background_executor().timer(POLL_INTERVAL).await;
+        })
+        .detach();
+        let mut viewer = Self {
+            focus: cx.focus_handle(),
+            path: crate::logging::log_file(),
+            offset: None,
+            partial: String::new(),
+            entries: VecDeque::new(),
+            min_level: Level::Info,
+            target_filter: String::new(),
+            showing_lint: false,
+            findings: Vec::new(),
+            lint_error: None,
+        };
+        viewer.run_lint();
+        viewer
+    }
+
+    /// Take in what a `read_appended` found; repaint if it completed any line.
+    fn poll(&mut self, read: Option<(u64, Vec<u8>)>, cx: &mut Context<Self>) {
+        if let Some((start, buf)) = read {
+            if self.append(start, &buf) {
+                cx.notify();
+            }
+        }
+    }
+
+    fn append(&mut self, start: u64, buf: &[u8]) -> bool {
+        if self.offset.is_some_and(|offset| start < offset) {
+            // Rotated (or truncated): start over on the new file.
+            self.partial.clear();
+        }
+        self.offset = Some(start + buf.len() as u64);
+        if buf.is_empty() {
+            return false;
+        }
+        self.partial.push_str(&String::from_utf8_lossy(buf));
+        let Some(end) = self.partial.rfind('\n') else {
+            return false;
+        };
+        let complete: String = self.partial.drain(..=end).collect();
+        // Reading from the middle of the file: the first line is incomplete.
+        let skip = usize::from(self.entries.is_empty() && start > 0);
+        for line in complete.lines().skip(skip) {
+            match parse_line(line) {
+                Some(entry) => {
+                    if self.entries.len() == MAX_ENTRIES {
+                        self.entries.pop_front();
+                    }
+                    self.entries.push_back(entry);
+                }
+                None => {
+                    if let Some(last) = self.entries.back_mut() {
+                        last.message.push('\n');
+                        last.message.push_str(line);
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// Re-check ~/.ssh/config and everything it includes.
+    fn run_lint(&mut self) {
+        match slarti_sshcfg::load::load_user_config_tree() {
+            Ok(tree) => {
+                self.findings = lint::lint(&tree);
+                self.lint_error = None;
+            }
+            Err(e) => {
+                self.findings.clear();
+                self.lint_error = Some(format!("{:#}", e));
+            }
+        }
+    }
+
+    fn apply_fix(&mut self, index: usize, cx: &mut Context<Self>) {
+        let Some(fix) = self.findings.get(index).and_then(|f| f.fix.clone()) else {
+            return;
+        };
+        let applied = lint::apply_fix(&fix);
+        self.run_lint();
+        if let Err(e) = applied {
+            self.lint_error = Some(format!("{:#}", e));
+        } else {
+            slarti_ssh::config_changed();
+            tracing::info!("ssh config: {} in {}", fix.description, fix.path.display());
+        }
+        cx.notify();
+    }
+
+    fn render_lint(&self, cx: &mut Context<Self>) -> gpui::Div {
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let mut list = div().flex().flex_col();
+        if let Some(e) = &self.lint_error {
+            list = list.child(
+                div()
+                    .px(ap.px(8.0))
+                    .text_color(Level::Error.color())
+                    .child(e.clone()),
+            );
+        }
+        for (index, f) in self.findings.iter().enumerate() {
+            let (label, color) = match f.severity {
+                Severity::Warning => ("WARN", Level::Warn.color()),
+                Severity::Info => ("INFO", Level::Info.color()),
+            };
+            let location = if f.line == 0 {
+                f.path.display().to_string()
+            } else {
+                format!("{}:{}", f.path.display(), f.line)
+            };
+            let path = f.path.clone();
+            list = list.child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .px(ap.px(8.0))
+                    .py(ap.px(4.0))
+                    .border_b_1()
+                    .border_color(pal.border)
+                    .child(
+                        div()
+                            .flex()
+                            .gap_2()
+                            .child(div().w(ap.px(48.0)).text_color(color).child(label))
+                            .child(div().text_color(pal.fg).child(f.message.clone()))
+                            .child(
+                                div()
+                                    .text_color(pal.accent)
+                                    .cursor_pointer()
+                                    .child(location)
+                                    .on_mouse_up(
+                                        MouseButton::Left,
+                                        cx.listener(move |_this: &mut Self, _ev, _w, cx| {
+                                            cx.open_with_system(&path)
+                                        }),
+                                    ),
+                            ),
+                    )
+                    .child(
+                        div()
+                            .pl(ap.px(56.0))
+                            .text_color(pal.fg_dim)
+                            .child(f.explanation),
+                    )
+                    .children(f.fix.as_ref().map(|fix| {
+                        div().pl(ap.px(56.0)).pt(ap.px(2.0)).flex().child(
+                            div()
+                                .px(ap.px(6.0))
+                                .h(ap.px(20.0))
+                                .flex()
+                                .items_center()
+                                .rounded_sm()
+                                .border_1()
+                                .border_color(pal.border)
+                                .text_color(pal.fg)
+                                .cursor_pointer()
+                                .child(format!("Fix: {}", fix.description))
+                                .on_mouse_up(
+                                    MouseButton::Left,
+                                    cx.listener(move |this: &mut Self, _ev, _w, cx| {
+                                        this.apply_fix(index, cx)
+                                    }),
+                                ),
+                        )
+                    })),
+            );
+        }
+        if self.findings.is_empty() && self.lint_error.is_none() {
+            list = list.child(
+                div()
+                    .px(ap.px(8.0))
+                    .child("No risky settings found in the ssh config"),
+            );
+        }
+        list
+    }
+
+    fn matches(&self, entry: &Entry) -> bool {
+        entry.level <= self.min_level
+            && (self.target_filter.is_empty() || entry.target.contains(&self.target_filter))
+    }
+
+    fn on_key(&mut self, ev: &KeyDownEvent, _window: &mut Window, cx: &mut Context<Self>) {
+        cx.stop_propagation();
+        match ev.keystroke.key.as_str() {
+            "escape" => cx.emit(Dismissed),
+            "backspace" => {
+                self.target_filter.pop();
+                cx.notify();
+            }
+            _ if !ev.keystroke.modifiers.control && !ev.keystroke.modifiers.platform => {
+                if let Some(ch) = ev.keystroke.key_char.as_ref() {
+                    self.target_filter.push_str(ch);
+                    cx.notify();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Focusable for LogViewer {
+    fn focus_handle(&self, _: &gpui::App) -> FocusHandle {
+        self.focus.clone()
+    }
+}
+
+impl gpui::Render for LogViewer {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+
+        let chip = |label: SharedString, active: bool| {
+            div()
+                .px(ap.px(6.0))
+                .h(ap.px(20.0))
+                .flex()
+                .items_center()
+                .rounded_sm()
+                .border_1()
+                .border_color(if active { pal.accent } else { pal.border })
+                .text_color(if active { pal.fg } else { pal.fg_dim })
+                .cursor_pointer()
+                .child(label)
+        };
+
+        let toolbar = div()
+            .flex()
+            .items_center()
+            .gap_2()
+            .px(ap.px(8.0))
+            .py(ap.px(6.0))
+            .border_b_1()
+            .border_color(pal.border)
+            .child(div().text_color(pal.fg).child("Diagnostics"))
+            .child(chip("Log".into(), !self.showing_lint).on_mouse_up(
+                MouseButton::Left,
+                cx.listener(|this: &mut Self, _ev, _w, cx| {
+                    this.showing_lint = false;
+                    cx.notify();
+                }),
+            ))
+            .child(
+                chip(
+                    format!("SSH config ({})", self.findings.len()).into(),
+                    self.showing_lint,
+                )
+                .on_mouse_up(
+                    MouseButton::Left,
+                    cx.listener(|this: &mut Self, _ev, _w, cx| {
+                        this.showing_lint = true;
+                        this.run_lint();
+                        cx.notify();
+                    }),
+                ),
+            )
+            .children(Level::ALL.into_iter().map(|level| {
+                chip(level.label().into(), level == self.min_level).on_mouse_up(
+                    MouseButton::Left,
+                    cx.listener(move |this: &mut Self, _ev, _w, cx| {
+                        this.min_level = level;
+                        cx.notify();
+                    }),
+                )
+            }))
+            // Target filter: type while the panel is focused.
+            .child(
+                div()
+                    .flex_1()
+                    .h(ap.px(20.0))
+                    .px(ap.px(6.0))
+                    .flex()
+                    .items_center()
+                    .rounded_sm()
+                    .border_1()
+                    .border_color(if self.focus.is_focused(window) {
+                        pal.focus_ring
+                    } else {
+                        pal.border
+                    })
+                    .text_color(if self.target_filter.is_empty() {
+                        pal.muted
+                    } else {
+                        pal.fg
+                    })
+                    .child(if self.target_filter.is_empty() {
+                        "filter by target…".to_string()
+                    } else {
+                        format!("{}▏", self.target_filter)
+                    }),
+            )
+            .child(chip("Open folder".into(), false).on_mouse_up(
+                MouseButton::Left,
+                cx.listener(|_this: &mut Self, _ev, window, cx| {
+                    window.dispatch_action(Box::new(crate::OpenLogFolder), cx);
+                }),
+            ))
+            .child(chip("Close".into(), false).on_mouse_up(
+                MouseButton::Left,
+                cx.listener(|_this: &mut Self, _ev, _w, cx| cx.emit(Dismissed)),
+            ));
+
+        let mut shown: Vec<&Entry> = self
+            .entries
+            .iter()
+            .rev()
+            .filter(|e| self.matches(e))
+            .take(MAX_SHOWN)
+            .collect();
+        shown.reverse();
+        let rows = shown.into_iter().map(|e| {
+            div()
+                .flex()
+                .gap_2()
+                .px(ap.px(8.0))
+                .child(div().text_color(pal.muted).child(e.time.clone()))
+                .child(
+                    div()
+                        .w(ap.px(48.0))
+                        .text_color(e.level.color())
+                        .child(e.level.label()),
+                )
+                .child(div().text_color(pal.fg_dim).child(e.target.clone()))
+                .child(div().flex_1().text_color(pal.fg).child(e.message.clone()))
+        });
+
+        div()
+            .track_focus(&self.focus)
+            .on_key_down(cx.listener(Self::on_key))
+            .on_mouse_down(
+                MouseButton::Left,
+                cx.listener(|this: &mut Self, _ev, window, _cx| window.focus(&this.focus)),
+            )
+            .flex()
+            .flex_col()
+            .size_full()
+            .rounded_md()
+            .border_1()
+            .border_color(pal.border)
+            .bg(pal.bg)
+            .text_color(pal.fg_dim)
+            .child(toolbar)
+            .child(
+                div()
+                    .id("LogViewerScroll")
+                    .flex()
+                    .flex_col()
+                    .flex_1()
+                    .min_h_0()
+                    .py(ap.px(4.0))
+                    .overflow_y_scroll()
+                    .when(self.showing_lint, |d| d.child(self.render_lint(cx)))
+                    .when(!self.showing_lint, |d| d.children(rows))
+                    .when(!self.showing_lint && self.entries.is_empty(), |d| {
+                        d.child(
+                            div()
+                                .px(ap.px(8.0))
+                                .child(format!("No log entries in {}", self.path.display())),
+                        )
+                    }),
+            )
+    }
+}
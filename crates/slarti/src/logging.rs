@@ -0,0 +1,124 @@
+//! Logging for the desktop app.
+//!
+//! Logs go to stderr (filtered by `RUST_LOG`, as before) and to
+//! `<state dir>/logs/slarti.log` (`RUST_LOG`, or `info` when unset). The file is
+//! rotated by size to `slarti.log.1`, `.2`, … A panic hook records panics with
+//! a backtrace, so crashes of packaged builds leave a trace.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing_subscriber::{fmt, prelude::*, EnvFilter};
+
+/// Rotate once the current log file would grow past this size.
+const MAX_LOG_BYTES: u64 = 5 * 1024 * 1024;
+/// Number of rotated files kept next to the current one.
+const KEEP_ROTATED: usize = 3;
+const LOG_FILE: &str = "slarti.log";
+
+/// Directory holding the log files.
+pub fn log_dir() -> PathBuf {
+    crate::slarti_state_dir().join("logs")
+}
+
+/// The current log file.
+pub fn log_file() -> PathBuf {
+    log_dir().join(LOG_FILE)
+}
+
+/// Install the stderr and file loggers and the panic hook. Safe to call more than once.
+pub fn init() {
+    static INIT: std::sync::Once = std::sync::Once::new();
+    INIT.call_once(|| {
+        let stderr = fmt::layer()
+            .with_writer(io::stderr)
+            .with_filter(EnvFilter::from_default_env());
+        let file = match RotatingFile::open(log_file()) {
+            Ok(f) => Some(
+                fmt::layer()
+                    .with_ansi(false)
+                    .with_writer(Mutex::new(f))
+                    .with_filter(
+                        EnvFilter::try_from_default_env()
+                            .unwrap_or_else(|_| EnvFilter::new("info")),
+                    ),
+            ),
+            Err(e) => {
+                eprintln!("slarti: file logging disabled: {}", e);
+                None
+            }
+        };
+        let _ = tracing_subscriber::registry()
+            .with(stderr)
+            .with(file)
+            .try_init();
+        install_panic_hook();
+    });
+}
+
+/// Log panics (with a backtrace) before running the default hook.
+fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let backtrace = std::backtrace::Backtrace::force_capture();
+        tracing::error!(target: "panic", "{}\n{}", info, backtrace);
+        default_hook(info);
+    }));
+}
+
+/// Append-only log file that rotates itself when it reaches `MAX_LOG_BYTES`.
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf) -> io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let file = Self::append(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { path, file, size })
+    }
+
+    fn append(path: &Path) -> io::Result<File> {
+        OpenOptions::new().create(true).append(true).open(path)
+    }
+
+    fn rotated(&self, n: usize) -> PathBuf {
+        let mut p = self.path.clone().into_os_string();
+        p.push(format!(".{}", n));
+        PathBuf::from(p)
+    }
+
+    /// slarti.log → slarti.log.1 → … → slarti.log.KEEP_ROTATED (dropped).
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        for n in (1..KEEP_ROTATED).rev() {
+            let _ = std::fs::rename(self.rotated(n), self.rotated(n + 1));
+        }
+        std::fs::rename(&self.path, self.rotated(1))?;
+        self.file = Self::append(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > MAX_LOG_BYTES {
+            // Keep logging to the current file if rotation fails.
+            let _ = self.rotate();
+        }
+        let n = self.file.write(buf)?;
+        self.size += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
@@ -1,23 +1,80 @@
 use gpui::{
     div, prelude::*, px, size, App, Application, Bounds, Context, FocusHandle, Focusable,
-    MouseButton, MouseDownEvent, MouseMoveEvent, MouseUpEvent, Pixels, Window, WindowBounds,
-    WindowOptions,
+    KeyDownEvent, MouseButton, MouseDownEvent, MouseMoveEvent, MouseUpEvent, Pixels, Window,
+    WindowBounds, WindowOptions,
 };
 use serde::{Deserialize, Serialize};
-use slarti_host::{make_host_panel, HostPanel as HostInfoPanel, HostPanelProps as HostInfoProps};
+use slarti_host::{
+    make_host_panel, ActionPolicy, ActionPreview, HandoffBundle, HostPanel as HostInfoPanel,
+    HostPanelProps as HostInfoProps, PendingAction, PreviewActions, QuickAction, SessionState,
+};
 use slarti_hosts::{make_hosts_panel, HostsPanel, HostsPanelProps};
-use slarti_ssh::{check_agent, deploy_agent, remote_user_is_root, run_agent};
+use slarti_ssh::askpass::{PendingPrompt, PromptKind};
+use slarti_ssh::container::{
+    check_container_agent, container_agent_path, deploy_container_agent, deploy_container_plan,
+    run_container_agent, Container,
+};
+use slarti_ssh::plan::ActionPlan;
+use slarti_ssh::serial::SerialConsole;
+use slarti_ssh::{
+    check_agent, check_local_agent, deploy_agent, remote_user_is_root, run_agent,
+    run_agent_with_flags, run_local_agent,
+};
 use slarti_sshcfg as sshcfg;
-use slarti_ui::{FsAssets, Vector as UiVector};
-use std::collections::HashMap;
+use slarti_ui::{Appearance, FsAssets, Vector as UiVector};
+use std::collections::{HashMap, VecDeque};
 use std::path::PathBuf;
 
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, OnceLock};
 
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+mod connection;
+mod debug_overlay;
+mod editor;
+mod instance;
+mod log_viewer;
+mod logging;
+mod rename;
+mod search;
+mod settings;
+mod stats;
+mod transfer;
+
+use connection::{ConnectionManager, RemoteAgentStatus};
+use debug_overlay::DebugOverlay;
+use editor::EditorCommand;
+use log_viewer::LogViewer;
+use rename::RenameOverlay;
+use search::SearchOverlay;
+use settings::SettingsView;
+use stats::StatsView;
+use transfer::TransferOverlay;
 
 static BG_RT: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
 
+/// Bumped on every host selection so background refresh loops for a previous selection stop.
+static SELECTION_EPOCH: AtomicU64 = AtomicU64::new(0);
+
+/// Set once the app starts shutting down; long-running tasks stop early.
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
+/// How long shutdown waits for background tasks to finish before closing anyway.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(3);
+
+/// How often the session loop asks the HostPanel polling scheduler for due sections.
+const POLL_TICK: Duration = Duration::from_secs(1);
+
+/// How often tailnet peers' online state is refreshed.
+const TAILSCALE_REFRESH: Duration = Duration::from_secs(60);
+
+/// How long a host selection must stay unchanged before it is probed.
+const SELECT_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// How long to wait for an interactive login before giving up on retrying the selection.
+const AUTH_WAIT_SECS: u32 = 120;
+
 fn bg_rt() -> &'static tokio::runtime::Runtime {
     BG_RT.get_or_init(|| {
         tokio::runtime::Builder::new_current_thread()
@@ -37,6 +94,70 @@ struct UiSettings {
     last_window_bounds: Option<(i32, i32, u32, u32)>, // x, y, w, h
     /// Whether the terminal is collapsed
     terminal_collapsed: bool,
+    /// UI scale factor (text and chrome)
+    #[serde(default = "default_ui_scale")]
+    ui_scale: f32,
+    /// High-contrast palette
+    high_contrast: bool,
+    /// Preview mutating actions (commands, host, user) and require confirmation before they run
+    preview_actions: bool,
+    /// Extra or overriding "Open in …" editor commands
+    editors: Vec<EditorCommand>,
+    /// Editor last opened a host in; `OpenInEditor` uses it
+    editor: Option<String>,
+    /// Agent install root on ssh hosts (`SlartiAgentRoot` overrides it per
+    /// host); None for the default, which depends on the remote user
+    agent_root: Option<String>,
+    /// Extra flags the agent is started with on ssh hosts, e.g. its
+    /// self-limits (`SlartiAgentFlags` overrides them per host)
+    agent_flags: Vec<String>,
+    /// Mark ssh hosts read-only on deploy, so their agent refuses mutating
+    /// commands whatever it is started with (`SlartiAgentReadOnly` overrides
+    /// it per host)
+    agent_read_only: bool,
+    /// Record local usage statistics (see `stats`)
+    usage_stats: bool,
+}
+
+fn default_ui_scale() -> f32 {
+    1.0
+}
+
+gpui::actions!(
+    slarti,
+    [
+        ZoomIn,
+        ZoomOut,
+        ResetZoom,
+        ToggleHighContrast,
+        TogglePreviewActions,
+        ToggleDebugOverlay,
+        ToggleDiagnostics,
+        OpenLogFolder,
+        ExportHandoff,
+        ImportHandoff,
+        OpenSearch,
+        RenameHost,
+        ImportHosts,
+        OpenInEditor,
+        CopyFiles,
+        ToggleUsageStats,
+        OpenStats,
+        OpenSettings
+    ]
+);
+
+/// Update the global appearance, persist it and redraw all windows.
+fn update_appearance(cx: &mut App, f: impl FnOnce(&mut Appearance)) {
+    let mut ap = Appearance::get(cx);
+    f(&mut ap);
+    let ap = Appearance::new(ap.scale, ap.high_contrast);
+    cx.set_global(ap);
+    let mut ui = load_ui_settings();
+    ui.ui_scale = ap.scale;
+    ui.high_contrast = ap.high_contrast;
+    save_ui_settings(ui);
+    cx.refresh_windows();
 }
 
 fn ui_settings_path() -> std::path::PathBuf {
@@ -47,27 +168,43 @@ fn ui_settings_path() -> std::path::PathBuf {
     dir
 }
 
+/// On-disk schema version of `ui/settings.json` (0: unversioned).
+const UI_SETTINGS_VERSION: u32 = 1;
+/// On-disk schema version of `agents/<alias>.json` (0: unversioned).
+const AGENT_STATE_VERSION: u32 = 1;
+
 fn load_ui_settings() -> UiSettings {
-    let path = ui_settings_path();
-    if let Ok(s) = std::fs::read_to_string(path) {
-        if let Ok(cfg) = serde_json::from_str::<UiSettings>(&s) {
-            return cfg;
-        }
+    match slarti_state::load_versioned(
+        ui_settings_path(),
+        UI_SETTINGS_VERSION,
+        slarti_state::no_migration,
+    ) {
+        Ok(Some(cfg)) => return cfg,
+        Ok(None) => {}
+        Err(e) => tracing::error!("UI settings not loaded, using defaults: {:#}", e),
     }
     UiSettings {
         split_top: 240.0,
         last_window_bounds: None,
         terminal_collapsed: false,
+        ui_scale: default_ui_scale(),
+        high_contrast: false,
+        preview_actions: false,
+        editors: Vec::new(),
+        editor: None,
+        agent_root: None,
+        agent_flags: Vec::new(),
+        agent_read_only: false,
+        usage_stats: false,
     }
 }
 
 fn save_ui_settings(mut cfg: UiSettings) {
     // Clamp split_top to sane bounds before saving
     cfg.split_top = cfg.split_top.clamp(120.0, 600.0);
-    let _ = std::fs::write(
-        ui_settings_path(),
-        serde_json::to_vec_pretty(&cfg).unwrap_or_else(|_| serde_json::to_vec(&cfg).unwrap()),
-    );
+    if let Err(e) = slarti_state::save_versioned(ui_settings_path(), UI_SETTINGS_VERSION, &cfg) {
+        tracing::warn!("saving UI settings failed: {}", e);
+    }
 }
 
 /// Persistent agent deployment information for a host alias.
@@ -79,17 +216,9 @@ pub struct AgentDeploymentState {
     pub remote_path: Option<PathBuf>,
     pub remote_checksum: Option<String>,
     pub last_seen_ok: bool,
-}
-
-/// Live/known remote agent status for a host.
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub enum RemoteAgentStatus {
-    Unknown,
-    NotPresent,
-    Outdated { remote_version: Option<String> },
-    Connecting,
-    Connected { agent_version: String },
-    Error { message: String },
+    /// Configured install root the agent was looked for under (None: the default)
+    #[serde(default)]
+    pub install_root: Option<String>,
 }
 
 /// Local persisted state store (per-app) keyed by host alias.
@@ -130,10 +259,263 @@ fn agent_state_path(alias: &str) -> std::path::PathBuf {
 fn save_agent_state(state: &AgentDeploymentState) -> std::io::Result<()> {
     let dir = slarti_agents_state_dir();
     std::fs::create_dir_all(&dir)?;
-    let path = agent_state_path(&state.alias);
-    let data =
-        serde_json::to_vec_pretty(state).unwrap_or_else(|_| serde_json::to_vec(state).unwrap());
-    std::fs::write(path, data)
+    slarti_state::save_versioned(agent_state_path(&state.alias), AGENT_STATE_VERSION, state)
+}
+
+/// The serial line configured for `alias` (`SlartiSerial`/`SlartiBaud` in its
+/// ssh config entry), if it is a serial host.
+fn serial_console_for(
+    tree: &sshcfg::model::ConfigTree,
+    alias: &str,
+) -> Option<anyhow::Result<SerialConsole>> {
+    let entry = sshcfg::load::host_entry_for_alias(tree, alias)?;
+    let device = entry.get(slarti_ssh::serial::SERIAL_KEYWORD)?;
+    Some(SerialConsole::new(
+        device,
+        entry.get(slarti_ssh::serial::BAUD_KEYWORD),
+    ))
+}
+
+/// Agent install root for `alias`: `SlartiAgentRoot` in its ssh config entry,
+/// else the global `agent_root` setting; None for the default. Unusable
+/// values are logged and ignored.
+fn agent_root_for(tree: &sshcfg::model::ConfigTree, alias: &str) -> Option<String> {
+    let root = sshcfg::load::host_entry_for_alias(tree, alias)
+        .and_then(|entry| entry.get(slarti_ssh::AGENT_ROOT_KEYWORD))
+        .map(str::to_string)
+        .or_else(|| load_ui_settings().agent_root)?;
+    if slarti_ssh::valid_install_root(&root) {
+        Some(root)
+    } else {
+        tracing::warn!(
+            "agent install root {:?} for {} ignored: use an absolute path or one under ~/",
+            root,
+            alias
+        );
+        None
+    }
+}
+
+/// Extra agent flags for `alias`: `SlartiAgentFlags` in its ssh config entry
+/// (split at spaces), else the global `agent_flags` setting.
+fn agent_flags_for(tree: &sshcfg::model::ConfigTree, alias: &str) -> Vec<String> {
+    match sshcfg::load::host_entry_for_alias(tree, alias)
+        .and_then(|entry| entry.get(slarti_ssh::AGENT_FLAGS_KEYWORD))
+    {
+        Some(flags) => flags.split_whitespace().map(str::to_string).collect(),
+        None => load_ui_settings().agent_flags,
+    }
+}
+
+/// Whether deploys mark `alias` read-only: `SlartiAgentReadOnly` in its ssh
+/// config entry ("yes" or "no"), else the global `agent_read_only` setting.
+fn agent_read_only_for(tree: &sshcfg::model::ConfigTree, alias: &str) -> bool {
+    match sshcfg::load::host_entry_for_alias(tree, alias)
+        .and_then(|entry| entry.get(slarti_ssh::AGENT_READ_ONLY_KEYWORD))
+    {
+        Some(value) => matches!(value.to_ascii_lowercase().as_str(), "yes" | "true"),
+        None => load_ui_settings().agent_read_only,
+    }
+}
+
+/// Tags of `alias` (`Tag` in its ssh config entry; several may be given
+/// separated by commas or spaces), which the action policy can restrict.
+fn host_tags_for(tree: &sshcfg::model::ConfigTree, alias: &str) -> Vec<String> {
+    sshcfg::load::host_entry_for_alias(tree, alias)
+        .and_then(|entry| entry.get("tag"))
+        .map(|tags| {
+            tags.split([',', ' '])
+                .filter(|t| !t.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// System-wide policy (admin-managed, e.g. an observer install on a shared
+/// workstation), combined with the user's own `policy.json`.
+const SYSTEM_POLICY: &str = "/etc/slarti/policy.json";
+
+/// Load the action policy. An unreadable or invalid policy file disables all
+/// actions rather than silently allowing them.
+fn load_action_policy() -> ActionPolicy {
+    [
+        PathBuf::from(SYSTEM_POLICY),
+        slarti_state_dir().join("policy.json"),
+    ]
+    .iter()
+    .map(|path| {
+        ActionPolicy::load(path).unwrap_or_else(|e| {
+            tracing::error!("action policy: {:#}; disabling all actions", e);
+            ActionPolicy::read_only()
+        })
+    })
+    .fold(ActionPolicy::default(), ActionPolicy::merge)
+}
+
+/// The slarti-remote binary to deploy or run locally: next to this executable
+/// (packaged builds), else the workspace's release or debug build.
+fn local_agent_binary() -> Option<PathBuf> {
+    let beside_exe = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join("slarti-remote")));
+    let target_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../target");
+    beside_exe
+        .into_iter()
+        .chain([
+            target_dir.join("release/slarti-remote"),
+            target_dir.join("debug/slarti-remote"),
+        ])
+        .find(|p| p.exists())
+}
+
+/// What `action` will run on `alias`, for the approval preview (None: nothing to
+/// preview, e.g. no local agent build to deploy).
+async fn action_plan(action: PendingAction, alias: &str) -> Option<ActionPlan> {
+    let version = env!("CARGO_PKG_VERSION");
+    match action {
+        PendingAction::InstallKey => Some(slarti_ssh::plan::install_key_plan(alias).await),
+        // The agent makes the syscall itself, as the login user.
+        PendingAction::SignalProcess { pid, signal } => Some(ActionPlan {
+            action: format!(
+                "Send {} to process {}",
+                slarti_host::signal_name(signal),
+                pid
+            ),
+            host: alias.to_string(),
+            user: slarti_ssh::plan::remote_user(alias).await,
+            escalation: None,
+            commands: vec![format!("kill -{} {}  (by the agent)", signal, pid)],
+        }),
+        PendingAction::ReniceProcess { pid, nice } => Some(ActionPlan {
+            action: format!("Renice process {} to {}", pid, nice),
+            host: alias.to_string(),
+            user: slarti_ssh::plan::remote_user(alias).await,
+            escalation: None,
+            commands: vec![format!("renice -n {} -p {}  (by the agent)", nice, pid)],
+        }),
+        // Local and container agents are not kept per version.
+        PendingAction::CleanAgents
+            if alias == slarti_hosts::LOCAL_HOST || Container::from_alias(alias).is_some() =>
+        {
+            None
+        }
+        PendingAction::CleanAgents => {
+            let root = sshcfg::load::load_user_config_tree()
+                .ok()
+                .and_then(|tree| agent_root_for(&tree, alias));
+            slarti_ssh::plan::clean_agents_plan(
+                alias,
+                version,
+                root.as_deref(),
+                slarti_ssh::KEEP_AGENT_VERSIONS,
+                Duration::from_secs(10),
+            )
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!("listing agent versions on {} failed: {:#}", alias, e);
+                None
+            })
+        }
+        PendingAction::Deploy if alias == slarti_hosts::LOCAL_HOST => None,
+        PendingAction::Deploy => {
+            let artifact = local_agent_binary()?;
+            Some(match Container::from_alias(alias) {
+                Some(c) => deploy_container_plan(&c, &artifact, version),
+                None => {
+                    let root = sshcfg::load::load_user_config_tree()
+                        .ok()
+                        .and_then(|tree| agent_root_for(&tree, alias));
+                    slarti_ssh::plan::deploy_plan(
+                        alias,
+                        &artifact,
+                        version,
+                        root.as_deref(),
+                        Duration::from_secs(10),
+                    )
+                    .await
+                }
+            })
+        }
+    }
+}
+
+/// Remove all but the newest agent versions on `alias` (never this build's),
+/// reported as a progress message.
+/// Leave the read-only marker on `alias` after a deploy when it is configured
+/// read-only; None when it is not.
+async fn mark_read_only_on(alias: &str) -> Option<String> {
+    if alias == slarti_hosts::LOCAL_HOST || Container::from_alias(alias).is_some() {
+        return None;
+    }
+    let tree = sshcfg::load::load_user_config_tree().ok()?;
+    if !agent_read_only_for(&tree, alias) {
+        return None;
+    }
+    let root = agent_root_for(&tree, alias);
+    let marked =
+        slarti_ssh::mark_agent_read_only(alias, root.as_deref(), Duration::from_secs(10)).await;
+    Some(match marked {
+        Ok(()) => "agent marked read-only".to_string(),
+        Err(e) => format!("marking the agent read-only failed: {}", e),
+    })
+}
+
+async fn clean_old_agents_on(alias: &str) -> String {
+    if alias == slarti_hosts::LOCAL_HOST || Container::from_alias(alias).is_some() {
+        return "no agent versions kept on this host".to_string();
+    }
+    let root = sshcfg::load::load_user_config_tree()
+        .ok()
+        .and_then(|tree| agent_root_for(&tree, alias));
+    let cleaned = slarti_ssh::clean_old_agents(
+        alias,
+        env!("CARGO_PKG_VERSION"),
+        root.as_deref(),
+        slarti_ssh::KEEP_AGENT_VERSIONS,
+        false,
+        Duration::from_secs(10),
+    )
+    .await;
+    match cleaned {
+        Ok(c) if c.removed.is_empty() => "no old agent versions to remove".to_string(),
+        Ok(c) => format!("removed old agent versions: {}", c.removed.join(", ")),
+        Err(e) => format!("cleaning old agents failed: {}", e),
+    }
+}
+
+/// Create an empty ssh config (and ~/.ssh) with the permissions ssh expects, if missing.
+fn ensure_ssh_config(path: &std::path::Path) -> std::io::Result<()> {
+    use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+    if let Some(dir) = path.parent() {
+        std::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)?;
+    }
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .mode(0o600)
+        .open(path)
+        .map(drop)
+}
+
+/// Enable state encryption (keychain key or passphrase) and encrypt existing state files.
+fn encrypt_state(keychain: bool) -> anyhow::Result<usize> {
+    let dir = slarti_state_dir();
+    if keychain {
+        slarti_state::enable(&dir, slarti_state::KeyMethod::Keychain, None)?;
+    } else {
+        let passphrase = slarti_state::new_passphrase()?;
+        slarti_state::enable(&dir, slarti_state::KeyMethod::Passphrase, Some(&passphrase))?;
+    }
+    let mut converted = slarti_state::encrypt_existing(&dir)?;
+    // HostPanel and the hosts tree keep their state under the XDG state dir.
+    if let Some(host_dir) = HostInfoPanel::state_dir().filter(|d| *d != dir) {
+        converted += slarti_state::encrypt_existing(&host_dir)?;
+    }
+    Ok(converted)
 }
 
 /// Minimal Vector wrapper around gpui::svg() to support Vector::color() like Zed.
@@ -166,7 +548,28 @@ struct ContainerView {
     last_split_y: f32,
     // Remote/selection state
     _selected_alias: Option<String>,
-    _agent_status: RemoteAgentStatus,
+    connection: gpui::Entity<ConnectionManager>,
+    // ssh askpass prompts (passphrase, PIN, security-key touch) shown as a modal, oldest first
+    askpass: VecDeque<PendingPrompt>,
+    askpass_input: String,
+    askpass_focus: FocusHandle,
+    // Whether the ≡ menu is open
+    menu_open: bool,
+    // Installed editors, listed in the ≡ menu (refreshed when it opens)
+    editors: Vec<EditorCommand>,
+    // Log viewer panel (ctrl-alt-l or ≡ → Diagnostics), when shown
+    log_viewer: Option<gpui::Entity<LogViewer>>,
+    // Usage statistics (≡ → Statistics), when shown
+    stats: Option<gpui::Entity<StatsView>>,
+    settings: Option<gpui::Entity<SettingsView>>,
+    // Diagnostics overlay (ctrl-alt-d), when shown
+    debug_overlay: Option<gpui::Entity<DebugOverlay>>,
+    // Global search (ctrl-alt-f or ≡ → Search), when shown
+    search: Option<gpui::Entity<SearchOverlay>>,
+    // Host rename (≡ → Rename host…), when shown
+    rename: Option<gpui::Entity<RenameOverlay>>,
+    // scp/rsync command builder (≡ → Copy files…), when shown
+    transfer: Option<gpui::Entity<TransferOverlay>>,
     // Window state for custom titlebar behavior
     dragging_window: bool,
     _saved_windowed_bounds: Option<Bounds<Pixels>>,
@@ -179,8 +582,11 @@ impl ContainerView {
         terminal: gpui::Entity<TerminalView>,
         hosts: gpui::Entity<HostsPanel>,
         host_info: gpui::Entity<HostInfoPanel>,
+        connection: gpui::Entity<ConnectionManager>,
         ui_fg: (f32, f32, f32, f32),
     ) -> Self {
+        // Repaint the footer status whenever the connection state changes.
+        cx.observe(&connection, |_, _, cx| cx.notify()).detach();
         Self {
             focus: cx.focus_handle(),
             terminal,
@@ -193,7 +599,19 @@ impl ContainerView {
             dragging_split: false,
             last_split_y: 0.0,
             _selected_alias: None,
-            _agent_status: RemoteAgentStatus::Unknown,
+            connection,
+            askpass: VecDeque::new(),
+            askpass_input: String::new(),
+            askpass_focus: cx.focus_handle(),
+            menu_open: false,
+            editors: Vec::new(),
+            log_viewer: None,
+            stats: None,
+            settings: None,
+            debug_overlay: None,
+            search: None,
+            rename: None,
+            transfer: None,
             dragging_window: false,
             _saved_windowed_bounds: None,
             _is_maximized: false,
@@ -201,8 +619,25 @@ impl ContainerView {
     }
 
     // Header controls: left menu is a placeholder for now.
-    fn on_close(&mut self, _: &MouseUpEvent, window: &mut Window, _cx: &mut Context<Self>) {
-        // Persist window bounds before closing, then remove the window.
+    fn on_close(&mut self, _: &MouseUpEvent, window: &mut Window, cx: &mut Context<Self>) {
+        self.shutdown(window, cx);
+    }
+
+    /// Shut the app down: stop agent sessions, hang up the shell, persist UI state, and
+    /// remove the window once background tasks have wound down (or `SHUTDOWN_GRACE` passed).
+    fn shutdown(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if SHUTTING_DOWN.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        tracing::info!("shutting down");
+        // Session refresh loops stop on an epoch change and terminate their AgentClient.
+        SELECTION_EPOCH.fetch_add(1, Ordering::SeqCst);
+        // ssh processes blocked on a prompt give up once it is cancelled.
+        for prompt in self.askpass.drain(..) {
+            prompt.answer(None);
+        }
+        self.terminal.read(cx).shutdown();
+
         let b = window.bounds();
         let mut ui = load_ui_settings();
         ui.last_window_bounds = Some((
@@ -211,9 +646,31 @@ impl ContainerView {
             b.size.width.0 as u32,
             b.size.height.0 as u32,
         ));
+        ui.split_top = self.split_top;
+        ui.terminal_collapsed = self.terminal_collapsed;
         save_ui_settings(ui);
-
-        window.remove_window();
+        self.host_info
+            .update(cx, |panel, cx| panel.save_view_state(cx));
+        stats::flush();
+
+        window
+            .spawn(cx, async move |cx| {
+                let deadline = Instant::now() + SHUTDOWN_GRACE;
+                while slarti_ui::diagnostics::outstanding_tasks() > 0 && Instant::now() < deadline {
+                    cx.background_executor()
+                        .timer(Duration::from_millis(50))
+                        .await;
+                }
+                let left = slarti_ui::diagnostics::outstanding_tasks();
+                if left > 0 {
+                    tracing::warn!("{} background tasks still running at shutdown", left);
+                }
+                let _ = cx.update(|window, cx| {
+                    window.remove_window();
+                    cx.quit();
+                });
+            })
+            .detach();
     }
 
     fn on_minimize(&mut self, _: &MouseUpEvent, window: &mut Window, _cx: &mut Context<Self>) {
@@ -265,6 +722,432 @@ impl ContainerView {
         cx.notify();
     }
 
+    /// Expand the terminal if it is collapsed.
+    fn expand_terminal(&mut self) {
+        if self.terminal_collapsed {
+            self.terminal_collapsed = false;
+            let mut ui = load_ui_settings();
+            ui.terminal_collapsed = false;
+            save_ui_settings(ui);
+        }
+    }
+
+    /// Run a command line in the embedded terminal, expanding it if collapsed.
+    fn run_in_terminal(&mut self, command: &str, cx: &mut Context<Self>) {
+        self.expand_terminal();
+        self.terminal
+            .update(cx, |term, _| term.run_command(command));
+        cx.notify();
+    }
+
+    /// Carry out a start-screen quick action.
+    fn quick_action(&mut self, action: QuickAction, window: &mut Window, cx: &mut Context<Self>) {
+        match action {
+            QuickAction::AddHost => {
+                // Hosts come from ~/.ssh/config; open it (created if missing) in the user's editor.
+                let Some(path) = dirs_next::home_dir().map(|h| h.join(".ssh").join("config"))
+                else {
+                    return;
+                };
+                if let Err(e) = ensure_ssh_config(&path) {
+                    tracing::warn!("cannot create {}: {}", path.display(), e);
+                    return;
+                }
+                cx.open_with_system(&path);
+            }
+            QuickAction::OpenTerminal => {
+                self.expand_terminal();
+                window.focus(&self.terminal.focus_handle(cx));
+            }
+            QuickAction::Settings => window.dispatch_action(Box::new(OpenSettings), cx),
+        }
+        cx.notify();
+    }
+
+    /// Select the host alias named on a command line (ours, or one forwarded by another launch).
+    fn open_args(&mut self, args: &[String], window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(alias) = instance::alias_arg(args) {
+            let alias = alias.to_string();
+            self.hosts
+                .update(cx, |hosts, cx| hosts.select(alias, window, cx));
+        }
+    }
+
+    /// Handle invocations forwarded by later launches: raise the window and apply their arguments.
+    fn poll_instance(
+        &mut self,
+        rx: &std::sync::mpsc::Receiver<Vec<String>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        for args in rx.try_iter() {
+            window.activate_window();
+            self.open_args(&args, window, cx);
+        }
+    }
+
+    /// Pick up new askpass prompts and drop the ones ssh no longer waits for.
+    fn poll_askpass(
+        &mut self,
+        rx: &std::sync::mpsc::Receiver<PendingPrompt>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let before = self.askpass.len();
+        self.askpass.extend(rx.try_iter());
+        let front_done = self.askpass.front().is_some_and(|p| p.is_done());
+        self.askpass.retain(|p| !p.is_done());
+        if front_done {
+            self.askpass_input.clear();
+        }
+        if self.askpass.len() != before || front_done {
+            if self.askpass.is_empty() {
+                window.focus(&self.focus);
+            } else {
+                window.focus(&self.askpass_focus);
+            }
+            cx.notify();
+        }
+    }
+
+    /// Answer the current askpass prompt (`None` cancels it).
+    fn answer_askpass(
+        &mut self,
+        answer: Option<String>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(prompt) = self.askpass.pop_front() {
+            prompt.answer(answer);
+        }
+        self.askpass_input.clear();
+        if self.askpass.is_empty() {
+            window.focus(&self.focus);
+        }
+        cx.notify();
+    }
+
+    fn on_askpass_key(&mut self, ev: &KeyDownEvent, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(kind) = self.askpass.front().map(|p| p.request.kind) else {
+            return;
+        };
+        cx.stop_propagation();
+        match ev.keystroke.key.as_str() {
+            "escape" => self.answer_askpass(None, window, cx),
+            "enter" => {
+                let answer = match kind {
+                    PromptKind::Secret => Some(std::mem::take(&mut self.askpass_input)),
+                    PromptKind::Confirm => Some("yes".to_string()),
+                    PromptKind::Notify => None,
+                };
+                self.answer_askpass(answer, window, cx);
+            }
+            "backspace" if kind == PromptKind::Secret => {
+                self.askpass_input.pop();
+                cx.notify();
+            }
+            _ if kind == PromptKind::Secret
+                && !ev.keystroke.modifiers.control
+                && !ev.keystroke.modifiers.platform =>
+            {
+                if let Some(ch) = ev.keystroke.key_char.as_ref() {
+                    self.askpass_input.push_str(ch);
+                    cx.notify();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Drop-down for the ≡ button, if open.
+    fn render_menu(&self, cx: &mut Context<Self>) -> Option<gpui::Div> {
+        if !self.menu_open {
+            return None;
+        }
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let item = |label: gpui::SharedString| {
+            div()
+                .px(ap.px(10.0))
+                .h(ap.px(24.0))
+                .flex()
+                .items_center()
+                .cursor_pointer()
+                .text_color(pal.fg)
+                .hover(|d| d.bg(gpui::opaque_grey(0.2, 0.5)))
+                .child(label)
+        };
+        Some(
+            div()
+                .absolute()
+                .top(ap.px(30.0))
+                .left(ap.px(8.0))
+                .flex()
+                .flex_col()
+                .py(ap.px(4.0))
+                .rounded_sm()
+                .border_1()
+                .border_color(pal.border)
+                .bg(pal.chrome_bg)
+                .child(item("Search".into()).on_mouse_up(
+                    MouseButton::Left,
+                    cx.listener(|this: &mut Self, _ev, window, cx| {
+                        cx.stop_propagation();
+                        this.menu_open = false;
+                        window.dispatch_action(Box::new(OpenSearch), cx);
+                        cx.notify();
+                    }),
+                ))
+                .child(item("Rename host…".into()).on_mouse_up(
+                    MouseButton::Left,
+                    cx.listener(|this: &mut Self, _ev, window, cx| {
+                        cx.stop_propagation();
+                        this.menu_open = false;
+                        window.dispatch_action(Box::new(RenameHost), cx);
+                        cx.notify();
+                    }),
+                ))
+                .child(item("Import hosts…".into()).on_mouse_up(
+                    MouseButton::Left,
+                    cx.listener(|this: &mut Self, _ev, window, cx| {
+                        cx.stop_propagation();
+                        this.menu_open = false;
+                        window.dispatch_action(Box::new(ImportHosts), cx);
+                        cx.notify();
+                    }),
+                ))
+                .child(item("Copy files…".into()).on_mouse_up(
+                    MouseButton::Left,
+                    cx.listener(|this: &mut Self, _ev, window, cx| {
+                        cx.stop_propagation();
+                        this.menu_open = false;
+                        window.dispatch_action(Box::new(CopyFiles), cx);
+                        cx.notify();
+                    }),
+                ))
+                .children(self.editors.iter().map(|editor| {
+                    let editor = editor.clone();
+                    item(format!("Open in {}", editor.name).into()).on_mouse_up(
+                        MouseButton::Left,
+                        cx.listener(move |this: &mut Self, _ev, _window, cx| {
+                            cx.stop_propagation();
+                            this.menu_open = false;
+                            this.launch_editor(editor.clone(), cx);
+                            cx.notify();
+                        }),
+                    )
+                }))
+                .child(item("Diagnostics".into()).on_mouse_up(
+                    MouseButton::Left,
+                    cx.listener(|this: &mut Self, _ev, window, cx| {
+                        cx.stop_propagation();
+                        this.menu_open = false;
+                        window.dispatch_action(Box::new(ToggleDiagnostics), cx);
+                        cx.notify();
+                    }),
+                ))
+                .child(item("Settings".into()).on_mouse_up(
+                    MouseButton::Left,
+                    cx.listener(|this: &mut Self, _ev, window, cx| {
+                        cx.stop_propagation();
+                        this.menu_open = false;
+                        window.dispatch_action(Box::new(OpenSettings), cx);
+                        cx.notify();
+                    }),
+                ))
+                .child(item("Statistics".into()).on_mouse_up(
+                    MouseButton::Left,
+                    cx.listener(|this: &mut Self, _ev, window, cx| {
+                        cx.stop_propagation();
+                        this.menu_open = false;
+                        window.dispatch_action(Box::new(OpenStats), cx);
+                        cx.notify();
+                    }),
+                ))
+                .child(item("Open log folder".into()).on_mouse_up(
+                    MouseButton::Left,
+                    cx.listener(|this: &mut Self, _ev, window, cx| {
+                        cx.stop_propagation();
+                        this.menu_open = false;
+                        window.dispatch_action(Box::new(OpenLogFolder), cx);
+                        cx.notify();
+                    }),
+                ))
+                .child(item("Export handoff".into()).on_mouse_up(
+                    MouseButton::Left,
+                    cx.listener(|this: &mut Self, _ev, window, cx| {
+                        cx.stop_propagation();
+                        this.menu_open = false;
+                        window.dispatch_action(Box::new(ExportHandoff), cx);
+                        cx.notify();
+                    }),
+                ))
+                .child(item("Import handoff…".into()).on_mouse_up(
+                    MouseButton::Left,
+                    cx.listener(|this: &mut Self, _ev, window, cx| {
+                        cx.stop_propagation();
+                        this.menu_open = false;
+                        window.dispatch_action(Box::new(ImportHandoff), cx);
+                        cx.notify();
+                    }),
+                ))
+                .child(
+                    item(if PreviewActions::get(cx) {
+                        "Preview actions: on".into()
+                    } else {
+                        "Preview actions: off".into()
+                    })
+                    .on_mouse_up(
+                        MouseButton::Left,
+                        cx.listener(|this: &mut Self, _ev, window, cx| {
+                            cx.stop_propagation();
+                            this.menu_open = false;
+                            window.dispatch_action(Box::new(TogglePreviewActions), cx);
+                            cx.notify();
+                        }),
+                    ),
+                )
+                .child(
+                    item(if stats::enabled() {
+                        "Usage statistics: on".into()
+                    } else {
+                        "Usage statistics: off".into()
+                    })
+                    .on_mouse_up(
+                        MouseButton::Left,
+                        cx.listener(|this: &mut Self, _ev, window, cx| {
+                            cx.stop_propagation();
+                            this.menu_open = false;
+                            window.dispatch_action(Box::new(ToggleUsageStats), cx);
+                            cx.notify();
+                        }),
+                    ),
+                ),
+        )
+    }
+
+    /// Modal for the oldest pending askpass prompt, if any.
+    fn render_askpass(&self, cx: &mut Context<Self>) -> Option<gpui::Div> {
+        let prompt = self.askpass.front()?;
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let button = |label: &'static str| {
+            div()
+                .px(ap.px(10.0))
+                .h(ap.px(22.0))
+                .flex()
+                .items_center()
+                .rounded_sm()
+                .border_1()
+                .border_color(pal.border)
+                .cursor_pointer()
+                .text_color(pal.fg)
+                .child(label)
+        };
+        let mut buttons = div().flex().justify_end().gap_2();
+        let body = match prompt.request.kind {
+            PromptKind::Secret => {
+                buttons = buttons
+                    .child(button("Cancel").on_mouse_up(
+                        MouseButton::Left,
+                        cx.listener(|this: &mut Self, _ev, window, cx| {
+                            cx.stop_propagation();
+                            this.answer_askpass(None, window, cx);
+                        }),
+                    ))
+                    .child(button("OK").on_mouse_up(
+                        MouseButton::Left,
+                        cx.listener(|this: &mut Self, _ev, window, cx| {
+                            cx.stop_propagation();
+                            let answer = std::mem::take(&mut this.askpass_input);
+                            this.answer_askpass(Some(answer), window, cx);
+                        }),
+                    ));
+                // Masked input; keystrokes are handled by on_askpass_key.
+                Some(
+                    div()
+                        .h(ap.px(24.0))
+                        .px(ap.px(6.0))
+                        .flex()
+                        .items_center()
+                        .rounded_sm()
+                        .border_1()
+                        .border_color(pal.accent)
+                        .bg(pal.bg)
+                        .text_color(pal.fg)
+                        .child(format!(
+                            "{}▏",
+                            "•".repeat(self.askpass_input.chars().count())
+                        )),
+                )
+            }
+            PromptKind::Confirm => {
+                buttons = buttons
+                    .child(button("Deny").on_mouse_up(
+                        MouseButton::Left,
+                        cx.listener(|this: &mut Self, _ev, window, cx| {
+                            cx.stop_propagation();
+                            this.answer_askpass(None, window, cx);
+                        }),
+                    ))
+                    .child(button("Allow").on_mouse_up(
+                        MouseButton::Left,
+                        cx.listener(|this: &mut Self, _ev, window, cx| {
+                            cx.stop_propagation();
+                            this.answer_askpass(Some("yes".to_string()), window, cx);
+                        }),
+                    ));
+                None
+            }
+            // ssh closes these itself once the key was touched; allow hiding it anyway.
+            PromptKind::Notify => {
+                buttons = buttons.child(button("Dismiss").on_mouse_up(
+                    MouseButton::Left,
+                    cx.listener(|this: &mut Self, _ev, window, cx| {
+                        cx.stop_propagation();
+                        this.answer_askpass(None, window, cx);
+                    }),
+                ));
+                None
+            }
+        };
+        Some(
+            div()
+                .absolute()
+                .inset(px(0.))
+                .flex()
+                .items_center()
+                .justify_center()
+                .bg(gpui::hsla(0.0, 0.0, 0.0, 0.5))
+                .child(
+                    div()
+                        .track_focus(&self.askpass_focus)
+                        .on_key_down(cx.listener(Self::on_askpass_key))
+                        .on_mouse_up(
+                            MouseButton::Left,
+                            cx.listener(|this: &mut Self, _ev, window, cx| {
+                                cx.stop_propagation();
+                                window.focus(&this.askpass_focus);
+                            }),
+                        )
+                        .w(ap.px(420.0))
+                        .flex()
+                        .flex_col()
+                        .gap_3()
+                        .p(ap.px(12.0))
+                        .rounded_md()
+                        .border_1()
+                        .border_color(pal.border)
+                        .bg(pal.chrome_bg)
+                        .text_color(pal.fg_dim)
+                        .child(div().text_color(pal.muted).child("ssh"))
+                        .child(prompt.request.prompt.trim().to_string())
+                        .children(body)
+                        .child(buttons),
+                ),
+        )
+    }
+
     // Split drag handlers
     fn on_split_mouse_down(
         &mut self,
@@ -314,80 +1197,504 @@ impl ContainerView {
         }
     }
 
-    fn on_focus_click(&mut self, _: &MouseUpEvent, window: &mut Window, cx: &mut Context<Self>) {
-        window.focus(&self.focus_handle(cx));
-    }
-
-    // Edge resize handlers (Wayland compat)
-    fn on_resize_left(&mut self, _: &MouseDownEvent, window: &mut Window, _cx: &mut Context<Self>) {
-        window.start_window_resize(gpui::ResizeEdge::Left);
-    }
-    fn on_resize_right(
+    fn toggle_debug_overlay(
         &mut self,
-        _: &MouseDownEvent,
-        window: &mut Window,
-        _cx: &mut Context<Self>,
+        _: &ToggleDebugOverlay,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
     ) {
-        window.start_window_resize(gpui::ResizeEdge::Right);
-    }
-    fn on_resize_top(&mut self, _: &MouseDownEvent, window: &mut Window, _cx: &mut Context<Self>) {
-        window.start_window_resize(gpui::ResizeEdge::Top);
+        self.debug_overlay = match self.debug_overlay.take() {
+            Some(_) => None,
+            None => Some(cx.new(DebugOverlay::new)),
+        };
+        cx.notify();
     }
-    fn on_resize_bottom(
+
+    fn toggle_diagnostics(
         &mut self,
-        _: &MouseDownEvent,
+        _: &ToggleDiagnostics,
         window: &mut Window,
-        _cx: &mut Context<Self>,
+        cx: &mut Context<Self>,
     ) {
-        window.start_window_resize(gpui::ResizeEdge::Bottom);
-    }
-    fn on_resize_tl(&mut self, _: &MouseDownEvent, window: &mut Window, _cx: &mut Context<Self>) {
-        window.start_window_resize(gpui::ResizeEdge::TopLeft);
+        if self.log_viewer.take().is_none() {
+            let viewer = cx.new(LogViewer::new);
+            cx.subscribe(&viewer, |this, _, _: &log_viewer::Dismissed, cx| {
+                this.log_viewer = None;
+                cx.notify();
+            })
+            .detach();
+            window.focus(&viewer.focus_handle(cx));
+            self.log_viewer = Some(viewer);
+        }
+        cx.notify();
     }
-    fn on_resize_tr(&mut self, _: &MouseDownEvent, window: &mut Window, _cx: &mut Context<Self>) {
-        window.start_window_resize(gpui::ResizeEdge::TopRight);
+
+    fn toggle_stats(&mut self, _: &OpenStats, _window: &mut Window, cx: &mut Context<Self>) {
+        if self.stats.take().is_none() {
+            let view = cx.new(StatsView::new);
+            cx.subscribe(&view, |this, _, _: &stats::Dismissed, cx| {
+                this.stats = None;
+                cx.notify();
+            })
+            .detach();
+            self.stats = Some(view);
+        }
+        cx.notify();
     }
-    fn on_resize_bl(&mut self, _: &MouseDownEvent, window: &mut Window, _cx: &mut Context<Self>) {
-        window.start_window_resize(gpui::ResizeEdge::BottomLeft);
+
+    fn toggle_settings(&mut self, _: &OpenSettings, _window: &mut Window, cx: &mut Context<Self>) {
+        if self.settings.take().is_none() {
+            let view = cx.new(SettingsView::new);
+            cx.subscribe(&view, |this, _, _: &settings::Dismissed, cx| {
+                this.settings = None;
+                cx.notify();
+            })
+            .detach();
+            self.settings = Some(view);
+        }
+        cx.notify();
     }
-    fn on_resize_br(&mut self, _: &MouseDownEvent, window: &mut Window, _cx: &mut Context<Self>) {
-        window.start_window_resize(gpui::ResizeEdge::BottomRight);
+
+    /// Write a handoff bundle for the selected host under the state dir and reveal it.
+    fn export_handoff(&mut self, _: &ExportHandoff, _window: &mut Window, cx: &mut Context<Self>) {
+        let Some(bundle) = self.host_info.read(cx).handoff_bundle() else {
+            self.host_info.update(cx, |panel, cx| {
+                panel.push_progress("select a host to export a handoff", cx)
+            });
+            return;
+        };
+        let dir = slarti_state_dir().join("handoff");
+        let path = dir.join(bundle.file_name());
+        let result = std::fs::create_dir_all(&dir).and_then(|_| bundle.save(&path));
+        self.host_info.update(cx, |panel, cx| match &result {
+            Ok(()) => panel.push_progress(format!("handoff exported to {}", path.display()), cx),
+            Err(e) => panel.push_progress(format!("handoff export failed: {}", e), cx),
+        });
+        if result.is_ok() {
+            cx.reveal_path(&path);
+        }
     }
-}
 
-impl Focusable for ContainerView {
-    fn focus_handle(&self, _: &App) -> FocusHandle {
-        self.focus.clone()
+    /// Ask for a handoff bundle file and import it into the host panel.
+    fn import_handoff(&mut self, _: &ImportHandoff, window: &mut Window, cx: &mut Context<Self>) {
+        let paths = cx.prompt_for_paths(gpui::PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: false,
+            prompt: Some("Import handoff".into()),
+        });
+        let host_info = self.host_info.clone();
+        window
+            .spawn(cx, async move |acx| {
+                let Ok(Ok(Some(paths))) = paths.await else {
+                    return;
+                };
+                let Some(path) = paths.into_iter().next() else {
+                    return;
+                };
+                let _ = acx.update(|window, cx| {
+                    host_info.update(cx, |panel, cx| match HandoffBundle::load(&path) {
+                        Ok(bundle) => panel.import_handoff(bundle, window, cx),
+                        Err(e) => {
+                            panel.push_progress(format!("handoff import failed: {:#}", e), cx)
+                        }
+                    });
+                });
+            })
+            .detach();
     }
-}
 
-impl gpui::Render for ContainerView {
-    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        let title_bar_bg = gpui::rgb(0x141414);
-        let chrome_border = gpui::opaque_grey(0.2, 0.7);
-        let text_color = gpui::hsla(self.ui_fg.0, self.ui_fg.1, self.ui_fg.2, self.ui_fg.3);
+    /// Ask for a CSV/YAML host list and a config file, then append a Host
+    /// entry for each listed alias the ssh config does not define yet.
+    fn import_hosts(&mut self, _: &ImportHosts, window: &mut Window, cx: &mut Context<Self>) {
+        let paths = cx.prompt_for_paths(gpui::PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: false,
+            prompt: Some("Import hosts (CSV or YAML)".into()),
+        });
+        let hosts = self.hosts.clone();
+        let host_info = self.host_info.clone();
+        window
+            .spawn(cx, async move |acx| {
+                let Ok(Ok(Some(paths))) = paths.await else {
+                    return;
+                };
+                let Some(source) = paths.into_iter().next() else {
+                    return;
+                };
+                let report = |msg: String, acx: &mut gpui::AsyncWindowContext| {
+                    let _ = acx.update(|_, cx| {
+                        host_info.update(cx, |panel, cx| panel.push_progress(msg, cx))
+                    });
+                };
+                let rows = match sshcfg::import::read_host_list(&source) {
+                    Ok(rows) => rows,
+                    Err(e) => return report(format!("host import failed: {:#}", e), acx),
+                };
+                let Some(ssh_dir) = dirs_next::home_dir().map(|h| h.join(".ssh")) else {
+                    return;
+                };
+                let dir = [ssh_dir.join("config.d"), ssh_dir.join("conf.d")]
+                    .into_iter()
+                    .find(|d| d.is_dir())
+                    .unwrap_or(ssh_dir);
+                let Ok(target) = acx.update(|_, cx| cx.prompt_for_new_path(&dir, Some("imported")))
+                else {
+                    return;
+                };
+                let Ok(Ok(Some(target))) = target.await else {
+                    return;
+                };
+                let imported = sshcfg::load::load_user_config_tree().and_then(|tree| {
+                    let plan = sshcfg::import::plan_import(&tree, rows, &target);
+                    plan.apply().map(|()| plan)
+                });
+                match imported {
+                    Ok(plan) => {
+                        slarti_ssh::config_changed();
+                        let _ = acx.update(|_, cx| {
+                            if let Err(e) = hosts.update(cx, |hosts, cx| hosts.reload(cx)) {
+                                tracing::warn!("hosts: reload after import: {}", e);
+                            }
+                        });
+                        report(plan.summary(), acx);
+                    }
+                    Err(e) => report(format!("host import failed: {:#}", e), acx),
+                }
+            })
+            .detach();
+    }
 
-        // Header: custom titlebar with drag-to-move and icon buttons
-        let header = div()
-            .flex()
-            .flex_row()
-            .items_center()
-            .justify_between()
-            .h(px(32.))
-            .px(px(8.))
-            .bg(title_bar_bg)
+    /// Open (or close) the global search over the ssh config and cached snapshots.
+    fn toggle_search(&mut self, _: &OpenSearch, window: &mut Window, cx: &mut Context<Self>) {
+        if self.search.take().is_none() {
+            let overlay = cx.new(SearchOverlay::new);
+            // Reading the config and every snapshot stays off the UI thread.
+            let snapshot_of = self.host_info.read(cx).snapshot_lookup();
+            let indexed = overlay.downgrade();
+            cx.spawn(async move |_, cx| {
+                let items = cx
+                    .background_executor()
+                    .spawn(async move {
+                        match sshcfg::load::load_user_config_tree() {
+                            Ok(tree) => search::build_index(&tree, snapshot_of),
+                            Err(e) => {
+                                tracing::warn!("search: ssh config: {:#}", e);
+                                Vec::new()
+                            }
+                        }
+                    })
+                    .await;
+                let _ = indexed.update(cx, |overlay, cx| overlay.set_items(items, cx));
+            })
+            .detach();
+            cx.subscribe(&overlay, |this, _, _: &search::Dismissed, cx| {
+                this.search = None;
+                cx.notify();
+            })
+            .detach();
+            cx.subscribe_in(
+                &overlay,
+                window,
+                |this, _, ev: &search::Selected, window, cx| {
+                    this.search = None;
+                    let alias = ev.0.clone();
+                    this.host_info
+                        .update(cx, |panel, cx| panel.select_host(alias, window, cx));
+                    cx.notify();
+                },
+            )
+            .detach();
+            window.focus(&overlay.focus_handle(cx));
+            self.search = Some(overlay);
+        }
+        cx.notify();
+    }
+
+    /// Open the selected host in the editor last used (else the first installed one).
+    fn open_in_editor(&mut self, _: &OpenInEditor, _window: &mut Window, cx: &mut Context<Self>) {
+        let ui = load_ui_settings();
+        let editors = editor::installed_editors(&ui.editors);
+        let chosen = editors
+            .iter()
+            .find(|e| ui.editor.as_deref() == Some(e.name.as_str()))
+            .or(editors.first())
+            .cloned();
+        match chosen {
+            Some(editor) => self.launch_editor(editor, cx),
+            None => self.host_info.update(cx, |panel, cx| {
+                panel.push_progress("no editor found (install VS Code, Cursor or Zed)", cx)
+            }),
+        }
+    }
+
+    /// Open the selected host's `SlartiFolder` in `editor` and remember the choice.
+    fn launch_editor(&mut self, editor: EditorCommand, cx: &mut Context<Self>) {
+        let Some(alias) = self.host_info.read(cx).selected_alias().map(str::to_string) else {
+            self.host_info.update(cx, |panel, cx| {
+                panel.push_progress("select a host to open in an editor", cx)
+            });
+            return;
+        };
+        let launched =
+            if alias == slarti_hosts::LOCAL_HOST || Container::from_alias(&alias).is_some() {
+                Err(anyhow::anyhow!("{} is not an ssh host", alias))
+            } else {
+                let folder = sshcfg::load::load_user_config_tree().ok().and_then(|tree| {
+                    sshcfg::load::host_entry_for_alias(&tree, &alias)
+                        .and_then(|entry| entry.get(editor::FOLDER_KEYWORD))
+                        .map(str::to_string)
+                });
+                editor.launch(&alias, folder.as_deref())
+            };
+        let msg = match launched {
+            Ok(()) => {
+                let mut ui = load_ui_settings();
+                ui.editor = Some(editor.name.clone());
+                save_ui_settings(ui);
+                format!("opening {} in {}", alias, editor.name)
+            }
+            Err(e) => format!("open in {} failed: {:#}", editor.name, e),
+        };
+        self.host_info
+            .update(cx, |panel, cx| panel.push_progress(msg, cx));
+    }
+
+    /// Open (or close) the guided rename, starting from the selected host.
+    fn toggle_rename(&mut self, _: &RenameHost, window: &mut Window, cx: &mut Context<Self>) {
+        if self.rename.take().is_none() {
+            let from = self.host_info.read(cx).selected_alias().map(str::to_string);
+            let overlay = cx.new(|cx| RenameOverlay::new(from, cx));
+            cx.subscribe(&overlay, |this, _, _: &rename::Dismissed, cx| {
+                this.rename = None;
+                cx.notify();
+            })
+            .detach();
+            cx.subscribe_in(
+                &overlay,
+                window,
+                |this, _, ev: &rename::Renamed, window, cx| {
+                    this.rename = None;
+                    slarti_ssh::config_changed();
+                    let reloaded = this.hosts.update(cx, |hosts, cx| hosts.reload(cx));
+                    let msg = match reloaded {
+                        Ok(()) => {
+                            format!("renamed {} to {} in {} file(s)", ev.from, ev.to, ev.files)
+                        }
+                        Err(e) => format!("renamed {} to {}; reload failed: {}", ev.from, ev.to, e),
+                    };
+                    let (from, to) = (ev.from.clone(), ev.to.clone());
+                    this.host_info.update(cx, |panel, cx| {
+                        if panel.selected_alias() == Some(from.as_str()) {
+                            panel.select_host(to, window, cx);
+                        }
+                        panel.push_progress(msg, cx);
+                    });
+                    cx.notify();
+                },
+            )
+            .detach();
+            window.focus(&overlay.focus_handle(cx));
+            self.rename = Some(overlay);
+        }
+        cx.notify();
+    }
+
+    /// Open (or close) the scp/rsync command builder for the selected host.
+    fn toggle_transfer(&mut self, _: &CopyFiles, window: &mut Window, cx: &mut Context<Self>) {
+        if self.transfer.take().is_none() {
+            let Some(alias) = self.host_info.read(cx).selected_alias().map(str::to_string) else {
+                self.host_info.update(cx, |panel, cx| {
+                    panel.push_progress("select a host to copy files to or from", cx)
+                });
+                return;
+            };
+            if alias == slarti_hosts::LOCAL_HOST || Container::from_alias(&alias).is_some() {
+                self.host_info.update(cx, |panel, cx| {
+                    panel.push_progress(format!("{} is not an ssh host", alias), cx)
+                });
+                return;
+            }
+            let overlay = cx.new(|cx| TransferOverlay::new(alias, cx));
+            cx.subscribe(&overlay, |this, _, _: &transfer::Dismissed, cx| {
+                this.transfer = None;
+                cx.notify();
+            })
+            .detach();
+            cx.subscribe(&overlay, |this, _, ev: &transfer::Finished, cx| {
+                this.transfer = None;
+                if ev.run {
+                    this.run_in_terminal(&ev.command, cx);
+                } else {
+                    cx.write_to_clipboard(gpui::ClipboardItem::new_string(ev.command.clone()));
+                    this.host_info.update(cx, |panel, cx| {
+                        panel.push_progress("copy command copied to the clipboard", cx)
+                    });
+                }
+                cx.notify();
+            })
+            .detach();
+            window.focus(&overlay.focus_handle(cx));
+            self.transfer = Some(overlay);
+        }
+        cx.notify();
+    }
+
+    /// Whether keystrokes belong to an in-app text field rather than the terminal.
+    fn keys_captured(&self, window: &Window, cx: &App) -> bool {
+        self.askpass_focus.is_focused(window)
+            || self
+                .log_viewer
+                .as_ref()
+                .is_some_and(|v| v.focus_handle(cx).contains_focused(window, cx))
+            || self
+                .search
+                .as_ref()
+                .is_some_and(|s| s.focus_handle(cx).contains_focused(window, cx))
+            || self
+                .rename
+                .as_ref()
+                .is_some_and(|r| r.focus_handle(cx).contains_focused(window, cx))
+            || self
+                .transfer
+                .as_ref()
+                .is_some_and(|t| t.focus_handle(cx).contains_focused(window, cx))
+    }
+
+    fn on_focus_click(&mut self, _: &MouseUpEvent, window: &mut Window, cx: &mut Context<Self>) {
+        // Keep focus on the clicked control (so its focus ring stays visible).
+        if !self.focus.contains_focused(window, cx) {
+            window.focus(&self.focus_handle(cx));
+        }
+    }
+
+    // Edge resize handlers (Wayland compat)
+    fn on_resize_left(&mut self, _: &MouseDownEvent, window: &mut Window, _cx: &mut Context<Self>) {
+        window.start_window_resize(gpui::ResizeEdge::Left);
+    }
+    fn on_resize_right(
+        &mut self,
+        _: &MouseDownEvent,
+        window: &mut Window,
+        _cx: &mut Context<Self>,
+    ) {
+        window.start_window_resize(gpui::ResizeEdge::Right);
+    }
+    fn on_resize_top(&mut self, _: &MouseDownEvent, window: &mut Window, _cx: &mut Context<Self>) {
+        window.start_window_resize(gpui::ResizeEdge::Top);
+    }
+    fn on_resize_bottom(
+        &mut self,
+        _: &MouseDownEvent,
+        window: &mut Window,
+        _cx: &mut Context<Self>,
+    ) {
+        window.start_window_resize(gpui::ResizeEdge::Bottom);
+    }
+    fn on_resize_tl(&mut self, _: &MouseDownEvent, window: &mut Window, _cx: &mut Context<Self>) {
+        window.start_window_resize(gpui::ResizeEdge::TopLeft);
+    }
+    fn on_resize_tr(&mut self, _: &MouseDownEvent, window: &mut Window, _cx: &mut Context<Self>) {
+        window.start_window_resize(gpui::ResizeEdge::TopRight);
+    }
+    fn on_resize_bl(&mut self, _: &MouseDownEvent, window: &mut Window, _cx: &mut Context<Self>) {
+        window.start_window_resize(gpui::ResizeEdge::BottomLeft);
+    }
+    fn on_resize_br(&mut self, _: &MouseDownEvent, window: &mut Window, _cx: &mut Context<Self>) {
+        window.start_window_resize(gpui::ResizeEdge::BottomRight);
+    }
+}
+
+impl Focusable for ContainerView {
+    fn focus_handle(&self, _: &App) -> FocusHandle {
+        self.focus.clone()
+    }
+}
+
+/// Embed a panel as a cached view: it fills its slot and only re-renders when it notifies.
+fn cached_panel<V: gpui::Render>(panel: &gpui::Entity<V>) -> gpui::AnyView {
+    gpui::AnyView::from(panel.clone()).cached(gpui::StyleRefinement::default().size_full())
+}
+
+impl gpui::Render for ContainerView {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        // App-wide appearance: text scales with the rem size, chrome sizes via `ap.px`.
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        window.set_rem_size(ap.rem_size());
+        let title_bar_bg = pal.chrome_bg;
+        let chrome_border = pal.border;
+        let text_color = if ap.high_contrast {
+            pal.fg
+        } else {
+            gpui::hsla(self.ui_fg.0, self.ui_fg.1, self.ui_fg.2, self.ui_fg.3)
+        };
+
+        // Header: custom titlebar with drag-to-move and icon buttons
+        let header = div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .justify_between()
+            .h(ap.px(32.))
+            .px(ap.px(8.))
+            .bg(title_bar_bg)
             .border_b_1()
             .border_color(chrome_border)
-            // Left: app/menu placeholder
+            // Left: app/menu placeholder and high-contrast toggle (also ctrl-alt-h)
             .child(
                 div()
-                    .w(px(28.))
-                    .h(px(18.))
-                    .rounded_sm()
-                    .border_1()
-                    .border_color(chrome_border)
-                    .cursor_default()
-                    .child("≡"),
+                    .flex()
+                    .items_center()
+                    .gap(ap.px(6.))
+                    .child(
+                        div()
+                            .w(ap.px(28.))
+                            .h(ap.px(18.))
+                            .rounded_sm()
+                            .border_1()
+                            .border_color(if self.menu_open {
+                                pal.accent
+                            } else {
+                                chrome_border
+                            })
+                            .cursor_pointer()
+                            .on_mouse_up(
+                                MouseButton::Left,
+                                cx.listener(|this: &mut Self, _ev, _window, cx| {
+                                    cx.stop_propagation();
+                                    this.menu_open = !this.menu_open;
+                                    cx.notify();
+                                }),
+                            )
+                            .child("≡"),
+                    )
+                    .child(
+                        div()
+                            .w(ap.px(22.))
+                            .h(ap.px(18.))
+                            .flex()
+                            .items_center()
+                            .justify_center()
+                            .rounded_sm()
+                            .border_1()
+                            .border_color(if ap.high_contrast {
+                                pal.accent
+                            } else {
+                                chrome_border
+                            })
+                            .text_color(text_color)
+                            .cursor_pointer()
+                            .on_mouse_up(
+                                MouseButton::Left,
+                                cx.listener(|_this: &mut Self, _ev, window, cx| {
+                                    cx.stop_propagation();
+                                    window.dispatch_action(Box::new(ToggleHighContrast), cx);
+                                }),
+                            )
+                            .child("◐"),
+                    ),
             )
             // Center: draggable region
             .child(
@@ -409,20 +1716,20 @@ impl gpui::Render for ContainerView {
                     .gap_3()
                     .child(
                         div()
-                            .size(px(14.0))
+                            .size(ap.px(14.0))
                             .window_control_area(gpui::WindowControlArea::Min)
                             .cursor_pointer()
                             .on_mouse_up(MouseButton::Left, cx.listener(Self::on_minimize))
                             .child(
                                 UiVector::new("assets/generic_minimize.svg")
-                                    .square(px(14.0))
+                                    .square(ap.px(14.0))
                                     .color(text_color)
                                     .render(),
                             ),
                     )
                     .child(
                         div()
-                            .size(px(14.0))
+                            .size(ap.px(14.0))
                             .window_control_area(gpui::WindowControlArea::Max)
                             .cursor_pointer()
                             .on_mouse_up(MouseButton::Left, cx.listener(Self::on_maximize))
@@ -432,20 +1739,20 @@ impl gpui::Render for ContainerView {
                                 } else {
                                     "assets/generic_maximize.svg"
                                 })
-                                .square(px(14.0))
+                                .square(ap.px(14.0))
                                 .color(text_color)
                                 .render(),
                             ),
                     )
                     .child(
                         div()
-                            .size(px(14.0))
+                            .size(ap.px(14.0))
                             .window_control_area(gpui::WindowControlArea::Close)
                             .cursor_pointer()
                             .on_mouse_up(MouseButton::Left, cx.listener(Self::on_close))
                             .child(
                                 UiVector::new("assets/generic_close.svg")
-                                    .square(px(14.0))
+                                    .square(ap.px(14.0))
                                     .color(text_color)
                                     .render(),
                             ),
@@ -454,17 +1761,17 @@ impl gpui::Render for ContainerView {
 
         // Content: two columns - hosts (left), terminal (right).
         let content = {
-            let bg = gpui::rgb(0x0b0b0b);
+            let bg = pal.bg;
 
             // Left: hosts tree sidebar
             let sidebar = div()
                 .flex()
                 .flex_col()
-                .w(px(260.0))
+                .w(ap.px(260.0))
                 .border_r_1()
                 .border_color(chrome_border)
                 .bg(bg)
-                .child(self.hosts.clone());
+                .child(cached_panel(&self.hosts));
 
             // Right: terminal panel fills remaining space
             let right_inner = div()
@@ -487,20 +1794,12 @@ impl gpui::Render for ContainerView {
                         .when(self.terminal_collapsed, |d| d.size_full())
                         .border_b_1()
                         .border_color(chrome_border)
-                        // Simple remote status header above the Host panel
-                        .child(
-                            div()
-                                .h(px(24.0))
-                                .px(px(8.0))
-                                .text_color(gpui::opaque_grey(1.0, 0.85))
-                                .child("Remote: unknown"),
-                        )
-                        .child(self.host_info.clone()),
+                        .child(cached_panel(&self.host_info)),
                 )
                 // Draggable split handle between top and bottom
                 .child(
                     div()
-                        .h(px(if self.terminal_collapsed { 0.0 } else { 6.0 }))
+                        .h(ap.px(if self.terminal_collapsed { 0.0 } else { 6.0 }))
                         .cursor_ns_resize()
                         .on_mouse_down(MouseButton::Left, cx.listener(Self::on_split_mouse_down))
                         .on_mouse_up(MouseButton::Left, cx.listener(Self::on_split_mouse_up))
@@ -536,7 +1835,9 @@ impl gpui::Render for ContainerView {
                         .flex()
                         .flex_col()
                         .size_full()
-                        .when(!self.terminal_collapsed, |d| d.child(self.terminal.clone())),
+                        .when(!self.terminal_collapsed, |d| {
+                            d.child(cached_panel(&self.terminal))
+                        }),
                 );
 
             let right = div()
@@ -554,26 +1855,57 @@ impl gpui::Render for ContainerView {
                 .child(right)
         };
 
-        // Footer: terminal toggle button uses icon instead of text.
+        // Footer: live connection status (left) and terminal toggle icon (right).
         let footer = {
+            let (status_color, status_text) = {
+                let conn = self.connection.read(cx);
+                (conn.color(), conn.summary())
+            };
+            // State files that failed to load were moved aside; say so instead of silently resetting.
+            let state_problem = {
+                let problems = slarti_state::problems();
+                problems.first().map(|(path, _)| {
+                    let name = path
+                        .file_name()
+                        .map(|n| n.to_string_lossy().into_owned())
+                        .unwrap_or_default();
+                    match problems.len() {
+                        1 => format!("⚠ could not load {} (see log)", name),
+                        n => format!("⚠ could not load {} and {} more (see log)", name, n - 1),
+                    }
+                })
+            };
             div()
                 .flex()
                 .flex_row()
-                .justify_end()
+                .items_center()
+                .justify_between()
                 .gap_2()
-                .h(px(32.))
-                .px(px(8.))
+                .h(ap.px(32.))
+                .px(ap.px(8.))
                 .bg(title_bar_bg)
                 .border_t_1()
                 .border_color(chrome_border)
                 .child(
                     div()
-                        .size(px(16.0))
+                        .flex()
+                        .items_center()
+                        .gap_2()
+                        .text_color(pal.fg_dim)
+                        .child(div().size(ap.px(8.0)).rounded_full().bg(status_color))
+                        .child(status_text)
+                        .when_some(state_problem, |d, problem| {
+                            d.child(div().text_color(gpui::yellow()).child(problem))
+                        }),
+                )
+                .child(
+                    div()
+                        .size(ap.px(16.0))
                         .cursor_pointer()
                         .on_mouse_up(MouseButton::Left, cx.listener(Self::on_toggle_terminal))
                         .child(
                             UiVector::new("assets/terminal.svg")
-                                .square(px(16.0))
+                                .square(ap.px(16.0))
                                 .color(if !self.terminal_collapsed {
                                     gpui::Hsla::from(gpui::rgba(0x74ace6ff))
                                 } else {
@@ -679,21 +2011,124 @@ impl gpui::Render for ContainerView {
             .child(content)
             .child(resize_overlay)
             .child(footer)
+            .children(self.log_viewer.clone().map(|viewer| {
+                div()
+                    .absolute()
+                    .top(ap.px(40.0))
+                    .left(ap.px(16.0))
+                    .right(ap.px(16.0))
+                    .bottom(ap.px(48.0))
+                    .child(viewer)
+            }))
+            .children(self.stats.clone().map(|stats| {
+                div()
+                    .absolute()
+                    .top(ap.px(40.0))
+                    .left(ap.px(16.0))
+                    .right(ap.px(16.0))
+                    .bottom(ap.px(48.0))
+                    .child(stats)
+            }))
+            .children(self.settings.clone().map(|settings| {
+                div()
+                    .absolute()
+                    .top(ap.px(40.0))
+                    .left(ap.px(120.0))
+                    .right(ap.px(120.0))
+                    .child(settings)
+            }))
+            .children(self.search.clone().map(|search| {
+                div()
+                    .absolute()
+                    .top(ap.px(40.0))
+                    .left(ap.px(120.0))
+                    .right(ap.px(120.0))
+                    .child(search)
+            }))
+            .children(self.rename.clone().map(|rename| {
+                div()
+                    .absolute()
+                    .top(ap.px(40.0))
+                    .left(ap.px(120.0))
+                    .right(ap.px(120.0))
+                    .child(rename)
+            }))
+            .children(self.transfer.clone().map(|transfer| {
+                div()
+                    .absolute()
+                    .top(ap.px(40.0))
+                    .left(ap.px(120.0))
+                    .right(ap.px(120.0))
+                    .child(transfer)
+            }))
+            .children(self.render_menu(cx))
+            .children(self.debug_overlay.clone())
+            .children(self.render_askpass(cx))
+            .on_action(cx.listener(Self::toggle_debug_overlay))
+            .on_action(cx.listener(Self::toggle_diagnostics))
+            .on_action(cx.listener(Self::toggle_stats))
+            .on_action(cx.listener(Self::toggle_settings))
+            .on_action(cx.listener(Self::export_handoff))
+            .on_action(cx.listener(Self::import_handoff))
+            .on_action(cx.listener(Self::toggle_search))
+            .on_action(cx.listener(Self::toggle_rename))
+            .on_action(cx.listener(Self::import_hosts))
+            .on_action(cx.listener(Self::open_in_editor))
+            .on_action(cx.listener(Self::toggle_transfer))
             .on_mouse_up(MouseButton::Left, cx.listener(Self::on_focus_click))
     }
 }
 
 fn main() {
-    // Initialize logging via tracing-subscriber to respect RUST_LOG
-    {
-        // Avoid initializing multiple times in tests or hot-reload scenarios.
-        static INIT: std::sync::Once = std::sync::Once::new();
-        INIT.call_once(|| {
-            let _ = tracing_subscriber::fmt()
-                .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-                .try_init();
+    // ssh runs this executable as SSH_ASKPASS; forward the prompt to the running app.
+    if slarti_ssh::askpass::is_helper() {
+        std::process::exit(slarti_ssh::askpass::run_helper());
+    }
+
+    // Log to stderr (RUST_LOG) and a rotated file under the state dir; record panics.
+    logging::init();
+
+    // `slarti encrypt-state [--keychain]`: enable state encryption and convert existing files.
+    if std::env::args().nth(1).as_deref() == Some("encrypt-state") {
+        let keychain = std::env::args().any(|a| a == "--keychain");
+        // Hold the instance lock while rewriting state, as a running app would.
+        if let Err(e) = instance::hold() {
+            eprintln!("encrypt-state: {}", e);
+            std::process::exit(1);
+        }
+        std::process::exit(match encrypt_state(keychain) {
+            Ok(n) => {
+                println!("state encryption enabled ({} files converted)", n);
+                0
+            }
+            Err(e) => {
+                eprintln!("encrypt-state: {:#}", e);
+                1
+            }
         });
     }
+    // Single instance: hand our arguments to a running slarti and exit.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let instance_rx = match instance::claim(&args) {
+        instance::Claim::Primary(rx) => Some(rx),
+        instance::Claim::Forwarded => std::process::exit(0),
+        instance::Claim::Busy(e) => {
+            eprintln!("slarti is already running but did not respond: {}", e);
+            std::process::exit(1);
+        }
+        instance::Claim::Unavailable(e) => {
+            tracing::warn!("single-instance lock unavailable: {}", e);
+            None
+        }
+    };
+
+    match slarti_state::init(&slarti_state_dir()) {
+        slarti_state::Status::Locked(reason) => tracing::warn!(
+            "state is encrypted and locked ({}); saved state will not be loaded or updated",
+            reason
+        ),
+        status => tracing::debug!("state store: {:?}", status),
+    }
 
     Application::new()
         .with_assets(
@@ -701,9 +2136,54 @@ fn main() {
                 std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../assets"),
             ),
         )
-        .run(|cx: &mut App| {
+        .run(move |cx: &mut App| {
             // Load last UI settings to restore window bounds if available
             let ui = load_ui_settings();
+            cx.set_global(Appearance::new(ui.ui_scale, ui.high_contrast));
+            cx.set_global(PreviewActions(ui.preview_actions));
+            stats::set_enabled(ui.usage_stats);
+            cx.set_global(load_action_policy());
+            cx.bind_keys([
+                gpui::KeyBinding::new("ctrl-=", ZoomIn, None),
+                gpui::KeyBinding::new("ctrl-+", ZoomIn, None),
+                gpui::KeyBinding::new("ctrl--", ZoomOut, None),
+                gpui::KeyBinding::new("ctrl-0", ResetZoom, None),
+                gpui::KeyBinding::new("ctrl-alt-h", ToggleHighContrast, None),
+                gpui::KeyBinding::new("ctrl-alt-d", ToggleDebugOverlay, None),
+                gpui::KeyBinding::new("ctrl-alt-l", ToggleDiagnostics, None),
+                gpui::KeyBinding::new("ctrl-alt-f", OpenSearch, None),
+            ]);
+            cx.on_action(|_: &ZoomIn, cx: &mut App| {
+                update_appearance(cx, |ap| ap.scale += slarti_ui::theme::SCALE_STEP)
+            });
+            cx.on_action(|_: &ZoomOut, cx: &mut App| {
+                update_appearance(cx, |ap| ap.scale -= slarti_ui::theme::SCALE_STEP)
+            });
+            cx.on_action(|_: &ResetZoom, cx: &mut App| update_appearance(cx, |ap| ap.scale = 1.0));
+            cx.on_action(|_: &OpenLogFolder, cx: &mut App| {
+                let dir = logging::log_dir();
+                let _ = std::fs::create_dir_all(&dir);
+                cx.open_with_system(&dir);
+            });
+            cx.on_action(|_: &ToggleHighContrast, cx: &mut App| {
+                update_appearance(cx, |ap| ap.high_contrast = !ap.high_contrast)
+            });
+            cx.on_action(|_: &TogglePreviewActions, cx: &mut App| {
+                let on = !PreviewActions::get(cx);
+                cx.set_global(PreviewActions(on));
+                let mut ui = load_ui_settings();
+                ui.preview_actions = on;
+                save_ui_settings(ui);
+                cx.refresh_windows();
+            });
+            cx.on_action(|_: &ToggleUsageStats, cx: &mut App| {
+                let on = !stats::enabled();
+                stats::set_enabled(on);
+                let mut ui = load_ui_settings();
+                ui.usage_stats = on;
+                save_ui_settings(ui);
+                cx.refresh_windows();
+            });
             let default_bounds = Bounds::centered(None, size(px(1000.0), px(700.0)), cx);
             let restored_bounds = ui.last_window_bounds.as_ref().map(|(x, y, w, h)| Bounds {
                 origin: gpui::point(px(*x as f32), px(*y as f32)),
@@ -717,7 +2197,7 @@ fn main() {
                         window_bounds: Some(WindowBounds::Windowed(open_bounds)),
                         ..Default::default()
                     },
-                    |_, cx| {
+                    |window, cx| {
                         // Build the terminal panel from slarti-term.
                         let term_cfg = TerminalConfig::default();
                         let ui_fg = term_cfg.theme.fg;
@@ -750,42 +2230,51 @@ fn main() {
                                         let host_handle2 = host_handle.clone();
                                         let current_alias_sel2 = current_alias_sel.clone();
                                         window.spawn(cxp, async move |acx| {
+                                            let _task = slarti_ui::diagnostics::TaskGuard::new();
                                             tracing::debug!(target: "slarti_ssh", "deploy: starting background task");
                                             let _ = tokio::runtime::Builder::new_current_thread()
                                                 .enable_all()
                                                 .build()
                                                 .map(|rt| {
-                                                    rt.block_on(async {
+                                                    // A deploy is user-initiated: ahead of background ssh work.
+                                                    rt.block_on(slarti_ssh::queue::interactive(async {
                                                         // Determine target alias
                                                         let target = current_alias_sel2
                                                             .lock()
                                                             .ok()
                                                             .and_then(|g| g.clone());
                                                         if let Some(target) = target {
+                                                            // The local agent runs from the build in place; nothing to upload.
+                                                            if target == slarti_hosts::LOCAL_HOST {
+                                                                let _ = acx.update(|_w, cxu| {
+                                                                    let _ = host_handle2.update(cxu, |panel, cxu| {
+                                                                        panel.set_status("local agent needs no deploy", cxu);
+                                                                        panel.clear_progress(cxu);
+                                                                        panel.set_deploy_running(false, cxu);
+                                                                    });
+                                                                });
+                                                                return;
+                                                            }
                                                             let version = env!("CARGO_PKG_VERSION").to_string();
                                                             let timeout = Duration::from_secs(10);
+                                                            // Containers and pods are reached via docker/kubectl exec, not ssh.
+                                                            let in_container = Container::from_alias(&target);
 
+                                                            // Configured install root (ssh config entry or settings), if any.
+                                                            let agent_root = sshcfg::load::load_user_config_tree()
+                                                                .ok()
+                                                                .and_then(|tree| agent_root_for(&tree, &target));
                                                             // Decide remote install path based on remote user.
-                                                            let is_root = remote_user_is_root(&target, timeout)
-                                                                .await
-                                                                .unwrap_or(false);
-                                                            let remote_dir = if is_root {
-                                                                format!("/usr/local/lib/slarti/agent/{}", version)
+                                                            let remote_path = if in_container.is_some() {
+                                                                container_agent_path(&version)
                                                             } else {
-                                                                format!("$HOME/.local/share/slarti/agent/{}", version)
+                                                                let is_root = remote_user_is_root(&target, timeout)
+                                                                    .await
+                                                                    .unwrap_or(false);
+                                                                slarti_ssh::agent_path(is_root, &version, agent_root.as_deref())
                                                             };
-                                                            let remote_path = format!("{remote_dir}/slarti-remote");
-
-                                                            // Resolve local artifact (prefer release, fallback to debug).
-                                                            let mut artifact = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-                                                            artifact.push("../../target/release/slarti-remote");
-                                                            if !artifact.exists() {
-                                                                let mut dbg = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-                                                                dbg.push("../../target/debug/slarti-remote");
-                                                                artifact = dbg;
-                                                            }
 
-                                                            if !artifact.exists() {
+                                                            let Some(artifact) = local_agent_binary() else {
                                                                 let _ = acx.update(|_w, cxu| {
                                                                     let _ = host_handle2.update(cxu, |panel, cxu| {
                                                                         panel.set_status("deploy failed: local agent binary not found", cxu);
@@ -794,7 +2283,7 @@ fn main() {
                                                                     });
                                                                 });
                                                                 return;
-                                                            }
+                                                            };
 
                                                             // Upload/install
                                                             let _ = acx.update(|_w, cxu| {
@@ -803,8 +2292,29 @@ fn main() {
                                                                 });
                                                             });
 
-                                                            match deploy_agent(&target, &artifact, &version, timeout).await {
+                                                            let deployed = match &in_container {
+                                                                Some(c) => deploy_container_agent(c, &artifact, &version, timeout).await,
+                                                                None => {
+                                                                    deploy_agent(&target, &artifact, &version, agent_root.as_deref(), timeout)
+                                                                        .await
+                                                                }
+                                                            };
+                                                            stats::record_deploy(&target, deployed.is_ok());
+                                                            match deployed {
                                                                 Ok(_res) => {
+                                                                    // Older versions accumulate with every deploy: keep the newest few.
+                                                                    if in_container.is_none() {
+                                                                        let marked = mark_read_only_on(&target).await;
+                                                                        let cleaned = clean_old_agents_on(&target).await;
+                                                                        let _ = acx.update(|_w, cxu| {
+                                                                            let _ = host_handle2.update(cxu, |panel, cxu| {
+                                                                                if let Some(marked) = marked {
+                                                                                    panel.push_progress(marked, cxu);
+                                                                                }
+                                                                                panel.push_progress(cleaned, cxu);
+                                                                            });
+                                                                        });
+                                                                    }
                                                                     // Verify agent
                                                                     let _ = acx.update(|_w, cxu| {
                                                                         let _ = host_handle2.update(cxu, |panel, cxu| {
@@ -812,10 +2322,18 @@ fn main() {
                                                                         });
                                                                     });
 
-                                                                    match check_agent(&target, &remote_path, timeout).await {
+                                                                    let checked = match &in_container {
+                                                                        Some(c) => check_container_agent(c, &remote_path, timeout).await,
+                                                                        None => check_agent(&target, &remote_path, timeout).await,
+                                                                    };
+                                                                    match checked {
                                                                         Ok(status) if status.present && status.can_run => {
                                                                             // Handshake
-                                                                            if let Ok(mut client) = run_agent(&target, &remote_path).await {
+                                                                            let spawned = match &in_container {
+                                                                                Some(c) => run_container_agent(c, &remote_path).await,
+                                                                                None => run_agent(&target, &remote_path).await,
+                                                                            };
+                                                                            if let Ok(mut client) = spawned {
                                                                                 if let Ok(hello) = client.hello(env!("CARGO_PKG_VERSION"), Some(timeout)).await {
                                                                                     let _ = acx.update(|_w, cxu| {
                                                                                         let _ = host_handle2.update(cxu, |panel, cxu| {
@@ -883,7 +2401,7 @@ fn main() {
                                                                 });
                                                             });
                                                         }
-                                                    })
+                                                    }))
                                                 });
                                         }).detach();
                                     })
@@ -892,23 +2410,51 @@ fn main() {
                             });
                         }
 
+                        // Connection state for the selected host (observed by the footer status).
+                        let connection = cx.new(|_| ConnectionManager::new());
+                        let connection_sel = connection.clone();
+
                         // Build the hosts panel from parsed SSH config.
                         let host_info_handle = host_info.clone();
                         let host_info_handle_for_recent = host_info_handle.clone();
                         let current_alias_sel = current_alias.clone();
 
                         // Load SSH config once and reuse for both tree rendering and selection path.
-                        let cfg_tree = sshcfg::load::load_user_config_tree().unwrap_or_else(|_| {
+                        let loaded_cfg = sshcfg::load::load_user_config_tree();
+                        // Start-screen fleet summary and recents follow the hosts in the config
+                        // (left alone if it failed to load).
+                        let mut known_aliases = None;
+                        if let Ok(tree) = &loaded_cfg {
+                            let mut aliases = sshcfg::load::list_aliases(tree);
+                            if !aliases.iter().any(|a| a == slarti_hosts::LOCAL_HOST) {
+                                aliases.insert(0, slarti_hosts::LOCAL_HOST.to_string());
+                            }
+                            known_aliases = Some(aliases.clone());
+                            // Recent containers are kept until discovery (below) reports
+                            // which of them are still running.
+                            let mut with_recent = aliases;
+                            with_recent.extend(
+                                host_info
+                                    .read(cx)
+                                    .recent_aliases()
+                                    .into_iter()
+                                    .filter(|a| Container::from_alias(a).is_some()),
+                            );
+                            host_info.update(cx, |panel, cx| panel.set_known_hosts(with_recent, cx));
+                        }
+                        let cfg_tree = loaded_cfg.unwrap_or_else(|_| {
                             sshcfg::model::ConfigTree {
                                 root: sshcfg::model::FileNode {
                                     path: std::path::PathBuf::from("~/.ssh/config"),
                                     hosts: vec![],
                                     includes: vec![],
                                     matches: vec![],
+                                    included_at: 0,
                                 },
                             }
                         });
                         let cfg_tree_for_select = cfg_tree.clone();
+                        let cfg_tree_for_console = cfg_tree.clone();
 
                         let on_select = Arc::new(
                             move |alias: String,
@@ -923,26 +2469,85 @@ fn main() {
                                     panel.clear_progress(cx);
                                     panel.push_progress("probing agent…", cx);
                                 });
+                                let tags = host_tags_for(&cfg_tree_for_select, &alias);
+                                let _ = host_info_handle.update(hosts_cx, |panel, cx| {
+                                    panel.set_host_tags(tags, cx);
+                                });
+                                // Serial hosts (SlartiSerial in their ssh config entry) have no agent.
+                                let serial = serial_console_for(&cfg_tree_for_select, &alias);
+                                let transport = if alias == slarti_hosts::LOCAL_HOST {
+                                    "local"
+                                } else if let Some(c) = Container::from_alias(&alias) {
+                                    c.runtime()
+                                } else if serial.is_some() {
+                                    "serial"
+                                } else {
+                                    "ssh"
+                                };
+                                let _ = connection_sel.update(hosts_cx, |conn, cx| {
+                                    conn.set_alias(Some(alias.clone()), cx);
+                                    conn.set_transport(transport, cx);
+                                });
                                 // Track the most recent alias for actions like Deploy
                                 if let Ok(mut g) = current_alias_sel.lock() {
                                     *g = Some(alias.clone());
                                 }
 
+                                let epoch = SELECTION_EPOCH.fetch_add(1, Ordering::SeqCst) + 1;
+
+                                // Nothing to probe on a serial line; offer the console instead.
+                                if let Some(serial) = serial {
+                                    let _ = host_info_handle.update(hosts_cx, |panel, cx| {
+                                        panel.set_checking(false, cx);
+                                        panel.clear_progress(cx);
+                                        match &serial {
+                                            Ok(line) => {
+                                                panel.set_status("serial console", cx);
+                                                panel.set_serial_console(Some(line.describe()), cx);
+                                            }
+                                            Err(e) => panel.set_status(format!("error: {}", e), cx),
+                                        }
+                                    });
+                                    let _ = connection_sel.update(hosts_cx, |conn, cx| {
+                                        conn.set_status(RemoteAgentStatus::Unknown, cx);
+                                    });
+                                    return;
+                                }
+
                                 // Spawn an async task to check agent presence/version and persist state.
                                 let target = alias.clone();
                                 let version = env!("CARGO_PKG_VERSION").to_string();
                                 let host_handle = host_info_handle.clone();
+                                let conn_handle = connection_sel.clone();
                                 // Compute effective user locally from SSH config to avoid moving cfg_tree_for_select into the async closure,
                                 // keeping this on_select closure Fn rather than FnOnce.
                                 let user_is_root =
                                     sshcfg::load::effective_user_for_alias(&cfg_tree_for_select, &target)
                                         .as_deref()
                                         == Some("root");
+                                let agent_root = agent_root_for(&cfg_tree_for_select, &target);
+                                let agent_flags = agent_flags_for(&cfg_tree_for_select, &target);
                                 window
                                     .spawn(hosts_cx, async move |acx| {
+                                        // Probe only once the selection settles; clicking through hosts
+                                        // just shows their cached data.
+                                        acx.background_executor().timer(SELECT_DEBOUNCE).await;
+                                        if SELECTION_EPOCH.load(Ordering::SeqCst) != epoch {
+                                            return;
+                                        }
+                                        let _task = slarti_ui::diagnostics::TaskGuard::new();
                                         // Run SSH/process IO on the global background runtime.
                                         let mut sys_summary: Option<String> = None;
-                                        bg_rt().block_on(async {
+                                        // Session kept open after a successful handshake for periodic refresh.
+                                        let mut live_client: Option<slarti_ssh::AgentClient> = None;
+                                        // Services as last received and the agent's token for them; refreshes ask only for changes.
+                                        let mut services: Vec<slarti_proto::ServiceInfo> = Vec::new();
+                                        let mut services_token: Option<u64> = None;
+                                        // Likewise for the open ports.
+                                        let mut listeners: Vec<slarti_proto::NetListener> = Vec::new();
+                                        let mut listeners_token: Option<u64> = None;
+                                        // The user picked this host: ahead of background ssh work.
+                                        bg_rt().block_on(slarti_ssh::queue::interactive(async {
                                             // NOTE: rsync/scp deployment will respect your SSH config (including ProxyJump)
                                             // because we invoke the system ssh/rsync binaries and inherit environment.
                                             // Increase SSH operation timeout for slower or multi-hop (ProxyJump) connections.
@@ -966,13 +2571,20 @@ fn main() {
 
                                             // Choose remote install path from SSH config (avoid SSH roundtrip).
                                             // If the configured User is "root" for this alias, use the system path; otherwise use user-level path.
-                                            // user_is_root computed before spawn to avoid moving cfg_tree_for_select into this closure.
-                                                    let remote_dir = if user_is_root {
-                                                        format!("/usr/local/lib/slarti/agent/{}", version)
+                                            // user_is_root and agent_root computed before spawn to avoid moving cfg_tree_for_select into this closure.
+                                                    // The built-in local host runs the agent binary directly, without ssh.
+                                                    let local = target == slarti_hosts::LOCAL_HOST;
+                                                    // Containers and pods run it via docker/kubectl exec.
+                                                    let in_container = Container::from_alias(&target);
+                                                    let remote_path = if local {
+                                                        local_agent_binary()
+                                                            .map(|p| p.display().to_string())
+                                                            .unwrap_or_else(|| "slarti-remote".to_string())
+                                                    } else if in_container.is_some() {
+                                                        container_agent_path(&version)
                                                     } else {
-                                                        format!("$HOME/.local/share/slarti/agent/{}", version)
+                                                        slarti_ssh::agent_path(user_is_root, &version, agent_root.as_deref())
                                                     };
-                                                    let remote_path = format!("{}/slarti-remote", remote_dir);
 
                                                     // Initialize a state record for this host.
                                                     let mut state = AgentDeploymentState {
@@ -986,6 +2598,7 @@ fn main() {
                                                         ),
                                                         remote_checksum: None,
                                                         last_seen_ok: false,
+                                                        install_root: agent_root.clone(),
                                                     };
 
                                                     // Check agent presence/version, then attempt a Hello handshake.
@@ -996,21 +2609,26 @@ fn main() {
                                                         timeout,
                                                         remote_path
                                                     );
-                                                    match check_agent(
-                                                        &target,
-                                                        &remote_path,
-                                                        timeout,
-                                                    )
-                                                    .await
-                                                    {
+                                                    let checked = if local {
+                                                        check_local_agent(std::path::Path::new(&remote_path), timeout).await
+                                                    } else if let Some(c) = &in_container {
+                                                        check_container_agent(c, &remote_path, timeout).await
+                                                    } else {
+                                                        check_agent(&target, &remote_path, timeout).await
+                                                    };
+                                                    match checked {
                                                         Ok(status)
                                                             if status.present && status.can_run =>
                                                         {
                                                             // Try to connect and perform Hello/HelloAck.
-                                                            if let Ok(mut client) =
-                                                                run_agent(&target, &remote_path)
-                                                                    .await
-                                                            {
+                                                            let spawned = if local {
+                                                                run_local_agent(std::path::Path::new(&remote_path)).await
+                                                            } else if let Some(c) = &in_container {
+                                                                run_container_agent(c, &remote_path).await
+                                                            } else {
+                                                                run_agent_with_flags(&target, &remote_path, &agent_flags).await
+                                                            };
+                                                            if let Ok(mut client) = spawned {
                                                                 if let Ok(hello) = client
                                                                     .hello(
                                                                         env!("CARGO_PKG_VERSION"),
@@ -1025,87 +2643,163 @@ fn main() {
                                                                                 .clone(),
                                                                         );
                                                                     state.last_seen_ok = true;
-
-                                                                    // Request SysInfo and persist a snapshot
-                                                                    // Import protocol types locally and track sys_info summary
-                                                                    use slarti_proto::{Command as ProtoCommand, Response as ProtoResponse};
-
-                                                                    let _ = client
-                                                                        .send_command(&ProtoCommand::SysInfo { id: 2 })
-                                                                        .await;
-                                                                    // Queue StaticConfig and ServicesList after SysInfo
-                                                                    let _ = client
-                                                                        .send_command(&ProtoCommand::StaticConfig { id: 3 })
-                                                                        .await;
-                                                                    let _ = client
-                                                                        .send_command(&ProtoCommand::ServicesList { id: 4 })
-                                                                        .await;
-
-                                                                    if let Ok(resp) = client.read_response_line().await {
-                                                                        if let ProtoResponse::SysInfoOk { id: _, info } = resp {
-                                                                            // Build a short summary for the HostPanel banner
-                                                                            sys_summary = Some(format!(
-                                                                                "{} {} {} host:{} uptime:{}s",
-                                                                                info.os,
-                                                                                info.kernel,
-                                                                                info.arch,
-                                                                                info.hostname,
-                                                                                info.uptime_secs
-                                                                            ));
-                                                                            // Persist snapshot under state dir
-                                                                            let mut snap_dir = slarti_state_dir();
-                                                                            snap_dir.push("hosts");
-                                                                            let _ = std::fs::create_dir_all(&snap_dir);
-                                                                            let mut snap_path = snap_dir.clone();
-                                                                            snap_path.push(format!("{}-sys_info.json", target));
-                                                                            let _ = std::fs::write(
-                                                                                snap_path,
-                                                                                serde_json::to_vec_pretty(&info)
-                                                                                    .unwrap_or_else(|_| serde_json::to_vec(&info).unwrap()),
-                                                                            );
-                                                                            // Update HostPanel with the latest SysInfo
-                                                                            let info_clone = info.clone();
-                                                                            let _ = acx.update(|_w, cxu| {
-                                                                                let _ = host_handle.update(cxu, |panel, cxp| {
-                                                                                    panel.set_sys_info(info_clone, cxp);
-                                                                                });
+                                                                    let rtt = client.last_rtt();
+                                                                    let setup = client.setup_time();
+                                                                    let read_only = client.supports(&slarti_proto::Capability::ReadOnly);
+                                                                    stats::record_connect(&target, setup);
+                                                                    let _ = acx.update(|_w, cxu| {
+                                                                        let _ = host_handle.update(cxu, |panel, cxp| {
+                                                                            panel.set_session_state(SessionState::Live, cxp);
+                                                                            if let Some(rtt) = rtt {
+                                                                                panel.record_latency(rtt, cxp);
+                                                                            }
+                                                                            if let Some(setup) = setup {
+                                                                                panel.set_connect_time(setup, cxp);
+                                                                            }
+                                                                            panel.set_agent_read_only(read_only, cxp);
+                                                                        });
+                                                                        if let Some(rtt) = rtt {
+                                                                            let _ = conn_handle.update(cxu, |conn, cxc| {
+                                                                                conn.record_ping(rtt, cxc);
                                                                             });
                                                                         }
-                                                                    }
-                                                                    // Read the StaticConfig response and show a brief summary
-                                                                    if let Ok(resp2) = client.read_response_line().await {
-                                                                        if let ProtoResponse::StaticConfigOk { id: _, config } = resp2 {
-                                                                            let gb = (config.mem_total_bytes as f64 / (1024.0 * 1024.0 * 1024.0)).round() as u64;
-                                                                            let brief = format!("cpus:{} mem:{}GB", config.cpu_count, gb);
+                                                                    });
+
+                                                                    // Initial load: SysInfo, StaticConfig and the services in one round trip
+                                                                    use slarti_host::Section;
+                                                                    use slarti_proto::Response as ProtoResponse;
+
+                                                                    let responses = match client.initial_load().await {
+                                                                        Ok(responses) => responses,
+                                                                        Err(e) => {
+                                                                            // Flag every section the round trip was to fill; polling retries them.
+                                                                            tracing::debug!(target: "slarti_ssh", "initial load for {} failed: {:#}", target, e);
+                                                                            let msg = format!("{:#}", e);
                                                                             let _ = acx.update(|_w, cxu| {
                                                                                 let _ = host_handle.update(cxu, |panel, cxp| {
-                                                                                    panel.push_progress(brief.clone(), cxp);
+                                                                                    for section in [Section::SysInfo, Section::Hardware, Section::Services] {
+                                                                                        panel.set_section_error(section, msg.clone(), cxp);
+                                                                                    }
                                                                                 });
                                                                             });
+                                                                            Vec::new()
                                                                         }
-                                                                    }
-                                                                    // Read the ServicesList response, store it, and add a brief summary
-                                                                    if let Ok(resp3) = client.read_response_line().await {
-                                                                        if let ProtoResponse::ServicesListOk { id: _, services } = resp3 {
-                                                                            let total = services.len();
-                                                                            let active = services.iter().filter(|s| s.active_state == "active").count();
-                                                                            let failed = services.iter().filter(|s| s.active_state == "failed").count();
-                                                                            let brief = format!("services: total {} active {} failed {}", total, active, failed);
-                                                                            let services_clone = services.clone();
-                                                                            let _ = acx.update(|_w, cxu| {
-                                                                                let _ = host_handle.update(cxu, |panel, cxp| {
-                                                                                    panel.set_services(services_clone, cxp);
-                                                                                    panel.push_progress(brief, cxp);
+                                                                    };
+                                                                    for resp in responses {
+                                                                        let resp = match resp {
+                                                                            ProtoResponse::ServicesDeltaOk { id, delta } => {
+                                                                                services_token = Some(delta.token);
+                                                                                delta.apply(&mut services);
+                                                                                ProtoResponse::ServicesListOk { id, services: services.clone() }
+                                                                            }
+                                                                            other => other,
+                                                                        };
+                                                                        match resp {
+                                                                            ProtoResponse::SysInfoOk { id: _, info } => {
+                                                                                // Build a short summary for the HostPanel banner
+                                                                                sys_summary = Some(format!(
+                                                                                    "{} {} {} host:{} uptime:{}s",
+                                                                                    info.os,
+                                                                                    info.kernel,
+                                                                                    info.arch,
+                                                                                    info.hostname,
+                                                                                    info.uptime_secs
+                                                                                ));
+                                                                                // Update HostPanel with the latest SysInfo (it persists the host snapshot)
+                                                                                let _ = acx.update(|_w, cxu| {
+                                                                                    let _ = host_handle.update(cxu, |panel, cxp| {
+                                                                                        panel.set_sys_info(info, cxp);
+                                                                                    });
                                                                                 });
-                                                                            });
+                                                                            }
+                                                                            ProtoResponse::StaticConfigOk { id: _, config } => {
+                                                                                let gb = (config.mem_total_bytes as f64 / (1024.0 * 1024.0 * 1024.0)).round() as u64;
+                                                                                let brief = format!("cpus:{} mem:{}GB", config.cpu_count, gb);
+                                                                                let _ = acx.update(|_w, cxu| {
+                                                                                    let _ = host_handle.update(cxu, |panel, cxp| {
+                                                                                        panel.push_progress(brief.clone(), cxp);
+                                                                                        panel.set_static_config(config, cxp);
+                                                                                    });
+                                                                                });
+                                                                            }
+                                                                            ProtoResponse::ServicesListOk { id: _, services } => {
+                                                                                let total = services.len();
+                                                                                let active = services.iter().filter(|s| s.active_state == "active").count();
+                                                                                let failed = services.iter().filter(|s| s.active_state == "failed").count();
+                                                                                let brief = format!("services: total {} active {} failed {}", total, active, failed);
+                                                                                let _ = acx.update(|_w, cxu| {
+                                                                                    let _ = host_handle.update(cxu, |panel, cxp| {
+                                                                                        panel.set_services(services, cxp);
+                                                                                        panel.push_progress(brief, cxp);
+                                                                                    });
+                                                                                });
+                                                                            }
+                                                                            ProtoResponse::DiskUsageOk { id: _, mounts } => {
+                                                                                let _ = acx.update(|_w, cxu| {
+                                                                                    let _ = host_handle.update(cxu, |panel, cxp| {
+                                                                                        panel.set_mounts(mounts, cxp);
+                                                                                    });
+                                                                                });
+                                                                            }
+                                                                            ProtoResponse::Error { id, message } => {
+                                                                                // Ids as initial_load sends them: 2 SysInfo, 3 StaticConfig, 4 services, 6 DiskUsage.
+                                                                                let section = match id {
+                                                                                    2 => Section::SysInfo,
+                                                                                    3 => Section::Hardware,
+                                                                                    6 => Section::Storage,
+                                                                                    _ => Section::Services,
+                                                                                };
+                                                                                let _ = acx.update(|_w, cxu| {
+                                                                                    let _ = host_handle.update(cxu, |panel, cxp| {
+                                                                                        panel.set_section_error(section, message, cxp);
+                                                                                    });
+                                                                                });
+                                                                            }
+                                                                            _ => {}
                                                                         }
                                                                     }
                                                                 }
-                                                                let _ = client.terminate().await;
+                                                                if state.last_seen_ok {
+                                                                    live_client = Some(client);
+                                                                } else {
+                                                                    let _ = client.terminate().await;
+                                                                }
                                                             }
                                                         }
                                                         Ok(_) => {
                                                             // Not present or not runnable; leave last_seen_ok = false and keep path for future deploy.
+                                                            let _ = acx.update(|_w, cxu| {
+                                                                let _ = conn_handle.update(cxu, |conn, cxc| {
+                                                                    conn.set_status(RemoteAgentStatus::NotPresent, cxc);
+                                                                });
+                                                            });
+                                                        }
+                                                        Err(e) if e.downcast_ref::<slarti_ssh::breaker::Suppressed>().is_some() => {
+                                                            // Repeated timeouts: no ssh was spawned; say so quietly and offer a manual retry.
+                                                            let msg = e
+                                                                .downcast_ref::<slarti_ssh::breaker::Suppressed>()
+                                                                .map(|s| {
+                                                                    format!(
+                                                                        "suppressed after {} timeouts (retry in {}s)",
+                                                                        s.failures,
+                                                                        s.retry_in.as_secs()
+                                                                    )
+                                                                })
+                                                                .unwrap_or_default();
+                                                            tracing::debug!("agent check skipped: {}", e);
+                                                            let _ = acx.update(|_window, cx| {
+                                                                let _ = host_handle.update(cx, |panel, cx| {
+                                                                    panel.set_status(msg.clone(), cx);
+                                                                    panel.set_suppressed(true, cx);
+                                                                    panel.set_checking(false, cx);
+                                                                });
+                                                                let _ = conn_handle.update(cx, |conn, cx| {
+                                                                    conn.set_status(
+                                                                        RemoteAgentStatus::Error { message: msg.clone() },
+                                                                        cx,
+                                                                    );
+                                                                });
+                                                            });
                                                         }
                                                         Err(e) => {
                                                             eprintln!(
@@ -1116,14 +2810,29 @@ fn main() {
                                                                 timeout,
                                                                 remote_path
                                                             );
+                                                            stats::record_connect_failed(&target);
+                                                            // ssh rejected BatchMode auth: offer an interactive login instead of a bare error.
+                                                            let auth_required =
+                                                                e.downcast_ref::<slarti_ssh::AuthRequired>().is_some();
                                                             // Surface error to HostPanel immediately
-                                                            let msg = format!("error: {}", e);
+                                                            let msg = if auth_required {
+                                                                "authentication required".to_string()
+                                                            } else {
+                                                                format!("error: {}", e)
+                                                            };
                                                             let _ = acx.update(|_window, cx| {
                                                                 let _ = host_handle.update(cx, |panel, cx| {
                                                                     panel.set_status(msg.clone(), cx);
                                                                     panel.push_progress("check failed", cx);
+                                                                    panel.set_auth_required(auth_required, cx);
                                                                     panel.set_checking(false, cx);
                                                                 });
+                                                                let _ = conn_handle.update(cx, |conn, cx| {
+                                                                    conn.set_status(
+                                                                        RemoteAgentStatus::Error { message: e.to_string() },
+                                                                        cx,
+                                                                    );
+                                                                });
                                                             });
                                                         }
                                                     }
@@ -1164,9 +2873,425 @@ fn main() {
                                                                     cx,
                                                                 );
                                                                 panel.set_checking(false, cx);
+                                                                if !state.last_seen_ok {
+                                                                    panel.set_session_state(SessionState::Dropped, cx);
+                                                                }
                                                             });
+                                                        let _ = conn_handle.update(cx, |conn, cx| {
+                                                            let status = match (&state.last_deployed_version, state.last_seen_ok) {
+                                                                (Some(v), true) if v != &version => RemoteAgentStatus::Outdated {
+                                                                    remote_version: Some(v.clone()),
+                                                                },
+                                                                (Some(v), true) => RemoteAgentStatus::Connected {
+                                                                    agent_version: v.clone(),
+                                                                },
+                                                                // NotPresent/Error were already reported by the check above.
+                                                                _ if !matches!(conn.status(), RemoteAgentStatus::Connecting) => return,
+                                                                _ => RemoteAgentStatus::Error {
+                                                                    message: "agent present but failed to connect".to_string(),
+                                                                },
+                                                            };
+                                                            conn.set_status(status, cx);
+                                                        });
                                                     });
-                                            });
+                                            }));
+
+                                        // Serve section refreshes (manual or auto-refresh interval) while this host stays selected.
+                                        if let Some(mut client) = live_client {
+                                            use slarti_host::Section;
+                                            use slarti_proto::{Command as ProtoCommand, Response as ProtoResponse};
+                                            let mut next_id = 100u64;
+                                            // Frames of followed files, handed over once per tick.
+                                            let mut streams = client.streams();
+                                            loop {
+                                                acx.background_executor().timer(POLL_TICK).await;
+                                                if SELECTION_EPOCH.load(Ordering::SeqCst) != epoch {
+                                                    break;
+                                                }
+                                                let mut frames = Vec::new();
+                                                if let Some(rx) = streams.as_mut() {
+                                                    while let Ok(frame) = rx.try_recv() {
+                                                        frames.push(frame);
+                                                    }
+                                                }
+                                                if !frames.is_empty() {
+                                                    let _ = acx.update(|_w, cxu| {
+                                                        let _ = host_handle.update(cxu, |panel, cxp| {
+                                                            panel.apply_stream_frames(frames, cxp);
+                                                        });
+                                                    });
+                                                }
+                                                // Interval polling pauses while the window is unfocused.
+                                                let due = acx
+                                                    .update(|window, cxu| {
+                                                        let focused = window.is_window_active();
+                                                        host_handle.update(cxu, |panel, _| {
+                                                            panel.take_due_sections(focused)
+                                                        })
+                                                    })
+                                                    .unwrap_or_default();
+                                                let mut failed = false;
+                                                for section in due {
+                                                    next_id += 1;
+                                                    let cmd = match section {
+                                                        // The agent's own resource use rides along with the identity.
+                                                        Section::SysInfo if client.supports(&slarti_proto::Capability::AgentStats) => {
+                                                            ProtoCommand::Batch {
+                                                                id: next_id,
+                                                                commands: vec![
+                                                                    ProtoCommand::SysInfo { id: next_id },
+                                                                    ProtoCommand::AgentStats { id: next_id },
+                                                                ],
+                                                            }
+                                                        }
+                                                        Section::SysInfo => ProtoCommand::SysInfo { id: next_id },
+                                                        Section::Services if client.supports(&slarti_proto::Capability::ServicesDelta) => {
+                                                            ProtoCommand::ServicesDelta { id: next_id, since: services_token }
+                                                        }
+                                                        Section::Services => ProtoCommand::ServicesList { id: next_id },
+                                                        Section::Processes => ProtoCommand::ProcessesSummary { id: next_id, limit: None },
+                                                        Section::Cgroups => ProtoCommand::CgroupTree { id: next_id },
+                                                        Section::Pressure => ProtoCommand::Pressure { id: next_id },
+                                                        // CPU topology, GPUs and the thermal status in one round trip
+                                                        Section::Hardware => ProtoCommand::Batch {
+                                                            id: next_id,
+                                                            commands: vec![
+                                                                ProtoCommand::StaticConfig { id: next_id },
+                                                                ProtoCommand::GpuInfo { id: next_id },
+                                                                ProtoCommand::Sensors { id: next_id },
+                                                            ],
+                                                        },
+                                                        Section::Inventory => ProtoCommand::HardwareInventory { id: next_id },
+                                                        // md arrays, plus the volume stacks and filesystem space where the agent has them
+                                                        Section::Storage => {
+                                                            let mut commands = vec![ProtoCommand::RaidStatus { id: next_id }];
+                                                            if client.supports(&slarti_proto::Capability::StorageStacks) {
+                                                                commands.push(ProtoCommand::StorageStacks { id: next_id });
+                                                            }
+                                                            if client.supports(&slarti_proto::Capability::DiskUsage) {
+                                                                commands.push(ProtoCommand::DiskUsage { id: next_id });
+                                                            }
+                                                            if commands.len() == 1 {
+                                                                commands.remove(0)
+                                                            } else {
+                                                                ProtoCommand::Batch { id: next_id, commands }
+                                                            }
+                                                        }
+                                                        Section::Kernel => ProtoCommand::Batch {
+                                                            id: next_id,
+                                                            commands: vec![
+                                                                ProtoCommand::KernelModules { id: next_id },
+                                                                ProtoCommand::Sysctl {
+                                                                    id: next_id,
+                                                                    keys: slarti_host::sysctl_keys(),
+                                                                },
+                                                            ],
+                                                        },
+                                                        Section::Backups => ProtoCommand::Backups { id: next_id },
+                                                        Section::Updates => ProtoCommand::UpdatesAvailable { id: next_id },
+                                                        Section::Sessions => ProtoCommand::Sessions { id: next_id },
+                                                        // sshd settings and failed logins in one round trip
+                                                        Section::Security => ProtoCommand::Batch {
+                                                            id: next_id,
+                                                            commands: vec![
+                                                                ProtoCommand::SshdConfig { id: next_id },
+                                                                ProtoCommand::AuthFailures { id: next_id, window_secs: None },
+                                                            ],
+                                                        },
+                                                        Section::Access => ProtoCommand::Users { id: next_id },
+                                                        Section::Listeners if client.supports(&slarti_proto::Capability::ListenersDelta) => {
+                                                            ProtoCommand::ListenersDelta { id: next_id, since: listeners_token }
+                                                        }
+                                                        Section::Listeners => ProtoCommand::NetListeners { id: next_id },
+                                                        Section::Connectivity => ProtoCommand::Batch {
+                                                            id: next_id,
+                                                            commands: vec![
+                                                                ProtoCommand::Reachability {
+                                                                    id: next_id,
+                                                                    targets: slarti_host::reachability_endpoints(),
+                                                                    timeout_ms: None,
+                                                                },
+                                                                ProtoCommand::DnsConfig { id: next_id },
+                                                                ProtoCommand::Routes { id: next_id },
+                                                                ProtoCommand::Sockets { id: next_id },
+                                                            ],
+                                                        },
+                                                    };
+                                                    let resp = bg_rt().block_on(client.request(&cmd)).map(|resp| match resp {
+                                                        ProtoResponse::ServicesDeltaOk { id, delta } => {
+                                                            services_token = Some(delta.token);
+                                                            delta.apply(&mut services);
+                                                            ProtoResponse::ServicesListOk { id, services: services.clone() }
+                                                        }
+                                                        ProtoResponse::ListenersDeltaOk { id, delta } => {
+                                                            listeners_token = Some(delta.token);
+                                                            delta.apply(&mut listeners);
+                                                            ProtoResponse::NetListenersOk { id, listeners: listeners.clone() }
+                                                        }
+                                                        other => other,
+                                                    });
+                                                    if let (true, Some(rtt)) = (resp.is_ok(), client.last_rtt()) {
+                                                        stats::record_request(&target, &format!("{:?}", section), rtt);
+                                                        tracing::debug!(
+                                                            target: "slarti_ssh",
+                                                            "refresh {:?} for {}: trace={} rtt={:?}",
+                                                            section,
+                                                            target,
+                                                            client.last_trace().unwrap_or("-"),
+                                                            rtt
+                                                        );
+                                                        let _ = acx.update(|_w, cxu| {
+                                                            let _ = host_handle.update(cxu, |panel, cxp| {
+                                                                panel.record_latency(rtt, cxp);
+                                                            });
+                                                            let _ = conn_handle.update(cxu, |conn, cxc| {
+                                                                conn.record_ping(rtt, cxc);
+                                                            });
+                                                        });
+                                                    }
+                                                    match resp {
+                                                        Ok(ProtoResponse::SysInfoOk { id: _, info }) => {
+                                                            let _ = acx.update(|_w, cxu| {
+                                                                let _ = host_handle.update(cxu, |panel, cxp| {
+                                                                    panel.set_sys_info(info, cxp);
+                                                                });
+                                                            });
+                                                        }
+                                                        Ok(ProtoResponse::StaticConfigOk { id: _, config }) => {
+                                                            let _ = acx.update(|_w, cxu| {
+                                                                let _ = host_handle.update(cxu, |panel, cxp| {
+                                                                    panel.set_static_config(config, cxp);
+                                                                });
+                                                            });
+                                                        }
+                                                        Ok(ProtoResponse::HardwareInventoryOk { id: _, inventory }) => {
+                                                            let _ = acx.update(|_w, cxu| {
+                                                                let _ = host_handle.update(cxu, |panel, cxp| {
+                                                                    panel.set_inventory(inventory, cxp);
+                                                                });
+                                                            });
+                                                        }
+                                                        Ok(ProtoResponse::UpdatesAvailableOk { id: _, updates }) => {
+                                                            let _ = acx.update(|_w, cxu| {
+                                                                let _ = host_handle.update(cxu, |panel, cxp| {
+                                                                    panel.set_updates(updates, cxp);
+                                                                });
+                                                            });
+                                                        }
+                                                        Ok(ProtoResponse::UsersOk { id: _, users }) => {
+                                                            let _ = acx.update(|_w, cxu| {
+                                                                let _ = host_handle.update(cxu, |panel, cxp| {
+                                                                    panel.set_users(users, cxp);
+                                                                });
+                                                            });
+                                                        }
+                                                        Ok(ProtoResponse::BatchOk { id: _, responses }) => {
+                                                            let _ = acx.update(|_w, cxu| {
+                                                                let _ = host_handle.update(cxu, |panel, cxp| {
+                                                                    for resp in responses {
+                                                                        match resp {
+                                                                            ProtoResponse::SysInfoOk { id: _, info } => {
+                                                                                panel.set_sys_info(info, cxp);
+                                                                            }
+                                                                            ProtoResponse::AgentStatsOk { id: _, stats } => {
+                                                                                panel.set_agent_stats(stats, cxp);
+                                                                            }
+                                                                            ProtoResponse::StaticConfigOk { id: _, config } => {
+                                                                                panel.set_static_config(config, cxp);
+                                                                            }
+                                                                            ProtoResponse::GpuInfoOk { id: _, gpus } => {
+                                                                                panel.set_gpus(gpus, cxp);
+                                                                            }
+                                                                            ProtoResponse::SensorsOk { id: _, readings } => {
+                                                                                panel.set_sensors(readings, cxp);
+                                                                            }
+                                                                            ProtoResponse::SshdConfigOk { id: _, config } => {
+                                                                                panel.set_sshd_config(config, cxp);
+                                                                            }
+                                                                            ProtoResponse::AuthFailuresOk { id: _, summary } => {
+                                                                                panel.set_auth_failures(summary, cxp);
+                                                                            }
+                                                                            ProtoResponse::KernelModulesOk { id: _, modules } => {
+                                                                                panel.set_kernel_modules(modules, cxp);
+                                                                            }
+                                                                            ProtoResponse::SysctlOk { id: _, values } => {
+                                                                                panel.set_sysctl(values, cxp);
+                                                                            }
+                                                                            ProtoResponse::ReachabilityOk { id: _, results } => {
+                                                                                panel.set_connectivity(results, cxp);
+                                                                            }
+                                                                            ProtoResponse::DnsConfigOk { id: _, config } => {
+                                                                                panel.set_dns_config(config, cxp);
+                                                                            }
+                                                                            ProtoResponse::RoutesOk { id: _, routes } => {
+                                                                                panel.set_routes(routes, cxp);
+                                                                            }
+                                                                            ProtoResponse::SocketsOk { id: _, summary } => {
+                                                                                panel.set_sockets(summary, cxp);
+                                                                            }
+                                                                            ProtoResponse::RaidStatusOk { id: _, arrays } => {
+                                                                                panel.set_raid_arrays(arrays, cxp);
+                                                                            }
+                                                                            ProtoResponse::StorageStacksOk { id: _, stacks } => {
+                                                                                panel.set_storage_stacks(stacks, cxp);
+                                                                            }
+                                                                            ProtoResponse::DiskUsageOk { id: _, mounts } => {
+                                                                                panel.set_mounts(mounts, cxp);
+                                                                            }
+                                                                            _ => {}
+                                                                        }
+                                                                    }
+                                                                });
+                                                            });
+                                                        }
+                                                        Ok(ProtoResponse::NetListenersOk { id: _, listeners }) => {
+                                                            let _ = acx.update(|_w, cxu| {
+                                                                let _ = host_handle.update(cxu, |panel, cxp| {
+                                                                    panel.set_listeners(listeners, cxp);
+                                                                });
+                                                            });
+                                                        }
+                                                        Ok(ProtoResponse::SessionsOk { id: _, sessions }) => {
+                                                            let _ = acx.update(|_w, cxu| {
+                                                                let _ = host_handle.update(cxu, |panel, cxp| {
+                                                                    panel.set_sessions(sessions, cxp);
+                                                                });
+                                                            });
+                                                        }
+                                                        Ok(ProtoResponse::BackupsOk { id: _, jobs }) => {
+                                                            let _ = acx.update(|_w, cxu| {
+                                                                let _ = host_handle.update(cxu, |panel, cxp| {
+                                                                    panel.set_backups(jobs, cxp);
+                                                                });
+                                                            });
+                                                        }
+                                                        Ok(ProtoResponse::RaidStatusOk { id: _, arrays }) => {
+                                                            let _ = acx.update(|_w, cxu| {
+                                                                let _ = host_handle.update(cxu, |panel, cxp| {
+                                                                    panel.set_raid_arrays(arrays, cxp);
+                                                                });
+                                                            });
+                                                        }
+                                                        Ok(ProtoResponse::ServicesListOk { id: _, services }) => {
+                                                            let _ = acx.update(|_w, cxu| {
+                                                                let _ = host_handle.update(cxu, |panel, cxp| {
+                                                                    panel.set_services(services, cxp);
+                                                                });
+                                                            });
+                                                        }
+                                                        Ok(ProtoResponse::ProcessesSummaryOk { id: _, summary }) => {
+                                                            let _ = acx.update(|_w, cxu| {
+                                                                let _ = host_handle.update(cxu, |panel, cxp| {
+                                                                    panel.set_processes(summary, cxp);
+                                                                });
+                                                            });
+                                                        }
+                                                        Ok(ProtoResponse::CgroupTreeOk { id: _, root }) => {
+                                                            let _ = acx.update(|_w, cxu| {
+                                                                let _ = host_handle.update(cxu, |panel, cxp| {
+                                                                    panel.set_cgroups(root, cxp);
+                                                                });
+                                                            });
+                                                        }
+                                                        Ok(ProtoResponse::PressureOk { id: _, pressure }) => {
+                                                            let _ = acx.update(|_w, cxu| {
+                                                                let _ = host_handle.update(cxu, |panel, cxp| {
+                                                                    panel.set_pressure(pressure, cxp);
+                                                                });
+                                                            });
+                                                        }
+                                                        Ok(ProtoResponse::Error { id: _, message }) => {
+                                                            let _ = acx.update(|_w, cxu| {
+                                                                let _ = host_handle.update(cxu, |panel, cxp| {
+                                                                    panel.set_section_error(section, message, cxp);
+                                                                });
+                                                            });
+                                                        }
+                                                        Ok(_) => {}
+                                                        Err(e) => {
+                                                            tracing::debug!(target: "slarti_ssh", "refresh for {} stopped: {}", target, e);
+                                                            failed = true;
+                                                            break;
+                                                        }
+                                                    }
+                                                }
+                                                // Drawer requests: process and service details, kill and renice,
+                                                // file windows and follows.
+                                                // The epoch is checked again on the UI thread, where selections
+                                                // change, so requests queued for another host stay queued.
+                                                let requests = if failed {
+                                                    Vec::new()
+                                                } else {
+                                                    acx.update(|_w, cxu| {
+                                                        if SELECTION_EPOCH.load(Ordering::SeqCst) != epoch {
+                                                            return Vec::new();
+                                                        }
+                                                        host_handle.update(cxu, |panel, _| panel.take_agent_requests())
+                                                    })
+                                                    .unwrap_or_default()
+                                                };
+                                                for request in requests {
+                                                    next_id += 1;
+                                                    let cmd = request.command(next_id);
+                                                    if request.starts_stream() || request.stops_stream() {
+                                                        let sent = if request.starts_stream() {
+                                                            bg_rt().block_on(client.start_stream(&cmd))
+                                                        } else {
+                                                            bg_rt().block_on(client.stop_stream(&cmd))
+                                                        };
+                                                        if let Err(e) = sent {
+                                                            tracing::debug!(target: "slarti_ssh", "stream request for {} stopped: {}", target, e);
+                                                            failed = true;
+                                                            break;
+                                                        }
+                                                        if request.starts_stream() {
+                                                            let _ = acx.update(|_w, cxu| {
+                                                                if SELECTION_EPOCH.load(Ordering::SeqCst) != epoch {
+                                                                    return;
+                                                                }
+                                                                let _ = host_handle.update(cxu, |panel, cxp| {
+                                                                    panel.stream_started(request, next_id, cxp);
+                                                                });
+                                                            });
+                                                        }
+                                                        continue;
+                                                    }
+                                                    match bg_rt().block_on(client.request(&cmd)) {
+                                                        Ok(resp) => {
+                                                            let _ = acx.update(|_w, cxu| {
+                                                                if SELECTION_EPOCH.load(Ordering::SeqCst) != epoch {
+                                                                    return;
+                                                                }
+                                                                let _ = host_handle.update(cxu, |panel, cxp| {
+                                                                    panel.apply_agent_response(request, resp, cxp);
+                                                                });
+                                                            });
+                                                        }
+                                                        Err(e) => {
+                                                            tracing::debug!(target: "slarti_ssh", "drawer request for {} stopped: {}", target, e);
+                                                            failed = true;
+                                                            break;
+                                                        }
+                                                    }
+                                                }
+                                                if failed {
+                                                    let _ = acx.update(|_w, cxu| {
+                                                        let _ = host_handle.update(cxu, |panel, cxp| {
+                                                            panel.set_session_state(SessionState::Dropped, cxp);
+                                                            panel.set_status("session lost", cxp);
+                                                        });
+                                                        let _ = conn_handle.update(cxu, |conn, cxc| {
+                                                            conn.set_status(
+                                                                RemoteAgentStatus::Error { message: "session lost".to_string() },
+                                                                cxc,
+                                                            );
+                                                        });
+                                                    });
+                                                    break;
+                                                }
+                                            }
+                                            let _ = bg_rt().block_on(client.terminate());
+                                        }
                                     })
                                     .detach();
                             },
@@ -1200,8 +3325,418 @@ fn main() {
                             tree: cfg_tree,
                             on_select: on_select.clone(),
                         }));
+
+                        // Running docker containers and kubernetes pods join the tree (and the
+                        // start-screen fleet) once discovered; listing runs off the UI thread.
+                        {
+                            let hosts_weak = hosts.downgrade();
+                            let host_info_weak = host_info.downgrade();
+                            window
+                                .spawn(cx, async move |acx| {
+                                    let _task = slarti_ui::diagnostics::TaskGuard::new();
+                                    let found = acx
+                                        .background_executor()
+                                        .spawn(async {
+                                            bg_rt().block_on(slarti_ssh::container::list_containers(
+                                                Duration::from_secs(5),
+                                            ))
+                                        })
+                                        .await;
+                                    let aliases: Vec<String> =
+                                        found.iter().map(Container::alias).collect();
+                                    let _ = acx.update(|_w, cx| {
+                                        let _ = hosts_weak
+                                            .update(cx, |panel, cx| panel.set_containers(aliases.clone(), cx));
+                                        if let Some(mut known) = known_aliases {
+                                            known.extend(aliases);
+                                            let _ = host_info_weak
+                                                .update(cx, |panel, cx| panel.set_known_hosts(known, cx));
+                                        }
+                                    });
+                                })
+                                .detach();
+                        }
+
+                        // SSH servers announcing themselves via mDNS, offered under "Discovered".
+                        {
+                            let hosts_weak = hosts.downgrade();
+                            window
+                                .spawn(cx, async move |acx| {
+                                    let _task = slarti_ui::diagnostics::TaskGuard::new();
+                                    let found = acx
+                                        .background_executor()
+                                        .spawn(async {
+                                            slarti_discovery::mdns::browse_ssh(Duration::from_secs(3))
+                                        })
+                                        .await;
+                                    match found {
+                                        Ok(found) => {
+                                            let _ = acx.update(|_w, cx| {
+                                                let _ = hosts_weak
+                                                    .update(cx, |panel, cx| panel.set_discovered(found, cx));
+                                            });
+                                        }
+                                        Err(e) => tracing::debug!("mdns discovery unavailable: {:#}", e),
+                                    }
+                                })
+                                .detach();
+                        }
+                        // Tailnet peers (and the online state of configured hosts that are peers),
+                        // refreshed while tailscale keeps answering.
+                        {
+                            let hosts_weak = hosts.downgrade();
+                            window
+                                .spawn(cx, async move |acx| loop {
+                                    let peers = {
+                                        let _task = slarti_ui::diagnostics::TaskGuard::new();
+                                        acx.background_executor()
+                                            .spawn(async {
+                                                slarti_discovery::tailscale::peers(Duration::from_secs(3))
+                                            })
+                                            .await
+                                    };
+                                    let peers = match peers {
+                                        Ok(peers) => peers,
+                                        Err(e) => {
+                                            tracing::debug!("tailscale peers unavailable: {:#}", e);
+                                            return;
+                                        }
+                                    };
+                                    let updated = acx.update(|_w, cx| {
+                                        hosts_weak.update(cx, |panel, cx| panel.set_tailscale_peers(peers, cx))
+                                    });
+                                    if !matches!(updated, Ok(Ok(()))) {
+                                        return;
+                                    }
+                                    acx.background_executor().timer(TAILSCALE_REFRESH).await;
+                                    if SHUTTING_DOWN.load(Ordering::SeqCst) {
+                                        return;
+                                    }
+                                })
+                                .detach();
+                        }
                         // Build the container that will host panels (hosts + host_info + terminal).
-                        cx.new(|cx| ContainerView::new(cx, terminal, hosts, host_info, ui_fg))
+                        let host_info_for_keys = host_info.clone();
+                        let hosts_for_auth = hosts.clone();
+                        let container = cx.new(|cx| {
+                            ContainerView::new(cx, terminal, hosts, host_info, connection, ui_fg)
+                        });
+
+                        // Closing from the window manager runs the same shutdown as the close button.
+                        {
+                            let container_weak = container.downgrade();
+                            window.on_window_should_close(cx, move |window, cx| {
+                                container_weak
+                                    .update(cx, |cv, cx| cv.shutdown(window, cx))
+                                    .is_err()
+                            });
+                        }
+
+                        // Start-screen quick actions (Add host, Open local terminal, Settings).
+                        {
+                            let container_weak = container.downgrade();
+                            host_info_for_keys.update(cx, |panel, cx| {
+                                panel.set_on_quick_action(
+                                    Some(Arc::new(
+                                        move |action: QuickAction,
+                                              window: &mut Window,
+                                              cxp: &mut Context<HostInfoPanel>| {
+                                            let _ = container_weak.update(cxp, |cv, cx| {
+                                                cv.quick_action(action, window, cx);
+                                            });
+                                        },
+                                    )),
+                                    cx,
+                                );
+                            });
+                        }
+
+                        // "Install my key": run ssh-copy-id interactively in the embedded terminal.
+                        {
+                            let container_weak = container.downgrade();
+                            host_info_for_keys.update(cx, |panel, cx| {
+                                panel.set_on_install_key(
+                                    Some(Arc::new(
+                                        move |alias: String,
+                                              _window: &mut Window,
+                                              cxp: &mut Context<HostInfoPanel>| {
+                                            let command = slarti_ssh::ssh_copy_id_command(&alias);
+                                            let _ = container_weak.update(cxp, |cv, cx| {
+                                                cv.run_in_terminal(&command, cx);
+                                            });
+                                        },
+                                    )),
+                                    cx,
+                                );
+                            });
+                        }
+
+                        // "Clean old agents": remove older agent versions after the dry-run preview.
+                        host_info_for_keys.update(cx, |panel, cx| {
+                            panel.set_on_clean_agents(
+                                Some(Arc::new(
+                                    |alias: String,
+                                     window: &mut Window,
+                                     cxp: &mut Context<HostInfoPanel>| {
+                                        let panel = cxp.entity().downgrade();
+                                        window
+                                            .spawn(cxp, async move |acx| {
+                                                let _task = slarti_ui::diagnostics::TaskGuard::new();
+                                                let cleaned = acx
+                                                    .background_executor()
+                                                    .spawn(async move {
+                                                        bg_rt().block_on(slarti_ssh::queue::interactive(
+                                                            clean_old_agents_on(&alias),
+                                                        ))
+                                                    })
+                                                    .await;
+                                                let _ = acx.update(|_w, cx| {
+                                                    let _ = panel.update(cx, |panel, cx| {
+                                                        panel.push_progress(cleaned, cx);
+                                                    });
+                                                });
+                                            })
+                                            .detach();
+                                    },
+                                )),
+                                cx,
+                            );
+                        });
+
+                        // "Open console": serial console of the selected host in the terminal.
+                        {
+                            let container_weak = container.downgrade();
+                            host_info_for_keys.update(cx, |panel, cx| {
+                                panel.set_on_open_console(
+                                    Some(Arc::new(
+                                        move |alias: String,
+                                              _window: &mut Window,
+                                              cxp: &mut Context<HostInfoPanel>| {
+                                            let command = serial_console_for(&cfg_tree_for_console, &alias)
+                                                .unwrap_or_else(|| Err(anyhow::anyhow!("{} is not a serial host", alias)))
+                                                .and_then(|line| line.command());
+                                            match command {
+                                                Ok(command) => {
+                                                    let _ = container_weak.update(cxp, |cv, cx| {
+                                                        cv.run_in_terminal(&command, cx);
+                                                    });
+                                                }
+                                                Err(e) => {
+                                                    // The panel is being updated by the caller; report once it is free.
+                                                    let panel = cxp.entity();
+                                                    cxp.defer(move |cx| {
+                                                        panel.update(cx, |panel, cx| {
+                                                            panel.set_status(format!("error: {}", e), cx);
+                                                        });
+                                                    });
+                                                }
+                                            }
+                                        },
+                                    )),
+                                    cx,
+                                );
+                            });
+                        }
+
+                        // "Authenticate interactively": open an ssh control master in the terminal so the
+                        // user can answer password/keyboard-interactive prompts, then retry the selection
+                        // once the master socket is up (BatchMode connections reuse it).
+                        {
+                            let container_weak = container.downgrade();
+                            let hosts_weak = hosts_for_auth.downgrade();
+                            let on_select_auth = on_select.clone();
+                            host_info_for_keys.update(cx, |panel, cx| {
+                                panel.set_on_authenticate(
+                                    Some(Arc::new(
+                                        move |alias: String,
+                                              window: &mut Window,
+                                              cxp: &mut Context<HostInfoPanel>| {
+                                            let container_weak = container_weak.clone();
+                                            let hosts_weak = hosts_weak.clone();
+                                            let on_select = on_select_auth.clone();
+                                            window
+                                                .spawn(cxp, async move |acx| {
+                                                    let _task = slarti_ui::diagnostics::TaskGuard::new();
+                                                    // Building the command reads the host's ssh config.
+                                                    let command = acx
+                                                        .background_executor()
+                                                        .spawn({
+                                                            let alias = alias.clone();
+                                                            async move {
+                                                                bg_rt().block_on(
+                                                                    slarti_ssh::interactive_master_command(&alias),
+                                                                )
+                                                            }
+                                                        })
+                                                        .await;
+                                                    let _ = acx.update(|_w, cxu| {
+                                                        let _ = container_weak.update(cxu, |cv, cx| {
+                                                            cv.run_in_terminal(&command, cx);
+                                                        });
+                                                    });
+                                                    for _ in 0..AUTH_WAIT_SECS {
+                                                        acx.background_executor()
+                                                            .timer(Duration::from_secs(1))
+                                                            .await;
+                                                        if SHUTTING_DOWN.load(Ordering::SeqCst) {
+                                                            break;
+                                                        }
+                                                        if bg_rt().block_on(
+                                                            slarti_ssh::control_master_alive(&alias),
+                                                        ) {
+                                                            let _ = acx.update(|window, cxu| {
+                                                                let _ = hosts_weak.update(cxu, |_, hcx| {
+                                                                    (on_select)(alias.clone(), window, hcx);
+                                                                });
+                                                            });
+                                                            return;
+                                                        }
+                                                    }
+                                                })
+                                                .detach();
+                                        },
+                                    )),
+                                    cx,
+                                );
+                            });
+                        }
+
+                        // "Retry now" on a suppressed host: clear its timeout history and select it again.
+                        {
+                            let hosts_weak = hosts_for_auth.downgrade();
+                            let on_select_retry = on_select.clone();
+                            host_info_for_keys.update(cx, |panel, cx| {
+                                panel.set_on_retry(
+                                    Some(Arc::new(
+                                        move |alias: String,
+                                              window: &mut Window,
+                                              cxp: &mut Context<HostInfoPanel>| {
+                                            slarti_ssh::breaker::reset(&alias);
+                                            let hosts_weak = hosts_weak.clone();
+                                            let on_select = on_select_retry.clone();
+                                            window
+                                                .spawn(cxp, async move |acx| {
+                                                    let _ = acx.update(|window, cxu| {
+                                                        let _ = hosts_weak.update(cxu, |_, hcx| {
+                                                            (on_select)(alias, window, hcx);
+                                                        });
+                                                    });
+                                                })
+                                                .detach();
+                                        },
+                                    )),
+                                    cx,
+                                );
+                            });
+                        }
+
+                        // Action previews: build the exact commands off the UI thread, then show
+                        // them in the panel for Run/Cancel.
+                        host_info_for_keys.update(cx, |panel, cx| {
+                            panel.set_on_preview(
+                                Some(Arc::new(
+                                    |action: PendingAction,
+                                     alias: String,
+                                     window: &mut Window,
+                                     cxp: &mut Context<HostInfoPanel>| {
+                                        let panel = cxp.entity().downgrade();
+                                        window
+                                            .spawn(cxp, async move |acx| {
+                                                let _task = slarti_ui::diagnostics::TaskGuard::new();
+                                                let plan = acx
+                                                    .background_executor()
+                                                    .spawn({
+                                                        let alias = alias.clone();
+                                                        async move {
+                                                            bg_rt().block_on(slarti_ssh::queue::interactive(
+                                                                action_plan(action, &alias),
+                                                            ))
+                                                        }
+                                                    })
+                                                    .await;
+                                                let _ = acx.update(|window, cx| {
+                                                    let _ = panel.update(cx, |panel, cx| match plan {
+                                                        // The plan was for a host no longer selected.
+                                                        _ if panel.selected_alias() != Some(alias.as_str()) => {}
+                                                        Some(plan) => {
+                                                            panel.show_action_preview(
+                                                                Some(ActionPreview {
+                                                                    action,
+                                                                    alias: alias.clone(),
+                                                                    title: plan.action.clone().into(),
+                                                                    lines: plan
+                                                                        .describe()
+                                                                        .into_iter()
+                                                                        .map(Into::into)
+                                                                        .collect(),
+                                                                }),
+                                                                cx,
+                                                            );
+                                                            panel.push_progress("review, then Run", cx);
+                                                        }
+                                                        // Nothing to preview: the action reports why itself.
+                                                        None => panel.run_action(action, window, cx),
+                                                    });
+                                                });
+                                            })
+                                            .detach();
+                                    },
+                                )),
+                                cx,
+                            );
+                        });
+
+                        // Surface ssh passphrase / security-key prompts as a modal.
+                        match slarti_ssh::askpass::start() {
+                            Ok(rx) => {
+                                let container_weak = container.downgrade();
+                                window
+                                    .spawn(cx, async move |acx| loop {
+                                        acx.background_executor()
+                                            .timer(Duration::from_millis(100))
+                                            .await;
+                                        let alive = acx
+                                            .update(|window, cx| {
+                                                container_weak
+                                                    .update(cx, |cv, cx| cv.poll_askpass(&rx, window, cx))
+                                                    .is_ok()
+                                            })
+                                            .unwrap_or(false);
+                                        if !alive {
+                                            break;
+                                        }
+                                    })
+                                    .detach();
+                            }
+                            Err(e) => {
+                                tracing::warn!("askpass unavailable, ssh prompts cannot be shown: {}", e)
+                            }
+                        }
+
+                        // Select the host named on the command line, then serve later launches.
+                        container.update(cx, |cv, cx| cv.open_args(&args, window, cx));
+                        if let Some(rx) = instance_rx {
+                            let container_weak = container.downgrade();
+                            window
+                                .spawn(cx, async move |acx| loop {
+                                    acx.background_executor()
+                                        .timer(Duration::from_millis(200))
+                                        .await;
+                                    let alive = acx
+                                        .update(|window, cx| {
+                                            container_weak
+                                                .update(cx, |cv, cx| cv.poll_instance(&rx, window, cx))
+                                                .is_ok()
+                                        })
+                                        .unwrap_or(false);
+                                    if !alive {
+                                        break;
+                                    }
+                                })
+                                .detach();
+                        }
+
+                        container
                     },
                 )
                 .unwrap();
@@ -1229,13 +3764,16 @@ fn main() {
 
             // Deploy callback is wired earlier via host_info.set_on_deploy; no additional wiring needed here.
 
-            cx.observe_keystrokes(move |ev, _window, cx| {
+            cx.observe_keystrokes(move |ev, window, cx| {
+                // Text typed into the askpass modal or the diagnostics filter is not for the shell.
+                if container.read(cx).keys_captured(window, cx) {
+                    return;
+                }
                 if let Some(ch) = ev.keystroke.key_char.clone() {
                     let bytes = ch.to_string().into_bytes();
+                    // The terminal repaints itself once the echo arrives.
                     let _ = container.update(cx, |cv, cx| {
                         cv.terminal.update(cx, |term, _| term.write_bytes(&bytes));
-                        // Request an immediate repaint after sending input
-                        cx.notify();
                     });
                 } else {
                     let name = ev.keystroke.unparse();
@@ -1251,7 +3789,6 @@ fn main() {
                     if let Some(bytes) = seq {
                         let _ = container.update(cx, |cv, cx| {
                             cv.terminal.update(cx, |term, _| term.write_bytes(bytes));
-                            cx.notify();
                         });
                     }
                 }
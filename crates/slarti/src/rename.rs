@@ -0,0 +1,220 @@
+//! Guided host rename (≡ → Rename host…): pick an alias and its new name,
+//! preview the rewrite of every ssh config file that mentions it (Host
+//! patterns, `Match host` criteria, ProxyJump hops), then write it.
+
+use gpui::{div, prelude::*, Context, EventEmitter, FocusHandle, Focusable, KeyDownEvent, Window};
+use slarti_sshcfg as sshcfg;
+use slarti_ui::Appearance;
+
+/// Emitted once the config files were rewritten.
+pub struct Renamed {
+    pub from: String,
+    pub to: String,
+    pub files: usize,
+}
+/// Emitted when the overlay is closed without renaming.
+pub struct Dismissed;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Field {
+    From,
+    To,
+}
+
+pub struct RenameOverlay {
+    focus: FocusHandle,
+    from: String,
+    to: String,
+    field: Field,
+    // Set once previewed; any edit drops it
+    plan: Option<sshcfg::edit::RenamePlan>,
+    error: Option<String>,
+}
+
+impl EventEmitter<Renamed> for RenameOverlay {}
+impl EventEmitter<Dismissed> for RenameOverlay {}
+
+impl RenameOverlay {
+    /// Start renaming `from` (usually the selected host); empty to type one.
+    pub fn new(from: Option<String>, cx: &mut Context<Self>) -> Self {
+        let field = if from.is_some() {
+            Field::To
+        } else {
+            Field::From
+        };
+        Self {
+            focus: cx.focus_handle(),
+            from: from.unwrap_or_default(),
+            to: String::new(),
+            field,
+            plan: None,
+            error: None,
+        }
+    }
+
+    fn preview(&mut self) {
+        self.plan = None;
+        self.error = None;
+        let planned = sshcfg::load::load_user_config_tree()
+            .and_then(|tree| sshcfg::edit::plan_rename(&tree, &self.from, &self.to));
+        match planned {
+            Ok(plan) => self.plan = Some(plan),
+            Err(e) => self.error = Some(format!("{:#}", e)),
+        }
+    }
+
+    fn apply(&mut self, cx: &mut Context<Self>) {
+        let Some(plan) = self.plan.take() else {
+            return;
+        };
+        match plan.apply() {
+            Ok(()) => cx.emit(Renamed {
+                from: plan.from,
+                to: plan.to,
+                files: plan.files.len(),
+            }),
+            Err(e) => self.error = Some(format!("{:#}", e)),
+        }
+    }
+
+    fn field_mut(&mut self) -> &mut String {
+        match self.field {
+            Field::From => &mut self.from,
+            Field::To => &mut self.to,
+        }
+    }
+
+    fn on_key(&mut self, ev: &KeyDownEvent, _window: &mut Window, cx: &mut Context<Self>) {
+        cx.stop_propagation();
+        match ev.keystroke.key.as_str() {
+            "escape" if self.plan.is_some() => self.plan = None,
+            "escape" => cx.emit(Dismissed),
+            "enter" if self.plan.is_some() => self.apply(cx),
+            "enter" => self.preview(),
+            "tab" => {
+                self.field = match self.field {
+                    Field::From => Field::To,
+                    Field::To => Field::From,
+                }
+            }
+            "backspace" => {
+                self.field_mut().pop();
+                self.plan = None;
+                self.error = None;
+            }
+            _ if !ev.keystroke.modifiers.control && !ev.keystroke.modifiers.platform => {
+                let Some(ch) = ev.keystroke.key_char.clone() else {
+                    return;
+                };
+                self.field_mut().push_str(&ch);
+                self.plan = None;
+                self.error = None;
+            }
+            _ => return,
+        }
+        cx.notify();
+    }
+}
+
+impl Focusable for RenameOverlay {
+    fn focus_handle(&self, _: &gpui::App) -> FocusHandle {
+        self.focus.clone()
+    }
+}
+
+impl gpui::Render for RenameOverlay {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let editing = self.plan.is_none();
+
+        let row = |label: &'static str, value: &str, active: bool| {
+            div()
+                .flex()
+                .gap_2()
+                .h(ap.px(24.0))
+                .px(ap.px(8.0))
+                .items_center()
+                .child(div().w(ap.px(80.0)).text_color(pal.muted).child(label))
+                .child(div().text_color(pal.fg).child(if active {
+                    format!("{}▏", value)
+                } else {
+                    value.to_string()
+                }))
+        };
+
+        let mut body = div().flex().flex_col().px(ap.px(8.0)).py(ap.px(4.0));
+        if let Some(e) = &self.error {
+            body = body.child(
+                div()
+                    .text_color(gpui::hsla(0.0, 0.8, 0.6, 1.0))
+                    .child(e.clone()),
+            );
+        }
+        match &self.plan {
+            Some(plan) if plan.files.is_empty() => {
+                body = body.child("Nothing to rewrite");
+            }
+            Some(plan) => {
+                for line in plan.diff().lines() {
+                    let color = if line.starts_with("---") || line.starts_with("+++") {
+                        pal.fg
+                    } else if line.starts_with('-') {
+                        gpui::hsla(0.0, 0.8, 0.6, 1.0)
+                    } else if line.starts_with('+') {
+                        gpui::green()
+                    } else {
+                        pal.muted
+                    };
+                    body = body.child(div().text_color(color).child(line.to_string()));
+                }
+            }
+            None => {}
+        }
+
+        div()
+            .track_focus(&self.focus)
+            .on_key_down(cx.listener(Self::on_key))
+            .flex()
+            .flex_col()
+            .w_full()
+            .rounded_md()
+            .border_1()
+            .border_color(pal.border)
+            .bg(pal.bg)
+            .text_color(pal.fg_dim)
+            .child(
+                div()
+                    .h(ap.px(24.0))
+                    .px(ap.px(8.0))
+                    .flex()
+                    .items_center()
+                    .border_b_1()
+                    .border_color(pal.border)
+                    .text_color(pal.fg)
+                    .child("Rename host"),
+            )
+            .child(row(
+                "Host",
+                &self.from,
+                editing && self.field == Field::From,
+            ))
+            .child(row(
+                "New name",
+                &self.to,
+                editing && self.field == Field::To,
+            ))
+            .child(body)
+            .child(
+                div()
+                    .px(ap.px(8.0))
+                    .pb(ap.px(4.0))
+                    .text_color(pal.muted)
+                    .child(if editing {
+                        "tab: switch field · enter: preview · esc: cancel"
+                    } else {
+                        "enter: write changes · esc: back"
+                    }),
+            )
+    }
+}
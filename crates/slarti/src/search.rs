@@ -0,0 +1,276 @@
+//! Global search (ctrl-alt-f or ≡ → Search): finds hosts by alias, hostname
+//! or tag, and the services last seen on them, grouped by category.
+//! Choosing a result selects its host.
+
+use gpui::{
+    div, prelude::*, Context, EventEmitter, FocusHandle, Focusable, KeyDownEvent, MouseButton,
+    Window,
+};
+use slarti_host::HostSnapshot;
+use slarti_sshcfg as sshcfg;
+use slarti_ui::Appearance;
+
+/// Results shown per category.
+const MAX_PER_KIND: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Kind {
+    Host,
+    Hostname,
+    Tag,
+    Service,
+}
+
+impl Kind {
+    const ALL: [Kind; 4] = [Kind::Host, Kind::Hostname, Kind::Tag, Kind::Service];
+
+    fn label(self) -> &'static str {
+        match self {
+            Kind::Host => "Hosts",
+            Kind::Hostname => "Hostnames",
+            Kind::Tag => "Tags",
+            Kind::Service => "Services",
+        }
+    }
+}
+
+/// One searchable thing and the host it leads to.
+#[derive(Clone, Debug)]
+pub struct Item {
+    kind: Kind,
+    text: String,
+    alias: String,
+    detail: String,
+}
+
+/// Index the ssh config's hosts and what their cached snapshots saw. Reads
+/// every snapshot, so callers run it on the background executor.
+pub fn build_index(
+    tree: &sshcfg::model::ConfigTree,
+    snapshot_of: impl Fn(&str) -> Option<HostSnapshot>,
+) -> Vec<Item> {
+    let mut items = Vec::new();
+    for alias in sshcfg::load::list_aliases(tree) {
+        let item = |kind, text: &str, detail: String| Item {
+            kind,
+            text: text.to_string(),
+            alias: alias.clone(),
+            detail,
+        };
+        let configured = sshcfg::load::host_entry_for_alias(tree, &alias)
+            .and_then(|e| e.get("hostname"))
+            .map(str::to_string);
+        let snapshot = snapshot_of(&alias).unwrap_or_default();
+        let seen = snapshot.sys_info.as_ref().map(|i| i.hostname.clone());
+        items.push(item(
+            Kind::Host,
+            &alias,
+            configured.clone().unwrap_or_default(),
+        ));
+        let mut hostnames: Vec<String> = configured.into_iter().chain(seen).collect();
+        hostnames.sort();
+        hostnames.dedup();
+        for hostname in hostnames.iter().filter(|h| !h.is_empty() && **h != alias) {
+            items.push(item(Kind::Hostname, hostname, alias.clone()));
+        }
+        for tag in crate::host_tags_for(tree, &alias) {
+            items.push(item(Kind::Tag, &tag, alias.clone()));
+        }
+        for service in snapshot.services.iter().flatten() {
+            items.push(item(
+                Kind::Service,
+                &service.name,
+                format!("{} on {}", service.active_state, alias),
+            ));
+        }
+    }
+    items
+}
+
+/// Emitted when a result is chosen (with its host alias).
+pub struct Selected(pub String);
+/// Emitted when the overlay is closed without choosing.
+pub struct Dismissed;
+
+pub struct SearchOverlay {
+    focus: FocusHandle,
+    // None until the index is built
+    items: Option<Vec<Item>>,
+    query: String,
+    // Index into `results()` of the highlighted result
+    cursor: usize,
+}
+
+impl EventEmitter<Selected> for SearchOverlay {}
+impl EventEmitter<Dismissed> for SearchOverlay {}
+
+impl SearchOverlay {
+    pub fn new(cx: &mut Context<Self>) -> Self {
+        Self {
+            focus: cx.focus_handle(),
+            items: None,
+            query: String::new(),
+            cursor: 0,
+        }
+    }
+
+    /// Search `items` (from `build_index`), re-running the typed query.
+    pub fn set_items(&mut self, items: Vec<Item>, cx: &mut Context<Self>) {
+        self.items = Some(items);
+        self.cursor = 0;
+        cx.notify();
+    }
+
+    /// Matching items (case-insensitive substring), by category, prefix matches first.
+    fn results(&self) -> Vec<&Item> {
+        let query = self.query.to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let mut matches: Vec<(&Item, bool)> = self
+            .items
+            .iter()
+            .flatten()
+            .filter_map(|item| {
+                let text = item.text.to_lowercase();
+                text.contains(&query)
+                    .then(|| (item, text.starts_with(&query)))
+            })
+            .collect();
+        matches.sort_by(|(a, a_prefix), (b, b_prefix)| {
+            (a.kind, !a_prefix, &a.text, &a.alias).cmp(&(b.kind, !b_prefix, &b.text, &b.alias))
+        });
+        Kind::ALL
+            .into_iter()
+            .flat_map(|kind| {
+                matches
+                    .iter()
+                    .filter(move |(item, _)| item.kind == kind)
+                    .take(MAX_PER_KIND)
+                    .map(|(item, _)| *item)
+            })
+            .collect()
+    }
+
+    fn choose(&mut self, index: usize, cx: &mut Context<Self>) {
+        if let Some(item) = self.results().get(index) {
+            cx.emit(Selected(item.alias.clone()));
+        }
+    }
+
+    fn on_key(&mut self, ev: &KeyDownEvent, _window: &mut Window, cx: &mut Context<Self>) {
+        cx.stop_propagation();
+        match ev.keystroke.key.as_str() {
+            "escape" => cx.emit(Dismissed),
+            "enter" => self.choose(self.cursor, cx),
+            "up" => {
+                self.cursor = self.cursor.saturating_sub(1);
+                cx.notify();
+            }
+            "down" => {
+                let last = self.results().len().saturating_sub(1);
+                self.cursor = (self.cursor + 1).min(last);
+                cx.notify();
+            }
+            "backspace" => {
+                self.query.pop();
+                self.cursor = 0;
+                cx.notify();
+            }
+            _ if !ev.keystroke.modifiers.control && !ev.keystroke.modifiers.platform => {
+                if let Some(ch) = ev.keystroke.key_char.as_ref() {
+                    self.query.push_str(ch);
+                    self.cursor = 0;
+                    cx.notify();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Focusable for SearchOverlay {
+    fn focus_handle(&self, _: &gpui::App) -> FocusHandle {
+        self.focus.clone()
+    }
+}
+
+impl gpui::Render for SearchOverlay {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let results = self.results();
+
+        let input = div()
+            .h(ap.px(24.0))
+            .px(ap.px(8.0))
+            .flex()
+            .items_center()
+            .border_b_1()
+            .border_color(pal.border)
+            .text_color(if self.query.is_empty() {
+                pal.muted
+            } else {
+                pal.fg
+            })
+            .child(if self.query.is_empty() {
+                "search hosts, hostnames, tags, services…".to_string()
+            } else {
+                format!("{}▏", self.query)
+            });
+
+        let mut list = div().flex().flex_col().py(ap.px(4.0));
+        let mut last_kind = None;
+        for (index, item) in results.iter().enumerate() {
+            if last_kind != Some(item.kind) {
+                last_kind = Some(item.kind);
+                list = list.child(
+                    div()
+                        .px(ap.px(8.0))
+                        .pt(ap.px(4.0))
+                        .text_color(pal.muted)
+                        .child(item.kind.label()),
+                );
+            }
+            list = list.child(
+                div()
+                    .flex()
+                    .justify_between()
+                    .gap_2()
+                    .px(ap.px(12.0))
+                    .h(ap.px(22.0))
+                    .items_center()
+                    .cursor_pointer()
+                    .when(index == self.cursor, |d| d.bg(gpui::opaque_grey(0.2, 0.5)))
+                    .hover(|d| d.bg(gpui::opaque_grey(0.2, 0.5)))
+                    .child(div().text_color(pal.fg).child(item.text.clone()))
+                    .child(div().text_color(pal.fg_dim).child(item.detail.clone()))
+                    .on_mouse_up(
+                        MouseButton::Left,
+                        cx.listener(move |this: &mut Self, _ev, _w, cx| this.choose(index, cx)),
+                    ),
+            );
+        }
+        if results.is_empty() && !self.query.is_empty() {
+            list = list.child(div().px(ap.px(8.0)).child(if self.items.is_some() {
+                "No matches"
+            } else {
+                "Indexing…"
+            }));
+        }
+
+        div()
+            .track_focus(&self.focus)
+            .on_key_down(cx.listener(Self::on_key))
+            .flex()
+            .flex_col()
+            .w_full()
+            .rounded_md()
+            .border_1()
+            .border_color(pal.border)
+            .bg(pal.bg)
+            .text_color(pal.fg_dim)
+            .child(input)
+            .child(list)
+    }
+}
@@ -0,0 +1,143 @@
+//! Settings view (start screen → Settings, or ≡ → Settings): the app's
+//! preferences in one place. Each control dispatches the same action as its
+//! shortcut or menu item, so the view only reads the current values.
+
+use gpui::{div, prelude::*, Context, EventEmitter, MouseButton, SharedString, Window};
+use slarti_host::PreviewActions;
+use slarti_ui::Appearance;
+
+use crate::{
+    stats, ResetZoom, ToggleHighContrast, TogglePreviewActions, ToggleUsageStats, ZoomIn, ZoomOut,
+};
+
+/// Emitted when the user closes the view.
+pub struct Dismissed;
+
+pub struct SettingsView;
+
+impl EventEmitter<Dismissed> for SettingsView {}
+
+impl SettingsView {
+    pub fn new(_cx: &mut Context<Self>) -> Self {
+        Self
+    }
+}
+
+fn on_off(on: bool) -> SharedString {
+    if on {
+        "on".into()
+    } else {
+        "off".into()
+    }
+}
+
+impl gpui::Render for SettingsView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let preview_actions = PreviewActions::get(cx);
+
+        let chip = |label: SharedString, action: Box<dyn gpui::Action>| {
+            div()
+                .px(ap.px(6.0))
+                .h(ap.px(20.0))
+                .flex()
+                .items_center()
+                .rounded_sm()
+                .border_1()
+                .border_color(pal.border)
+                .text_color(pal.fg)
+                .cursor_pointer()
+                .child(label)
+                .on_mouse_up(
+                    MouseButton::Left,
+                    cx.listener(move |_this: &mut Self, _ev, window, cx| {
+                        window.dispatch_action(action.boxed_clone(), cx);
+                        cx.notify();
+                    }),
+                )
+        };
+        let row = |label: &'static str, hint: &'static str| {
+            div()
+                .flex()
+                .items_center()
+                .gap_2()
+                .px(ap.px(8.0))
+                .py(ap.px(4.0))
+                .border_b_1()
+                .border_color(pal.border)
+                .child(div().w(ap.px(160.0)).text_color(pal.fg).child(label))
+                .child(div().flex_1().text_color(pal.muted).child(hint))
+        };
+
+        let toolbar = div()
+            .flex()
+            .items_center()
+            .gap_2()
+            .px(ap.px(8.0))
+            .py(ap.px(6.0))
+            .border_b_1()
+            .border_color(pal.border)
+            .child(div().flex_1().text_color(pal.fg).child("Settings"))
+            .child(
+                div()
+                    .px(ap.px(6.0))
+                    .h(ap.px(20.0))
+                    .flex()
+                    .items_center()
+                    .rounded_sm()
+                    .border_1()
+                    .border_color(pal.border)
+                    .text_color(pal.fg)
+                    .cursor_pointer()
+                    .child("Close")
+                    .on_mouse_up(
+                        MouseButton::Left,
+                        cx.listener(|_this: &mut Self, _ev, _w, cx| cx.emit(Dismissed)),
+                    ),
+            );
+
+        div()
+            .flex()
+            .flex_col()
+            .w_full()
+            .rounded_md()
+            .border_1()
+            .border_color(pal.border)
+            .bg(pal.bg)
+            .text_color(pal.fg_dim)
+            .child(toolbar)
+            .child(
+                row("Text size", "ctrl-+ / ctrl-- / ctrl-0")
+                    .child(
+                        div()
+                            .text_color(pal.fg)
+                            .child(format!("{:.0}%", ap.scale * 100.0)),
+                    )
+                    .child(chip("−".into(), Box::new(ZoomOut)))
+                    .child(chip("+".into(), Box::new(ZoomIn)))
+                    .child(chip("Reset".into(), Box::new(ResetZoom))),
+            )
+            .child(
+                row("High contrast", "ctrl-alt-h")
+                    .child(chip(on_off(ap.high_contrast), Box::new(ToggleHighContrast))),
+            )
+            .child(
+                row(
+                    "Preview actions",
+                    "show the commands a mutating action runs before running it",
+                )
+                .child(chip(
+                    on_off(preview_actions),
+                    Box::new(TogglePreviewActions),
+                )),
+            )
+            .child(
+                row(
+                    "Usage statistics",
+                    "connect and request timings, stored only on this machine",
+                )
+                .child(chip(on_off(stats::enabled()), Box::new(ToggleUsageStats))),
+            )
+    }
+}
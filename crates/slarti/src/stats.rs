@@ -0,0 +1,453 @@
+//! Opt-in usage statistics kept on this machine only (≡ → Usage statistics):
+//! connects per host, connect and request latencies, and deploy outcomes, so
+//! slow hosts stand out. Nothing is sent anywhere; the Stats view (≡ →
+//! Statistics) reads them and can reset them.
+
+use gpui::{div, prelude::*, Context, EventEmitter, MouseButton, SharedString, Window};
+use serde::{Deserialize, Serialize};
+use slarti_ui::Appearance;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// On-disk schema version of `stats.json` (0: unversioned).
+const STATS_VERSION: u32 = 1;
+/// Recording saves at most this often, in the background; `flush` saves
+/// whatever is left.
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+/// Requests listed per host in the view (the slowest by mean).
+const SLOWEST_SHOWN: usize = 3;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static RECORDER: Mutex<Option<Recorder>> = Mutex::new(None);
+/// Generation of the records last written, so a slow background save never
+/// overwrites a newer one or brings the file back after a reset.
+static SAVED: Mutex<u64> = Mutex::new(0);
+
+/// Count, total and worst of a set of durations.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Timing {
+    pub count: u64,
+    pub total_ms: u64,
+    pub max_ms: u64,
+}
+
+impl Timing {
+    fn add(&mut self, d: Duration) {
+        let ms = d.as_millis() as u64;
+        self.count += 1;
+        self.total_ms += ms;
+        self.max_ms = self.max_ms.max(ms);
+    }
+
+    pub fn mean_ms(&self) -> Option<u64> {
+        (self.count > 0).then(|| self.total_ms / self.count)
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HostStats {
+    pub connects: u64,
+    pub connect_failures: u64,
+    /// Time from spawning ssh to the agent's HelloAck
+    pub connect: Timing,
+    pub deploys_ok: u64,
+    pub deploys_failed: u64,
+    /// Round trips by HostPanel section
+    pub requests: BTreeMap<String, Timing>,
+    /// Unix seconds of the last successful connect
+    pub last_connect: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UsageStats {
+    pub hosts: BTreeMap<String, HostStats>,
+    /// Unix seconds of the first record since the last reset
+    pub since: Option<u64>,
+}
+
+impl UsageStats {
+    /// Count one record for `alias`.
+    fn tally(&mut self, alias: &str, f: impl FnOnce(&mut HostStats)) {
+        self.since.get_or_insert_with(now_secs);
+        f(self.hosts.entry(alias.to_string()).or_default());
+    }
+
+    /// Hosts with the slowest mean connect first, then by alias.
+    fn by_connect_time(&self) -> Vec<(&String, &HostStats)> {
+        let mut hosts: Vec<_> = self.hosts.iter().collect();
+        hosts.sort_by_key(|(alias, h)| (std::cmp::Reverse(h.connect.mean_ms()), *alias));
+        hosts
+    }
+}
+
+struct Recorder {
+    stats: UsageStats,
+    /// Bumped on every record and reset.
+    generation: u64,
+    dirty: bool,
+    saved_at: Instant,
+}
+
+fn stats_path() -> std::path::PathBuf {
+    crate::slarti_state_dir().join("stats.json")
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn load(path: &Path) -> UsageStats {
+    match slarti_state::load_versioned(path, STATS_VERSION, slarti_state::no_migration) {
+        Ok(stats) => stats.unwrap_or_default(),
+        Err(e) => {
+            tracing::warn!("usage statistics not loaded, starting over: {:#}", e);
+            UsageStats::default()
+        }
+    }
+}
+
+/// Write `stats` unless records of a later `generation` were written already.
+fn save(path: &Path, stats: &UsageStats, generation: u64) {
+    let mut saved = SAVED.lock().unwrap_or_else(|e| e.into_inner());
+    if generation <= *saved {
+        return;
+    }
+    match slarti_state::save_versioned(path, STATS_VERSION, stats) {
+        Ok(()) => *saved = generation,
+        Err(e) => tracing::warn!("saving usage statistics failed: {}", e),
+    }
+}
+
+/// Run `f` on the recorder, loading it on first use; `None` while disabled.
+fn with_recorder<T>(f: impl FnOnce(&mut Recorder) -> T) -> Option<T> {
+    if !enabled() {
+        return None;
+    }
+    let mut guard = RECORDER.lock().unwrap_or_else(|e| e.into_inner());
+    let recorder = guard.get_or_insert_with(|| Recorder {
+        stats: load(&stats_path()),
+        generation: 0,
+        dirty: false,
+        saved_at: Instant::now(),
+    });
+    Some(f(recorder))
+}
+
+/// Update `alias`'s counters and save if the last save was a while ago.
+fn record(alias: &str, f: impl FnOnce(&mut HostStats)) {
+    let due = with_recorder(|r| {
+        r.stats.tally(alias, f);
+        r.generation += 1;
+        r.dirty = true;
+        (r.saved_at.elapsed() >= SAVE_INTERVAL).then(|| {
+            r.dirty = false;
+            r.saved_at = Instant::now();
+            (r.stats.clone(), r.generation)
+        })
+    })
+    .flatten();
+    // Callers are on the UI and connect paths; write off them and outside the lock.
+    if let Some((stats, generation)) = due {
+        std::thread::spawn(move || save(&stats_path(), &stats, generation));
+    }
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Turn recording on or off; turning it off saves what was recorded so far.
+pub fn set_enabled(on: bool) {
+    if !on {
+        flush();
+    }
+    ENABLED.store(on, Ordering::Relaxed);
+}
+
+pub fn record_connect(alias: &str, setup: Option<Duration>) {
+    record(alias, |h| {
+        h.connects += 1;
+        if let Some(setup) = setup {
+            h.connect.add(setup);
+        }
+        h.last_connect = Some(now_secs());
+    });
+}
+
+pub fn record_connect_failed(alias: &str) {
+    record(alias, |h| h.connect_failures += 1);
+}
+
+pub fn record_request(alias: &str, section: &str, rtt: Duration) {
+    record(alias, |h| {
+        h.requests.entry(section.to_string()).or_default().add(rtt)
+    });
+}
+
+pub fn record_deploy(alias: &str, ok: bool) {
+    record(alias, |h| {
+        if ok {
+            h.deploys_ok += 1;
+        } else {
+            h.deploys_failed += 1;
+        }
+    });
+}
+
+/// Save unsaved records (on shutdown and when recording is turned off).
+pub fn flush() {
+    with_recorder(|r| {
+        if r.dirty {
+            save(&stats_path(), &r.stats, r.generation);
+            r.dirty = false;
+            r.saved_at = Instant::now();
+        }
+    });
+}
+
+/// What has been recorded, including records made while recording was on
+/// in earlier runs.
+pub fn snapshot() -> UsageStats {
+    with_recorder(|r| r.stats.clone()).unwrap_or_else(|| load(&stats_path()))
+}
+
+/// Forget everything recorded and remove the file.
+pub fn reset() {
+    let mut guard = RECORDER.lock().unwrap_or_else(|e| e.into_inner());
+    let mut saved = SAVED.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(r) = guard.as_mut() {
+        r.stats = UsageStats::default();
+        r.generation += 1;
+        r.dirty = false;
+        // Saves of what came before the reset are now stale.
+        *saved = r.generation;
+    }
+    let _ = std::fs::remove_file(stats_path());
+}
+
+/// Emitted when the user closes the view.
+pub struct Dismissed;
+
+/// Per-host table of the recorded statistics, slowest connect first.
+pub struct StatsView {
+    stats: UsageStats,
+}
+
+impl EventEmitter<Dismissed> for StatsView {}
+
+impl StatsView {
+    pub fn new(_cx: &mut Context<Self>) -> Self {
+        Self { stats: snapshot() }
+    }
+}
+
+fn fmt_ms(ms: Option<u64>) -> String {
+    ms.map(|ms| format!("{} ms", ms))
+        .unwrap_or_else(|| "–".to_string())
+}
+
+impl gpui::Render for StatsView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+
+        let chip = |label: SharedString| {
+            div()
+                .px(ap.px(6.0))
+                .h(ap.px(20.0))
+                .flex()
+                .items_center()
+                .rounded_sm()
+                .border_1()
+                .border_color(pal.border)
+                .text_color(pal.fg)
+                .cursor_pointer()
+                .child(label)
+        };
+
+        let status = if !enabled() {
+            "recording is off (≡ → Usage statistics)".to_string()
+        } else if let Some(since) = self.stats.since {
+            let days = now_secs().saturating_sub(since) / 86_400;
+            format!(
+                "recording, {} days of data, stored only on this machine",
+                days
+            )
+        } else {
+            "recording, stored only on this machine".to_string()
+        };
+
+        let toolbar = div()
+            .flex()
+            .items_center()
+            .gap_2()
+            .px(ap.px(8.0))
+            .py(ap.px(6.0))
+            .border_b_1()
+            .border_color(pal.border)
+            .child(div().text_color(pal.fg).child("Statistics"))
+            .child(div().flex_1().text_color(pal.muted).child(status))
+            .child(chip("Reset".into()).on_mouse_up(
+                MouseButton::Left,
+                cx.listener(|this: &mut Self, _ev, _w, cx| {
+                    reset();
+                    this.stats = UsageStats::default();
+                    cx.notify();
+                }),
+            ))
+            .child(chip("Close".into()).on_mouse_up(
+                MouseButton::Left,
+                cx.listener(|_this: &mut Self, _ev, _w, cx| cx.emit(Dismissed)),
+            ));
+
+        let cell = |w: f32, text: String| div().w(ap.px(w)).child(text);
+        let header = div()
+            .flex()
+            .gap_2()
+            .px(ap.px(8.0))
+            .text_color(pal.muted)
+            .child(cell(160.0, "Host".into()))
+            .child(cell(80.0, "Connects".into()))
+            .child(cell(80.0, "Failed".into()))
+            .child(cell(100.0, "Connect avg".into()))
+            .child(cell(100.0, "Connect max".into()))
+            .child(cell(120.0, "Deploys".into()))
+            .child(div().flex_1().child("Slowest requests (avg)"));
+
+        let rows = self.stats.by_connect_time().into_iter().map(|(alias, h)| {
+            let mut requests: Vec<_> = h.requests.iter().collect();
+            requests.sort_by_key(|(_, t)| std::cmp::Reverse(t.mean_ms()));
+            let slowest = requests
+                .into_iter()
+                .take(SLOWEST_SHOWN)
+                .map(|(name, t)| format!("{} {}", name, fmt_ms(t.mean_ms())))
+                .collect::<Vec<_>>()
+                .join(", ");
+            div()
+                .flex()
+                .gap_2()
+                .px(ap.px(8.0))
+                .border_b_1()
+                .border_color(pal.border)
+                .child(cell(160.0, alias.clone()).text_color(pal.fg))
+                .child(cell(80.0, h.connects.to_string()))
+                .child(cell(80.0, h.connect_failures.to_string()))
+                .child(cell(100.0, fmt_ms(h.connect.mean_ms())))
+                .child(cell(
+                    100.0,
+                    fmt_ms((h.connect.count > 0).then_some(h.connect.max_ms)),
+                ))
+                .child(cell(
+                    120.0,
+                    format!("{} ok / {} failed", h.deploys_ok, h.deploys_failed),
+                ))
+                .child(div().flex_1().child(slowest))
+        });
+
+        div()
+            .flex()
+            .flex_col()
+            .size_full()
+            .rounded_md()
+            .border_1()
+            .border_color(pal.border)
+            .bg(pal.bg)
+            .text_color(pal.fg_dim)
+            .child(toolbar)
+            .child(
+                div()
+                    .id("StatsScroll")
+                    .flex()
+                    .flex_col()
+                    .flex_1()
+                    .min_h_0()
+                    .py(ap.px(4.0))
+                    .overflow_y_scroll()
+                    .child(header)
+                    .children(rows)
+                    .when(self.stats.hosts.is_empty(), |d| {
+                        d.child(div().px(ap.px(8.0)).child("Nothing recorded yet"))
+                    }),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_file(name: &str) -> std::path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("slarti-stats-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("stats.json")
+    }
+
+    #[test]
+    fn timings_aggregate_count_mean_and_max() {
+        let mut t = Timing::default();
+        assert_eq!(t.mean_ms(), None);
+        for ms in [10, 30, 50] {
+            t.add(Duration::from_millis(ms));
+        }
+        assert_eq!((t.count, t.total_ms, t.max_ms), (3, 90, 50));
+        assert_eq!(t.mean_ms(), Some(30));
+    }
+
+    #[test]
+    fn records_aggregate_per_host_and_section() {
+        let mut stats = UsageStats::default();
+        stats.tally("web", |h| {
+            h.connects += 1;
+            h.connect.add(Duration::from_millis(200));
+        });
+        stats.tally("db", |h| {
+            h.connects += 1;
+            h.connect.add(Duration::from_millis(900));
+        });
+        stats.tally("web", |h| h.connect_failures += 1);
+        for ms in [40, 60] {
+            stats.tally("web", |h| {
+                h.requests
+                    .entry("Services".to_string())
+                    .or_default()
+                    .add(Duration::from_millis(ms))
+            });
+        }
+        assert!(stats.since.is_some());
+        let web = &stats.hosts["web"];
+        assert_eq!((web.connects, web.connect_failures), (1, 1));
+        assert_eq!(web.requests["Services"].mean_ms(), Some(50));
+        let order: Vec<_> = stats
+            .by_connect_time()
+            .into_iter()
+            .map(|(a, _)| a.as_str())
+            .collect();
+        assert_eq!(order, ["db", "web"]);
+    }
+
+    #[test]
+    fn saved_stats_load_back_and_stale_saves_are_dropped() {
+        let path = scratch_file("roundtrip");
+        let mut newer = UsageStats::default();
+        newer.tally("web", |h| h.deploys_ok += 2);
+        save(&path, &newer, 1_000_001);
+        // A slower background save of older records lands afterwards.
+        save(&path, &UsageStats::default(), 1_000_000);
+
+        let loaded = load(&path);
+        assert_eq!(loaded.since, newer.since);
+        assert_eq!(loaded.hosts["web"].deploys_ok, 2);
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
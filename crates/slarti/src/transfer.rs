@@ -0,0 +1,295 @@
+//! Copy files (≡ → Copy files…): build an scp or rsync command between this
+//! machine and the selected host (by alias, so its ssh config applies), then
+//! copy it to the clipboard or run it in the terminal.
+
+use gpui::{
+    div, prelude::*, Context, EventEmitter, FocusHandle, Focusable, KeyDownEvent, MouseButton,
+    Window,
+};
+use slarti_ssh::transfer::{Direction, Tool, Transfer};
+use slarti_ui::Appearance;
+
+/// Emitted with the built command line.
+pub struct Finished {
+    pub command: String,
+    /// Run it in the terminal rather than copy it
+    pub run: bool,
+}
+/// Emitted when the overlay is closed without a command.
+pub struct Dismissed;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Local,
+    Remote,
+}
+
+/// Options toggled with ctrl-<key> or a click.
+#[derive(Clone, Copy)]
+enum Toggle {
+    Tool,
+    Direction,
+    Recursive,
+    Preserve,
+    Compress,
+    DryRun,
+}
+
+pub struct TransferOverlay {
+    focus: FocusHandle,
+    alias: String,
+    transfer: Transfer,
+    field: Field,
+}
+
+impl EventEmitter<Finished> for TransferOverlay {}
+impl EventEmitter<Dismissed> for TransferOverlay {}
+
+impl TransferOverlay {
+    pub fn new(alias: String, cx: &mut Context<Self>) -> Self {
+        Self {
+            focus: cx.focus_handle(),
+            alias,
+            transfer: Transfer {
+                remote: "~/".to_string(),
+                ..Default::default()
+            },
+            field: Field::Local,
+        }
+    }
+
+    fn command(&self) -> String {
+        self.transfer.command(&self.alias)
+    }
+
+    fn toggle(&mut self, toggle: Toggle) {
+        let t = &mut self.transfer;
+        match toggle {
+            Toggle::Tool => {
+                t.tool = match t.tool {
+                    Tool::Scp => Tool::Rsync,
+                    Tool::Rsync => Tool::Scp,
+                }
+            }
+            Toggle::Direction => {
+                t.direction = match t.direction {
+                    Direction::Upload => Direction::Download,
+                    Direction::Download => Direction::Upload,
+                }
+            }
+            Toggle::Recursive => t.recursive = !t.recursive,
+            Toggle::Preserve => t.preserve = !t.preserve,
+            Toggle::Compress => t.compress = !t.compress,
+            Toggle::DryRun => t.dry_run = !t.dry_run,
+        }
+    }
+
+    /// Pick the local path with the system dialog (a folder for downloads).
+    fn pick_local(&mut self, cx: &mut Context<Self>) {
+        let download = self.transfer.direction == Direction::Download;
+        let paths = cx.prompt_for_paths(gpui::PathPromptOptions {
+            files: !download,
+            directories: true,
+            multiple: false,
+            prompt: Some("Local path".into()),
+        });
+        cx.spawn(async move |this, cx| {
+            let Ok(Ok(Some(paths))) = paths.await else {
+                return;
+            };
+            let Some(path) = paths.into_iter().next() else {
+                return;
+            };
+            let _ = this.update(cx, |this, cx| {
+                if path.is_dir() {
+                    this.transfer.recursive = true;
+                }
+                this.transfer.local = path.to_string_lossy().into_owned();
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    fn finish(&mut self, run: bool, cx: &mut Context<Self>) {
+        if self.transfer.local.is_empty() || self.transfer.remote.is_empty() {
+            return;
+        }
+        let command = self.command();
+        cx.emit(Finished { command, run });
+    }
+
+    fn field_mut(&mut self) -> &mut String {
+        match self.field {
+            Field::Local => &mut self.transfer.local,
+            Field::Remote => &mut self.transfer.remote,
+        }
+    }
+
+    fn on_key(&mut self, ev: &KeyDownEvent, _window: &mut Window, cx: &mut Context<Self>) {
+        cx.stop_propagation();
+        let mods = &ev.keystroke.modifiers;
+        if mods.control {
+            match ev.keystroke.key.as_str() {
+                "enter" => self.finish(true, cx),
+                "o" => self.pick_local(cx),
+                "t" => self.toggle(Toggle::Tool),
+                "d" => self.toggle(Toggle::Direction),
+                "r" => self.toggle(Toggle::Recursive),
+                "p" => self.toggle(Toggle::Preserve),
+                "z" => self.toggle(Toggle::Compress),
+                "n" => self.toggle(Toggle::DryRun),
+                _ => return,
+            }
+            cx.notify();
+            return;
+        }
+        match ev.keystroke.key.as_str() {
+            "escape" => cx.emit(Dismissed),
+            "enter" => self.finish(false, cx),
+            "tab" => {
+                self.field = match self.field {
+                    Field::Local => Field::Remote,
+                    Field::Remote => Field::Local,
+                }
+            }
+            "backspace" => {
+                self.field_mut().pop();
+            }
+            _ if !mods.platform => {
+                let Some(ch) = ev.keystroke.key_char.clone() else {
+                    return;
+                };
+                self.field_mut().push_str(&ch);
+            }
+            _ => return,
+        }
+        cx.notify();
+    }
+}
+
+impl Focusable for TransferOverlay {
+    fn focus_handle(&self, _: &gpui::App) -> FocusHandle {
+        self.focus.clone()
+    }
+}
+
+impl gpui::Render for TransferOverlay {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let ap = Appearance::get(cx);
+        let pal = ap.palette();
+        let t = &self.transfer;
+
+        let row = |label: &'static str, value: &str, active: bool| {
+            div()
+                .flex()
+                .gap_2()
+                .h(ap.px(24.0))
+                .px(ap.px(8.0))
+                .items_center()
+                .child(div().w(ap.px(80.0)).text_color(pal.muted).child(label))
+                .child(div().text_color(pal.fg).child(if active {
+                    format!("{}▏", value)
+                } else {
+                    value.to_string()
+                }))
+        };
+        let chip = |label: String, on: bool, toggle: Toggle| {
+            div()
+                .px(ap.px(6.0))
+                .rounded_sm()
+                .border_1()
+                .border_color(pal.border)
+                .cursor_pointer()
+                .text_color(if on { pal.fg } else { pal.muted })
+                .child(label)
+                .on_mouse_up(
+                    MouseButton::Left,
+                    cx.listener(move |this: &mut Self, _ev, _window, cx| {
+                        this.toggle(toggle);
+                        cx.notify();
+                    }),
+                )
+        };
+        let check = |on: bool, name: &str| format!("{} {}", if on { "☑" } else { "☐" }, name);
+
+        let mut options = div()
+            .flex()
+            .gap_2()
+            .px(ap.px(8.0))
+            .py(ap.px(4.0))
+            .child(chip(
+                match t.tool {
+                    Tool::Scp => "scp".to_string(),
+                    Tool::Rsync => "rsync".to_string(),
+                },
+                true,
+                Toggle::Tool,
+            ))
+            .child(chip(
+                match t.direction {
+                    Direction::Upload => "local → host".to_string(),
+                    Direction::Download => "host → local".to_string(),
+                },
+                true,
+                Toggle::Direction,
+            ))
+            .child(chip(
+                check(t.recursive, "recursive"),
+                t.recursive,
+                Toggle::Recursive,
+            ))
+            .child(chip(
+                check(t.preserve, "preserve"),
+                t.preserve,
+                Toggle::Preserve,
+            ))
+            .child(chip(
+                check(t.compress, "compress"),
+                t.compress,
+                Toggle::Compress,
+            ));
+        if t.tool == Tool::Rsync {
+            options = options.child(chip(check(t.dry_run, "dry run"), t.dry_run, Toggle::DryRun));
+        }
+
+        let preview = div().text_color(pal.fg).child(self.command());
+
+        div()
+            .track_focus(&self.focus)
+            .on_key_down(cx.listener(Self::on_key))
+            .flex()
+            .flex_col()
+            .w_full()
+            .rounded_md()
+            .border_1()
+            .border_color(pal.border)
+            .bg(pal.bg)
+            .text_color(pal.fg_dim)
+            .child(
+                div()
+                    .h(ap.px(24.0))
+                    .px(ap.px(8.0))
+                    .flex()
+                    .items_center()
+                    .border_b_1()
+                    .border_color(pal.border)
+                    .text_color(pal.fg)
+                    .child(format!("Copy files: {}", self.alias)),
+            )
+            .child(row("Local", &t.local, self.field == Field::Local))
+            .child(row("Remote", &t.remote, self.field == Field::Remote))
+            .child(options)
+            .child(div().px(ap.px(8.0)).py(ap.px(4.0)).child(preview))
+            .child(
+                div()
+                    .px(ap.px(8.0))
+                    .pb(ap.px(4.0))
+                    .text_color(pal.muted)
+                    .child(
+                    "tab: switch field · ctrl-o: pick local path · ctrl-t/d/r/p/z/n: options · \
+                         enter: copy command · ctrl-enter: run in terminal · esc: cancel",
+                ),
+            )
+    }
+}